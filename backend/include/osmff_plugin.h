@@ -0,0 +1,66 @@
+/*
+ * C interface of fire containment strategy plugins, see backend/src/firefighter/plugin.rs.
+ *
+ * A plugin is a shared library that exports the function `osmff_strategy_plugin`, which returns
+ * the strategy of the plugin. Backends built with the `plugins` feature load every plugin in the
+ * directory `plugins_path` of their configuration at startup and list the strategies of the
+ * plugins next to the built-in strategies.
+ */
+#ifndef OSMFF_PLUGIN_H
+#define OSMFF_PLUGIN_H
+
+#include <stdint.h>
+
+#define OSMFF_PLUGIN_API_VERSION 1
+
+#define OSMFF_NODE_UNTOUCHED 0
+#define OSMFF_NODE_BURNING 1
+#define OSMFF_NODE_DEFENDED 2
+
+/*
+ * Graph in compressed sparse row format. The outgoing edges of node i are the entries
+ * offsets[i]..offsets[i + 1] of targets and weights. All arrays stay valid until the strategy
+ * instance is destroyed.
+ */
+typedef struct {
+    uint64_t num_nodes;
+    const uint64_t *offsets;
+    const uint64_t *targets;
+    const uint64_t *weights;
+} osmff_plugin_graph_t;
+
+/* Input of a single execution of a strategy instance, which is only valid during the execution */
+typedef struct {
+    uint64_t global_time;
+    uint64_t num_ffs;
+    /* num_nodes node states, i.e. OSMFF_NODE_UNTOUCHED, OSMFF_NODE_BURNING or OSMFF_NODE_DEFENDED */
+    const uint8_t *node_states;
+} osmff_plugin_round_t;
+
+typedef struct {
+    /* Create a new strategy instance for a graph. Returns an opaque state, which may be NULL. */
+    void *(*create)(const osmff_plugin_graph_t *graph);
+    /*
+     * Write the ids of at most capacity nodes to defend to to_defend and return their number.
+     * Nodes that do not exist or are burning or defended already are not defended.
+     */
+    uint64_t (*execute)(void *state, const osmff_plugin_round_t *round, uint64_t *to_defend, uint64_t capacity);
+    /* Destroy a strategy instance */
+    void (*destroy)(void *state);
+} osmff_strategy_vtable_t;
+
+typedef struct {
+    /* Must be OSMFF_PLUGIN_API_VERSION */
+    uint32_t api_version;
+    /* Name of the strategy, which must not collide with the names of other strategies */
+    const char *name;
+    osmff_strategy_vtable_t vtable;
+} osmff_strategy_plugin_t;
+
+/*
+ * Instances may be used from different threads, but never concurrently. The returned strategy
+ * must stay valid while the library is loaded.
+ */
+const osmff_strategy_plugin_t *osmff_strategy_plugin(void);
+
+#endif
@@ -0,0 +1,118 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Allocator that counts the bytes allocated by every thread on top of the system allocator.
+/// It is the global allocator of the backend if the `alloc-stats` feature is enabled.
+pub struct TrackingAllocator;
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+thread_local! {
+    /// Bytes allocated by this thread minus the bytes it freed, which is negative if the thread
+    /// freed memory allocated by other threads
+    static ALLOCATED: Cell<isize> = const { Cell::new(0) };
+    /// Maximum of `ALLOCATED` since the start of the current measurement
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+}
+
+/// Add `delta` bytes to the allocated bytes of the current thread
+fn record(delta: isize) {
+    // The thread locals are inaccessible while the thread is destroyed, so allocations made
+    // during that time are not counted
+    let _ = ALLOCATED.try_with(|allocated| {
+        let current = allocated.get() + delta;
+        allocated.set(current);
+        let _ = PEAK.try_with(|peak| if current > peak.get() {
+            peak.set(current);
+        });
+    });
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record(-(layout.size() as isize));
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            record(new_size as isize - layout.size() as isize);
+        }
+        new_ptr
+    }
+}
+
+/// Whether allocations are counted, i.e. whether the `alloc-stats` feature is enabled
+pub const fn is_enabled() -> bool {
+    cfg!(feature = "alloc-stats")
+}
+
+/// Execute `f` and measure the peak number of bytes that it held allocated at once on the current
+/// thread on top of the memory allocated before. Memory allocated by other threads, e.g. those of
+/// the compute pool, is not counted.
+/// Returns the result of `f` together with the peak, which is `None` unless allocations are
+/// counted.
+pub fn measure_peak<T>(f: impl FnOnce() -> T) -> (T, Option<usize>) {
+    if !is_enabled() {
+        return (f(), None);
+    }
+
+    let start = ALLOCATED.with(Cell::get);
+    // Restart the peak of the current thread, such that nested measurements can be restored
+    let outer_peak = PEAK.with(|peak| peak.replace(start));
+    let result = f();
+    let peak = PEAK.with(|peak| {
+        let inner_peak = peak.get();
+        peak.set(inner_peak.max(outer_peak));
+        inner_peak
+    });
+
+    (result, Some((peak - start).max(0) as usize))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::alloc_stats;
+
+    #[test]
+    fn test_measure_peak() {
+        let (len, peak) = alloc_stats::measure_peak(|| {
+            let buffer = vec![0u8; 1 << 20];
+            drop(buffer);
+            vec![0u8; 1 << 10].len()
+        });
+        assert_eq!(len, 1 << 10);
+
+        if alloc_stats::is_enabled() {
+            let peak = peak.expect("Peak should be measured if allocations are counted");
+            assert!(peak >= 1 << 20);
+            // Nested measurements only count their own allocations
+            let (_, outer) = alloc_stats::measure_peak(|| {
+                let _buffer = vec![0u8; 1 << 16];
+                alloc_stats::measure_peak(|| vec![0u8; 1 << 8]).1
+            });
+            assert!(outer.unwrap() >= 1 << 16);
+        } else {
+            assert_eq!(peak, None);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use image::{Rgb, RgbImage};
+use rayon::prelude::*;
+
+use crate::graph::{Graph, GraphView, GridBounds};
+use crate::graph_binary::SourceFile;
+
+/// File extension of base layer files
+pub(crate) const FILE_EXTENSION: &str = "osmfb";
+
+/// Magic bytes at the start of every base layer file
+const MAGIC: [u8; 8] = *b"OSMFBASE";
+
+/// Version of the base layer format. Files of other versions are rendered again.
+const FORMAT_VERSION: u64 = 1;
+
+/// Side length in pixels of the coarsest base layer, every finer layer doubles the side length
+const BASE_SIDE: usize = 1024;
+
+/// Number of base layers of every graph
+const NUM_LAYERS: usize = 3;
+
+/// Maximum number of layer pixels per frame pixel along each axis. Frames that are zoomed out
+/// further are drawn from the edges instead.
+const MAX_LAYER_PX_PER_PX: f64 = 4.0;
+
+/// Static raster of the edges of a graph over its grid bounds with one bit per pixel. Rows are
+/// ordered from south to north.
+#[derive(Debug)]
+struct BaseLayer {
+    side: usize,
+    bits: Vec<u64>,
+}
+
+impl BaseLayer {
+    /// Mark the pixel in column `x` and row `y` as part of an edge
+    fn set(&mut self, x: usize, y: usize) {
+        let i = y * self.side + x;
+        self.bits[i / 64] |= 1 << (i % 64);
+    }
+}
+
+/// Base layers of a graph in increasing resolutions, i.e. rasters of all its edges from which the
+/// frames of views are drawn instead of drawing every edge. Base layers are rendered once per
+/// graph file and stored next to the binary graph files.
+#[derive(Debug)]
+pub(crate) struct BaseLayers {
+    bounds: GridBounds,
+    layers: Vec<BaseLayer>,
+}
+
+impl BaseLayers {
+    /// Render the base layers of `graph`
+    fn render(graph: &Graph) -> Self {
+        let bounds = graph.get_grid_bounds();
+        let layers = (0..NUM_LAYERS).into_par_iter()
+            .map(|i| {
+                let side = BASE_SIDE << i;
+                let mut layer = BaseLayer { side, bits: vec![0; side * side / 64] };
+                let to_px = |lat: f64, lon: f64| (
+                    (lon - bounds.min_lon) / (bounds.max_lon - bounds.min_lon) * side as f64,
+                    (lat - bounds.min_lat) / (bounds.max_lat - bounds.min_lat) * side as f64,
+                );
+                let clamp = |px: f64| (px.max(0.0) as usize).min(side - 1);
+                for edge in graph.iter_edges() {
+                    let (src, tgt) = (graph.get_node(edge.src()), graph.get_node(edge.tgt()));
+                    let (a, b) = (to_px(src.lat, src.lon), to_px(tgt.lat, tgt.lon));
+                    // Step less than a pixel at a time along the edge
+                    let num_steps = (b.0 - a.0).abs().max((b.1 - a.1).abs()).ceil() as usize + 1;
+                    for step in 0..=num_steps {
+                        let t = step as f64 / num_steps as f64;
+                        layer.set(clamp(a.0 + t * (b.0 - a.0)), clamp(a.1 + t * (b.1 - a.1)));
+                    }
+                }
+                layer
+            })
+            .collect();
+
+        Self { bounds, layers }
+    }
+
+    /// Load the base layers of `graph` parsed from the source file `source` from the base layer
+    /// file at `path`. If the file is missing or outdated, the base layers are rendered and the
+    /// file is written.
+    pub(crate) fn load_or_render(graph: &Graph, path: &Path, source: &SourceFile) -> Self {
+        match Self::read_file(path, source) {
+            Ok(Some(layers)) => {
+                log::info!("Read base layers: {}", path.display());
+                return Self { bounds: graph.get_grid_bounds(), layers };
+            }
+            Ok(None) => log::info!("Base layers are outdated: {}", path.display()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => log::warn!("Failed to read base layers {}: {}", path.display(), err)
+        }
+
+        let base_layers = Self::render(graph);
+        match base_layers.write_file(path, source) {
+            Ok(()) => log::info!("Wrote base layers: {}", path.display()),
+            Err(err) => log::warn!("Failed to write base layers {}: {}", path.display(), err)
+        }
+        base_layers
+    }
+
+    /// Get the header of base layer files for the source file `source`
+    fn header(source: &SourceFile) -> Vec<u64> {
+        let mut header = vec![u64::from_le_bytes(MAGIC), FORMAT_VERSION, BASE_SIDE as u64, NUM_LAYERS as u64];
+        header.extend_from_slice(&source.fingerprint());
+        header
+    }
+
+    /// Write these base layers of the graph parsed from the source file `source` to `path`.
+    /// The file is written to a temporary file first and then renamed, such that concurrent
+    /// readers never observe partially written files.
+    fn write_file(&self, path: &Path, source: &SourceFile) -> io::Result<()> {
+        let tmp_path = path.with_extension(format!("{}.{}.tmp", FILE_EXTENSION, std::process::id()));
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        let words = Self::header(source).into_iter()
+            .chain(self.layers.iter().flat_map(|layer| layer.bits.iter().copied()));
+        for word in words {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+        writer.into_inner()?.sync_all()?;
+
+        if let Err(err) = fs::rename(&tmp_path, path) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Read the base layers from the file at `path` that was written for the source file
+    /// `source`. Returns `None` if the file is outdated, i.e. was written for another version of
+    /// the source file or in another format.
+    fn read_file(path: &Path, source: &SourceFile) -> io::Result<Option<Vec<BaseLayer>>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let expected = Self::header(source);
+        let mut header = Vec::with_capacity(expected.len());
+        for _ in 0..expected.len() {
+            let mut buf = [0u8; 8];
+            reader.read_exact(&mut buf)?;
+            header.push(u64::from_le_bytes(buf));
+        }
+        if header[0] != expected[0] {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("Not a base layer file: {}", path.display())));
+        }
+        if header != expected {
+            return Ok(None);
+        }
+
+        let mut layers = Vec::with_capacity(NUM_LAYERS);
+        let mut bytes = Vec::new();
+        for i in 0..NUM_LAYERS {
+            let side = BASE_SIDE << i;
+            bytes.resize(side * side / 8, 0);
+            reader.read_exact(&mut bytes)?;
+            let bits = bytes.chunks_exact(8)
+                .map(|chunk| {
+                    let mut buf = [0u8; 8];
+                    buf.copy_from_slice(chunk);
+                    u64::from_le_bytes(buf)
+                })
+                .collect();
+            layers.push(BaseLayer { side, bits });
+        }
+        Ok(Some(layers))
+    }
+
+    /// Draw the edges from the coarsest base layer that is at least as fine as a frame with
+    /// `deg_per_px_hz` and `deg_per_px_vert` degrees per pixel over the grid bounds `gb` onto the
+    /// image buffer `img_buf`. Every pixel of the frame that covers the center of an edge pixel of
+    /// the layer is colored `rgb`.
+    /// Returns `false` if no base layer matches the resolution of the frame.
+    pub(crate) fn draw(&self, img_buf: &mut RgbImage, gb: &GridBounds, deg_per_px_hz: f64,
+                       deg_per_px_vert: f64, rgb: Rgb<u8>) -> bool {
+        let delta_lon = self.bounds.max_lon - self.bounds.min_lon;
+        let delta_lat = self.bounds.max_lat - self.bounds.min_lat;
+        if !(delta_lon > 0.0 && delta_lat > 0.0) {
+            return false;
+        }
+        // Number of layer pixels per frame pixel along each axis
+        let ratios = |layer: &BaseLayer| (deg_per_px_hz * layer.side as f64 / delta_lon,
+                                          deg_per_px_vert * layer.side as f64 / delta_lat);
+        let layer = self.layers.iter()
+            .find(|layer| {
+                let (ratio_hz, ratio_vert) = ratios(layer);
+                ratio_hz >= 1.0 && ratio_vert >= 1.0
+            })
+            .filter(|layer| {
+                let (ratio_hz, ratio_vert) = ratios(layer);
+                ratio_hz <= MAX_LAYER_PX_PER_PX && ratio_vert <= MAX_LAYER_PX_PER_PX
+            });
+        let layer = match layer {
+            Some(layer) => layer,
+            None => return false
+        };
+
+        // Range of layer pixels within the frame along one axis
+        let side = layer.side;
+        let window = |min: f64, num_px: u32, deg_per_px: f64, layer_min: f64, layer_res: f64| {
+            let start = ((min - layer_min) / layer_res).floor().max(0.0).min(side as f64) as usize;
+            let end = ((min + num_px as f64 * deg_per_px - layer_min) / layer_res).ceil().max(0.0)
+                .min(side as f64) as usize;
+            (start, end)
+        };
+        let (res_hz, res_vert) = (delta_lon / side as f64, delta_lat / side as f64);
+        let (x_start, x_end) = window(gb.min_lon, img_buf.width(), deg_per_px_hz, self.bounds.min_lon, res_hz);
+        let (y_start, y_end) = window(gb.min_lat, img_buf.height(), deg_per_px_vert, self.bounds.min_lat, res_vert);
+
+        // Color the frame pixel of the center of every edge pixel of the layer within the frame,
+        // skipping words of the layer without edge pixels
+        let (w_max, h_max) = (img_buf.width() as i64 - 1, img_buf.height() as i64 - 1);
+        for y in y_start..y_end {
+            let h = ((self.bounds.min_lat + (y as f64 + 0.5) * res_vert - gb.min_lat) / deg_per_px_vert).floor() as i64;
+            if h < 0 || h > h_max {
+                continue;
+            }
+            let (row_start, row_end) = (y * side + x_start, y * side + x_end);
+            let mut i = row_start;
+            while i < row_end {
+                let word = layer.bits[i / 64] >> (i % 64);
+                if word == 0 {
+                    i += 64 - i % 64;
+                    continue;
+                }
+                i += word.trailing_zeros() as usize;
+                if i >= row_end {
+                    break;
+                }
+                let x = i - y * side;
+                let w = ((self.bounds.min_lon + (x as f64 + 0.5) * res_hz - gb.min_lon) / deg_per_px_hz).floor() as i64;
+                if w >= 0 && w <= w_max {
+                    img_buf.put_pixel(w as u32, (h_max - h) as u32, rgb);
+                }
+                i += 1;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use image::{ImageBuffer, Rgb, RgbImage};
+
+    use crate::base_layer::BaseLayers;
+    use crate::graph::{Graph, GraphView};
+    use crate::graph_binary::SourceFile;
+
+    #[test]
+    fn test_base_layers() {
+        let graph_path = Path::new("data/bbgrund_undirected.fmi");
+        let graph = Graph::parse_from_file(graph_path.to_str().unwrap()).unwrap();
+        let source = SourceFile::open(graph_path, &[]).unwrap();
+        let path = std::env::temp_dir().join(format!("osmff_base_layers_{}.osmfb", std::process::id()));
+
+        let rendered = BaseLayers::load_or_render(&graph, &path, &source);
+        let read = BaseLayers::read_file(&path, &source).unwrap().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read.len(), rendered.layers.len());
+        assert!(read.iter().zip(&rendered.layers).all(|(a, b)| a.side == b.side && a.bits == b.bits));
+
+        // A frame of the full graph shows an edge next to every node with an edge
+        let gb = graph.get_grid_bounds();
+        let (width, height) = (960, 540);
+        let deg_per_px_hz = (gb.max_lon - gb.min_lon) / width as f64;
+        let deg_per_px_vert = (gb.max_lat - gb.min_lat) / height as f64;
+        let mut img_buf: RgbImage = ImageBuffer::new(width, height);
+        assert!(rendered.draw(&mut img_buf, &gb, deg_per_px_hz, deg_per_px_vert, Rgb([255, 255, 255])));
+        for node in graph.iter_nodes().filter(|node| graph.get_node_degree(node.id()) > 0) {
+            let w = (((node.lon - gb.min_lon) / deg_per_px_hz) as u32).min(width - 1);
+            let h = height - 1 - (((node.lat - gb.min_lat) / deg_per_px_vert) as u32).min(height - 1);
+            let is_drawn = (w.saturating_sub(1)..=(w + 1).min(width - 1))
+                .any(|w| (h.saturating_sub(1)..=(h + 1).min(height - 1))
+                    .any(|h| img_buf.get_pixel(w, h) == &Rgb([255, 255, 255])));
+            assert!(is_drawn, "Node {} is not drawn", node.id());
+        }
+
+        // Frames zoomed in beyond the finest layer are not drawn from base layers
+        assert!(!rendered.draw(&mut img_buf, &gb, deg_per_px_hz / 64.0, deg_per_px_vert / 64.0, Rgb([0, 0, 0])));
+    }
+}
@@ -1,7 +1,20 @@
-use std::env;
+use std::{env, fmt, fs};
 
+use rand::prelude::*;
+use rayon::prelude::*;
+
+use osmff_lib::compute;
 use osmff_lib::firefighter::problem::{OSMFProblem, OSMFSettings};
+use osmff_lib::firefighter::report::{ReportRun, StrategyReport};
 use osmff_lib::firefighter::strategy::OSMFStrategy;
+use osmff_lib::firefighter::view::ViewOptions;
+
+use crate::bench_http::HttpTarget;
+
+mod bench_http;
+
+/// Zoom levels at which views are requested in the `--http` mode, unless given by `--zooms`
+const DEFAULT_HTTP_ZOOMS: [f64; 3] = [1.0, 4.0, 16.0];
 
 #[derive(Debug)]
 struct BenchResults {
@@ -9,6 +22,23 @@ struct BenchResults {
     avg_def: f64,
     avg_end_time: f64,
     avg_sim_millis: f64,
+    avg_root_rejections: f64,
+    /// Largest peak memory of the strategy in any round of any run, if allocations are counted
+    max_peak_strategy_bytes: Option<usize>,
+}
+
+impl fmt::Display for BenchResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  avg nodes burned: {:.2}", self.avg_burned)?;
+        writeln!(f, "  avg nodes defended: {:.2}", self.avg_def)?;
+        writeln!(f, "  avg end time: {:.2}", self.avg_end_time)?;
+        writeln!(f, "  avg simulation time: {:.2} ms", self.avg_sim_millis)?;
+        writeln!(f, "  avg root rejections: {:.2}", self.avg_root_rejections)?;
+        match self.max_peak_strategy_bytes {
+            Some(bytes) => write!(f, "  max peak strategy memory: {} bytes", bytes),
+            None => write!(f, "  max peak strategy memory: not counted")
+        }
+    }
 }
 
 fn main() {
@@ -17,9 +47,6 @@ fn main() {
     env::set_var("RUST_BACKTRACE", "1");
     env_logger::init();
 
-    let graphs = osmff_lib::load_graphs("data/")
-        .expect("Failed to load graphs. Check whether 'data/' directory exists.");
-
     let args: Vec<_> = env::args().collect();
 
     if !args.contains(&"--graph".to_string()) {
@@ -32,15 +59,13 @@ fn main() {
         panic!("{}", err);
     }
 
-    let mut settings = OSMFSettings {
-        graph_name: "".to_string(),
-        strategy_name: "".to_string(),
-        num_roots: 1,
-        num_ffs: 1,
-        strategy_every: 1,
-    };
+    let mut settings = OSMFSettings::new("", "", 1, 1, 1);
 
     let mut loop_count: usize = 1;
+    let mut num_threads: usize = 0;
+    let mut report_path: Option<String> = None;
+    let mut http_target: Option<HttpTarget> = None;
+    let mut http_zooms = DEFAULT_HTTP_ZOOMS.to_vec();
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
@@ -62,10 +87,38 @@ fn main() {
                 settings.strategy_every = args[i+1].parse()
                     .expect("Invalid argument: strategy_every");
             }
+            "--min-burn" => {
+                settings.min_burn_potential = args[i+1].parse()
+                    .expect("Invalid argument: min_burn_potential");
+            }
+            "--resamples" => {
+                settings.max_root_resamples = Some(args[i+1].parse()
+                    .expect("Invalid argument: max_root_resamples"));
+            }
+            "--failures" => {
+                settings.defense_failure_prob = Some(args[i+1].parse()
+                    .expect("Invalid argument: defense_failure_prob"));
+            }
             "--loop" => {
                 loop_count = args[i+1].parse()
                     .expect("Invalid argument: loop_count");
             }
+            "--threads" => {
+                num_threads = args[i+1].parse()
+                    .expect("Invalid argument: num_threads");
+            }
+            "--report" => {
+                report_path = Some(args[i+1].clone());
+            }
+            "--http" => {
+                http_target = Some(HttpTarget::parse(&args[i+1])
+                    .unwrap_or_else(|err| panic!("Invalid argument: base_url: {}", err)));
+            }
+            "--zooms" => {
+                http_zooms = args[i+1].split(',')
+                    .map(|zoom| zoom.parse().expect("Invalid argument: zooms"))
+                    .collect();
+            }
             _ => {
                 let err = format!("Unknown argument: {}", &args[i]);
                 log::error!("{}", &err);
@@ -78,36 +131,100 @@ fn main() {
     log::info!("Benchmarking with the following problem settings: {:?}", &settings);
     log::info!("Loop count: {}", loop_count);
 
+    // Benchmark a running server instead of the library. The server loads the graphs itself.
+    if let Some(target) = http_target {
+        run_http_bench(&target, &settings, loop_count, num_threads, &http_zooms);
+        return;
+    }
+
+    let graphs = osmff_lib::load_graphs("data/")
+        .expect("Failed to load graphs. Check whether 'data/' directory exists.");
     let graph = graphs.get(&settings.graph_name)
         .expect("No such graph parsed");
 
     log::info!("Starting benchmarks");
 
-    let mut sum_burned = 0;
-    let mut sum_defended = 0;
-    let mut sum_end_time = 0;
-    let mut sum_sim_millis = 0;
-    for _ in 0..loop_count {
-        let strategy = OSMFStrategy::from_name_and_graph(&settings.strategy_name, graph.clone())
-            .expect("Invalid strategy specified");
-        let mut problem = OSMFProblem::new(graph.clone(), settings.clone(), strategy)
-            .expect("Invalid simulation settings");
-
-        problem.simulate();
-
-        let results = problem.simulation_response();
-        sum_burned += results.nodes_burned;
-        sum_defended += results.nodes_defended;
-        sum_end_time += results.end_time;
-        sum_sim_millis += results.simulation_time_millis;
+    // Several strategies can be compared by separating their names with commas. All strategies
+    // are simulated with the same seeds, i.e. from the same fire roots.
+    let strategy_names: Vec<_> = settings.strategy_name.split(',').map(str::to_string).collect();
+    let seeds: Vec<u64> = (0..loop_count).map(|_| thread_rng().gen()).collect();
+    let mut report = StrategyReport::new(&format!("Strategy comparison on {}", &settings.graph_name));
+
+    // Run the simulations in parallel on the compute pool
+    let pool = compute::build_compute_pool(num_threads);
+    for strategy_name in strategy_names {
+        let mut settings = settings.clone();
+        settings.strategy_name = strategy_name;
+
+        let runs: Vec<_> = pool.install(|| {
+            seeds.par_iter().enumerate()
+                .map(|(i, seed)| {
+                    let strategy = OSMFStrategy::from_name_and_graph(&settings.strategy_name, graph.clone())
+                        .expect("Invalid strategy specified");
+                    let mut settings = settings.clone();
+                    settings.seed = Some(*seed);
+                    let mut problem = OSMFProblem::new(graph.clone(), settings, strategy)
+                        .expect("Invalid simulation settings");
+
+                    problem.simulate();
+
+                    // The report shows the final state of the first simulation of every strategy
+                    let frame = if report_path.is_some() && i == 0 {
+                        let end_time = problem.simulation_response().end_time;
+                        Some(problem.view_response_alt(1.0, &end_time, &ViewOptions::default()))
+                    } else {
+                        None
+                    };
+                    let response = problem.simulation_response();
+                    let stats = (response.root_rejections, response.peak_strategy_bytes);
+                    (ReportRun::from_problem(&problem), frame, stats)
+                })
+                .collect()
+        });
+
+        let n = loop_count as f64;
+        let bench_results = BenchResults {
+            avg_burned: runs.iter().map(|(run, _, _)| run.nodes_burned as f64).sum::<f64>() / n,
+            avg_def: runs.iter().map(|(run, _, _)| run.nodes_defended as f64).sum::<f64>() / n,
+            avg_end_time: runs.iter().map(|(run, _, _)| run.end_time as f64).sum::<f64>() / n,
+            avg_sim_millis: runs.iter().map(|(run, _, _)| run.simulation_time_millis as f64).sum::<f64>() / n,
+            avg_root_rejections: runs.iter().map(|(_, _, (root_rejections, _))| *root_rejections as f64).sum::<f64>() / n,
+            max_peak_strategy_bytes: runs.iter().filter_map(|(_, _, (_, peak_bytes))| *peak_bytes).max(),
+        };
+
+        log::info!("Benchmark results of strategy {}:\n{}", &settings.strategy_name, bench_results);
+
+        for (run, frame, _) in runs {
+            if let Some(png) = frame {
+                report.add_frame(&format!("{} (seed {})", run.strategy_name, run.seed), png);
+            }
+            report.add_run(run);
+        }
     }
 
-    let bench_results = BenchResults {
-        avg_burned: sum_burned as f64 / loop_count as f64,
-        avg_def: sum_defended as f64 / loop_count as f64,
-        avg_end_time: sum_end_time as f64 / loop_count as f64,
-        avg_sim_millis: sum_sim_millis as f64 / loop_count as f64,
-    };
+    if let Some(path) = report_path {
+        fs::write(&path, report.to_html())
+            .unwrap_or_else(|err| panic!("Failed to write report to {}: {}", path, err));
+        log::info!("Wrote report to {}", path);
+    }
+}
 
-    log::info!("Benchmark results:\n{:#?}", bench_results);
-}
\ No newline at end of file
+/// Benchmark the server `target` through its HTTP API. Every strategy of the settings `settings`
+/// is simulated `loop_count` times by `num_threads` concurrent clients, after which the
+/// metadata of several rounds and the final view at the zoom levels `zooms` are requested.
+fn run_http_bench(target: &HttpTarget, settings: &OSMFSettings, loop_count: usize, num_threads: usize, zooms: &[f64]) {
+    log::info!("Benchmarking server {:?} at zoom levels {:?}", target, zooms);
+
+    let seeds: Vec<u64> = (0..loop_count).map(|_| thread_rng().gen()).collect();
+    let pool = compute::build_compute_pool(num_threads);
+    for strategy_name in settings.strategy_name.split(',') {
+        let mut settings = settings.clone();
+        settings.strategy_name = strategy_name.to_string();
+
+        let stats = pool.install(|| bench_http::run(target, &settings, &seeds, zooms));
+        log::info!("Latencies by endpoint of strategy {}:", strategy_name);
+        for (endpoint, stats) in stats {
+            log::info!("  {}: {}", endpoint, stats);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use serde_json::Value;
+
+use osmff_lib::firefighter::problem::OSMFSettings;
+
+/// Number of rounds whose step metadata is requested after every simulation
+const NUM_STEP_SAMPLES: u64 = 10;
+
+/// Server that is benchmarked, given by a base URL like `http://localhost:8080`
+#[derive(Debug, Clone)]
+pub struct HttpTarget {
+    host: String,
+    port: u16,
+    /// Path prefix of all endpoints, without a trailing slash
+    base_path: String,
+}
+
+impl HttpTarget {
+    /// Parse the base URL `base_url`. Only plain HTTP is supported.
+    pub fn parse(base_url: &str) -> Result<Self, String> {
+        let rest = base_url.strip_prefix("http://")
+            .ok_or_else(|| format!("Unsupported base URL {}, only http:// URLs are supported", base_url))?;
+        let (authority, base_path) = match rest.find('/') {
+            Some(i) => (&rest[..i], rest[i..].trim_end_matches('/')),
+            None => (rest, "")
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse()
+                .map_err(|_| format!("Invalid port in base URL {}", base_url))?),
+            None => (authority, 80)
+        };
+        if host.is_empty() {
+            return Err(format!("Missing host in base URL {}", base_url));
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            base_path: base_path.to_string(),
+        })
+    }
+}
+
+/// Response to a request of an `HttpSession`
+struct HttpResponse {
+    status: u16,
+    body: Vec<u8>,
+}
+
+/// Client that keeps the session cookie of the server across its requests, such that all
+/// requests refer to the same simulation
+struct HttpSession<'a> {
+    target: &'a HttpTarget,
+    cookie: Option<String>,
+}
+
+impl<'a> HttpSession<'a> {
+    fn new(target: &'a HttpTarget) -> Self {
+        Self { target, cookie: None }
+    }
+
+    /// Send a request with method `method` and the JSON body `body`, if any, to the endpoint
+    /// `path`, which includes its query string
+    fn request(&mut self, method: &str, path: &str, body: Option<&[u8]>) -> io::Result<HttpResponse> {
+        let mut stream = TcpStream::connect((self.target.host.as_str(), self.target.port))?;
+
+        let mut head = format!("{} {}{} HTTP/1.1\r\nHost: {}:{}\r\nConnection: close\r\n",
+                               method, self.target.base_path, path, self.target.host, self.target.port);
+        if let Some(cookie) = &self.cookie {
+            head.push_str(&format!("Cookie: {}\r\n", cookie));
+        }
+        if let Some(body) = body {
+            head.push_str(&format!("Content-Type: application/json\r\nContent-Length: {}\r\n", body.len()));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes())?;
+        if let Some(body) = body {
+            stream.write_all(body)?;
+        }
+
+        // The server closes the connection after the response
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw)?;
+        self.parse_response(&raw)
+    }
+
+    /// Parse the raw response `raw` and remember the session cookie it sets, if any
+    fn parse_response(&mut self, raw: &[u8]) -> io::Result<HttpResponse> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        let head_end = raw.windows(4).position(|window| window == b"\r\n\r\n")
+            .ok_or_else(|| invalid("Incomplete response head"))?;
+        let head = std::str::from_utf8(&raw[..head_end]).map_err(|_| invalid("Invalid response head"))?;
+        let mut lines = head.split("\r\n");
+        let status = lines.next()
+            .and_then(|status_line| status_line.split(' ').nth(1))
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| invalid("Invalid status line"))?;
+
+        let mut is_chunked = false;
+        for line in lines {
+            let (name, value) = match line.split_once(':') {
+                Some((name, value)) => (name.trim().to_ascii_lowercase(), value.trim()),
+                None => continue
+            };
+            if name == "set-cookie" && value.starts_with("sid=") {
+                let cookie = value.split(';').next().unwrap_or(value);
+                self.cookie = Some(cookie.to_string());
+            } else if name == "transfer-encoding" && value.eq_ignore_ascii_case("chunked") {
+                is_chunked = true;
+            }
+        }
+
+        let body = &raw[head_end + 4..];
+        let body = if is_chunked {
+            decode_chunked(body).ok_or_else(|| invalid("Invalid chunked response body"))?
+        } else {
+            body.to_vec()
+        };
+        Ok(HttpResponse { status, body })
+    }
+}
+
+/// Decode the body `body` in chunked transfer encoding
+fn decode_chunked(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|window| window == b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line_end]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(decoded);
+        }
+        decoded.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+/// Latency percentiles of an endpoint
+#[derive(Debug)]
+pub struct LatencyStats {
+    pub num_requests: usize,
+    /// Number of requests that failed or were answered with an error status
+    pub num_errors: usize,
+    pub p50_millis: f64,
+    pub p90_millis: f64,
+    pub p99_millis: f64,
+    pub max_millis: f64,
+}
+
+impl LatencyStats {
+    /// Compute the percentiles of the latencies `latencies` of the successful requests
+    fn new(mut latencies: Vec<Duration>, num_errors: usize) -> Self {
+        latencies.sort();
+        let percentile = |p: f64| match latencies.len() {
+            0 => 0.0,
+            n => {
+                // Nearest-rank percentile
+                let rank = ((p * n as f64).ceil() as usize).clamp(1, n);
+                latencies[rank - 1].as_secs_f64() * 1000.0
+            }
+        };
+
+        Self {
+            num_requests: latencies.len() + num_errors,
+            num_errors,
+            p50_millis: percentile(0.5),
+            p90_millis: percentile(0.9),
+            p99_millis: percentile(0.99),
+            max_millis: percentile(1.0),
+        }
+    }
+}
+
+impl fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} requests ({} failed), p50 {:.1} ms, p90 {:.1} ms, p99 {:.1} ms, max {:.1} ms",
+               self.num_requests, self.num_errors, self.p50_millis, self.p90_millis, self.p99_millis,
+               self.max_millis)
+    }
+}
+
+/// Latencies of the successful requests to every endpoint and the number of failed requests
+#[derive(Default)]
+struct Latencies {
+    by_endpoint: BTreeMap<&'static str, (Vec<Duration>, usize)>,
+}
+
+impl Latencies {
+    /// Time the request `request` to the endpoint `endpoint`. Returns the response body if the
+    /// request succeeded.
+    fn time(&mut self, endpoint: &'static str, request: impl FnOnce() -> io::Result<HttpResponse>)
+        -> Option<Vec<u8>> {
+        let start = Instant::now();
+        let response = request();
+        let elapsed = start.elapsed();
+
+        let (latencies, num_errors) = self.by_endpoint.entry(endpoint).or_default();
+        match response {
+            Ok(response) if response.status < 400 => {
+                latencies.push(elapsed);
+                Some(response.body)
+            }
+            Ok(response) => {
+                log::warn!("Request to {} failed with status {}: {}", endpoint, response.status,
+                           String::from_utf8_lossy(&response.body));
+                *num_errors += 1;
+                None
+            }
+            Err(err) => {
+                log::warn!("Request to {} failed: {}", endpoint, err);
+                *num_errors += 1;
+                None
+            }
+        }
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        for (endpoint, (latencies, num_errors)) in other.by_endpoint {
+            let entry = self.by_endpoint.entry(endpoint).or_default();
+            entry.0.extend(latencies);
+            entry.1 += num_errors;
+        }
+        self
+    }
+}
+
+/// Simulate with the settings `settings` in a new session of the server `target`, then request
+/// the metadata of evenly spaced rounds and the final view at every zoom level of `zooms`
+fn run_session(target: &HttpTarget, settings: &OSMFSettings, zooms: &[f64]) -> Latencies {
+    let mut latencies = Latencies::default();
+    let mut session = HttpSession::new(target);
+
+    let body = serde_json::to_vec(settings).expect("Settings should be serializable");
+    let sim = match latencies.time("POST /simulate", || session.request("POST", "/simulate", Some(&body))) {
+        Some(sim) => sim,
+        None => return latencies
+    };
+    let end_time = serde_json::from_slice::<Value>(&sim).ok()
+        .and_then(|sim| sim["end_time"].as_u64())
+        .unwrap_or(0);
+
+    let num_samples = NUM_STEP_SAMPLES.min(end_time + 1);
+    for i in 0..num_samples {
+        let time = if num_samples > 1 { i * end_time / (num_samples - 1) } else { 0 };
+        latencies.time("GET /stepmeta",
+                       || session.request("GET", &format!("/stepmeta?time={}", time), None));
+    }
+    for zoom in zooms {
+        latencies.time("GET /view",
+                       || session.request("GET", &format!("/view?zoom={}&time={}", zoom, end_time), None));
+    }
+
+    latencies
+}
+
+/// Benchmark the server `target` by simulating once per seed of `seeds` with the settings
+/// `settings`, each in its own session. The sessions run concurrently on the current thread pool.
+/// Returns the latency percentiles by endpoint.
+pub fn run(target: &HttpTarget, settings: &OSMFSettings, seeds: &[u64], zooms: &[f64])
+    -> BTreeMap<&'static str, LatencyStats> {
+    let latencies = seeds.par_iter()
+        .map(|seed| {
+            let mut settings = settings.clone();
+            settings.seed = Some(*seed);
+            run_session(target, &settings, zooms)
+        })
+        .reduce(Latencies::default, Latencies::merge);
+
+    latencies.by_endpoint.into_iter()
+        .map(|(endpoint, (latencies, num_errors))| (endpoint, LatencyStats::new(latencies, num_errors)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles() {
+        // Latencies of 1 to 100 ms in reverse order
+        let latencies = (1..=100).rev().map(Duration::from_millis).collect();
+        let stats = LatencyStats::new(latencies, 3);
+        assert_eq!((stats.num_requests, stats.num_errors), (103, 3));
+        assert_eq!((stats.p50_millis, stats.p90_millis, stats.p99_millis, stats.max_millis),
+                   (50.0, 90.0, 99.0, 100.0));
+
+        // Nearest-rank percentiles of few latencies are the next larger latency
+        let latencies = vec![Duration::from_millis(20), Duration::from_millis(10), Duration::from_millis(30)];
+        let stats = LatencyStats::new(latencies, 0);
+        assert_eq!((stats.p50_millis, stats.p90_millis, stats.p99_millis, stats.max_millis),
+                   (20.0, 30.0, 30.0, 30.0));
+
+        let stats = LatencyStats::new(Vec::new(), 2);
+        assert_eq!((stats.num_requests, stats.num_errors), (2, 2));
+        assert_eq!(stats.max_millis, 0.0);
+    }
+}
@@ -1,7 +1,11 @@
-/// Efficient binary min-heap to be used as Dijkstra PQ on FMI graph data
+/// Position of keys that are not contained in the heap
+const NOT_CONTAINED: u32 = u32::MAX;
+
+/// Efficient binary min-heap to be used as Dijkstra PQ on FMI graph data.
+/// Keys and positions are stored with 32 bits like the node ids of graphs.
 pub struct BinaryMinHeap {
-    heap: Vec<usize>,
-    positions: Vec<usize>,
+    heap: Vec<u32>,
+    positions: Vec<u32>,
 }
 
 /// Get the left child index of `index`
@@ -28,14 +32,14 @@ impl BinaryMinHeap {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             heap: Vec::with_capacity(capacity),
-            positions: vec![usize::MAX; capacity],
+            positions: vec![NOT_CONTAINED; capacity],
         }
     }
 
     /// Set key `key` at position `index`
-    fn set_key_and_pos(&mut self, key: usize, index: usize) {
+    fn set_key_and_pos(&mut self, key: u32, index: usize) {
         self.heap[index] = key;
-        self.positions[key] = index;
+        self.positions[key as usize] = index as u32;
     }
 
     /// Swap positions of the keys at `parent` and `child`
@@ -54,12 +58,12 @@ impl BinaryMinHeap {
         let right = get_right(index);
 
         let mut smallest;
-        if left < len && priorities[self.heap[left]] < priorities[self.heap[index]] {
+        if left < len && priorities[self.heap[left] as usize] < priorities[self.heap[index] as usize] {
             smallest = left;
         } else {
             smallest = index;
         }
-        if right < len && priorities[self.heap[right]] < priorities[self.heap[smallest]] {
+        if right < len && priorities[self.heap[right] as usize] < priorities[self.heap[smallest] as usize] {
             smallest = right;
         }
 
@@ -71,12 +75,12 @@ impl BinaryMinHeap {
 
     /// Push a key on the heap
     pub fn push(&mut self, key: usize, priorities: &Vec<usize>) {
-        self.heap.push(key);
+        self.heap.push(key as u32);
         let mut index = self.heap.len() - 1;
-        self.positions[key] = index;
+        self.positions[key] = index as u32;
 
         let mut parent = get_parent(index);
-        while parent != index && priorities[self.heap[index]] < priorities[self.heap[parent]] {
+        while parent != index && priorities[self.heap[index] as usize] < priorities[self.heap[parent] as usize] {
             self.swap(parent, index);
             index = parent;
             parent = get_parent(index);
@@ -86,7 +90,7 @@ impl BinaryMinHeap {
     /// Pop the minimum key from the heap
     pub fn pop(&mut self, priorities: &Vec<usize>) -> usize {
         let min_key = self.heap[0];
-        self.positions[min_key] = usize::MAX;
+        self.positions[min_key as usize] = NOT_CONTAINED;
 
         let tail_key = self.heap.pop().unwrap();
         if !self.is_empty() {
@@ -94,16 +98,16 @@ impl BinaryMinHeap {
             self.reheap(0, priorities);
         }
 
-        min_key
+        min_key as usize
     }
 
     /// Decrease the position of a key.
     /// This method must be called iff the priority of a key
     /// decreases after the heap creation.
     pub fn decrease_key(&mut self, key: usize, priorities: &Vec<usize>) {
-        let mut index = self.positions[key];
+        let mut index = self.positions[key] as usize;
         let mut parent = get_parent(index);
-        while index > 0 && priorities[self.heap[parent]] > priorities[self.heap[index]] {
+        while index > 0 && priorities[self.heap[parent] as usize] > priorities[self.heap[index] as usize] {
             self.swap(parent, index);
             index = parent;
             parent = get_parent(index);
@@ -112,7 +116,7 @@ impl BinaryMinHeap {
 
     /// Returns `true` if the heap contains `key`
     pub fn contains(&self, key: usize) -> bool {
-        self.positions[key] != usize::MAX
+        self.positions[key] != NOT_CONTAINED
     }
 
     /// Returns `true` if the heap is empty
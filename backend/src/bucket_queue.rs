@@ -0,0 +1,70 @@
+/// Bucket queue for Dial's variant of the Dijkstra on graphs with small integer edge weights.
+/// Keys are kept in one bucket per priority. Since the priorities of all keys in the queue lie
+/// within the maximum edge weight of the smallest priority, the buckets are reused cyclically.
+/// Keys are not moved when their priority decreases, they are pushed again instead and outdated
+/// entries are skipped by the caller.
+pub struct BucketQueue {
+    buckets: Vec<Vec<usize>>,
+    /// Priority of the bucket that is popped next
+    current: usize,
+    len: usize,
+}
+
+impl BucketQueue {
+    /// Create a new `BucketQueue` for edge weights of at most `max_edge_dist`
+    pub fn new(max_edge_dist: usize) -> Self {
+        Self {
+            buckets: vec![Vec::new(); max_edge_dist + 1],
+            current: 0,
+            len: 0,
+        }
+    }
+
+    /// Push the key `key` with priority `priority` on the queue.
+    /// The priority must not be smaller than the priority of the last popped key or greater than
+    /// it by more than the maximum edge weight.
+    pub fn push(&mut self, key: usize, priority: usize) {
+        debug_assert!(priority >= self.current && priority - self.current < self.buckets.len());
+        let num_buckets = self.buckets.len();
+        self.buckets[priority % num_buckets].push(key);
+        self.len += 1;
+    }
+
+    /// Pop a key with minimum priority from the queue.
+    /// Returns the key and its priority, or `None` if the queue is empty.
+    pub fn pop(&mut self) -> Option<(usize, usize)> {
+        if self.len == 0 {
+            return None;
+        }
+        let num_buckets = self.buckets.len();
+        loop {
+            if let Some(key) = self.buckets[self.current % num_buckets].pop() {
+                self.len -= 1;
+                return Some((key, self.current));
+            }
+            self.current += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bucket_queue::BucketQueue;
+
+    #[test]
+    fn test_push_pop() {
+        let mut queue = BucketQueue::new(3);
+        queue.push(0, 0);
+        queue.push(1, 3);
+        queue.push(2, 2);
+
+        assert_eq!(Some((0, 0)), queue.pop());
+        queue.push(3, 1);
+        assert_eq!(Some((3, 1)), queue.pop());
+        queue.push(4, 4);
+        assert_eq!(Some((2, 2)), queue.pop());
+        assert_eq!(Some((1, 3)), queue.pop());
+        assert_eq!(Some((4, 4)), queue.pop());
+        assert_eq!(None, queue.pop());
+    }
+}
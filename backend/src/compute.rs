@@ -0,0 +1,310 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use once_cell::sync::{Lazy, OnceCell};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::Serialize;
+
+/// Thread pool of parallel Dijkstras, see `init_dijkstra_pool`
+static DIJKSTRA_POOL: OnceCell<ThreadPool> = OnceCell::new();
+
+/// Thread pools of the parallel components of simulations with a thread limit by their number of
+/// threads, see `with_thread_limit`
+static LIMITED_POOLS: Lazy<Mutex<HashMap<usize, Arc<ThreadPool>>>> = Lazy::new(Default::default);
+
+thread_local! {
+    /// Thread pool that limits the parallel components started on this thread, if any
+    static THREAD_LIMIT: RefCell<Option<Arc<ThreadPool>>> = const { RefCell::new(None) };
+}
+
+/// Build the shared compute pool that runs batch simulations and analyses with `num_threads`
+/// worker threads. If `num_threads` is `0`, one worker thread per logical core is spawned.
+pub fn build_compute_pool(num_threads: usize) -> ThreadPool {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .thread_name(|i| format!("osmff-compute-{}", i))
+        .build()
+        .expect("Failed to build compute pool");
+    log::info!("Initialized compute pool with {} threads", pool.current_num_threads());
+
+    pool
+}
+
+/// Build the thread pool that parallel Dijkstras run on with at most `num_threads` worker
+/// threads. If `num_threads` is `0`, one worker thread per logical core is spawned.
+/// The pool can only be built once, before any parallel Dijkstra runs. Otherwise, it is built
+/// with one thread per logical core on first use.
+pub fn init_dijkstra_pool(num_threads: usize) {
+    if DIJKSTRA_POOL.set(build_dijkstra_pool(num_threads)).is_err() {
+        log::warn!("Dijkstra pool has already been initialized");
+    }
+}
+
+/// Get the thread pool that parallel Dijkstras run on
+pub(crate) fn dijkstra_pool() -> &'static ThreadPool {
+    DIJKSTRA_POOL.get_or_init(|| build_dijkstra_pool(0))
+}
+
+/// Build the thread pool of parallel Dijkstras with `num_threads` worker threads
+fn build_dijkstra_pool(num_threads: usize) -> ThreadPool {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .thread_name(|i| format!("osmff-dijkstra-{}", i))
+        .build()
+        .expect("Failed to build Dijkstra pool");
+    log::info!("Initialized Dijkstra pool with {} threads", pool.current_num_threads());
+
+    pool
+}
+
+/// Get the maximum thread limit of simulations, i.e. the number of logical cores
+pub fn max_thread_limit() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Get the thread pool with `num_threads` worker threads that is shared by the parallel
+/// components of all simulations with the same thread limit, such that concurrent simulations do
+/// not use more threads in total than their limit either
+fn limited_pool(num_threads: usize) -> Arc<ThreadPool> {
+    LIMITED_POOLS.lock().unwrap()
+        .entry(num_threads)
+        .or_insert_with(|| {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .thread_name(move |i| format!("osmff-limited-{}-{}", num_threads, i))
+                .build()
+                .expect("Failed to build limited thread pool");
+            log::info!("Initialized limited thread pool with {} threads", num_threads);
+            Arc::new(pool)
+        })
+        .clone()
+}
+
+/// Restores the thread limit of the current thread when dropped, even if limited code panicked
+struct ThreadLimitGuard {
+    previous: Option<Arc<ThreadPool>>,
+}
+
+impl Drop for ThreadLimitGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        THREAD_LIMIT.with(|limit| *limit.borrow_mut() = previous);
+    }
+}
+
+/// Call `f` such that the parallel components it starts on the current thread, i.e. parallel
+/// Dijkstras and analyses, use at most `num_threads` threads, if set. Otherwise, they use the
+/// Dijkstra pool or the current thread pool as usual.
+pub fn with_thread_limit<R>(num_threads: Option<usize>, f: impl FnOnce() -> R) -> R {
+    let pool = match num_threads {
+        Some(num_threads) => limited_pool(num_threads),
+        None => return f()
+    };
+    let previous = THREAD_LIMIT.with(|limit| limit.replace(Some(pool)));
+    let _guard = ThreadLimitGuard { previous };
+    f()
+}
+
+/// Get the thread pool that limits the parallel components started on the current thread, if any
+pub(crate) fn limited_pool_of_thread() -> Option<Arc<ThreadPool>> {
+    THREAD_LIMIT.with(|limit| limit.borrow().clone())
+}
+
+/// Run `op` on the thread pool that limits the parallel components started on the current thread,
+/// if any, or on the current thread otherwise
+pub(crate) fn install_limited<R: Send>(op: impl FnOnce() -> R + Send) -> R {
+    match limited_pool_of_thread() {
+        Some(pool) => pool.install(op),
+        None => op()
+    }
+}
+
+/// Progress of a job that consists of a number of independent work items
+#[derive(Debug, Default)]
+pub struct JobProgress {
+    done: AtomicUsize,
+    total: AtomicUsize,
+}
+
+/// Snapshot of the progress of a job
+#[derive(Serialize)]
+pub struct OSMFJobProgress {
+    done: usize,
+    total: usize,
+    progress: f64,
+}
+
+impl JobProgress {
+    /// Create a new job progress without any work items
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the total number of work items of the job to `total`
+    pub fn set_total(&self, total: usize) {
+        self.total.store(total, Ordering::SeqCst);
+    }
+
+    /// Mark one more work item of the job as done
+    pub fn inc(&self) {
+        self.done.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Get a snapshot of the current progress of the job
+    pub fn snapshot(&self) -> OSMFJobProgress {
+        let done = self.done.load(Ordering::SeqCst);
+        let total = self.total.load(Ordering::SeqCst);
+        OSMFJobProgress {
+            done,
+            total,
+            progress: if total > 0 { done as f64 / total as f64 } else { 0.0 },
+        }
+    }
+}
+
+/// Priority of a render job. Waiting jobs with a higher priority are always started before
+/// waiting jobs with a lower priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RenderPriority {
+    /// Renders a user is waiting for, e.g. views
+    Interactive,
+    /// Exports of simulation results
+    Export,
+    /// Pre-generation of frames in the background
+    Pregen,
+}
+
+/// State of a render queue
+#[derive(Debug, Default)]
+struct RenderQueueState {
+    num_running: usize,
+    /// Waiting jobs, ordered by their priority, fairness round and arrival
+    waiting: BTreeSet<(RenderPriority, u64, u64)>,
+    /// Fairness round of the next job of every owner with waiting jobs per priority
+    next_rounds: HashMap<(RenderPriority, String), u64>,
+    /// Fairness round of the most recently started job per priority
+    current_rounds: HashMap<RenderPriority, u64>,
+    next_arrival: u64,
+}
+
+/// Queue that limits the number of concurrently running render jobs.
+/// Waiting jobs are started by priority. Jobs of the same priority are started round-robin by
+/// their owners, e.g. sessions, such that a single owner with many jobs cannot starve others.
+#[derive(Debug)]
+pub struct RenderQueue {
+    max_running: usize,
+    state: Mutex<RenderQueueState>,
+    changed: Condvar,
+}
+
+/// Permit to run a render job, which is returned to its render queue when dropped
+pub struct RenderPermit<'a> {
+    queue: &'a RenderQueue,
+}
+
+impl Drop for RenderPermit<'_> {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().num_running -= 1;
+        self.queue.changed.notify_all();
+    }
+}
+
+impl RenderQueue {
+    /// Create a new render queue that runs at most `max_running` jobs concurrently. If
+    /// `max_running` is `0`, one job per logical core may run.
+    pub fn new(max_running: usize) -> Self {
+        let max_running = if max_running > 0 {
+            max_running
+        } else {
+            std::thread::available_parallelism().map_or(1, |n| n.get())
+        };
+        log::info!("Initialized render queue with {} concurrent jobs", max_running);
+
+        Self {
+            max_running,
+            state: Mutex::new(RenderQueueState::default()),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Wait until a job of `owner` with priority `priority` may run.
+    /// The job runs for as long as the returned permit lives.
+    pub fn acquire(&self, priority: RenderPriority, owner: &str) -> RenderPermit<'_> {
+        let mut state = self.state.lock().unwrap();
+
+        // Every job of an owner is scheduled one round after its previous job
+        let current_round = state.current_rounds.get(&priority).copied().unwrap_or(0);
+        let next_round = state.next_rounds.entry((priority, owner.to_string())).or_insert(0);
+        let round = current_round.max(*next_round);
+        *next_round = round + 1;
+        let job = (priority, round, state.next_arrival);
+        state.next_arrival += 1;
+        state.waiting.insert(job);
+
+        while state.num_running >= self.max_running || state.waiting.iter().next() != Some(&job) {
+            state = self.changed.wait(state).unwrap();
+        }
+
+        state.waiting.remove(&job);
+        state.num_running += 1;
+        state.current_rounds.insert(priority, round);
+        // Owners without jobs in later rounds are no longer ahead of the current round
+        state.next_rounds.retain(|(other, _), next_round| *other != priority || *next_round > round);
+        drop(state);
+
+        // The next waiting job may be able to run as well
+        self.changed.notify_all();
+
+        RenderPermit { queue: self }
+    }
+
+    /// Get the number of waiting jobs
+    pub fn num_waiting(&self) -> usize {
+        self.state.lock().unwrap().waiting.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::compute::{RenderPriority, RenderQueue};
+
+    #[test]
+    fn test_render_queue() {
+        let queue = Arc::new(RenderQueue::new(1));
+        let started = Arc::new(Mutex::new(Vec::new()));
+
+        let permit = queue.acquire(RenderPriority::Interactive, "a");
+        let jobs = [(RenderPriority::Pregen, "a"), (RenderPriority::Pregen, "a"),
+                    (RenderPriority::Pregen, "b"), (RenderPriority::Interactive, "c")];
+        let handles: Vec<_> = jobs.iter().enumerate()
+            .map(|(i, &(priority, owner))| {
+                let (job_queue, started) = (queue.clone(), started.clone());
+                let handle = thread::spawn(move || {
+                    let _permit = job_queue.acquire(priority, owner);
+                    started.lock().unwrap().push(owner);
+                });
+                // Jobs have to arrive in order
+                while queue.num_waiting() <= i {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                handle
+            })
+            .collect();
+
+        drop(permit);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // Interactive jobs come first, and the pre-generation jobs of b do not wait for all of a
+        assert_eq!(*started.lock().unwrap(), vec!["c", "a", "b", "a"]);
+        assert_eq!(queue.num_waiting(), 0);
+    }
+}
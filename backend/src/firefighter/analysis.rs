@@ -0,0 +1,675 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use geo::{ChamberlainDuquetteArea, ConvexHull, HaversineDistance, MultiPoint, Point};
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::compute::{self, JobProgress};
+use crate::firefighter::problem::{self, NodeDataStorage, OSMFProblem, OSMFSettings};
+use crate::firefighter::TimeUnit;
+use crate::graph::GraphView;
+
+/// A spread corridor, i.e. a path in the burn forest along which the fire reached the largest
+/// number of nodes
+#[derive(Serialize)]
+pub struct OSMFCorridor {
+    nodes: Vec<usize>,
+    subtree_sizes: Vec<usize>,
+}
+
+impl OSMFCorridor {
+    /// Returns the ids of the nodes along this corridor, starting at its head
+    pub fn nodes(&self) -> &[usize] {
+        &self.nodes
+    }
+
+    /// Returns the number of nodes that burned through the head of this corridor
+    pub fn size(&self) -> usize {
+        self.subtree_sizes[0]
+    }
+}
+
+/// Predicted burn times of all nodes of a graph for a fire that spreads from given roots without
+/// being contained
+#[derive(Serialize)]
+pub struct OSMFBurnPrediction {
+    roots: Vec<usize>,
+    num_reachable: usize,
+    max_burn_time: Option<TimeUnit>,
+    /// Burn time of each node, indexed by node id, or `None` if the fire never reaches the node
+    burn_times: Vec<Option<TimeUnit>>,
+}
+
+impl OSMFBurnPrediction {
+    /// Returns the predicted burn time of the node with id `node_id`, if the fire reaches it
+    pub fn burn_time(&self, node_id: usize) -> Option<TimeUnit> {
+        self.burn_times.get(node_id).copied().flatten()
+    }
+}
+
+/// Predict the time at which each node of `graph` would start burning if the fire spread from
+/// `roots` without any firefighters, i.e. the shortest distance from any root
+pub fn predict_burn_times(graph: &dyn GraphView, roots: &[usize]) -> OSMFBurnPrediction {
+    let burn_times: Vec<_> = graph.run_dijkstra(roots).into_iter()
+        .map(|dist| if dist < usize::MAX { Some(dist as TimeUnit) } else { None })
+        .collect();
+
+    OSMFBurnPrediction {
+        roots: roots.to_vec(),
+        num_reachable: burn_times.iter().filter(|time| time.is_some()).count(),
+        max_burn_time: burn_times.iter().flatten().max().copied(),
+        burn_times,
+    }
+}
+
+/// Bin of an edge weight histogram, i.e. the number of edges with weights in `[min, max)`
+#[derive(Serialize)]
+pub struct OSMFWeightBin {
+    min: usize,
+    max: usize,
+    count: usize,
+}
+
+/// Suggested strategy interval for a firefighter response cadence of `response_minutes`
+#[derive(Serialize)]
+pub struct OSMFCadenceSuggestion {
+    response_minutes: f64,
+    strategy_every: TimeUnit,
+    /// Number of median edges the fire advances along between two firefighter responses
+    edges_per_response: f64,
+}
+
+/// Distribution of the edge weights of a graph together with suggested strategy intervals that
+/// correspond to realistic firefighter response cadences for a given fire spread rate
+#[derive(Serialize)]
+pub struct OSMFSpreadCalibration {
+    num_edges: usize,
+    min_weight: usize,
+    max_weight: usize,
+    mean_weight: f64,
+    median_weight: usize,
+    bins: Vec<OSMFWeightBin>,
+    fire_spread_m_per_min: f64,
+    /// Estimated minutes the fire needs to advance along an edge of median weight
+    minutes_per_median_edge: f64,
+    suggestions: Vec<OSMFCadenceSuggestion>,
+}
+
+/// Compute the edge weight histogram of `graph` with `num_bins` bins of equal width and suggest
+/// `strategy_every` values for firefighters that respond every `response_minutes` minutes to a
+/// fire spreading at `fire_spread_m_per_min`.
+/// Edge weights are interpreted as meters and the fire advances by one meter per round.
+pub fn calibrate_spread(graph: &dyn GraphView, num_bins: usize, fire_spread_m_per_min: f64,
+                        response_minutes: &[f64]) -> OSMFSpreadCalibration {
+    let mut weights: Vec<_> = graph.iter_edges().map(|edge| edge.dist()).collect();
+    weights.sort_unstable();
+
+    let min_weight = weights.first().copied().unwrap_or(0);
+    let max_weight = weights.last().copied().unwrap_or(0);
+    let median_weight = weights.get(weights.len() / 2).copied().unwrap_or(0);
+    let mean_weight = weights.iter().map(|&weight| weight as f64).sum::<f64>() / weights.len().max(1) as f64;
+
+    // Bins cover all weights from the minimum up to and including the maximum weight
+    let num_bins = num_bins.max(1);
+    let bin_width = ((max_weight - min_weight) / num_bins + 1).max(1);
+    let mut bins: Vec<_> = (0..num_bins)
+        .map(|i| OSMFWeightBin {
+            min: min_weight + i * bin_width,
+            max: min_weight + (i + 1) * bin_width,
+            count: 0,
+        })
+        .collect();
+    for &weight in &weights {
+        let bin = ((weight - min_weight) / bin_width).min(num_bins - 1);
+        bins[bin].count += 1;
+    }
+
+    let minutes_per_median_edge = median_weight as f64 / fire_spread_m_per_min;
+    let suggestions = response_minutes.iter()
+        .map(|&minutes| OSMFCadenceSuggestion {
+            response_minutes: minutes,
+            strategy_every: (minutes * fire_spread_m_per_min).round().max(1.0) as TimeUnit,
+            edges_per_response: if median_weight > 0 { minutes / minutes_per_median_edge } else { 0.0 },
+        })
+        .collect();
+
+    OSMFSpreadCalibration {
+        num_edges: weights.len(),
+        min_weight,
+        max_weight,
+        mean_weight,
+        median_weight,
+        bins,
+        fire_spread_m_per_min,
+        minutes_per_median_edge,
+        suggestions,
+    }
+}
+
+/// A defended node together with whether its defense contributed to containing the fire
+#[derive(Serialize)]
+pub struct OSMFDefendedNode {
+    node_id: usize,
+    defended_at: TimeUnit,
+    effective: bool,
+}
+
+/// Container for the classification of all defended nodes of a simulation
+#[derive(Serialize)]
+pub struct OSMFDefenseEfficiency {
+    num_effective: usize,
+    num_wasted: usize,
+    nodes: Vec<OSMFDefendedNode>,
+}
+
+impl OSMFDefenseEfficiency {
+    /// Returns the number of defended nodes that contributed to containing the fire
+    pub fn num_effective(&self) -> usize {
+        self.num_effective
+    }
+
+    /// Returns the number of defended nodes that did not contribute to containing the fire
+    pub fn num_wasted(&self) -> usize {
+        self.num_wasted
+    }
+}
+
+/// The counterfactual importance of a single node, i.e. how the outcome of a simulation would have
+/// changed if the node had been defended from the start
+#[derive(Serialize)]
+pub struct OSMFNodeImportance {
+    node_id: usize,
+    burned_at: TimeUnit,
+    subtree_size: usize,
+    nodes_burned: usize,
+    burned_reduction: i64,
+}
+
+/// Container for the results of a counterfactual node importance analysis
+#[derive(Serialize)]
+pub struct OSMFNodeImportanceResponse {
+    pub(super) baseline_burned: usize,
+    pub(super) num_simulations: usize,
+    nodes: Vec<OSMFNodeImportance>,
+}
+
+/// A prepared counterfactual node importance analysis of a finished simulation that can be run
+/// independently of the simulated problem instance
+pub struct NodeImportanceAnalysis {
+    graph: Arc<dyn GraphView>,
+    settings: OSMFSettings,
+    roots: Vec<usize>,
+    baseline_burned: usize,
+    candidates: Vec<(usize, TimeUnit, usize)>,
+}
+
+impl OSMFNodeImportance {
+    /// Returns the id of the node that was pre-defended
+    pub fn node_id(&self) -> usize {
+        self.node_id
+    }
+
+    /// Returns by how many nodes the number of burned nodes decreased
+    pub fn burned_reduction(&self) -> i64 {
+        self.burned_reduction
+    }
+}
+
+impl OSMFNodeImportanceResponse {
+    /// Returns the analysed nodes, ranked by decreasing burned count reduction
+    pub fn nodes(&self) -> &[OSMFNodeImportance] {
+        &self.nodes
+    }
+}
+
+impl NodeImportanceAnalysis {
+    /// Prepare the counterfactual node importance analysis of the simulation on `graph` with
+    /// settings `settings` that resulted in `node_data`
+    pub(super) fn new(graph: Arc<dyn GraphView>, settings: OSMFSettings, node_data: &NodeDataStorage,
+                      k: usize, num_rounds: usize, budget: usize) -> Self {
+        Self {
+            graph,
+            settings,
+            roots: node_data.get_roots(),
+            baseline_burned: node_data.get_burning().len(),
+            candidates: select_importance_candidates(node_data, k, num_rounds, budget),
+        }
+    }
+
+    /// Returns the number of re-simulations this analysis consists of
+    pub fn num_simulations(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Run this analysis and report the progress of the re-simulations to `progress`.
+    /// The re-simulations run in parallel on the current rayon thread pool, or on the pool of the
+    /// thread limit of the settings, if set.
+    pub fn run(&self, progress: &JobProgress) -> OSMFNodeImportanceResponse {
+        progress.set_total(self.candidates.len());
+
+        let simulate = || self.candidates.par_iter()
+            .map(|&(node_id, burned_at, subtree_size)| {
+                let nodes_burned = OSMFProblem::simulate_counterfactual(&self.graph,
+                                                                         &self.settings,
+                                                                         &self.roots, node_id);
+                progress.inc();
+                OSMFNodeImportance {
+                    node_id,
+                    burned_at,
+                    subtree_size,
+                    nodes_burned,
+                    burned_reduction: self.baseline_burned as i64 - nodes_burned as i64,
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut nodes = compute::with_thread_limit(self.settings.threads, || compute::install_limited(simulate));
+        nodes.sort_by_key(|node| (std::cmp::Reverse(node.burned_reduction), node.node_id));
+
+        OSMFNodeImportanceResponse {
+            baseline_burned: self.baseline_burned,
+            num_simulations: self.candidates.len(),
+            nodes,
+        }
+    }
+}
+
+/// Compute for every burning node the number of burning nodes in its burn subtree, i.e. the
+/// number of nodes the fire reached through this node (including the node itself).
+/// Also returns for every burning node its children in the burn forest.
+pub(super) fn compute_burn_subtrees(node_data: &NodeDataStorage)
+    -> (HashMap<usize, usize>, HashMap<usize, Vec<usize>>) {
+    let mut burning = node_data.get_burning();
+    // Children always start burning after their parents, so processing the nodes in descending
+    // order of their burn times handles all children before their parents
+    burning.sort_unstable_by_key(|node_id| std::cmp::Reverse(node_data.get_burning_time(node_id)));
+
+    let mut subtree_sizes: HashMap<usize, usize> = HashMap::with_capacity(burning.len());
+    let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+    for node_id in burning {
+        let size = *subtree_sizes.entry(node_id).or_insert(0) + 1;
+        subtree_sizes.insert(node_id, size);
+        if let Some(parent) = node_data.get_burn_parent(&node_id) {
+            *subtree_sizes.entry(parent).or_insert(0) += size;
+            children.entry(parent).or_default().push(node_id);
+        }
+    }
+
+    (subtree_sizes, children)
+}
+
+/// Compute the set of effective defended nodes of a simulation, i.e. of the defended nodes that
+/// the fire reached, either directly from a burning neighbour or through a chain of other
+/// defended nodes
+fn compute_effective_defended(graph: &dyn GraphView, node_data: &NodeDataStorage) -> HashSet<usize> {
+    // Search from all burning nodes through defended nodes only
+    let mut effective = HashSet::new();
+    let mut queue: VecDeque<_> = node_data.get_burning().into_iter().collect();
+    while let Some(node_id) = queue.pop_front() {
+        for edge in graph.get_outgoing_edges(node_id) {
+            if node_data.is_defended(&edge.tgt()) && effective.insert(edge.tgt()) {
+                queue.push_back(edge.tgt());
+            }
+        }
+    }
+
+    effective
+}
+
+/// Classify each defended node of a simulation as effective or wasted.
+/// A defended node is effective if the fire reached it, either directly from a burning neighbour
+/// or through a chain of other defended nodes. Such nodes blocked the fire or lie on a path from
+/// a fire root to a safe node. All other defended nodes were never threatened by the fire.
+pub(super) fn classify_defended(graph: &dyn GraphView, node_data: &NodeDataStorage) -> OSMFDefenseEfficiency {
+    let effective = compute_effective_defended(graph, node_data);
+
+    let nodes: Vec<_> = node_data.get_defended().into_iter()
+        .map(|node_id| OSMFDefendedNode {
+            node_id,
+            defended_at: node_data.get_defended_time(&node_id).unwrap(),
+            effective: effective.contains(&node_id),
+        })
+        .collect();
+
+    OSMFDefenseEfficiency {
+        num_effective: effective.len(),
+        num_wasted: nodes.len() - effective.len(),
+        nodes,
+    }
+}
+
+/// Compute for every effective defended node of a simulation the number of safe nodes it shields.
+/// The effective defended nodes form a cut between the burned area and the regions of safe nodes
+/// that border them. Each such region is attributed to the defended nodes bordering it in equal
+/// shares, so that the savings of all defended nodes add up to the number of shielded nodes.
+pub(super) fn compute_defense_savings(graph: &dyn GraphView, node_data: &NodeDataStorage) -> HashMap<usize, f64> {
+    // Label the regions of safe nodes, i.e. of nodes that neither burned nor were defended
+    let mut regions: Vec<Option<usize>> = vec![None; graph.num_nodes()];
+    let mut region_sizes = Vec::new();
+    for start in 0..graph.num_nodes() {
+        if regions[start].is_some() || !node_data.is_undefended(&start) {
+            continue;
+        }
+        let region = region_sizes.len();
+        regions[start] = Some(region);
+        let mut size = 0;
+        let mut queue = VecDeque::from([start]);
+        while let Some(node_id) = queue.pop_front() {
+            size += 1;
+            for edge in graph.get_outgoing_edges(node_id) {
+                if regions[edge.tgt()].is_none() && node_data.is_undefended(&edge.tgt()) {
+                    regions[edge.tgt()] = Some(region);
+                    queue.push_back(edge.tgt());
+                }
+            }
+        }
+        region_sizes.push(size);
+    }
+
+    // Find the regions that border each effective defended node
+    let mut bordered_regions: HashMap<usize, HashSet<usize>> = HashMap::new();
+    let mut num_borders = vec![0; region_sizes.len()];
+    for node_id in compute_effective_defended(graph, node_data) {
+        let bordered: HashSet<_> = graph.get_outgoing_edges(node_id).iter()
+            .filter_map(|edge| regions[edge.tgt()])
+            .collect();
+        for &region in &bordered {
+            num_borders[region] += 1;
+        }
+        bordered_regions.insert(node_id, bordered);
+    }
+
+    bordered_regions.into_iter()
+        .map(|(node_id, bordered)| {
+            let savings = bordered.into_iter()
+                .map(|region| region_sizes[region] as f64 / num_borders[region] as f64)
+                .sum();
+            (node_id, savings)
+        })
+        .collect()
+}
+
+/// Compute for every burning node of a simulation the fire root whose burn tree it belongs to
+pub(super) fn compute_burn_roots(node_data: &NodeDataStorage) -> HashMap<usize, usize> {
+    // Processing the nodes in ascending order of their burn times handles all parents before
+    // their children
+    let mut burning = node_data.get_burning();
+    burning.sort_unstable_by_key(|node_id| node_data.get_burning_time(node_id));
+
+    let mut burn_roots: HashMap<usize, usize> = HashMap::with_capacity(burning.len());
+    for node_id in burning {
+        let root = match node_data.get_burn_parent(&node_id) {
+            Some(parent) => burn_roots[&parent],
+            None => node_id
+        };
+        burn_roots.insert(node_id, root);
+    }
+
+    burn_roots
+}
+
+/// Compute the nearest-root partition of a simulation by time `time`, i.e. assign every node to
+/// the fire root ignited by then that would reach it first if the fire spread without any
+/// firefighters. The fire crosses edges in the rounds given by the spread factors
+/// `spread_factors`, and roots of ignition waves start spreading in the round they ignite. Ties
+/// go to the root with the lower id.
+/// Returns the root of every node by node id, or `None` for nodes that no root reaches.
+pub(super) fn compute_nearest_roots(graph: &dyn GraphView, node_data: &NodeDataStorage, time: TimeUnit,
+                                    spread_factors: &BTreeMap<u32, f64>) -> Vec<Option<usize>> {
+    let mut nearest_roots = vec![None; graph.num_nodes()];
+    let mut arrival_times = vec![TimeUnit::MAX; graph.num_nodes()];
+    let mut pq = BinaryHeap::new();
+    for root in node_data.get_burning() {
+        if node_data.is_root(&root) && node_data.is_burning_by(&root, &time) {
+            let ignition_time = node_data.get_burning_time(&root).unwrap_or(0);
+            pq.push(Reverse((ignition_time, root, root)));
+        }
+    }
+
+    // Settling the nodes in the order of their arrival times and then root ids assigns every
+    // node to the first root that reaches it
+    while let Some(Reverse((arrival_time, root, node_id))) = pq.pop() {
+        if nearest_roots[node_id].is_some() {
+            continue;
+        }
+        nearest_roots[node_id] = Some(root);
+        for edge in graph.get_outgoing_edges(node_id) {
+            let tgt_time = arrival_time + problem::spread_rounds(spread_factors, edge);
+            if nearest_roots[edge.tgt()].is_none() && tgt_time <= arrival_times[edge.tgt()] {
+                arrival_times[edge.tgt()] = tgt_time;
+                pq.push(Reverse((tgt_time, root, edge.tgt())));
+            }
+        }
+    }
+
+    nearest_roots
+}
+
+/// Forecast the danger zone of a simulation at time `time`, i.e. the nodes that would burn within
+/// the next `rounds` rounds if no further nodes were defended. The fire spreads from the nodes
+/// burning by time `time` across edges in the rounds given by the spread factors
+/// `spread_factors`, but never through nodes defended by then. The search is bounded by the
+/// forecast horizon, so only the surroundings of the fire front are explored.
+/// Returns the number of rounds after time `time` in which every node of the danger zone would
+/// burn by node id, or `None` for all other nodes.
+pub(super) fn compute_danger_zone(graph: &dyn GraphView, node_data: &NodeDataStorage, time: TimeUnit,
+                                  rounds: TimeUnit, spread_factors: &BTreeMap<u32, f64>) -> Vec<Option<TimeUnit>> {
+    let mut danger = vec![None; graph.num_nodes()];
+    let mut arrival_times = vec![TimeUnit::MAX; graph.num_nodes()];
+    let mut pq = BinaryHeap::new();
+    for node_id in node_data.get_burning() {
+        if let Some(burn_time) = node_data.get_burning_time(&node_id).filter(|&t| t <= time) {
+            arrival_times[node_id] = burn_time;
+            pq.push(Reverse((burn_time, node_id)));
+        }
+    }
+
+    // Nodes that are not burning yet ignite in the next round at the earliest
+    let horizon = time.saturating_add(rounds);
+    let next_round = time.saturating_add(1);
+    while let Some(Reverse((arrival_time, node_id))) = pq.pop() {
+        if arrival_time > arrival_times[node_id] {
+            continue;
+        }
+        if arrival_time > time {
+            danger[node_id] = Some(arrival_time - time);
+        }
+        for edge in graph.get_outgoing_edges(node_id) {
+            let tgt = edge.tgt();
+            if node_data.is_burning_by(&tgt, &time) || node_data.is_defended_by(&tgt, &time) {
+                continue;
+            }
+            let tgt_time = arrival_time.saturating_add(problem::spread_rounds(spread_factors, edge)).max(next_round);
+            if tgt_time <= horizon && tgt_time < arrival_times[tgt] {
+                arrival_times[tgt] = tgt_time;
+                pq.push(Reverse((tgt_time, tgt)));
+            }
+        }
+    }
+
+    danger
+}
+
+/// Estimate the geographic area in square kilometers that was affected by the fire of a
+/// simulation. The area is estimated as the sum of the convex hull areas of the burn trees of all
+/// fire roots, so areas where burn trees overlap are counted multiple times.
+pub(super) fn estimate_burned_area_km2(graph: &dyn GraphView, node_data: &NodeDataStorage) -> f64 {
+    // Nodes and roots are processed in a fixed order, such that the areas are always summed up in
+    // the same order
+    let mut burn_roots: Vec<_> = compute_burn_roots(node_data).into_iter().collect();
+    burn_roots.sort_unstable();
+    let mut points_by_root: BTreeMap<usize, Vec<Point<f64>>> = BTreeMap::new();
+    for (node_id, root) in burn_roots {
+        let node = graph.get_node(node_id);
+        points_by_root.entry(root).or_default().push(Point::new(node.lon, node.lat));
+    }
+
+    let area_m2: f64 = points_by_root.into_values()
+        .filter(|points| points.len() >= 3)
+        .map(|points| MultiPoint::from(points).convex_hull().chamberlain_duquette_unsigned_area())
+        .sum();
+    area_m2 / 1_000_000.0
+}
+
+/// Estimate the distance in meters that the firefighters travelled in a simulation. Every defense
+/// is reached from the nearest node defended in an earlier round, while the defenses of the first
+/// round are the initial deployment and do not count. Distances are great-circle distances, since
+/// firefighters are not bound to the roads along which the fire spreads.
+pub(super) fn estimate_travel_distance_m(graph: &dyn GraphView, node_data: &NodeDataStorage) -> f64 {
+    let mut defenses: Vec<_> = node_data.get_defended().into_iter()
+        .filter_map(|node_id| node_data.get_defended_time(&node_id).map(|time| (time, node_id)))
+        .collect();
+    defenses.sort_unstable();
+    let point = |node_id: usize| {
+        let node = graph.get_node(node_id);
+        Point::new(node.lon, node.lat)
+    };
+
+    let mut reached: Vec<Point<f64>> = Vec::new();
+    let mut distance = 0.0;
+    let mut start = 0;
+    while start < defenses.len() {
+        let time = defenses[start].0;
+        let end = defenses[start..].iter()
+            .position(|&(other, _)| other != time)
+            .map_or(defenses.len(), |len| start + len);
+        let round: Vec<_> = defenses[start..end].iter().map(|&(_, node_id)| point(node_id)).collect();
+        if !reached.is_empty() {
+            distance += round.iter()
+                .map(|p| reached.iter().map(|r| p.haversine_distance(r)).fold(f64::INFINITY, f64::min))
+                .sum::<f64>();
+        }
+        reached.extend(round);
+        start = end;
+    }
+    distance
+}
+
+/// Lower bound of the length of a degree of latitude in meters, such that grid cells of this
+/// length are at least as long as intended
+const MIN_METERS_PER_DEG_LAT: f64 = 111_000.0;
+
+/// Compute the smoke exposure of every node of a simulation by time `time` as proxy for health
+/// impacts. The exposure of a node is the number of rounds it spent within the distance
+/// `radius_m` of each burning node, weighted by `1 - distance / radius_m`, summed over all burning
+/// nodes. Distances are great-circle distances, since smoke is not bound to the roads along which
+/// the fire spreads. Returns the exposure by node id.
+pub(super) fn compute_exposure(graph: &dyn GraphView, node_data: &NodeDataStorage, radius_m: f64,
+                               time: TimeUnit) -> Vec<f64> {
+    let mut exposure = vec![0.0; graph.num_nodes()];
+    if radius_m <= 0.0 {
+        return exposure;
+    }
+
+    // Bucket the burning nodes into grid cells that are at least `radius_m` long in both
+    // directions, such that all burning nodes near a node are in its cell or in adjacent cells
+    let gb = graph.get_grid_bounds();
+    let max_abs_lat = gb.min_lat.abs().max(gb.max_lat.abs()).min(89.0);
+    let cell_lat = radius_m / MIN_METERS_PER_DEG_LAT;
+    let cell_lon = cell_lat / max_abs_lat.to_radians().cos();
+    let cell = |node_id: usize| {
+        let node = graph.get_node(node_id);
+        (((node.lat - gb.min_lat) / cell_lat) as i64, ((node.lon - gb.min_lon) / cell_lon) as i64)
+    };
+    let point = |node_id: usize| {
+        let node = graph.get_node(node_id);
+        Point::new(node.lon, node.lat)
+    };
+
+    // Burning nodes are ordered, such that exposures are always summed up in the same order
+    let mut burning = node_data.get_burning();
+    burning.sort_unstable();
+    let mut cells = HashMap::new();
+    for node_id in burning {
+        if let Some(burn_time) = node_data.get_burning_time(&node_id).filter(|&t| t <= time) {
+            cells.entry(cell(node_id)).or_insert_with(Vec::new).push((point(node_id), time - burn_time + 1));
+        }
+    }
+
+    compute::install_limited(|| exposure.par_iter_mut().enumerate().for_each(|(node_id, exposure)| {
+        let (lat_cell, lon_cell) = cell(node_id);
+        let p = point(node_id);
+        for lat_cell in lat_cell - 1..=lat_cell + 1 {
+            for lon_cell in lon_cell - 1..=lon_cell + 1 {
+                for (q, rounds) in cells.get(&(lat_cell, lon_cell)).into_iter().flatten() {
+                    let dist = p.haversine_distance(q);
+                    if dist < radius_m {
+                        *exposure += *rounds as f64 * (1.0 - dist / radius_m);
+                    }
+                }
+            }
+        }
+    }));
+    exposure
+}
+
+/// Compute the `k` dominant spread corridors of a simulation.
+/// The burn forest is decomposed into heavy paths, i.e. paths that always continue with the
+/// child with the largest burn subtree. Heavy paths are headed by fire root children or light
+/// children and are ranked by the subtree size of their heads, which is the number of nodes
+/// that would have been saved if the head had been defended early.
+pub(super) fn compute_corridors(node_data: &NodeDataStorage, k: usize) -> Vec<OSMFCorridor> {
+    let (subtree_sizes, children) = compute_burn_subtrees(node_data);
+    let heavy_child = |node_id: &usize| children.get(node_id)
+        .and_then(|children| children.iter().max_by_key(|&child| (subtree_sizes[child], *child)))
+        .copied();
+
+    // Heads of heavy paths are all burning non-root nodes that are not the heavy child of
+    // their parent, or whose parent is a fire root
+    let mut heads: Vec<_> = subtree_sizes.keys()
+        .filter(|&node_id| match node_data.get_burn_parent(node_id) {
+            Some(parent) => node_data.is_root(&parent) || heavy_child(&parent) != Some(*node_id),
+            None => false
+        })
+        .copied()
+        .collect();
+    heads.sort_unstable_by_key(|head| (std::cmp::Reverse(subtree_sizes[head]), *head));
+    heads.truncate(k);
+
+    heads.into_iter()
+        .map(|head| {
+            let mut nodes = vec![head];
+            while let Some(child) = heavy_child(nodes.last().unwrap()) {
+                nodes.push(child);
+            }
+            let subtree_sizes = nodes.iter()
+                .map(|node_id| subtree_sizes[node_id])
+                .collect();
+            OSMFCorridor {
+                nodes,
+                subtree_sizes,
+            }
+        })
+        .collect()
+}
+
+/// Select the candidates for a counterfactual node importance analysis.
+/// For each of the first `num_rounds` rounds after the fire started, the `k` nodes that caught
+/// fire in that round with the largest burn subtrees are selected, until `budget` candidates
+/// have been selected. Returns the candidates with their burn times and burn subtree sizes.
+fn select_importance_candidates(node_data: &NodeDataStorage, k: usize,
+                                num_rounds: usize, budget: usize)
+    -> Vec<(usize, TimeUnit, usize)> {
+    let (subtree_sizes, _) = compute_burn_subtrees(node_data);
+
+    // Group the nodes that caught fire after the fire started by their burn times
+    let mut rounds: BTreeMap<TimeUnit, Vec<usize>> = BTreeMap::new();
+    for (&node_id, _) in subtree_sizes.iter().filter(|(node_id, _)| !node_data.is_root(node_id)) {
+        if let Some(time) = node_data.get_burning_time(&node_id) {
+            rounds.entry(time).or_default().push(node_id);
+        }
+    }
+
+    rounds.into_iter()
+        .take(num_rounds)
+        .flat_map(|(time, mut nodes)| {
+            nodes.sort_unstable_by_key(|node_id| (std::cmp::Reverse(subtree_sizes[node_id]), *node_id));
+            nodes.into_iter()
+                .take(k)
+                .map(|node_id| (node_id, time, subtree_sizes[&node_id]))
+                .collect::<Vec<_>>()
+        })
+        .take(budget)
+        .collect()
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::firefighter::problem::{OSMFProblem, OSMFSettings, OSMFSettingsError};
+use crate::firefighter::strategy::OSMFStrategy;
+use crate::firefighter::TimeUnit;
+use crate::graph::GraphView;
+
+/// Version of the simulation engine that audit logs are recorded with
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Maximum number of divergences that are listed in an audit report
+const MAX_REPORTED_DIVERGENCES: usize = 100;
+
+/// Rule by which a node was ignited
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cause", rename_all = "snake_case")]
+pub enum OSMFIgnitionCause {
+    /// The node is one of the initial fire roots
+    Root,
+    /// The node is a fire root of an ignition wave
+    Wave,
+    /// The fire spread to the node along the edge from its burning neighbour `source`, which
+    /// started burning at `source_burn_time`. The edge with weight `edge_weight` takes
+    /// `spread_rounds` rounds to burn.
+    Spread { source: usize, source_burn_time: TimeUnit, edge_weight: usize, spread_rounds: TimeUnit },
+}
+
+/// Ignition of a node in the audit log of a simulation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OSMFIgnitionRecord {
+    pub node: usize,
+    pub round: TimeUnit,
+    #[serde(flatten)]
+    pub cause: OSMFIgnitionCause,
+}
+
+/// Audit log of a simulation, i.e. the rule evaluation behind every ignition together with
+/// everything needed to replay the simulation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OSMFAuditLog {
+    pub engine_version: String,
+    /// Fully resolved settings the simulation ran with
+    pub settings: OSMFSettings,
+    pub ignitions: Vec<OSMFIgnitionRecord>,
+}
+
+/// Node whose ignition differs between an audit log and its replay. Either record is missing if
+/// the node did not burn in the respective simulation.
+#[derive(Debug, Serialize)]
+pub struct OSMFAuditDivergence {
+    pub node: usize,
+    pub recorded: Option<OSMFIgnitionRecord>,
+    pub replayed: Option<OSMFIgnitionRecord>,
+}
+
+impl OSMFAuditDivergence {
+    /// Get the earliest round in which the ignition of the node differs
+    fn round(&self) -> TimeUnit {
+        let rounds = self.recorded.iter().chain(&self.replayed).map(|record| record.round);
+        rounds.min().unwrap_or(0)
+    }
+}
+
+/// Result of the verification of an audit log against the current engine
+#[derive(Debug, Serialize)]
+pub struct OSMFAuditReport {
+    pub recorded_engine_version: String,
+    pub engine_version: &'static str,
+    pub is_consistent: bool,
+    pub num_ignitions: usize,
+    pub num_divergences: usize,
+    /// Earliest round in which the replay diverges, if it diverges at all
+    pub first_divergent_round: Option<TimeUnit>,
+    /// The earliest divergences ordered by round and node
+    pub divergences: Vec<OSMFAuditDivergence>,
+}
+
+impl OSMFAuditLog {
+    /// Replay the simulation of this audit log on `graph` with the containment strategy
+    /// `strategy` and compare every ignition with the recorded one
+    pub fn verify(&self, graph: Arc<dyn GraphView>, strategy: OSMFStrategy)
+        -> Result<OSMFAuditReport, OSMFSettingsError> {
+        let mut problem = OSMFProblem::new(graph, self.settings.clone(), strategy)?;
+        problem.enable_audit();
+        problem.simulate();
+        let replayed = problem.audit_log()
+            .expect("Audit log should be recorded after enabling the audit mode");
+        Ok(self.compare(&replayed))
+    }
+
+    /// Get the ignitions of this audit log by node
+    fn ignitions_by_node(&self) -> BTreeMap<usize, &OSMFIgnitionRecord> {
+        self.ignitions.iter().map(|record| (record.node, record)).collect()
+    }
+
+    /// Compare the ignitions of this audit log with those of the audit log `replayed`
+    fn compare(&self, replayed: &OSMFAuditLog) -> OSMFAuditReport {
+        let (recorded_by_node, mut replayed_by_node) = (self.ignitions_by_node(), replayed.ignitions_by_node());
+
+        let mut divergences = Vec::new();
+        for (node, recorded) in recorded_by_node {
+            let replayed = replayed_by_node.remove(&node);
+            if replayed != Some(recorded) {
+                divergences.push(OSMFAuditDivergence { node, recorded: Some(recorded.clone()), replayed: replayed.cloned() });
+            }
+        }
+        divergences.extend(replayed_by_node.into_iter()
+            .map(|(node, replayed)| OSMFAuditDivergence { node, recorded: None, replayed: Some(replayed.clone()) }));
+        divergences.sort_by_key(|divergence| (divergence.round(), divergence.node));
+
+        let num_divergences = divergences.len();
+        let first_divergent_round = divergences.first().map(OSMFAuditDivergence::round);
+        divergences.truncate(MAX_REPORTED_DIVERGENCES);
+        OSMFAuditReport {
+            recorded_engine_version: self.engine_version.clone(),
+            engine_version: ENGINE_VERSION,
+            is_consistent: num_divergences == 0,
+            num_ignitions: self.ignitions.len(),
+            num_divergences,
+            first_divergent_round,
+            divergences,
+        }
+    }
+}
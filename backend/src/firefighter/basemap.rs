@@ -0,0 +1,300 @@
+extern crate image;
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use self::image::{Rgb, RgbImage};
+
+use crate::graph::GridBounds;
+
+/// Width and height of raster tiles in pixels
+const TILE_SIZE: u32 = 256;
+
+/// Maximum zoom level of raster tiles
+const MAX_TILE_ZOOM: u8 = 19;
+
+/// Maximum latitude that can be represented in the Web Mercator projection
+const MAX_MERCATOR_LAT: f64 = 85.051_128_78;
+
+/// Timeout for connecting to and reading from the tile server
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Weight of the tile colors when blending them with the dark grey background, such that the
+/// simulation layer remains clearly visible on top of the basemap
+const TILE_OPACITY: f64 = 0.6;
+
+/// Background color that is blended with the tile colors and shown where tiles are missing
+const BACKGROUND_RGB: Rgb<u8> = Rgb([64, 64, 64]);
+
+/// Maximum number of decoded tiles that are held in memory, i.e. about 50 MB. Tiles that have
+/// not been used for the longest time are evicted first, and are loaded from disk again.
+const MAX_MEMORY_TILES: usize = 256;
+
+/// Key of a raster tile, i.e. its zoom level and its x and y index
+type TileKey = (u8, u32, u32);
+
+/// Decoded tiles that are held in memory, or `None` for tiles that could not be loaded, together
+/// with the number of the lookup that used them last
+#[derive(Debug)]
+struct TileMemory {
+    max_tiles: usize,
+    tiles: HashMap<TileKey, (Option<Arc<RgbImage>>, u64)>,
+    num_lookups: u64,
+}
+
+impl TileMemory {
+    /// Create a new tile memory that holds at most `max_tiles` tiles
+    fn new(max_tiles: usize) -> Self {
+        Self {
+            max_tiles,
+            tiles: HashMap::new(),
+            num_lookups: 0,
+        }
+    }
+
+    /// Get the tile with key `key`, if it is held in memory
+    fn get(&mut self, key: &TileKey) -> Option<Option<Arc<RgbImage>>> {
+        self.num_lookups += 1;
+        let num_lookups = self.num_lookups;
+        self.tiles.get_mut(key).map(|(tile, last_used)| {
+            *last_used = num_lookups;
+            tile.clone()
+        })
+    }
+
+    /// Keep the tile `tile` with key `key`, evicting the least recently used tile if the memory
+    /// is full
+    fn insert(&mut self, key: TileKey, tile: Option<Arc<RgbImage>>) {
+        if self.tiles.len() >= self.max_tiles && !self.tiles.contains_key(&key) {
+            let lru_key = self.tiles.iter()
+                .min_by_key(|(_, &(_, last_used))| last_used)
+                .map(|(&lru_key, _)| lru_key);
+            if let Some(lru_key) = lru_key {
+                self.tiles.remove(&lru_key);
+            }
+        }
+        self.tiles.insert(key, (tile, self.num_lookups));
+    }
+}
+
+/// Project `lat` and `lon` to global pixel coordinates in the Web Mercator projection at tile
+/// zoom level `zoom`
+fn project(lat: f64, lon: f64, zoom: u8) -> (f64, f64) {
+    let lat = lat.clamp(-MAX_MERCATOR_LAT, MAX_MERCATOR_LAT).to_radians();
+    let world_px = (TILE_SIZE as f64) * 2f64.powi(zoom as i32);
+    let x = (lon + 180.0) / 360.0 * world_px;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * world_px;
+    (x, y)
+}
+
+/// Choose the tile zoom level whose pixels cover about `deg_per_px` degrees of longitude
+fn choose_zoom(deg_per_px: f64) -> u8 {
+    let zoom = (360.0 / (TILE_SIZE as f64 * deg_per_px)).log2().round();
+    zoom.clamp(0.0, MAX_TILE_ZOOM as f64) as u8
+}
+
+/// Fetch the body of the resource at the plain HTTP URL `url`
+fn fetch_http(url: &str) -> io::Result<Vec<u8>> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", msg, url));
+
+    let rest = url.strip_prefix("http://").ok_or_else(|| invalid("Not a plain HTTP URL"))?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/")
+    };
+    let addr = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+    let addr = addr.to_socket_addrs()?.next().ok_or_else(|| invalid("Unknown host"))?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, FETCH_TIMEOUT)?;
+    stream.set_read_timeout(Some(FETCH_TIMEOUT))?;
+    // HTTP/1.0 responses are neither chunked nor kept alive, so the body is the rest of the stream
+    write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: OSM-Firefighter\r\n\r\n", path, host)?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+
+    let header_end = response.windows(4).position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| invalid("Malformed response from"))?;
+    let status_line = String::from_utf8_lossy(&response[..header_end]);
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Failed to fetch {}: {}",
+                                   url, status_line.lines().next().unwrap_or(""))));
+    }
+
+    Ok(response.split_off(header_end + 4))
+}
+
+/// Source of raster basemap tiles in the Web Mercator projection that are fetched from a tile
+/// server and cached in memory and on disk
+#[derive(Debug)]
+pub struct TileCache {
+    url_template: String,
+    cache_dir: PathBuf,
+    tiles: Mutex<TileMemory>,
+}
+
+impl TileCache {
+    /// Create a new tile cache for the tile server at `url_template`, which must be a plain HTTP
+    /// URL containing the placeholders `{z}`, `{x}` and `{y}`. Fetched tiles are stored in
+    /// `cache_dir`.
+    pub fn new(url_template: &str, cache_dir: &str) -> io::Result<Self> {
+        if !url_template.starts_with("http://")
+            || ["{z}", "{x}", "{y}"].iter().any(|p| !url_template.contains(p)) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!(
+                "Tile URL must be a plain HTTP URL with placeholders {{z}}, {{x}} and {{y}}: {}",
+                url_template)));
+        }
+        fs::create_dir_all(cache_dir)?;
+
+        Ok(Self {
+            url_template: url_template.to_string(),
+            cache_dir: PathBuf::from(cache_dir),
+            tiles: Mutex::new(TileMemory::new(MAX_MEMORY_TILES)),
+        })
+    }
+
+    /// Get the tile with key `key` from memory, from disk or from the tile server, in that order.
+    /// Returns `None` if the tile could not be loaded. Failed tiles are not requested again while
+    /// they are held in memory.
+    fn get_tile(&self, key: TileKey) -> Option<Arc<RgbImage>> {
+        if let Some(tile) = self.tiles.lock().unwrap().get(&key) {
+            return tile;
+        }
+
+        let (z, x, y) = key;
+        let path = self.cache_dir.join(z.to_string()).join(x.to_string()).join(format!("{}.png", y));
+        let bytes = fs::read(&path).or_else(|_| {
+            let url = self.url_template
+                .replace("{z}", &z.to_string())
+                .replace("{x}", &x.to_string())
+                .replace("{y}", &y.to_string());
+            log::debug!("Fetching basemap tile {}", url);
+            let bytes = fetch_http(&url)?;
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, &bytes)?;
+            Ok::<_, io::Error>(bytes)
+        });
+
+        let tile = match bytes.map(|bytes| image::load_from_memory(&bytes)) {
+            Ok(Ok(img)) => Some(Arc::new(img.to_rgb8())),
+            Ok(Err(err)) => {
+                log::warn!("Failed to decode basemap tile {:?}: {}", key, err);
+                None
+            }
+            Err(err) => {
+                log::warn!("Failed to load basemap tile {:?}: {}", key, err);
+                None
+            }
+        };
+        self.tiles.lock().unwrap().insert(key, tile.clone());
+        tile
+    }
+
+    /// Render the basemap for the area `gb` into `img_buf`, whose pixels cover `deg_per_px_hz`
+    /// and `deg_per_px_vert` degrees. Every pixel of the linearly projected view is sampled from
+    /// the Web Mercator tile it falls into. Pixels without a tile are filled with the background.
+    pub(super) fn render(&self, img_buf: &mut RgbImage, gb: &GridBounds, deg_per_px_hz: f64,
+                         deg_per_px_vert: f64) {
+        let zoom = choose_zoom(deg_per_px_hz);
+        let num_tiles = 1u32 << zoom;
+
+        // Neighbouring pixels mostly share their tile, so the last tile is kept at hand
+        let mut last_tile: Option<(TileKey, Option<Arc<RgbImage>>)> = None;
+        for (w, h, px) in img_buf.enumerate_pixels_mut() {
+            let lat = gb.max_lat - (h as f64 + 0.5) * deg_per_px_vert;
+            let lon = gb.min_lon + (w as f64 + 0.5) * deg_per_px_hz;
+            let (x, y) = project(lat, lon, zoom);
+            let key = (zoom, (x / TILE_SIZE as f64) as u32, (y / TILE_SIZE as f64) as u32);
+            if key.1 >= num_tiles || key.2 >= num_tiles {
+                *px = BACKGROUND_RGB;
+                continue;
+            }
+            if last_tile.as_ref().map(|(last_key, _)| *last_key != key).unwrap_or(true) {
+                last_tile = Some((key, self.get_tile(key)));
+            }
+
+            *px = match last_tile.as_ref().and_then(|(_, tile)| tile.as_ref()) {
+                Some(tile) => {
+                    let tile_px = tile.get_pixel(
+                        (x as u32 % TILE_SIZE).min(tile.width() - 1),
+                        (y as u32 % TILE_SIZE).min(tile.height() - 1));
+                    let mut rgb = BACKGROUND_RGB;
+                    for (c, &t) in rgb.0.iter_mut().zip(tile_px.0.iter()) {
+                        *c = (TILE_OPACITY * t as f64 + (1.0 - TILE_OPACITY) * *c as f64) as u8;
+                    }
+                    rgb
+                }
+                None => BACKGROUND_RGB
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::sync::Arc;
+
+    use super::image::{ImageBuffer, Rgb, RgbImage};
+
+    use crate::firefighter::basemap::{choose_zoom, project, TileCache, TileMemory, BACKGROUND_RGB};
+    use crate::graph::GridBounds;
+
+    #[test]
+    fn test_projection() {
+        assert_eq!(project(0.0, 0.0, 0), (128.0, 128.0));
+        let (x, y) = project(85.051_128_78, -180.0, 1);
+        assert!(x.abs() < 1e-6 && y.abs() < 1e-6);
+        assert_eq!(choose_zoom(360.0 / 256.0), 0);
+        assert_eq!(choose_zoom(360.0 / 256.0 / 1024.0), 10);
+    }
+
+    #[test]
+    fn test_tile_memory() {
+        let tile = |value| Some(Arc::new(ImageBuffer::from_pixel(1, 1, Rgb([value, value, value]))));
+        let mut memory = TileMemory::new(2);
+        memory.insert((0, 0, 0), tile(1));
+        memory.insert((1, 0, 0), None);
+        assert!(memory.get(&(0, 0, 0)).is_some());
+
+        // The least recently used tile is evicted
+        memory.insert((1, 1, 0), tile(2));
+        assert_eq!(memory.tiles.len(), 2);
+        assert!(memory.get(&(1, 0, 0)).is_none());
+        assert_eq!(memory.get(&(0, 0, 0)), Some(tile(1)));
+        assert_eq!(memory.get(&(1, 1, 0)), Some(tile(2)));
+    }
+
+    #[test]
+    fn test_render_cached_tiles() {
+        let cache_dir = std::env::temp_dir().join(format!("osmff_tiles_{}", std::process::id()));
+        fs::create_dir_all(cache_dir.join("0/0")).unwrap();
+        let tile: RgbImage = ImageBuffer::from_pixel(256, 256, Rgb([255, 255, 255]));
+        tile.save(cache_dir.join("0/0/0.png")).unwrap();
+
+        // The tile server is unreachable, so only the cached tile can be used
+        let tiles = TileCache::new("http://127.0.0.1:1/{z}/{x}/{y}.png",
+                                   cache_dir.to_str().unwrap()).unwrap();
+        let mut img_buf: RgbImage = ImageBuffer::new(4, 4);
+        let gb = GridBounds { min_lat: -10.0, max_lat: 10.0, min_lon: -180.0, max_lon: 180.0 };
+        tiles.render(&mut img_buf, &gb, 90.0, 5.0);
+        assert!(img_buf.pixels().all(|px| px.0[0] > BACKGROUND_RGB.0[0]));
+
+        // Tiles of other zoom levels are missing and rendered as background
+        let mut img_buf: RgbImage = ImageBuffer::new(4, 4);
+        let gb = GridBounds { min_lat: 0.0, max_lat: 0.04, min_lon: 0.0, max_lon: 0.04 };
+        tiles.render(&mut img_buf, &gb, 0.01, 0.01);
+        assert!(img_buf.pixels().all(|px| *px == BACKGROUND_RGB));
+
+        assert!(TileCache::new("https://tile.openstreetmap.org/{z}/{x}/{y}.png",
+                               cache_dir.to_str().unwrap()).is_err());
+        fs::remove_dir_all(cache_dir).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+#[cfg(feature = "charts")]
+extern crate plotters;
+
+use derive_more::{Display, Error};
+
+use crate::firefighter::TimeUnit;
+
+/// Width of rendered charts in pixels
+pub const CHART_WIDTH: u32 = 800;
+/// Height of rendered charts in pixels
+pub const CHART_HEIGHT: u32 = 450;
+
+#[derive(Debug, Display, Error)]
+pub enum ChartError {
+    #[display(fmt = "Charts require the backend to be built with the 'charts' feature")]
+    Unavailable,
+    #[display(fmt = "Failed to render chart: {}", message)]
+    Render { message: String },
+}
+
+/// Labelled data series of a line chart, i.e. its values in every round
+pub type ChartSeries = (String, Vec<(TimeUnit, f64)>);
+
+/// Render a bar chart titled `caption` as SVG document with one bar of height `value` labelled
+/// `label` for every `(label, value)` pair in `bars`. The y-axis is described by `y_desc`.
+#[cfg_attr(not(feature = "charts"), allow(unused_variables))]
+pub fn bar_chart_svg(caption: &str, y_desc: &str, bars: &[(String, f64)]) -> Result<String, ChartError> {
+    #[cfg(feature = "charts")]
+    return plotting::bar_chart_svg(caption, y_desc, bars);
+    #[cfg(not(feature = "charts"))]
+    return Err(ChartError::Unavailable);
+}
+
+/// Render a bar chart as PNG image, see `bar_chart_svg`
+#[cfg_attr(not(feature = "charts"), allow(unused_variables))]
+pub fn bar_chart_png(caption: &str, y_desc: &str, bars: &[(String, f64)]) -> Result<Vec<u8>, ChartError> {
+    #[cfg(feature = "charts")]
+    return plotting::render_png(|root| plotting::draw_bar_chart(root, caption, y_desc, bars));
+    #[cfg(not(feature = "charts"))]
+    return Err(ChartError::Unavailable);
+}
+
+/// Render a line chart titled `caption` as PNG image with one line for every data series in
+/// `series`. The axes are described by `x_desc` and `y_desc`.
+#[cfg_attr(not(feature = "charts"), allow(unused_variables))]
+pub fn line_chart_png(caption: &str, x_desc: &str, y_desc: &str, series: &[ChartSeries])
+    -> Result<Vec<u8>, ChartError> {
+    #[cfg(feature = "charts")]
+    return plotting::render_png(|root| plotting::draw_line_chart(root, caption, x_desc, y_desc, series));
+    #[cfg(not(feature = "charts"))]
+    return Err(ChartError::Unavailable);
+}
+
+#[cfg(feature = "charts")]
+mod plotting {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageOutputFormat, RgbImage};
+
+    use super::plotters::coord::Shift;
+    use super::plotters::drawing::DrawingAreaErrorKind;
+    use super::plotters::prelude::*;
+
+    use crate::firefighter::charts::{CHART_HEIGHT, CHART_WIDTH, ChartError, ChartSeries};
+
+    /// Colors of the lines of line charts
+    const LINE_COLORS: [RGBColor; 4] = [RED, BLUE, GREEN, MAGENTA];
+
+    impl<E: std::error::Error + Send + Sync> From<DrawingAreaErrorKind<E>> for ChartError {
+        fn from(err: DrawingAreaErrorKind<E>) -> Self {
+            Self::Render { message: err.to_string() }
+        }
+    }
+
+    /// Draw a bar chart onto `root`, see `charts::bar_chart_svg`
+    pub(super) fn draw_bar_chart<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, caption: &str, y_desc: &str,
+                                                     bars: &[(String, f64)]) -> Result<(), ChartError> {
+        root.fill(&WHITE)?;
+        let y_max = bars.iter().map(|(_, value)| *value).fold(0.0, f64::max).max(1.0) * 1.1;
+
+        let mut chart = ChartBuilder::on(root)
+            .caption(caption, ("sans-serif", 24))
+            .margin(16)
+            .x_label_area_size(40)
+            .y_label_area_size(70)
+            .build_cartesian_2d((0..bars.len()).into_segmented(), 0.0..y_max)?;
+
+        chart.configure_mesh()
+            .disable_x_mesh()
+            .x_labels(bars.len())
+            .x_label_formatter(&|x| match x {
+                SegmentValue::CenterOf(i) => bars.get(*i).map(|(label, _)| label.clone()).unwrap_or_default(),
+                _ => String::new()
+            })
+            .y_desc(y_desc)
+            .draw()?;
+
+        chart.draw_series(bars.iter().enumerate().map(|(i, (_, value))| {
+            let mut bar = Rectangle::new([(SegmentValue::Exact(i), 0.0), (SegmentValue::Exact(i + 1), *value)],
+                                         BLUE.mix(0.6).filled());
+            bar.set_margin(0, 0, 12, 12);
+            bar
+        }))?;
+
+        root.present()?;
+        Ok(())
+    }
+
+    /// Draw a line chart onto `root`, see `charts::line_chart_png`
+    pub(super) fn draw_line_chart<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, caption: &str, x_desc: &str,
+                                                      y_desc: &str, series: &[ChartSeries]) -> Result<(), ChartError> {
+        root.fill(&WHITE)?;
+        let points = || series.iter().flat_map(|(_, points)| points.iter());
+        let x_max = points().map(|(x, _)| *x).max().unwrap_or(0).max(1);
+        let y_max = points().map(|(_, y)| *y).fold(0.0, f64::max).max(1.0) * 1.1;
+
+        let mut chart = ChartBuilder::on(root)
+            .caption(caption, ("sans-serif", 24))
+            .margin(16)
+            .x_label_area_size(40)
+            .y_label_area_size(70)
+            .build_cartesian_2d(0..x_max, 0.0..y_max)?;
+
+        chart.configure_mesh()
+            .x_desc(x_desc)
+            .y_desc(y_desc)
+            .draw()?;
+
+        for (i, (label, points)) in series.iter().enumerate() {
+            let color = LINE_COLORS[i % LINE_COLORS.len()];
+            chart.draw_series(LineSeries::new(points.iter().copied(), color.stroke_width(2)))?
+                .label(label.as_str())
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+        }
+        chart.configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    }
+
+    /// Render a bar chart as SVG document, see `charts::bar_chart_svg`
+    pub(super) fn bar_chart_svg(caption: &str, y_desc: &str, bars: &[(String, f64)]) -> Result<String, ChartError> {
+        let mut svg = String::new();
+        {
+            let root = SVGBackend::with_string(&mut svg, (CHART_WIDTH, CHART_HEIGHT)).into_drawing_area();
+            draw_bar_chart(&root, caption, y_desc, bars)?;
+        }
+        Ok(svg)
+    }
+
+    /// Render the chart drawn by `draw` as PNG image
+    pub(super) fn render_png<F>(draw: F) -> Result<Vec<u8>, ChartError>
+        where F: FnOnce(&DrawingArea<BitMapBackend, Shift>) -> Result<(), ChartError> {
+        let mut buf = vec![0u8; (CHART_WIDTH * CHART_HEIGHT * 3) as usize];
+        {
+            let root = BitMapBackend::with_buffer(&mut buf, (CHART_WIDTH, CHART_HEIGHT)).into_drawing_area();
+            draw(&root)?;
+        }
+
+        let img = RgbImage::from_raw(CHART_WIDTH, CHART_HEIGHT, buf)
+            .expect("Chart buffer should match the chart size");
+        let mut png = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(img).write_to(&mut png, ImageOutputFormat::Png)
+            .map_err(|err| ChartError::Render { message: err.to_string() })?;
+        Ok(png.into_inner())
+    }
+}
@@ -0,0 +1,131 @@
+extern crate arrow_array;
+extern crate arrow_schema;
+extern crate parquet;
+
+use std::sync::Arc;
+
+use self::arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array};
+use self::arrow_schema::{DataType, Field, Schema};
+use self::parquet::arrow::ArrowWriter;
+use self::parquet::errors::ParquetError;
+use self::parquet::file::metadata::KeyValue;
+use self::parquet::file::properties::WriterProperties;
+
+use crate::firefighter::problem::OSMFNodeState;
+use crate::graph::GraphMetadata;
+
+/// Write the node states `states` as a Parquet file with the columns `node_id`, `lat`, `lon`,
+/// `burn_time`, `defend_time`, `burn_time_minutes`, `defend_time_minutes`, `burn_timestamp`,
+/// `defend_timestamp`, `root_id`, `exposure`, `weight` and `class` and return the raw bytes of
+/// the file.
+/// Nodes that did not burn or were not defended have null values in the respective columns, and
+/// so have nodes without weight or class. The timestamps are null unless the start of the fire
+/// is set.
+/// The source, license and attribution of the graph in `metadata` are stored as key-value
+/// metadata of the file.
+pub fn write_parquet(states: &[OSMFNodeState], metadata: &GraphMetadata) -> Result<Vec<u8>, ParquetError> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("node_id", DataType::UInt64, false),
+        Field::new("lat", DataType::Float64, false),
+        Field::new("lon", DataType::Float64, false),
+        Field::new("burn_time", DataType::UInt64, true),
+        Field::new("defend_time", DataType::UInt64, true),
+        Field::new("burn_time_minutes", DataType::Float64, true),
+        Field::new("defend_time_minutes", DataType::Float64, true),
+        Field::new("burn_timestamp", DataType::UInt64, true),
+        Field::new("defend_timestamp", DataType::UInt64, true),
+        Field::new("root_id", DataType::UInt64, true),
+        Field::new("exposure", DataType::Float64, false),
+        Field::new("weight", DataType::Float64, true),
+        Field::new("class", DataType::Utf8, true),
+    ]));
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(states.iter().map(|s| s.node_id as u64).collect::<UInt64Array>()),
+        Arc::new(states.iter().map(|s| s.lat).collect::<Float64Array>()),
+        Arc::new(states.iter().map(|s| s.lon).collect::<Float64Array>()),
+        Arc::new(states.iter().map(|s| s.burn_time).collect::<UInt64Array>()),
+        Arc::new(states.iter().map(|s| s.defend_time).collect::<UInt64Array>()),
+        Arc::new(states.iter().map(|s| s.burn_time_minutes).collect::<Float64Array>()),
+        Arc::new(states.iter().map(|s| s.defend_time_minutes).collect::<Float64Array>()),
+        Arc::new(states.iter().map(|s| s.burn_timestamp).collect::<UInt64Array>()),
+        Arc::new(states.iter().map(|s| s.defend_timestamp).collect::<UInt64Array>()),
+        Arc::new(states.iter().map(|s| s.root_id.map(|id| id as u64)).collect::<UInt64Array>()),
+        Arc::new(states.iter().map(|s| Some(s.exposure)).collect::<Float64Array>()),
+        Arc::new(states.iter().map(|s| s.weight).collect::<Float64Array>()),
+        Arc::new(states.iter().map(|s| s.class.as_deref()).collect::<StringArray>()),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+
+    let mut buf = Vec::new();
+    let props = WriterProperties::builder()
+        .set_key_value_metadata(Some(vec![
+            KeyValue::new("source".to_string(), metadata.source.clone()),
+            KeyValue::new("license".to_string(), metadata.license.clone()),
+            KeyValue::new("attribution".to_string(), metadata.attribution.clone()),
+        ]))
+        .build();
+    let mut writer = ArrowWriter::try_new(&mut buf, schema, Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{self, File};
+
+    use super::arrow_array::{Array, StringArray, UInt64Array};
+    use super::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use crate::firefighter::export::write_parquet;
+    use crate::firefighter::problem::OSMFNodeState;
+    use crate::graph::GraphMetadata;
+
+    #[test]
+    fn test_write_parquet() {
+        let states: Vec<_> = (0..100)
+            .map(|node_id| OSMFNodeState {
+                node_id,
+                lat: 48.0,
+                lon: 9.0,
+                burn_time: if node_id % 2 == 0 { Some(node_id as u64) } else { None },
+                defend_time: None,
+                burn_time_minutes: if node_id % 2 == 0 { Some(node_id as f64 / 10.0) } else { None },
+                defend_time_minutes: None,
+                burn_timestamp: if node_id % 2 == 0 { Some(1_700_000_000 + node_id as u64 * 6) } else { None },
+                defend_timestamp: None,
+                root_id: if node_id % 2 == 0 { Some(0) } else { None },
+                exposure: node_id as f64,
+                weight: Some(1.5),
+                class: if node_id < 10 { Some("residential".to_string()) } else { None },
+            })
+            .collect();
+        let path = std::env::temp_dir().join(format!("osmff_export_{}.parquet", std::process::id()));
+        fs::write(&path, write_parquet(&states, &GraphMetadata::default()).unwrap()).unwrap();
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let key_values = builder.metadata().file_metadata().key_value_metadata().unwrap();
+        assert!(key_values.iter()
+            .any(|kv| kv.key == "license" && kv.value.as_deref() == Some("ODbL-1.0")));
+        let reader = builder.build().unwrap();
+        let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), states.len());
+
+        let batch = &batches[0];
+        assert_eq!(batch.num_columns(), 13);
+        let burn_times = batch.column_by_name("burn_time").unwrap()
+            .as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(burn_times.value(2), 2);
+        assert!(burn_times.is_null(3));
+        let burn_timestamps = batch.column_by_name("burn_timestamp").unwrap()
+            .as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(burn_timestamps.value(2), 1_700_000_012);
+        let classes = batch.column_by_name("class").unwrap()
+            .as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(classes.value(9), "residential");
+        assert!(classes.is_null(10));
+        fs::remove_file(path).unwrap();
+    }
+}
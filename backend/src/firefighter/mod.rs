@@ -1,6 +1,17 @@
+pub mod analysis;
+pub mod audit;
+pub mod basemap;
+pub mod charts;
+#[cfg(feature = "parquet")]
+pub mod export;
+pub mod playback;
+pub mod plugin;
 pub mod problem;
+pub mod report;
+mod snapshot;
 pub mod strategy;
-mod view;
+mod timeline;
+pub mod view;
 
 /// `u64` type alias to denote a time unit in the firefighter problem
 pub type TimeUnit = u64;
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+use serde::Serialize;
+
+use crate::compute::{RenderPriority, RenderQueue};
+use crate::firefighter::problem::NodeDataStorage;
+use crate::firefighter::TimeUnit;
+use crate::firefighter::view::{Coords, View, ViewOptions};
+use crate::graph::GraphView;
+
+/// Cache of pre-rendered frames of a firefighter simulation for a fixed center, zoom and view
+/// options
+#[derive(Debug)]
+pub struct FrameCache {
+    center: Coords,
+    zoom: f64,
+    options: ViewOptions,
+    end_time: TimeUnit,
+    frames: Mutex<HashMap<TimeUnit, Vec<u8>>>,
+    num_rendered: AtomicU64,
+}
+
+/// Container for data about the progress of a frame pre-generation
+#[derive(Serialize)]
+pub struct OSMFPregenStatus {
+    zoom: f64,
+    frames_total: u64,
+    frames_rendered: u64,
+    progress: f64,
+    pub(super) is_finished: bool,
+}
+
+impl FrameCache {
+    /// Create a new, empty frame cache for all frames from time `0` to `end_time`
+    pub(super) fn new(center: Coords, zoom: f64, options: ViewOptions, end_time: TimeUnit) -> Self {
+        Self {
+            center,
+            zoom,
+            options,
+            end_time,
+            frames: Mutex::new(HashMap::new()),
+            num_rendered: AtomicU64::new(0),
+        }
+    }
+
+    /// Get a copy of the cached frame for `center`, `zoom`, `time` and `options`, if it has
+    /// already been rendered
+    pub(super) fn get(&self, center: Coords, zoom: f64, time: &TimeUnit, options: &ViewOptions) -> Option<Vec<u8>> {
+        if center != self.center || zoom != self.zoom || *options != self.options {
+            return None;
+        }
+        self.frames.lock().unwrap().get(time).cloned()
+    }
+
+    /// Insert the rendered frame for time `time`
+    fn insert(&self, time: TimeUnit, png: Vec<u8>) {
+        self.frames.lock().unwrap().insert(time, png);
+        self.num_rendered.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Get the total size in bytes of all frames rendered so far
+    pub(super) fn size_bytes(&self) -> usize {
+        self.frames.lock().unwrap().values().map(Vec::len).sum()
+    }
+
+    /// Get the current pre-generation status of this frame cache
+    pub fn status(&self) -> OSMFPregenStatus {
+        let frames_total = self.end_time + 1;
+        let frames_rendered = self.num_rendered.load(Ordering::SeqCst);
+        OSMFPregenStatus {
+            zoom: self.zoom,
+            frames_total,
+            frames_rendered,
+            progress: frames_rendered as f64 / frames_total as f64,
+            is_finished: frames_rendered >= frames_total,
+        }
+    }
+}
+
+/// Pre-render all frames of a finished simulation in a background thread and store them
+/// in `cache`.
+/// If a render queue is given together with the owner of the simulation, every frame is
+/// rendered as pre-generation job of the owner, such that the thread yields to all other
+/// renders. The thread stops as soon as the cache has been dropped.
+pub(super) fn spawn_pregen(cache: &Arc<FrameCache>, graph: Arc<dyn GraphView>, node_data: NodeDataStorage,
+                           width: u32, height: u32, render_queue: Option<(Arc<RenderQueue>, String)>) {
+    let weak_cache = Arc::downgrade(cache);
+    let (center, zoom, end_time) = (cache.center, cache.zoom, cache.end_time);
+    let options = cache.options.clone();
+
+    thread::spawn(move || {
+        log::info!("Starting frame pre-generation. zoom={}, frames={}.", zoom, end_time + 1);
+
+        let view = View::new(graph, width, height);
+        for time in 0..=end_time {
+            let _permit = render_queue.as_ref()
+                .map(|(queue, owner)| queue.acquire(RenderPriority::Pregen, owner));
+            if weak_cache.strong_count() == 0 {
+                log::debug!("Frame cache dropped, stopping frame pre-generation");
+                return;
+            }
+
+            let png = view.png_bytes(&view.compute(center, zoom, &time, &node_data, &options));
+
+            match weak_cache.upgrade() {
+                Some(cache) => cache.insert(time, png),
+                None => return,
+            }
+        }
+
+        log::info!("Finished frame pre-generation. zoom={}.", zoom);
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use once_cell::sync::Lazy;
+
+    use crate::compute::{RenderPriority, RenderQueue};
+    use crate::firefighter::playback::{self, FrameCache, OSMFPregenStatus};
+    use crate::firefighter::problem::{NodeDataStorage, OSMFProblem, OSMFSettings};
+    use crate::firefighter::strategy::OSMFStrategy;
+    use crate::firefighter::view::{NodeRadius, ViewOptions};
+    use crate::graph::{Graph, GraphView};
+
+    static GRAPH: Lazy<Arc<dyn GraphView>> = Lazy::new(||
+        Arc::new(Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap()));
+
+    /// Simulate a fire that is contained within a few rounds with a fixed seed, such that all
+    /// simulations burn the same nodes
+    fn simulate() -> OSMFProblem {
+        let mut settings = OSMFSettings::new("bbgrund", "Greedy", 3, 3, 2);
+        settings.seed = Some(7);
+        let strategy = OSMFStrategy::from_name_and_graph("Greedy", GRAPH.clone()).unwrap();
+        let mut problem = OSMFProblem::new(GRAPH.clone(), settings, strategy).unwrap();
+        problem.simulate();
+        problem
+    }
+
+    /// Wait until the pre-generation with the status `status` has rendered all frames
+    fn wait_until_finished(status: impl Fn() -> OSMFPregenStatus) {
+        for _ in 0..600 {
+            if status().is_finished {
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        panic!("Frame pre-generation did not finish");
+    }
+
+    #[test]
+    fn test_pregen_frames_equal_rendered_frames() {
+        let mut pregen = simulate();
+        pregen.start_frame_pregen(1.0, NodeRadius::default());
+        wait_until_finished(|| pregen.pregen_status_response().unwrap());
+
+        // Frames of the finished pre-generation are served from the cache, while the frames of
+        // the simulation without pre-generation are rendered on demand
+        let on_demand = simulate();
+        let options = ViewOptions::default();
+        let end_time = on_demand.simulation_response().end_time;
+        assert!(end_time > 0);
+        for time in 0..=end_time {
+            assert_eq!(pregen.view_response_alt(1.0, &time, &options),
+                       on_demand.view_response_alt(1.0, &time, &options), "Frame {} differs", time);
+        }
+    }
+
+    #[test]
+    fn test_interactive_render_preempts_pregen() {
+        let queue = Arc::new(RenderQueue::new(1));
+        let mut node_data = NodeDataStorage::new();
+        node_data.mark_burning(&vec![0], 0);
+        let cache = Arc::new(FrameCache::new((48.7, 9.1), 1.0, ViewOptions::default(), 3));
+
+        // The pre-generation waits for the running interactive render
+        let permit = queue.acquire(RenderPriority::Interactive, "a");
+        playback::spawn_pregen(&cache, GRAPH.clone(), node_data, 64, 48, Some((queue.clone(), "a".to_string())));
+        while queue.num_waiting() < 1 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // An interactive render that arrives later is still started before any frame
+        let (interactive_queue, interactive_cache) = (queue.clone(), cache.clone());
+        let interactive = thread::spawn(move || {
+            let _permit = interactive_queue.acquire(RenderPriority::Interactive, "b");
+            interactive_cache.status().frames_rendered
+        });
+        while queue.num_waiting() < 2 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        drop(permit);
+        assert_eq!(interactive.join().unwrap(), 0);
+
+        wait_until_finished(|| cache.status());
+        assert_eq!(cache.status().frames_rendered, 4);
+    }
+}
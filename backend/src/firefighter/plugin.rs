@@ -0,0 +1,363 @@
+use std::cmp::min;
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use derive_more::{Display, Error};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use strum::VariantNames;
+
+use crate::firefighter::problem::{NodeDataStorage, OSMFSettings};
+use crate::firefighter::strategy::{OSMFStrategy, Strategy};
+use crate::firefighter::TimeUnit;
+use crate::graph::GraphView;
+
+/// Version of the C interface of strategy plugins. Plugins built against another version are
+/// rejected when they are registered.
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// Name of the function that every plugin library exports to register its strategy.
+/// The function has the signature of [`OSMFPluginEntry`].
+pub const PLUGIN_ENTRY_SYMBOL: &str = "osmff_strategy_plugin";
+
+/// State of a node that is neither burning nor defended
+pub const NODE_UNTOUCHED: u8 = 0;
+/// State of a burning node
+pub const NODE_BURNING: u8 = 1;
+/// State of a defended node
+pub const NODE_DEFENDED: u8 = 2;
+
+/// Graph that a plugin strategy operates on, in compressed sparse row format.
+/// The outgoing edges of node `i` are the entries `offsets[i]..offsets[i + 1]` of `targets`
+/// and `weights`. All arrays stay valid until the strategy instance is destroyed.
+#[derive(Debug)]
+#[repr(C)]
+pub struct OSMFPluginGraph {
+    pub num_nodes: u64,
+    /// `num_nodes + 1` offsets into the edge arrays
+    pub offsets: *const u64,
+    pub targets: *const u64,
+    pub weights: *const u64,
+}
+
+/// Input of a single execution of a plugin strategy, which is only valid during the execution
+#[derive(Debug)]
+#[repr(C)]
+pub struct OSMFPluginRound {
+    pub global_time: u64,
+    pub num_ffs: u64,
+    /// `num_nodes` node states, i.e. `NODE_UNTOUCHED`, `NODE_BURNING` or `NODE_DEFENDED`
+    pub node_states: *const u8,
+}
+
+/// Functions that implement a plugin strategy
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct OSMFStrategyVTable {
+    /// Create a new strategy instance for the graph `graph`. Returns an opaque state that is
+    /// passed to the other functions, which may be null.
+    pub create: unsafe extern "C" fn(graph: *const OSMFPluginGraph) -> *mut c_void,
+    /// Execute the strategy instance `state` for the round `round`. Writes the ids of at most
+    /// `capacity` nodes to defend to `to_defend` and returns their number.
+    pub execute: unsafe extern "C" fn(state: *mut c_void, round: *const OSMFPluginRound,
+                                      to_defend: *mut u64, capacity: u64) -> u64,
+    /// Destroy the strategy instance `state`
+    pub destroy: unsafe extern "C" fn(state: *mut c_void),
+}
+
+/// Strategy that a plugin registers, as returned by its entry function
+#[derive(Debug)]
+#[repr(C)]
+pub struct OSMFStrategyPlugin {
+    /// Must be the first field, such that the versions of plugins can always be checked
+    pub api_version: u32,
+    /// Null-terminated UTF-8 name of the strategy, which must not collide with other strategies
+    pub name: *const c_char,
+    pub vtable: OSMFStrategyVTable,
+}
+
+/// Entry function that plugin libraries export as `PLUGIN_ENTRY_SYMBOL`
+pub type OSMFPluginEntry = unsafe extern "C" fn() -> *const OSMFStrategyPlugin;
+
+#[derive(Debug, Display, Error)]
+pub enum OSMFPluginError {
+    #[display(fmt = "Failed to load plugin {}: {}", path, reason)]
+    InvalidLibrary { path: String, reason: String },
+    #[display(fmt = "Plugin {} implements plugin API version {}, but version {} is required", name, version, required)]
+    IncompatibleVersion { name: String, version: u32, required: u32 },
+    #[display(fmt = "Invalid plugin strategy name: {}", reason)]
+    InvalidName { reason: String },
+    #[display(fmt = "Strategy {} is already registered", name)]
+    DuplicateName { name: String },
+    #[display(fmt = "Loading plugins requires the backend to be built with the 'plugins' feature")]
+    Unsupported,
+}
+
+/// Strategy of a registered plugin
+#[derive(Debug, Clone, Copy)]
+pub struct RegisteredPlugin {
+    name: &'static str,
+    vtable: OSMFStrategyVTable,
+}
+
+/// Strategies of all registered plugins in the order of their registration
+static PLUGINS: Lazy<RwLock<Vec<RegisteredPlugin>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Libraries of all loaded plugins, which are never unloaded since their strategies stay
+/// registered
+#[cfg(feature = "plugins")]
+static LIBRARIES: Lazy<std::sync::Mutex<Vec<libloading::Library>>> =
+    Lazy::new(|| std::sync::Mutex::new(Vec::new()));
+
+/// Register the strategy of the plugin `plugin`, such that it can be used like the built-in
+/// strategies. Returns the name of the strategy.
+///
+/// # Safety
+///
+/// `plugin` must point to a valid `OSMFStrategyPlugin` of the same API version, whose name is a
+/// valid null-terminated string and whose functions stay loaded.
+pub unsafe fn register_plugin(plugin: *const OSMFStrategyPlugin) -> Result<&'static str, OSMFPluginError> {
+    let plugin = match plugin.as_ref() {
+        Some(plugin) => plugin,
+        None => return Err(OSMFPluginError::InvalidName { reason: "The plugin did not register a strategy".to_string() })
+    };
+    if plugin.name.is_null() {
+        return Err(OSMFPluginError::InvalidName { reason: "The name is missing".to_string() });
+    }
+    let name = match CStr::from_ptr(plugin.name).to_str() {
+        Ok(name) => name,
+        Err(_) => return Err(OSMFPluginError::InvalidName { reason: "The name is not valid UTF-8".to_string() })
+    };
+    if plugin.api_version != PLUGIN_API_VERSION {
+        return Err(OSMFPluginError::IncompatibleVersion {
+            name: name.to_string(),
+            version: plugin.api_version,
+            required: PLUGIN_API_VERSION,
+        });
+    }
+    if name.is_empty() || name.contains(',') {
+        return Err(OSMFPluginError::InvalidName { reason: format!("'{}'", name) });
+    }
+
+    let mut plugins = PLUGINS.write().unwrap();
+    if OSMFStrategy::VARIANTS.contains(&name) || plugins.iter().any(|registered| registered.name == name) {
+        return Err(OSMFPluginError::DuplicateName { name: name.to_string() });
+    }
+    // Registered strategies are never removed, so their names live as long as the process
+    let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+    plugins.push(RegisteredPlugin { name, vtable: plugin.vtable });
+    log::info!("Registered plugin strategy {}", name);
+    Ok(name)
+}
+
+/// Load every plugin library in the directory `path` and register its strategy.
+/// Returns the names of the registered strategies.
+#[cfg(feature = "plugins")]
+pub fn load_plugins(path: &str) -> Result<Vec<&'static str>, OSMFPluginError> {
+    let invalid_library = |path: &std::path::Path, reason: String| OSMFPluginError::InvalidLibrary {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let mut paths = Vec::new();
+    let entries = std::fs::read_dir(path)
+        .map_err(|err| invalid_library(std::path::Path::new(path), err.to_string()))?;
+    for entry in entries {
+        let entry_path = entry.map_err(|err| invalid_library(std::path::Path::new(path), err.to_string()))?.path();
+        if entry_path.extension().and_then(|ext| ext.to_str()) == Some(std::env::consts::DLL_EXTENSION) {
+            paths.push(entry_path);
+        }
+    }
+    paths.sort();
+
+    let mut names = Vec::with_capacity(paths.len());
+    for path in paths {
+        // Plugins are trusted code that is executed with the privileges of the backend
+        let library = unsafe { libloading::Library::new(&path) }
+            .map_err(|err| invalid_library(&path, err.to_string()))?;
+        let name = unsafe {
+            let entry = library.get::<OSMFPluginEntry>(PLUGIN_ENTRY_SYMBOL.as_bytes())
+                .map_err(|err| invalid_library(&path, err.to_string()))?;
+            register_plugin(entry())?
+        };
+        LIBRARIES.lock().unwrap().push(library);
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// Load every plugin library in the directory `path` and register its strategy.
+/// Always fails since the backend is built without the `plugins` feature.
+#[cfg(not(feature = "plugins"))]
+pub fn load_plugins(_path: &str) -> Result<Vec<&'static str>, OSMFPluginError> {
+    Err(OSMFPluginError::Unsupported)
+}
+
+/// Get the names of the strategies of all registered plugins
+pub fn plugin_names() -> Vec<&'static str> {
+    PLUGINS.read().unwrap().iter().map(|plugin| plugin.name).collect()
+}
+
+/// Get the registered plugin strategy with name `name`
+pub(super) fn find_plugin(name: &str) -> Option<RegisteredPlugin> {
+    PLUGINS.read().unwrap().iter().find(|plugin| plugin.name == name).copied()
+}
+
+/// Graph of a plugin strategy instance together with the arrays it points to
+#[derive(Debug)]
+struct PluginGraph {
+    graph: OSMFPluginGraph,
+    /// Offsets, targets and weights that the graph points to
+    _arrays: [Vec<u64>; 3],
+}
+
+impl PluginGraph {
+    fn new(graph: &dyn GraphView) -> Box<Self> {
+        let mut offsets = Vec::with_capacity(graph.num_nodes() + 1);
+        let mut targets = Vec::with_capacity(graph.num_edges());
+        let mut weights = Vec::with_capacity(graph.num_edges());
+        offsets.push(0);
+        for node_id in 0..graph.num_nodes() {
+            for edge in graph.get_outgoing_edges(node_id) {
+                targets.push(edge.tgt() as u64);
+                weights.push(edge.dist() as u64);
+            }
+            offsets.push(targets.len() as u64);
+        }
+        // The arrays are not moved along with the box, so the pointers stay valid
+        let graph = OSMFPluginGraph {
+            num_nodes: graph.num_nodes() as u64,
+            offsets: offsets.as_ptr(),
+            targets: targets.as_ptr(),
+            weights: weights.as_ptr(),
+        };
+        Box::new(Self { graph, _arrays: [offsets, targets, weights] })
+    }
+}
+
+/// Instance of a plugin strategy, which is destroyed when dropped
+#[derive(Debug)]
+struct PluginInstance {
+    state: *mut c_void,
+    destroy: unsafe extern "C" fn(state: *mut c_void),
+    /// Graph that the instance may point to until it is destroyed
+    _graph: Box<PluginGraph>,
+}
+
+// Plugins must support their instances being used from different threads one at a time, and
+// instances are only used through mutable references
+unsafe impl Send for PluginInstance {}
+unsafe impl Sync for PluginInstance {}
+
+impl PluginInstance {
+    fn new(plugin: &RegisteredPlugin, graph: &dyn GraphView) -> Self {
+        let graph = PluginGraph::new(graph);
+        let state = unsafe { (plugin.vtable.create)(&graph.graph) };
+        Self { state, destroy: plugin.vtable.destroy, _graph: graph }
+    }
+}
+
+impl Drop for PluginInstance {
+    fn drop(&mut self) {
+        unsafe { (self.destroy)(self.state) }
+    }
+}
+
+/// Fire containment strategy that is implemented by a plugin. The plugin instance is created on
+/// the first execution, so clones start with a fresh instance. Nodes that the plugin chooses to
+/// defend are only defended if they exist and are neither burning nor defended yet.
+#[derive(Debug)]
+pub struct PluginStrategy {
+    graph: Arc<dyn GraphView>,
+    plugin: RegisteredPlugin,
+    instance: Option<PluginInstance>,
+    node_states: Vec<u8>,
+    num_executions: usize,
+    num_rejected: usize,
+}
+
+impl Clone for PluginStrategy {
+    fn clone(&self) -> Self {
+        Self::new(self.plugin, self.graph.clone())
+    }
+}
+
+impl PluginStrategy {
+    /// Create a new instance of the strategy of the plugin `plugin` that operates on the graph
+    /// `graph`
+    pub(super) fn new(plugin: RegisteredPlugin, graph: Arc<dyn GraphView>) -> Self {
+        Self {
+            graph,
+            plugin,
+            instance: None,
+            node_states: Vec::new(),
+            num_executions: 0,
+            num_rejected: 0,
+        }
+    }
+
+    /// Returns the name of the strategy of the plugin
+    pub fn name(&self) -> &'static str {
+        self.plugin.name
+    }
+}
+
+impl Strategy for PluginStrategy {
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               _deadline: Option<Instant>) {
+        let (plugin, graph) = (&self.plugin, &self.graph);
+        let instance = self.instance.get_or_insert_with(|| PluginInstance::new(plugin, graph.as_ref()));
+
+        let num_nodes = self.graph.num_nodes();
+        self.node_states.clear();
+        self.node_states.resize(num_nodes, NODE_UNTOUCHED);
+        for node_id in node_data.get_burning() {
+            self.node_states[node_id] = NODE_BURNING;
+        }
+        for node_id in node_data.get_defended() {
+            self.node_states[node_id] = NODE_DEFENDED;
+        }
+
+        let round = OSMFPluginRound {
+            global_time,
+            num_ffs: settings.num_ffs as u64,
+            node_states: self.node_states.as_ptr(),
+        };
+        let mut chosen = vec![0u64; settings.num_ffs];
+        let num_chosen = unsafe {
+            (self.plugin.vtable.execute)(instance.state, &round, chosen.as_mut_ptr(), chosen.len() as u64)
+        };
+        chosen.truncate(min(num_chosen, chosen.len() as u64) as usize);
+
+        // Defend every node at most once and never defend burning nodes
+        let mut to_defend = Vec::with_capacity(chosen.len());
+        for node_id in chosen.iter().map(|&node_id| node_id as usize) {
+            if node_id < num_nodes && self.node_states[node_id] == NODE_UNTOUCHED {
+                self.node_states[node_id] = NODE_DEFENDED;
+                to_defend.push(node_id);
+            }
+        }
+        if to_defend.len() < chosen.len() {
+            log::debug!("Plugin strategy {} chose {} invalid nodes in round {}", self.plugin.name,
+                chosen.len() - to_defend.len(), global_time);
+        }
+        self.num_rejected += chosen.len() - to_defend.len();
+        self.num_executions += 1;
+        node_data.mark_defended(&to_defend, global_time);
+    }
+
+    fn report(&self) -> Value {
+        json!({
+            "plugin_api_version": PLUGIN_API_VERSION,
+            "num_executions": self.num_executions,
+            "num_rejected": self.num_rejected,
+        })
+    }
+
+    fn release_buffers(&mut self) {
+        self.instance = None;
+        self.node_states = Vec::new();
+    }
+}
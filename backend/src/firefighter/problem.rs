@@ -1,61 +1,726 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
-use std::sync::Arc;
-use std::time::Instant;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use derive_more::{Display, Error};
+use geo::{Contains, LineString, Point, Polygon};
 use log;
+use once_cell::sync::OnceCell;
 use rand::prelude::*;
 use serde::{Serialize, Deserialize};
-
-use crate::firefighter::strategy::OSMFStrategy;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::alloc_stats;
+use crate::compute::{self, RenderPermit, RenderPriority, RenderQueue};
+use crate::firefighter::audit::{ENGINE_VERSION, OSMFAuditLog, OSMFIgnitionCause, OSMFIgnitionRecord};
+use crate::firefighter::basemap::TileCache;
+use crate::firefighter::analysis::{self, NodeImportanceAnalysis, OSMFCorridor, OSMFDefenseEfficiency};
+use crate::firefighter::playback::{self, FrameCache, OSMFPregenStatus};
+use crate::firefighter::strategy::{self, GreedyStrategy, OSMFEnsembleMode, OSMFStrategy};
+use crate::firefighter::snapshot::StateSnapshot;
+use crate::firefighter::timeline::TimelineIndex;
 use crate::firefighter::TimeUnit;
-use crate::firefighter::view::{View, Coords};
-use crate::graph::{Graph, GridBounds};
+use crate::firefighter::view::{self, View, Coords, NodeRadius, ViewOptions};
+use crate::graph::{Edge, Graph, GraphMetadata, GraphView, GridBounds};
+use crate::graph_coarsen::CoarsenedGraph;
+use crate::graph_components::Components;
 
 /// Settings for a firefighter problem instance
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OSMFSettings {
     pub graph_name: String,
     pub strategy_name: String,
     pub num_roots: usize,
     pub num_ffs: usize,
     pub strategy_every: TimeUnit,
+    pub pregen_zoom: Option<f64>,
+    /// Seed for the generation of the fire roots. A random seed is chosen if not set.
+    pub seed: Option<u64>,
+    /// Wall-clock budget in milliseconds for each execution of the strategy. Strategies that plan
+    /// expensively stop planning once the budget is exceeded. Then, the Greedy strategy places the
+    /// firefighters left unused in that round and takes over the remaining rounds.
+    pub strategy_budget_millis: Option<u64>,
+    /// Additional fire roots that ignite in later rounds, given as pairs of the round and either
+    /// the ids of the nodes to ignite or the number of random nodes to ignite
+    #[serde(default)]
+    pub ignition_waves: Vec<(TimeUnit, OSMFIgnition)>,
+    /// Nodes that must not be chosen as random fire roots
+    #[serde(default)]
+    pub excluded_nodes: Vec<usize>,
+    /// Areas whose nodes must not be chosen as random fire roots, given as polygons of
+    /// `(lat, lon)` coordinates
+    #[serde(default)]
+    pub excluded_areas: Vec<Vec<(f64, f64)>>,
+    /// Nodes that are defended at time 0 before the fire roots ignite, e.g. pre-positioned
+    /// firebreaks or the defenses of a previous simulation. They are never chosen as fire roots
+    /// and never fail.
+    #[serde(default)]
+    pub predefended: Vec<usize>,
+    /// Plan of defenses that the `ReplayPlan` strategy replays verbatim
+    #[serde(default)]
+    pub defense_plan: Option<OSMFDefensePlan>,
+    /// Names of the strategies that the `Ensemble` strategy combines
+    #[serde(default)]
+    pub ensemble_strategies: Vec<String>,
+    /// How the `Ensemble` strategy picks the strategy that acts in a round. Rotates among the
+    /// strategies if not set.
+    #[serde(default)]
+    pub ensemble_mode: Option<OSMFEnsembleMode>,
+    /// Minimum distance from any random fire root to every other random fire root
+    #[serde(default)]
+    pub min_root_dist: usize,
+    /// Minimum number of nodes the random fire roots must be able to reach without firefighters.
+    /// Root sets with a lower burn potential are rejected and resampled.
+    #[serde(default)]
+    pub min_burn_potential: usize,
+    /// Maximum number of times a rejected set of random fire roots is resampled. Defaults to
+    /// `DEFAULT_MAX_ROOT_RESAMPLES` if not set.
+    #[serde(default)]
+    pub max_root_resamples: Option<usize>,
+    /// Probability with which each planned defense fails, i.e. is not applied, to simulate
+    /// unreliable firefighters. Defenses never fail if not set.
+    #[serde(default)]
+    pub defense_failure_prob: Option<f64>,
+    /// Rate of spread of the fire in meters per minute, which converts rounds to estimated
+    /// minutes. Defaults to `DEFAULT_FIRE_SPREAD_M_PER_MIN` if not set.
+    #[serde(default)]
+    pub fire_spread_m_per_min: Option<f64>,
+    /// Unix timestamp in seconds at which the fire starts, i.e. of round `0`. If set, times are
+    /// also reported as estimated timestamps, which are derived from the estimated minutes.
+    #[serde(default)]
+    pub start_timestamp: Option<u64>,
+    /// Factors by which the fire spreads faster along the edges of the given road classes, i.e.
+    /// edge types, e.g. a factor of 2 lets the fire cross edges in half the rounds. The fire
+    /// spreads along edges of all other road classes with factor 1.
+    #[serde(default)]
+    pub spread_factors: BTreeMap<u32, f64>,
+    /// Maximum number of threads that the parallel components of the simulation, i.e. parallel
+    /// Dijkstras of strategies and analyses, may use. Unlimited if not set.
+    #[serde(default)]
+    pub threads: Option<usize>,
+    /// Restrict the simulation to the largest weakly connected component of the graph, i.e.
+    /// choose random fire roots and the nodes of ignition waves given by a count only among its
+    /// nodes, and count only its nodes in the statistics. Fires never leave the component of
+    /// their roots, so nodes on disconnected islands can never burn.
+    #[serde(default)]
+    pub largest_component_only: bool,
+}
+
+/// Nodes that ignite in an ignition wave
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum OSMFIgnition {
+    /// Ignite the nodes with the given ids
+    Nodes(Vec<usize>),
+    /// Ignite the given number of randomly chosen nodes
+    Count(usize),
+}
+
+/// Plan of the defenses of a firefighter simulation, i.e. the nodes defended in each round.
+/// Plans can be exported from a simulation and replayed against other fires with the
+/// `ReplayPlan` strategy.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OSMFDefensePlan {
+    /// Name of the graph the plan was made for
+    pub graph_name: String,
+    /// Rounds of the plan in ascending order
+    pub rounds: Vec<OSMFPlanRound>,
+}
+
+/// Nodes defended in a single round of a defense plan
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OSMFPlanRound {
+    pub round: TimeUnit,
+    pub nodes: Vec<usize>,
+}
+
+/// Suggested settings for firefighter problem instances on a specific graph, chosen such that
+/// the fire can neither be contained trivially nor spreads unhindered
+#[derive(Debug, Clone, Serialize)]
+pub struct OSMFSettingsDefaults {
+    pub num_roots: usize,
+    pub num_ffs: usize,
+    pub strategy_every: TimeUnit,
+}
+
+impl OSMFSettingsDefaults {
+    /// Compute suggested settings for `graph`.
+    /// The number of fire roots grows with the square root of the graph size. The strategy is
+    /// executed about once per average edge weight, i.e. about as often as the fire advances by
+    /// one edge, and the firefighters then roughly match the growth of the fire front.
+    pub fn for_graph(graph: &dyn GraphView) -> Self {
+        let num_roots = ((graph.num_nodes() as f64).sqrt() / 10.0).round()
+            .max(1.0).min(MAX_DEFAULT_ROOTS as f64) as usize;
+        let num_roots = num_roots.min(graph.num_nodes());
+
+        let avg_dist = if graph.num_edges() > 0 {
+            graph.iter_edges().map(|edge| edge.dist() as f64).sum::<f64>() / graph.num_edges() as f64
+        } else {
+            1.0
+        };
+        let strategy_every = avg_dist.ceil().max(1.0) as TimeUnit;
+
+        let avg_degree = graph.num_edges() as f64 / graph.num_nodes().max(1) as f64;
+        let num_ffs = (num_roots as f64 * (avg_degree - 1.0)).ceil().max(1.0) as usize;
+
+        Self {
+            num_roots,
+            num_ffs,
+            strategy_every,
+        }
+    }
 }
 
 #[derive(Debug, Display, Error)]
 pub enum OSMFSettingsError {
     #[display(fmt = "Number of fire roots must not be greater than {}: {}", num_nodes, num_roots)]
     InvalidNumRoots { num_nodes: usize, num_roots: usize },
+    #[display(fmt = "Strategy must be executed at least every {} rounds: {}", min, strategy_every)]
+    InvalidStrategyEvery { min: TimeUnit, strategy_every: TimeUnit },
+    #[display(fmt = "Invalid ignition wave in round {}: {}", time, reason)]
+    InvalidIgnitionWave { time: TimeUnit, reason: String },
+    #[display(fmt = "Invalid root exclusion: {}", reason)]
+    InvalidRootExclusion { reason: String },
+    #[display(fmt = "Invalid pre-defended node: {}", node_id)]
+    InvalidPredefended { node_id: usize },
+    #[display(fmt = "Invalid defense plan: {}", reason)]
+    InvalidDefensePlan { reason: String },
+    #[display(fmt = "Invalid ensemble: {}", reason)]
+    InvalidEnsemble { reason: String },
+    #[display(fmt = "Rate of spread of the fire must be a positive number of meters per minute: {}", fire_spread)]
+    InvalidFireSpread { fire_spread: f64 },
+    #[display(fmt = "Minimum burn potential must not be greater than {}: {}", num_nodes, min_burn_potential)]
+    InvalidBurnPotential { num_nodes: usize, min_burn_potential: usize },
+    #[display(fmt = "Probability of failing defenses must be between 0 and 1: {}", probability)]
+    InvalidFailureProbability { probability: f64 },
+    #[display(fmt = "Spread factor of edge type {} must be a positive number: {}", edge_type, factor)]
+    InvalidSpreadFactor { edge_type: u32, factor: f64 },
+    #[display(fmt = "Number of threads must be between 1 and {}: {}", max, threads)]
+    InvalidThreads { max: usize, threads: usize },
+}
+
+impl OSMFSettings {
+    /// Create settings for `num_roots` random fire roots and `num_ffs` firefighters that act
+    /// every `strategy_every` rounds with strategy `strategy_name` on graph `graph_name`, with
+    /// all optional settings unset
+    pub fn new(graph_name: &str, strategy_name: &str, num_roots: usize, num_ffs: usize,
+               strategy_every: TimeUnit) -> Self {
+        Self {
+            graph_name: graph_name.to_string(),
+            strategy_name: strategy_name.to_string(),
+            num_roots,
+            num_ffs,
+            strategy_every,
+            pregen_zoom: None,
+            seed: None,
+            strategy_budget_millis: None,
+            ignition_waves: Vec::new(),
+            excluded_nodes: Vec::new(),
+            excluded_areas: Vec::new(),
+            predefended: Vec::new(),
+            defense_plan: None,
+            ensemble_strategies: Vec::new(),
+            ensemble_mode: None,
+            min_root_dist: 0,
+            min_burn_potential: 0,
+            max_root_resamples: None,
+            defense_failure_prob: None,
+            fire_spread_m_per_min: None,
+            start_timestamp: None,
+            spread_factors: BTreeMap::new(),
+            threads: None,
+            largest_component_only: false,
+        }
+    }
+
+    /// Check whether these settings are valid for a firefighter problem instance on `graph`
+    pub fn validate(&self, graph: &dyn GraphView) -> Result<(), OSMFSettingsError> {
+        if self.num_roots > graph.num_nodes() {
+            return Err(OSMFSettingsError::InvalidNumRoots {
+                num_nodes: graph.num_nodes(),
+                num_roots: self.num_roots,
+            });
+        }
+        if let Some(node_id) = self.excluded_nodes.iter().find(|&&node_id| node_id >= graph.num_nodes()) {
+            return Err(OSMFSettingsError::InvalidRootExclusion {
+                reason: format!("Unknown node {}", node_id),
+            });
+        }
+        if let Some(&node_id) = self.predefended.iter().find(|&&node_id| node_id >= graph.num_nodes()) {
+            return Err(OSMFSettingsError::InvalidPredefended { node_id });
+        }
+        if self.excluded_areas.iter().any(|area| area.len() < 3) {
+            return Err(OSMFSettingsError::InvalidRootExclusion {
+                reason: "Excluded areas must consist of at least 3 coordinates".to_string(),
+            });
+        }
+        let num_candidates = self.root_candidates(graph).len();
+        if self.num_roots > num_candidates {
+            return Err(OSMFSettingsError::InvalidNumRoots {
+                num_nodes: num_candidates,
+                num_roots: self.num_roots,
+            });
+        }
+        if self.min_burn_potential > graph.num_nodes() {
+            return Err(OSMFSettingsError::InvalidBurnPotential {
+                num_nodes: graph.num_nodes(),
+                min_burn_potential: self.min_burn_potential,
+            });
+        }
+        if self.strategy_every == 0 {
+            return Err(OSMFSettingsError::InvalidStrategyEvery {
+                min: 1,
+                strategy_every: self.strategy_every,
+            });
+        }
+        if let Some(fire_spread) = self.fire_spread_m_per_min {
+            if !(fire_spread.is_finite() && fire_spread > 0.0) {
+                return Err(OSMFSettingsError::InvalidFireSpread { fire_spread });
+            }
+        }
+        if let Some(probability) = self.defense_failure_prob {
+            if !(0.0..=1.0).contains(&probability) {
+                return Err(OSMFSettingsError::InvalidFailureProbability { probability });
+            }
+        }
+        if let Some((&edge_type, &factor)) = self.spread_factors.iter()
+            .find(|(_, &factor)| !(factor.is_finite() && factor > 0.0)) {
+            return Err(OSMFSettingsError::InvalidSpreadFactor { edge_type, factor });
+        }
+        if let Some(threads) = self.threads {
+            let max = compute::max_thread_limit();
+            if !(1..=max).contains(&threads) {
+                return Err(OSMFSettingsError::InvalidThreads { max, threads });
+            }
+        }
+        match &self.defense_plan {
+            Some(plan) => {
+                let invalid = |reason: String| OSMFSettingsError::InvalidDefensePlan { reason };
+                if plan.graph_name != self.graph_name {
+                    return Err(invalid(format!("Plan was made for graph '{}'", plan.graph_name)));
+                }
+                if plan.rounds.iter().any(|round| round.round == 0) {
+                    return Err(invalid("Defenses must not be planned before round 1".to_string()));
+                }
+                if let Some(node_id) = plan.rounds.iter()
+                    .flat_map(|round| &round.nodes)
+                    .find(|&&node_id| node_id >= graph.num_nodes()) {
+                    return Err(invalid(format!("Unknown node {}", node_id)));
+                }
+            }
+            None if self.uses_strategy("ReplayPlan") => {
+                return Err(OSMFSettingsError::InvalidDefensePlan {
+                    reason: "The ReplayPlan strategy requires a defense plan".to_string(),
+                });
+            }
+            None => ()
+        }
+        if self.strategy_name == "Ensemble" {
+            let invalid = |reason: String| OSMFSettingsError::InvalidEnsemble { reason };
+            if self.ensemble_strategies.is_empty() {
+                return Err(invalid("The Ensemble strategy requires at least one strategy".to_string()));
+            }
+            let available = OSMFStrategy::available_strategies();
+            if let Some(name) = self.ensemble_strategies.iter()
+                .find(|&name| name == "Ensemble" || !available.contains(name)) {
+                return Err(invalid(format!("Invalid strategy '{}'", name)));
+            }
+        }
+        for (time, ignition) in &self.ignition_waves {
+            let invalid = |reason: String| OSMFSettingsError::InvalidIgnitionWave {
+                time: *time,
+                reason,
+            };
+            if *time == 0 {
+                return Err(invalid("Ignition waves must not start before round 1".to_string()));
+            }
+            if let OSMFIgnition::Nodes(nodes) = ignition {
+                if let Some(node_id) = nodes.iter().find(|&&node_id| node_id >= graph.num_nodes()) {
+                    return Err(invalid(format!("Unknown node {}", node_id)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Does the simulation use the strategy with name `strategy_name`, either directly or as part
+    /// of an ensemble?
+    fn uses_strategy(&self, strategy_name: &str) -> bool {
+        self.strategy_name == strategy_name
+            || (self.strategy_name == "Ensemble" && self.ensemble_strategies.iter().any(|name| name == strategy_name))
+    }
+
+    /// Get the estimated number of minutes per round. Edge weights are distances in meters, so the
+    /// fire advances by one meter per round.
+    pub fn minutes_per_round(&self) -> f64 {
+        1.0 / self.fire_spread_m_per_min.unwrap_or(DEFAULT_FIRE_SPREAD_M_PER_MIN)
+    }
+
+    /// Convert `time` in rounds to estimated minutes
+    pub fn to_minutes(&self, time: TimeUnit) -> f64 {
+        time as f64 * self.minutes_per_round()
+    }
+
+    /// Convert `time` in rounds to an estimated Unix timestamp in seconds, if the start of the
+    /// fire is set
+    pub fn to_timestamp(&self, time: TimeUnit) -> Option<u64> {
+        self.start_timestamp.map(|start| start + (self.to_minutes(time) * 60.0).round() as u64)
+    }
+
+    /// Convert `time` in rounds to all units of time
+    pub fn to_time(&self, time: TimeUnit) -> OSMFTime {
+        OSMFTime {
+            round: time,
+            minutes: self.to_minutes(time),
+            timestamp: self.to_timestamp(time),
+        }
+    }
+
+    /// Get the number of rounds the fire takes to spread along the edge `edge`, i.e. its distance
+    /// scaled by the spread factor of its road class
+    pub(super) fn spread_rounds(&self, edge: &Edge) -> TimeUnit {
+        spread_rounds(&self.spread_factors, edge)
+    }
+
+    /// Get the ids of all nodes of `graph` that may be chosen as random fire roots, i.e. all
+    /// nodes that are neither excluded explicitly, pre-defended nor located in an excluded area
+    pub(super) fn root_candidates(&self, graph: &dyn GraphView) -> Vec<usize> {
+        let areas: Vec<_> = self.excluded_areas.iter()
+            .map(|area| {
+                let exterior: Vec<_> = area.iter().map(|&(lat, lon)| (lon, lat)).collect();
+                Polygon::new(LineString::from(exterior), vec![])
+            })
+            .collect();
+        let excluded_nodes: BTreeSet<_> = self.excluded_nodes.iter().chain(&self.predefended).collect();
+        let component_mask = self.component_mask(graph);
+
+        graph.iter_nodes()
+            .filter(|node| !excluded_nodes.contains(&node.id()))
+            .filter(|node| match &component_mask {
+                Some(mask) => mask[node.id()],
+                None => true
+            })
+            .filter(|node| {
+                let point = Point::new(node.lon, node.lat);
+                !areas.iter().any(|area| area.contains(&point))
+            })
+            .map(|node| node.id())
+            .collect()
+    }
+
+    /// Translate these settings for a simulation on the coarsened graph `coarsened` of a graph with
+    /// `num_nodes` nodes, i.e. replace all nodes by their representatives. The number of fire roots
+    /// and the minimum burn potential are reduced to what the coarsened graph allows.
+    fn for_coarsened(&self, coarsened: &CoarsenedGraph, num_nodes: usize) -> Self {
+        let representatives = |node_ids: &[usize]| {
+            let mut representatives: Vec<_> = node_ids.iter()
+                .map(|&node_id| coarsened.representative(node_id))
+                .collect();
+            representatives.sort_unstable();
+            representatives.dedup();
+            representatives
+        };
+
+        let mut settings = self.clone();
+        settings.excluded_nodes = representatives(&self.excluded_nodes);
+        settings.predefended = representatives(&self.predefended);
+        for (_, ignition) in &mut settings.ignition_waves {
+            if let OSMFIgnition::Nodes(nodes) = ignition {
+                *nodes = representatives(nodes);
+            }
+        }
+        if let Some(plan) = &mut settings.defense_plan {
+            for round in &mut plan.rounds {
+                round.nodes = representatives(&round.nodes);
+            }
+        }
+        let graph = coarsened.graph();
+        settings.num_roots = settings.num_roots.min(settings.root_candidates(&**graph).len());
+        settings.min_burn_potential = settings.min_burn_potential * graph.num_nodes() / num_nodes.max(1);
+        settings.pregen_zoom = None;
+        settings
+    }
+
+    /// Get whether every node of `graph`, by node id, belongs to the largest weakly connected
+    /// component if the simulation is restricted to it, or `None` otherwise
+    pub(super) fn component_mask(&self, graph: &dyn GraphView) -> Option<Vec<bool>> {
+        if !self.largest_component_only {
+            return None;
+        }
+        let mask = match graph.as_graph() {
+            Some(graph) => graph.weak_components().largest_mask(),
+            None => Components::weakly_connected(graph).largest_mask()
+        };
+        Some(mask)
+    }
+}
+
+/// Get the number of rounds the fire takes to spread along the edge `edge`, i.e. its distance
+/// scaled by the factor of its road class in the spread factors `spread_factors`
+pub(super) fn spread_rounds(spread_factors: &BTreeMap<u32, f64>, edge: &Edge) -> TimeUnit {
+    match spread_factors.get(&edge.attrs.edge_type) {
+        Some(factor) => (edge.dist() as f64 / factor).ceil() as TimeUnit,
+        None => edge.dist() as TimeUnit
+    }
 }
 
 /// Node data related to the firefighter problem
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 pub(super) struct NodeData {
     pub node_id: usize,
     time: TimeUnit,
 }
 
+/// Map from node ids to values of type `V`.
+/// Values are kept in a tree map while simulating and compacted into an array sorted by node id
+/// once the simulation has finished, which is inserted into the tree map again if modified.
+#[derive(Debug, Clone)]
+enum NodeMap<V> {
+    Tree(BTreeMap<usize, V>),
+    Compact(Box<[(usize, V)]>),
+}
+
+impl<V: Clone> NodeMap<V> {
+    /// Create a new empty node map
+    fn new() -> Self {
+        Self::Tree(BTreeMap::new())
+    }
+
+    /// Get the value of the node with id `node_id`
+    fn get(&self, node_id: &usize) -> Option<&V> {
+        match self {
+            Self::Tree(map) => map.get(node_id),
+            Self::Compact(entries) => entries.binary_search_by_key(node_id, |(id, _)| *id)
+                .ok()
+                .map(|i| &entries[i].1)
+        }
+    }
+
+    /// Does this map contain a value for the node with id `node_id`?
+    fn contains_key(&self, node_id: &usize) -> bool {
+        self.get(node_id).is_some()
+    }
+
+    /// Get the number of nodes in this map
+    fn len(&self) -> usize {
+        match self {
+            Self::Tree(map) => map.len(),
+            Self::Compact(entries) => entries.len()
+        }
+    }
+
+    /// Iterate over all `(node id, value)` pairs ordered by node id
+    fn iter(&self) -> impl Iterator<Item = (&usize, &V)> {
+        let (tree, compact) = match self {
+            Self::Tree(map) => (Some(map.iter()), None),
+            Self::Compact(entries) => (None, Some(entries.iter().map(|(id, value)| (id, value))))
+        };
+        tree.into_iter().flatten().chain(compact.into_iter().flatten())
+    }
+
+    /// Iterate over all node ids in ascending order
+    fn keys(&self) -> impl Iterator<Item = &usize> {
+        self.iter().map(|(node_id, _)| node_id)
+    }
+
+    /// Iterate over all values ordered by node id
+    fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Get the tree map of this map to modify it, expanding a compacted map if necessary
+    fn tree_mut(&mut self) -> &mut BTreeMap<usize, V> {
+        if let Self::Compact(entries) = self {
+            *self = Self::Tree(entries.iter().cloned().collect());
+        }
+        match self {
+            Self::Tree(map) => map,
+            Self::Compact(_) => unreachable!()
+        }
+    }
+
+    /// Insert the value `value` for the node with id `node_id`.
+    /// Returns the previous value of the node, if any.
+    fn insert(&mut self, node_id: usize, value: V) -> Option<V> {
+        self.tree_mut().insert(node_id, value)
+    }
+
+    /// Insert all `(node id, value)` pairs of `entries`
+    fn extend(&mut self, entries: impl IntoIterator<Item = (usize, V)>) {
+        self.tree_mut().extend(entries);
+    }
+
+    /// Compact this map into an array sorted by node id
+    fn compact(&mut self) {
+        if let Self::Tree(map) = self {
+            *self = Self::Compact(std::mem::take(map).into_iter().collect());
+        }
+    }
+}
+
+impl<V: Clone + Serialize> Serialize for NodeMap<V> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Cumulative number of nodes that changed by every time, e.g. started burning, such that counting
+/// the nodes that changed by some time is an array lookup
+#[derive(Debug, Clone, Default)]
+struct CumulativeCounts(Vec<usize>);
+
+impl CumulativeCounts {
+    /// Count a node that changed at time `time`
+    fn add(&mut self, time: TimeUnit) {
+        let time = time as usize;
+        if time >= self.0.len() {
+            let count = self.0.last().copied().unwrap_or(0);
+            self.0.resize(time + 1, count);
+        }
+        // Nodes usually change in the latest round, so this only updates a single count
+        for count in &mut self.0[time..] {
+            *count += 1;
+        }
+    }
+
+    /// Uncount a node that changed at time `time`
+    fn remove(&mut self, time: TimeUnit) {
+        for count in &mut self.0[time as usize..] {
+            *count -= 1;
+        }
+    }
+
+    /// Get the number of nodes that changed by time `time`
+    fn count_by(&self, time: TimeUnit) -> usize {
+        match self.0.len() {
+            0 => 0,
+            len => self.0[(time as usize).min(len - 1)]
+        }
+    }
+}
+
+/// Injected failures of planned defenses
+#[derive(Debug, Clone)]
+struct DefenseFailures {
+    probability: f64,
+    rng: StdRng,
+    num_failed: usize,
+}
+
 /// Storage for node data
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 pub(super) struct NodeDataStorage {
-    burning: BTreeMap<usize, NodeData>,
-    defended: BTreeMap<usize, NodeData>,
+    burning: NodeMap<NodeData>,
+    defended: NodeMap<NodeData>,
+    burn_parents: NodeMap<usize>,
+    wave_roots: BTreeSet<usize>,
+    #[serde(skip)]
+    burned_by: CumulativeCounts,
+    #[serde(skip)]
+    defended_by: CumulativeCounts,
+    /// Nodes marked as burning and defended since the journal was last taken, if journaling is
+    /// enabled
+    #[serde(skip)]
+    journal: Option<(Vec<usize>, Vec<usize>)>,
+    /// Injected failures of planned defenses, if failure injection is enabled
+    #[serde(skip)]
+    defense_failures: Option<DefenseFailures>,
+    /// Index of the timeline once the simulation has finished, which is dropped if the storage
+    /// is modified again
+    #[serde(skip)]
+    timeline: Option<Arc<TimelineIndex>>,
 }
 
 impl NodeDataStorage {
     /// Create a new node data storage
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
+        Self {
+            burning: NodeMap::new(),
+            defended: NodeMap::new(),
+            burn_parents: NodeMap::new(),
+            wave_roots: BTreeSet::new(),
+            burned_by: CumulativeCounts::default(),
+            defended_by: CumulativeCounts::default(),
+            journal: None,
+            defense_failures: None,
+            timeline: None,
+        }
+    }
+
+    /// Let every defense that is marked from now on fail with probability `probability`, using
+    /// a random sequence derived from `seed`
+    fn inject_defense_failures(&mut self, probability: f64, seed: u64) {
+        self.defense_failures = Some(DefenseFailures {
+            probability,
+            rng: StdRng::seed_from_u64(seed),
+            num_failed: 0,
+        });
+    }
+
+    /// Mark all nodes in `nodes` as defended at time 0 ahead of the simulation. In contrast to
+    /// planned defenses, these defenses never fail.
+    fn mark_predefended(&mut self, nodes: &[usize]) {
+        let defense_failures = self.defense_failures.take();
+        self.mark_defended(nodes, 0);
+        self.defense_failures = defense_failures;
+    }
+
+    /// Copy this storage to let a strategy plan on the copy without affecting the simulation.
+    /// The copy neither journals nor fails defenses.
+    pub(super) fn lookahead_copy(&self) -> Self {
         Self {
-            burning: BTreeMap::new(),
-            defended: BTreeMap::new(),
+            journal: None,
+            defense_failures: None,
+            ..self.clone()
+        }
+    }
+
+    /// Get the number of planned defenses that failed
+    fn num_failed_defenses(&self) -> usize {
+        self.defense_failures.as_ref().map_or(0, |failures| failures.num_failed)
+    }
+
+    /// Start recording all nodes that are marked as burning or defended from now on
+    fn start_journal(&mut self) {
+        self.journal = Some((Vec::new(), Vec::new()));
+    }
+
+    /// Take the sorted id's of all nodes that were marked as burning and defended since the
+    /// journal was started or last taken
+    fn take_journal(&mut self) -> (Vec<usize>, Vec<usize>) {
+        match self.journal {
+            Some((ref mut burned, ref mut defended)) => {
+                let (mut burned, mut defended) = (std::mem::take(burned), std::mem::take(defended));
+                burned.sort_unstable();
+                defended.sort_unstable();
+                (burned, defended)
+            }
+            None => (Vec::new(), Vec::new())
         }
     }
 
-    /// Is node with id `node_id` a fire root?
+    /// Compact this storage once the simulation has finished, such that it only occupies the
+    /// memory needed to answer queries about the simulation
+    fn compact(&mut self) {
+        self.burning.compact();
+        self.defended.compact();
+        self.burn_parents.compact();
+        self.journal = None;
+        self.timeline = Some(Arc::new(TimelineIndex::new(
+            self.burning.values().map(|nd| (nd.node_id, nd.time)),
+            self.defended.values().map(|nd| (nd.node_id, nd.time)))));
+    }
+
+    /// Is node with id `node_id` a fire root? This includes the roots of later ignition waves.
     pub fn is_root(&self, node_id: &usize) -> bool {
         match self.burning.get(node_id) {
-            Some(nd) => nd.time == 0,
+            Some(nd) => nd.time == 0 || self.wave_roots.contains(node_id),
             None => false
         }
     }
@@ -67,17 +732,23 @@ impl NodeDataStorage {
 
     /// Is node with id `node_id` burning by time `time`?
     pub fn is_burning_by(&self, node_id: &usize, time: &TimeUnit) -> bool {
+        if let Some(ref timeline) = self.timeline {
+            return timeline.is_burning_by(*node_id, *time);
+        }
         match self.burning.get(node_id) {
             Some(nd) => nd.time <= *time,
             None => false
         }
     }
 
+    /// Get the time at which the node with id `node_id` started burning, if it is burning
+    pub fn get_burning_time(&self, node_id: &usize) -> Option<TimeUnit> {
+        self.burning.get(node_id).map(|nd| nd.time)
+    }
+
     /// Count all nodes burning by time `time`
     pub fn count_burning_by(&self, time: &TimeUnit) -> usize {
-        self.burning.values()
-            .filter(|nd| nd.time <= *time)
-            .count()
+        self.burned_by.count_by(*time)
     }
 
     /// Is node with id `node_id` defended?
@@ -87,17 +758,23 @@ impl NodeDataStorage {
 
     /// Is node with id `node_id` defended by time `time`?
     pub fn is_defended_by(&self, node_id: &usize, time: &TimeUnit) -> bool {
+        if let Some(ref timeline) = self.timeline {
+            return timeline.is_defended_by(*node_id, *time);
+        }
         match self.defended.get(node_id) {
             Some(nd) => nd.time <= *time,
             None => false
         }
     }
 
+    /// Get the time at which the node with id `node_id` was defended, if it is defended
+    pub fn get_defended_time(&self, node_id: &usize) -> Option<TimeUnit> {
+        self.defended.get(node_id).map(|nd| nd.time)
+    }
+
     /// Count all nodes defended by time `time`
     pub fn count_defended_by(&self, time: &TimeUnit) -> usize {
-        self.defended.values()
-            .filter(|nd| nd.time <= *time)
-            .count()
+        self.defended_by.count_by(*time)
     }
 
     /// Is node with id `node_id` undefended?
@@ -106,28 +783,82 @@ impl NodeDataStorage {
     }
 
     /// Mark all nodes in `nodes` as burning at time `time`
-    fn mark_burning(&mut self, nodes: &Vec<usize>, time: TimeUnit) {
+    pub(super) fn mark_burning(&mut self, nodes: &Vec<usize>, time: TimeUnit) {
         if !nodes.is_empty() {
             log::debug!("Burning nodes {:?} in round {}", nodes, time);
+            self.timeline = None;
         }
-        for node_id in nodes {
-            self.burning.insert(*node_id, NodeData {
-                node_id: *node_id,
-                time,
-            });
+        for &node_id in nodes {
+            self.insert_burning(node_id, time);
+        }
+        if let Some((ref mut burned, _)) = self.journal {
+            burned.extend(nodes);
+        }
+    }
+
+    /// Record that the node with id `node_id` burns from time `time` and update the counts
+    fn insert_burning(&mut self, node_id: usize, time: TimeUnit) {
+        if let Some(previous) = self.burning.insert(node_id, NodeData { node_id, time }) {
+            self.burned_by.remove(previous.time);
+        }
+        self.burned_by.add(time);
+    }
+
+    /// Record that the node with id `node_id` is defended from time `time` and update the counts
+    fn insert_defended(&mut self, node_id: usize, time: TimeUnit) {
+        if let Some(previous) = self.defended.insert(node_id, NodeData { node_id, time }) {
+            self.defended_by.remove(previous.time);
         }
+        self.defended_by.add(time);
+    }
+
+    /// Mark all nodes in `nodes` as fire roots of an ignition wave that ignite at time `time`
+    pub(super) fn mark_wave_roots(&mut self, nodes: &Vec<usize>, time: TimeUnit) {
+        self.mark_burning(nodes, time);
+        self.wave_roots.extend(nodes);
+    }
+
+    /// Record for every `(node, parent)` pair in `ignitions` that `node` has been
+    /// ignited by its neighbour `parent`
+    fn mark_ignited_by(&mut self, ignitions: impl Iterator<Item = (usize, usize)>) {
+        self.burn_parents.extend(ignitions);
     }
 
-    /// Mark all nodes in `nodes` as defended at time `time`
+    /// Get the id of the neighbour that ignited the node with id `node_id`.
+    /// Returns `None` if the node is a fire root or not burning.
+    pub fn get_burn_parent(&self, node_id: &usize) -> Option<usize> {
+        self.burn_parents.get(node_id).copied()
+    }
+
+    /// Mark all nodes in `nodes` as defended at time `time`.
+    /// This is the single path through which all strategies apply their defenses, so if failure
+    /// injection is enabled, each defense fails here with the configured probability.
     pub fn mark_defended(&mut self, nodes: &[usize], time: TimeUnit) {
+        let applied: Vec<usize>;
+        let nodes = match self.defense_failures {
+            Some(ref mut failures) => {
+                let DefenseFailures { probability, rng, num_failed } = failures;
+                applied = nodes.iter()
+                    .filter(|_| !rng.gen_bool(*probability))
+                    .copied()
+                    .collect();
+                if applied.len() < nodes.len() {
+                    log::debug!("{} defenses failed in round {}", nodes.len() - applied.len(), time);
+                    *num_failed += nodes.len() - applied.len();
+                }
+                &applied[..]
+            }
+            None => nodes
+        };
         if !nodes.is_empty() {
             log::debug!("Defending nodes {:?} in round {}", nodes, time);
+            self.timeline = None;
         }
-        for node_id in nodes {
-            self.defended.insert(*node_id, NodeData {
-                node_id: *node_id,
-                time,
-            });
+        for &node_id in nodes {
+            self.insert_defended(node_id, time);
+        }
+        if let Some((_, ref mut defended)) = self.journal {
+            defended.extend(nodes);
         }
     }
 
@@ -141,8 +872,16 @@ impl NodeDataStorage {
         self.burning.keys().map(usize::to_owned).collect()
     }
 
+    /// Get the id's of all defended vertices
+    pub fn get_defended(&self) -> Vec<usize> {
+        self.defended.keys().map(usize::to_owned).collect()
+    }
+
     /// Get the id's of all burning vertices at time `time`
     pub fn get_burning_at(&self, time: &TimeUnit) -> Vec<usize> {
+        if let Some(ref timeline) = self.timeline {
+            return timeline.burning_at(*time).to_vec();
+        }
         self.burning.values()
             .filter(|&nd| nd.time == *time)
             .map(|nd| nd.node_id)
@@ -156,6 +895,9 @@ impl NodeDataStorage {
 
     /// Get the id's of all defended vertices at time `time`
     pub fn get_defended_at(&self, time: &TimeUnit) -> Vec<usize> {
+        if let Some(ref timeline) = self.timeline {
+            return timeline.defended_at(*time).to_vec();
+        }
         self.defended.values()
             .filter(|&nd| nd.time == *time)
             .map(|nd| nd.node_id)
@@ -163,266 +905,2737 @@ impl NodeDataStorage {
     }
 }
 
+/// An execution of a containment strategy that exceeded the strategy time budget
+#[derive(Debug, Serialize)]
+pub struct OSMFBudgetViolation {
+    round: TimeUnit,
+    strategy_name: String,
+    elapsed_millis: u128,
+}
+
+/// Peak memory that a containment strategy held allocated at once during its execution in a
+/// round, which is only measured if the `alloc-stats` feature is enabled
+#[derive(Debug, Serialize)]
+pub struct OSMFStrategyMemory {
+    round: TimeUnit,
+    strategy_name: String,
+    peak_bytes: usize,
+}
+
+/// Units of the numbers in responses about a firefighter simulation
+#[derive(Serialize)]
+pub struct OSMFUnits {
+    time: &'static str,
+    distance: &'static str,
+    area: &'static str,
+    minutes_per_round: f64,
+    /// Unix timestamp in seconds of round `0`, if the start of the fire is set
+    start_timestamp: Option<u64>,
+}
+
+impl OSMFUnits {
+    /// Get the units of simulations with settings `settings`
+    fn for_settings(settings: &OSMFSettings) -> Self {
+        Self {
+            time: "rounds",
+            distance: "m",
+            area: "km2",
+            minutes_per_round: settings.minutes_per_round(),
+            start_timestamp: settings.start_timestamp,
+        }
+    }
+}
+
+/// Time of a firefighter simulation in rounds, in estimated minutes since the start of the fire
+/// and, if the start of the fire is set, as estimated Unix timestamp in seconds, such that
+/// clients need not convert between them
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct OSMFTime {
+    pub round: TimeUnit,
+    pub minutes: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+}
+
 /// Container for data about the simulation of a firefighter problem instance
 #[derive(Serialize)]
 pub struct OSMFSimulationResponse<'a> {
     pub nodes_burned: usize,
     pub nodes_defended: usize,
-    nodes_total: usize,
+    pub nodes_total: usize,
+    pub burned_area_km2: f64,
+    /// Total weight of the burned and of the defended nodes, if the graph has node weights
+    pub weight_burned: Option<f64>,
+    pub weight_defended: Option<f64>,
+    /// Estimated distance in meters that the firefighters travelled between their defenses
+    pub travel_distance_m: f64,
     pub end_time: TimeUnit,
+    end_time_minutes: f64,
+    /// End of the simulation in all units of time
+    end: OSMFTime,
+    units: OSMFUnits,
     pub simulation_time_millis: u128,
+    pub seed: u64,
+    /// Fully resolved settings the simulation ran with, which reproduce the simulation
+    pub settings: OSMFSettings,
+    /// Number of random fire root sets that were rejected due to a low burn potential
+    pub root_rejections: usize,
+    /// Number of planned defenses that failed due to failure injection
+    pub failed_defenses: usize,
+    strategy_report: Value,
+    budget_violations: &'a [OSMFBudgetViolation],
+    /// Largest peak memory of the strategy over all rounds, if allocations are counted
+    pub peak_strategy_bytes: Option<usize>,
+    strategy_memory: &'a [OSMFStrategyMemory],
     view_bounds: &'a GridBounds,
     view_center: Coords,
 }
 
-/// Container for data about a specific step of a firefighter simulation
+/// Container for the estimated outcome of a firefighter simulation from a preview on the
+/// coarsened graph
 #[derive(Serialize)]
+pub struct OSMFPreviewResponse {
+    /// Always `true`, since all numbers are estimates that may deviate from a full simulation
+    is_estimate: bool,
+    /// Estimated number of burned nodes of the original graph, i.e. the total number of nodes
+    /// represented by the burned nodes of the coarsened graph
+    pub nodes_burned: usize,
+    pub nodes_defended: usize,
+    pub nodes_total: usize,
+    pub end_time: TimeUnit,
+    end_time_minutes: f64,
+    units: OSMFUnits,
+    pub simulation_time_millis: u128,
+    pub seed: u64,
+    /// Number of nodes of the coarsened graph
+    pub coarsened_nodes: usize,
+    /// Number of edges of the coarsened graph
+    pub coarsened_edges: usize,
+}
+
+/// Container for data about a specific step of a firefighter simulation
+#[derive(Debug, Clone, Serialize)]
 pub struct OSMFSimulationStepMetadata {
+    /// Time of the step in all units of time
+    time: OSMFTime,
+    time_minutes: f64,
     nodes_burned_by: usize,
     nodes_defended_by: usize,
     nodes_burned_at: Vec<usize>,
     nodes_defended_at: Vec<usize>,
 }
 
-/// A firefighter problem instance
-#[derive(Debug)]
-pub struct OSMFProblem {
-    graph: Arc<Graph>,
-    settings: OSMFSettings,
-    strategy: OSMFStrategy,
-    node_data: NodeDataStorage,
-    global_time: TimeUnit,
-    simulation_time_millis: u128,
-    is_active: bool,
-    view: View,
+/// A fire root together with the color of its burn tree in color-by-root views
+#[derive(Debug, Serialize)]
+pub struct OSMFRootColor {
+    root: usize,
+    lat: f64,
+    lon: f64,
+    /// Hex color code, e.g. `#ff0000`
+    color: String,
 }
 
-impl OSMFProblem {
-    /// Create a new firefighter problem instance
-    pub fn new(graph: Arc<Graph>, settings: OSMFSettings, strategy: OSMFStrategy) -> Result<Self, OSMFSettingsError> {
-        if settings.num_roots > graph.num_nodes {
-            let err = OSMFSettingsError::InvalidNumRoots {
-                num_nodes: graph.num_nodes,
-                num_roots: settings.num_roots,
-            };
-            log::warn!("{}", err.to_string());
-            return Err(err);
+impl OSMFRootColor {
+    /// Get the colors of the burn trees of all fire roots by time `time` in `node_data`
+    fn for_roots(graph: &dyn GraphView, node_data: &NodeDataStorage, time: &TimeUnit) -> Vec<Self> {
+        view::root_colors(node_data, time).into_iter()
+            .map(|(root, rgb)| {
+                let node = graph.get_node(root);
+                Self {
+                    root,
+                    lat: node.lat,
+                    lon: node.lon,
+                    color: format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Voronoi region of a fire root, i.e. all nodes that the root would reach first without
+/// firefighters, together with the outcome of the simulation in the region
+#[derive(Serialize)]
+pub struct OSMFVoronoiRegion {
+    #[serde(flatten)]
+    root: OSMFRootColor,
+    num_nodes: usize,
+    num_burned: usize,
+    num_defended: usize,
+}
+
+/// Nearest-root partition of a firefighter simulation
+#[derive(Serialize)]
+pub struct OSMFVoronoi {
+    regions: Vec<OSMFVoronoiRegion>,
+    /// Root of the region of every node, indexed by node id, or `None` if no root reaches the node
+    labels: Vec<Option<usize>>,
+}
+
+/// Container for summary statistics about the outcome of a firefighter simulation
+#[derive(Serialize)]
+pub struct OSMFSimulationStats {
+    /// End of the simulation in all units of time
+    end: OSMFTime,
+    nodes_burned: usize,
+    nodes_defended: usize,
+    nodes_total: usize,
+    nodes_effective: usize,
+    total_savings: f64,
+    avg_savings_per_defense: f64,
+}
+
+/// Progress of a firefighter simulation that can be read while the simulation is running
+#[derive(Debug, Default)]
+pub struct SimulationProgress {
+    round: AtomicU64,
+    round_estimate: AtomicU64,
+    nodes_settled: AtomicUsize,
+    nodes_reachable: AtomicUsize,
+    is_finished: AtomicBool,
+    /// Metadata of all completed rounds, if the simulation publishes its steps
+    steps: Mutex<Vec<OSMFSimulationStepMetadata>>,
+    /// Snapshots of the node data at every multiple of `SNAPSHOT_EVERY` rounds, if the simulation
+    /// publishes its steps
+    snapshots: Mutex<Vec<StateSnapshot>>,
+}
+
+/// Container for data about the progress of a firefighter simulation
+#[derive(Serialize)]
+pub struct OSMFSimulationProgress {
+    round: TimeUnit,
+    round_estimate: TimeUnit,
+    nodes_settled: usize,
+    nodes_reachable: usize,
+    pub progress: f64,
+    pub is_finished: bool,
+}
+
+impl SimulationProgress {
+    /// Get a snapshot of the current progress of the simulation.
+    /// The progress is estimated by the current round in relation to the latest time at which
+    /// the fire would reach a node without firefighters, and by the number of burning or
+    /// defended nodes in relation to the number of nodes reachable by the fire, whichever is
+    /// further advanced. It stays below `1` until the simulation has finished.
+    pub fn snapshot(&self) -> OSMFSimulationProgress {
+        let round = self.round.load(Ordering::SeqCst);
+        let round_estimate = self.round_estimate.load(Ordering::SeqCst);
+        let nodes_settled = self.nodes_settled.load(Ordering::SeqCst);
+        let nodes_reachable = self.nodes_reachable.load(Ordering::SeqCst);
+        let is_finished = self.is_finished.load(Ordering::SeqCst);
+
+        let progress = if is_finished {
+            1.0
+        } else {
+            let by_rounds = if round_estimate > 0 { round as f64 / round_estimate as f64 } else { 0.0 };
+            let by_nodes = if nodes_reachable > 0 { nodes_settled as f64 / nodes_reachable as f64 } else { 0.0 };
+            by_rounds.max(by_nodes).min(0.99)
+        };
+
+        OSMFSimulationProgress {
+            round,
+            round_estimate,
+            nodes_settled,
+            nodes_reachable,
+            progress,
+            is_finished,
+        }
+    }
+
+    /// Is the simulation finished?
+    pub fn is_finished(&self) -> bool {
+        self.is_finished.load(Ordering::SeqCst)
+    }
+
+    /// Get the number of completed rounds whose metadata has been published, i.e. the watermark
+    /// up to which steps of the running simulation can be played back
+    pub fn num_published_steps(&self) -> usize {
+        self.steps.lock().unwrap().len()
+    }
+
+    /// Get the published metadata of the completed round at time `time`, if available
+    pub fn get_step(&self, time: TimeUnit) -> Option<OSMFSimulationStepMetadata> {
+        self.steps.lock().unwrap().get(time as usize).cloned()
+    }
+
+    /// Publish the metadata of the next completed round
+    fn publish_step(&self, step: OSMFSimulationStepMetadata) {
+        self.steps.lock().unwrap().push(step);
+    }
+
+    /// Is the snapshot of the node data of the round at time `time` due? Rounds are published in
+    /// order, so this is the case for every multiple of `SNAPSHOT_EVERY` rounds.
+    fn is_snapshot_due(&self, time: TimeUnit) -> bool {
+        self.snapshots.lock().unwrap().len() as TimeUnit * SNAPSHOT_EVERY == time
+    }
+
+    /// Store the snapshot of the node data of the round whose metadata is published next
+    fn store_snapshot(&self, snapshot: StateSnapshot) {
+        self.snapshots.lock().unwrap().push(snapshot);
+    }
+
+    /// Reconstruct the node data of all rounds up to time `time` from the published steps,
+    /// starting at the latest snapshot before, such that at most `SNAPSHOT_EVERY` rounds are
+    /// replayed. Returns `None` if the round at time `time` has not been published yet.
+    pub(super) fn rewind_to(&self, time: TimeUnit) -> Option<NodeDataStorage> {
+        let steps = self.steps.lock().unwrap();
+        if time as usize >= steps.len() {
+            return None;
+        }
+
+        let mut node_data = NodeDataStorage::new();
+        let snapshots = self.snapshots.lock().unwrap();
+        let num_usable = snapshots.len().min((time / SNAPSHOT_EVERY) as usize + 1);
+        let start = match num_usable.checked_sub(1) {
+            Some(i) => {
+                for (node_id, t) in snapshots[i].burned() {
+                    node_data.insert_burning(node_id, t);
+                }
+                for (node_id, t) in snapshots[i].defended() {
+                    node_data.insert_defended(node_id, t);
+                }
+                i as TimeUnit * SNAPSHOT_EVERY + 1
+            }
+            None => 0
+        };
+        drop(snapshots);
+
+        for (t, step) in steps.iter().enumerate().take(time as usize + 1).skip(start as usize) {
+            let t = t as TimeUnit;
+            for &node_id in &step.nodes_burned_at {
+                node_data.insert_burning(node_id, t);
+            }
+            for &node_id in &step.nodes_defended_at {
+                node_data.insert_defended(node_id, t);
+            }
+        }
+        Some(node_data)
+    }
+
+    /// Discard the published metadata and the snapshots of all completed rounds.
+    /// Returns the approximate number of reclaimed bytes.
+    fn clear_steps(&self) -> usize {
+        let steps = std::mem::take(&mut *self.steps.lock().unwrap());
+        let snapshots = std::mem::take(&mut *self.snapshots.lock().unwrap());
+        let steps_bytes: usize = steps.iter()
+            .map(|step| mem::size_of::<OSMFSimulationStepMetadata>()
+                + (step.nodes_burned_at.capacity() + step.nodes_defended_at.capacity()) * mem::size_of::<usize>())
+            .sum();
+        steps_bytes + snapshots.iter().map(StateSnapshot::size_bytes).sum::<usize>()
+    }
+}
+
+/// Handle of a running firefighter simulation that allows to view its completed rounds while the
+/// simulation is still running, if the simulation publishes its steps
+pub struct OSMFRunningSimulation {
+    graph: Arc<dyn GraphView>,
+    graph_name: String,
+    progress: Arc<SimulationProgress>,
+    publishes_steps: bool,
+    basemap: Option<Arc<TileCache>>,
+    render_queue: Option<(Arc<RenderQueue>, String)>,
+    spread_factors: BTreeMap<u32, f64>,
+    view: OnceCell<View>,
+}
+
+impl OSMFRunningSimulation {
+    /// Get the progress of the running simulation
+    pub fn progress(&self) -> &Arc<SimulationProgress> {
+        &self.progress
+    }
+
+    /// Get the name of the graph the simulation is running on
+    pub fn graph_name(&self) -> &str {
+        &self.graph_name
+    }
+
+    /// Does the running simulation publish its completed rounds, i.e. can it be viewed while it
+    /// is still running?
+    pub fn publishes_steps(&self) -> bool {
+        self.publishes_steps
+    }
+
+    /// Get the time of the last completed round of the running simulation, if any round has been
+    /// published yet
+    pub fn watermark(&self) -> Option<TimeUnit> {
+        self.progress.num_published_steps().checked_sub(1).map(|time| time as TimeUnit)
+    }
+
+    /// Reconstruct the node data of all rounds up to time `time` from the published steps and
+    /// snapshots. Returns `None` if the round at time `time` has not been published yet.
+    fn node_data_until(&self, time: TimeUnit) -> Option<NodeDataStorage> {
+        self.progress.rewind_to(time)
+    }
+
+    /// Generate the view response for the completed round at time `time` of the running
+    /// simulation, centered at `center` or at the center of the graph.
+    /// Fire roots of ignition waves and the fire flow are only shown once the simulation has
+    /// finished. Returns `None` if the round at time `time` has not been completed yet.
+    pub fn view_response(&self, center: Option<Coords>, zoom: f64, time: &TimeUnit,
+                         options: &ViewOptions) -> Option<Vec<u8>> {
+        log::info!("Generating partial view response. center={:?}, zoom={}, time={}, options={:?}.",
+            center, zoom, time, options);
+
+        let node_data = self.node_data_until(*time)?;
+        let view = self.view.get_or_init(|| {
+            let mut view = View::new(self.graph.clone(), VIEW_WIDTH, VIEW_HEIGHT);
+            if let Some(basemap) = &self.basemap {
+                view.set_basemap(basemap.clone());
+            }
+            view.set_spread_factors(self.spread_factors.clone());
+            view
+        });
+        let _permit = self.render_queue.as_ref()
+            .map(|(queue, owner)| queue.acquire(RenderPriority::Interactive, owner));
+        let img_buf = match center {
+            Some(center) => view.compute(center, zoom, time, &node_data, options),
+            None => view.compute_alt(zoom, time, &node_data, options)
+        };
+        Some(view.png_bytes(&img_buf))
+    }
+
+    /// Generate the step metadata response for the completed round at time `time` of the running
+    /// simulation. Returns `None` if the round at time `time` has not been completed yet.
+    pub fn sim_step_metadata_response(&self, time: &TimeUnit) -> Option<OSMFSimulationStepMetadata> {
+        log::info!("Generating partial simulation step metadata response. time={}.", time);
+
+        self.progress.get_step(*time)
+    }
+
+    /// Generate the root palette response for the completed round at time `time` of the running
+    /// simulation. Returns `None` if the round at time `time` has not been completed yet.
+    pub fn root_palette_response(&self, time: &TimeUnit) -> Option<Vec<OSMFRootColor>> {
+        let node_data = self.node_data_until(*time)?;
+        Some(OSMFRootColor::for_roots(&*self.graph, &node_data, time))
+    }
+}
+
+/// Rate of spread of the fire in meters per minute that is assumed if not specified otherwise
+pub const DEFAULT_FIRE_SPREAD_M_PER_MIN: f64 = 10.0;
+/// Maximum number of times a set of random fire roots with a low burn potential is resampled
+/// that is assumed if not specified otherwise
+pub const DEFAULT_MAX_ROOT_RESAMPLES: usize = 10;
+/// Default distance in meters within which nodes are exposed to the smoke of burning nodes
+pub const DEFAULT_EXPOSURE_RADIUS_M: f64 = 500.0;
+/// Maximum number of fire roots suggested for any graph
+const MAX_DEFAULT_ROOTS: usize = 50;
+/// Number of rounds between the snapshots of the node data of simulations that publish their
+/// steps, which bounds the number of rounds replayed to reconstruct the node data of any round
+const SNAPSHOT_EVERY: TimeUnit = 64;
+/// Width of rendered views in pixels
+const VIEW_WIDTH: u32 = 1920;
+/// Height of rendered views in pixels
+const VIEW_HEIGHT: u32 = 1080;
+/// Approximate number of bytes a simulation allocates per graph node, including node data and
+/// strategy state
+const EST_BYTES_PER_NODE: usize = 128;
+/// Approximate number of bytes the undefended roots tracking of the MultiMinDistanceSets and
+/// Priority strategies allocates per graph node and fire root
+const EST_BYTES_PER_NODE_AND_ROOT: usize = 16;
+
+/// State of a single node at the end of a firefighter simulation
+#[derive(Serialize)]
+pub struct OSMFNodeState {
+    pub(super) node_id: usize,
+    pub(super) lat: f64,
+    pub(super) lon: f64,
+    pub(super) burn_time: Option<TimeUnit>,
+    pub(super) defend_time: Option<TimeUnit>,
+    pub(super) burn_time_minutes: Option<f64>,
+    pub(super) defend_time_minutes: Option<f64>,
+    /// Estimated Unix timestamps of the burn and defend time, if the start of the fire is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) burn_timestamp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) defend_timestamp: Option<u64>,
+    /// Fire root whose burn tree the node belongs to
+    pub(super) root_id: Option<usize>,
+    /// Smoke exposure of the node, i.e. the rounds it spent near burning nodes weighted by their
+    /// distance
+    pub(super) exposure: f64,
+    /// Weight and class of the node, if the graph has node weights
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) weight: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) class: Option<String>,
+}
+
+/// Parent pointer of a burning node in the burn forest of a firefighter simulation
+#[derive(Serialize)]
+pub struct OSMFBurnForestEntry {
+    node_id: usize,
+    parent: Option<usize>,
+    burned_at: TimeUnit,
+}
+
+/// A single hop on the path along which the fire reached a node
+#[derive(Serialize)]
+pub struct OSMFFirePathHop {
+    node_id: usize,
+    burned_at: TimeUnit,
+}
+
+/// Container for data about the timeline of a specific node of a firefighter simulation
+#[derive(Serialize)]
+pub struct OSMFNodeTimeline {
+    node_id: usize,
+    burned_at: Option<TimeUnit>,
+    defended_at: Option<TimeUnit>,
+    burned_at_minutes: Option<f64>,
+    defended_at_minutes: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    burned_at_timestamp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    defended_at_timestamp: Option<u64>,
+    nearest_root: Option<usize>,
+    /// Distance in meters to the nearest fire root
+    nearest_root_dist: Option<usize>,
+    fire_path: Vec<OSMFFirePathHop>,
+}
+
+/// A firefighter problem instance
+#[derive(Debug)]
+pub struct OSMFProblem {
+    graph: Arc<dyn GraphView>,
+    settings: OSMFSettings,
+    strategy: OSMFStrategy,
+    fallback_strategy: Option<OSMFStrategy>,
+    budget_violations: Vec<OSMFBudgetViolation>,
+    /// Peak memory of the strategy in every round it was executed, if allocations are counted
+    strategy_memory: Vec<OSMFStrategyMemory>,
+    /// Final report of the strategy, which is retained once the simulation has been compacted
+    strategy_report: Option<Value>,
+    next_wave: usize,
+    node_data: NodeDataStorage,
+    seed: u64,
+    root_rejections: usize,
+    global_time: TimeUnit,
+    progress: Arc<SimulationProgress>,
+    publish_steps: bool,
+    simulation_time_millis: u128,
+    is_active: bool,
+    view: View,
+    frame_cache: Option<Arc<FrameCache>>,
+    /// Queue through which all views are rendered, together with the owner of this problem
+    /// instance, if renders are scheduled
+    render_queue: Option<(Arc<RenderQueue>, String)>,
+    /// Rule evaluation behind every ignition, if the audit mode is enabled
+    audit: Option<Vec<OSMFIgnitionRecord>>,
+    /// Whether every node belongs to the largest component, if the simulation is restricted to it
+    component_mask: Option<Vec<bool>>,
+    /// Directory in which the full intermediate results of the strategy are written to a debug
+    /// file, if any
+    strategy_debug_path: Option<PathBuf>,
+}
+
+/// Arrival of the fire at a node from a burning neighbour
+struct FireArrival {
+    source: usize,
+    source_burn_time: TimeUnit,
+    edge_weight: usize,
+    spread_rounds: TimeUnit,
+}
+
+impl FireArrival {
+    /// Get the round in which the fire arrives
+    fn time(&self) -> TimeUnit {
+        self.source_burn_time + self.spread_rounds
+    }
+}
+
+impl OSMFProblem {
+    /// Create a new firefighter problem instance
+    pub fn new(graph: Arc<dyn GraphView>, settings: OSMFSettings, strategy: OSMFStrategy) -> Result<Self, OSMFSettingsError> {
+        if let Err(err) = settings.validate(&*graph) {
+            log::warn!("{}", err.to_string());
+            return Err(err);
+        }
+
+        let seed = settings.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut settings = settings;
+        settings.ignition_waves.sort_by_key(|(time, _)| *time);
+        if let Some(plan) = &mut settings.defense_plan {
+            plan.rounds.sort_by_key(|round| round.round);
+        }
+        let node_data = Self::init_node_data(&settings, seed);
+        let component_mask = settings.component_mask(&*graph);
+        let mut problem = Self {
+            graph: graph.clone(),
+            settings,
+            strategy,
+            fallback_strategy: None,
+            budget_violations: Vec::new(),
+            strategy_memory: Vec::new(),
+            strategy_report: None,
+            next_wave: 0,
+            node_data,
+            seed,
+            root_rejections: 0,
+            global_time: 0,
+            progress: Arc::new(SimulationProgress::default()),
+            publish_steps: false,
+            simulation_time_millis: 0,
+            is_active: true,
+            view: View::new(graph, VIEW_WIDTH, VIEW_HEIGHT),
+            frame_cache: None,
+            render_queue: None,
+            audit: None,
+            component_mask,
+            strategy_debug_path: strategy::debug_path().map(Path::to_path_buf),
+        };
+        problem.view.set_spread_factors(problem.settings.spread_factors.clone());
+        log::info!("Initialized problem configuration. settings={:?}.", &problem.settings);
+
+        Ok(problem)
+    }
+
+    /// Create the node data storage of a firefighter problem instance with settings `settings`
+    /// and seed `seed`
+    fn init_node_data(settings: &OSMFSettings, seed: u64) -> NodeDataStorage {
+        let mut node_data = NodeDataStorage::new();
+        if let Some(probability) = settings.defense_failure_prob {
+            // Failures draw from their own random sequence derived from the seed
+            node_data.inject_defense_failures(probability, !seed);
+        }
+        node_data
+    }
+
+    /// Roughly estimate the number of bytes a firefighter problem instance on `graph` with settings
+    /// `settings` allocates, without creating it
+    pub fn estimate_memory_bytes(graph: &dyn GraphView, settings: &OSMFSettings) -> usize {
+        let mut bytes = graph.num_nodes() * EST_BYTES_PER_NODE
+            + (VIEW_WIDTH * VIEW_HEIGHT * 3) as usize;
+        if settings.uses_strategy("MultiMinDistanceSets") || settings.uses_strategy("Priority") {
+            bytes += graph.num_nodes() * settings.num_roots * EST_BYTES_PER_NODE_AND_ROOT;
+        }
+        bytes
+    }
+
+    /// Is the node with id `node_id` part of the simulation, i.e. part of the largest component if
+    /// the simulation is restricted to it?
+    fn is_in_scope(&self, node_id: usize) -> bool {
+        match &self.component_mask {
+            Some(mask) => mask[node_id],
+            None => true
+        }
+    }
+
+    /// Get the number of nodes that are part of the simulation
+    fn num_nodes_in_scope(&self) -> usize {
+        match &self.component_mask {
+            Some(mask) => mask.iter().filter(|&&in_scope| in_scope).count(),
+            None => self.graph.num_nodes()
+        }
+    }
+
+    /// Generate `num_roots` fire roots from the seed of this problem instance.
+    /// If a minimum burn potential is set, root sets that reach fewer nodes without firefighters
+    /// are rejected and resampled up to the maximum number of resamples. If no sample reaches the
+    /// minimum burn potential, the sample with the highest burn potential is used.
+    fn gen_fire_roots(&mut self) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let candidates = self.settings.root_candidates(&*self.graph);
+        let min_burn_potential = self.settings.min_burn_potential;
+        let max_resamples = self.settings.max_root_resamples.unwrap_or(DEFAULT_MAX_ROOT_RESAMPLES);
+
+        let mut roots = self.sample_fire_roots(&mut rng, candidates.clone());
+        if min_burn_potential > 0 {
+            let graph = self.graph.clone();
+            let burn_potential = |roots: &[usize]| graph.run_dijkstra(roots).into_iter()
+                .filter(|&dist| dist < usize::MAX)
+                .count();
+            let mut best_potential = burn_potential(&roots);
+            while best_potential < min_burn_potential && self.root_rejections < max_resamples {
+                self.root_rejections += 1;
+                let sample = self.sample_fire_roots(&mut rng, candidates.clone());
+                let potential = burn_potential(&sample);
+                if potential > best_potential {
+                    roots = sample;
+                    best_potential = potential;
+                }
+            }
+            if best_potential < min_burn_potential {
+                log::warn!("Fire roots reach only {} of at least {} nodes after {} resamples",
+                           best_potential, min_burn_potential, self.root_rejections);
+            }
+        }
+
+        self.node_data.mark_burning(&roots, self.global_time);
+        self.record_ignitions(roots.iter().map(|&node| (node, OSMFIgnitionCause::Root)));
+
+        log::info!("Generated fire roots. rejections={}.", self.root_rejections);
+
+        roots
+    }
+
+    /// Sample `num_roots` fire roots among the root candidates `candidates` using `rng`. If a
+    /// minimum root distance is set, roots are chosen greedily in random order such that no root
+    /// is closer to another one than that.
+    fn sample_fire_roots(&self, rng: &mut StdRng, mut candidates: Vec<usize>) -> Vec<usize> {
+        let num_roots = self.settings.num_roots;
+        let min_root_dist = self.settings.min_root_dist;
+
+        if min_root_dist == 0 {
+            return candidates.into_iter().choose_multiple(rng, num_roots);
+        }
+
+        candidates.shuffle(rng);
+        let mut blocked = vec![false; self.graph.num_nodes()];
+        let mut roots = Vec::with_capacity(num_roots);
+        for node_id in candidates {
+            if roots.len() == num_roots {
+                break;
+            }
+            if blocked[node_id] {
+                continue;
+            }
+
+            // In directed graphs, the candidate may still be too close to previous roots
+            let dists = self.graph.run_dijkstra_within(&[node_id], min_root_dist - 1);
+            if roots.iter().any(|&root: &usize| dists[root] < usize::MAX) {
+                continue;
+            }
+
+            roots.push(node_id);
+            // Nodes closer to the new root than the minimum root distance must not become roots
+            for (blocked, dist) in blocked.iter_mut().zip(dists) {
+                *blocked |= dist < usize::MAX;
+            }
+        }
+        if roots.len() < num_roots {
+            log::warn!("Only {} of {} fire roots are at least {} apart", roots.len(), num_roots,
+                       min_root_dist);
+        }
+        roots
+    }
+
+    /// Ignite the nodes of all ignition waves that are scheduled for the current round and notify
+    /// the fire containment strategy about the new fire roots
+    fn ignite_waves(&mut self) {
+        while let Some((time, ignition)) = self.settings.ignition_waves.get(self.next_wave) {
+            if *time > self.global_time {
+                break;
+            }
+
+            let roots: Vec<_> = match ignition {
+                OSMFIgnition::Nodes(nodes) => nodes.iter()
+                    .filter(|&node_id| self.node_data.is_undefended(node_id))
+                    .copied()
+                    .collect(),
+                OSMFIgnition::Count(count) => {
+                    // Every wave draws from its own random sequence derived from the seed
+                    let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(self.next_wave as u64 + 1));
+                    self.graph.iter_nodes()
+                        .map(|node| node.id())
+                        .filter(|node_id| self.node_data.is_undefended(node_id) && self.is_in_scope(*node_id))
+                        .choose_multiple(&mut rng, *count)
+                }
+            };
+            self.next_wave += 1;
+
+            log::debug!("Igniting wave of {} nodes in round {}", roots.len(), self.global_time);
+            self.node_data.mark_wave_roots(&roots, self.global_time);
+            self.record_ignitions(roots.iter().map(|&node| (node, OSMFIgnitionCause::Wave)));
+            self.strategy.on_ignition(&roots, &self.settings, &self.node_data);
+            if !roots.is_empty() {
+                self.is_active = true;
+            }
+        }
+    }
+
+    /// Spread the fire to all nodes that are adjacent to burning nodes.
+    /// Defended nodes will remain defended.
+    /// Returns the earliest time after the current global time at which the fire may reach another
+    /// node, or `None` if no undefended node is adjacent to a burning node anymore.
+    fn spread_fire(&mut self) -> Option<TimeUnit> {
+        // Nodes to burn, mapped to the earliest arrival of the fire from a neighbour
+        let mut to_burn: BTreeMap<usize, FireArrival> = BTreeMap::new();
+        let mut next_arrival = TimeUnit::MAX;
+
+        // For all undefended neighbours that are not already burning, check whether they have
+        // to be added to `to_burn`
+        self.is_active = false;
+        for node_data in self.node_data.get_burning_node_data() {
+            for edge in self.graph.get_outgoing_edges(node_data.node_id) {
+                if self.node_data.is_undefended(&edge.tgt()) {
+                    // There is at least one node to be burned at some point in the future
+                    if !self.is_active {
+                        self.is_active = true;
+                    }
+                    // Burn the node if the global time exceeds the time at which the edge source
+                    // started burning plus the edge weight
+                    let arrival = FireArrival {
+                        source: node_data.node_id,
+                        source_burn_time: node_data.time,
+                        edge_weight: edge.dist(),
+                        spread_rounds: self.settings.spread_rounds(edge),
+                    };
+                    if self.global_time >= arrival.time() {
+                        match to_burn.get_mut(&edge.tgt()) {
+                            Some(earliest) if arrival.time() < earliest.time() => *earliest = arrival,
+                            Some(_) => {}
+                            None => {
+                                to_burn.insert(edge.tgt(), arrival);
+                            }
+                        }
+                    } else {
+                        next_arrival = next_arrival.min(arrival.time());
+                    }
+                }
+            }
+        }
+
+        // Burn all nodes in `to_burn` and record which neighbour ignited them
+        let nodes: Vec<_> = to_burn.keys().copied().collect();
+        self.node_data.mark_burning(&nodes, self.global_time);
+        self.node_data.mark_ignited_by(to_burn.iter().map(|(&node, arrival)| (node, arrival.source)));
+        self.record_ignitions(to_burn.into_iter().map(|(node, arrival)| (node, OSMFIgnitionCause::Spread {
+            source: arrival.source,
+            source_burn_time: arrival.source_burn_time,
+            edge_weight: arrival.edge_weight,
+            spread_rounds: arrival.spread_rounds,
+        })));
+
+        // The fire spreads from the nodes burned in this round from the next round on
+        for &node_id in &nodes {
+            for edge in self.graph.get_outgoing_edges(node_id) {
+                if self.node_data.is_undefended(&edge.tgt()) {
+                    let arrival = self.global_time + self.settings.spread_rounds(edge);
+                    next_arrival = next_arrival.min(arrival.max(self.global_time + 1));
+                }
+            }
+        }
+
+        if next_arrival < TimeUnit::MAX {
+            Some(next_arrival)
+        } else {
+            None
+        }
+    }
+
+    /// Record the ignitions of nodes in the current round by their causes `ignitions` in the audit
+    /// log, if the audit mode is enabled
+    fn record_ignitions(&mut self, ignitions: impl Iterator<Item = (usize, OSMFIgnitionCause)>) {
+        let round = self.global_time;
+        if let Some(audit) = &mut self.audit {
+            audit.extend(ignitions.map(|(node, cause)| OSMFIgnitionRecord { node, round, cause }));
+        }
+    }
+
+    /// Execute the containment strategy to prevent as much nodes as
+    /// possible from catching fire
+    fn contain_fire(&mut self) {
+        if self.global_time % self.settings.strategy_every == 0 {
+            let (strategy, strategy_name) = match self.fallback_strategy {
+                Some(ref mut fallback) => (fallback, "Greedy"),
+                None => (&mut self.strategy, self.settings.strategy_name.as_str())
+            };
+
+            let budget = self.settings.strategy_budget_millis.map(Duration::from_millis);
+            let start = Instant::now();
+            let deadline = budget.map(|budget| start + budget);
+            let settings = &self.settings;
+            let node_data = &mut self.node_data;
+            let global_time = self.global_time;
+            let (_, peak_bytes) = alloc_stats::measure_peak(
+                || strategy.mut_inner().execute(settings, node_data, global_time, deadline));
+            let elapsed = start.elapsed();
+
+            if let Some(peak_bytes) = peak_bytes {
+                log::debug!("Strategy {} held at most {} bytes allocated in round {}",
+                    strategy_name, peak_bytes, self.global_time);
+                self.strategy_memory.push(OSMFStrategyMemory {
+                    round: self.global_time,
+                    strategy_name: strategy_name.to_string(),
+                    peak_bytes,
+                });
+            }
+
+            if let Some(budget) = budget {
+                if elapsed > budget {
+                    log::warn!("Strategy {} exceeded its time budget of {} ms in round {}: {} ms",
+                        strategy_name, budget.as_millis(), self.global_time, elapsed.as_millis());
+                    self.budget_violations.push(OSMFBudgetViolation {
+                        round: self.global_time,
+                        strategy_name: strategy_name.to_string(),
+                        elapsed_millis: elapsed.as_millis(),
+                    });
+                    if self.fallback_strategy.is_none() {
+                        self.start_fallback_strategy();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fall back to the Greedy strategy for the remaining rounds, once the strategy exceeded its
+    /// time budget. The fallback places the firefighters the interrupted strategy left unused in
+    /// the current round right away.
+    fn start_fallback_strategy(&mut self) {
+        let mut fallback = OSMFStrategy::Greedy(GreedyStrategy::new(self.graph.clone()));
+        let num_defended = self.node_data.get_defended_at(&self.global_time).len();
+        if num_defended < self.settings.num_ffs {
+            let settings = OSMFSettings {
+                num_ffs: self.settings.num_ffs - num_defended,
+                ..self.settings.clone()
+            };
+            fallback.mut_inner().execute(&settings, &mut self.node_data, self.global_time, None);
+        }
+        self.fallback_strategy = Some(fallback);
+    }
+
+    /// Execute one time step in the firefighter problem.
+    /// That is, execute the containment strategy, spread the fire and
+    /// check whether the game is finished.
+    /// Returns the earliest time at which the fire may reach another node, if any.
+    fn exec_step(&mut self) -> Option<TimeUnit> {
+        self.global_time += 1;
+
+        self.ignite_waves();
+        self.contain_fire();
+        self.spread_fire()
+    }
+
+    /// Get the time of the next round after the current global time in which anything can happen,
+    /// i.e. the fire may reach another node at `next_arrival`, the containment strategy is
+    /// executed or an ignition wave ignites. All rounds before are empty.
+    fn next_event_time(&self, next_arrival: Option<TimeUnit>) -> TimeUnit {
+        let next_round = self.global_time + 1;
+        // If no fire is spreading anymore, the simulation ends in the next round
+        if self.is_active && next_arrival.is_none() {
+            return next_round;
+        }
+
+        let every = self.settings.strategy_every;
+        let mut next_event = (self.global_time / every + 1) * every;
+        if let Some(arrival) = next_arrival {
+            next_event = next_event.min(arrival);
+        }
+        if let Some((time, _)) = self.settings.ignition_waves.get(self.next_wave) {
+            next_event = next_event.min(*time);
+        }
+        next_event.max(next_round)
+    }
+
+    /// Simulate the firefighter problem until the `is_active` flag is set to `false`
+    pub fn simulate(&mut self) {
+        if !self.is_active {
+            return;
+        }
+
+        log::info!("Starting problem simulation");
+
+        if self.publish_steps {
+            self.node_data.start_journal();
+        }
+        self.node_data.mark_predefended(&self.settings.predefended);
+        let roots = self.gen_fire_roots();
+        self.publish_step();
+
+        // Measure simulation time
+        let start = Instant::now();
+
+        // Estimate the extent of the fire without firefighters to report the progress
+        let dists = self.graph.run_dijkstra(&roots);
+        let reachable: Vec<_> = dists.into_iter().filter(|&dist| dist < usize::MAX).collect();
+        let round_estimate = reachable.iter().max().copied().unwrap_or(0) as TimeUnit;
+        self.progress.round_estimate.store(round_estimate, Ordering::SeqCst);
+        self.progress.nodes_reachable.store(reachable.len(), Ordering::SeqCst);
+
+        let debug_name = format!("{}-{}-{}", self.settings.graph_name, self.strategy.name(), self.seed);
+        let debug_path = self.strategy_debug_path.clone();
+        strategy::with_debug_file(debug_path.as_deref(), &debug_name, || {
+            compute::with_thread_limit(self.settings.threads, || self.simulate_from_roots(&roots))
+        });
+        self.simulation_time_millis = start.elapsed().as_millis();
+
+        self.compact();
+        self.progress.is_finished.store(true, Ordering::SeqCst);
+
+        log::info!("Finished problem simulation");
+    }
+
+    /// Compact this firefighter problem instance once its simulation has finished, i.e. release
+    /// all buffers of the strategies and compact the node data into arrays
+    fn compact(&mut self) {
+        self.strategy_report = Some(self.strategy.report());
+        self.strategy.release_buffers();
+        if let Some(fallback_strategy) = &mut self.fallback_strategy {
+            fallback_strategy.release_buffers();
+        }
+        self.node_data.compact();
+        self.budget_violations.shrink_to_fit();
+        self.strategy_memory.shrink_to_fit();
+    }
+
+    /// Initialize the fire containment strategy for the fire roots `roots` and simulate until
+    /// the `is_active` flag is set to `false`
+    fn simulate_from_roots(&mut self, roots: &Vec<usize>) {
+        self.strategy.initialize(roots, &self.settings, &self.node_data);
+        log::debug!("Initialized fire containment strategy");
+
+        while self.is_active || self.next_wave < self.settings.ignition_waves.len() {
+            let next_arrival = self.exec_step();
+            self.progress.round.store(self.global_time, Ordering::SeqCst);
+            self.progress.nodes_settled.store(self.node_data.burning.len() + self.node_data.defended.len(),
+                                              Ordering::SeqCst);
+            self.publish_step();
+
+            // Skip all empty rounds up to the next event, e.g. while the fire spreads along long
+            // edges. Empty rounds are still published, such that every round can be played back.
+            if self.is_active || self.next_wave < self.settings.ignition_waves.len() {
+                let next_event = self.next_event_time(next_arrival);
+                while self.global_time + 1 < next_event {
+                    self.global_time += 1;
+                    self.publish_step();
+                }
+            }
+        }
+    }
+
+    /// Publish the metadata of the round at the current global time to the simulation progress,
+    /// if step publishing is enabled
+    fn publish_step(&mut self) {
+        if !self.publish_steps {
+            return;
+        }
+        let (nodes_burned_at, nodes_defended_at) = self.node_data.take_journal();
+        if self.progress.is_snapshot_due(self.global_time) {
+            self.progress.store_snapshot(StateSnapshot::new(
+                self.node_data.burning.values().map(|nd| (nd.node_id, nd.time)),
+                self.node_data.defended.values().map(|nd| (nd.node_id, nd.time))));
+        }
+        self.progress.publish_step(OSMFSimulationStepMetadata {
+            time: self.settings.to_time(self.global_time),
+            time_minutes: self.settings.to_minutes(self.global_time),
+            nodes_burned_by: self.node_data.burning.len(),
+            nodes_defended_by: self.node_data.defended.len(),
+            nodes_burned_at,
+            nodes_defended_at,
+        });
+    }
+
+    /// Simulate the firefighter problem on `graph` with settings `settings` from the fire roots
+    /// `roots` with the node with id `node_id` defended from the start.
+    /// Returns the number of burned nodes.
+    pub(super) fn simulate_counterfactual(graph: &Arc<dyn GraphView>, settings: &OSMFSettings,
+                                          roots: &Vec<usize>, node_id: usize) -> usize {
+        let mut node_data = NodeDataStorage::new();
+        node_data.mark_predefended(&settings.predefended);
+        node_data.mark_burning(roots, 0);
+        node_data.mark_defended(&[node_id], 0);
+        if let Some(probability) = settings.defense_failure_prob {
+            node_data.inject_defense_failures(probability, !settings.seed.unwrap_or(0));
+        }
+
+        let strategy = OSMFStrategy::from_name_and_graph(&settings.strategy_name, graph.clone())
+            .expect("Strategy of an initialized problem should be known");
+        let mut problem = Self {
+            graph: graph.clone(),
+            settings: settings.clone(),
+            strategy,
+            fallback_strategy: None,
+            budget_violations: Vec::new(),
+            strategy_memory: Vec::new(),
+            strategy_report: None,
+            next_wave: 0,
+            node_data,
+            // The fire roots are given, so the seed is only used for ignition waves
+            seed: settings.seed.unwrap_or(0),
+            root_rejections: 0,
+            global_time: 0,
+            progress: Arc::new(SimulationProgress::default()),
+            publish_steps: false,
+            simulation_time_millis: 0,
+            is_active: true,
+            // Counterfactual simulations are never rendered
+            view: View::new(graph.clone(), 1, 1),
+            frame_cache: None,
+            render_queue: None,
+            audit: None,
+            component_mask: settings.component_mask(&**graph),
+            // Counterfactual simulations never write debug files
+            strategy_debug_path: None,
+        };
+        problem.simulate_from_roots(roots);
+
+        problem.node_data.burning.len()
+    }
+
+    /// Estimate the outcome of a firefighter simulation on `graph` with settings `settings` by
+    /// simulating it on the precomputed coarsened graph, which takes milliseconds even on large
+    /// graphs. The fire roots are chosen among the nodes of the coarsened graph, so the estimate
+    /// does not follow the same fire as a full simulation with the same seed.
+    /// The strategy of the settings must be known.
+    pub fn simulate_preview(graph: &Graph, settings: &OSMFSettings) -> Result<OSMFPreviewResponse, OSMFSettingsError> {
+        settings.validate(graph)?;
+        let coarsened = graph.coarsened();
+        let coarse_graph: Arc<dyn GraphView> = coarsened.graph().clone();
+        let strategy = OSMFStrategy::from_name_and_graph(&settings.strategy_name, coarse_graph.clone())
+            .expect("Strategy should be known after validation");
+        let mut problem = Self::new(coarse_graph, settings.for_coarsened(coarsened, graph.num_nodes()), strategy)?;
+        problem.simulate();
+
+        let represented = |node_ids: Vec<usize>| -> usize {
+            node_ids.into_iter().map(|node_id| coarsened.weight(node_id)).sum()
+        };
+        Ok(OSMFPreviewResponse {
+            is_estimate: true,
+            nodes_burned: represented(problem.node_data.get_burning()),
+            // Every firefighter still defends a single node
+            nodes_defended: problem.node_data.defended.len(),
+            nodes_total: represented((0..problem.graph.num_nodes())
+                .filter(|&node_id| problem.is_in_scope(node_id))
+                .collect()),
+            end_time: problem.global_time,
+            end_time_minutes: problem.settings.to_minutes(problem.global_time),
+            units: OSMFUnits::for_settings(&problem.settings),
+            simulation_time_millis: problem.simulation_time_millis,
+            seed: problem.seed,
+            coarsened_nodes: problem.graph.num_nodes(),
+            coarsened_edges: problem.graph.num_edges(),
+        })
+    }
+
+    /// Publish the metadata of every completed round to the simulation progress while simulating,
+    /// such that the simulation can be played back while it is still running
+    pub fn enable_step_publishing(&mut self) {
+        self.publish_steps = true;
+    }
+
+    /// Record the rule evaluation behind every ignition while simulating, such that the
+    /// simulation can be audited and verified against other engine versions
+    pub fn enable_audit(&mut self) {
+        self.audit = Some(Vec::new());
+    }
+
+    /// Get the audit log of the simulation of this firefighter problem instance, if the audit
+    /// mode is enabled
+    pub fn audit_log(&self) -> Option<OSMFAuditLog> {
+        self.audit.as_ref().map(|ignitions| OSMFAuditLog {
+            engine_version: ENGINE_VERSION.to_string(),
+            settings: self.resolved_settings(),
+            ignitions: ignitions.clone(),
+        })
+    }
+
+    /// Generate the simulation response for this firefighter problem instance
+    pub fn simulation_response(&self) -> OSMFSimulationResponse {
+        log::info!("Generating simulation response");
+
+        OSMFSimulationResponse {
+            nodes_burned: self.node_data.burning.len(),
+            nodes_defended: self.node_data.defended.len(),
+            nodes_total: self.num_nodes_in_scope(),
+            burned_area_km2: analysis::estimate_burned_area_km2(&*self.graph, &self.node_data),
+            weight_burned: self.graph.node_weights().map(|weights| weights.total_weight(self.node_data.burning.keys())),
+            weight_defended: self.graph.node_weights().map(|weights| weights.total_weight(self.node_data.defended.keys())),
+            travel_distance_m: analysis::estimate_travel_distance_m(&*self.graph, &self.node_data),
+            end_time: self.global_time,
+            end_time_minutes: self.settings.to_minutes(self.global_time),
+            end: self.settings.to_time(self.global_time),
+            units: OSMFUnits::for_settings(&self.settings),
+            simulation_time_millis: self.simulation_time_millis,
+            seed: self.seed,
+            settings: self.resolved_settings(),
+            root_rejections: self.root_rejections,
+            failed_defenses: self.node_data.num_failed_defenses(),
+            strategy_report: self.strategy_report.clone().unwrap_or_else(|| self.strategy.report()),
+            budget_violations: &self.budget_violations,
+            peak_strategy_bytes: self.strategy_memory.iter().map(|memory| memory.peak_bytes).max(),
+            strategy_memory: &self.strategy_memory,
+            view_bounds: &self.view.grid_bounds,
+            view_center: self.view.initial_center,
+        }
+    }
+
+    /// Get a shared reference to the progress of the simulation of this firefighter problem
+    /// instance, which can be read while the simulation is running
+    pub fn progress(&self) -> Arc<SimulationProgress> {
+        self.progress.clone()
+    }
+
+    /// Get a handle of the simulation of this firefighter problem instance, which allows to view
+    /// its completed rounds while it is running if step publishing is enabled
+    pub fn running_simulation(&self) -> OSMFRunningSimulation {
+        OSMFRunningSimulation {
+            graph: self.graph.clone(),
+            graph_name: self.settings.graph_name.clone(),
+            progress: self.progress.clone(),
+            publishes_steps: self.publish_steps,
+            basemap: self.view.basemap().cloned(),
+            render_queue: self.render_queue.clone(),
+            spread_factors: self.settings.spread_factors.clone(),
+            view: OnceCell::new(),
+        }
+    }
+
+    /// Get the time of the last simulated round of this firefighter problem instance
+    pub fn end_time(&self) -> TimeUnit {
+        self.global_time
+    }
+
+    /// Get the settings of this firefighter problem instance
+    pub fn settings(&self) -> &OSMFSettings {
+        &self.settings
+    }
+
+    /// Get the fully resolved settings of this firefighter problem instance, i.e. its settings
+    /// with the seed, the canonical strategy name and all defaults filled in, such that they
+    /// reproduce the simulation without relying on implicit defaults
+    pub fn resolved_settings(&self) -> OSMFSettings {
+        let mut settings = self.settings.clone();
+        settings.strategy_name = self.strategy.name().to_string();
+        settings.seed = Some(self.seed);
+        settings.max_root_resamples = Some(settings.max_root_resamples.unwrap_or(DEFAULT_MAX_ROOT_RESAMPLES));
+        settings.fire_spread_m_per_min = Some(settings.fire_spread_m_per_min.unwrap_or(DEFAULT_FIRE_SPREAD_M_PER_MIN));
+        settings.excluded_nodes.sort_unstable();
+        settings.excluded_nodes.dedup();
+        settings.predefended.sort_unstable();
+        settings.predefended.dedup();
+        settings
+    }
+
+    /// Compute a hash of the outcome of this simulation, i.e. of the times at which each node
+    /// started burning or was defended
+    pub fn result_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for nd in self.node_data.burning.values() {
+            hasher.update((nd.node_id as u64).to_le_bytes());
+            hasher.update(nd.time.to_le_bytes());
+        }
+        hasher.update(b"|");
+        for nd in self.node_data.defended.values() {
+            hasher.update((nd.node_id as u64).to_le_bytes());
+            hasher.update(nd.time.to_le_bytes());
+        }
+
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Get the ids of all nodes burned in this simulation in ascending order
+    pub fn burned_nodes(&self) -> Vec<usize> {
+        let mut burned = self.node_data.get_burning();
+        burned.sort_unstable();
+        burned
+    }
+
+    /// Pre-render all frames of this simulation at zoom `zoom` with nodes drawn with radius
+    /// `node_radius` in the background.
+    /// Frames are rendered around the initial view center and served by the view responses
+    /// with the same options as soon as they are available.
+    pub fn start_frame_pregen(&mut self, zoom: f64, node_radius: NodeRadius) {
+        let options = ViewOptions {
+            node_radius,
+            ..ViewOptions::default()
+        };
+        let cache = Arc::new(FrameCache::new(self.view.initial_center, zoom, options, self.global_time));
+        playback::spawn_pregen(&cache, self.graph.clone(), self.node_data.clone(),
+                               VIEW_WIDTH, VIEW_HEIGHT, self.render_queue.clone());
+        self.frame_cache = Some(cache);
+    }
+
+    /// Evict the caches and intermediate data of this finished firefighter problem instance that
+    /// later requests do not depend on, i.e. frames pre-generated completely and the published
+    /// metadata of all rounds. Views are rendered on demand again after frames were evicted.
+    /// Returns the approximate number of reclaimed bytes.
+    pub fn evict_caches(&mut self) -> usize {
+        if !self.progress.is_finished() {
+            return 0;
+        }
+
+        let mut reclaimed = self.progress.clear_steps();
+        if let Some(ref cache) = self.frame_cache {
+            if cache.status().is_finished {
+                reclaimed += cache.size_bytes();
+                self.frame_cache = None;
+            }
+        }
+        if reclaimed > 0 {
+            log::debug!("Evicted {} bytes of cached simulation data", reclaimed);
+        }
+        reclaimed
+    }
+
+    /// Generate the frame pre-generation status response for this firefighter problem instance.
+    /// Returns `None` if no frame pre-generation has been started.
+    pub fn pregen_status_response(&self) -> Option<OSMFPregenStatus> {
+        self.frame_cache.as_ref().map(|cache| cache.status())
+    }
+
+    /// Generate the view response for this firefighter problem instance
+    pub fn view_response(&self, center: Coords, zoom: f64, time: &TimeUnit, options: &ViewOptions) -> Vec<u8> {
+        log::info!("Generating view response. center={:?}, zoom={}, time={}, options={:?}.",
+            center, zoom, time, options);
+
+        if let Some(png) = self.frame_cache.as_ref()
+            .and_then(|cache| cache.get(center, zoom, time, options)) {
+            log::debug!("Serving pre-generated frame for time {}", time);
+            return png;
+        }
+
+        let _permit = self.acquire_interactive_render();
+        let view = compute::with_thread_limit(self.settings.threads,
+            || self.view.compute(center, zoom, time, &self.node_data, options));
+        self.view.png_bytes(&view)
+    }
+
+    /// Generate the alternative view response for this firefighter problem instance
+    pub fn view_response_alt(&self, zoom: f64, time: &TimeUnit, options: &ViewOptions) -> Vec<u8> {
+        log::info!("Generating view response. zoom={}, time={}, options={:?}.", zoom, time, options);
+
+        let center = self.view.initial_center;
+        if let Some(png) = self.frame_cache.as_ref()
+            .and_then(|cache| cache.get(center, zoom, time, options)) {
+            log::debug!("Serving pre-generated frame for time {}", time);
+            return png;
+        }
+
+        let _permit = self.acquire_interactive_render();
+        let view = compute::with_thread_limit(self.settings.threads,
+            || self.view.compute_alt(zoom, time, &self.node_data, options));
+        self.view.png_bytes(&view)
+    }
+
+    /// Attach the basemap tiles `basemap` to the view of this firefighter problem instance
+    pub fn set_basemap(&mut self, basemap: Arc<TileCache>) {
+        self.view.set_basemap(basemap);
+    }
+
+    /// Schedule all renders of this firefighter problem instance through `render_queue` as jobs
+    /// of `owner`, i.e. interactive views and the frame pre-generation
+    pub fn set_render_queue(&mut self, render_queue: Arc<RenderQueue>, owner: &str) {
+        self.render_queue = Some((render_queue, owner.to_string()));
+    }
+
+    /// Wait until an interactive render may run, if renders are scheduled
+    fn acquire_interactive_render(&self) -> Option<RenderPermit<'_>> {
+        self.render_queue.as_ref()
+            .map(|(queue, owner)| queue.acquire(RenderPriority::Interactive, owner))
+    }
+
+    /// Compute the path along which the fire reached the node with id `node_id`, starting at
+    /// a fire root. Returns an empty path if the node is not burning.
+    fn trace_fire_path(&self, node_id: usize) -> Vec<OSMFFirePathHop> {
+        let mut path = Vec::new();
+        let mut cur = Some(node_id);
+        while let Some(node_id) = cur {
+            match self.node_data.get_burning_time(&node_id) {
+                Some(burned_at) => path.push(OSMFFirePathHop {
+                    node_id,
+                    burned_at,
+                }),
+                None => break
+            }
+            cur = self.node_data.get_burn_parent(&node_id);
+        }
+        path.reverse();
+
+        path
+    }
+
+    /// Generate the timeline response for the node with id `node_id` of this firefighter problem
+    /// instance.
+    /// Returns `None` if there is no node with id `node_id`.
+    pub fn node_timeline_response(&self, node_id: usize) -> Option<OSMFNodeTimeline> {
+        if node_id >= self.graph.num_nodes() {
+            return None;
+        }
+
+        log::info!("Generating node timeline response. node_id={}.", node_id);
+
+        // Find the fire root that would reach the node first if there were no firefighters
+        let (dists, preds) = self.graph.run_dijkstra_tree(&self.node_data.get_roots());
+        let (nearest_root, nearest_root_dist) = if dists[node_id] < usize::MAX {
+            let mut root = node_id;
+            while preds[root] < usize::MAX {
+                root = preds[root];
+            }
+            (Some(root), Some(dists[node_id]))
+        } else {
+            (None, None)
+        };
+
+        let burned_at = self.node_data.get_burning_time(&node_id);
+        let defended_at = self.node_data.get_defended_time(&node_id);
+        Some(OSMFNodeTimeline {
+            node_id,
+            burned_at,
+            defended_at,
+            burned_at_minutes: burned_at.map(|time| self.settings.to_minutes(time)),
+            defended_at_minutes: defended_at.map(|time| self.settings.to_minutes(time)),
+            burned_at_timestamp: burned_at.and_then(|time| self.settings.to_timestamp(time)),
+            defended_at_timestamp: defended_at.and_then(|time| self.settings.to_timestamp(time)),
+            nearest_root,
+            nearest_root_dist,
+            fire_path: self.trace_fire_path(node_id),
+        })
+    }
+
+    /// Generate the burn forest response for this firefighter problem instance, i.e. for every
+    /// burning node the neighbour that ignited it
+    pub fn burn_forest_response(&self) -> Vec<OSMFBurnForestEntry> {
+        log::info!("Generating burn forest response");
+
+        self.node_data.get_burning_node_data().into_iter()
+            .map(|nd| OSMFBurnForestEntry {
+                node_id: nd.node_id,
+                parent: self.node_data.get_burn_parent(&nd.node_id),
+                burned_at: nd.time,
+            })
+            .collect()
+    }
+
+    /// Get the metadata of the graph of this firefighter problem instance, e.g. its license
+    pub fn graph_metadata(&self) -> &GraphMetadata {
+        self.graph.metadata()
+    }
+
+    /// Get the plan of the defenses of this firefighter problem instance, i.e. the nodes the
+    /// strategy defended in each round. Pre-defended nodes are part of the settings instead.
+    pub fn defense_plan(&self) -> OSMFDefensePlan {
+        let mut rounds: BTreeMap<TimeUnit, Vec<usize>> = BTreeMap::new();
+        for nd in self.node_data.defended.values().filter(|nd| nd.time > 0) {
+            rounds.entry(nd.time).or_default().push(nd.node_id);
+        }
+
+        OSMFDefensePlan {
+            graph_name: self.settings.graph_name.clone(),
+            rounds: rounds.into_iter()
+                .map(|(round, nodes)| OSMFPlanRound { round, nodes })
+                .collect(),
+        }
+    }
+
+    /// Generate the node state response for this firefighter problem instance, i.e. the state of
+    /// every graph node at the end of the simulation, including its smoke exposure within the
+    /// distance `exposure_radius_m` in meters of burning nodes
+    pub fn node_states_response(&self, exposure_radius_m: f64) -> Vec<OSMFNodeState> {
+        log::info!("Generating node state response. exposure_radius_m={}.", exposure_radius_m);
+
+        let burn_roots = analysis::compute_burn_roots(&self.node_data);
+        let exposure = compute::with_thread_limit(self.settings.threads,
+            || analysis::compute_exposure(&*self.graph, &self.node_data, exposure_radius_m, self.global_time));
+        let weights = self.graph.node_weights();
+        self.graph.iter_nodes()
+            .map(|node| {
+                let burn_time = self.node_data.get_burning_time(&node.id());
+                let defend_time = self.node_data.get_defended_time(&node.id());
+                OSMFNodeState {
+                    node_id: node.id(),
+                    lat: node.lat,
+                    lon: node.lon,
+                    burn_time,
+                    defend_time,
+                    burn_time_minutes: burn_time.map(|time| self.settings.to_minutes(time)),
+                    defend_time_minutes: defend_time.map(|time| self.settings.to_minutes(time)),
+                    burn_timestamp: burn_time.and_then(|time| self.settings.to_timestamp(time)),
+                    defend_timestamp: defend_time.and_then(|time| self.settings.to_timestamp(time)),
+                    root_id: burn_roots.get(&node.id()).copied(),
+                    exposure: exposure[node.id()],
+                    weight: weights.map(|weights| weights.weight(node.id())),
+                    class: weights.and_then(|weights| weights.class(node.id())).map(str::to_string),
+                }
+            })
+            .collect()
+    }
+
+    /// Generate the response with the `k` dominant spread corridors of this firefighter problem
+    /// instance
+    pub fn corridors_response(&self, k: usize) -> Vec<OSMFCorridor> {
+        log::info!("Generating spread corridors response. k={}.", k);
+
+        analysis::compute_corridors(&self.node_data, k)
+    }
+
+    /// Generate the response classifying each defended node of this firefighter problem instance
+    /// as effective or wasted
+    pub fn defense_efficiency_response(&self) -> OSMFDefenseEfficiency {
+        log::info!("Generating defense efficiency response");
+
+        analysis::classify_defended(&*self.graph, &self.node_data)
+    }
+
+    /// Get the number of nodes burned and defended by every round of the simulation of this
+    /// firefighter problem instance, indexed by round
+    pub fn cumulative_counts(&self) -> Vec<(usize, usize)> {
+        let num_rounds = self.global_time as usize + 1;
+        let mut counts = vec![(0, 0); num_rounds];
+        for nd in self.node_data.burning.values() {
+            counts[nd.time as usize].0 += 1;
+        }
+        for nd in self.node_data.defended.values() {
+            counts[nd.time as usize].1 += 1;
+        }
+        for i in 1..num_rounds {
+            counts[i].0 += counts[i - 1].0;
+            counts[i].1 += counts[i - 1].1;
+        }
+        counts
+    }
+
+    /// Generate the summary statistics response for this firefighter problem instance, including
+    /// the number of safe nodes shielded per defended node
+    pub fn sim_stats_response(&self) -> OSMFSimulationStats {
+        log::info!("Generating simulation statistics response");
+
+        let savings = analysis::compute_defense_savings(&*self.graph, &self.node_data);
+        let total_savings: f64 = savings.values().sum();
+        let nodes_defended = self.node_data.defended.len();
+        let avg_savings_per_defense = if nodes_defended > 0 {
+            total_savings / nodes_defended as f64
+        } else {
+            0.0
+        };
+
+        OSMFSimulationStats {
+            end: self.settings.to_time(self.global_time),
+            nodes_burned: self.node_data.burning.len(),
+            nodes_defended,
+            nodes_total: self.num_nodes_in_scope(),
+            nodes_effective: savings.len(),
+            total_savings,
+            avg_savings_per_defense,
+        }
+    }
+
+    /// Prepare a counterfactual node importance analysis of this firefighter problem instance.
+    /// The analysis re-simulates the problem with each of the `k` most promising nodes of the
+    /// first `num_rounds` rounds defended from the start, using at most `budget` re-simulations.
+    pub fn node_importance_analysis(&self, k: usize, num_rounds: usize, budget: usize)
+        -> NodeImportanceAnalysis {
+        log::info!("Preparing node importance analysis. k={}, rounds={}, budget={}.",
+            k, num_rounds, budget);
+
+        // Re-simulations have to ignite the same random ignition waves
+        let mut settings = self.settings.clone();
+        settings.seed = Some(self.seed);
+        NodeImportanceAnalysis::new(self.graph.clone(), settings, &self.node_data,
+                                    k, num_rounds, budget)
+    }
+
+    /// Generate the response with the colors of the burn trees of all fire roots by time `time`
+    /// in color-by-root views
+    pub fn root_palette_response(&self, time: &TimeUnit) -> Vec<OSMFRootColor> {
+        OSMFRootColor::for_roots(&*self.graph, &self.node_data, time)
+    }
+
+    /// Generate the nearest-root partition response for this firefighter problem instance, i.e.
+    /// the fire root that would reach each node first without firefighters and the Voronoi
+    /// regions of all fire roots with their number of burned and defended nodes
+    pub fn voronoi_response(&self) -> OSMFVoronoi {
+        log::info!("Generating Voronoi response");
+
+        let labels = analysis::compute_nearest_roots(&*self.graph, &self.node_data, self.global_time,
+                                                     &self.settings.spread_factors);
+        let mut regions: Vec<_> = OSMFRootColor::for_roots(&*self.graph, &self.node_data, &self.global_time)
+            .into_iter()
+            .map(|root| OSMFVoronoiRegion { root, num_nodes: 0, num_burned: 0, num_defended: 0 })
+            .collect();
+        let region_of: BTreeMap<_, _> = regions.iter().enumerate()
+            .map(|(i, region)| (region.root.root, i))
+            .collect();
+        for (node_id, root) in labels.iter().enumerate() {
+            if let Some(root) = root {
+                let region = &mut regions[region_of[root]];
+                region.num_nodes += 1;
+                region.num_burned += self.node_data.is_burning(&node_id) as usize;
+                region.num_defended += self.node_data.is_defended(&node_id) as usize;
+            }
+        }
+
+        OSMFVoronoi { regions, labels }
+    }
+
+    pub fn sim_step_metadata_response(&self, time: &TimeUnit) -> OSMFSimulationStepMetadata {
+        log::info!("Generating simulation step metadata response. time={}.", time);
+
+        OSMFSimulationStepMetadata {
+            time: self.settings.to_time(*time),
+            time_minutes: self.settings.to_minutes(*time),
+            nodes_burned_by: self.node_data.count_burning_by(time),
+            nodes_defended_by: self.node_data.count_defended_by(time),
+            nodes_burned_at: self.node_data.get_burning_at(time),
+            nodes_defended_at: self.node_data.get_defended_at(time),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use std::ffi::c_void;
+    use std::os::raw::c_char;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use geo::{ChamberlainDuquetteArea, ConvexHull, HaversineDistance, MultiPoint, Point};
+    use once_cell::sync::Lazy;
+
+    use crate::alloc_stats;
+    use crate::compute::{self, JobProgress};
+    use crate::firefighter::analysis;
+    use crate::firefighter::audit::{OSMFAuditLog, OSMFIgnitionCause};
+    use crate::firefighter::plugin::{self, NODE_BURNING, NODE_UNTOUCHED, OSMFPluginError, OSMFPluginGraph,
+                                     OSMFPluginRound, OSMFStrategyPlugin, OSMFStrategyVTable, PLUGIN_API_VERSION};
+    use crate::firefighter::{problem::{DEFAULT_FIRE_SPREAD_M_PER_MIN, DEFAULT_MAX_ROOT_RESAMPLES, NodeData,
+                                       NodeDataStorage, NodeMap, OSMFIgnition, OSMFProblem, OSMFSettings,
+                                       OSMFSettingsDefaults, OSMFSettingsError, OSMFTime, SNAPSHOT_EVERY},
+                             strategy::{OSMFStrategy,
+                                        GreedyStrategy,
+                                        MultiMinDistSetsStrategy,
+                                        KServerStrategy,
+                                        OSMFEnsembleMode,
+                                        RandomStrategy,
+                                        PriorityStrategy,
+                                        SingleMinDistSetStrategy}};
+    use crate::firefighter::strategy::ScoreStrategy;
+    use crate::firefighter::TimeUnit;
+    use crate::firefighter::view::ViewOptions;
+    use crate::graph::{weights, Graph, GraphView};
+    use crate::graph_components::Components;
+
+    struct TestData {
+        graph: Arc<dyn GraphView>,
+        settings: OSMFSettings,
+    }
+
+    static TEST_DATA: Lazy<TestData> = Lazy::new(||
+        TestData {
+            graph: Arc::new(Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap()),
+            settings: OSMFSettings {
+                graph_name: "bbgrund".to_string(),
+                strategy_name:"Greedy".to_string(),
+                num_roots: 10,
+                num_ffs: 2,
+                strategy_every: 10,
+                pregen_zoom: None,
+                seed: None,
+                strategy_budget_millis: None,
+                ignition_waves: vec![],
+                excluded_nodes: vec![],
+                excluded_areas: vec![],
+                predefended: vec![],
+                defense_plan: None,
+                ensemble_strategies: vec![],
+                ensemble_mode: None,
+                min_root_dist: 0,
+                min_burn_potential: 0,
+                max_root_resamples: None,
+                defense_failure_prob: None,
+                fire_spread_m_per_min: None,
+                start_timestamp: None,
+                spread_factors: BTreeMap::new(),
+                threads: None,
+                largest_component_only: false,
+            },
+        });
+
+    fn initialize(strategy: OSMFStrategy) -> OSMFProblem {
+        OSMFProblem::new(TEST_DATA.graph.clone(), TEST_DATA.settings.clone(), strategy).unwrap()
+    }
+
+    fn initialize_with(settings: OSMFSettings) -> OSMFProblem {
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).unwrap()
+    }
+
+    #[test]
+    fn test_roots() {
+        let mut problem = initialize(OSMFStrategy::Random(
+            RandomStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+
+        let num_roots = problem.node_data.get_roots().len();
+        let settings = &TEST_DATA.settings;
+        assert_eq!(num_roots, settings.num_roots, "num burning at 0: {}, num roots: {}",
+                   num_roots, settings.num_roots);
+    }
+
+    #[test]
+    fn test_strategy_report() {
+        let mut problem = initialize(OSMFStrategy::Greedy(
+            GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+        let report = problem.simulation_response().strategy_report;
+        assert!(report["num_executions"].as_u64().unwrap() > 0);
+
+        let mut problem = initialize(OSMFStrategy::Priority(
+            PriorityStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+        let report = problem.simulation_response().strategy_report;
+        assert!(report["q25_threshold"].is_u64());
+        assert!(report["num_replans"].is_u64());
+
+        let mut problem = initialize(OSMFStrategy::Random(
+            RandomStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+        assert!(problem.simulation_response().strategy_report.is_null());
+    }
+
+    #[test]
+    fn test_evict_caches() {
+        let mut problem = initialize(OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.enable_step_publishing();
+        problem.simulate();
+        let response = serde_json::to_value(problem.simulation_response()).unwrap();
+        let center = problem.view.initial_center;
+        let view = problem.view_response(center, 1.0, &problem.end_time(), &ViewOptions::default());
+        assert!(problem.progress.num_published_steps() > 0);
+
+        assert!(problem.evict_caches() > 0);
+        assert_eq!(problem.progress.num_published_steps(), 0);
+        assert_eq!(problem.evict_caches(), 0);
+
+        // Evicting caches does not change any response
+        assert_eq!(serde_json::to_value(problem.simulation_response()).unwrap(), response);
+        assert_eq!(problem.view_response(center, 1.0, &problem.end_time(), &ViewOptions::default()), view);
+        assert!(problem.sim_step_metadata_response(&1).nodes_burned_by > 0);
+    }
+
+    #[test]
+    fn test_compaction() {
+        let mut problem = initialize(OSMFStrategy::Priority(
+            PriorityStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+        assert!(matches!(problem.node_data.burning, NodeMap::Compact(_)));
+        assert!(matches!(problem.node_data.burn_parents, NodeMap::Compact(_)));
+        assert!(problem.simulation_response().strategy_report["final_queue_length"].is_u64());
+
+        // Compacted node data answers the same queries as the node data while simulating
+        let node_data = problem.node_data.clone();
+        let mut expanded = node_data.clone();
+        expanded.defended.tree_mut();
+        expanded.burning.insert(usize::MAX, NodeData { node_id: usize::MAX, time: 0 });
+        assert!(matches!(expanded.burning, NodeMap::Tree(_)));
+        assert!(matches!(expanded.defended, NodeMap::Tree(_)));
+        for node in TEST_DATA.graph.iter_nodes() {
+            assert_eq!(node_data.get_burning_time(&node.id()), expanded.get_burning_time(&node.id()));
+            assert_eq!(node_data.get_defended_time(&node.id()), expanded.get_defended_time(&node.id()));
+            assert_eq!(node_data.get_burn_parent(&node.id()), expanded.get_burn_parent(&node.id()));
+        }
+        assert_eq!(node_data.get_burning().len() + 1, expanded.get_burning().len());
+        assert_eq!(serde_json::to_value(&node_data.defended).unwrap(),
+                   serde_json::to_value(&expanded.defended).unwrap());
+    }
+
+    #[test]
+    fn test_round_skipping() {
+        for strategy_every in [1, 7, 25] {
+            let settings = OSMFSettings {
+                strategy_every,
+                seed: Some(42),
+                ignition_waves: vec![(60, OSMFIgnition::Count(2))],
+                ..TEST_DATA.settings.clone()
+            };
+            let mut problem = initialize_with(settings.clone());
+            problem.enable_step_publishing();
+            problem.simulate();
+
+            // Simulating every single round yields the same simulation
+            let mut naive = initialize_with(settings);
+            naive.node_data.mark_predefended(&naive.settings.predefended.clone());
+            let roots = naive.gen_fire_roots();
+            naive.strategy.initialize(&roots, &naive.settings, &naive.node_data);
+            while naive.is_active || naive.next_wave < naive.settings.ignition_waves.len() {
+                naive.exec_step();
+            }
+            assert_eq!(problem.end_time(), naive.end_time());
+            for node in TEST_DATA.graph.iter_nodes() {
+                assert_eq!(problem.node_data.get_burning_time(&node.id()), naive.node_data.get_burning_time(&node.id()));
+                assert_eq!(problem.node_data.get_defended_time(&node.id()), naive.node_data.get_defended_time(&node.id()));
+            }
+
+            // Skipped rounds are published as empty rounds
+            assert_eq!(problem.progress.num_published_steps() as TimeUnit, problem.end_time() + 1);
+        }
+    }
+
+    #[test]
+    fn test_timeline_index() {
+        let mut problem = initialize(OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+        assert!(problem.node_data.timeline.is_some());
+
+        // The index answers the same queries as scanning the node data
+        let indexed = &problem.node_data;
+        let mut scanned = indexed.clone();
+        scanned.timeline = None;
+        for time in 0..=problem.end_time() + 2 {
+            assert_eq!(indexed.count_burning_by(&time),
+                       indexed.burning.values().filter(|nd| nd.time <= time).count());
+            assert_eq!(indexed.count_defended_by(&time),
+                       indexed.defended.values().filter(|nd| nd.time <= time).count());
+            assert_eq!(indexed.get_burning_at(&time), scanned.get_burning_at(&time));
+            assert_eq!(indexed.get_defended_at(&time), scanned.get_defended_at(&time));
+            let num_burning = TEST_DATA.graph.iter_nodes()
+                .filter(|node| indexed.is_burning_by(&node.id(), &time))
+                .count();
+            assert_eq!(num_burning, problem.sim_step_metadata_response(&time).nodes_burned_by);
+            for node in TEST_DATA.graph.iter_nodes() {
+                assert_eq!(indexed.is_defended_by(&node.id(), &time), scanned.is_defended_by(&node.id(), &time));
+            }
+        }
+
+        // Modifications drop the index
+        let mut modified = problem.node_data.clone();
+        let node_id = TEST_DATA.graph.iter_nodes()
+            .find(|node| modified.is_undefended(&node.id()))
+            .unwrap().id();
+        modified.mark_burning(&vec![node_id], 1);
+        assert!(modified.timeline.is_none());
+        assert_eq!(modified.count_burning_by(&1), scanned.count_burning_by(&1) + 1);
+
+        // Counts follow nodes that are marked again at another time
+        modified.mark_burning(&vec![node_id], 3);
+        assert_eq!(modified.count_burning_by(&1), scanned.count_burning_by(&1));
+        assert_eq!(modified.count_burning_by(&3), scanned.count_burning_by(&3) + 1);
+        assert_eq!(modified.count_burning_by(&TimeUnit::MAX), indexed.burning.len() + 1);
+    }
+
+    #[test]
+    fn test_active() {
+        let mut problem = initialize(OSMFStrategy::Random(
+            RandomStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+
+        assert!(!problem.is_active);
+    }
+
+    #[test]
+    fn test_burned() {
+        let mut problem = initialize(OSMFStrategy::Random(
+            RandomStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+
+        let burned_times: Vec<_> = problem.node_data.burning.values()
+            .map(|nd| nd.time)
+            .collect();
+        for time in burned_times {
+            assert!(time <= problem.global_time, "burned time: {}, global time: {}",
+                    time, problem.global_time);
+        }
+    }
+
+    #[test]
+    fn test_fire_path() {
+        let mut problem = initialize(OSMFStrategy::Greedy(
+            GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+
+        let roots = problem.node_data.get_roots();
+        for node_id in problem.node_data.get_burning() {
+            let timeline = problem.node_timeline_response(node_id).unwrap();
+            let path = &timeline.fire_path;
+            assert_eq!(path.last().unwrap().node_id, node_id);
+            assert!(roots.contains(&path[0].node_id));
+            assert_eq!(path[0].burned_at, 0);
+            for hops in path.windows(2) {
+                assert!(hops[0].burned_at < hops[1].burned_at);
+            }
+            assert!(timeline.nearest_root_dist.unwrap() as TimeUnit <= timeline.burned_at.unwrap());
+        }
+        assert!(problem.node_timeline_response(TEST_DATA.graph.num_nodes()).is_none());
+    }
+
+    #[test]
+    fn test_burn_forest() {
+        let mut problem = initialize(OSMFStrategy::Greedy(
+            GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+
+        let forest = problem.burn_forest_response();
+        assert_eq!(forest.len(), problem.node_data.burning.len());
+        for entry in forest {
+            match entry.parent {
+                Some(parent) => {
+                    let parent_time = problem.node_data.get_burning_time(&parent).unwrap();
+                    let edge_dist = TEST_DATA.graph.get_outgoing_edges(parent).iter()
+                        .filter(|e| e.tgt() == entry.node_id)
+                        .map(|e| e.dist())
+                        .min()
+                        .unwrap();
+                    assert!(parent_time + edge_dist as TimeUnit <= entry.burned_at);
+                }
+                None => assert!(problem.node_data.is_root(&entry.node_id))
+            }
+        }
+    }
+
+    #[test]
+    fn test_defense_efficiency() {
+        // Greedy only defends neighbours of burning nodes, hence all of its defenses are effective
+        let mut problem = initialize(OSMFStrategy::Greedy(
+            GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+        let efficiency = problem.defense_efficiency_response();
+        assert_eq!(efficiency.num_effective(), problem.node_data.defended.len());
+        assert_eq!(efficiency.num_wasted(), 0);
+
+        let mut problem = initialize(OSMFStrategy::Random(
+            RandomStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+        let efficiency = problem.defense_efficiency_response();
+        assert_eq!(efficiency.num_effective() + efficiency.num_wasted(),
+                   problem.node_data.defended.len());
+    }
+
+    #[test]
+    fn test_sim_stats() {
+        let mut problem = initialize(OSMFStrategy::Greedy(
+            GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+
+        let stats = problem.sim_stats_response();
+        let num_safe = problem.graph.num_nodes() - problem.node_data.burning.len()
+            - problem.node_data.defended.len();
+        assert_eq!(stats.nodes_effective, problem.node_data.defended.len());
+        assert!(stats.total_savings >= 0.0 && stats.total_savings <= num_safe as f64 + 1e-9);
+        assert!((stats.avg_savings_per_defense * stats.nodes_defended as f64
+            - stats.total_savings).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_strategy_budget() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.strategy_name = "Priority".to_string();
+        settings.strategy_budget_millis = Some(0);
+        let strategy = OSMFStrategy::Priority(PriorityStrategy::new(TEST_DATA.graph.clone()));
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).unwrap();
+        problem.simulate();
+
+        // Every execution exceeds a zero budget, but the fallback only takes over once
+        let violations = &problem.budget_violations;
+        assert!(!violations.is_empty());
+        assert_eq!(violations[0].strategy_name, "Priority");
+        assert!(violations[1..].iter().all(|v| v.strategy_name == "Greedy"));
+        assert!(problem.fallback_strategy.is_some());
+        // The strategy did not replan once it exceeded the budget
+        assert_eq!(problem.strategy.report()["num_replans"], 0);
+
+        // The Score strategy stops before scoring any node, and the fallback places all
+        // firefighters in the round that exceeded the budget
+        let mut settings = TEST_DATA.settings.clone();
+        settings.strategy_name = "Score".to_string();
+        settings.strategy_budget_millis = Some(0);
+        let strategy = OSMFStrategy::Score(ScoreStrategy::new(TEST_DATA.graph.clone()));
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).unwrap();
+        problem.simulate();
+        let violation = &problem.budget_violations[0];
+        assert_eq!(violation.strategy_name, "Score");
+        assert_eq!(problem.strategy.report()["num_executions"], 0);
+        assert_eq!(problem.node_data.get_defended_at(&violation.round).len(), problem.settings.num_ffs);
+
+        let mut problem = initialize(OSMFStrategy::Priority(
+            PriorityStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+        assert!(problem.budget_violations.is_empty());
+    }
+
+    #[test]
+    fn test_thread_limit() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.strategy_name = "MultiMinDistanceSets".to_string();
+        settings.seed = Some(7);
+        let simulate = |settings: &OSMFSettings| {
+            let strategy = OSMFStrategy::from_name_and_graph(&settings.strategy_name, TEST_DATA.graph.clone()).unwrap();
+            let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings.clone(), strategy).unwrap();
+            problem.simulate();
+            let _ = problem.node_states_response(500.0);
+            problem.result_hash()
+        };
+
+        // Limiting the threads does not change the outcome
+        let unlimited = simulate(&settings);
+        settings.threads = Some(1);
+        assert_eq!(simulate(&settings), unlimited);
+
+        for threads in [0, compute::max_thread_limit() + 1] {
+            settings.threads = Some(threads);
+            assert!(matches!(settings.validate(&*TEST_DATA.graph), Err(OSMFSettingsError::InvalidThreads { .. })));
+        }
+    }
+
+    #[test]
+    fn test_strategy_memory() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.strategy_name = "MultiMinDistanceSets".to_string();
+        let strategy = OSMFStrategy::from_name_and_graph(&settings.strategy_name, TEST_DATA.graph.clone()).unwrap();
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).unwrap();
+        problem.simulate();
+
+        let response = problem.simulation_response();
+        if alloc_stats::is_enabled() {
+            assert!(!problem.strategy_memory.is_empty());
+            assert!(problem.strategy_memory.iter()
+                .all(|memory| memory.round % problem.settings.strategy_every == 0));
+            assert_eq!(response.peak_strategy_bytes,
+                       problem.strategy_memory.iter().map(|memory| memory.peak_bytes).max());
+        } else {
+            assert!(problem.strategy_memory.is_empty());
+            assert_eq!(response.peak_strategy_bytes, None);
+        }
+    }
+
+    #[test]
+    fn test_settings_defaults() {
+        let defaults = OSMFSettingsDefaults::for_graph(&*TEST_DATA.graph);
+        assert!(defaults.num_roots >= 1 && defaults.num_roots <= TEST_DATA.graph.num_nodes());
+        assert!(defaults.num_ffs >= 1);
+        assert!(defaults.strategy_every >= 1);
+
+        let mut settings = TEST_DATA.settings.clone();
+        settings.num_roots = defaults.num_roots;
+        settings.num_ffs = defaults.num_ffs;
+        settings.strategy_every = defaults.strategy_every;
+        assert!(settings.validate(&*TEST_DATA.graph).is_ok());
+    }
+
+    #[test]
+    fn test_burned_area() {
+        let mut problem = initialize(OSMFStrategy::Greedy(
+            GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+        let area = problem.simulation_response().burned_area_km2;
+
+        // No burn tree can cover more than the convex hull of the whole graph
+        let points: Vec<_> = TEST_DATA.graph.iter_nodes()
+            .map(|node| Point::new(node.lon, node.lat))
+            .collect();
+        let graph_area = MultiPoint::from(points).convex_hull().chamberlain_duquette_unsigned_area()
+            / 1_000_000.0;
+        assert!(area > 0.0);
+        assert!(area <= graph_area * TEST_DATA.settings.num_roots as f64);
+    }
+
+    #[test]
+    fn test_exposure() {
+        let graph = &TEST_DATA.graph;
+        let mut problem = initialize_with(TEST_DATA.settings.clone());
+        problem.simulate();
+        let node_data = &problem.node_data;
+        let end_time = problem.end_time();
+
+        // The grid of burning nodes finds the same nodes as comparing all pairs of nodes
+        for &(radius_m, time) in &[(50.0, end_time), (400.0, end_time), (400.0, end_time / 2)] {
+            let exposure = analysis::compute_exposure(&**graph, node_data, radius_m, time);
+            for node in graph.iter_nodes() {
+                let p = Point::new(node.lon, node.lat);
+                let expected: f64 = node_data.get_burning().into_iter()
+                    .filter_map(|node_id| {
+                        let burn_time = node_data.get_burning_time(&node_id).filter(|&t| t <= time)?;
+                        let other = graph.get_node(node_id);
+                        let dist = p.haversine_distance(&Point::new(other.lon, other.lat));
+                        (dist < radius_m).then(|| (time - burn_time + 1) as f64 * (1.0 - dist / radius_m))
+                    })
+                    .sum();
+                assert!((exposure[node.id()] - expected).abs() < 1e-9);
+            }
+        }
+
+        // Roots are exposed to their own smoke from the first round on
+        let exposure = analysis::compute_exposure(&**graph, node_data, 400.0, 0);
+        let roots: Vec<_> = node_data.get_burning().into_iter()
+            .filter(|node_id| node_data.get_burning_time(node_id) == Some(0))
+            .collect();
+        assert!(roots.iter().all(|&root| exposure[root] >= 1.0));
+        assert!(analysis::compute_exposure(&**graph, node_data, 0.0, end_time).iter().all(|&e| e == 0.0));
+    }
+
+    #[test]
+    fn test_danger_zone() {
+        let graph = &TEST_DATA.graph;
+        let mut problem = initialize_with(TEST_DATA.settings.clone());
+        problem.simulate();
+        let node_data = &problem.node_data;
+        let end_time = problem.end_time();
+
+        // Without further defenses, the fire reaches every node at the latest when it burned
+        for &(time, rounds) in &[(0, end_time), (end_time / 2, 10), (end_time / 2, end_time)] {
+            let danger = analysis::compute_danger_zone(&**graph, node_data, time, rounds, &BTreeMap::new());
+            for (node_id, &danger) in danger.iter().enumerate() {
+                if let Some(r) = danger {
+                    assert!(r >= 1 && r <= rounds);
+                    assert!(!node_data.is_burning_by(&node_id, &time) && !node_data.is_defended_by(&node_id, &time));
+                }
+                if let Some(burn_time) = node_data.get_burning_time(&node_id).filter(|&t| t > time && t - time <= rounds) {
+                    assert!(matches!(danger, Some(r) if r <= burn_time - time));
+                }
+            }
+        }
+
+        // Without any defenses, the danger zone of the roots are the nodes within the forecast
+        // distance
+        let roots = node_data.get_roots();
+        let dists = graph.run_dijkstra(&roots);
+        let mut unimpeded = NodeDataStorage::new();
+        unimpeded.mark_burning(&roots, 0);
+        let danger = analysis::compute_danger_zone(&**graph, &unimpeded, 0, 20, &BTreeMap::new());
+        assert!(danger.iter().any(Option::is_some));
+        for node_id in 0..graph.num_nodes() {
+            let expected = (dists[node_id] > 0 && dists[node_id] <= 20).then(|| dists[node_id] as TimeUnit);
+            assert_eq!(danger[node_id], expected);
+        }
+    }
+
+    #[test]
+    fn test_node_weights() {
+        // Weight every node of the test graph by its id modulo 3, matched by its coordinates
+        let graph = Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+        let mut csv = String::from("lat,lon,weight\n");
+        for node in graph.nodes() {
+            csv.push_str(&format!("{},{},{}\n", node.lat, node.lon, node.id() % 3));
         }
+        let path = std::env::temp_dir().join(format!("osmff_problem_{}.weights.csv", std::process::id()));
+        std::fs::write(&path, csv).unwrap();
+        graph.set_node_weights(weights::read_sidecar(&graph, &path).unwrap());
+        let graph: Arc<dyn GraphView> = Arc::new(graph);
+        let weights = graph.node_weights().unwrap();
+        assert_eq!(weights.summary().num_unmatched, 0);
+
+        let mut settings = TEST_DATA.settings.clone();
+        settings.strategy_name = "Score".to_string();
+        let strategy = OSMFStrategy::Score(ScoreStrategy::new(graph.clone()));
+        let mut problem = OSMFProblem::new(graph.clone(), settings, strategy).unwrap();
+        problem.simulate();
+        let response = problem.simulation_response();
+        let node_data = &problem.node_data;
+        assert_eq!(response.weight_burned, Some(weights.total_weight(node_data.burning.keys())));
+        assert_eq!(response.weight_defended, Some(weights.total_weight(node_data.defended.keys())));
+        assert!(response.weight_burned.unwrap() > 0.0);
+        assert!(problem.node_states_response(0.0).iter()
+            .all(|state| state.weight == Some(weights.weight(state.node_id))));
+
+        // Graphs without node weights have no weighted objectives
+        let problem = initialize(OSMFStrategy::Score(ScoreStrategy::new(TEST_DATA.graph.clone())));
+        let response = problem.simulation_response();
+        assert_eq!((response.weight_burned, response.weight_defended), (None, None));
+    }
 
-        let problem = Self {
-            graph: graph.clone(),
-            settings,
-            strategy,
-            node_data: NodeDataStorage::new(),
-            global_time: 0,
-            simulation_time_millis: 0,
-            is_active: true,
-            view: View::new(graph, 1920, 1080),
-        };
-        log::info!("Initialized problem configuration. settings={:?}.", &problem.settings);
+    #[test]
+    fn test_nearest_roots() {
+        let graph = &TEST_DATA.graph;
+        let mut problem = initialize_with(TEST_DATA.settings.clone());
+        problem.simulate();
+        let roots = problem.node_data.get_roots();
+        let root_dists: Vec<_> = roots.iter().map(|&root| graph.run_dijkstra(&[root])).collect();
+
+        // Every node belongs to the nearest root, and to the lowest one among equally near roots
+        let labels = analysis::compute_nearest_roots(&**graph, &problem.node_data, problem.end_time(),
+                                                     &BTreeMap::new());
+        for node_id in 0..graph.num_nodes() {
+            let nearest = (0..roots.len())
+                .filter(|&i| root_dists[i][node_id] < usize::MAX)
+                .min_by_key(|&i| (root_dists[i][node_id], roots[i]))
+                .map(|i| roots[i]);
+            assert_eq!(labels[node_id], nearest);
+        }
 
-        Ok(problem)
+        let voronoi = problem.voronoi_response();
+        assert_eq!(voronoi.regions.len(), roots.len());
+        assert_eq!(voronoi.regions.iter().map(|region| region.num_nodes).sum::<usize>(),
+                   labels.iter().flatten().count());
+        assert_eq!(voronoi.regions.iter().map(|region| region.num_burned).sum::<usize>(),
+                   problem.node_data.get_burning().into_iter().filter(|&node_id| labels[node_id].is_some()).count());
+
+        // Roots of ignition waves only start spreading once they ignite
+        let dists = graph.run_dijkstra(&[0]);
+        let max_dist = dists.iter().filter(|&&dist| dist < usize::MAX).max().copied().unwrap();
+        let mut node_data = NodeDataStorage::new();
+        node_data.mark_burning(&vec![0], 0);
+        node_data.mark_wave_roots(&vec![1], max_dist as TimeUnit + 1);
+        let labels = analysis::compute_nearest_roots(&**graph, &node_data, TimeUnit::MAX, &BTreeMap::new());
+        assert!((0..graph.num_nodes()).filter(|&node_id| dists[node_id] < usize::MAX)
+            .all(|node_id| labels[node_id] == Some(0)));
+        let labels = analysis::compute_nearest_roots(&**graph, &node_data, 0, &BTreeMap::new());
+        assert_eq!(labels[1], if dists[1] < usize::MAX { Some(0) } else { None });
     }
 
-    /// Generate `num_roots` fire roots
-    fn gen_fire_roots(&mut self) -> Vec<usize> {
-        let mut rng = thread_rng();
-        let roots = self.graph.nodes().iter()
-            .map(|node| node.id)
-            .choose_multiple(&mut rng, self.settings.num_roots);
+    #[test]
+    fn test_travel_distance() {
+        let graph = &TEST_DATA.graph;
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        settings.strategy_every = 1;
+        let mut problem = initialize_with(settings);
+        problem.simulate();
+        let distance = problem.simulation_response().travel_distance_m;
+        assert!(distance > 0.0);
+
+        // Every defense is at most the diameter of the graph away from the previous ones
+        let gb = graph.get_grid_bounds();
+        let diameter = Point::new(gb.min_lon, gb.min_lat).haversine_distance(&Point::new(gb.max_lon, gb.max_lat));
+        assert!(distance <= diameter * problem.node_data.defended.len() as f64);
+
+        // The initial deployment does not count
+        let mut node_data = NodeDataStorage::new();
+        node_data.mark_defended(&[0, 1, 2], 1);
+        assert_eq!(analysis::estimate_travel_distance_m(&**graph, &node_data), 0.0);
+        node_data.mark_defended(&[3], 2);
+        let expected = [0, 1, 2].iter()
+            .map(|&node_id| {
+                let (a, b) = (graph.get_node(node_id), graph.get_node(3));
+                Point::new(a.lon, a.lat).haversine_distance(&Point::new(b.lon, b.lat))
+            })
+            .fold(f64::INFINITY, f64::min);
+        assert!((analysis::estimate_travel_distance_m(&**graph, &node_data) - expected).abs() < 1e-6);
+    }
 
-        self.node_data.mark_burning(&roots, self.global_time);
+    #[test]
+    fn test_predict_burn_times() {
+        // Without firefighters, the fire spreads exactly as predicted
+        let mut settings = TEST_DATA.settings.clone();
+        settings.num_ffs = 0;
+        settings.seed = Some(42);
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).unwrap();
+        problem.simulate();
 
-        log::info!("Generated fire roots");
+        let prediction = analysis::predict_burn_times(&*TEST_DATA.graph, &problem.node_data.get_roots());
+        for node in TEST_DATA.graph.iter_nodes() {
+            assert_eq!(problem.node_data.get_burning_time(&node.id()), prediction.burn_time(node.id()));
+        }
+    }
 
-        roots
+    #[test]
+    fn test_fire_spread_units() {
+        let mut settings = TEST_DATA.settings.clone();
+        assert_eq!(settings.minutes_per_round(), 1.0 / DEFAULT_FIRE_SPREAD_M_PER_MIN);
+
+        // At 4 meters per minute, the fire needs 15 minutes for 60 meters
+        settings.fire_spread_m_per_min = Some(4.0);
+        assert_eq!(settings.to_minutes(60), 15.0);
+        settings.seed = Some(42);
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings.clone(), strategy).unwrap();
+        problem.simulate();
+        let timeline = serde_json::to_value(problem.node_timeline_response(problem.node_data.get_roots()[0])).unwrap();
+        assert_eq!(timeline["burned_at_minutes"], 0.0);
+        let step = serde_json::to_value(problem.sim_step_metadata_response(&8)).unwrap();
+        assert_eq!(step["time_minutes"], 2.0);
+        assert_eq!(step["time"]["round"], 8);
+        assert_eq!(step["time"]["minutes"], 2.0);
+        assert!(step["time"].get("timestamp").is_none());
+
+        // Given the start of the fire, times are also reported as timestamps
+        settings.start_timestamp = Some(1_700_000_000);
+        assert_eq!(settings.to_time(60), OSMFTime { round: 60, minutes: 15.0, timestamp: Some(1_700_000_900) });
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings.clone(), strategy).unwrap();
+        problem.simulate();
+        let step = serde_json::to_value(problem.sim_step_metadata_response(&8)).unwrap();
+        assert_eq!(step["time"]["timestamp"], 1_700_000_120);
+        let stats = serde_json::to_value(problem.sim_stats_response()).unwrap();
+        assert_eq!(stats["end"]["round"], problem.end_time());
+        let root = problem.node_data.get_roots()[0];
+        let states = problem.node_states_response(0.0);
+        assert_eq!(states[root].burn_timestamp, Some(1_700_000_000));
+        assert!(states.iter().all(|state| state.burn_timestamp == state.burn_time.and_then(|time| settings.to_timestamp(time))));
+
+        for fire_spread in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            settings.fire_spread_m_per_min = Some(fire_spread);
+            assert!(settings.validate(&*TEST_DATA.graph).is_err());
+        }
     }
 
-    /// Spread the fire to all nodes that are adjacent to burning nodes.
-    /// Defended nodes will remain defended.
-    fn spread_fire(&mut self) {
-        let mut to_burn = Vec::new();
+    #[test]
+    fn test_spread_factors() {
+        let graph = &TEST_DATA.graph;
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        // Most edges of the test graph are of type 12
+        let edge_type = 12;
+        settings.spread_factors = BTreeMap::from([(edge_type, 4.0)]);
+        let mut problem = initialize_with(settings.clone());
+        problem.simulate();
 
-        // For all undefended neighbours that are not already burning, check whether they have
-        // to be added to `to_burn`
-        self.is_active = false;
-        for node_data in self.node_data.get_burning_node_data() {
-            for edge in self.graph.get_outgoing_edges(node_data.node_id) {
-                if self.node_data.is_undefended(&edge.tgt) {
-                    // There is at least one node to be burned at some point in the future
-                    if !self.is_active {
-                        self.is_active = true;
-                    }
-                    // Burn the node if the global time exceeds the time at which the edge source
-                    // started burning plus the edge weight
-                    if self.global_time >= node_data.time + edge.dist as TimeUnit {
-                        to_burn.push(edge.tgt);
-                    }
-                }
+        // The fire crosses edges of the faster road class in a quarter of the rounds
+        let mut num_faster = 0;
+        for node_id in problem.node_data.get_burning() {
+            if let Some(parent) = problem.node_data.get_burn_parent(&node_id) {
+                let edge = graph.get_outgoing_edges(parent).iter()
+                    .filter(|edge| edge.tgt() == node_id)
+                    .min_by_key(|edge| settings.spread_rounds(edge))
+                    .unwrap();
+                let expected = if edge.attrs.edge_type == edge_type {
+                    num_faster += 1;
+                    (edge.dist() as f64 / 4.0).ceil() as TimeUnit
+                } else {
+                    edge.dist() as TimeUnit
+                };
+                let parent_time = problem.node_data.get_burning_time(&parent).unwrap();
+                assert_eq!(problem.node_data.get_burning_time(&node_id).unwrap(),
+                           parent_time + expected.max(1));
             }
         }
+        assert!(num_faster > 0);
+
+        for factor in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            settings.spread_factors = BTreeMap::from([(edge_type, factor)]);
+            assert!(settings.validate(&*TEST_DATA.graph).is_err());
+        }
+    }
 
-        // Burn all nodes in `to_burn`
-        self.node_data.mark_burning(&to_burn, self.global_time);
+    #[test]
+    fn test_root_exclusions() {
+        let graph = &TEST_DATA.graph;
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+
+        // Only the last nodes remain as root candidates
+        settings.excluded_nodes = (0..graph.num_nodes() - settings.num_roots).collect();
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(graph.clone()));
+        let mut problem = OSMFProblem::new(graph.clone(), settings.clone(), strategy).unwrap();
+        problem.simulate();
+        let mut roots = problem.node_data.get_roots();
+        roots.sort_unstable();
+        assert_eq!(roots, (graph.num_nodes() - settings.num_roots..graph.num_nodes()).collect::<Vec<_>>());
+
+        // An area covering the whole graph leaves no root candidates
+        settings.excluded_nodes = vec![];
+        let gb = graph.get_grid_bounds();
+        settings.excluded_areas = vec![vec![(gb.min_lat - 1.0, gb.min_lon - 1.0),
+                                            (gb.min_lat - 1.0, gb.max_lon + 1.0),
+                                            (gb.max_lat + 1.0, gb.max_lon + 1.0),
+                                            (gb.max_lat + 1.0, gb.min_lon - 1.0)]];
+        assert!(settings.root_candidates(&**graph).is_empty());
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(graph.clone()));
+        assert!(OSMFProblem::new(graph.clone(), settings.clone(), strategy).is_err());
+
+        settings.excluded_areas = vec![];
+        settings.excluded_nodes = vec![graph.num_nodes()];
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(graph.clone()));
+        assert!(OSMFProblem::new(graph.clone(), settings, strategy).is_err());
     }
 
-    /// Execute the containment strategy to prevent as much nodes as
-    /// possible from catching fire
-    fn contain_fire(&mut self) {
-        if self.global_time % self.settings.strategy_every == 0 {
-            self.strategy.mut_inner().execute(&self.settings, &mut self.node_data, self.global_time);
+    #[test]
+    fn test_min_root_dist() {
+        let graph = &TEST_DATA.graph;
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        settings.num_roots = 3;
+        let dists = graph.run_dijkstra(&[0]);
+        settings.min_root_dist = dists.iter().filter(|&&dist| dist < usize::MAX).max().unwrap() / 4;
+
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(graph.clone()));
+        let mut problem = OSMFProblem::new(graph.clone(), settings.clone(), strategy).unwrap();
+        problem.simulate();
+        let roots = problem.node_data.get_roots();
+        assert_eq!(roots.len(), settings.num_roots);
+        for &root in &roots {
+            let dists = graph.run_dijkstra(&[root]);
+            for &other in roots.iter().filter(|&&other| other != root) {
+                assert!(dists[other] >= settings.min_root_dist);
+            }
         }
     }
 
-    /// Execute one time step in the firefighter problem.
-    /// That is, execute the containment strategy, spread the fire and
-    /// check whether the game is finished.
-    fn exec_step(&mut self) {
-        self.global_time += 1;
+    #[test]
+    fn test_min_burn_potential() {
+        let graph = &TEST_DATA.graph;
+        let burn_potential = |roots: &[usize]| graph.run_dijkstra(roots).into_iter()
+            .filter(|&dist| dist < usize::MAX)
+            .count();
+        let max_potential = (0..graph.num_nodes()).map(|node_id| burn_potential(&[node_id])).max().unwrap();
+
+        let mut settings = TEST_DATA.settings.clone();
+        settings.num_roots = 1;
+        settings.min_burn_potential = max_potential;
+        settings.max_root_resamples = Some(1000);
+        let mut num_rejections = 0;
+        for seed in 0..20 {
+            settings.seed = Some(seed);
+            let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(graph.clone()));
+            let mut problem = OSMFProblem::new(graph.clone(), settings.clone(), strategy).unwrap();
+            problem.simulate();
+            assert_eq!(burn_potential(&problem.node_data.get_roots()), max_potential);
+            num_rejections += problem.simulation_response().root_rejections;
+        }
+        assert!(num_rejections > 0);
 
-        self.contain_fire();
-        self.spread_fire();
+        // A single root cannot exceed the maximum burn potential, so all resamples are used
+        settings.min_burn_potential = max_potential + 1;
+        settings.max_root_resamples = Some(3);
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(graph.clone()));
+        let mut problem = OSMFProblem::new(graph.clone(), settings.clone(), strategy).unwrap();
+        problem.simulate();
+        assert_eq!(problem.simulation_response().root_rejections, 3);
+
+        settings.min_burn_potential = graph.num_nodes() + 1;
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(graph.clone()));
+        assert!(OSMFProblem::new(graph.clone(), settings, strategy).is_err());
     }
 
-    /// Simulate the firefighter problem until the `is_active` flag is set to `false`
-    pub fn simulate(&mut self) {
-        if !self.is_active {
-            return;
+    #[test]
+    fn test_largest_component_only() {
+        let graph = TEST_DATA.graph.clone();
+        let components = Components::weakly_connected(&*graph);
+        let largest = components.largest().unwrap();
+        assert!(components.num_components() > 1);
+
+        let mut settings = TEST_DATA.settings.clone();
+        settings.largest_component_only = true;
+        settings.ignition_waves = vec![(5, OSMFIgnition::Count(5))];
+        for seed in 0..5 {
+            settings.seed = Some(seed);
+            let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(graph.clone()));
+            let mut problem = OSMFProblem::new(graph.clone(), settings.clone(), strategy).unwrap();
+            problem.simulate();
+
+            // Fires never leave the largest component, and only its nodes are counted
+            assert!(problem.node_data.get_burning().iter().all(|&node_id| components.component_of(node_id) == largest));
+            assert_eq!(problem.simulation_response().nodes_total, components.size(largest));
         }
 
-        log::info!("Starting problem simulation");
+        settings.num_roots = components.size(largest) + 1;
+        assert!(matches!(settings.validate(&*graph), Err(OSMFSettingsError::InvalidNumRoots { .. })));
+    }
 
-        let roots = self.gen_fire_roots();
+    #[test]
+    fn test_snapshots() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(7);
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).unwrap();
+        problem.enable_step_publishing();
+        problem.simulate();
+        assert!(problem.global_time > SNAPSHOT_EVERY, "end time: {}", problem.global_time);
+        assert_eq!(problem.progress.snapshots.lock().unwrap().len() as TimeUnit,
+                   problem.global_time / SNAPSHOT_EVERY + 1);
+
+        // Rewinding from the latest snapshot yields the state of the simulation in every round
+        for time in 0..=problem.global_time {
+            let node_data = problem.progress.rewind_to(time).unwrap();
+            for node in TEST_DATA.graph.iter_nodes() {
+                let node_id = node.id();
+                assert_eq!(node_data.is_burning(&node_id), problem.node_data.is_burning_by(&node_id, &time));
+                assert_eq!(node_data.is_defended(&node_id), problem.node_data.is_defended_by(&node_id, &time));
+                if node_data.is_burning(&node_id) {
+                    assert_eq!(node_data.get_burning_time(&node_id), problem.node_data.get_burning_time(&node_id));
+                }
+            }
+            assert_eq!(node_data.count_burning_by(&time), problem.node_data.count_burning_by(&time));
+        }
+        assert!(problem.progress.rewind_to(problem.global_time + 1).is_none());
+    }
 
-        // Measure simulation time
-        let start = Instant::now();
+    #[test]
+    fn test_step_publishing() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).unwrap();
+        problem.enable_step_publishing();
+        problem.simulate();
 
-        self.strategy.initialize(&roots, &self.settings, &self.node_data);
-        log::info!("Initialized fire containment strategy");
+        // Every completed round is published with the same metadata as for the finished simulation
+        let progress = problem.progress();
+        assert_eq!(progress.num_published_steps() as TimeUnit, problem.global_time + 1);
+        for time in 0..=problem.global_time {
+            let published = serde_json::to_value(progress.get_step(time).unwrap()).unwrap();
+            let expected = serde_json::to_value(problem.sim_step_metadata_response(&time)).unwrap();
+            assert_eq!(published, expected, "time: {}", time);
+        }
+        assert!(progress.get_step(problem.global_time + 1).is_none());
+
+        // The node data of completed rounds can be reconstructed from the published steps
+        let running = problem.running_simulation();
+        assert_eq!(running.watermark(), Some(problem.global_time));
+        let node_data = running.node_data_until(problem.global_time).unwrap();
+        assert_eq!(node_data.get_burning(), problem.node_data.get_burning());
+        assert_eq!(node_data.get_defended(), problem.node_data.get_defended());
+        for node_id in problem.node_data.get_burning() {
+            assert_eq!(node_data.get_burning_time(&node_id), problem.node_data.get_burning_time(&node_id));
+        }
+        assert!(running.node_data_until(problem.global_time + 1).is_none());
 
-        while self.is_active {
-            self.exec_step();
+        let counts = problem.cumulative_counts();
+        assert_eq!(counts.len() as TimeUnit, problem.global_time + 1);
+        for (time, (burned, defended)) in counts.into_iter().enumerate() {
+            let step = progress.get_step(time as TimeUnit).unwrap();
+            assert_eq!((burned, defended), (step.nodes_burned_by, step.nodes_defended_by));
         }
+    }
 
-        self.simulation_time_millis = start.elapsed().as_millis();
+    #[test]
+    fn test_ignition_waves() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings.clone(), strategy).unwrap();
+        problem.simulate();
+        let wave_time = problem.global_time + 5;
+        let wave_nodes: Vec<_> = TEST_DATA.graph.iter_nodes()
+            .map(|node| node.id())
+            .filter(|node_id| problem.node_data.is_undefended(node_id))
+            .take(2)
+            .collect();
+        assert_eq!(wave_nodes.len(), 2);
+
+        // Nodes that survived the initial fire ignite as new roots in later rounds
+        settings.ignition_waves = vec![(wave_time + 10, OSMFIgnition::Count(3)),
+                                       (wave_time, OSMFIgnition::Nodes(wave_nodes.clone()))];
+        for strategy in [
+            OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone())),
+            OSMFStrategy::MultiMinDistanceSets(MultiMinDistSetsStrategy::new(TEST_DATA.graph.clone())),
+            OSMFStrategy::Priority(PriorityStrategy::new(TEST_DATA.graph.clone())),
+        ] {
+            let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings.clone(), strategy).unwrap();
+            problem.simulate();
+            for node_id in &wave_nodes {
+                assert_eq!(problem.node_data.get_burning_time(node_id), Some(wave_time));
+                assert!(problem.node_data.is_root(node_id));
+                assert_eq!(problem.node_data.get_burn_parent(node_id), None);
+            }
+            assert_eq!(problem.node_data.get_burning_at(&(wave_time + 10)).iter()
+                           .filter(|node_id| problem.node_data.is_root(node_id))
+                           .count(), 3);
+            assert!(problem.global_time > wave_time + 10);
+        }
 
-        log::info!("Finished problem simulation");
+        settings.ignition_waves = vec![(0, OSMFIgnition::Count(1))];
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        assert!(OSMFProblem::new(TEST_DATA.graph.clone(), settings.clone(), strategy).is_err());
+        settings.ignition_waves = vec![(1, OSMFIgnition::Nodes(vec![TEST_DATA.graph.num_nodes()]))];
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        assert!(OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).is_err());
     }
 
-    /// Generate the simulation response for this firefighter problem instance
-    pub fn simulation_response(&self) -> OSMFSimulationResponse {
-        log::info!("Generating simulation response");
+    #[test]
+    fn test_corridors() {
+        let mut problem = initialize(OSMFStrategy::Greedy(
+            GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
 
-        OSMFSimulationResponse {
-            nodes_burned: self.node_data.burning.len(),
-            nodes_defended: self.node_data.defended.len(),
-            nodes_total: self.graph.num_nodes,
-            end_time: self.global_time,
-            simulation_time_millis: self.simulation_time_millis,
-            view_bounds: &self.view.grid_bounds,
-            view_center: self.view.initial_center,
+        let corridors = problem.corridors_response(3);
+        assert!(!corridors.is_empty() && corridors.len() <= 3);
+        for w in corridors.windows(2) {
+            assert!(w[0].size() >= w[1].size());
+        }
+        for corridor in &corridors {
+            let nodes = corridor.nodes();
+            assert!(!problem.node_data.is_root(&nodes[0]));
+            assert!(corridor.size() < problem.node_data.burning.len());
+            for w in nodes.windows(2) {
+                assert_eq!(problem.node_data.get_burn_parent(&w[1]), Some(w[0]));
+            }
         }
     }
 
-    /// Generate the view response for this firefighter problem instance
-    pub fn view_response(&mut self, center: Coords, zoom: f64, time: &TimeUnit) -> Vec<u8> {
-        log::info!("Generating view response. center={:?}, zoom={}, time={}.", center, zoom, time);
+    #[test]
+    fn test_node_importance() {
+        let mut problem = initialize(OSMFStrategy::Greedy(
+            GreedyStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
 
-        self.view.compute(center, zoom, time, &self.node_data);
-        self.view.png_bytes()
+        let importance = problem.node_importance_analysis(2, 3, 5).run(&JobProgress::new());
+        assert!(importance.num_simulations <= 5);
+        assert_eq!(importance.nodes().len(), importance.num_simulations);
+        assert_eq!(importance.baseline_burned, problem.node_data.burning.len());
+        for w in importance.nodes().windows(2) {
+            assert!(w[0].burned_reduction() >= w[1].burned_reduction());
+        }
+        for node in importance.nodes() {
+            assert!(problem.node_data.is_burning(&node.node_id()));
+            assert!(!problem.node_data.is_root(&node.node_id()));
+        }
     }
 
-    /// Generate the alternative view response for this firefighter problem instance
-    pub fn view_response_alt(&mut self, zoom: f64, time: &TimeUnit) -> Vec<u8> {
-        log::info!("Generating view response. zoom={}, time={}.", zoom, time);
+    #[test]
+    fn test_seeded_roots() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        let mut problems: Vec<_> = (0..2)
+            .map(|_| OSMFProblem::new(TEST_DATA.graph.clone(), settings.clone(),
+                                      OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone())))
+                .unwrap())
+            .collect();
+        for problem in problems.iter_mut() {
+            problem.simulate();
+        }
 
-        self.view.compute_alt(zoom, time, &self.node_data);
-        self.view.png_bytes()
+        assert_eq!(problems[0].simulation_response().seed, 42);
+        assert_eq!(problems[0].node_data.get_roots(), problems[1].node_data.get_roots());
+        assert_eq!(problems[0].result_hash(), problems[1].result_hash());
     }
 
-    pub fn sim_step_metadata_response(&self, time: &TimeUnit) -> OSMFSimulationStepMetadata {
-        log::info!("Generating simulation step metadata response. time={}.", time);
+    #[test]
+    fn test_audit() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.ignition_waves = vec![(20, OSMFIgnition::Count(2))];
+        let mut problem = initialize_with(settings);
+        problem.enable_audit();
+        problem.simulate();
 
-        OSMFSimulationStepMetadata {
-            nodes_burned_by: self.node_data.count_burning_by(time),
-            nodes_defended_by: self.node_data.count_defended_by(time),
-            nodes_burned_at: self.node_data.get_burning_at(time),
-            nodes_defended_at: self.node_data.get_defended_at(time),
+        // Every burning node is ignited exactly once by the recorded rule
+        let log = problem.audit_log().unwrap();
+        assert_eq!(log.ignitions.len(), problem.node_data.burning.len());
+        for record in &log.ignitions {
+            assert_eq!(problem.node_data.get_burning_time(&record.node), Some(record.round));
+            match record.cause {
+                OSMFIgnitionCause::Root => assert_eq!(record.round, 0),
+                OSMFIgnitionCause::Wave => assert_eq!(record.round, 20),
+                OSMFIgnitionCause::Spread { source, source_burn_time, edge_weight, spread_rounds } => {
+                    assert_eq!(problem.node_data.get_burn_parent(&record.node), Some(source));
+                    assert_eq!(problem.node_data.get_burning_time(&source), Some(source_burn_time));
+                    assert!(TEST_DATA.graph.get_outgoing_edges(source).iter()
+                        .any(|edge| edge.tgt() == record.node && edge.dist() == edge_weight));
+                    assert!(record.round >= source_burn_time + spread_rounds);
+                }
+            }
         }
-    }
-}
-
-#[cfg(test)]
-mod test {
-    use std::sync::Arc;
 
-    use once_cell::sync::Lazy;
+        // The log survives a round trip through its file format and replays consistently
+        let log: OSMFAuditLog = serde_json::from_str(&serde_json::to_string(&log).unwrap()).unwrap();
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        let report = log.verify(TEST_DATA.graph.clone(), strategy).unwrap();
+        assert!(report.is_consistent);
+        assert_eq!(report.num_ignitions, log.ignitions.len());
+
+        // Tampered ignitions are flagged
+        let mut tampered = log.clone();
+        let last = tampered.ignitions.pop().unwrap();
+        tampered.ignitions[1].round += 1;
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        let report = tampered.verify(TEST_DATA.graph.clone(), strategy).unwrap();
+        assert!(!report.is_consistent);
+        assert_eq!(report.num_divergences, 2);
+        assert!(report.divergences.iter()
+            .any(|divergence| divergence.node == last.node && divergence.recorded.is_none()));
+        assert_eq!(report.first_divergent_round, Some(log.ignitions[1].round.min(last.round)));
+    }
 
-    use crate::firefighter::{problem::{OSMFProblem, OSMFSettings},
-                             strategy::{OSMFStrategy,
-                                        GreedyStrategy,
-                                        MultiMinDistSetsStrategy,
-                                        RandomStrategy,
-                                        PriorityStrategy,
-                                        Strategy}};
-    use crate::firefighter::strategy::ScoreStrategy;
-    use crate::graph::Graph;
+    #[test]
+    fn test_resolved_settings() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.excluded_nodes = vec![5, 1, 5];
+        let mut problem = initialize_with(settings);
+        problem.simulate();
 
-    struct TestData {
-        graph: Arc<Graph>,
-        settings: OSMFSettings,
+        // The resolved settings reproduce the simulation with a random seed
+        let resolved = problem.simulation_response().settings;
+        assert_eq!(resolved.seed, Some(problem.seed));
+        assert_eq!(resolved.strategy_name, "Greedy");
+        assert_eq!(resolved.fire_spread_m_per_min, Some(DEFAULT_FIRE_SPREAD_M_PER_MIN));
+        assert_eq!(resolved.max_root_resamples, Some(DEFAULT_MAX_ROOT_RESAMPLES));
+        assert_eq!(resolved.excluded_nodes, vec![1, 5]);
+
+        let mut replay = initialize_with(resolved);
+        replay.simulate();
+        assert_eq!(problem.result_hash(), replay.result_hash());
     }
 
-    static TEST_DATA: Lazy<TestData> = Lazy::new(||
-        TestData {
-            graph: Arc::new(Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap()),
-            settings: OSMFSettings {
-                graph_name: "bbgrund".to_string(),
-                strategy_name:"Greedy".to_string(),
-                num_roots: 10,
-                num_ffs: 2,
-                strategy_every: 10,
-            },
-        });
+    #[test]
+    fn test_defense_failures() {
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        let mut reliable = initialize_with(settings.clone());
+        reliable.simulate();
+
+        // Defenses that never fail do not change the outcome
+        settings.defense_failure_prob = Some(0.0);
+        let mut problem = initialize_with(settings.clone());
+        problem.simulate();
+        assert_eq!(problem.result_hash(), reliable.result_hash());
+        assert_eq!(problem.simulation_response().failed_defenses, 0);
 
-    fn initialize(strategy: OSMFStrategy) -> OSMFProblem {
-        OSMFProblem::new(TEST_DATA.graph.clone(), TEST_DATA.settings.clone(), strategy).unwrap()
+        settings.defense_failure_prob = Some(1.0);
+        let mut problem = initialize_with(settings.clone());
+        problem.simulate();
+        let sim = problem.simulation_response();
+        assert_eq!(sim.nodes_defended, 0);
+        assert!(sim.failed_defenses > 0);
+        assert!(sim.nodes_burned >= reliable.simulation_response().nodes_burned);
+
+        settings.defense_failure_prob = Some(-0.1);
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(TEST_DATA.graph.clone()));
+        assert!(OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).is_err());
     }
 
     #[test]
-    fn test_roots() {
-        let mut problem = initialize(OSMFStrategy::Random(
-            RandomStrategy::new(TEST_DATA.graph.clone())));
+    fn test_predefended() {
+        let graph = &TEST_DATA.graph;
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        let mut previous = initialize_with(settings.clone());
+        previous.simulate();
+
+        // Seeding a simulation with the defenses of a previous one keeps them from the start
+        settings.predefended = previous.node_data.get_defended();
+        settings.defense_failure_prob = Some(1.0);
+        let mut problem = initialize_with(settings.clone());
         problem.simulate();
+        assert_eq!(problem.node_data.get_defended_at(&0), settings.predefended);
+        assert!(settings.predefended.iter().all(|node_id| !problem.node_data.is_burning(node_id)));
 
-        let num_roots = problem.node_data.get_roots().len();
-        let settings = &TEST_DATA.settings;
-        assert_eq!(num_roots, settings.num_roots, "num burning at 0: {}, num roots: {}",
-                   num_roots, settings.num_roots);
+        // Pre-defended nodes are never chosen as fire roots
+        settings.predefended = (0..graph.num_nodes() - settings.num_roots).collect();
+        settings.defense_failure_prob = None;
+        let mut problem = initialize_with(settings.clone());
+        problem.simulate();
+        let mut roots = problem.node_data.get_roots();
+        roots.sort_unstable();
+        assert_eq!(roots, (graph.num_nodes() - settings.num_roots..graph.num_nodes()).collect::<Vec<_>>());
+
+        settings.predefended = vec![graph.num_nodes()];
+        let strategy = OSMFStrategy::Greedy(GreedyStrategy::new(graph.clone()));
+        assert!(OSMFProblem::new(graph.clone(), settings, strategy).is_err());
     }
 
     #[test]
-    fn test_active() {
-        let mut problem = initialize(OSMFStrategy::Random(
-            RandomStrategy::new(TEST_DATA.graph.clone())));
+    fn test_replay_plan() {
+        let graph = &TEST_DATA.graph;
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        let mut problem = initialize_with(settings.clone());
         problem.simulate();
-
-        assert!(!problem.is_active);
+        let plan = problem.defense_plan();
+        assert_eq!(plan.rounds.iter().map(|round| round.nodes.len()).sum::<usize>(),
+                   problem.node_data.defended.len());
+
+        // Replaying the plan against the same fire reproduces the simulation
+        settings.strategy_name = "ReplayPlan".to_string();
+        settings.defense_plan = Some(plan.clone());
+        let strategy = OSMFStrategy::from_name_and_graph("ReplayPlan", graph.clone()).unwrap();
+        let mut replay = OSMFProblem::new(graph.clone(), settings.clone(), strategy).unwrap();
+        replay.simulate();
+        assert_eq!(replay.result_hash(), problem.result_hash());
+
+        // Planned defenses of rounds without strategy execution are applied in the next one
+        settings.strategy_every = 1000;
+        let strategy = OSMFStrategy::from_name_and_graph("ReplayPlan", graph.clone()).unwrap();
+        let mut replay = OSMFProblem::new(graph.clone(), settings.clone(), strategy).unwrap();
+        replay.simulate();
+        assert!(replay.node_data.get_defended().iter()
+            .all(|node_id| replay.node_data.get_defended_time(node_id) == Some(1000)));
+
+        settings.defense_plan = None;
+        let strategy = OSMFStrategy::from_name_and_graph("ReplayPlan", graph.clone()).unwrap();
+        assert!(OSMFProblem::new(graph.clone(), settings.clone(), strategy).is_err());
+
+        let mut invalid_plan = plan;
+        invalid_plan.rounds[0].round = 0;
+        settings.defense_plan = Some(invalid_plan);
+        let strategy = OSMFStrategy::from_name_and_graph("ReplayPlan", graph.clone()).unwrap();
+        assert!(OSMFProblem::new(graph.clone(), settings, strategy).is_err());
     }
 
     #[test]
-    fn test_burned() {
-        let mut problem = initialize(OSMFStrategy::Random(
-            RandomStrategy::new(TEST_DATA.graph.clone())));
+    fn test_simulation_progress() {
+        let mut problem = initialize(OSMFStrategy::Greedy(
+            GreedyStrategy::new(TEST_DATA.graph.clone())));
+        let progress = problem.progress();
+        assert_eq!(progress.snapshot().progress, 0.0);
+        assert!(!progress.snapshot().is_finished);
+
         problem.simulate();
 
-        let burned_times: Vec<_> = problem.node_data.burning.values()
-            .map(|nd| nd.time)
-            .collect();
-        for time in burned_times {
-            assert!(time <= problem.global_time, "burned time: {}, global time: {}",
-                    time, problem.global_time);
-        }
+        let snapshot = progress.snapshot();
+        assert!(snapshot.is_finished);
+        assert_eq!(snapshot.progress, 1.0);
+        assert_eq!(snapshot.round, problem.global_time);
+        assert_eq!(snapshot.nodes_settled, problem.node_data.burning.len() + problem.node_data.defended.len());
+        assert!(snapshot.nodes_reachable >= problem.node_data.burning.len());
     }
 
     #[test]
@@ -524,4 +3737,206 @@ mod test {
             .count();
         assert_eq!(num_ambiguous, 0, "num ambiguous: {}", num_ambiguous);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_k_server() {
+        let mut problem = initialize(OSMFStrategy::KServer(
+            KServerStrategy::new(TEST_DATA.graph.clone())));
+        problem.simulate();
+
+        let ffs = problem.settings.num_ffs;
+        let gt = problem.global_time as usize;
+        let se = problem.settings.strategy_every as usize;
+        let num_defended = problem.node_data.defended.len();
+        let should_defended = ffs * (gt / se);
+        assert!(num_defended <= should_defended, "num defended: {}, should defended: {}",
+                num_defended, should_defended);
+
+        let num_ambiguous = problem.node_data.burning.keys()
+            .filter(|&node_id| problem.node_data.defended.contains_key(node_id))
+            .count();
+        assert_eq!(num_ambiguous, 0, "num ambiguous: {}", num_ambiguous);
+
+        // Firefighters that moved are positioned at the nodes they defended last
+        let report = problem.simulation_response().strategy_report;
+        let positions = report["positions"].as_array().unwrap();
+        assert_eq!(positions.len(), ffs);
+        assert!(positions.iter()
+            .any(|node_id| problem.node_data.is_defended(&(node_id.as_u64().unwrap() as usize))));
+        assert!(report["travel_distance_m"].as_f64().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_ensemble() {
+        let graph = &TEST_DATA.graph;
+        let mut settings = TEST_DATA.settings.clone();
+        settings.seed = Some(42);
+        settings.strategy_name = "Ensemble".to_string();
+        settings.ensemble_strategies = vec!["Greedy".to_string(), "MultiMinDistanceSets".to_string()];
+
+        // Rotating strategies take turns round by round
+        let strategy = OSMFStrategy::from_name_and_graph("Ensemble", graph.clone()).unwrap();
+        let mut problem = OSMFProblem::new(graph.clone(), settings.clone(), strategy).unwrap();
+        problem.simulate();
+        let report = problem.simulation_response().strategy_report;
+        let decisions = report["decisions"].as_array().unwrap();
+        assert!(decisions.len() >= 2);
+        assert_eq!(decisions[0]["strategy"], "Greedy");
+        assert_eq!(decisions[1]["strategy"], "MultiMinDistanceSets");
+        assert_eq!(decisions[1]["round"], 2 * settings.strategy_every);
+        assert_eq!(report["members"].as_array().unwrap().len(), 2);
+
+        // Lookahead picks a strategy in every execution
+        settings.ensemble_mode = Some(OSMFEnsembleMode::Lookahead);
+        let strategy = OSMFStrategy::from_name_and_graph("Ensemble", graph.clone()).unwrap();
+        let mut problem = OSMFProblem::new(graph.clone(), settings.clone(), strategy).unwrap();
+        problem.simulate();
+        let report = problem.simulation_response().strategy_report;
+        let num_decisions: u64 = report["members"].as_array().unwrap().iter()
+            .map(|member| member["num_decisions"].as_u64().unwrap())
+            .sum();
+        assert_eq!(num_decisions as usize, report["decisions"].as_array().unwrap().len());
+        let num_ambiguous = problem.node_data.burning.keys()
+            .filter(|&node_id| problem.node_data.defended.contains_key(node_id))
+            .count();
+        assert_eq!(num_ambiguous, 0);
+
+        for strategies in [vec![], vec!["Ensemble".to_string()], vec!["Unknown".to_string()]] {
+            settings.ensemble_strategies = strategies;
+            let strategy = OSMFStrategy::from_name_and_graph("Ensemble", graph.clone()).unwrap();
+            assert!(OSMFProblem::new(graph.clone(), settings.clone(), strategy).is_err());
+        }
+    }
+
+    #[test]
+    fn test_plugin_strategy() {
+        static NUM_INSTANCES: AtomicUsize = AtomicUsize::new(0);
+
+        // Plugin strategy that chooses a node that does not exist and a burning node before the
+        // untouched nodes with the smallest ids
+        unsafe extern "C" fn create(graph: *const OSMFPluginGraph) -> *mut c_void {
+            NUM_INSTANCES.fetch_add(1, Ordering::SeqCst);
+            Box::into_raw(Box::new((*graph).num_nodes)) as *mut c_void
+        }
+        unsafe extern "C" fn execute(state: *mut c_void, round: *const OSMFPluginRound,
+                                     to_defend: *mut u64, capacity: u64) -> u64 {
+            let num_nodes = *(state as *const u64);
+            let states = std::slice::from_raw_parts((*round).node_states, num_nodes as usize);
+            let burning = states.iter().position(|&state| state == NODE_BURNING).unwrap() as u64;
+            let untouched = (0..num_nodes).filter(|&node_id| states[node_id as usize] == NODE_UNTOUCHED);
+            let invalid = [num_nodes, burning];
+            let chosen = invalid.iter().copied().chain(untouched);
+            let to_defend = std::slice::from_raw_parts_mut(to_defend, capacity as usize);
+            for (slot, node_id) in to_defend.iter_mut().zip(chosen) {
+                *slot = node_id;
+            }
+            capacity + 1
+        }
+        unsafe extern "C" fn destroy(state: *mut c_void) {
+            drop(Box::from_raw(state as *mut u64));
+            NUM_INSTANCES.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        let register = |api_version: u32, name: &'static [u8]| {
+            let plugin = OSMFStrategyPlugin {
+                api_version,
+                name: name.as_ptr() as *const c_char,
+                vtable: OSMFStrategyVTable { create, execute, destroy },
+            };
+            unsafe { plugin::register_plugin(&plugin) }
+        };
+        assert!(matches!(register(PLUGIN_API_VERSION + 1, b"TestPlugin\0"),
+                         Err(OSMFPluginError::IncompatibleVersion { .. })));
+        assert!(matches!(register(PLUGIN_API_VERSION, b"Greedy\0"), Err(OSMFPluginError::DuplicateName { .. })));
+        assert_eq!(register(PLUGIN_API_VERSION, b"TestPlugin\0").unwrap(), "TestPlugin");
+        assert!(matches!(register(PLUGIN_API_VERSION, b"TestPlugin\0"), Err(OSMFPluginError::DuplicateName { .. })));
+
+        let strategies = OSMFStrategy::available_strategies();
+        assert_eq!(strategies.last().unwrap(), "TestPlugin");
+        assert!(!strategies.contains(&"Plugin".to_string()));
+
+        let mut settings = TEST_DATA.settings.clone();
+        settings.strategy_name = "TestPlugin".to_string();
+        settings.num_ffs = 4;
+        let strategy = OSMFStrategy::from_name_and_graph("TestPlugin", TEST_DATA.graph.clone()).unwrap();
+        assert_eq!(strategy.name(), "TestPlugin");
+        let mut problem = OSMFProblem::new(TEST_DATA.graph.clone(), settings, strategy).unwrap();
+        problem.simulate();
+
+        // Only the untouched nodes are defended, and the plugin instance is destroyed once the
+        // simulation has finished
+        let report = problem.simulation_response().strategy_report;
+        let num_executions = report["num_executions"].as_u64().unwrap() as usize;
+        assert!(num_executions > 0);
+        assert_eq!(report["num_rejected"].as_u64().unwrap() as usize, 2 * num_executions);
+        assert_eq!(problem.node_data.get_defended().len(), 2 * num_executions);
+        let num_ambiguous = problem.node_data.burning.keys()
+            .filter(|&node_id| problem.node_data.defended.contains_key(node_id))
+            .count();
+        assert_eq!(num_ambiguous, 0);
+        assert_eq!(problem.resolved_settings().strategy_name, "TestPlugin");
+        assert_eq!(NUM_INSTANCES.load(Ordering::SeqCst), 0);
+    }
+
+    thread_local! {
+        /// Messages logged on this thread, if they are captured
+        static CAPTURED_LOGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+    }
+
+    /// Logger that captures the messages logged on threads that capture their logs
+    struct CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            CAPTURED_LOGS.with(|logs| {
+                if let Some(logs) = logs.borrow_mut().as_mut() {
+                    logs.push(record.args().to_string());
+                }
+            });
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURE_LOGGER: CaptureLogger = CaptureLogger;
+
+    #[test]
+    fn test_strategy_debug_file() {
+        // No other test installs a logger, and messages of other threads are not captured
+        log::set_logger(&CAPTURE_LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let dir = std::env::temp_dir().join(format!("osmff_strategy_debug_{}", std::process::id()));
+        let mut problem = initialize(OSMFStrategy::SingleMinDistanceSet(
+            SingleMinDistSetStrategy::new(TEST_DATA.graph.clone())));
+        problem.strategy_debug_path = Some(dir.clone());
+        CAPTURED_LOGS.with(|logs| logs.replace(Some(Vec::new())));
+        problem.simulate();
+        let logs = CAPTURED_LOGS.with(|logs| logs.replace(None)).unwrap();
+
+        // The debug file holds every entry of the global distances, one per line
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        assert_eq!(files.len(), 1);
+        let debug = std::fs::read_to_string(&files[0]).unwrap();
+        let mut lines = debug.lines().skip_while(|line| !line.starts_with("Global distances ("));
+        let header = lines.next().unwrap();
+        let num_entries: usize = header.trim_start_matches("Global distances (")
+            .trim_end_matches(" entries):")
+            .parse().unwrap();
+        assert!(num_entries > 10);
+        assert_eq!(lines.take_while(|line| line.starts_with('(')).count(), num_entries);
+
+        // The log only holds the first entries
+        let summary = format!("Global distances ({} entries), first 10: ", num_entries);
+        let logged: Vec<_> = logs.iter().filter(|message| message.starts_with("Global distances")).collect();
+        assert_eq!(logged.len(), 1);
+        assert!(logged[0].starts_with(&summary));
+        assert_eq!(logged[0].matches("), (").count(), 9);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
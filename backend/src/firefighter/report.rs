@@ -0,0 +1,499 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::Serialize;
+
+use crate::firefighter::charts::{self, ChartError};
+use crate::firefighter::problem::OSMFProblem;
+use crate::firefighter::TimeUnit;
+
+/// Style sheet embedded into every report
+const REPORT_STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: right; }
+th:first-child, td:first-child { text-align: left; }
+th { background: #eee; }
+figure { display: inline-block; margin: 0 1em 1em 0; }
+figure img { max-width: 640px; border: 1px solid #ccc; }";
+
+/// Names of the objectives on which configurations are compared in the Pareto analysis, all of
+/// which are minimized
+pub const PARETO_OBJECTIVES: [&str; 4] = ["burned_nodes", "burned_area_km2", "travel_distance_m",
+                                          "containment_time"];
+
+/// Outcome of a single simulation in a strategy comparison report
+#[derive(Debug, Clone)]
+pub struct ReportRun {
+    pub graph_name: String,
+    pub strategy_name: String,
+    pub seed: u64,
+    pub num_ffs: usize,
+    pub strategy_every: TimeUnit,
+    /// Probability with which each planned defense failed, `0` if defenses never fail
+    pub defense_failure_prob: f64,
+    pub nodes_burned: usize,
+    pub nodes_defended: usize,
+    /// Estimated burned area, if known
+    pub burned_area_km2: Option<f64>,
+    /// Estimated distance the firefighters travelled, if known
+    pub travel_distance_m: Option<f64>,
+    pub end_time: TimeUnit,
+    pub simulation_time_millis: u128,
+}
+
+impl ReportRun {
+    /// Get the outcome of the simulated firefighter problem instance `problem`
+    pub fn from_problem(problem: &OSMFProblem) -> Self {
+        let settings = problem.settings();
+        let sim = problem.simulation_response();
+        Self {
+            graph_name: settings.graph_name.clone(),
+            strategy_name: settings.strategy_name.clone(),
+            seed: sim.seed,
+            num_ffs: settings.num_ffs,
+            strategy_every: settings.strategy_every,
+            defense_failure_prob: settings.defense_failure_prob.unwrap_or(0.0),
+            nodes_burned: sim.nodes_burned,
+            nodes_defended: sim.nodes_defended,
+            burned_area_km2: Some(sim.burned_area_km2),
+            travel_distance_m: Some(sim.travel_distance_m),
+            end_time: sim.end_time,
+            simulation_time_millis: sim.simulation_time_millis,
+        }
+    }
+}
+
+/// Rendered map frame of a strategy comparison report
+struct ReportFrame {
+    caption: String,
+    png: Vec<u8>,
+}
+
+/// Aggregated outcome of all runs of a single strategy
+struct StrategySummary {
+    strategy_name: String,
+    num_runs: usize,
+    mean_burned: f64,
+    min_burned: usize,
+    max_burned: usize,
+    mean_defended: f64,
+    mean_end_time: f64,
+    mean_millis: f64,
+}
+
+/// Outcome of all runs of a single strategy with the same probability of failing defenses
+struct FailureSensitivity {
+    strategy_name: String,
+    defense_failure_prob: f64,
+    num_runs: usize,
+    mean_burned: f64,
+    /// Increase of the mean number of burned nodes over the runs of the same strategy without
+    /// failing defenses, if there are any
+    burned_increase: Option<f64>,
+}
+
+/// Mean outcome of all runs of a single configuration, i.e. a strategy with specific parameters,
+/// on the objectives of the Pareto analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct ParetoConfiguration {
+    pub strategy_name: String,
+    pub num_ffs: usize,
+    pub strategy_every: TimeUnit,
+    pub defense_failure_prob: f64,
+    pub num_runs: usize,
+    pub mean_burned: f64,
+    pub mean_burned_area_km2: f64,
+    pub mean_travel_distance_m: f64,
+    /// Mean round in which the fire was contained
+    pub mean_containment_time: f64,
+}
+
+impl ParetoConfiguration {
+    /// Get the values of this configuration for all objectives in the order of
+    /// `PARETO_OBJECTIVES`
+    fn objectives(&self) -> [f64; 4] {
+        [self.mean_burned, self.mean_burned_area_km2, self.mean_travel_distance_m,
+         self.mean_containment_time]
+    }
+
+    /// Does this configuration dominate `other`, i.e. is it at least as good on all objectives and
+    /// better on at least one?
+    fn dominates(&self, other: &Self) -> bool {
+        let mut better = false;
+        for (own, others) in self.objectives().iter().zip(other.objectives().iter()) {
+            if own > others {
+                return false;
+            }
+            better |= own < others;
+        }
+        better
+    }
+}
+
+/// Report comparing the outcomes of simulations with different containment strategies, which is
+/// rendered as self-contained HTML document
+pub struct StrategyReport {
+    title: String,
+    runs: Vec<ReportRun>,
+    frames: Vec<ReportFrame>,
+}
+
+impl StrategyReport {
+    /// Create a new empty report titled `title`
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            runs: Vec::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Add the outcome of the simulation `run` to this report
+    pub fn add_run(&mut self, run: ReportRun) {
+        self.runs.push(run);
+    }
+
+    /// Add the rendered map frame `png` with caption `caption` to this report
+    pub fn add_frame(&mut self, caption: &str, png: Vec<u8>) {
+        self.frames.push(ReportFrame {
+            caption: caption.to_string(),
+            png,
+        });
+    }
+
+    /// Aggregate the runs of every strategy, ordered by strategy name
+    fn summaries(&self) -> Vec<StrategySummary> {
+        let mut runs_by_strategy: BTreeMap<&str, Vec<&ReportRun>> = BTreeMap::new();
+        for run in &self.runs {
+            runs_by_strategy.entry(&run.strategy_name).or_default().push(run);
+        }
+
+        runs_by_strategy.into_iter()
+            .map(|(strategy_name, runs)| {
+                let n = runs.len() as f64;
+                StrategySummary {
+                    strategy_name: strategy_name.to_string(),
+                    num_runs: runs.len(),
+                    mean_burned: runs.iter().map(|run| run.nodes_burned as f64).sum::<f64>() / n,
+                    min_burned: runs.iter().map(|run| run.nodes_burned).min().unwrap_or(0),
+                    max_burned: runs.iter().map(|run| run.nodes_burned).max().unwrap_or(0),
+                    mean_defended: runs.iter().map(|run| run.nodes_defended as f64).sum::<f64>() / n,
+                    mean_end_time: runs.iter().map(|run| run.end_time as f64).sum::<f64>() / n,
+                    mean_millis: runs.iter().map(|run| run.simulation_time_millis as f64).sum::<f64>() / n,
+                }
+            })
+            .collect()
+    }
+
+    /// Aggregate the runs of every strategy by their probability of failing defenses, ordered by
+    /// strategy name and probability. Returns an empty list if defenses never failed in any run.
+    fn failure_sensitivities(&self) -> Vec<FailureSensitivity> {
+        if self.runs.iter().all(|run| run.defense_failure_prob == 0.0) {
+            return Vec::new();
+        }
+
+        // Bit patterns of non-negative floats are ordered like the floats themselves
+        let mut runs_by_prob: BTreeMap<(&str, u64), Vec<&ReportRun>> = BTreeMap::new();
+        for run in &self.runs {
+            runs_by_prob.entry((&run.strategy_name, run.defense_failure_prob.to_bits()))
+                .or_default()
+                .push(run);
+        }
+
+        let mut sensitivities: Vec<FailureSensitivity> = Vec::new();
+        for ((strategy_name, prob_bits), runs) in runs_by_prob {
+            let mean_burned = runs.iter().map(|run| run.nodes_burned as f64).sum::<f64>() / runs.len() as f64;
+            let defense_failure_prob = f64::from_bits(prob_bits);
+            // Runs without failing defenses come first among the runs of every strategy
+            let burned_increase = sensitivities.iter()
+                .find(|other| other.strategy_name == strategy_name && other.defense_failure_prob == 0.0)
+                .map(|reliable| mean_burned - reliable.mean_burned);
+            sensitivities.push(FailureSensitivity {
+                strategy_name: strategy_name.to_string(),
+                defense_failure_prob,
+                num_runs: runs.len(),
+                mean_burned,
+                burned_increase,
+            });
+        }
+        sensitivities
+    }
+
+    /// Aggregate the runs of every configuration, i.e. of every strategy with the same number of
+    /// firefighters, strategy interval and probability of failing defenses, on the objectives of
+    /// the Pareto analysis. Runs with unknown objectives are ignored.
+    fn configurations(&self) -> Vec<ParetoConfiguration> {
+        let mut runs_by_config: BTreeMap<(&str, usize, TimeUnit, u64), Vec<&ReportRun>> = BTreeMap::new();
+        for run in &self.runs {
+            if run.burned_area_km2.is_some() && run.travel_distance_m.is_some() {
+                runs_by_config.entry((&run.strategy_name, run.num_ffs, run.strategy_every,
+                                      run.defense_failure_prob.to_bits()))
+                    .or_default()
+                    .push(run);
+            }
+        }
+
+        runs_by_config.into_iter()
+            .map(|((strategy_name, num_ffs, strategy_every, prob_bits), runs)| {
+                let n = runs.len() as f64;
+                ParetoConfiguration {
+                    strategy_name: strategy_name.to_string(),
+                    num_ffs,
+                    strategy_every,
+                    defense_failure_prob: f64::from_bits(prob_bits),
+                    num_runs: runs.len(),
+                    mean_burned: runs.iter().map(|run| run.nodes_burned as f64).sum::<f64>() / n,
+                    mean_burned_area_km2: runs.iter().filter_map(|run| run.burned_area_km2).sum::<f64>() / n,
+                    mean_travel_distance_m: runs.iter().filter_map(|run| run.travel_distance_m).sum::<f64>() / n,
+                    mean_containment_time: runs.iter().map(|run| run.end_time as f64).sum::<f64>() / n,
+                }
+            })
+            .collect()
+    }
+
+    /// Get the Pareto-optimal configurations of the runs of this report, i.e. all configurations
+    /// that are not dominated by another configuration on the objectives in `PARETO_OBJECTIVES`,
+    /// ordered by their mean number of burned nodes
+    pub fn pareto_front(&self) -> Vec<ParetoConfiguration> {
+        let configurations = self.configurations();
+        let mut front: Vec<_> = configurations.iter()
+            .filter(|config| !configurations.iter().any(|other| other.dominates(config)))
+            .cloned()
+            .collect();
+        front.sort_by(|a, b| a.mean_burned.partial_cmp(&b.mean_burned).unwrap());
+        front
+    }
+
+    /// Render the charts of the strategy summaries `summaries` as SVG documents.
+    /// Charts are omitted if the backend is built without the `charts` feature.
+    fn charts(summaries: &[StrategySummary]) -> Vec<String> {
+        let burned: Vec<_> = summaries.iter()
+            .map(|summary| (summary.strategy_name.clone(), summary.mean_burned))
+            .collect();
+        let millis: Vec<_> = summaries.iter()
+            .map(|summary| (summary.strategy_name.clone(), summary.mean_millis))
+            .collect();
+
+        vec![charts::bar_chart_svg("Mean burned nodes", "Nodes", &burned),
+             charts::bar_chart_svg("Mean simulation time", "Milliseconds", &millis)]
+            .into_iter()
+            .filter_map(|svg| match svg {
+                Ok(svg) => Some(svg),
+                Err(ChartError::Unavailable) => None,
+                Err(err) => {
+                    log::warn!("Failed to render report chart: {}", err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Render this report as self-contained HTML document with all charts and map frames
+    /// embedded
+    pub fn to_html(&self) -> String {
+        let summaries = self.summaries();
+        let graph_names: BTreeSet<_> = self.runs.iter().map(|run| escape_html(&run.graph_name)).collect();
+        let title = escape_html(&self.title);
+
+        let mut html = String::new();
+        writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">").unwrap();
+        writeln!(html, "<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>", title, REPORT_STYLE).unwrap();
+        writeln!(html, "<h1>{}</h1>", title).unwrap();
+        writeln!(html, "<p>{} simulations of {} strategies on {}</p>", self.runs.len(), summaries.len(),
+                 graph_names.into_iter().collect::<Vec<_>>().join(", ")).unwrap();
+
+        writeln!(html, "<h2>Summary</h2>\n<table>").unwrap();
+        writeln!(html, "<tr><th>Strategy</th><th>Runs</th><th>Mean burned</th><th>Min burned</th>\
+            <th>Max burned</th><th>Mean defended</th><th>Mean end time</th>\
+            <th>Mean simulation time (ms)</th></tr>").unwrap();
+        for summary in &summaries {
+            writeln!(html, "<tr><td>{}</td><td>{}</td><td>{:.1}</td><td>{}</td><td>{}</td><td>{:.1}</td>\
+                <td>{:.1}</td><td>{:.1}</td></tr>",
+                     escape_html(&summary.strategy_name), summary.num_runs, summary.mean_burned,
+                     summary.min_burned, summary.max_burned, summary.mean_defended,
+                     summary.mean_end_time, summary.mean_millis).unwrap();
+        }
+        writeln!(html, "</table>").unwrap();
+
+        let sensitivities = self.failure_sensitivities();
+        if !sensitivities.is_empty() {
+            writeln!(html, "<h2>Failure sensitivity</h2>\n<table>").unwrap();
+            writeln!(html, "<tr><th>Strategy</th><th>Failure probability</th><th>Runs</th>\
+                <th>Mean burned</th><th>Increase over reliable defenses</th></tr>").unwrap();
+            for sensitivity in &sensitivities {
+                let increase = sensitivity.burned_increase
+                    .map_or_else(|| "-".to_string(), |increase| format!("{:+.1}", increase));
+                writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.1}</td><td>{}</td></tr>",
+                         escape_html(&sensitivity.strategy_name), sensitivity.defense_failure_prob,
+                         sensitivity.num_runs, sensitivity.mean_burned, increase).unwrap();
+            }
+            writeln!(html, "</table>").unwrap();
+        }
+
+        let front = self.pareto_front();
+        if !front.is_empty() {
+            writeln!(html, "<h2>Pareto-optimal configurations</h2>").unwrap();
+            writeln!(html, "<p>{} of {} configurations are not outperformed on all objectives by \
+                another configuration</p>\n<table>", front.len(), self.configurations().len()).unwrap();
+            writeln!(html, "<tr><th>Strategy</th><th>Firefighters</th><th>Strategy interval</th>\
+                <th>Failure probability</th><th>Runs</th><th>Mean burned</th><th>Mean burned area (km²)</th>\
+                <th>Mean travel distance (m)</th><th>Mean containment time</th></tr>").unwrap();
+            for config in &front {
+                writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.1}</td>\
+                    <td>{:.3}</td><td>{:.0}</td><td>{:.1}</td></tr>",
+                         escape_html(&config.strategy_name), config.num_ffs, config.strategy_every,
+                         config.defense_failure_prob, config.num_runs, config.mean_burned,
+                         config.mean_burned_area_km2, config.mean_travel_distance_m,
+                         config.mean_containment_time).unwrap();
+            }
+            writeln!(html, "</table>").unwrap();
+        }
+
+        let charts = Self::charts(&summaries);
+        if !charts.is_empty() {
+            writeln!(html, "<h2>Charts</h2>").unwrap();
+            for chart in charts {
+                writeln!(html, "<figure>{}</figure>", chart).unwrap();
+            }
+        }
+
+        if !self.frames.is_empty() {
+            writeln!(html, "<h2>Map frames</h2>").unwrap();
+            for frame in &self.frames {
+                let caption = escape_html(&frame.caption);
+                writeln!(html, "<figure><img src=\"data:image/png;base64,{}\" alt=\"{}\">\
+                    <figcaption>{}</figcaption></figure>",
+                         BASE64.encode(&frame.png), caption, caption).unwrap();
+            }
+        }
+
+        writeln!(html, "<h2>Runs</h2>\n<table>").unwrap();
+        writeln!(html, "<tr><th>Strategy</th><th>Graph</th><th>Seed</th><th>Burned</th><th>Defended</th>\
+            <th>End time</th><th>Simulation time (ms)</th></tr>").unwrap();
+        for run in &self.runs {
+            writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                <td>{}</td></tr>",
+                     escape_html(&run.strategy_name), escape_html(&run.graph_name), run.seed,
+                     run.nodes_burned, run.nodes_defended, run.end_time,
+                     run.simulation_time_millis).unwrap();
+        }
+        writeln!(html, "</table>\n</body>\n</html>").unwrap();
+
+        html
+    }
+}
+
+/// Escape all characters of `text` that have a special meaning in HTML
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::firefighter::report::{ReportRun, StrategyReport};
+
+    fn run(strategy_name: &str, nodes_burned: usize) -> ReportRun {
+        ReportRun {
+            graph_name: "bbgrund_undirected".to_string(),
+            strategy_name: strategy_name.to_string(),
+            seed: 42,
+            num_ffs: 2,
+            strategy_every: 5,
+            defense_failure_prob: 0.0,
+            nodes_burned,
+            nodes_defended: 10,
+            burned_area_km2: Some(nodes_burned as f64 / 100.0),
+            travel_distance_m: Some(1000.0),
+            end_time: 20,
+            simulation_time_millis: 5,
+        }
+    }
+
+    #[test]
+    fn test_report_html() {
+        let mut report = StrategyReport::new("Greedy vs. <Priority>");
+        report.add_run(run("Greedy", 100));
+        report.add_run(run("Greedy", 200));
+        report.add_run(run("<Priority>", 50));
+        report.add_frame("Greedy", vec![1, 2, 3]);
+
+        let summaries = report.summaries();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[1].strategy_name, "Greedy");
+        assert_eq!(summaries[1].num_runs, 2);
+        assert_eq!(summaries[1].mean_burned, 150.0);
+        assert_eq!((summaries[1].min_burned, summaries[1].max_burned), (100, 200));
+
+        let html = report.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>Greedy vs. &lt;Priority&gt;</h1>"));
+        assert!(html.contains("<td>&lt;Priority&gt;</td>"));
+        assert!(!html.contains("<Priority>"));
+        assert!(html.contains("<p>3 simulations of 2 strategies on bbgrund_undirected</p>"));
+        assert!(html.contains("<td>Greedy</td><td>2</td><td>150.0</td><td>100</td><td>200</td>"));
+        assert!(html.contains("src=\"data:image/png;base64,AQID\""));
+        assert!(html.trim_end().ends_with("</html>"));
+        assert!(!html.contains("Failure sensitivity"));
+    }
+
+    #[test]
+    fn test_failure_sensitivity() {
+        let mut report = StrategyReport::new("Failure injection");
+        report.add_run(run("Greedy", 100));
+        for (nodes_burned, defense_failure_prob) in [(130, 0.2), (170, 0.2), (200, 0.5)] {
+            report.add_run(ReportRun { defense_failure_prob, ..run("Greedy", nodes_burned) });
+        }
+        report.add_run(ReportRun { defense_failure_prob: 0.2, ..run("Priority", 80) });
+
+        let sensitivities = report.failure_sensitivities();
+        assert_eq!(sensitivities.len(), 4);
+        assert_eq!(sensitivities[1].defense_failure_prob, 0.2);
+        assert_eq!(sensitivities[1].num_runs, 2);
+        assert_eq!(sensitivities[1].burned_increase, Some(50.0));
+        assert_eq!(sensitivities[2].burned_increase, Some(100.0));
+        assert_eq!(sensitivities[3].burned_increase, None);
+
+        let html = report.to_html();
+        assert!(html.contains("<td>Greedy</td><td>0.2</td><td>2</td><td>150.0</td><td>+50.0</td>"));
+        assert!(html.contains("<td>Priority</td><td>0.2</td><td>1</td><td>80.0</td><td>-</td>"));
+    }
+    #[test]
+    fn test_pareto_front() {
+        let mut report = StrategyReport::new("Pareto analysis");
+        report.add_run(run("Greedy", 100));
+        report.add_run(run("Greedy", 120));
+        // Burns less but travels further
+        report.add_run(ReportRun { travel_distance_m: Some(5000.0), ..run("Priority", 80) });
+        // Dominated by Greedy
+        report.add_run(ReportRun { end_time: 30, ..run("Random", 200) });
+        // More firefighters are a different configuration, and unknown objectives are ignored
+        report.add_run(ReportRun { num_ffs: 4, travel_distance_m: Some(3000.0), ..run("Random", 90) });
+        report.add_run(ReportRun { travel_distance_m: None, ..run("Score", 10) });
+
+        assert_eq!(report.configurations().len(), 4);
+        let front = report.pareto_front();
+        let names: Vec<_> = front.iter()
+            .map(|config| (config.strategy_name.as_str(), config.num_ffs))
+            .collect();
+        assert_eq!(names, vec![("Priority", 2), ("Random", 4), ("Greedy", 2)]);
+        assert_eq!(front[2].num_runs, 2);
+        assert_eq!(front[2].mean_burned, 110.0);
+
+        let html = report.to_html();
+        assert!(html.contains("<p>3 of 4 configurations are not outperformed"));
+        assert!(html.contains("<td>Priority</td><td>2</td><td>5</td><td>0</td><td>1</td><td>80.0</td>"));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::firefighter::TimeUnit;
+
+/// Compressed state of a firefighter simulation at the end of some round, i.e. the times at which
+/// the nodes burning or defended by then started burning or were defended. The `(node id, time)`
+/// pairs of each kind are ordered by node id and stored as variable-length integers, with node ids
+/// as differences to their predecessor, which mostly take one or two bytes per pair.
+#[derive(Debug)]
+pub(super) struct StateSnapshot {
+    burned: Vec<u8>,
+    defended: Vec<u8>,
+}
+
+impl StateSnapshot {
+    /// Create the snapshot of the burning times `burned` and the defense times `defended` of all
+    /// burning and defended nodes, given as `(node id, time)` pairs
+    pub(super) fn new(burned: impl Iterator<Item = (usize, TimeUnit)>,
+                      defended: impl Iterator<Item = (usize, TimeUnit)>) -> Self {
+        Self {
+            burned: encode(burned.collect()),
+            defended: encode(defended.collect()),
+        }
+    }
+
+    /// Iterate over the `(node id, time)` pairs of all burning nodes ordered by node id
+    pub(super) fn burned(&self) -> impl Iterator<Item = (usize, TimeUnit)> + '_ {
+        decode(&self.burned)
+    }
+
+    /// Iterate over the `(node id, time)` pairs of all defended nodes ordered by node id
+    pub(super) fn defended(&self) -> impl Iterator<Item = (usize, TimeUnit)> + '_ {
+        decode(&self.defended)
+    }
+
+    /// Get the approximate number of bytes occupied by this snapshot
+    pub(super) fn size_bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.burned.capacity() + self.defended.capacity()
+    }
+}
+
+/// Encode the `(node id, time)` pairs `changes` ordered by node id
+fn encode(mut changes: Vec<(usize, TimeUnit)>) -> Vec<u8> {
+    changes.sort_unstable();
+    let mut bytes = Vec::with_capacity(changes.len() * 2);
+    let mut prev_node_id = 0;
+    for (node_id, time) in changes {
+        write_varint(&mut bytes, (node_id - prev_node_id) as u64);
+        write_varint(&mut bytes, time);
+        prev_node_id = node_id;
+    }
+    bytes.shrink_to_fit();
+    bytes
+}
+
+/// Decode the `(node id, time)` pairs encoded in `bytes`
+fn decode(mut bytes: &[u8]) -> impl Iterator<Item = (usize, TimeUnit)> + '_ {
+    let mut node_id = 0;
+    std::iter::from_fn(move || {
+        if bytes.is_empty() {
+            return None;
+        }
+        node_id += read_varint(&mut bytes) as usize;
+        let time = read_varint(&mut bytes);
+        Some((node_id, time))
+    })
+}
+
+/// Append `value` to `bytes` as LEB128 variable-length integer, i.e. in groups of 7 bits with the
+/// highest bit of every byte set if more bytes follow
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Read a LEB128 variable-length integer from the start of `bytes` and advance `bytes` past it
+fn read_varint(bytes: &mut &[u8]) -> u64 {
+    let mut value = 0;
+    let mut shift = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return value;
+        }
+        shift += 7;
+    }
+    unreachable!("Snapshots only contain complete variable-length integers")
+}
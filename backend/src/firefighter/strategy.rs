@@ -1,20 +1,145 @@
+use std::cell::RefCell;
 use std::cmp::min;
 use std::collections::{BTreeMap, HashMap, VecDeque, HashSet};
 use std::fmt::Debug;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
+use geo::{HaversineDistance, Point};
+use once_cell::sync::OnceCell;
 use rand::prelude::*;
 use rand::seq::SliceRandom;
 
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
 use strum::VariantNames;
-use strum_macros::{EnumString, EnumVariantNames};
+use strum_macros::{EnumVariantNames, IntoStaticStr};
 
+use crate::alloc_stats;
+use crate::firefighter::plugin::{self, PluginStrategy};
 use crate::firefighter::problem::{NodeDataStorage, OSMFSettings};
 use crate::firefighter::TimeUnit;
-use crate::graph::Graph;
+use crate::graph::{GraphView, IncrementalDistances};
+
+/// Number of entries of large intermediate results of strategies, like distance maps, that are
+/// logged unless verbose strategy logs are enabled
+const LOG_SUMMARY_ENTRIES: usize = 10;
+
+/// Has the deadline `deadline` of an execution of a strategy passed?
+fn is_past(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Debug output settings of the strategies, see `init_strategy_logs`
+static STRATEGY_LOGS: OnceCell<StrategyLogs> = OnceCell::new();
+
+thread_local! {
+    /// Debug file of the simulation running on this thread, if any, see `with_debug_file`
+    static DEBUG_FILE: RefCell<Option<BufWriter<File>>> = const { RefCell::new(None) };
+}
+
+/// Debug output settings of the strategies
+#[derive(Debug, Default)]
+struct StrategyLogs {
+    /// Whether large intermediate results are logged in full instead of summarized
+    verbose: bool,
+    /// Directory of the debug files of simulations, which receive the full intermediate results
+    debug_path: Option<PathBuf>,
+}
+
+/// Set whether strategies log their large intermediate results, like distance maps, priority maps
+/// and scores, in full (`verbose`) or only their number of entries and first entries, and the
+/// directory `debug_path` in which every simulation writes the full results to its own debug file.
+/// The settings can only be initialized once, before any simulation runs.
+pub fn init_strategy_logs(verbose: bool, debug_path: Option<&str>) {
+    let logs = StrategyLogs {
+        verbose,
+        debug_path: debug_path.map(PathBuf::from),
+    };
+    if STRATEGY_LOGS.set(logs).is_err() {
+        log::warn!("Strategy logs have already been initialized");
+    }
+}
+
+/// Get the debug output settings of the strategies
+fn strategy_logs() -> &'static StrategyLogs {
+    STRATEGY_LOGS.get_or_init(StrategyLogs::default)
+}
+
+/// Get the configured directory of the debug files of simulations, if any
+pub(super) fn debug_path() -> Option<&'static Path> {
+    strategy_logs().debug_path.as_deref()
+}
+
+/// Run `f` on the current thread and write the full intermediate results of the strategies it
+/// executes to a new debug file named after `name` in the directory `debug_path`, if any
+pub(super) fn with_debug_file<R>(debug_path: Option<&Path>, name: &str, f: impl FnOnce() -> R) -> R {
+    let file = debug_path.and_then(|dir| {
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_millis());
+        let path = dir.join(format!("{}-{}.log", name, millis));
+        match fs::create_dir_all(dir).and_then(|_| File::create(&path)) {
+            Ok(file) => {
+                log::info!("Writing strategy debug output to {}", path.display());
+                Some(BufWriter::new(file))
+            }
+            Err(err) => {
+                log::warn!("Failed to create strategy debug file {}: {}", path.display(), err);
+                None
+            }
+        }
+    });
+
+    /// Restores the debug file of the enclosing simulation, even if `f` panics
+    struct DebugFileGuard(Option<BufWriter<File>>);
+    impl Drop for DebugFileGuard {
+        fn drop(&mut self) {
+            DEBUG_FILE.with(|debug_file| debug_file.replace(self.0.take()));
+        }
+    }
+    let _guard = DebugFileGuard(DEBUG_FILE.with(|debug_file| debug_file.replace(file)));
+    f()
+}
+
+/// Log the large intermediate result `entries` of a strategy, e.g. a distance map, described by
+/// `description`. Unless verbose strategy logs are enabled, only the number of entries and the
+/// first `LOG_SUMMARY_ENTRIES` entries are logged. All entries are written to the debug file of
+/// the running simulation, if any.
+fn log_entries<I>(description: &str, entries: I)
+    where I: IntoIterator, I::IntoIter: ExactSizeIterator + Clone, I::Item: Debug {
+    let entries = entries.into_iter();
+    let has_debug_file = DEBUG_FILE.with(|debug_file| debug_file.borrow().is_some());
+    if !has_debug_file && !log::log_enabled!(log::Level::Debug) {
+        return;
+    }
+
+    let num_entries = entries.len();
+    if strategy_logs().verbose {
+        log::debug!("{} ({} entries):\n{:?}", description, num_entries, entries.clone().collect::<Vec<_>>());
+    } else {
+        log::debug!("{} ({} entries), first {}: {:?}", description, num_entries,
+                    num_entries.min(LOG_SUMMARY_ENTRIES),
+                    entries.clone().take(LOG_SUMMARY_ENTRIES).collect::<Vec<_>>());
+    }
+
+    if has_debug_file {
+        DEBUG_FILE.with(|debug_file| {
+            if let Some(writer) = debug_file.borrow_mut().as_mut() {
+                let result = writeln!(writer, "{} ({} entries):", description, num_entries)
+                    .and_then(|_| entries.into_iter().try_for_each(|entry| writeln!(writer, "{:?}", entry)));
+                if let Err(err) = result {
+                    log::warn!("Failed to write strategy debug file: {}", err);
+                }
+            }
+        });
+    }
+}
 
 /// Strategy to contain the fire in the firefighter problem
-#[derive(Debug, EnumString, EnumVariantNames)]
+#[derive(Debug, Clone, EnumVariantNames, IntoStaticStr)]
 #[strum(serialize_all = "PascalCase")]
 pub enum OSMFStrategy {
     Greedy(GreedyStrategy),
@@ -23,18 +148,34 @@ pub enum OSMFStrategy {
     SingleMinDistanceSet(SingleMinDistSetStrategy),
     Priority(PriorityStrategy),
     Random(RandomStrategy),
+    KServer(KServerStrategy),
+    Ensemble(EnsembleStrategy),
+    ReplayPlan(ReplayPlanStrategy),
+    /// Strategy of a registered plugin, which is listed under the name of the plugin strategy
+    Plugin(PluginStrategy),
 }
 
 impl OSMFStrategy {
-    /// Returns a list of available fire containment strategies
+    /// Returns a list of available fire containment strategies, i.e. the built-in strategies
+    /// followed by the strategies of all registered plugins
     pub fn available_strategies() -> Vec<String> {
         Self::VARIANTS.iter()
+            .filter(|&&name| name != "Plugin")
+            .chain(plugin::plugin_names().iter())
             .map(<&str>::to_string)
             .collect::<Vec<_>>()
     }
 
+    /// Returns the canonical name of this strategy
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Plugin(ref strategy) => strategy.name(),
+            _ => self.into()
+        }
+    }
+
     /// Return a new strategy with given name that operates on given graph
-    pub fn from_name_and_graph(strategy_name: &str, graph: Arc<Graph>) -> Option<Self> {
+    pub fn from_name_and_graph(strategy_name: &str, graph: Arc<dyn GraphView>) -> Option<Self> {
         match strategy_name {
             "Greedy" => Some(Self::Greedy(GreedyStrategy::new(graph))),
             "Score" => Some(Self::Score(ScoreStrategy::new(graph))),
@@ -42,7 +183,11 @@ impl OSMFStrategy {
             "SingleMinDistanceSet" => Some(Self::SingleMinDistanceSet(SingleMinDistSetStrategy::new(graph))),
             "Priority" => Some(Self::Priority(PriorityStrategy::new(graph))),
             "Random" => Some(Self::Random(RandomStrategy::new(graph))),
-            _ => None
+            "KServer" => Some(Self::KServer(KServerStrategy::new(graph))),
+            "Ensemble" => Some(Self::Ensemble(EnsembleStrategy::new(graph))),
+            "ReplayPlan" => Some(Self::ReplayPlan(ReplayPlanStrategy::new(graph))),
+            _ => plugin::find_plugin(strategy_name)
+                .map(|plugin| Self::Plugin(PluginStrategy::new(plugin, graph)))
         }
     }
 
@@ -55,9 +200,67 @@ impl OSMFStrategy {
             Self::SingleMinDistanceSet(ref mut strategy) => strategy.as_mut_strategy(),
             Self::Priority(ref mut strategy) => strategy.as_mut_strategy(),
             Self::Random(ref mut strategy) => strategy.as_mut_strategy(),
+            Self::KServer(ref mut strategy) => strategy.as_mut_strategy(),
+            Self::Ensemble(ref mut strategy) => strategy.as_mut_strategy(),
+            Self::ReplayPlan(ref mut strategy) => strategy.as_mut_strategy(),
+            Self::Plugin(ref mut strategy) => strategy.as_mut_strategy(),
         }
     }
 
+    /// Returns strategy-specific diagnostics about the executions of this strategy instance
+    pub(super) fn report(&self) -> Value {
+        match self {
+            Self::Greedy(ref strategy) => strategy.report(),
+            Self::Score(ref strategy) => strategy.report(),
+            Self::MultiMinDistanceSets(ref strategy) => strategy.report(),
+            Self::SingleMinDistanceSet(ref strategy) => strategy.report(),
+            Self::Priority(ref strategy) => strategy.report(),
+            Self::Random(ref strategy) => strategy.report(),
+            Self::KServer(ref strategy) => strategy.report(),
+            Self::Ensemble(ref strategy) => strategy.report(),
+            Self::ReplayPlan(ref strategy) => strategy.report(),
+            Self::Plugin(ref strategy) => strategy.report(),
+        }
+    }
+
+    /// Release all buffers of this strategy instance that are only needed while simulating
+    pub(super) fn release_buffers(&mut self) {
+        self.mut_inner().release_buffers();
+    }
+
+    /// Notify this strategy that the nodes `roots` ignited in an additional ignition wave, such
+    /// that it can re-plan which nodes to defend
+    pub(super) fn on_ignition(&mut self, roots: &Vec<usize>, settings: &OSMFSettings, node_data: &NodeDataStorage) {
+        match self {
+            Self::MultiMinDistanceSets(ref mut strategy) => {
+                strategy.initialize_undefended_roots(roots);
+                let undefended_roots: Vec<_> = strategy.undefended_roots.keys().copied().collect();
+                strategy.compute_nodes_to_defend(&undefended_roots, settings, node_data);
+                strategy.num_replans += 1;
+            }
+            Self::SingleMinDistanceSet(ref mut strategy) => {
+                let all_roots: Vec<_> = node_data.get_burning().into_iter()
+                    .filter(|node_id| node_data.is_root(node_id))
+                    .collect();
+                strategy.compute_nodes_to_defend(&all_roots, settings);
+                strategy.nodes_to_defend.retain(|node_id| node_data.is_undefended(node_id));
+                strategy.current_defended = 0;
+            }
+            Self::Priority(ref mut strategy) => {
+                strategy.initialize_undefended_roots(roots);
+                let undefended_roots: Vec<_> = strategy.undefended_roots.keys().copied().collect();
+                strategy.compute_nodes_to_defend(&undefended_roots, settings, node_data);
+                strategy.num_replans += 1;
+            }
+            Self::Ensemble(ref mut strategy) => {
+                for member in &mut strategy.members {
+                    member.on_ignition(roots, settings, node_data);
+                }
+            }
+            _ => ()
+        };
+    }
+
     pub(super) fn initialize(&mut self, roots: &Vec<usize>, settings: &OSMFSettings, node_data: &NodeDataStorage) {
         match self {
             Self::MultiMinDistanceSets(ref mut strategy) => {
@@ -71,6 +274,12 @@ impl OSMFStrategy {
                 strategy.initialize_undefended_roots(roots);
                 strategy.compute_nodes_to_defend(roots, settings, node_data);
             }
+            Self::Ensemble(ref mut strategy) => {
+                strategy.initialize_members(settings);
+                for member in &mut strategy.members {
+                    member.initialize(roots, settings, node_data);
+                }
+            }
             _ => ()
         };
     }
@@ -78,11 +287,20 @@ impl OSMFStrategy {
 
 /// Strategy trait that each strategy needs to implement
 pub(super) trait Strategy {
-    /// Create a new fire containment strategy instance
-    fn new (graph: Arc<Graph>) -> Self where Self: Sized;
+    /// Execute the fire containment strategy. Strategies that plan expensively stop planning
+    /// once the deadline `deadline` has passed, if any, and may leave firefighters unused.
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               deadline: Option<Instant>);
+
+    /// Returns strategy-specific diagnostics about the executions of the fire containment
+    /// strategy, or `null` if the strategy does not report any
+    fn report(&self) -> Value {
+        Value::Null
+    }
 
-    /// Execute the fire containment strategy
-    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit);
+    /// Release all buffers that are only needed while executing the fire containment strategy.
+    /// Called once the simulation has finished and after the final `report`.
+    fn release_buffers(&mut self) {}
 
     /// Returns a mutable reference to the fire containment strategy as an object of
     /// the `Strategy` trait
@@ -92,26 +310,34 @@ pub(super) trait Strategy {
 }
 
 /// Greedy fire containment strategy
-#[derive(Debug, Default)]
+#[derive(Debug, Clone)]
 pub struct GreedyStrategy {
-    graph: Arc<Graph>,
+    graph: Arc<dyn GraphView>,
+    num_executions: usize,
+    max_candidates: usize,
 }
 
-impl Strategy for GreedyStrategy {
-    fn new(graph: Arc<Graph>) -> Self {
+impl GreedyStrategy {
+    /// Create a new strategy instance that operates on the graph `graph`
+    pub fn new(graph: Arc<dyn GraphView>) -> Self {
         Self {
             graph,
+            num_executions: 0,
+            max_candidates: 0,
         }
     }
+}
 
-    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit) {
+impl Strategy for GreedyStrategy {
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               _deadline: Option<Instant>) {
         let burning = node_data.get_burning();
 
         // Get all edges with targets that are not burned or defended yet
         let mut edges = Vec::new();
         for node_id in burning {
             for edge in self.graph.get_outgoing_edges(node_id) {
-                if node_data.is_undefended(&edge.tgt) {
+                if node_data.is_undefended(&edge.tgt()) {
                     edges.push(edge);
                 }
             }
@@ -119,48 +345,75 @@ impl Strategy for GreedyStrategy {
 
         // Sort the edges by their weight and by the _out degree_ of their targets
         edges.sort_unstable_by(|&e1, &e2|
-            e1.dist.cmp(&e2.dist).then_with(|| {
-                let tgt1_deg = self.graph.get_node_degree(e1.tgt);
-                let tgt2_deg = self.graph.get_node_degree(e2.tgt);
+            e1.dist().cmp(&e2.dist()).then_with(|| {
+                let tgt1_deg = self.graph.get_node_degree(e1.tgt());
+                let tgt2_deg = self.graph.get_node_degree(e2.tgt());
                 tgt2_deg.cmp(&tgt1_deg)
             }));
 
         // Defend as many targets as firefighters are available
         let num_to_defend = min(edges.len(), settings.num_ffs);
         let to_defend: Vec<_> = edges[0..num_to_defend].iter()
-            .map(|&e| e.tgt)
+            .map(|&e| e.tgt())
             .collect();
         node_data.mark_defended(&to_defend, global_time);
+
+        self.num_executions += 1;
+        self.max_candidates = self.max_candidates.max(edges.len());
+    }
+
+    fn report(&self) -> Value {
+        json!({
+            "num_executions": self.num_executions,
+            "max_candidates": self.max_candidates,
+        })
     }
 }
 
-/// Score based fire containment strategy
-#[derive(Debug, Default)]
+/// Score based fire containment strategy. Nodes are scored by their distance to the fire and
+/// their degree, and on graphs with node weights also by their weight.
+#[derive(Debug, Clone)]
 pub struct ScoreStrategy {
-    graph: Arc<Graph>,
+    graph: Arc<dyn GraphView>,
     node_degrees: Vec<usize>,
+    /// Distances of all nodes to the fire, which are updated as the fire spreads
+    fire_dists: IncrementalDistances,
+    num_executions: usize,
+    max_candidates: usize,
 }
 
-impl Strategy for ScoreStrategy {
-    fn new(graph: Arc<Graph>) -> Self {
+impl ScoreStrategy {
+    /// Create a new strategy instance that operates on the graph `graph`
+    pub fn new(graph: Arc<dyn GraphView>) -> Self {
         // Store node degrees
-        let node_degrees: Vec<_> = graph.nodes().iter()
-            .map(|node| graph.get_node_degree(node.id))
+        let node_degrees: Vec<_> = graph.iter_nodes()
+            .map(|node| graph.get_node_degree(node.id()))
             .collect();
         Self {
             graph,
             node_degrees,
+            fire_dists: IncrementalDistances::new(),
+            num_executions: 0,
+            max_candidates: 0,
         }
     }
+}
 
-    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit) {
-        // Run burning-to-all dijkstra to compute shortest distances for all nodes to the fire
-        let dists = self.graph.run_dijkstra(node_data.get_burning().as_slice());
+impl Strategy for ScoreStrategy {
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               deadline: Option<Instant>) {
+        // Compute shortest distances for all nodes to the fire. Nodes keep burning, so only the
+        // nodes that started burning since the last execution have to be searched from.
+        self.fire_dists.add_sources(&*self.graph, node_data.get_burning().as_slice());
+        let dists = self.fire_dists.distances();
+        if is_past(deadline) {
+            return;
+        }
 
         // Compute max distance for normalization
-        let maybe_max_dist = self.graph.nodes().iter()
-            .filter(|&node| node_data.is_undefended(&node.id) && dists[node.id] < usize::MAX)
-            .map(|node| dists[node.id])
+        let maybe_max_dist = self.graph.iter_nodes()
+            .filter(|&node| node_data.is_undefended(&node.id()) && dists[node.id()] < usize::MAX)
+            .map(|node| dists[node.id()])
             .max();
         let max_dist = match maybe_max_dist {
             Some(max_dist) => max_dist,
@@ -168,26 +421,37 @@ impl Strategy for ScoreStrategy {
         };
 
         // Compute max degree for normalization
-        let max_deg = self.graph.nodes().iter()
-            .filter(|&node| node_data.is_undefended(&node.id) && dists[node.id] < usize::MAX)
-            .map(|node| self.node_degrees[node.id])
+        let max_deg = self.graph.iter_nodes()
+            .filter(|&node| node_data.is_undefended(&node.id()) && dists[node.id()] < usize::MAX)
+            .map(|node| self.node_degrees[node.id()])
             .max()
             .unwrap(); // Unwrap because iterator cannot be empty
 
+        // Weights are normalized by the greatest weight of all nodes
+        let weights = self.graph.node_weights()
+            .map(|weights| (weights, weights.max_weight()))
+            .filter(|&(_, max_weight)| max_weight > 0.0);
+
         // Compute normalized scores and sort them in descending order
-        let mut scores: Vec<_> = self.graph.nodes().iter()
-            .filter(|&node| node_data.is_undefended(&node.id) && dists[node.id] < usize::MAX)
+        let mut scores: Vec<_> = self.graph.iter_nodes()
+            .filter(|&node| node_data.is_undefended(&node.id()) && dists[node.id()] < usize::MAX)
             .map(|node| {
-                let norm_dist_score = 1.0 - dists[node.id] as f64 / max_dist as f64;
-                let norm_deg_score = self.node_degrees[node.id] as f64 / max_deg as f64;
-                let score = (2.0 * norm_dist_score + norm_deg_score) / 3.0;
-                (node.id, score)
+                let norm_dist_score = 1.0 - dists[node.id()] as f64 / max_dist as f64;
+                let norm_deg_score = self.node_degrees[node.id()] as f64 / max_deg as f64;
+                let score = match weights {
+                    Some((weights, max_weight)) => {
+                        let norm_weight_score = weights.weight(node.id()).max(0.0) / max_weight;
+                        (2.0 * norm_dist_score + norm_deg_score + 2.0 * norm_weight_score) / 5.0
+                    }
+                    None => (2.0 * norm_dist_score + norm_deg_score) / 3.0
+                };
+                (node.id(), score)
             })
             .collect();
         scores.sort_unstable_by(|(_, score1), &(_, score2)|
             score2.partial_cmp(score1).unwrap());
 
-        log::debug!("Scores: {:?}", &scores);
+        log_entries("Scores", &scores);
 
         // Defend as many targets as firefighters are available
         let num_to_defend = min(scores.len(), settings.num_ffs);
@@ -195,6 +459,21 @@ impl Strategy for ScoreStrategy {
             .map(|&(node_id, _)| node_id)
             .collect();
         node_data.mark_defended(&to_defend, global_time);
+
+        self.num_executions += 1;
+        self.max_candidates = self.max_candidates.max(scores.len());
+    }
+
+    fn report(&self) -> Value {
+        json!({
+            "num_executions": self.num_executions,
+            "max_candidates": self.max_candidates,
+        })
+    }
+
+    fn release_buffers(&mut self) {
+        self.node_degrees = Vec::new();
+        self.fire_dists = IncrementalDistances::new();
     }
 }
 
@@ -204,7 +483,7 @@ type Visited = HashSet<usize>;
 type RiskyNodes = HashSet<usize>;
 
 fn compute_undefended_roots(undefended_roots: &mut HashMap<usize, (Visited, RiskyNodes)>,
-                            graph: &Arc<Graph>, node_data: &NodeDataStorage) -> Option<Vec<usize>> {
+                            graph: &Arc<dyn GraphView>, node_data: &NodeDataStorage) -> Option<Vec<usize>> {
     for (_, (visited, risky_nodes)) in undefended_roots.iter_mut() {
         // Filter all burning risky nodes
         let mut burning: VecDeque<_> = risky_nodes.iter()
@@ -225,10 +504,10 @@ fn compute_undefended_roots(undefended_roots: &mut HashMap<usize, (Visited, Risk
             risky_nodes.reserve(out_deg);
             burning.reserve(out_deg);
             for edge in graph.get_outgoing_edges(node) {
-                if node_data.is_undefended(&edge.tgt) {
-                    risky_nodes.insert(edge.tgt);
-                } else if node_data.is_burning(&edge.tgt) && !visited.contains(&edge.tgt) {
-                    burning.push_back(edge.tgt);
+                if node_data.is_undefended(&edge.tgt()) {
+                    risky_nodes.insert(edge.tgt());
+                } else if node_data.is_burning(&edge.tgt()) && !visited.contains(&edge.tgt()) {
+                    burning.push_back(edge.tgt());
                 }
             }
         }
@@ -250,10 +529,10 @@ fn compute_undefended_roots(undefended_roots: &mut HashMap<usize, (Visited, Risk
 
 /// For every node, compute the minimum shortest distance between the node and any fire root.
 /// Then, group the nodes by minimum shortest distance.
-fn group_nodes_by_distance(undefended_roots: &Vec<usize>, graph: &Arc<Graph>,
+fn group_nodes_by_distance(undefended_roots: &Vec<usize>, graph: &Arc<dyn GraphView>,
                            node_data: &NodeDataStorage) -> BTreeMap<usize, Vec<usize>> {
-    let dists = graph.run_dijkstra(undefended_roots.as_slice());
-    let mut sho_dists = HashMap::with_capacity(graph.num_nodes);
+    let dists = graph.run_dijkstra_par(undefended_roots.as_slice());
+    let mut sho_dists = HashMap::with_capacity(graph.num_nodes());
     for (node, &dist) in dists.iter().enumerate() {
         if node_data.is_undefended(&node) && dist < usize::MAX {
             sho_dists.insert(node, dist);
@@ -267,22 +546,34 @@ fn group_nodes_by_distance(undefended_roots: &Vec<usize>, graph: &Arc<Graph>,
             .or_insert(vec![node_id]);
     }
 
-    log::debug!("Computed distance sets:\n{:?}", &nodes_by_sho_dist);
+    log_entries("Computed distance sets", &nodes_by_sho_dist);
 
     nodes_by_sho_dist
 }
 
 /// Shortest distance based fire containment strategy
 /// that selects multiple sets to defend
-#[derive(Debug, Default)]
+#[derive(Debug, Clone)]
 pub struct MultiMinDistSetsStrategy {
-    graph: Arc<Graph>,
+    graph: Arc<dyn GraphView>,
     nodes_to_defend: VecDeque<usize>,
     possible_defended: usize,
     undefended_roots: HashMap<usize, (Visited, RiskyNodes)>,
+    num_replans: usize,
 }
 
 impl MultiMinDistSetsStrategy {
+    /// Create a new strategy instance that operates on the graph `graph`
+    pub fn new(graph: Arc<dyn GraphView>) -> Self {
+        Self {
+            graph,
+            nodes_to_defend: VecDeque::new(),
+            possible_defended: 0,
+            undefended_roots: HashMap::new(),
+            num_replans: 0,
+        }
+    }
+
     /// Initialize the undefended roots datastructure
     pub(super) fn initialize_undefended_roots(&mut self, roots: &Vec<usize>) {
         self.undefended_roots.reserve(roots.len());
@@ -374,53 +665,78 @@ impl MultiMinDistSetsStrategy {
 }
 
 impl Strategy for MultiMinDistSetsStrategy {
-    fn new(graph: Arc<Graph>) -> Self {
-        Self {
-            graph,
-            nodes_to_defend: VecDeque::new(),
-            possible_defended: 0,
-            undefended_roots: HashMap::new(),
-        }
-    }
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               deadline: Option<Instant>) {
+        // Other strategies of an ensemble may have acted since the nodes were planned
+        self.nodes_to_defend.retain(|node| node_data.is_undefended(node));
+        self.nodes_to_defend.make_contiguous();
 
-    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit) {
         let num_to_defend = min(settings.num_ffs, self.nodes_to_defend.len());
         let len = self.nodes_to_defend.len();
         let to_defend = &self.nodes_to_defend.as_slices().0[(len-num_to_defend)..len];
 
-        for node in to_defend {
-            assert!(node_data.is_undefended(node));
-        }
-
         node_data.mark_defended(to_defend, global_time);
 
         self.nodes_to_defend.truncate(len-num_to_defend);
         self.possible_defended += settings.num_ffs;
 
+        // Replanning is skipped once the deadline has passed
+        if is_past(deadline) {
+            return;
+        }
+
         // One or more fire roots have been defended and hence shouldn't be considered
         // in the min_distance_groups anymore
         if let Some(roots) = self.compute_undefended_roots(node_data) {
             self.compute_nodes_to_defend(&roots, settings, node_data);
+            self.num_replans += 1;
         }
     }
+
+    fn report(&self) -> Value {
+        json!({
+            "num_replans": self.num_replans,
+            "final_queue_length": self.nodes_to_defend.len(),
+        })
+    }
+
+    fn release_buffers(&mut self) {
+        self.nodes_to_defend = VecDeque::new();
+        self.undefended_roots = HashMap::new();
+    }
 }
 
 /// Shortest distance based fire containment strategy
 /// that selects
-#[derive(Debug, Default)]
+#[derive(Debug, Clone)]
 pub struct SingleMinDistSetStrategy {
-    graph: Arc<Graph>,
+    graph: Arc<dyn GraphView>,
+    /// Distances of all nodes to the fire roots, which are updated by additional ignition waves
+    root_dists: IncrementalDistances,
     nodes_to_defend: Vec<usize>,
     current_defended: usize,
+    selected_distance: Option<usize>,
 }
 
 impl SingleMinDistSetStrategy {
+    /// Create a new strategy instance that operates on the graph `graph`
+    pub fn new(graph: Arc<dyn GraphView>) -> Self {
+        Self {
+            graph,
+            root_dists: IncrementalDistances::new(),
+            nodes_to_defend: vec![],
+            current_defended: 0,
+            selected_distance: None,
+        }
+    }
+
     /// Compute nodes to defend and order in which nodes should be defended
     pub(super) fn compute_nodes_to_defend(&mut self, roots: &Vec<usize>, settings: &OSMFSettings) {
         // For each root, run an one-to-all Dijkstra to all nodes in the underlying graph.
         // Then, filter the distances to the nodes for the minimum distance from any fire root.
-        let dists = self.graph.run_dijkstra(roots.as_slice());
-        let mut global_dists = HashMap::with_capacity(self.graph.num_nodes);
+        // Roots of previous ignition waves have been searched from before.
+        let dists = self.root_dists.add_sources(&*self.graph, roots.as_slice());
+        let mut global_dists = HashMap::with_capacity(self.graph.num_nodes());
         for (node, &dist) in dists.iter().enumerate() {
             if dist < usize::MAX {
                 global_dists.insert(node, dist);
@@ -429,21 +745,21 @@ impl SingleMinDistSetStrategy {
 
         // For each node, get its predecessor with the lowest _global distance_ and
         // store that predecessor as its respective _global predecessor_
-        let mut global_preds = vec![usize::MAX; self.graph.num_nodes];
-        for edge in self.graph.edges() {
-            let cur_pred = global_preds[edge.tgt];
+        let mut global_preds = vec![usize::MAX; self.graph.num_nodes()];
+        for edge in self.graph.iter_edges() {
+            let cur_pred = global_preds[edge.tgt()];
             if cur_pred < usize::MAX {
                 let cur_dist = global_dists[&cur_pred];
-                let dist = global_dists[&edge.src];
+                let dist = global_dists[&edge.src()];
                 if dist < cur_dist {
-                    global_preds[edge.tgt] = edge.src;
+                    global_preds[edge.tgt()] = edge.src();
                 }
-            } else if global_dists.contains_key(&edge.src) {
-                global_preds[edge.tgt] = edge.src;
+            } else if global_dists.contains_key(&edge.src()) {
+                global_preds[edge.tgt()] = edge.src();
             }
         }
 
-        log::debug!("Global distances:\n{:?}", &global_dists);
+        log_entries("Global distances", &global_dists);
 
         // Transform the global distance map into a data structure that maps each distance
         // to the nodes that have to be defended in order to protect all nodes with a higher
@@ -460,55 +776,70 @@ impl SingleMinDistSetStrategy {
             }
         }
 
-        log::debug!("Distance nodes map:\n{:?}", &distance_nodes_map);
+        log_entries("Distance nodes map", &distance_nodes_map);
 
         let strategy_every = settings.strategy_every as usize;
         let num_ffs = settings.num_ffs as usize;
 
         let maybe_nodes = distance_nodes_map.into_iter()
-            .find_map(|(dist, nodes)|
-                if nodes.len() <= dist / strategy_every * num_ffs {
-                    log::debug!("Selected {} nodes to defend: {:?} with distance {}",
-                            nodes.len(), &nodes, dist);
-                    Some(nodes)
-                } else {
-                    None
-                }
-            );
-        if let Some(nodes) = maybe_nodes {
+            .find(|(dist, nodes)| nodes.len() <= dist / strategy_every * num_ffs);
+        if let Some((dist, nodes)) = maybe_nodes {
+            log::debug!("Selected {} nodes to defend with distance {}", nodes.len(), dist);
+            log_entries("Selected nodes to defend", &nodes);
             self.nodes_to_defend = nodes;
+            self.selected_distance = Some(dist);
         }
     }
 }
 
 impl Strategy for SingleMinDistSetStrategy {
-    fn new(graph: Arc<Graph>) -> Self {
-        Self {
-            graph,
-            nodes_to_defend: vec![],
-            current_defended: 0,
-        }
-    }
-
-    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit) {
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               _deadline: Option<Instant>) {
         let num_to_defend = min(settings.num_ffs, self.nodes_to_defend.len() - self.current_defended);
         let to_defend = &self.nodes_to_defend[self.current_defended..self.current_defended + num_to_defend];
         node_data.mark_defended(to_defend, global_time);
 
         self.current_defended += num_to_defend;
     }
+
+    fn report(&self) -> Value {
+        json!({
+            "selected_distance": self.selected_distance,
+            "num_planned": self.nodes_to_defend.len(),
+            "num_defended": self.current_defended,
+        })
+    }
+
+    fn release_buffers(&mut self) {
+        self.nodes_to_defend = Vec::new();
+        self.root_dists = IncrementalDistances::new();
+    }
 }
 
 /// Priority based fire containment strategy
-#[derive(Debug, Default)]
+#[derive(Debug, Clone)]
 pub struct PriorityStrategy {
-    graph: Arc<Graph>,
+    graph: Arc<dyn GraphView>,
     nodes_to_defend: VecDeque<usize>,
     possible_defended: usize,
     undefended_roots: HashMap<usize, (Visited, RiskyNodes)>,
+    num_replans: usize,
+    q25_threshold: Option<usize>,
 }
 
 impl PriorityStrategy {
+    /// Create a new strategy instance that operates on the graph `graph`
+    pub fn new(graph: Arc<dyn GraphView>) -> Self {
+        Self {
+            graph,
+            nodes_to_defend: VecDeque::new(),
+            possible_defended: 0,
+            undefended_roots: HashMap::new(),
+            num_replans: 0,
+            q25_threshold: None,
+        }
+    }
+
     /// Initialize the undefended roots datastructure
     pub(super) fn initialize_undefended_roots(&mut self, roots: &Vec<usize>) {
         self.undefended_roots.reserve(roots.len());
@@ -528,19 +859,19 @@ impl PriorityStrategy {
     /// Compute nodes to defend and order in which nodes should be defended
     pub(super) fn compute_nodes_to_defend(&mut self, undefended_roots: &Vec<usize>, settings: &OSMFSettings,
                                    node_data: &NodeDataStorage) {
-        let mut priority_map = HashMap::with_capacity(self.graph.num_nodes);
-        for node in self.graph.nodes() {
-            if node_data.is_undefended(&node.id) && self.graph.get_node_degree(node.id) > 0 {
-                let prio = self.graph.get_node_degree(node.id);
+        let mut priority_map = HashMap::with_capacity(self.graph.num_nodes());
+        for node in self.graph.iter_nodes() {
+            if node_data.is_undefended(&node.id()) && self.graph.get_node_degree(node.id()) > 0 {
+                let prio = self.graph.get_node_degree(node.id());
                 // for i in graph.offsets[node.id]..graph.offsets[node.id+1] {
                 //     let edge = &graph.edges[i];
                 //     prio += 1.0 / edge.dist as f64;
                 // }
-                priority_map.insert(node.id, prio);
+                priority_map.insert(node.id(), prio);
             }
         }
 
-        log::debug!("Computed priority map:\n{:?}", &priority_map);
+        log_entries("Computed priority map", &priority_map);
 
         let mut sorted_priorities: Vec<_> = priority_map.values().map(|prio|*prio).collect();
         sorted_priorities.sort_unstable_by(usize::cmp);
@@ -553,6 +884,7 @@ impl PriorityStrategy {
                 + sorted_priorities[(sorted_priorities.len() / 4) + 1]) / 2
         };
         log::debug!("Computed 25 percent quantile: {}", q25);
+        self.q25_threshold = Some(q25);
 
         let mut nodes_by_sho_dist = group_nodes_by_distance(undefended_roots,
                                                         &self.graph, node_data);
@@ -566,7 +898,7 @@ impl PriorityStrategy {
             });
         }
 
-        log::debug!("Distance sets after sorting by priority:\n{:?}", &nodes_by_sho_dist);
+        log_entries("Distance sets after sorting by priority", &nodes_by_sho_dist);
 
         let strategy_every = settings.strategy_every as usize;
         let num_ffs = settings.num_ffs;
@@ -608,7 +940,7 @@ impl PriorityStrategy {
         }
 
         // Nodes with a lower priority than the mean should be defended
-        let mut low_prio_defend = Vec::with_capacity(self.graph.num_nodes - high_prio_defend.len());
+        let mut low_prio_defend = Vec::with_capacity(self.graph.num_nodes() - high_prio_defend.len());
         for (&dist, nodes) in low_prio_map.iter() {
             let can_defend_total = dist / strategy_every * num_ffs;
             if can_defend_total > total_defended {
@@ -620,7 +952,7 @@ impl PriorityStrategy {
                 total_defended += num_of_nodes;
             }
         }
-        assert!(high_prio_defend.len() + low_prio_defend.len() <= self.graph.num_nodes);
+        assert!(high_prio_defend.len() + low_prio_defend.len() <= self.graph.num_nodes());
 
         self.nodes_to_defend.clear();
         self.nodes_to_defend.reserve_exact(total_defended - self.possible_defended);
@@ -638,54 +970,69 @@ impl PriorityStrategy {
 }
 
 impl Strategy for PriorityStrategy {
-    fn new(graph: Arc<Graph>) -> Self {
-        Self {
-            graph,
-            nodes_to_defend: VecDeque::new(),
-            possible_defended: 0,
-            undefended_roots: HashMap::new(),
-        }
-    }
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               deadline: Option<Instant>) {
+        // Other strategies of an ensemble may have acted since the nodes were planned
+        self.nodes_to_defend.retain(|node| node_data.is_undefended(node));
+        self.nodes_to_defend.make_contiguous();
 
-    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit) {
         let num_to_defend = min(settings.num_ffs, self.nodes_to_defend.len());
         let len = self.nodes_to_defend.len();
         let to_defend = &self.nodes_to_defend.as_slices().0[(len-num_to_defend)..len];
 
-        for node in to_defend {
-            assert!(node_data.is_undefended(node));
-        }
-
         node_data.mark_defended(to_defend, global_time);
 
         self.nodes_to_defend.truncate(len-num_to_defend);
         self.possible_defended += settings.num_ffs;
 
+        // Replanning is skipped once the deadline has passed
+        if is_past(deadline) {
+            return;
+        }
+
         // One or more fire roots have been defended and hence shouldn't be considered
         // in the min_distance_groups anymore
         if let Some(roots) = self.compute_undefended_roots(node_data) {
             self.compute_nodes_to_defend(&roots, settings, node_data);
+            self.num_replans += 1;
         }
     }
+
+    fn report(&self) -> Value {
+        json!({
+            "num_replans": self.num_replans,
+            "final_queue_length": self.nodes_to_defend.len(),
+            "q25_threshold": self.q25_threshold,
+        })
+    }
+
+    fn release_buffers(&mut self) {
+        self.nodes_to_defend = VecDeque::new();
+        self.undefended_roots = HashMap::new();
+    }
 }
 
 /// Random fire containment strategy
-#[derive(Debug, Default)]
+#[derive(Debug, Clone)]
 pub struct RandomStrategy {
-    graph: Arc<Graph>,
+    graph: Arc<dyn GraphView>,
 }
 
-impl Strategy for RandomStrategy {
-    fn new(graph: Arc<Graph>) -> Self {
+impl RandomStrategy {
+    /// Create a new strategy instance that operates on the graph `graph`
+    pub fn new(graph: Arc<dyn GraphView>) -> Self {
         Self {
             graph,
         }
     }
+}
 
-    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit) {
-        let nodes_to_defend: Vec<_> = self.graph.nodes().iter()
-            .filter(|&node| node_data.is_undefended(&node.id))
-            .map(|node| node.id)
+impl Strategy for RandomStrategy {
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               _deadline: Option<Instant>) {
+        let nodes_to_defend: Vec<_> = self.graph.iter_nodes()
+            .filter(|&node| node_data.is_undefended(&node.id()))
+            .map(|node| node.id())
             .collect();
 
         let num_to_defend = min(settings.num_ffs, nodes_to_defend.len());
@@ -697,4 +1044,289 @@ impl Strategy for RandomStrategy {
 
         node_data.mark_defended(&to_defend, global_time);
     }
+}
+
+/// Online fire containment strategy in the style of the k-server problem, which is a common
+/// baseline in the literature. Every firefighter is a server positioned at a node of the graph,
+/// starting at the node closest to the center of the graph. In every execution, the threatened
+/// nodes, i.e. the undefended neighbours of burning nodes, are served in descending order of
+/// their value, which is the number of their undefended neighbours the fire could spread to next.
+/// Every threatened node is intercepted by the closest free firefighter, which moves there.
+#[derive(Debug, Clone)]
+pub struct KServerStrategy {
+    graph: Arc<dyn GraphView>,
+    /// Nodes at which the firefighters are positioned
+    positions: Vec<usize>,
+    /// Great-circle distance in meters all firefighters travelled so far
+    travel_distance_m: f64,
+    num_executions: usize,
+}
+
+impl KServerStrategy {
+    /// Create a new strategy instance that operates on the graph `graph`
+    pub fn new(graph: Arc<dyn GraphView>) -> Self {
+        Self {
+            graph,
+            positions: Vec::new(),
+            travel_distance_m: 0.0,
+            num_executions: 0,
+        }
+    }
+
+    /// Get the great-circle distance in meters between the nodes with ids `src` and `tgt`
+    fn distance(&self, src: usize, tgt: usize) -> f64 {
+        let (src, tgt) = (self.graph.get_node(src), self.graph.get_node(tgt));
+        Point::new(src.lon, src.lat).haversine_distance(&Point::new(tgt.lon, tgt.lat))
+    }
+}
+
+impl Strategy for KServerStrategy {
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               _deadline: Option<Instant>) {
+        if self.positions.len() != settings.num_ffs {
+            let gb = self.graph.get_grid_bounds();
+            let center = self.graph.nearest_node((gb.min_lat + gb.max_lat) / 2.0,
+                                                 (gb.min_lon + gb.max_lon) / 2.0).id();
+            self.positions.resize(settings.num_ffs, center);
+        }
+
+        // Threatened nodes ordered by descending value, ties broken by node id
+        let threatened: HashSet<_> = node_data.get_burning().into_iter()
+            .flat_map(|node_id| self.graph.get_outgoing_edges(node_id))
+            .map(|edge| edge.tgt())
+            .filter(|node_id| node_data.is_undefended(node_id))
+            .collect();
+        let mut threatened: Vec<_> = threatened.into_iter()
+            .map(|node_id| {
+                let value = self.graph.get_outgoing_edges(node_id).iter()
+                    .filter(|edge| node_data.is_undefended(&edge.tgt()))
+                    .count();
+                (value, node_id)
+            })
+            .collect();
+        threatened.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let mut is_free = vec![true; self.positions.len()];
+        let mut to_defend = Vec::new();
+        for (_, node_id) in threatened.into_iter().take(self.positions.len()) {
+            let (server, distance) = self.positions.iter().enumerate()
+                .filter(|&(server, _)| is_free[server])
+                .map(|(server, &position)| (server, self.distance(position, node_id)))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .expect("There should be a free firefighter for every served node");
+            is_free[server] = false;
+            self.positions[server] = node_id;
+            self.travel_distance_m += distance;
+            to_defend.push(node_id);
+        }
+        node_data.mark_defended(&to_defend, global_time);
+
+        self.num_executions += 1;
+    }
+
+    fn report(&self) -> Value {
+        json!({
+            "num_executions": self.num_executions,
+            "travel_distance_m": self.travel_distance_m,
+            "positions": self.positions,
+        })
+    }
+}
+
+/// How the `Ensemble` strategy picks the strategy that acts in a round
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OSMFEnsembleMode {
+    /// Rotate among the strategies round by round
+    Rotate,
+    /// Let every strategy act on a copy of the simulation and pick the one that leaves the
+    /// fewest threatened nodes, i.e. undefended neighbours of burning nodes
+    Lookahead,
+}
+
+/// Fire containment strategy that combines the strategies of the settings, e.g. the reactive
+/// Greedy strategy and the structural MultiMinDistanceSets strategy. In every execution, a single
+/// strategy acts, which is chosen according to the ensemble mode of the settings and rotates
+/// among the strategies by default. All strategies are notified about fire roots, so each of them
+/// can plan ahead on its own.
+#[derive(Debug, Clone)]
+pub struct EnsembleStrategy {
+    graph: Arc<dyn GraphView>,
+    members: Vec<OSMFStrategy>,
+    /// Round of every execution together with the index of the strategy that acted in it and
+    /// the peak memory that strategy held allocated, if allocations are counted
+    decisions: Vec<(TimeUnit, usize, Option<usize>)>,
+}
+
+impl EnsembleStrategy {
+    /// Create a new strategy instance that operates on the graph `graph`
+    pub fn new(graph: Arc<dyn GraphView>) -> Self {
+        Self {
+            graph,
+            members: Vec::new(),
+            decisions: Vec::new(),
+        }
+    }
+
+    /// Create the strategies of the settings `settings`, if not done yet
+    pub(super) fn initialize_members(&mut self, settings: &OSMFSettings) {
+        if !self.members.is_empty() {
+            return;
+        }
+        self.members = settings.ensemble_strategies.iter()
+            .map(|name| OSMFStrategy::from_name_and_graph(name, self.graph.clone())
+                .expect("Strategies of an ensemble should be known after validation"))
+            .collect();
+    }
+
+    /// Let every strategy act on a copy of `node_data` and pick the strategy that leaves the
+    /// fewest threatened nodes. Once the deadline `deadline` has passed, the remaining strategies
+    /// are not tried anymore. Returns the index of that strategy, the copy it acted on and its
+    /// peak memory.
+    fn pick_lookahead(&mut self, settings: &OSMFSettings, node_data: &NodeDataStorage,
+                      global_time: TimeUnit, deadline: Option<Instant>) -> (usize, NodeDataStorage, Option<usize>) {
+        let mut best: Option<(usize, usize, OSMFStrategy, NodeDataStorage, Option<usize>)> = None;
+        for (i, member) in self.members.iter().enumerate() {
+            if best.is_some() && is_past(deadline) {
+                break;
+            }
+            let mut member = member.clone();
+            let mut proposal = node_data.lookahead_copy();
+            let (_, peak_bytes) = alloc_stats::measure_peak(
+                || member.mut_inner().execute(settings, &mut proposal, global_time, deadline));
+
+            let num_threatened = count_threatened(&*self.graph, &proposal);
+            let is_better = match &best {
+                Some((best_threatened, ..)) => num_threatened < *best_threatened,
+                None => true
+            };
+            if is_better {
+                best = Some((num_threatened, i, member, proposal, peak_bytes));
+            }
+        }
+
+        let (_, i, member, proposal, peak_bytes) = best.expect("An ensemble should consist of at least one strategy");
+        self.members[i] = member;
+        (i, proposal, peak_bytes)
+    }
+}
+
+/// Count the threatened nodes in `node_data`, i.e. the undefended neighbours of burning nodes
+fn count_threatened(graph: &dyn GraphView, node_data: &NodeDataStorage) -> usize {
+    node_data.get_burning().into_iter()
+        .flat_map(|node_id| graph.get_outgoing_edges(node_id))
+        .filter(|edge| node_data.is_undefended(&edge.tgt()))
+        .map(|edge| edge.tgt())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+impl Strategy for EnsembleStrategy {
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               deadline: Option<Instant>) {
+        self.initialize_members(settings);
+
+        // Strategies act on a copy, such that plans of strategies that did not act in the previous
+        // rounds are only applied to nodes that are still undefended
+        let (acting, proposal, peak_bytes) = match settings.ensemble_mode.unwrap_or(OSMFEnsembleMode::Rotate) {
+            OSMFEnsembleMode::Rotate => {
+                let i = self.decisions.len() % self.members.len();
+                let mut proposal = node_data.lookahead_copy();
+                let member = &mut self.members[i];
+                let (_, peak_bytes) = alloc_stats::measure_peak(
+                    || member.mut_inner().execute(settings, &mut proposal, global_time, deadline));
+                (i, proposal, peak_bytes)
+            }
+            OSMFEnsembleMode::Lookahead => self.pick_lookahead(settings, node_data, global_time, deadline)
+        };
+        let to_defend: Vec<_> = proposal.get_defended_at(&global_time).into_iter()
+            .filter(|node_id| node_data.is_undefended(node_id))
+            .collect();
+        node_data.mark_defended(&to_defend, global_time);
+
+        log::debug!("Strategy {} acted in round {}", self.members[acting].name(), global_time);
+        self.decisions.push((global_time, acting, peak_bytes));
+    }
+
+    fn report(&self) -> Value {
+        let decisions: Vec<_> = self.decisions.iter()
+            .map(|&(round, i, peak_bytes)| json!({
+                "round": round,
+                "strategy": self.members[i].name(),
+                "peak_bytes": peak_bytes,
+            }))
+            .collect();
+        let members: Vec<_> = self.members.iter()
+            .enumerate()
+            .map(|(i, member)| json!({
+                "strategy": member.name(),
+                "num_decisions": self.decisions.iter().filter(|&&(_, acting, _)| acting == i).count(),
+                "report": member.report(),
+            }))
+            .collect();
+
+        json!({
+            "members": members,
+            "decisions": decisions,
+        })
+    }
+
+    fn release_buffers(&mut self) {
+        for member in &mut self.members {
+            member.release_buffers();
+        }
+    }
+}
+
+/// Fire containment strategy that replays the defense plan of the settings verbatim, regardless
+/// of the fire and the number of firefighters. Planned defenses of rounds in which the strategy
+/// is not executed are applied in its next execution. Planned nodes that are already burning are
+/// skipped.
+#[derive(Debug, Default, Clone)]
+pub struct ReplayPlanStrategy {
+    next_round: usize,
+    num_applied: usize,
+    num_skipped: usize,
+}
+
+impl ReplayPlanStrategy {
+    /// Create a new strategy instance that operates on the graph `graph`
+    pub fn new(_graph: Arc<dyn GraphView>) -> Self {
+        Self::default()
+    }
+}
+
+impl Strategy for ReplayPlanStrategy {
+    fn execute(&mut self, settings: &OSMFSettings, node_data: &mut NodeDataStorage, global_time: TimeUnit,
+               _deadline: Option<Instant>) {
+        let plan = match &settings.defense_plan {
+            Some(plan) => plan,
+            None => return
+        };
+
+        let mut to_defend = Vec::new();
+        while let Some(round) = plan.rounds.get(self.next_round) {
+            if round.round > global_time {
+                break;
+            }
+            to_defend.extend(&round.nodes);
+            self.next_round += 1;
+        }
+        to_defend.sort_unstable();
+        to_defend.dedup();
+
+        let num_planned = to_defend.len();
+        to_defend.retain(|node_id| node_data.is_undefended(node_id));
+        self.num_skipped += num_planned - to_defend.len();
+        self.num_applied += to_defend.len();
+        node_data.mark_defended(&to_defend, global_time);
+    }
+
+    fn report(&self) -> Value {
+        json!({
+            "num_rounds_replayed": self.next_round,
+            "num_applied": self.num_applied,
+            "num_skipped": self.num_skipped,
+        })
+    }
 }
\ No newline at end of file
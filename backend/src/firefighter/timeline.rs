@@ -0,0 +1,100 @@
+use crate::firefighter::TimeUnit;
+
+/// Index of the timeline of a finished firefighter simulation, i.e. the nodes that started
+/// burning and that were defended in every round. Renderer and metadata endpoints answer queries
+/// about the state of the simulation at some time from this index, such that their results are
+/// consistent and no query scans the node data.
+#[derive(Debug)]
+pub(super) struct TimelineIndex {
+    burned: Changes,
+    defended: Changes,
+}
+
+impl TimelineIndex {
+    /// Create the index of the timeline with the burning times `burned` and the defense times
+    /// `defended` of all burning and defended nodes, given as `(node id, time)` pairs
+    pub(super) fn new(burned: impl Iterator<Item = (usize, TimeUnit)>,
+                      defended: impl Iterator<Item = (usize, TimeUnit)>) -> Self {
+        Self {
+            burned: Changes::new(burned.collect()),
+            defended: Changes::new(defended.collect()),
+        }
+    }
+
+    /// Is the node with id `node_id` burning by time `time`?
+    pub(super) fn is_burning_by(&self, node_id: usize, time: TimeUnit) -> bool {
+        self.burned.is_changed_by(node_id, time)
+    }
+
+    /// Is the node with id `node_id` defended by time `time`?
+    pub(super) fn is_defended_by(&self, node_id: usize, time: TimeUnit) -> bool {
+        self.defended.is_changed_by(node_id, time)
+    }
+
+    /// Get the ids of all nodes that started burning at time `time` in ascending order
+    pub(super) fn burning_at(&self, time: TimeUnit) -> &[usize] {
+        self.burned.at(time)
+    }
+
+    /// Get the ids of all nodes that were defended at time `time` in ascending order
+    pub(super) fn defended_at(&self, time: TimeUnit) -> &[usize] {
+        self.defended.at(time)
+    }
+}
+
+/// Changes of a single kind, e.g. nodes that started burning, of all rounds of a simulation
+#[derive(Debug)]
+struct Changes {
+    /// Time of the change of every node by node id, or `TimeUnit::MAX` if the node never changed
+    times: Box<[TimeUnit]>,
+    /// Ids of all changed nodes ordered by the time of their change and by id
+    nodes: Box<[usize]>,
+    /// Offsets of the changes of every round in `nodes`, such that the nodes that changed at
+    /// time `t` are `nodes[offsets[t]..offsets[t + 1]]`
+    offsets: Box<[usize]>,
+}
+
+impl Changes {
+    /// Create the changes from the `(node id, time)` pairs `changes`
+    fn new(mut changes: Vec<(usize, TimeUnit)>) -> Self {
+        let num_nodes = changes.iter().map(|&(node_id, _)| node_id + 1).max().unwrap_or(0);
+        let end_time = changes.iter().map(|&(_, time)| time).max().unwrap_or(0);
+
+        let mut times = vec![TimeUnit::MAX; num_nodes];
+        for &(node_id, time) in &changes {
+            times[node_id] = time;
+        }
+
+        // Count the changes of every round, then turn the counts into offsets
+        let mut offsets = vec![0; end_time as usize + 2];
+        for &(_, time) in &changes {
+            offsets[time as usize] += 1;
+        }
+        let mut offset = 0;
+        for count in offsets.iter_mut() {
+            let num_changes = *count;
+            *count = offset;
+            offset += num_changes;
+        }
+
+        changes.sort_unstable_by_key(|&(node_id, time)| (time, node_id));
+        Self {
+            times: times.into_boxed_slice(),
+            nodes: changes.into_iter().map(|(node_id, _)| node_id).collect(),
+            offsets: offsets.into_boxed_slice(),
+        }
+    }
+
+    fn is_changed_by(&self, node_id: usize, time: TimeUnit) -> bool {
+        matches!(self.times.get(node_id), Some(&changed) if changed <= time)
+    }
+
+    fn at(&self, time: TimeUnit) -> &[usize] {
+        let end_time = self.offsets.len() as TimeUnit - 2;
+        if time > end_time {
+            return &[];
+        }
+        let time = time as usize;
+        &self.nodes[self.offsets[time]..self.offsets[time + 1]]
+    }
+}
@@ -1,13 +1,15 @@
 extern crate image;
 
-use std::io::Cursor;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::str::FromStr;
 
-use self::image::{DynamicImage, ImageBuffer, ImageOutputFormat, Rgb, RgbImage};
+use self::image::{ImageBuffer, Rgb, RgbImage};
+use serde::Deserialize;
 
-use crate::firefighter::{problem::NodeDataStorage, TimeUnit};
-use crate::graph::{CompassDirection, Graph, GridBounds};
+use crate::firefighter::{analysis, basemap::TileCache, problem::NodeDataStorage, TimeUnit};
+use crate::graph::{CompassDirection, GraphView, GridBounds};
 
 /// Type alias for a latitude/longitude tuple
 pub type Coords = (f64, f64);
@@ -15,20 +17,287 @@ pub type Coords = (f64, f64);
 /// Type alias for a layer index associated with a `Color`
 type LayerIndex = u32;
 
+/// Color of the edges when they are drawn as a faint network below selected node layers only
+const FAINT_EDGE_RGB: Rgb<u8> = Rgb([112, 112, 112]);
+
+/// Color of the arrows that show the fire flow
+const FIRE_FLOW_RGB: Rgb<u8> = Rgb([255, 140, 0]);
+
+/// Color of the edges along dominant spread corridors
+const CORRIDOR_RGB: Rgb<u8> = Rgb([255, 0, 255]);
+
+/// Length of arrow heads in pixels
+const ARROW_HEAD_PX: f64 = 6.0;
+
+/// Angle between arrow heads and arrow shafts in radians
+const ARROW_HEAD_ANGLE: f64 = 0.5;
+
+/// Minimum zoom of views
+pub const MIN_ZOOM: f64 = 0.1;
+
+/// Maximum zoom of views, if not configured otherwise
+pub const DEFAULT_MAX_ZOOM: f64 = 1000.0;
+
+/// Number of frames whose layers a view keeps, such that a frame is composited from other layers
+/// without rendering it again
+const LAYER_CACHE_FRAMES: usize = 4;
+
+/// Parameters of the radius of the circles that nodes are drawn as. The radius in pixels is
+/// `scale * min(width, height) / 300 * max(1, log_base(zoom))`, clamped to `[min_px, max_px]`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct NodeRadius {
+    pub scale: f64,
+    pub log_base: f64,
+    pub min_px: u32,
+    pub max_px: Option<u32>,
+}
+
+impl Default for NodeRadius {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            log_base: 4.0,
+            min_px: 0,
+            max_px: None,
+        }
+    }
+}
+
+impl NodeRadius {
+    /// Check whether these parameters describe a valid radius
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.scale.is_finite() && self.scale > 0.0) {
+            return Err(format!("Node radius scale must be a positive number: {}", self.scale));
+        }
+        if !(self.log_base.is_finite() && self.log_base > 1.0) {
+            return Err(format!("Node radius log base must be greater than 1: {}", self.log_base));
+        }
+        if let Some(max_px) = self.max_px.filter(|&max_px| max_px < self.min_px) {
+            return Err(format!("Maximum node radius must not be less than the minimum node radius {}: {}",
+                               self.min_px, max_px));
+        }
+        Ok(())
+    }
+
+    /// Compute the radius in pixels of nodes in a view of size `width` x `height` at zoom `zoom`
+    fn radius_px(&self, width: u32, height: u32, zoom: f64) -> i64 {
+        let r = (width.min(height) as f64 * self.scale * zoom.log(self.log_base).max(1.0) / 300.0) as i64;
+        let r = r.max(self.min_px as i64);
+        match self.max_px {
+            Some(max_px) => r.min(max_px as i64),
+            None => r
+        }
+    }
+}
+
+/// Options that control what is drawn in a view in addition to the graph and the node states
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ViewOptions {
+    /// Draw arrows along the edges through which the fire spread
+    pub fire_flow: bool,
+    /// Highlight the edges along this many dominant spread corridors
+    pub corridors: usize,
+    /// Draw the simulation over basemap tiles, if a basemap is attached to the view
+    pub basemap: bool,
+    /// Radius of the circles that nodes are drawn as
+    pub node_radius: NodeRadius,
+    /// Color burning nodes by the fire root whose burn tree they belong to
+    pub color_by_root: bool,
+    /// Draw a heatmap of the smoke exposure of the nodes within this distance in meters of burning
+    /// nodes instead of the node states
+    pub exposure_radius_m: Option<f64>,
+    /// Color every node by the fire root that would reach it first without firefighters instead
+    /// of drawing the node states, i.e. draw the Voronoi regions of the fire roots
+    pub voronoi: bool,
+    /// Color the untouched nodes that would burn within this many rounds if no further nodes were
+    /// defended in shades of orange, the darker the sooner they would burn
+    pub forecast_rounds: Option<TimeUnit>,
+    /// Layers that are composited into the view
+    pub layers: ViewLayers,
+}
+
+/// Layer of a view that can be drawn on its own. Nodes belong to the layer of their state at the
+/// time of the view, also in exposure and Voronoi views.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViewLayer {
+    /// Edges of the graph
+    Edges,
+    /// Nodes that are neither burning nor defended
+    Untouched,
+    /// Burning nodes except for the fire roots
+    Burned,
+    /// Defended nodes
+    Defended,
+    /// Burning fire roots
+    Roots,
+    /// Untouched nodes that are defended in a later round of the simulation
+    Planned,
+}
+
+impl ViewLayer {
+    /// All layers in the order in which they are composited
+    pub const ALL: [ViewLayer; 6] = [ViewLayer::Edges, ViewLayer::Untouched, ViewLayer::Planned,
+        ViewLayer::Burned, ViewLayer::Defended, ViewLayer::Roots];
+
+    /// Get the name of this layer in queries
+    pub fn name(self) -> &'static str {
+        match self {
+            ViewLayer::Edges => "edges",
+            ViewLayer::Untouched => "untouched",
+            ViewLayer::Burned => "burned",
+            ViewLayer::Defended => "defended",
+            ViewLayer::Roots => "roots",
+            ViewLayer::Planned => "planned",
+        }
+    }
+}
+
+/// Set of the layers of a view. By default, views consist of the edges and the nodes in all
+/// states, but planned defenses are not highlighted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewLayers(u8);
+
+impl Default for ViewLayers {
+    fn default() -> Self {
+        ViewLayers::empty().with(ViewLayer::Edges).with(ViewLayer::Untouched).with(ViewLayer::Burned)
+            .with(ViewLayer::Defended).with(ViewLayer::Roots)
+    }
+}
+
+impl ViewLayers {
+    /// Create a set without any layers
+    pub fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Get this set with the layer `layer` added
+    pub fn with(self, layer: ViewLayer) -> Self {
+        Self(self.0 | 1 << layer as u8)
+    }
+
+    /// Check whether this set contains the layer `layer`
+    pub fn contains(&self, layer: ViewLayer) -> bool {
+        self.0 & 1 << layer as u8 != 0
+    }
+}
+
+impl FromStr for ViewLayers {
+    type Err = String;
+
+    /// Parse a comma-separated list of layer names
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .try_fold(ViewLayers::empty(), |layers, name| {
+                match ViewLayer::ALL.iter().find(|layer| layer.name() == name) {
+                    Some(&layer) => Ok(layers.with(layer)),
+                    None => Err(format!("Unknown view layer: '{}'", name))
+                }
+            })
+    }
+}
+
 /// A color with a `Rgb<u8>` value and a `LayerIndex` to determine a drawing order among different
 /// colors
+#[derive(Debug, Clone, Copy)]
 struct Color {
     rgb: Rgb<u8>,
     layer: LayerIndex,
 }
 
 impl Color {
-    // Predefined colors
+    // Predefined colors. The colors of danger zones are layered between white and light blue.
     const DARK_GREY: &'static Color = &Color { rgb: Rgb([64, 64, 64]), layer: 0 };
     const WHITE: &'static Color = &Color { rgb: Rgb([255, 255, 255]), layer: 1 };
-    const RED: &'static Color = &Color { rgb: Rgb([255, 0, 0]), layer: 2 };
-    const BLUE: &'static Color = &Color { rgb: Rgb([0, 0, 255]), layer: 3 };
-    const YELLOW: &'static Color = &Color { rgb: Rgb([255, 255, 0]), layer: 4 };
+    const LIGHT_BLUE: &'static Color = &Color { rgb: Rgb([128, 170, 255]), layer: 258 };
+    const RED: &'static Color = &Color { rgb: Rgb([255, 0, 0]), layer: 259 };
+    const BLUE: &'static Color = &Color { rgb: Rgb([0, 0, 255]), layer: 260 };
+    const YELLOW: &'static Color = &Color { rgb: Rgb([255, 255, 0]), layer: 261 };
+
+    /// Create a color on the layer of burning nodes from hue `hue` in degrees, saturation `sat`
+    /// and value `val`
+    fn burning_from_hsv(hue: f64, sat: f64, val: f64) -> Color {
+        let c = val * sat;
+        let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+        let (r, g, b) = match (hue / 60.0) as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x)
+        };
+        let m = val - c;
+        let channel = |v: f64| ((v + m) * 255.0).round() as u8;
+        Color { rgb: Rgb([channel(r), channel(g), channel(b)]), layer: Color::RED.layer }
+    }
+
+    /// Create a color of danger zones from the `urgency` in `(0, 1]` with which a node would burn,
+    /// which ranges from light orange to dark orange. Colors of more urgent nodes are drawn on top.
+    fn danger(urgency: f64) -> Color {
+        let urgency = urgency.clamp(0.0, 1.0);
+        let channel = |far: f64, near: f64| (far + (near - far) * urgency).round() as u8;
+        Color {
+            rgb: Rgb([channel(255.0, 230.0), channel(205.0, 90.0), channel(140.0, 0.0)]),
+            layer: Color::WHITE.layer + 1 + (urgency * 255.0) as LayerIndex,
+        }
+    }
+
+    /// Create a color of exposure heatmaps from the relative exposure `intensity` in `(0, 1]`,
+    /// which ranges from yellow over red to dark red. Colors of higher exposures are drawn on top.
+    fn heat(intensity: f64) -> Color {
+        let intensity = intensity.clamp(0.0, 1.0);
+        let rgb = if intensity < 0.5 {
+            Rgb([255, (255.0 * (1.0 - 2.0 * intensity)).round() as u8, 0])
+        } else {
+            Rgb([(255.0 * (1.5 - intensity)).round() as u8, 0, 0])
+        };
+        Color { rgb, layer: Color::YELLOW.layer + 1 + (intensity * 255.0) as LayerIndex }
+    }
+}
+
+/// Hue ranges in degrees of the colors of burn trees, which exclude the yellow of fire roots and
+/// the blue of defended nodes
+const ROOT_HUE_RANGES: [(f64, f64); 3] = [(0.0, 45.0), (75.0, 195.0), (270.0, 360.0)];
+
+/// Golden angle in degrees, by which the hues of consecutive burn trees are rotated such that
+/// any number of burn trees get well distinguishable colors
+const GOLDEN_ANGLE: f64 = 137.507_764;
+
+/// Generate `n` distinct colors to tell the burn trees of different fire roots apart
+fn root_palette(n: usize) -> Vec<Color> {
+    let total: f64 = ROOT_HUE_RANGES.iter().map(|(start, end)| end - start).sum();
+    (0..n)
+        .map(|i| {
+            // Map the rotated hue into the allowed hue ranges
+            let mut offset = (i as f64 * GOLDEN_ANGLE) % total;
+            let mut hue = 0.0;
+            for &(start, end) in &ROOT_HUE_RANGES {
+                if offset < end - start {
+                    hue = start + offset;
+                    break;
+                }
+                offset -= end - start;
+            }
+            // Alternate the brightness to tell apart burn trees with similar hues
+            let val = if i % 2 == 0 { 1.0 } else { 0.7 };
+            Color::burning_from_hsv(hue, 0.9, val)
+        })
+        .collect()
+}
+
+/// Get the ids of all fire roots of a simulation by time `time` in ascending order together with
+/// the colors of their burn trees in color-by-root views
+pub(super) fn root_colors(node_data: &NodeDataStorage, time: &TimeUnit) -> Vec<(usize, Rgb<u8>)> {
+    let roots: Vec<_> = node_data.get_burning().into_iter()
+        .filter(|node_id| node_data.is_root(node_id) && node_data.is_burning_by(node_id, time))
+        .collect();
+    let palette = root_palette(roots.len());
+    roots.into_iter()
+        .zip(palette)
+        .map(|(root, color)| (root, color.rgb))
+        .collect()
 }
 
 impl Eq for Color {}
@@ -99,20 +368,50 @@ impl LineSegment {
     }
 }
 
-/// View of a specific firefighter simulation
+/// View of a specific firefighter simulation.
+/// Views only hold the geometry of the graph, every frame is rendered into its own image buffer,
+/// such that frames of the same simulation can be rendered concurrently.
 #[derive(Debug)]
 pub struct View {
-    graph: Arc<Graph>,
+    graph: Arc<dyn GraphView>,
     pub(crate) grid_bounds: GridBounds,
     delta_horiz: f64,
     delta_vert: f64,
-    img_buf: RgbImage,
+    width: u32,
+    height: u32,
     pub initial_center: Coords,
+    basemap: Option<Arc<TileCache>>,
+    /// Spread factors of the simulation, by which the Voronoi regions of the fire roots are drawn
+    spread_factors: BTreeMap<u32, f64>,
+    /// Layers of the most recently rendered frames
+    layer_cache: Mutex<VecDeque<(FrameKey, Arc<FrameLayers>)>>,
+}
+
+/// Parameters that identify a rendered frame regardless of its selected layers
+#[derive(Debug, PartialEq)]
+struct FrameKey {
+    center: Coords,
+    zoom: f64,
+    time: TimeUnit,
+    options: ViewOptions,
+}
+
+/// Separately rendered layers of a frame, from which frames with any selection of layers are
+/// composited
+#[derive(Debug)]
+struct FrameLayers {
+    /// Bitset of the pixels that edges are drawn on, by `y * width + x`
+    edges: Vec<u64>,
+    /// Pixels of the fire flow and the spread corridors, which are drawn over the edges
+    overlays: Vec<(u32, u32, Rgb<u8>)>,
+    /// Center pixels of the nodes of every node layer with their colors. Nodes of the same layer
+    /// sharing a pixel are drawn with the color of the highest layer only.
+    nodes: HashMap<ViewLayer, HashMap<(i64, i64), Color>>,
 }
 
 impl View {
     /// Create a new firefighter simulation view
-    pub fn new(graph: Arc<Graph>, width: u32, height: u32) -> Self {
+    pub fn new(graph: Arc<dyn GraphView>, width: u32, height: u32) -> Self {
         let w = if width > 0 { width } else { 1 };
         let h = if height > 0 { height } else { 1 };
 
@@ -127,25 +426,64 @@ impl View {
             grid_bounds,
             delta_horiz,
             delta_vert,
-            img_buf: ImageBuffer::new(w, h),
+            width: w,
+            height: h,
             initial_center,
+            basemap: None,
+            spread_factors: BTreeMap::new(),
+            layer_cache: Mutex::new(VecDeque::new()),
         };
 
         view
     }
 
-    /// (Re-)compute this view
-    pub(super) fn compute(&mut self, center: Coords, zoom: f64, time: &TimeUnit, node_data: &NodeDataStorage) {
-        let z = if zoom < 0.0 { 0.0 } else { zoom };
+    /// Attach the basemap tiles `basemap` to this view, which are drawn below the simulation if
+    /// requested
+    pub(super) fn set_basemap(&mut self, basemap: Arc<TileCache>) {
+        self.basemap = Some(basemap);
+    }
+
+    /// Set the spread factors of the simulation shown in this view to `spread_factors`
+    pub(super) fn set_spread_factors(&mut self, spread_factors: BTreeMap<u32, f64>) {
+        self.spread_factors = spread_factors;
+    }
 
-        // Reset view
-        for px in self.img_buf.pixels_mut() {
-            *px = Color::DARK_GREY.rgb;
+    /// Get the basemap tiles attached to this view, if any
+    pub(super) fn basemap(&self) -> Option<&Arc<TileCache>> {
+        self.basemap.as_ref()
+    }
+
+    /// Encode the frame `img_buf` of this view as PNG image. The source, license and attribution
+    /// of the graph are stamped into text chunks of the image.
+    pub fn png_bytes(&self, img_buf: &RgbImage) -> Vec<u8> {
+        let metadata = self.graph.metadata();
+        let mut buf = Vec::new();
+        let mut encoder = png::Encoder::new(&mut buf, img_buf.width(), img_buf.height());
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        // International text chunks, since attributions are not necessarily Latin-1
+        for (keyword, text) in [("Copyright", &metadata.attribution), ("Source", &metadata.source),
+                                ("License", &metadata.license)] {
+            encoder.add_itxt_chunk(keyword.to_string(), text.clone())
+                .expect("Failed to add text chunk to PNG image");
         }
+        encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(img_buf.as_raw()))
+            .expect("Failed to encode view as PNG image");
+        buf
+    }
+
+    /// Compute the frame of this view centered at `center` into a new image buffer. The layers of
+    /// the frame are rendered separately and kept for the next frames, such that the same frame
+    /// is composited from other layers without rendering it again.
+    pub(super) fn compute(&self, center: Coords, zoom: f64, time: &TimeUnit, node_data: &NodeDataStorage,
+                          options: &ViewOptions) -> RgbImage {
+        let mut img_buf: RgbImage = ImageBuffer::new(self.width, self.height);
+        let z = if zoom < 0.0 { 0.0 } else { zoom };
 
         // Maximum width and length
-        let w_max = (self.img_buf.width() - 1) as i64;
-        let h_max = (self.img_buf.height() - 1) as i64;
+        let w_max = (img_buf.width() - 1) as i64;
+        let h_max = (img_buf.height() - 1) as i64;
 
         // Delta horizontal and vertical depending on zoom
         let d_hz = self.delta_horiz / z;
@@ -163,175 +501,595 @@ impl View {
         let deg_per_px_hz = d_hz / (w_max+1) as f64;
         let deg_per_px_vert = d_vert / (h_max+1) as f64;
 
-        // For every edge, compute the pixel of its respective source node and iteratively draw the
-        // edge until we reach the pixel of the target node
-        for edge in self.graph.edges() {
-            let src = self.graph.get_node(edge.src);
-            let tgt = self.graph.get_node(edge.tgt);
-
-            let mut w_px = ((src.lon - gb.min_lon) / deg_per_px_hz) as i64;
-            let mut h_px = ((src.lat - gb.min_lat) / deg_per_px_vert) as i64;
+        let key = FrameKey {
+            center,
+            zoom: z,
+            time: *time,
+            options: ViewOptions { layers: ViewLayers::default(), ..options.clone() },
+        };
+        let layers = match self.cached_layers(&key) {
+            Some(layers) => layers,
+            None => {
+                let layers = Arc::new(self.render_layers(&gb, deg_per_px_hz, deg_per_px_vert, time,
+                                                         node_data, options));
+                self.cache_layers(key, layers.clone());
+                layers
+            }
+        };
 
-            let ls_edge = LineSegment {
-                a: (src.lat, src.lon),
-                b: (tgt.lat, tgt.lon),
-            };
+        // Reset view, either to the basemap or to the background color
+        match self.basemap.as_ref().filter(|_| options.basemap) {
+            Some(basemap) => basemap.render(&mut img_buf, &gb, deg_per_px_hz, deg_per_px_vert),
+            None => {
+                for px in img_buf.pixels_mut() {
+                    *px = Color::DARK_GREY.rgb;
+                }
+            }
+        }
 
-            let min_lon_px = gb.min_lon + w_px as f64 * deg_per_px_hz;
-            let min_lat_px = gb.min_lat + h_px as f64 * deg_per_px_vert;
-            let mut gb_px = GridBounds {
-                min_lat: min_lat_px,
-                max_lat: min_lat_px + deg_per_px_vert,
-                min_lon: min_lon_px,
-                max_lon: min_lon_px + deg_per_px_hz,
-            };
+        // Edges are drawn as a faint network if untouched nodes are not drawn, such that the
+        // selected node layers stand out
+        let selected = options.layers;
+        if selected.contains(ViewLayer::Edges) {
+            let edge_rgb = if selected.contains(ViewLayer::Untouched) { Color::WHITE.rgb } else { FAINT_EDGE_RGB };
+            for (i, &word) in layers.edges.iter().enumerate() {
+                let mut word = word;
+                while word != 0 {
+                    let px = i * 64 + word.trailing_zeros() as usize;
+                    img_buf.put_pixel(px as u32 % self.width, px as u32 / self.width, edge_rgb);
+                    word &= word - 1;
+                }
+            }
+        }
+        for &(w, h, rgb) in &layers.overlays {
+            img_buf.put_pixel(w, h, rgb);
+        }
 
-            fn on_north(ls_edge: &LineSegment, gb_px: &mut GridBounds, deg_per_px_vert: f64,
-                        h_px: &mut i64) -> bool {
-                let ls_px = LineSegment {
-                    a: (gb_px.max_lat, gb_px.min_lon),
-                    b: (gb_px.max_lat, gb_px.max_lon),
-                };
-                if ls_edge.intersects(&ls_px) {
-                    gb_px.min_lat += deg_per_px_vert;
-                    gb_px.max_lat += deg_per_px_vert;
-                    *h_px += 1;
-                    true
-                } else {
-                    false
+        // For every node pixel of the selected layers, compute a circle around it and color it
+        let r = options.node_radius.radius_px(self.width, self.height, z);
+        let mut pxs_to_draw = Vec::new();
+        for node_pxs in ViewLayer::ALL.iter()
+            .filter(|&&layer| selected.contains(layer))
+            .filter_map(|layer| layers.nodes.get(layer)) {
+            for (&(w_px, h_px), col_px) in node_pxs {
+                pxs_to_draw.reserve((4 * r * r) as usize);
+                for w in w_px-r..=w_px+r {
+                    for h in h_px-r..=h_px+r {
+                        if (((w-w_px).pow(2) + (h-h_px).pow(2)) as f64).sqrt() as i64 <= r
+                            && w >= 0 && w <= w_max && h >= 0 && h <= h_max {
+                            pxs_to_draw.push((w as u32, h as u32, col_px));
+                        }
+                    }
                 }
             }
+        }
+        pxs_to_draw.sort_unstable_by_key(|&(_, _, col)| col);
+        for (w, h, col) in pxs_to_draw {
+            img_buf.put_pixel(w, h_max as u32 - h, col.rgb);
+        }
 
-            fn on_east(ls_edge: &LineSegment, gb_px: &mut GridBounds, deg_per_px_hz: f64,
-                       w_px: &mut i64) -> bool {
-                let ls_px = LineSegment {
-                    a: (gb_px.min_lat, gb_px.max_lon),
-                    b: (gb_px.max_lat, gb_px.max_lon),
-                };
-                if ls_edge.intersects(&ls_px) {
-                    gb_px.min_lon += deg_per_px_hz;
-                    gb_px.max_lon += deg_per_px_hz;
-                    *w_px += 1;
-                    true
-                } else {
-                    false
+        img_buf
+    }
+
+    /// Get the layers of the frame `key` if it was rendered recently
+    fn cached_layers(&self, key: &FrameKey) -> Option<Arc<FrameLayers>> {
+        self.layer_cache.lock().unwrap().iter()
+            .find(|(cached, _)| cached == key)
+            .map(|(_, layers)| layers.clone())
+    }
+
+    /// Keep the layers `layers` of the frame `key`, evicting the layers of the oldest frame
+    fn cache_layers(&self, key: FrameKey, layers: Arc<FrameLayers>) {
+        let mut cache = self.layer_cache.lock().unwrap();
+        if cache.len() >= LAYER_CACHE_FRAMES {
+            cache.pop_front();
+        }
+        cache.push_back((key, layers));
+    }
+
+    /// Render the layers of the frame within the grid bounds `gb` at time `time`
+    fn render_layers(&self, gb: &GridBounds, deg_per_px_hz: f64, deg_per_px_vert: f64, time: &TimeUnit,
+                     node_data: &NodeDataStorage, options: &ViewOptions) -> FrameLayers {
+        // Edges and overlays are drawn into a black scratch buffer, from which their pixels are
+        // collected
+        let mut scratch: RgbImage = ImageBuffer::new(self.width, self.height);
+
+        // Draw the edges from the base layer of the graph that matches the current zoom, if any.
+        // Otherwise, draw the edges of the coarsest level of detail that is indistinguishable from
+        // the full graph at the current zoom, or all edges of the graph if there is no such level.
+        // Base layers and levels of detail are only available for graphs, overlays of graphs are
+        // always drawn in full.
+        let graph = &self.graph;
+        let is_drawn = match graph.as_graph().and_then(|graph| graph.base_layers()) {
+            Some(base_layers) => base_layers.draw(&mut scratch, gb, deg_per_px_hz, deg_per_px_vert,
+                                                  Color::WHITE.rgb),
+            None => false
+        };
+        // Levels of detail are not needed with base layers
+        let lod_level = graph.as_graph()
+            .filter(|_| !is_drawn)
+            .and_then(|graph| graph.get_lod_level(deg_per_px_hz.min(deg_per_px_vert)));
+        match lod_level {
+            _ if is_drawn => (),
+            Some(level) => {
+                for &(a, b) in &level.segments {
+                    draw_line(&mut scratch, a, b, gb, deg_per_px_hz, deg_per_px_vert, Color::WHITE.rgb);
                 }
             }
+            None => {
+                for edge in graph.iter_edges() {
+                    let src = graph.get_node(edge.src());
+                    let tgt = graph.get_node(edge.tgt());
+                    draw_line(&mut scratch, (src.lat, src.lon), (tgt.lat, tgt.lon), gb,
+                                   deg_per_px_hz, deg_per_px_vert, Color::WHITE.rgb);
+                }
+            }
+        }
+        let mut edges = vec![0u64; (self.width * self.height) as usize / 64 + 1];
+        for (i, px) in scratch.pixels_mut().enumerate() {
+            if px.0 != [0, 0, 0] {
+                edges[i / 64] |= 1 << (i % 64);
+                *px = Rgb([0, 0, 0]);
+            }
+        }
 
-            fn on_south(ls_edge: &LineSegment, gb_px: &mut GridBounds, deg_per_px_vert: f64,
-                        h_px: &mut i64) -> bool {
-                let ls_px = LineSegment {
-                    a: (gb_px.min_lat, gb_px.min_lon),
-                    b: (gb_px.min_lat, gb_px.max_lon),
-                };
-                if ls_edge.intersects(&ls_px) {
-                    gb_px.min_lat -= deg_per_px_vert;
-                    gb_px.max_lat -= deg_per_px_vert;
-                    *h_px -= 1;
-                    true
-                } else {
-                    false
+        // Draw an arrow from every node that ignited another node by time `time` to that node
+        let has_overlays = options.fire_flow || options.corridors > 0;
+        if options.fire_flow {
+            for node in graph.iter_nodes() {
+                if !node_data.is_burning_by(&node.id(), time) {
+                    continue;
+                }
+                if let Some(parent) = node_data.get_burn_parent(&node.id()) {
+                    let parent = graph.get_node(parent);
+                    draw_arrow(&mut scratch, (parent.lat, parent.lon), (node.lat, node.lon), gb,
+                                    deg_per_px_hz, deg_per_px_vert, FIRE_FLOW_RGB);
                 }
             }
+        }
 
-            fn on_west(ls_edge: &LineSegment, gb_px: &mut GridBounds, deg_per_px_hz: f64,
-                       w_px: &mut i64) -> bool {
-                let ls_px = LineSegment {
-                    a: (gb_px.min_lat, gb_px.min_lon),
-                    b: (gb_px.max_lat, gb_px.min_lon),
-                };
-                if ls_edge.intersects(&ls_px) {
-                    gb_px.min_lon -= deg_per_px_hz;
-                    gb_px.max_lon -= deg_per_px_hz;
-                    *w_px -= 1;
-                    true
-                } else {
-                    false
+        // Highlight the edges along the dominant spread corridors that the fire crossed by time `time`
+        if options.corridors > 0 {
+            for corridor in analysis::compute_corridors(node_data, options.corridors) {
+                for w in corridor.nodes().windows(2) {
+                    if !node_data.is_burning_by(&w[1], time) {
+                        break;
+                    }
+                    let (a, b) = (graph.get_node(w[0]), graph.get_node(w[1]));
+                    draw_line(&mut scratch, (a.lat, a.lon), (b.lat, b.lon), gb,
+                                   deg_per_px_hz, deg_per_px_vert, CORRIDOR_RGB);
                 }
             }
+        }
+        let overlays = if has_overlays {
+            scratch.enumerate_pixels()
+                .filter(|(_, _, px)| px.0 != [0, 0, 0])
+                .map(|(w, h, px)| (w, h, *px))
+                .collect()
+        } else {
+            Vec::new()
+        };
 
-            loop {
-                let has_next_px = match tgt.get_relative_compass_direction(&gb_px) {
-                    CompassDirection::North => on_north(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px),
-                    CompassDirection::NorthEast => on_north(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px)
-                        || on_east(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px),
-                    CompassDirection::East => on_east(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px),
-                    CompassDirection::SouthEast => on_east(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px)
-                        || on_south(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px),
-                    CompassDirection::South => on_south(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px),
-                    CompassDirection::SouthWest => on_south(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px)
-                        || on_west(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px),
-                    CompassDirection::West => on_west(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px),
-                    CompassDirection::NorthWest => on_west(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px)
-                        || on_north(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px),
-                    CompassDirection::Zero => false
-                };
+        // In Voronoi views, nodes are colored like the root that would reach them first
+        let nearest_roots = if options.voronoi {
+            analysis::compute_nearest_roots(&**graph, node_data, *time, &self.spread_factors)
+        } else {
+            Vec::new()
+        };
+        let region_colors: HashMap<usize, Color> = if options.voronoi {
+            root_colors(node_data, time).into_iter()
+                .map(|(root, rgb)| (root, Color { rgb, layer: Color::RED.layer }))
+                .collect()
+        } else {
+            HashMap::new()
+        };
 
-                if !has_next_px {
-                    break;
-                } else if !gb_px.is_located_in(&gb) {
-                    continue;
-                }
+        // In color-by-root views, burning nodes are colored like the root of their burn tree
+        let (palette, burn_roots) = if options.color_by_root {
+            let palette: Vec<_> = root_colors(node_data, time).into_iter()
+                .map(|(root, rgb)| (root, Color { rgb, layer: Color::RED.layer }))
+                .collect();
+            (palette, analysis::compute_burn_roots(node_data))
+        } else {
+            (Vec::new(), HashMap::new())
+        };
+        let root_colors: HashMap<usize, &Color> = palette.iter().map(|(root, color)| (*root, color)).collect();
 
-                self.img_buf.put_pixel(w_px as u32, (h_max - h_px) as u32, Color::WHITE.rgb);
+        // In exposure views, exposed nodes are colored by their exposure relative to the maximum
+        let heat: Vec<_> = match options.exposure_radius_m {
+            Some(radius_m) => {
+                let exposure = analysis::compute_exposure(&**graph, node_data, radius_m, *time);
+                let max_exposure = exposure.iter().copied().fold(0.0, f64::max);
+                exposure.into_iter()
+                    .map(|exposure| (exposure > 0.0).then(|| Color::heat(exposure / max_exposure)))
+                    .collect()
+            }
+            None => Vec::new()
+        };
+
+        // In forecast views, untouched nodes in the danger zone are colored by how soon they would burn
+        let danger: Vec<_> = match options.forecast_rounds {
+            Some(rounds) => analysis::compute_danger_zone(&**graph, node_data, *time, rounds, &self.spread_factors)
+                .into_iter()
+                .map(|danger| danger.map(|r| Color::danger((rounds - r + 1) as f64 / rounds as f64)))
+                .collect(),
+            None => Vec::new()
+        };
+
+        // For every node, compute its respective pixel, layer and color. Nodes of the same layer
+        // sharing a pixel are drawn with the color of the highest layer only
+        let mut nodes: HashMap<ViewLayer, HashMap<(i64, i64), Color>> = HashMap::new();
+        for node in self.graph.nodes_in_bounds(gb) {
+            let w_px = ((node.lon - gb.min_lon) / deg_per_px_hz) as i64;
+            let h_px = ((node.lat - gb.min_lat) / deg_per_px_vert) as i64;
+
+            let is_burning = node_data.is_burning_by(&node.id(), time);
+            let layer = if is_burning && node_data.is_root(&node.id()) {
+                ViewLayer::Roots
+            } else if is_burning {
+                ViewLayer::Burned
+            } else if node_data.is_defended_by(&node.id(), time) {
+                ViewLayer::Defended
+            } else {
+                ViewLayer::Untouched
+            };
+
+            let col_px;
+            if options.exposure_radius_m.is_some() {
+                col_px = heat[node.id()].as_ref().unwrap_or(Color::WHITE);
+            } else if options.voronoi {
+                col_px = match nearest_roots[node.id()] {
+                    Some(root) if root == node.id() => Color::YELLOW,
+                    Some(root) => &region_colors[&root],
+                    None => Color::WHITE
+                };
+            } else if layer == ViewLayer::Roots {
+                col_px = Color::YELLOW;
+            } else if layer == ViewLayer::Burned {
+                col_px = burn_roots.get(&node.id())
+                    .and_then(|root| root_colors.get(root))
+                    .copied()
+                    .unwrap_or(Color::RED);
+            } else if layer == ViewLayer::Defended {
+                col_px = Color::BLUE;
+            } else if options.forecast_rounds.is_some() {
+                col_px = danger[node.id()].as_ref().unwrap_or(Color::WHITE);
+            } else {
+                col_px = Color::WHITE;
+            }
+
+            let mut add = |layer: ViewLayer, col_px: &Color| {
+                nodes.entry(layer)
+                    .or_default()
+                    .entry((w_px, h_px))
+                    .and_modify(|col| *col = (*col).max(*col_px))
+                    .or_insert(*col_px);
+            };
+            add(layer, col_px);
+            // Untouched nodes that are defended later are highlighted on top of the untouched nodes
+            if layer == ViewLayer::Untouched && node_data.is_defended(&node.id()) {
+                add(ViewLayer::Planned, Color::LIGHT_BLUE);
             }
         }
 
-        // For every node, compute a circle around its respective pixel and color it
-        let mut pxs_to_draw = Vec::with_capacity(self.graph.num_nodes);
-        for node in self.graph.nodes() {
-            if node.is_located_in(&gb) {
-                let w_px = ((node.lon - gb.min_lon) / deg_per_px_hz) as i64;
-                let h_px = ((node.lat - gb.min_lat) / deg_per_px_vert) as i64;
-
-                let col_px;
-                if node_data.is_root(&node.id) {
-                    col_px = Color::YELLOW;
-                } else if node_data.is_burning_by(&node.id, time) {
-                    col_px = Color::RED;
-                } else if node_data.is_defended_by(&node.id, time) {
-                    col_px = Color::BLUE;
-                } else {
-                    col_px = Color::WHITE;
-                }
+        FrameLayers { edges, overlays, nodes }
+    }
 
-                let r = ((h_max.min(w_max)+1) as f64 * z.log(4.0).max(1.0) / 300.0) as i64;
-                pxs_to_draw.reserve((4 * r * r) as usize);
-                for w in w_px-r..=w_px+r {
-                    for h in h_px-r..=h_px+r {
-                        if (((w-w_px).pow(2) + (h-h_px).pow(2)) as f64).sqrt() as i64 <= r {
-                            if w >= 0 && w <= w_max && h >= 0 && h <= h_max {
-                                pxs_to_draw.push((w as u32, h as u32, col_px));
-                            }
-                        }
-                    }
-                }
+    /// Compute the frame of this view centered at the initial center into a new image buffer
+    pub(super) fn compute_alt(&self, zoom: f64, time: &TimeUnit, node_data: &NodeDataStorage,
+                              options: &ViewOptions) -> RgbImage {
+        self.compute(self.initial_center, zoom, time, node_data, options)
+    }
+}
+
+/// Draw an arrow from `a` to `b` onto the image buffer `img_buf`
+fn draw_arrow(img_buf: &mut RgbImage, a: Coords, b: Coords, gb: &GridBounds, deg_per_px_hz: f64,
+              deg_per_px_vert: f64, rgb: Rgb<u8>) {
+    draw_line(img_buf, a, b, gb, deg_per_px_hz, deg_per_px_vert, rgb);
+
+    // Compute the arrow direction in pixel space
+    let d_w = (b.1 - a.1) / deg_per_px_hz;
+    let d_h = (b.0 - a.0) / deg_per_px_vert;
+    let len = (d_w * d_w + d_h * d_h).sqrt();
+    if len < ARROW_HEAD_PX {
+        return;
+    }
+    let (u_w, u_h) = (-d_w / len, -d_h / len);
+
+    for angle in [ARROW_HEAD_ANGLE, -ARROW_HEAD_ANGLE] {
+        let (sin, cos) = angle.sin_cos();
+        let head_w = (u_w * cos - u_h * sin) * ARROW_HEAD_PX;
+        let head_h = (u_w * sin + u_h * cos) * ARROW_HEAD_PX;
+        let head = (b.0 + head_h * deg_per_px_vert, b.1 + head_w * deg_per_px_hz);
+        draw_line(img_buf, b, head, gb, deg_per_px_hz, deg_per_px_vert, rgb);
+    }
+}
+
+/// Draw a line from `a` to `b` onto the image buffer `img_buf` by computing the pixel of `a` and
+/// iteratively walking through all pixels the line intersects until reaching the pixel of `b`.
+/// Only pixels within `gb` are drawn.
+fn draw_line(img_buf: &mut RgbImage, a: Coords, b: Coords, gb: &GridBounds, deg_per_px_hz: f64,
+             deg_per_px_vert: f64, rgb: Rgb<u8>) {
+    let h_max = (img_buf.height() - 1) as i64;
+
+    let mut w_px = ((a.1 - gb.min_lon) / deg_per_px_hz) as i64;
+    let mut h_px = ((a.0 - gb.min_lat) / deg_per_px_vert) as i64;
+
+    let ls_edge = LineSegment {
+        a,
+        b,
+    };
+
+    let min_lon_px = gb.min_lon + w_px as f64 * deg_per_px_hz;
+    let min_lat_px = gb.min_lat + h_px as f64 * deg_per_px_vert;
+    let mut gb_px = GridBounds {
+        min_lat: min_lat_px,
+        max_lat: min_lat_px + deg_per_px_vert,
+        min_lon: min_lon_px,
+        max_lon: min_lon_px + deg_per_px_hz,
+    };
+
+    fn on_north(ls_edge: &LineSegment, gb_px: &mut GridBounds, deg_per_px_vert: f64,
+                h_px: &mut i64) -> bool {
+        let ls_px = LineSegment {
+            a: (gb_px.max_lat, gb_px.min_lon),
+            b: (gb_px.max_lat, gb_px.max_lon),
+        };
+        if ls_edge.intersects(&ls_px) {
+            gb_px.min_lat += deg_per_px_vert;
+            gb_px.max_lat += deg_per_px_vert;
+            *h_px += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn on_east(ls_edge: &LineSegment, gb_px: &mut GridBounds, deg_per_px_hz: f64,
+               w_px: &mut i64) -> bool {
+        let ls_px = LineSegment {
+            a: (gb_px.min_lat, gb_px.max_lon),
+            b: (gb_px.max_lat, gb_px.max_lon),
+        };
+        if ls_edge.intersects(&ls_px) {
+            gb_px.min_lon += deg_per_px_hz;
+            gb_px.max_lon += deg_per_px_hz;
+            *w_px += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn on_south(ls_edge: &LineSegment, gb_px: &mut GridBounds, deg_per_px_vert: f64,
+                h_px: &mut i64) -> bool {
+        let ls_px = LineSegment {
+            a: (gb_px.min_lat, gb_px.min_lon),
+            b: (gb_px.min_lat, gb_px.max_lon),
+        };
+        if ls_edge.intersects(&ls_px) {
+            gb_px.min_lat -= deg_per_px_vert;
+            gb_px.max_lat -= deg_per_px_vert;
+            *h_px -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn on_west(ls_edge: &LineSegment, gb_px: &mut GridBounds, deg_per_px_hz: f64,
+               w_px: &mut i64) -> bool {
+        let ls_px = LineSegment {
+            a: (gb_px.min_lat, gb_px.min_lon),
+            b: (gb_px.max_lat, gb_px.min_lon),
+        };
+        if ls_edge.intersects(&ls_px) {
+            gb_px.min_lon -= deg_per_px_hz;
+            gb_px.max_lon -= deg_per_px_hz;
+            *w_px -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    loop {
+        let has_next_px = match gb_px.get_compass_direction_of(b.0, b.1) {
+            CompassDirection::North => on_north(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px),
+            CompassDirection::NorthEast => on_north(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px)
+                || on_east(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px),
+            CompassDirection::East => on_east(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px),
+            CompassDirection::SouthEast => on_east(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px)
+                || on_south(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px),
+            CompassDirection::South => on_south(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px),
+            CompassDirection::SouthWest => on_south(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px)
+                || on_west(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px),
+            CompassDirection::West => on_west(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px),
+            CompassDirection::NorthWest => on_west(&ls_edge, &mut gb_px, deg_per_px_hz, &mut w_px)
+                || on_north(&ls_edge, &mut gb_px, deg_per_px_vert, &mut h_px),
+            CompassDirection::Zero => false
+        };
+
+        if !has_next_px {
+            break;
+        } else if !gb_px.is_located_in(gb) {
+            continue;
+        }
+
+        img_buf.put_pixel(w_px as u32, (h_max - h_px) as u32, rgb);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::sync::Arc;
+
+    use rayon::prelude::*;
+
+    use crate::firefighter::problem::NodeDataStorage;
+    use crate::firefighter::TimeUnit;
+    use crate::firefighter::view::{Color, FAINT_EDGE_RGB, NodeRadius, View, ViewLayer, ViewLayers, ViewOptions,
+                                   root_palette};
+    use crate::graph::{Graph, GraphView};
+
+    /// Directory containing the reference images
+    const GOLDEN_DIR: &str = "data/golden";
+    /// Environment variable that, if set, causes the reference images to be (re-)generated
+    const BLESS_VAR: &str = "OSMFF_BLESS_GOLDEN";
+    /// Maximum ratio of pixels that may differ from the reference image
+    const MAX_DIFF_RATIO: f64 = 0.001;
+
+    /// Deterministic simulation: the fire spreads unimpeded from three fixed roots and a fixed
+    /// set of nodes is defended before the fire reaches them
+    fn deterministic_node_data(graph: &dyn GraphView) -> NodeDataStorage {
+        let roots = [0, 120, 240];
+        let defended = [10, 130, 250, 300];
+        let dists = graph.run_dijkstra(&roots);
+
+        let mut node_data = NodeDataStorage::new();
+        node_data.mark_defended(&defended, 1);
+        for node in graph.iter_nodes() {
+            let dist = dists[node.id()];
+            if dist < usize::MAX && !defended.contains(&node.id()) {
+                node_data.mark_burning(&vec![node.id()], dist as TimeUnit);
             }
         }
-        pxs_to_draw.sort_unstable_by(|(_, _, col1), (_, _, col2)| col1.cmp(&col2));
-        for (w, h, col) in pxs_to_draw {
-            self.img_buf.put_pixel(w, h_max as u32 - h, col.rgb);
+        node_data
+    }
+
+    /// Render the deterministic simulation and compare it to the reference image `name`
+    fn assert_matches_golden(name: &str, zoom: f64, time: TimeUnit) {
+        let graph = Arc::new(Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap());
+        let node_data = deterministic_node_data(&*graph);
+        let view = View::new(graph, 960, 540);
+        let img_buf = view.compute_alt(zoom, &time, &node_data, &ViewOptions::default());
+
+        let path = format!("{}/{}.png", GOLDEN_DIR, name);
+        if env::var(BLESS_VAR).is_ok() {
+            img_buf.save(&path).expect("Failed to save reference image");
+            return;
         }
+
+        let golden = image::open(&path)
+            .unwrap_or_else(|_| panic!("Missing reference image {}, run with {} set", path, BLESS_VAR))
+            .to_rgb8();
+        assert_eq!(golden.dimensions(), img_buf.dimensions());
+
+        let num_diff = golden.pixels()
+            .zip(img_buf.pixels())
+            .filter(|(px1, px2)| px1 != px2)
+            .count();
+        let diff_ratio = num_diff as f64 / (golden.width() * golden.height()) as f64;
+        assert!(diff_ratio <= MAX_DIFF_RATIO, "{}: {} pixels ({:.4}%) differ from the reference image",
+                name, num_diff, diff_ratio * 100.0);
     }
 
-    /// (Re-)compute this view, using the initial center
-    pub(super) fn compute_alt(&mut self, zoom: f64, time: &TimeUnit, node_data: &NodeDataStorage) {
-        self.compute(self.initial_center, zoom, time, node_data)
+    #[test]
+    fn test_node_radius() {
+        let radius = NodeRadius::default();
+        assert!(radius.validate().is_ok());
+        assert_eq!(radius.radius_px(1920, 1080, 1.0), 3);
+        assert_eq!(radius.radius_px(1920, 1080, 64.0), 10);
+
+        let clamped = NodeRadius { min_px: 2, max_px: Some(5), ..NodeRadius::default() };
+        assert_eq!(clamped.radius_px(480, 270, 0.1), 2);
+        assert_eq!(clamped.radius_px(1920, 1080, 64.0), 5);
+
+        let scaled = NodeRadius { scale: 0.5, log_base: 2.0, ..NodeRadius::default() };
+        assert_eq!(scaled.radius_px(1920, 1080, 64.0), 10);
+
+        for invalid in [NodeRadius { scale: 0.0, ..NodeRadius::default() },
+                        NodeRadius { scale: f64::NAN, ..NodeRadius::default() },
+                        NodeRadius { log_base: 1.0, ..NodeRadius::default() },
+                        NodeRadius { min_px: 3, max_px: Some(2), ..NodeRadius::default() }] {
+            assert!(invalid.validate().is_err());
+        }
     }
 
-    /// Clones the underlying image buffer, transforms it into a PNG image and returns the image
-    /// as raw bytes
-    pub fn png_bytes(&self) -> Vec<u8> {
-        let mut buf = Cursor::new(Vec::new());
-        DynamicImage::ImageRgb8(self.img_buf.clone())
-            .write_to(&mut buf, ImageOutputFormat::Png)
-            .expect("Failed to encode view as PNG image");
-        buf.into_inner()
+    #[test]
+    fn test_root_palette() {
+        let palette = root_palette(50);
+        assert_eq!(palette.len(), 50);
+        for (i, color) in palette.iter().enumerate() {
+            assert_eq!(color.layer, Color::RED.layer);
+            assert!(color.rgb != Color::YELLOW.rgb && color.rgb != Color::BLUE.rgb);
+            assert!(palette[..i].iter().all(|other| other.rgb != color.rgb));
+        }
+    }
+
+    #[test]
+    fn test_concurrent_compute() {
+        let graph = Arc::new(Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap());
+        let node_data = deterministic_node_data(&*graph);
+        let view = View::new(graph, 480, 270);
+        let options = ViewOptions::default();
+
+        // Frames of the same view can be rendered concurrently without affecting each other
+        let times: Vec<TimeUnit> = vec![0, 150, 0, 150];
+        let frames: Vec<_> = times.par_iter()
+            .map(|time| view.compute_alt(1.0, time, &node_data, &options))
+            .collect();
+        assert_eq!(frames[0], frames[2]);
+        assert_eq!(frames[1], frames[3]);
+        assert_ne!(frames[0], frames[1]);
     }
 
-    /// Save the underlying image buffer to a file
-    #[allow(dead_code)]
-    pub fn save_to_file(&self, path: &str) {
-        self.img_buf.save(path).expect("Failed to save view to file");
+    #[test]
+    fn test_view_layers() {
+        assert_eq!("burned".parse(), Ok(ViewLayers::empty().with(ViewLayer::Burned)));
+        let layers: ViewLayers = "edges, roots,planned".parse().unwrap();
+        assert!(layers.contains(ViewLayer::Edges) && layers.contains(ViewLayer::Roots)
+            && layers.contains(ViewLayer::Planned) && !layers.contains(ViewLayer::Untouched));
+        assert!(!ViewLayers::default().contains(ViewLayer::Planned));
+        assert!("burned,smoke".parse::<ViewLayers>().is_err());
+        assert!("".parse::<ViewLayers>().is_err());
+
+        let graph = Arc::new(Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap());
+        let node_data = deterministic_node_data(&*graph);
+        let view = View::new(graph, 480, 270);
+        let compute = |time: TimeUnit, layers: &str| {
+            let options = ViewOptions { layers: layers.parse().unwrap(), ..ViewOptions::default() };
+            view.compute_alt(1.0, &time, &node_data, &options)
+        };
+
+        // Selecting all default layers yields the default view
+        let full = compute(150, "edges,untouched,burned,defended,roots");
+        assert_eq!(full, view.compute_alt(1.0, &150, &node_data, &ViewOptions::default()));
+
+        // Burned nodes over a faint network, without any other nodes
+        let burned = compute(150, "edges,burned");
+        assert_ne!(burned, full);
+        assert!(burned.pixels().all(|px| [Color::DARK_GREY.rgb, FAINT_EDGE_RGB, Color::RED.rgb].contains(px)));
+        assert!(burned.pixels().any(|px| *px == Color::RED.rgb));
+
+        let defended = compute(150, "defended");
+        assert!(defended.pixels().all(|px| [Color::DARK_GREY.rgb, Color::BLUE.rgb].contains(px)));
+        assert!(defended.pixels().any(|px| *px == Color::BLUE.rgb));
+
+        // Nodes defended in round 1 are planned in round 0
+        let planned = compute(0, "planned");
+        assert!(planned.pixels().any(|px| *px == Color::LIGHT_BLUE.rgb));
+        assert!(compute(1, "planned").pixels().all(|px| *px == Color::DARK_GREY.rgb));
+    }
+
+    #[test]
+    fn test_golden_overview_start() {
+        assert_matches_golden("overview_start", 1.0, 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_golden_overview_spread() {
+        assert_matches_golden("overview_spread", 1.0, 150);
+    }
+
+    #[test]
+    fn test_golden_zoomed_spread() {
+        assert_matches_golden("zoomed_spread", 4.0, 150);
+    }
+
+    #[test]
+    fn test_golden_zoomed_end() {
+        assert_matches_golden("zoomed_end", 4.0, TimeUnit::MAX);
+    }
+}
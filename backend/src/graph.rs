@@ -1,19 +1,226 @@
-use std::cmp::Ordering;
-use std::fmt::Formatter;
-use std::fs::File;
-use std::io::{prelude::*, BufReader};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fmt::{Debug, Formatter};
+use std::fs::{self, File};
+use std::io::{self, prelude::*, BufReader};
 use std::num::{ParseIntError, ParseFloatError};
+use std::str::FromStr;
+#[cfg(feature = "pbf")]
+use std::collections::{HashMap, HashSet};
 
-use serde::Serialize;
+#[cfg(feature = "pbf")]
+use geo::{HaversineDistance, Point};
+use once_cell::sync::OnceCell;
+use rayon::prelude::*;
+#[cfg(feature = "pbf")]
+use osmpbf::{Element, ElementReader};
+use serde::{Deserialize, Serialize};
 
+use crate::base_layer::BaseLayers;
 use crate::binary_minheap::BinaryMinHeap;
+use crate::compute;
+use crate::bucket_queue::BucketQueue;
+use crate::graph_binary::GraphStorage;
+use crate::graph_ch::ContractionHierarchy;
+use crate::graph_coarsen::CoarsenedGraph;
+use crate::graph_components::Components;
+use crate::graph_validate::{validate_graph, GraphValidation};
+use crate::simplify::{self, LodLevel};
+use crate::spatial_index::SpatialIndex;
+use self::weights::NodeWeights;
+
+pub mod formats;
+pub mod weights;
 
 /// Type alias for the result of a run of the Dijkstra algorithm
 type DijkstraResult = Vec<usize>;
 
+/// Type alias for the result of a run of the Dijkstra algorithm with distances and predecessors
+type DijkstraTreeResult = (Vec<usize>, Vec<usize>);
+
+/// Maximum edge weight of graphs on which Dijkstras use a bucket queue (Dial's algorithm) instead
+/// of a binary heap. The queue keeps one bucket per edge weight, so it does not pay off for large
+/// edge weights.
+const DIAL_MAX_EDGE_DIST: usize = 1 << 12;
+
+/// Maximum number of landmarks of the ALT algorithm
+const MAX_LANDMARKS: usize = 8;
+
+/// Landmarks of the ALT algorithm with their distances to and from all nodes, from which lower
+/// bounds of the distances between any two nodes follow by the triangle inequality
+#[derive(Debug, Default)]
+struct Landmarks {
+    /// Distances from every landmark to all nodes
+    from: Vec<Vec<usize>>,
+    /// Distances from all nodes to every landmark
+    to: Vec<Vec<usize>>,
+}
+
+impl Landmarks {
+    /// Select up to `MAX_LANDMARKS` landmarks of `graph` far apart from each other, i.e. every
+    /// landmark is the node farthest from all previous landmarks, starting with the node farthest
+    /// from the first node
+    fn select(graph: &Graph) -> Self {
+        let mut landmarks = Self::default();
+        let mut min_dists = graph.run_dijkstra_plain(&[0]);
+        while landmarks.from.len() < MAX_LANDMARKS.min(graph.num_nodes) {
+            // Unreachable nodes are the farthest, such that every component gets landmarks
+            let landmark = (0..graph.num_nodes)
+                .max_by_key(|&node_id| (min_dists[node_id], node_id))
+                .unwrap();
+            if min_dists[landmark] == 0 {
+                break;
+            }
+            let from = graph.run_dijkstra_plain(&[landmark]);
+            for (min_dist, &dist) in min_dists.iter_mut().zip(&from) {
+                *min_dist = (*min_dist).min(dist);
+            }
+            landmarks.from.push(from);
+            landmarks.to.push(reverse_dijkstra(graph, landmark));
+        }
+        landmarks
+    }
+
+    /// Get a lower bound of the distance from the node with id `node_id` to the node with id
+    /// `tgt_id`
+    fn lower_bound(&self, node_id: usize, tgt_id: usize) -> usize {
+        let mut bound = 0;
+        for (from, to) in self.from.iter().zip(&self.to) {
+            // d(v, t) >= d(L, t) - d(L, v) and d(v, t) >= d(v, L) - d(t, L)
+            if from[node_id] < usize::MAX && from[tgt_id] < usize::MAX {
+                bound = bound.max(from[tgt_id].saturating_sub(from[node_id]));
+            }
+            if to[node_id] < usize::MAX && to[tgt_id] < usize::MAX {
+                bound = bound.max(to[node_id].saturating_sub(to[tgt_id]));
+            }
+        }
+        bound
+    }
+}
+
+/// Index the incoming edges of all nodes of a graph with `num_nodes` nodes and the edges `edges`
+/// ordered by source node. Returns the indices of all edges ordered by target node and then by
+/// source node, and the offsets of the incoming edges of every node into them.
+fn index_incoming_edges(num_nodes: usize, edges: &[Edge]) -> (Vec<u32>, Vec<u32>) {
+    let mut in_offsets = vec![0u32; num_nodes + 1];
+    for edge in edges {
+        in_offsets[edge.tgt() + 1] += 1;
+    }
+    for i in 1..in_offsets.len() {
+        in_offsets[i] += in_offsets[i - 1];
+    }
+    // Placing the edges in their order keeps the incoming edges of every node ordered by source
+    let mut next_slots = in_offsets.clone();
+    let mut in_edge_ids = vec![0u32; edges.len()];
+    for (edge_id, edge) in edges.iter().enumerate() {
+        let slot = &mut next_slots[edge.tgt()];
+        in_edge_ids[*slot as usize] = edge_id as u32;
+        *slot += 1;
+    }
+    (in_edge_ids, in_offsets)
+}
+
+/// Run a Dijkstra to the node with id `tgt_id` along the incoming edges of `graph`.
+/// Returns the distances of all nodes to the target node.
+fn reverse_dijkstra(graph: &Graph, tgt_id: usize) -> DijkstraResult {
+    let mut distances = vec![usize::MAX; graph.num_nodes];
+    distances[tgt_id] = 0;
+    let mut pq = BinaryMinHeap::with_capacity(graph.num_nodes);
+    pq.push(tgt_id, &distances);
+    while !pq.is_empty() {
+        let node = pq.pop(&distances);
+        for edge in graph.get_incoming_edges(node) {
+            let (src, dist) = (edge.src(), distances[node] + edge.dist());
+            if dist < distances[src] {
+                distances[src] = dist;
+                if pq.contains(src) {
+                    pq.decrease_key(src, &distances);
+                } else {
+                    pq.push(src, &distances);
+                }
+            }
+        }
+    }
+    distances
+}
+
+/// Distances from a growing set of source nodes to all nodes of a graph. Adding sources can only
+/// shorten distances, so when sources are added, only the nodes that get closer to the new
+/// sources than to the previous ones are searched again instead of running a Dijkstra from all
+/// sources.
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalDistances {
+    distances: Vec<usize>,
+    is_source: Vec<bool>,
+    num_sources: usize,
+}
+
+impl IncrementalDistances {
+    /// Create new incremental distances without any sources
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the distances from the sources to all nodes, which are empty before any sources have
+    /// been added
+    pub fn distances(&self) -> &[usize] {
+        &self.distances
+    }
+
+    /// Get the number of sources
+    pub fn num_sources(&self) -> usize {
+        self.num_sources
+    }
+
+    /// Add the nodes with ids `src_ids` of `graph` as sources and update the distances. Nodes
+    /// that are sources already are skipped. The graph must not change between updates.
+    /// Returns the updated distances.
+    pub fn add_sources(&mut self, graph: &dyn GraphView, src_ids: &[usize]) -> &[usize] {
+        if self.is_source.is_empty() {
+            self.is_source = vec![false; graph.num_nodes()];
+        }
+        let new_src_ids: Vec<_> = src_ids.iter().copied()
+            .filter(|&src_id| !self.is_source[src_id])
+            .collect();
+        for &src_id in &new_src_ids {
+            if !self.is_source[src_id] {
+                self.is_source[src_id] = true;
+                self.num_sources += 1;
+            }
+        }
+
+        if self.distances.is_empty() {
+            self.distances = graph.run_dijkstra_par(&new_src_ids);
+            return &self.distances;
+        }
+
+        // Search from the new sources, but only through nodes whose distances shorten
+        let mut pq = BinaryHeap::new();
+        for &src_id in &new_src_ids {
+            if self.distances[src_id] > 0 {
+                self.distances[src_id] = 0;
+                pq.push(Reverse((0, src_id)));
+            }
+        }
+        while let Some(Reverse((node_dist, node_id))) = pq.pop() {
+            if node_dist > self.distances[node_id] {
+                continue;
+            }
+            for edge in graph.get_outgoing_edges(node_id) {
+                let dist = node_dist + edge.dist();
+                if dist < self.distances[edge.tgt()] {
+                    self.distances[edge.tgt()] = dist;
+                    pq.push(Reverse((dist, edge.tgt())));
+                }
+            }
+        }
+        &self.distances
+    }
+}
+
 /// Struct to hold the grid bounds of a graph or part of a graph
-#[derive(Debug, Serialize)]
-pub(crate) struct GridBounds {
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GridBounds {
     pub min_lat: f64,
     pub max_lat: f64,
     pub min_lon: f64,
@@ -26,6 +233,29 @@ impl GridBounds {
         self.min_lat >= other.min_lat && self.max_lat <= other.max_lat
             && self.min_lon >= other.min_lon && self.max_lon <= other.max_lon
     }
+
+    /// Get the compass direction of the coordinates `lat`/`lon` relative to this grid bounds
+    pub(crate) fn get_compass_direction_of(&self, lat: f64, lon: f64) -> CompassDirection {
+        if lon >= self.min_lon && lon <= self.max_lon && lat > self.max_lat {
+            CompassDirection::North
+        } else if lon > self.max_lon && lat > self.max_lat {
+            CompassDirection::NorthEast
+        } else if lon > self.max_lon && lat >= self.min_lat && lat <= self.max_lat {
+            CompassDirection::East
+        } else if lon > self.max_lon && lat < self.min_lat {
+            CompassDirection::SouthEast
+        } else if lon >= self.min_lon && lon <= self.max_lon && lat < self.min_lat {
+            CompassDirection::South
+        } else if lon < self.min_lon && lat < self.min_lat {
+            CompassDirection::SouthWest
+        } else if lon < self.min_lon && lat >= self.min_lat && lat <= self.max_lat {
+            CompassDirection::West
+        } else if lon < self.min_lon && lat > self.max_lat {
+            CompassDirection::NorthWest
+        } else {
+            CompassDirection::Zero
+        }
+    }
 }
 
 /// Compass directions related to grid bounds
@@ -41,48 +271,84 @@ pub(crate) enum CompassDirection {
     Zero,
 }
 
+/// Maximum number of nodes and of edges of a graph, such that their ids fit into 32 bits
+pub const MAX_GRAPH_ELEMENTS: usize = u32::MAX as usize;
+
+/// Maximum distance of an edge, such that distances fit into 32 bits
+pub const MAX_EDGE_DIST: usize = u32::MAX as usize;
+
+/// Id of a node, i.e. its index in the nodes of its graph. Ids are stored with 32 bits, which
+/// nearly halves the memory of edges and edge offsets compared to `usize` ids. The accessors of
+/// nodes and edges return ids as `usize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// Create the id of the node at index `index`.
+    /// Panics if the index exceeds `MAX_GRAPH_ELEMENTS`, which graphs are checked against when
+    /// they are parsed.
+    pub fn new(index: usize) -> Self {
+        assert!(index <= MAX_GRAPH_ELEMENTS, "Node id out of range: {}", index);
+        Self(index as u32)
+    }
+
+    /// Get the index of the node
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl FromStr for NodeId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl From<NodeId> for usize {
+    fn from(node_id: NodeId) -> Self {
+        node_id.index()
+    }
+}
+
 /// A graph node
 ///
 /// # Attributes
 /// * `id` - An id uniquely identifying the node
+/// * `osm_id` - The external id of the node in the OSM data the graph was derived from
 /// * `lat` - The nodes latitude coordinate
 /// * `lon` - The nodes longitude coordinate
-#[derive(Debug, Serialize, Default)]
+#[derive(Debug, Clone, Serialize, Default)]
+#[repr(C)]
 pub struct Node {
-    pub id: usize,
+    id: NodeId,
+    /// Explicit padding, such that nodes can be written to binary graph files as raw bytes
+    #[serde(skip)]
+    _padding: u32,
+    pub osm_id: u64,
     pub lat: f64,
     pub lon: f64,
 }
 
 impl Node {
+    /// Create a new node with id `id`
+    pub fn new(id: usize, osm_id: u64, lat: f64, lon: f64) -> Self {
+        Self { id: NodeId::new(id), _padding: 0, osm_id, lat, lon }
+    }
+
+    /// Get the id of this node
+    pub fn id(&self) -> usize {
+        self.id.index()
+    }
+
     /// Returns true if this node is located within the given grid bounds
     pub(crate) fn is_located_in(&self, gb: &GridBounds) -> bool {
         self.lat >= gb.min_lat && self.lat <= gb.max_lat
             && self.lon >= gb.min_lon && self.lon  <= gb.max_lon
     }
-
-    /// Get the compass direction of this node relative to the given grid bounds
-    pub(crate) fn get_relative_compass_direction(&self, gb: &GridBounds) -> CompassDirection {
-        if self.lon >= gb.min_lon && self.lon <= gb.max_lon && self.lat > gb.max_lat {
-            CompassDirection::North
-        } else if self.lon > gb.max_lon && self.lat > gb.max_lat {
-            CompassDirection::NorthEast
-        } else if self.lon > gb.max_lon && self.lat >= gb.min_lat && self.lat <= gb.max_lat {
-            CompassDirection::East
-        } else if self.lon > gb.max_lon && self.lat < gb.min_lat {
-            CompassDirection::SouthEast
-        } else if self.lon >= gb.min_lon && self.lon <= gb.max_lon && self.lat < gb.min_lat {
-            CompassDirection::South
-        } else if self.lon < gb.min_lon && self.lat < gb.min_lat {
-            CompassDirection::SouthWest
-        } else if self.lon < gb.min_lon && self.lat >= gb.min_lat && self.lat <= gb.max_lat {
-            CompassDirection::West
-        } else if self.lon < gb.min_lon && self.lat > gb.max_lat {
-            CompassDirection::NorthWest
-        } else {
-            CompassDirection::Zero
-        }
-    }
 }
 
 /// A directed and weighted graph edge
@@ -91,23 +357,191 @@ impl Node {
 /// * `src` - The id of the source node
 /// * `tgt` - The id of the target node
 /// * `dist` - The distance between source and target
-#[derive(Debug, Serialize, Default)]
+/// * `attrs` - The road class and speed limit of the road the edge belongs to
+#[derive(Debug, Clone, Serialize, Default)]
+#[repr(C)]
 pub struct Edge {
-    pub src: usize,
-    pub tgt: usize,
-    pub dist: usize,
+    src: NodeId,
+    tgt: NodeId,
+    dist: u32,
+    pub attrs: EdgeAttrs,
+}
+
+impl Edge {
+    /// Create a new edge from the node with id `src` to the node with id `tgt`.
+    /// Panics if the distance exceeds `MAX_EDGE_DIST`.
+    pub fn new(src: usize, tgt: usize, dist: usize, attrs: EdgeAttrs) -> Self {
+        assert!(dist <= MAX_EDGE_DIST, "Edge distance out of range: {}", dist);
+        Self { src: NodeId::new(src), tgt: NodeId::new(tgt), dist: dist as u32, attrs }
+    }
+
+    /// Get the id of the source node
+    pub fn src(&self) -> usize {
+        self.src.index()
+    }
+
+    /// Get the id of the target node
+    pub fn tgt(&self) -> usize {
+        self.tgt.index()
+    }
+
+    /// Get the distance between source and target
+    pub fn dist(&self) -> usize {
+        self.dist as usize
+    }
+}
+
+/// Attributes of the road an edge belongs to, as written by the graph tool.
+/// Attributes that are unknown or missing in the graph file are `0`.
+///
+/// # Attributes
+/// * `edge_type` - The road class, e.g. motorway or footpath
+/// * `maxspeed` - The speed limit in km/h
+#[derive(Debug, Clone, Copy, Serialize, Default, PartialEq)]
+#[repr(C)]
+pub struct EdgeAttrs {
+    pub edge_type: u32,
+    pub maxspeed: u32,
 }
 
-/// A directed and weighted graph with nodes and edges
+/// Mapping of the external OSM id of a node to its id
+#[derive(Debug, Serialize, Default)]
+#[repr(C)]
+pub(crate) struct ExtId {
+    osm_id: u64,
+    node_id: NodeId,
+    /// Explicit padding, such that external ids can be written to binary graph files as raw bytes
+    #[serde(skip)]
+    _padding: u32,
+}
+
+impl ExtId {
+    fn new(osm_id: u64, node_id: usize) -> Self {
+        Self { osm_id, node_id: NodeId::new(node_id), _padding: 0 }
+    }
+}
+
+/// Source of graph files that do not specify their source
+const DEFAULT_SOURCE: &str = "OpenStreetMap";
+/// License of graph files that do not specify their license
+const DEFAULT_LICENSE: &str = "ODbL-1.0";
+/// Attribution of graph files that do not specify their attribution
+const DEFAULT_ATTRIBUTION: &str = "© OpenStreetMap contributors";
+
+/// Metadata of a graph from the header lines of its graph file, which look like `# Key : Value`.
+/// The source, license and attribution default to those of OpenStreetMap data, since all graphs
+/// are derived from it.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphMetadata {
+    pub source: String,
+    pub license: String,
+    /// Attribution required for all images and data derived from the graph
+    pub attribution: String,
+    /// Unix timestamp of the data the graph was derived from
+    pub timestamp: Option<u64>,
+    /// All header fields by key
+    pub properties: BTreeMap<String, String>,
+}
+
+impl Default for GraphMetadata {
+    fn default() -> Self {
+        Self {
+            source: DEFAULT_SOURCE.to_string(),
+            license: DEFAULT_LICENSE.to_string(),
+            attribution: DEFAULT_ATTRIBUTION.to_string(),
+            timestamp: None,
+            properties: BTreeMap::new(),
+        }
+    }
+}
+
+impl GraphMetadata {
+    /// Parse the metadata from the header lines `lines` of a graph file, without the leading `#`.
+    /// Lines that are not of the form `Key : Value` are ignored.
+    pub(crate) fn from_header_lines<'a>(lines: impl Iterator<Item = &'a str>) -> Self {
+        let properties: BTreeMap<_, _> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .filter(|(key, _)| !key.is_empty())
+            .collect();
+        let get = |key: &str| properties.iter()
+            .find(|(other, _)| other.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.clone());
+
+        let defaults = Self::default();
+        Self {
+            source: get("Source").unwrap_or(defaults.source),
+            license: get("License").unwrap_or(defaults.license),
+            attribution: get("Attribution").unwrap_or(defaults.attribution),
+            timestamp: get("Timestamp").and_then(|timestamp| timestamp.parse().ok()),
+            properties,
+        }
+    }
+
+    /// Read the metadata from the header lines of the graph file at `graph_file_path`
+    pub(crate) fn read_from_file(graph_file_path: &str) -> io::Result<Self> {
+        let mut header_lines = Vec::new();
+        for line in BufReader::new(File::open(graph_file_path)?).lines() {
+            let line = line?;
+            match line.strip_prefix('#') {
+                Some(header_line) => header_lines.push(header_line.to_string()),
+                None => break
+            }
+        }
+        Ok(Self::from_header_lines(header_lines.iter().map(String::as_str)))
+    }
+}
+
+/// A directed and weighted graph with nodes and edges.
+/// Nodes, edges and their indices are either owned or memory-mapped from a binary graph file.
+/// The incoming edges of all nodes are indexed when the graph is created.
 #[derive(Debug, Serialize, Default)]
 pub struct Graph {
-    nodes: Vec<Node>,
-    edges: Vec<Edge>,
-    offsets: Vec<usize>,
+    nodes: GraphStorage<Node>,
+    edges: GraphStorage<Edge>,
+    offsets: GraphStorage<u32>,
+    ext_ids: GraphStorage<ExtId>,
+    /// Indices of all edges into the edges, ordered by target node and then by source node
+    #[serde(skip)]
+    in_edge_ids: Vec<u32>,
+    /// Offsets of the incoming edges of all nodes into the edge indices `in_edge_ids`
+    #[serde(skip)]
+    in_offsets: Vec<u32>,
     pub num_nodes: usize,
     pub num_edges: usize,
+    metadata: GraphMetadata,
+    #[serde(skip)]
+    lod_levels: OnceCell<Vec<LodLevel>>,
+    #[serde(skip)]
+    ch: OnceCell<ContractionHierarchy>,
+    #[serde(skip)]
+    landmarks: OnceCell<Landmarks>,
+    #[serde(skip)]
+    weak_components: OnceCell<Components>,
+    #[serde(skip)]
+    strong_components: OnceCell<Components>,
+    #[serde(skip)]
+    coarsened: OnceCell<CoarsenedGraph>,
+    /// Rasters of all edges from which views are drawn, which are only available for graphs
+    /// loaded along with their base layer files
+    #[serde(skip)]
+    base_layers: OnceCell<BaseLayers>,
+    /// Weights and classes of the nodes, which are only available for graphs loaded along with
+    /// a sidecar file of node weights
+    #[serde(skip)]
+    node_weights: OnceCell<NodeWeights>,
+    #[serde(skip)]
+    max_edge_dist: usize,
+    /// Grid over the node coordinates, which is built along with the graph
+    #[serde(skip)]
+    spatial_index: SpatialIndex,
 }
 
+/// Values of the `highway` tag of OSM ways that are not roads, e.g. planned or abandoned roads
+#[cfg(feature = "pbf")]
+const NON_ROAD_HIGHWAYS: [&str; 6] = ["proposed", "construction", "abandoned", "disused", "platform",
+    "razed"];
+
 /// Unstable float comparison.
 /// # Returns
 /// * `a < b`: `Ordering::Less`
@@ -116,207 +550,894 @@ fn unstable_cmp_f64(a: f64, b: f64) -> Ordering {
     if a < b { Ordering::Less } else { Ordering::Greater }
 }
 
+/// Split the text `text` after its first `num_lines` lines, or at its end if it has fewer lines
+fn split_lines(text: &str, num_lines: usize) -> (&str, &str) {
+    let mut end = 0;
+    for _ in 0..num_lines {
+        match text[end..].find('\n') {
+            Some(len) => end += len + 1,
+            None => return (text, "")
+        }
+    }
+    text.split_at(end)
+}
+
 impl Graph {
     /// Parse node and edge data from a file into a directed graph.
+    /// The file is read into memory at once, and its node and edge blocks are parsed concurrently.
     /// Returns a `Result` containing the parsed graph if the operation succeeds, or an
     /// `Err` otherwise.
     pub fn parse_from_file(graph_file_path: &str) -> Result<Self, ParseError> {
-        let graph_file = File::open(graph_file_path)?;
-        let graph_reader = BufReader::new(graph_file);
+        let text = fs::read_to_string(graph_file_path)?;
 
         log::debug!("Start parsing graph: {}", graph_file_path);
 
-        let mut lines = graph_reader.lines();
-        let mut line_no = 0;
-
-        loop {
-            let line = lines.next()
-                .expect(&format!("Unexpected EOF while parsing header after line {}", line_no))?;
-            line_no += 1;
+        let mut rest = text.as_str();
+        let mut num_lines = 0;
+        // Get the next line and its number, or an error if the file ends before the `expected` line
+        let mut next_line = |expected: &'static str| -> Result<(&str, usize), ParseError> {
+            if rest.is_empty() {
+                return Err(ParseError::UnexpectedEof { line: num_lines, expected });
+            }
+            let (line, tail) = split_lines(rest, 1);
+            rest = tail;
+            num_lines += 1;
+            Ok((line.lines().next().unwrap_or(""), num_lines))
+        };
 
-            if !line.starts_with("#") {
-                break;
+        // The header ends with an empty line
+        let mut header_lines = Vec::new();
+        let (mut line, mut line_no) = loop {
+            let (line, line_no) = next_line("number of nodes")?;
+            match line.strip_prefix('#') {
+                Some(header_line) => header_lines.push(header_line),
+                None => break (line, line_no)
             }
+        };
+        let metadata = GraphMetadata::from_header_lines(header_lines.into_iter());
+        if line.trim().is_empty() {
+            (line, line_no) = next_line("number of nodes")?;
         }
 
-        let num_nodes = lines.next()
-            .expect("Unexpected EOF while parsing number of nodes")?
-            .parse()?;
-        if num_nodes <= 0 {
+        let num_nodes: usize = LineTokens::new(line, line_no).parse("number of nodes")?;
+        if num_nodes == 0 {
             return Err(ParseError::EmptyNodes);
         }
-        let num_edges = lines.next()
-            .expect("Unexpected EOF while parsing number of edges")?
-            .parse()?;
-        line_no += 2;
+        let (line, line_no) = next_line("number of edges")?;
+        let num_edges: usize = LineTokens::new(line, line_no).parse("number of edges")?;
+        if num_nodes > MAX_GRAPH_ELEMENTS || num_edges > MAX_GRAPH_ELEMENTS {
+            return Err(ParseError::TooLarge);
+        }
+
+        // The node block is followed by the edge block, so errors in the node block come first
+        let (node_block, edge_block) = split_lines(rest, num_nodes);
+        let (nodes, edges) = rayon::join(
+            || Self::parse_nodes(node_block, line_no + 1, num_nodes),
+            || Self::parse_edges(edge_block, line_no + num_nodes + 1, num_nodes, num_edges));
+        let (nodes, ext_ids) = nodes?;
+        log::debug!("Parsed {} nodes", num_nodes);
+        let (edges, offsets) = edges?;
+        log::debug!("Parsed {} edges and computed node offsets", num_edges);
+
+        let mut graph = Self::from_storage(GraphStorage::Owned(nodes), GraphStorage::Owned(edges),
+                                           GraphStorage::Owned(offsets), GraphStorage::Owned(ext_ids));
+        graph.metadata = metadata;
+        Ok(graph)
+    }
 
+    /// Parse the `num_nodes` nodes from the node block `block` of a graph file, which starts in
+    /// line `first_line_no`. Returns the nodes and their external ids sorted by OSM id.
+    fn parse_nodes(block: &str, first_line_no: usize, num_nodes: usize) -> Result<(Vec<Node>, Vec<ExtId>), ParseError> {
+        let mut lines = block.lines();
         let mut nodes = Vec::with_capacity(num_nodes);
+        let mut ext_ids = Vec::with_capacity(num_nodes);
         for i in 0..num_nodes {
+            let line_no = first_line_no + i;
             let line = lines.next()
-                .expect(&format!("Unexpected EOF while parsing nodes after line {}", line_no))?;
-            let mut split = line.split(" ");
-            line_no += 1;
-            split.next(); // id
-            split.next(); // second id
-
-            let node = Node {
-                id: i,
-                lat: split.next()
-                    .expect(&format!("Unexpected EOL while parsing node latitude in line {}",
-                                     line_no))
-                    .parse()?,
-                lon: split.next()
-                    .expect(&format!("Unexpected EOL while parsing node longitude in line {}",
-                                     line_no))
-                    .parse()?,
-            };
+                .ok_or(ParseError::UnexpectedEof { line: line_no - 1, expected: "node" })?;
+            let mut tokens = LineTokens::new(line, line_no);
+            tokens.next("node id")?;
+
+            let node = Node::new(
+                i,
+                tokens.parse("node OSM id")?,
+                tokens.parse("node latitude")?,
+                tokens.parse("node longitude")?,
+            );
+            ext_ids.push(ExtId::new(node.osm_id, i));
             nodes.push(node);
         }
-        log::debug!("Parsed {} nodes", num_nodes);
+        ext_ids.sort_unstable_by_key(|ext_id| ext_id.osm_id);
+        Ok((nodes, ext_ids))
+    }
 
+    /// Parse the `num_edges` edges between `num_nodes` nodes from the edge block `block` of a
+    /// graph file, which starts in line `first_line_no`. Lines after the edges are ignored.
+    /// Returns the edges and the offsets of the outgoing edges of all nodes.
+    fn parse_edges(block: &str, first_line_no: usize, num_nodes: usize, num_edges: usize)
+        -> Result<(Vec<Edge>, Vec<u32>), ParseError> {
+        let mut lines = block.lines();
         let mut next_src: usize = 0;
-        let mut offset: usize = 0;
         let mut edges = Vec::with_capacity(num_edges);
         let mut offsets = vec![0; num_nodes + 1];
-        for _ in 0..num_edges {
+        for i in 0..num_edges {
+            let line_no = first_line_no + i;
             let line = lines.next()
-                .expect(&format!("Unexpected EOF while parsing edges after line {}", line_no))?;
-            let mut split = line.split(" ");
-            line_no += 1;
+                .ok_or(ParseError::UnexpectedEof { line: line_no - 1, expected: "edge" })?;
+            let mut tokens = LineTokens::new(line, line_no);
 
+            let src = tokens.parse_node_id("edge source", num_nodes)?;
+            if src.index() + 1 < next_src {
+                return Err(tokens.unordered_source());
+            }
             let edge = Edge {
-                src: split.next()
-                    .expect(&format!("Unexpected EOL while parsing edge source in line {}",
-                                     line_no))
-                    .parse()?,
-                tgt: split.next()
-                    .expect(&format!("Unexpected EOL while parsing edge target in line {}",
-                                     line_no))
-                    .parse()?,
-                dist: split.next()
-                    .expect(&format!("Unexpected EOL while parsing edge weight in line {}",
-                                     line_no))
-                    .parse()?,
+                src,
+                tgt: tokens.parse_node_id("edge target", num_nodes)?,
+                dist: tokens.parse("edge weight")?,
+                // Road attributes are optional and may be non-numeric, e.g. a maxspeed of `walk`
+                attrs: EdgeAttrs {
+                    edge_type: tokens.next_optional().and_then(|edge_type| edge_type.parse().ok()).unwrap_or(0),
+                    maxspeed: tokens.next_optional().and_then(|maxspeed| maxspeed.parse().ok()).unwrap_or(0),
+                },
             };
 
-            if edge.src >= next_src {
-                for j in next_src..=edge.src {
-                    offsets[j] = offset;
-                }
-                next_src = edge.src + 1;
+            // The offset of the first outgoing edge of a node is the index of that edge
+            if edge.src() >= next_src {
+                offsets[next_src..=edge.src()].fill(i as u32);
+                next_src = edge.src() + 1;
             }
-            offset += 1;
 
             edges.push(edge);
         }
-        for i in next_src..=num_nodes {
-            offsets[i] = num_edges;
+        offsets[next_src..=num_nodes].fill(num_edges as u32);
+        Ok((edges, offsets))
+    }
+
+    /// Import the road network of the OSM PBF extract at `pbf_file_path` into an undirected graph.
+    /// All ways tagged as roads become edges between their consecutive nodes in both directions,
+    /// weighted by their great-circle distance in meters. Edges keep the speed limit of their road,
+    /// but have no road class. Nodes that are not part of any road are
+    /// dropped, and the remaining nodes get ids in the order of their OSM ids.
+    /// Returns a `Result` containing the imported graph if the operation succeeds, or an
+    /// `Err` otherwise.
+    #[cfg(feature = "pbf")]
+    pub fn parse_from_pbf(pbf_file_path: &str) -> Result<Self, ParseError> {
+        log::debug!("Start importing OSM PBF extract: {}", pbf_file_path);
+
+        // The nodes of ways may be stored after them, so ways and nodes are read in two passes
+        let mut ways = Vec::new();
+        ElementReader::from_path(pbf_file_path)?.for_each(|element| {
+            if let Element::Way(way) = element {
+                let is_road = way.tags().any(|(key, value)| key == "highway"
+                    && !NON_ROAD_HIGHWAYS.contains(&value));
+                if is_road {
+                    let maxspeed = way.tags()
+                        .find(|(key, _)| *key == "maxspeed")
+                        .and_then(|(_, maxspeed)| maxspeed.parse().ok())
+                        .unwrap_or(0);
+                    ways.push((way.refs().collect::<Vec<_>>(), maxspeed));
+                }
+            }
+        })?;
+        let road_osm_ids: HashSet<_> = ways.iter().flat_map(|(refs, _)| refs).copied().collect();
+        log::debug!("Read {} roads", ways.len());
+
+        let mut coords = HashMap::with_capacity(road_osm_ids.len());
+        ElementReader::from_path(pbf_file_path)?.for_each(|element| {
+            let (osm_id, lat, lon) = match element {
+                Element::Node(node) => (node.id(), node.lat(), node.lon()),
+                Element::DenseNode(node) => (node.id(), node.lat(), node.lon()),
+                _ => return
+            };
+            if road_osm_ids.contains(&osm_id) {
+                coords.insert(osm_id, (lat, lon));
+            }
+        })?;
+
+        // Nodes missing in the extract, e.g. outside of its bounding box, are skipped
+        let mut osm_ids: Vec<_> = coords.keys().copied().filter(|&osm_id| osm_id >= 0).collect();
+        if osm_ids.is_empty() {
+            return Err(ParseError::EmptyNodes);
         }
-        log::debug!("Parsed {} edges and computed node offsets", num_edges);
+        if osm_ids.len() > MAX_GRAPH_ELEMENTS {
+            return Err(ParseError::TooLarge);
+        }
+        osm_ids.sort_unstable();
+        let node_ids: HashMap<_, _> = osm_ids.iter().enumerate()
+            .map(|(node_id, &osm_id)| (osm_id, node_id))
+            .collect();
+        let nodes: Vec<_> = osm_ids.iter().enumerate()
+            .map(|(id, osm_id)| {
+                let (lat, lon) = coords[osm_id];
+                Node::new(id, *osm_id as u64, lat, lon)
+            })
+            .collect();
+        let ext_ids: Vec<_> = nodes.iter()
+            .map(|node| ExtId::new(node.osm_id, node.id()))
+            .collect();
+        log::debug!("Imported {} nodes", nodes.len());
+
+        let mut edges = Vec::new();
+        for (refs, maxspeed) in &ways {
+            let road_node_ids: Vec<_> = refs.iter()
+                .filter_map(|osm_id| node_ids.get(osm_id).copied())
+                .collect();
+            for pair in road_node_ids.windows(2) {
+                let (src, tgt) = (&nodes[pair[0]], &nodes[pair[1]]);
+                if src.id == tgt.id {
+                    continue;
+                }
+                let dist = Point::new(src.lon, src.lat)
+                    .haversine_distance(&Point::new(tgt.lon, tgt.lat))
+                    .round() as u32;
+                let attrs = EdgeAttrs { edge_type: 0, maxspeed: *maxspeed };
+                edges.push(Edge { src: src.id, tgt: tgt.id, dist, attrs });
+                edges.push(Edge { src: tgt.id, tgt: src.id, dist, attrs });
+            }
+        }
+        // Roads sharing a segment result in parallel edges, of which only the shortest is kept
+        edges.sort_unstable_by_key(|edge| (edge.src, edge.tgt, edge.dist));
+        edges.dedup_by_key(|edge| (edge.src, edge.tgt));
+        if edges.len() > MAX_GRAPH_ELEMENTS {
+            return Err(ParseError::TooLarge);
+        }
+
+        // Count the outgoing edges of each node, then turn the counts into offsets
+        let mut offsets = vec![0; nodes.len() + 1];
+        for edge in &edges {
+            offsets[edge.src()] += 1;
+        }
+        let mut offset = 0;
+        for count in offsets.iter_mut() {
+            let num_outgoing = *count;
+            *count = offset;
+            offset += num_outgoing;
+        }
+        log::debug!("Imported {} edges and computed node offsets", edges.len());
+
+        Ok(Self::from_storage(GraphStorage::Owned(nodes), GraphStorage::Owned(edges),
+                              GraphStorage::Owned(offsets), GraphStorage::Owned(ext_ids)))
+    }
 
-        Ok(Self {
+    /// Create a new graph from its nodes, edges, edge offsets and external ids sorted by OSM id
+    pub(crate) fn from_storage(nodes: GraphStorage<Node>, edges: GraphStorage<Edge>,
+                               offsets: GraphStorage<u32>, ext_ids: GraphStorage<ExtId>) -> Self {
+        let (in_edge_ids, in_offsets) = index_incoming_edges(nodes.len(), &edges);
+        Self {
+            num_nodes: nodes.len(),
+            num_edges: edges.len(),
+            max_edge_dist: edges.iter().map(Edge::dist).max().unwrap_or(0),
+            spatial_index: SpatialIndex::new(&nodes),
+            in_edge_ids,
+            in_offsets,
             nodes,
             edges,
             offsets,
-            num_nodes,
-            num_edges,
-        })
+            ext_ids,
+            metadata: GraphMetadata::default(),
+            lod_levels: OnceCell::new(),
+            ch: OnceCell::new(),
+            landmarks: OnceCell::new(),
+            weak_components: OnceCell::new(),
+            strong_components: OnceCell::new(),
+            coarsened: OnceCell::new(),
+            base_layers: OnceCell::new(),
+            node_weights: OnceCell::new(),
+        }
     }
 
-    /// Returns a reference to the vector containing all graph nodes
-    pub fn nodes(&self) -> &Vec<Node> {
-        &self.nodes
+    /// Create a new graph from its nodes, which must have consecutive ids starting at `0`, and
+    /// its edges in any order
+    pub(crate) fn from_nodes_and_edges(nodes: Vec<Node>, mut edges: Vec<Edge>) -> Self {
+        edges.sort_by_key(|edge| edge.src());
+        let mut offsets = vec![0u32; nodes.len() + 1];
+        for edge in &edges {
+            offsets[edge.src() + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+        let mut ext_ids: Vec<_> = nodes.iter()
+            .map(|node| ExtId::new(node.osm_id, node.id()))
+            .collect();
+        ext_ids.sort_unstable_by_key(|ext_id| ext_id.osm_id);
+
+        Self::from_storage(GraphStorage::Owned(nodes), GraphStorage::Owned(edges),
+                           GraphStorage::Owned(offsets), GraphStorage::Owned(ext_ids))
     }
 
-    /// Returns a reference to the node with id `node_id`
-    pub fn get_node(&self, node_id: usize) -> &Node {
-        &self.nodes[node_id]
+    /// Replace the metadata of this graph by `metadata`
+    pub(crate) fn set_metadata(&mut self, metadata: GraphMetadata) {
+        self.metadata = metadata;
     }
 
-    /// Get the number of outgoing edges of the node with id `node_id`
-    pub fn get_node_degree(&self, node_id: usize) -> usize {
-        self.offsets[node_id + 1] - self.offsets[node_id]
+    /// Returns a reference to the slice containing all graph nodes
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
     }
 
-    /// Returns a reference to the vector containing all graph edges
-    pub fn edges(&self) -> &Vec<Edge> {
+    /// Returns a reference to the node with OSM id `osm_id`, if there is such a node
+    pub fn get_node_by_osm_id(&self, osm_id: u64) -> Option<&Node> {
+        self.ext_ids.binary_search_by_key(&osm_id, |ext_id| ext_id.osm_id)
+            .ok()
+            .map(|i| &self.nodes[self.ext_ids[i].node_id.index()])
+    }
+
+    /// Returns a reference to the slice containing all graph edges
+    pub fn edges(&self) -> &[Edge] {
         &self.edges
     }
 
-    /// Get the outgoing edges of the node with id `node_id`
-    pub fn get_outgoing_edges(&self, node_id: usize) -> &[Edge] {
-        &self.edges[self.offsets[node_id]..self.offsets[node_id + 1]]
+    /// Get the incoming edges of the node with id `node_id` ordered by source node, e.g. to find
+    /// the predecessors of the node
+    pub fn get_incoming_edges(&self, node_id: usize) -> impl Iterator<Item = &Edge> + '_ {
+        self.in_edge_ids[self.in_offsets[node_id] as usize..self.in_offsets[node_id + 1] as usize]
+            .iter()
+            .map(move |&edge_id| &self.edges[edge_id as usize])
     }
 
-    /// Run an one-to-all Dijkstra from the source node with id `src_id`
-    pub fn run_dijkstra(&self, src_ids: &[usize]) -> DijkstraResult {
+    /// Returns the offsets of the outgoing edges of all nodes into the edges
+    pub(crate) fn offsets(&self) -> &[u32] {
+        &self.offsets
+    }
+
+    /// Returns the external ids of all nodes sorted by OSM id
+    pub(crate) fn ext_ids(&self) -> &[ExtId] {
+        &self.ext_ids
+    }
+
+    /// Get the levels of detail of this graph ordered from finest to coarsest, which are computed
+    /// on first use. Graphs are loaded with their levels of detail precomputed.
+    pub(crate) fn lod_levels(&self) -> &[LodLevel] {
+        self.lod_levels.get_or_init(|| simplify::compute_lod_levels(self))
+    }
+
+    /// Get the coarsest level of detail whose simplification tolerance is at most half of
+    /// `deg_per_px`, i.e. that is visually indistinguishable from the full graph when rendered
+    /// with `deg_per_px` degrees per pixel.
+    /// Returns `None` if the full graph should be rendered.
+    pub(crate) fn get_lod_level(&self, deg_per_px: f64) -> Option<&LodLevel> {
+        self.lod_levels()
+            .iter()
+            .rev()
+            .find(|level| level.tolerance <= deg_per_px / 2.0)
+    }
+
+    /// Get the base layers of this graph, if they have been loaded
+    pub(crate) fn base_layers(&self) -> Option<&BaseLayers> {
+        self.base_layers.get()
+    }
+
+    /// Set the base layers of this graph to `base_layers`, unless they are already set
+    pub(crate) fn set_base_layers(&self, base_layers: BaseLayers) {
+        let _ = self.base_layers.set(base_layers);
+    }
+
+    /// Set the node weights of this graph to `node_weights`, unless they are already set
+    pub(crate) fn set_node_weights(&self, node_weights: NodeWeights) {
+        let _ = self.node_weights.set(node_weights);
+    }
+
+    /// Run a Dijkstra on this graph without using its contraction hierarchy
+    fn run_dijkstra_plain(&self, src_ids: &[usize]) -> DijkstraResult {
+        dijkstra(self, src_ids, None, usize::MAX)
+    }
+
+    /// Run an A* search with landmarks (ALT) from the source nodes with ids `src_ids` to the
+    /// nearest of the target nodes with ids `tgt_ids`. The search is guided towards the targets
+    /// by lower bounds of their distances, which follow from the distances to and from a few
+    /// landmarks selected on first use. Point-to-point queries settle far fewer nodes than a
+    /// Dijkstra this way.
+    /// Returns the id of the nearest target node and its distance, or `None` if no target node
+    /// is reachable.
+    pub fn run_alt(&self, src_ids: &[usize], tgt_ids: &[usize]) -> Option<(usize, usize)> {
+        let landmarks = self.landmarks.get_or_init(|| Landmarks::select(self));
+        let heuristic = |node_id: usize| tgt_ids.iter()
+            .map(|&tgt_id| landmarks.lower_bound(node_id, tgt_id))
+            .min()
+            .unwrap_or(0);
+        let mut is_target = vec![false; self.num_nodes];
+        for &tgt_id in tgt_ids {
+            is_target[tgt_id] = true;
+        }
+
         let mut distances = vec![usize::MAX; self.num_nodes];
+        let mut pq = BinaryHeap::new();
         for &src_id in src_ids {
             distances[src_id] = 0;
+            pq.push(Reverse((heuristic(src_id), src_id)));
+        }
+        while let Some(Reverse((estimate, node_id))) = pq.pop() {
+            if estimate > distances[node_id] + heuristic(node_id) {
+                continue;
+            }
+            // The heuristic is consistent, so the first settled target is the nearest one
+            if is_target[node_id] {
+                return Some((node_id, distances[node_id]));
+            }
+            for edge in self.get_outgoing_edges(node_id) {
+                let dist = distances[node_id] + edge.dist();
+                if dist < distances[edge.tgt()] {
+                    distances[edge.tgt()] = dist;
+                    pq.push(Reverse((dist + heuristic(edge.tgt()), edge.tgt())));
+                }
+            }
         }
+        None
+    }
 
-        let mut pq = BinaryMinHeap::with_capacity(self.num_nodes);
-        for &src_id in src_ids {
-            pq.push(src_id, &distances);
+    /// Get the contraction hierarchy of this graph, which is built on first use. Once it is
+    /// built, all Dijkstras on this graph that only compute distances use it.
+    pub fn contraction_hierarchy(&self) -> &ContractionHierarchy {
+        self.ch.get_or_init(|| ContractionHierarchy::new(self))
+    }
+
+    /// Get the weakly connected components of this graph, which are computed on first use
+    pub fn weak_components(&self) -> &Components {
+        self.weak_components.get_or_init(|| Components::weakly_connected(self))
+    }
+
+    /// Get the strongly connected components of this graph, which are computed on first use
+    pub fn strong_components(&self) -> &Components {
+        self.strong_components.get_or_init(|| Components::strongly_connected(self))
+    }
+
+    /// Get the coarsened version of this graph, which is computed on first use. Simulations on it
+    /// approximate simulations on this graph at a fraction of the cost.
+    pub fn coarsened(&self) -> &CoarsenedGraph {
+        self.coarsened.get_or_init(|| CoarsenedGraph::new(self))
+    }
+
+    /// Validate this graph, i.e. report dangling, duplicate, zero-distance and asymmetric edges
+    /// and nodes with outlying coordinates
+    pub fn validate(&self) -> GraphValidation {
+        validate_graph(self.nodes(), self.edges())
+    }
+
+    /// Extract the subgraph of all nodes located within the grid bounds `gb` and all edges
+    /// between them. The nodes keep their order, but get consecutive ids starting at `0`. The
+    /// subgraph has no nodes if no node is located within `gb`.
+    pub fn subgraph(&self, gb: &GridBounds) -> Graph {
+        let mut node_ids: Vec<_> = self.spatial_index.in_bounds(&self.nodes, gb).collect();
+        node_ids.sort_unstable();
+        let mut new_ids = vec![usize::MAX; self.num_nodes];
+        for (new_id, &node_id) in node_ids.iter().enumerate() {
+            new_ids[node_id] = new_id;
+        }
+
+        let nodes: Vec<_> = node_ids.iter().enumerate()
+            .map(|(new_id, &node_id)| {
+                let node = &self.nodes[node_id];
+                Node::new(new_id, node.osm_id, node.lat, node.lon)
+            })
+            .collect();
+        // The new ids are in the order of the old ids, so the edges stay ordered by source node
+        let mut edges = Vec::new();
+        let mut offsets = Vec::with_capacity(nodes.len() + 1);
+        for &node_id in &node_ids {
+            offsets.push(edges.len() as u32);
+            edges.extend(self.get_outgoing_edges(node_id).iter()
+                .filter(|edge| new_ids[edge.tgt()] != usize::MAX)
+                .map(|edge| Edge::new(new_ids[node_id], new_ids[edge.tgt()], edge.dist(), edge.attrs)));
+        }
+        offsets.push(edges.len() as u32);
+        let ext_ids: Vec<_> = self.ext_ids.iter()
+            .filter(|ext_id| new_ids[ext_id.node_id.index()] != usize::MAX)
+            .map(|ext_id| ExtId::new(ext_id.osm_id, new_ids[ext_id.node_id.index()]))
+            .collect();
+
+        let mut subgraph = Self::from_storage(GraphStorage::Owned(nodes), GraphStorage::Owned(edges),
+                                              GraphStorage::Owned(offsets), GraphStorage::Owned(ext_ids));
+        subgraph.set_metadata(self.metadata.clone());
+        subgraph
+    }
+}
+
+/// Read-only view of a directed and weighted graph.
+/// Views are implemented by graphs themselves and by overlays that modify the edges of a shared
+/// graph without copying it, such that the Dijkstra, the fire spread and the strategies work on
+/// both.
+pub trait GraphView: Debug + Send + Sync {
+    /// Get the number of nodes
+    fn num_nodes(&self) -> usize;
+
+    /// Get the number of edges
+    fn num_edges(&self) -> usize;
+
+    /// Returns a reference to the node with id `node_id`
+    fn get_node(&self, node_id: usize) -> &Node;
+
+    /// Get the outgoing edges of the node with id `node_id`
+    fn get_outgoing_edges(&self, node_id: usize) -> &[Edge];
+
+    /// Returns the metadata of the graph
+    fn metadata(&self) -> &GraphMetadata;
+
+    /// Returns the graph itself if this view does not modify any graph
+    fn as_graph(&self) -> Option<&Graph> {
+        None
+    }
+
+    /// Returns the weights and classes of the nodes, if the graph has been loaded along with them
+    fn node_weights(&self) -> Option<&NodeWeights> {
+        None
+    }
+
+    /// Get the number of outgoing edges of the node with id `node_id`
+    fn get_node_degree(&self, node_id: usize) -> usize {
+        self.get_outgoing_edges(node_id).len()
+    }
+
+    /// Get the attributes of the edge from the node with id `src` to the node with id `tgt`.
+    /// Returns `None` if there is no such edge.
+    fn get_edge_attrs(&self, src: usize, tgt: usize) -> Option<EdgeAttrs> {
+        self.get_outgoing_edges(src).iter()
+            .find(|edge| edge.tgt() == tgt)
+            .map(|edge| edge.attrs)
+    }
+
+    /// Get an upper bound of the weights of all edges
+    fn max_edge_dist(&self) -> usize {
+        self.iter_edges().map(|edge| edge.dist()).max().unwrap_or(0)
+    }
+
+    /// Iterate over all nodes ordered by id
+    fn iter_nodes(&self) -> Box<dyn Iterator<Item = &Node> + '_> {
+        Box::new((0..self.num_nodes()).map(move |node_id| self.get_node(node_id)))
+    }
+
+    /// Iterate over all edges ordered by source node
+    fn iter_edges(&self) -> Box<dyn Iterator<Item = &Edge> + '_> {
+        Box::new((0..self.num_nodes()).flat_map(move |node_id| self.get_outgoing_edges(node_id)))
+    }
+
+    /// Returns a reference to the node that is closest to the coordinates `lat`/`lon`
+    /// # Panics
+    /// Panics if the graph has no nodes
+    fn nearest_node(&self, lat: f64, lon: f64) -> &Node {
+        self.iter_nodes()
+            .min_by(|n1, n2| {
+                let d1 = (n1.lat - lat).powi(2) + (n1.lon - lon).powi(2);
+                let d2 = (n2.lat - lat).powi(2) + (n2.lon - lon).powi(2);
+                unstable_cmp_f64(d1, d2)
+            })
+            // Calling unwrap is safe because the graph consists of at least one node
+            .unwrap()
+    }
+
+    /// Iterate over all nodes that are located within the grid bounds `gb`
+    fn nodes_in_bounds<'a>(&'a self, gb: &'a GridBounds) -> Box<dyn Iterator<Item = &'a Node> + 'a> {
+        Box::new(self.iter_nodes().filter(move |node| node.is_located_in(gb)))
+    }
+
+    /// Returns the grid bounds of the graph, i.e. the minimal/maximal latitude/longitude
+    fn get_grid_bounds(&self) -> GridBounds {
+        let mut gb = GridBounds {
+            min_lat: f64::INFINITY,
+            max_lat: f64::NEG_INFINITY,
+            min_lon: f64::INFINITY,
+            max_lon: f64::NEG_INFINITY,
+        };
+        // The graph consists of at least one node, so all bounds are finite
+        for node in self.iter_nodes() {
+            gb.min_lat = gb.min_lat.min(node.lat);
+            gb.max_lat = gb.max_lat.max(node.lat);
+            gb.min_lon = gb.min_lon.min(node.lon);
+            gb.max_lon = gb.max_lon.max(node.lon);
+        }
+        gb
+    }
+
+    /// Run an one-to-all Dijkstra from the source node with id `src_id`
+    fn run_dijkstra(&self, src_ids: &[usize]) -> DijkstraResult {
+        dijkstra(self, src_ids, None, usize::MAX)
+    }
+
+    /// Run an one-to-all Dijkstra from the source nodes with ids `src_ids` in parallel. The
+    /// sources are split into one chunk per thread of the Dijkstra pool, the chunks are searched
+    /// in parallel and the minimum distances of all chunks are merged.
+    fn run_dijkstra_par(&self, src_ids: &[usize]) -> DijkstraResult {
+        dijkstra_par(self, src_ids)
+    }
+
+    /// Run an one-to-all Dijkstra from the source nodes with ids `src_ids` that only explores
+    /// nodes within distance `max_dist`. All other nodes have the distance `usize::MAX`.
+    fn run_dijkstra_within(&self, src_ids: &[usize], max_dist: usize) -> DijkstraResult {
+        dijkstra(self, src_ids, None, max_dist)
+    }
+
+    /// Run an one-to-all Dijkstra from the source nodes with ids `src_ids` and additionally
+    /// return the predecessor of every node in the resulting shortest path tree.
+    /// Source nodes and unreachable nodes have the predecessor `usize::MAX`.
+    fn run_dijkstra_tree(&self, src_ids: &[usize]) -> DijkstraTreeResult {
+        let mut preds = vec![usize::MAX; self.num_nodes()];
+        let distances = dijkstra(self, src_ids, Some(&mut preds), usize::MAX);
+        (distances, preds)
+    }
+}
+
+impl GraphView for Graph {
+    fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    fn num_edges(&self) -> usize {
+        self.num_edges
+    }
+
+    fn get_node(&self, node_id: usize) -> &Node {
+        &self.nodes[node_id]
+    }
+
+    fn get_outgoing_edges(&self, node_id: usize) -> &[Edge] {
+        &self.edges[self.offsets[node_id] as usize..self.offsets[node_id + 1] as usize]
+    }
+
+    fn metadata(&self) -> &GraphMetadata {
+        &self.metadata
+    }
+
+    fn as_graph(&self) -> Option<&Graph> {
+        Some(self)
+    }
+
+    fn node_weights(&self) -> Option<&NodeWeights> {
+        self.node_weights.get()
+    }
+
+    fn get_node_degree(&self, node_id: usize) -> usize {
+        (self.offsets[node_id + 1] - self.offsets[node_id]) as usize
+    }
+
+    fn max_edge_dist(&self) -> usize {
+        self.max_edge_dist
+    }
+
+    fn iter_nodes(&self) -> Box<dyn Iterator<Item = &Node> + '_> {
+        Box::new(self.nodes.iter())
+    }
+
+    fn iter_edges(&self) -> Box<dyn Iterator<Item = &Edge> + '_> {
+        Box::new(self.edges.iter())
+    }
+
+    fn nearest_node(&self, lat: f64, lon: f64) -> &Node {
+        let node_id = self.spatial_index.nearest(&self.nodes, lat, lon)
+            .expect("Graph should consist of at least one node");
+        &self.nodes[node_id]
+    }
+
+    fn nodes_in_bounds<'a>(&'a self, gb: &'a GridBounds) -> Box<dyn Iterator<Item = &'a Node> + 'a> {
+        Box::new(self.spatial_index.in_bounds(&self.nodes, gb).map(move |node_id| &self.nodes[node_id]))
+    }
+
+    fn run_dijkstra(&self, src_ids: &[usize]) -> DijkstraResult {
+        match self.ch.get() {
+            Some(ch) => ch.distances_from(src_ids),
+            None => dijkstra(self, src_ids, None, usize::MAX)
         }
+    }
+
+    fn run_dijkstra_par(&self, src_ids: &[usize]) -> DijkstraResult {
+        // A single sweep of the contraction hierarchy covers all sources at once, which is
+        // cheaper than merging one sweep per thread
+        match self.ch.get() {
+            Some(ch) => ch.distances_from(src_ids),
+            None => dijkstra_par(self, src_ids)
+        }
+    }
+}
+
+/// Parallel Dijkstra implementation on `graph`, see `GraphView::run_dijkstra_par`
+fn dijkstra_par<G: GraphView + ?Sized>(graph: &G, src_ids: &[usize]) -> DijkstraResult {
+    // Simulations with a thread limit run their parallel Dijkstras on the pool of the limit
+    let limited_pool = compute::limited_pool_of_thread();
+    let pool = match &limited_pool {
+        Some(pool) => pool,
+        None => compute::dijkstra_pool()
+    };
+    let num_threads = pool.current_num_threads();
+    if num_threads < 2 || src_ids.len() < 2 {
+        return graph.run_dijkstra(src_ids);
+    }
+
+    let chunk_size = (src_ids.len() - 1) / num_threads + 1;
+    pool.install(|| src_ids.par_chunks(chunk_size)
+        .map(|chunk| graph.run_dijkstra(chunk))
+        .reduce_with(|mut dists, chunk_dists| {
+            for (dist, chunk_dist) in dists.iter_mut().zip(chunk_dists) {
+                *dist = (*dist).min(chunk_dist);
+            }
+            dists
+        })
+        .unwrap_or_else(|| vec![usize::MAX; graph.num_nodes()]))
+}
+
+/// Common Dijkstra implementation on `graph` that optionally tracks predecessors in `preds` and
+/// does not explore nodes farther away than `max_dist`. Graphs with small edge weights are
+/// searched with Dial's algorithm, which avoids the logarithmic cost of the binary heap.
+fn dijkstra<G: GraphView + ?Sized>(graph: &G, src_ids: &[usize], preds: Option<&mut Vec<usize>>,
+                                   max_dist: usize) -> DijkstraResult {
+    let max_edge_dist = graph.max_edge_dist();
+    if max_edge_dist <= DIAL_MAX_EDGE_DIST {
+        dial(graph, src_ids, preds, max_dist, max_edge_dist)
+    } else {
+        heap_dijkstra(graph, src_ids, preds, max_dist)
+    }
+}
 
-        while !pq.is_empty() {
-            let node = pq.pop(&distances);
+/// Dijkstra implementation with a binary heap, see `dijkstra`
+fn heap_dijkstra<G: GraphView + ?Sized>(graph: &G, src_ids: &[usize], mut preds: Option<&mut Vec<usize>>,
+                                        max_dist: usize) -> DijkstraResult {
+    let mut distances = vec![usize::MAX; graph.num_nodes()];
+    for &src_id in src_ids {
+        distances[src_id] = 0;
+    }
+
+    let mut pq = BinaryMinHeap::with_capacity(graph.num_nodes());
+    for &src_id in src_ids {
+        pq.push(src_id, &distances);
+    }
 
-            for i in self.offsets[node]..self.offsets[node +1] {
-                let edge = &self.edges[i];
-                let dist = distances[node] + edge.dist;
+    while !pq.is_empty() {
+        let node = pq.pop(&distances);
 
-                if dist < distances[edge.tgt] {
-                    distances[edge.tgt] = dist;
+        for edge in graph.get_outgoing_edges(node) {
+            let dist = distances[node] + edge.dist();
 
-                    if pq.contains(edge.tgt) {
-                        pq.decrease_key(edge.tgt, &distances);
-                    } else {
-                        pq.push(edge.tgt, &distances);
-                    }
+            if dist < distances[edge.tgt()] && dist <= max_dist {
+                distances[edge.tgt()] = dist;
+                if let Some(ref mut preds) = preds {
+                    preds[edge.tgt()] = node;
+                }
+
+                if pq.contains(edge.tgt()) {
+                    pq.decrease_key(edge.tgt(), &distances);
+                } else {
+                    pq.push(edge.tgt(), &distances);
                 }
             }
         }
+    }
+
+    distances
+}
 
-        distances
+/// Dijkstra implementation with a bucket queue (Dial's algorithm) for graphs whose edge weights
+/// are at most `max_edge_dist`, see `dijkstra`
+fn dial<G: GraphView + ?Sized>(graph: &G, src_ids: &[usize], mut preds: Option<&mut Vec<usize>>,
+                               max_dist: usize, max_edge_dist: usize) -> DijkstraResult {
+    let mut distances = vec![usize::MAX; graph.num_nodes()];
+    let mut pq = BucketQueue::new(max_edge_dist);
+    for &src_id in src_ids {
+        if distances[src_id] > 0 {
+            distances[src_id] = 0;
+            pq.push(src_id, 0);
+        }
     }
 
-    /// Returns this graphs grid bounds, i.e. the minimal/maximal latitude/longitude
-    /// of this graph
-    pub(crate) fn get_grid_bounds(&self) -> GridBounds {
-        let latitudes: Vec<_> = self.nodes.iter()
-            .map(|n| n.lat)
-            .collect();
-        let longitudes: Vec<_> = self.nodes.iter()
-            .map(|n| n.lon)
-            .collect();
+    while let Some((node, node_dist)) = pq.pop() {
+        // Nodes are pushed again whenever their distance decreases, so skip outdated entries
+        if node_dist > distances[node] {
+            continue;
+        }
+
+        for edge in graph.get_outgoing_edges(node) {
+            let dist = node_dist + edge.dist();
 
-        GridBounds {
-            min_lat: *latitudes.iter()
-                .min_by(|&lat1, &lat2| unstable_cmp_f64(*lat1, *lat2))
-                // Calling unwrap is safe because the implementation of parse_graph ensures that the graph
-                // consists of at least one node
-                .unwrap(),
-            max_lat: *latitudes.iter()
-                .max_by(|&lat1, &lat2| unstable_cmp_f64(*lat1, *lat2))
-                .unwrap(),
-            min_lon: *longitudes.iter()
-                .min_by(|&lon1, &lon2| unstable_cmp_f64(*lon1, *lon2))
-                .unwrap(),
-            max_lon: *longitudes.iter()
-                .max_by(|&lon1, &lon2| unstable_cmp_f64(*lon1, *lon2))
-                .unwrap(),
+            if dist < distances[edge.tgt()] && dist <= max_dist {
+                distances[edge.tgt()] = dist;
+                if let Some(ref mut preds) = preds {
+                    preds[edge.tgt()] = node;
+                }
+                pq.push(edge.tgt(), dist);
+            }
+        }
+    }
+
+    distances
+}
+
+/// Whitespace separated tokens of a line of a graph file, which locate parse errors by their line
+/// and column
+struct LineTokens<'a> {
+    line: &'a str,
+    line_no: usize,
+    /// Byte offset of the rest of the line
+    pos: usize,
+    /// Column and text of the last token
+    last: (usize, &'a str),
+}
+
+impl<'a> LineTokens<'a> {
+    fn new(line: &'a str, line_no: usize) -> Self {
+        Self { line, line_no, pos: 0, last: (1, "") }
+    }
+
+    /// Get the next token, or `None` at the end of the line
+    fn next_optional(&mut self) -> Option<&'a str> {
+        let rest = &self.line[self.pos..];
+        let start = self.pos + rest.len() - rest.trim_start().len();
+        let end = self.line[start..].find(char::is_whitespace).map_or(self.line.len(), |len| start + len);
+        if start == end {
+            return None;
+        }
+        self.pos = end;
+        self.last = (self.line[..start].chars().count() + 1, &self.line[start..end]);
+        Some(self.last.1)
+    }
+
+    /// Get the next token, or an error if the line ends before the `expected` token
+    fn next(&mut self, expected: &'static str) -> Result<&'a str, ParseError> {
+        let (line, column) = (self.line_no, self.line.chars().count() + 1);
+        self.next_optional().ok_or(ParseError::UnexpectedEol { line, column, expected })
+    }
+
+    /// Parse the next token as the `expected` value
+    fn parse<T: FromStr>(&mut self, expected: &'static str) -> Result<T, ParseError> {
+        self.next(expected)?.parse().map_err(|_| ParseError::InvalidToken {
+            line: self.line_no,
+            column: self.last.0,
+            token: self.last.1.to_string(),
+            expected,
+        })
+    }
+
+    /// Parse the next token as the `expected` id of one of the first `num_nodes` nodes
+    fn parse_node_id(&mut self, expected: &'static str, num_nodes: usize) -> Result<NodeId, ParseError> {
+        let node_id: usize = self.parse(expected)?;
+        if node_id >= num_nodes {
+            return Err(ParseError::UnknownNode {
+                line: self.line_no,
+                column: self.last.0,
+                token: self.last.1.to_string(),
+            });
+        }
+        Ok(NodeId::new(node_id))
+    }
+
+    /// Create the error that the last token is the source of an edge that is not ordered by source
+    fn unordered_source(&self) -> ParseError {
+        ParseError::UnorderedEdges {
+            line: self.line_no,
+            column: self.last.0,
+            token: self.last.1.to_string(),
         }
     }
 }
 
+/// Error while parsing a graph. Errors in graph files are located by their line and column, which
+/// start at `1`, and name the offending token if there is one.
 #[derive(Debug)]
 pub enum ParseError {
     IO(std::io::Error),
     ParseInt(ParseIntError),
     ParseFloat(ParseFloatError),
+    #[cfg(feature = "pbf")]
+    Pbf(osmpbf::Error),
     EmptyNodes,
+    TooLarge,
+    /// The file ends after line `line` before the `expected` line
+    UnexpectedEof { line: usize, expected: &'static str },
+    /// The line `line` ends at column `column` before the `expected` token
+    UnexpectedEol { line: usize, column: usize, expected: &'static str },
+    /// The token `token` is no valid `expected` value
+    InvalidToken { line: usize, column: usize, token: String, expected: &'static str },
+    /// The token `token` is the id of no node of the graph
+    UnknownNode { line: usize, column: usize, token: String },
+    /// The token `token` is the source of an edge that follows edges with greater sources
+    UnorderedEdges { line: usize, column: usize, token: String },
+    /// The XML of a GraphML file is malformed at line `line` and column `column`
+    Xml { line: usize, column: usize, source: quick_xml::Error },
+    /// The node `node` has no coordinates
+    MissingCoordinates { node: String },
+    /// The JSON of a GeoJSON file is malformed
+    Json(serde_json::Error),
+    /// The header of a CSV file has no column of the `expected` values
+    MissingColumn { expected: &'static str },
+    /// The feature with number `feature` of a GeoJSON file, starting at `1`, has no valid
+    /// `expected` value
+    InvalidFeature { feature: usize, expected: &'static str },
 }
 
 impl std::fmt::Display for ParseError {
@@ -325,7 +1446,27 @@ impl std::fmt::Display for ParseError {
             Self::IO(err) => write!(f, "{}", err.to_string()),
             Self::ParseInt(err) => write!(f, "{}", err.to_string()),
             Self::ParseFloat(err) => write!(f, "{}", err.to_string()),
+            #[cfg(feature = "pbf")]
+            Self::Pbf(err) => write!(f, "{}", err),
             Self::EmptyNodes => write!(f, "Graph must consist of at least one node"),
+            Self::TooLarge => write!(f, "Graph must consist of at most {} nodes and edges", MAX_GRAPH_ELEMENTS),
+            Self::UnexpectedEof { line, expected } =>
+                write!(f, "Unexpected end of file after line {} while parsing {}", line, expected),
+            Self::UnexpectedEol { line, column, expected } =>
+                write!(f, "Unexpected end of line {} at column {} while parsing {}", line, column, expected),
+            Self::InvalidToken { line, column, token, expected } =>
+                write!(f, "Invalid {} '{}' in line {}, column {}", expected, token, line, column),
+            Self::UnknownNode { line, column, token } =>
+                write!(f, "Unknown node '{}' in line {}, column {}", token, line, column),
+            Self::UnorderedEdges { line, column, token } =>
+                write!(f, "Edges are not ordered by source at '{}' in line {}, column {}", token, line, column),
+            Self::Xml { line, column, source } =>
+                write!(f, "Malformed XML in line {}, column {}: {}", line, column, source),
+            Self::MissingCoordinates { node } => write!(f, "Missing coordinates of node '{}'", node),
+            Self::Json(err) => write!(f, "{}", err),
+            Self::MissingColumn { expected } => write!(f, "Missing column of the {}", expected),
+            Self::InvalidFeature { feature, expected } =>
+                write!(f, "Invalid {} of feature {}", expected, feature),
         }
     }
 }
@@ -336,7 +1477,13 @@ impl std::error::Error for ParseError {
             Self::IO(ref err) => Some(err),
             Self::ParseInt(ref err) => Some(err),
             Self::ParseFloat(ref err) => Some(err),
-            Self::EmptyNodes => None,
+            #[cfg(feature = "pbf")]
+            Self::Pbf(ref err) => Some(err),
+            Self::Xml { ref source, .. } => Some(source),
+            Self::Json(ref err) => Some(err),
+            Self::EmptyNodes | Self::TooLarge | Self::UnexpectedEof { .. } | Self::UnexpectedEol { .. }
+            | Self::InvalidToken { .. } | Self::UnknownNode { .. } | Self::UnorderedEdges { .. }
+            | Self::MissingCoordinates { .. } | Self::MissingColumn { .. } | Self::InvalidFeature { .. } => None,
         }
     }
 }
@@ -359,12 +1506,21 @@ impl From<ParseFloatError> for ParseError {
     }
 }
 
+#[cfg(feature = "pbf")]
+impl From<osmpbf::Error> for ParseError {
+    fn from(err: osmpbf::Error) -> Self {
+        Self::Pbf(err)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::cmp::min;
+    use std::mem;
     use rand::prelude::*;
 
-    use crate::graph::Graph;
+    use crate::graph::{dial, heap_dijkstra, Edge, EdgeAttrs, ExtId, Graph, GraphMetadata, GraphView,
+                       GridBounds, IncrementalDistances, Node, NodeId, MAX_GRAPH_ELEMENTS};
 
     #[test]
     fn test_nodes_edges() {
@@ -373,6 +1529,85 @@ mod test {
 
         assert_eq!(graph.nodes.len(), 350);
         assert_eq!(graph.edges.len(), 706);
+
+        // Road class and speed limit of the edge `1 274 6 11 50`
+        let attrs = graph.get_edge_attrs(1, 274).unwrap();
+        assert_eq!((attrs.edge_type, attrs.maxspeed), (11, 50));
+        assert!(graph.get_edge_attrs(1, 2).is_none());
+    }
+
+    #[test]
+    fn test_compact_layout() {
+        // Node ids, edge distances and offsets are stored with 32 bits
+        assert_eq!(mem::size_of::<Edge>(), 20);
+        assert_eq!(mem::size_of::<Node>(), 32);
+        assert_eq!(mem::size_of::<ExtId>(), 16);
+
+        let edge = Edge::new(3, 7, 42, EdgeAttrs::default());
+        assert_eq!((edge.src(), edge.tgt(), edge.dist()), (3, 7, 42));
+        assert_eq!(usize::from(NodeId::new(MAX_GRAPH_ELEMENTS)), MAX_GRAPH_ELEMENTS);
+        assert!(std::panic::catch_unwind(|| NodeId::new(MAX_GRAPH_ELEMENTS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_metadata() {
+        let graph =
+            Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+
+        let metadata = graph.metadata();
+        assert_eq!(metadata.timestamp, Some(1633904534));
+        assert_eq!(metadata.properties["Type"], "maxspeed");
+        assert_eq!(metadata.license, "ODbL-1.0");
+
+        let metadata = GraphMetadata::from_header_lines(
+            [" License : CC-BY-4.0", " Attribution : Example", "no key value"].iter().copied());
+        assert_eq!(metadata.license, "CC-BY-4.0");
+        assert_eq!(metadata.attribution, "Example");
+        assert_eq!(metadata.source, "OpenStreetMap");
+        assert_eq!(metadata.properties.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |name: &str, content: &str| {
+            let path = std::env::temp_dir().join(format!("osmff_parse_{}_{}.fmi", std::process::id(), name));
+            std::fs::write(&path, content).unwrap();
+            let result = Graph::parse_from_file(path.to_str().unwrap());
+            std::fs::remove_file(path).unwrap();
+            result
+        };
+        let nodes = "# Id : 0\n\n3\n2\n0 10 48.7 9.1\n1 11 48.7 9.2\n2 12 48.8 9.1\n";
+
+        let graph = parse("valid", &format!("{}0 1 5\n1 2 7 3 walk\n", nodes)).unwrap();
+        assert_eq!(graph.num_edges, 2);
+        assert_eq!(graph.edges()[1].attrs, EdgeAttrs { edge_type: 3, maxspeed: 0 });
+        let graph = parse("crlf", &format!("{}0 1 5\n1 2 7\n", nodes).replace('\n', "\r\n")).unwrap();
+        assert_eq!((graph.num_nodes, graph.num_edges), (3, 2));
+        assert_eq!(graph.get_node(2).lon, 9.1);
+
+        let errors = [
+            ("truncated", format!("{}0 1 5\n", nodes), "Unexpected end of file after line 8 while parsing edge"),
+            ("truncated_nodes", "\n3\n0\n0 10 48.7 9.1\n".to_string(), "Unexpected end of file after line 4 while parsing node"),
+            ("eol", format!("{}0 1\n1 2 7\n", nodes), "Unexpected end of line 8 at column 4 while parsing edge weight"),
+            ("token", "\n3\n0\n0 10 48.7 9.1\n1 x 48.7 9.2\n".to_string(), "Invalid node OSM id 'x' in line 5, column 3"),
+            ("node", format!("{}0 1 5\n1  3 7\n", nodes), "Unknown node '3' in line 9, column 4"),
+            ("order", format!("{}1 2 5\n0 1 7\n", nodes), "Edges are not ordered by source at '0' in line 9, column 1"),
+        ];
+        for (name, content, message) in errors {
+            assert_eq!(parse(name, &content).unwrap_err().to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_node_lookup() {
+        let graph =
+            Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+
+        let node = graph.get_node(347);
+        assert_eq!(node.osm_id, 8684768723);
+        assert_eq!(graph.get_node_by_osm_id(8684768723).unwrap().id(), 347);
+        assert!(graph.get_node_by_osm_id(1).is_none());
+        assert_eq!(graph.nearest_node(node.lat + 1e-7, node.lon - 1e-7).id(), 347);
     }
 
     #[test]
@@ -393,7 +1628,7 @@ mod test {
             Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
 
         let edges_with_src_70: Vec<_> = graph.edges.iter()
-            .filter(|&e| e.src == 70)
+            .filter(|&e| e.src() == 70)
             .collect();
         assert_eq!(edges_with_src_70.len(), 3);
     }
@@ -405,7 +1640,7 @@ mod test {
 
         let mut rng = thread_rng();
         let sources: Vec<_> = graph.nodes.iter()
-            .map(|node| node.id)
+            .map(|node| node.id())
             .choose_multiple(&mut rng, 2);
         let tgt = rng.gen_range(0..graph.num_nodes);
 
@@ -416,13 +1651,201 @@ mod test {
         assert_eq!(min(dists2[tgt], dists3[tgt]), dists1[tgt]);
     }
 
+    #[test]
+    fn test_dial() {
+        for name in ["bbgrund", "stgcenter", "tokio"] {
+            let graph = Graph::parse_from_file(&format!("data/{}_undirected.fmi", name)).unwrap();
+            let max_edge_dist = graph.max_edge_dist();
+            assert_eq!(max_edge_dist, graph.edges.iter().map(|edge| edge.dist()).max().unwrap());
+
+            let sources = [0, graph.num_nodes / 2];
+            let mut preds = vec![usize::MAX; graph.num_nodes];
+            let dists = dial(&graph, &sources, Some(&mut preds), usize::MAX, max_edge_dist);
+            assert_eq!(dists, heap_dijkstra(&graph, &sources, None, usize::MAX));
+            for (node_id, &pred) in preds.iter().enumerate() {
+                if pred < usize::MAX {
+                    let edge = graph.get_outgoing_edges(pred).iter().find(|edge| edge.tgt() == node_id).unwrap();
+                    assert_eq!(dists[pred] + edge.dist(), dists[node_id]);
+                }
+            }
+
+            let max_dist = 500;
+            assert_eq!(dial(&graph, &sources, None, max_dist, max_edge_dist),
+                       heap_dijkstra(&graph, &sources, None, max_dist));
+        }
+    }
+
+    #[test]
+    fn test_dijkstra_par() {
+        let graph = Graph::parse_from_file("data/stgcenter_undirected.fmi").unwrap();
+        let sources: Vec<_> = (0..graph.num_nodes).step_by(97).collect();
+        assert_eq!(graph.run_dijkstra_par(&sources), graph.run_dijkstra(&sources));
+        assert_eq!(graph.run_dijkstra_par(&sources[..1]), graph.run_dijkstra(&sources[..1]));
+
+        graph.contraction_hierarchy();
+        assert_eq!(graph.run_dijkstra_par(&sources), graph.run_dijkstra(&sources));
+    }
+
+    #[test]
+    fn test_incremental_distances() {
+        let graph = Graph::parse_from_file("data/stgcenter_undirected.fmi").unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut incremental = IncrementalDistances::new();
+        let mut sources = Vec::new();
+        for num_new in [3, 1, 0, 10] {
+            let mut new_sources: Vec<_> = (0..num_new).map(|_| rng.gen_range(0..graph.num_nodes)).collect();
+            // Sources that have been added before are skipped
+            new_sources.extend(sources.first());
+            sources.extend(&new_sources);
+            sources.sort_unstable();
+            sources.dedup();
+
+            let dists = incremental.add_sources(&graph, &new_sources).to_vec();
+            assert_eq!(incremental.num_sources(), sources.len());
+            assert_eq!(dists, graph.run_dijkstra(&sources));
+        }
+    }
+
+    #[test]
+    fn test_dijkstra_within() {
+        let graph =
+            Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+
+        let dists = graph.run_dijkstra(&[0]);
+        let max_dist = dists.iter().filter(|&&dist| dist < usize::MAX).max().unwrap() / 2;
+        let dists_within = graph.run_dijkstra_within(&[0], max_dist);
+        for (dist, dist_within) in dists.into_iter().zip(dists_within) {
+            if dist <= max_dist {
+                assert_eq!(dist, dist_within);
+            } else {
+                assert_eq!(dist_within, usize::MAX);
+            }
+        }
+    }
+
+    #[test]
+    fn test_alt() {
+        let graph =
+            Graph::parse_from_file("data/stgcenter_undirected.fmi").unwrap();
+
+        let mut rng = thread_rng();
+        for _ in 0..20 {
+            let src = rng.gen_range(0..graph.num_nodes);
+            let dists = graph.run_dijkstra(&[src]);
+            let tgt = rng.gen_range(0..graph.num_nodes);
+            let expected = Some((tgt, dists[tgt])).filter(|&(_, dist)| dist < usize::MAX);
+            assert_eq!(graph.run_alt(&[src], &[tgt]), expected);
+
+            // The nearest of multiple targets is found
+            let tgts: Vec<_> = (0..3).map(|_| rng.gen_range(0..graph.num_nodes)).collect();
+            let nearest = graph.run_alt(&[src], &tgts);
+            if let Some((tgt, _)) = nearest {
+                assert!(tgts.contains(&tgt));
+            }
+            let expected = tgts.iter().map(|&tgt| dists[tgt]).filter(|&dist| dist < usize::MAX).min();
+            assert_eq!(nearest.map(|(_, dist)| dist), expected);
+        }
+        assert_eq!(graph.run_alt(&[0], &[]), None);
+    }
+
+    #[test]
+    fn test_dijkstra_tree() {
+        let graph =
+            Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+
+        let (dists, preds) = graph.run_dijkstra_tree(&[0, 100]);
+        assert_eq!(dists, graph.run_dijkstra(&[0, 100]));
+        assert_eq!(preds[0], usize::MAX);
+        assert_eq!(preds[100], usize::MAX);
+        for node in graph.nodes() {
+            let pred = preds[node.id()];
+            if pred < usize::MAX {
+                let edge_dist = graph.get_outgoing_edges(pred).iter()
+                    .filter(|e| e.tgt() == node.id())
+                    .map(|e| e.dist())
+                    .min()
+                    .unwrap();
+                assert_eq!(dists[pred] + edge_dist, dists[node.id()]);
+            }
+        }
+    }
+
     #[test]
     fn test_offsets() {
         let graph =
             Graph::parse_from_file("data/stgcenter_undirected.fmi").unwrap();
 
-        let mut offsets_clone = graph.offsets.clone();
+        let mut offsets_clone = graph.offsets.to_vec();
         offsets_clone.sort();
-        assert_eq!(offsets_clone, graph.offsets);
+        assert_eq!(offsets_clone, graph.offsets.to_vec());
+    }
+
+    #[test]
+    fn test_incoming_edges() {
+        let nodes = (0..4).map(|id| Node::new(id, id as u64, 48.0, 9.0)).collect();
+        let edges = [(0, 1, 5), (0, 2, 7), (1, 2, 3), (3, 2, 1), (2, 0, 4)].iter()
+            .map(|&(src, tgt, dist)| Edge::new(src, tgt, dist, EdgeAttrs::default()))
+            .collect();
+        let graph = Graph::from_nodes_and_edges(nodes, edges);
+        let incoming = |node_id| graph.get_incoming_edges(node_id)
+            .map(|edge| (edge.src(), edge.tgt(), edge.dist()))
+            .collect::<Vec<_>>();
+        assert_eq!(incoming(0), vec![(2, 0, 4)]);
+        assert_eq!(incoming(1), vec![(0, 1, 5)]);
+        assert_eq!(incoming(2), vec![(0, 2, 7), (1, 2, 3), (3, 2, 1)]);
+        assert!(incoming(3).is_empty());
+
+        // Every edge is an incoming edge of its target exactly once
+        let graph =
+            Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+        let mut all_incoming: Vec<_> = (0..graph.num_nodes)
+            .flat_map(|node_id| graph.get_incoming_edges(node_id).map(move |edge| {
+                assert_eq!(edge.tgt(), node_id);
+                (edge.src(), edge.tgt(), edge.dist())
+            }))
+            .collect();
+        let mut all_edges: Vec<_> = graph.iter_edges().map(|edge| (edge.src(), edge.tgt(), edge.dist())).collect();
+        all_incoming.sort_unstable();
+        all_edges.sort_unstable();
+        assert_eq!(all_incoming, all_edges);
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let graph =
+            Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+        let gb = graph.get_grid_bounds();
+        let half = GridBounds {
+            min_lat: gb.min_lat,
+            max_lat: gb.max_lat,
+            min_lon: gb.min_lon,
+            max_lon: (gb.min_lon + gb.max_lon) / 2.0,
+        };
+
+        let subgraph = graph.subgraph(&half);
+        let inside: Vec<_> = graph.iter_nodes().filter(|node| node.is_located_in(&half)).collect();
+        assert_eq!(subgraph.num_nodes, inside.len());
+        assert!(subgraph.num_nodes > 0 && subgraph.num_nodes < graph.num_nodes);
+        for (node, old) in subgraph.nodes().iter().zip(&inside) {
+            assert_eq!(node.osm_id, old.osm_id);
+            assert_eq!(subgraph.get_node_by_osm_id(old.osm_id).unwrap().id(), node.id());
+        }
+
+        // Exactly the edges between nodes within the bounds are kept
+        let num_inner_edges = graph.iter_edges()
+            .filter(|edge| graph.get_node(edge.src()).is_located_in(&half)
+                && graph.get_node(edge.tgt()).is_located_in(&half))
+            .count();
+        assert_eq!(subgraph.num_edges, num_inner_edges);
+        for edge in subgraph.edges() {
+            let (src, tgt) = (subgraph.get_node(edge.src()), subgraph.get_node(edge.tgt()));
+            let (old_src, old_tgt) = (graph.get_node_by_osm_id(src.osm_id).unwrap(),
+                                      graph.get_node_by_osm_id(tgt.osm_id).unwrap());
+            assert_eq!(graph.get_edge_attrs(old_src.id(), old_tgt.id()), Some(edge.attrs));
+        }
+
+        assert_eq!(graph.subgraph(&gb).num_edges, graph.num_edges);
+        let outside = GridBounds { min_lat: 0.0, max_lat: 1.0, min_lon: 0.0, max_lon: 1.0 };
+        assert_eq!(graph.subgraph(&outside).num_nodes, 0);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,538 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{prelude::*, BufReader};
+use std::path::{Path, PathBuf};
+
+use geo::{HaversineDistance, Point};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::graph::{Edge, EdgeAttrs, Graph, LineTokens, Node, ParseError, MAX_EDGE_DIST, MAX_GRAPH_ELEMENTS};
+
+/// Reader of graph files in one format. Graph files are assigned to the reader of their format by
+/// their file extension.
+pub trait GraphReader: Sync {
+    /// Get the name of the format
+    fn name(&self) -> &'static str;
+
+    /// Get the file extensions of graph files in this format, including the leading dot
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Get the paths of the files besides the graph file at `graph_path` that the graph is read
+    /// from, such that binary graph files are rewritten whenever any of them changes
+    fn companion_paths(&self, _graph_path: &Path) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Read the graph from the graph file at `graph_path`
+    fn read(&self, graph_path: &str) -> Result<Graph, ParseError>;
+}
+
+/// Readers of all supported graph formats
+static READERS: &[&dyn GraphReader] = &[
+    #[cfg(feature = "pbf")]
+    &PbfReader,
+    &FmiReader,
+    &GraphmlReader,
+    &DimacsReader,
+];
+
+/// Get the readers of all supported graph formats
+pub fn readers() -> &'static [&'static dyn GraphReader] {
+    READERS
+}
+
+/// Find the reader of the graph file `file_name` by its extension. Returns the name of the graph,
+/// i.e. the file name without extension, and the reader, or `None` if the file is no graph file.
+pub fn find_reader(file_name: &str) -> Option<(&str, &'static dyn GraphReader)> {
+    READERS.iter().find_map(|&reader| {
+        reader.extensions().iter()
+            .find_map(|extension| file_name.strip_suffix(extension))
+            .filter(|graph_name| !graph_name.is_empty())
+            .map(|graph_name| (graph_name, reader))
+    })
+}
+
+/// Reader of graph files in the FMI text format
+pub struct FmiReader;
+
+impl GraphReader for FmiReader {
+    fn name(&self) -> &'static str {
+        "FMI"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".fmi"]
+    }
+
+    fn read(&self, graph_path: &str) -> Result<Graph, ParseError> {
+        Graph::parse_from_file(graph_path)
+    }
+}
+
+/// Reader of OSM PBF extracts, see `Graph::parse_from_pbf`
+#[cfg(feature = "pbf")]
+pub struct PbfReader;
+
+#[cfg(feature = "pbf")]
+impl GraphReader for PbfReader {
+    fn name(&self) -> &'static str {
+        "OSM PBF"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".osm.pbf"]
+    }
+
+    fn read(&self, graph_path: &str) -> Result<Graph, ParseError> {
+        Graph::parse_from_pbf(graph_path)
+    }
+}
+
+/// Factor of the coordinates in DIMACS coordinate files, which are given in millionths of degrees
+const DIMACS_COORDINATE_FACTOR: f64 = 1e6;
+
+/// Reader of road networks in the format of the 9th DIMACS implementation challenge. The arcs of
+/// the graph are read from a `.gr` file, and the coordinates of its nodes from the `.co` file with
+/// the same name. Nodes keep their DIMACS ids as OSM ids, arcs become directed edges weighted by
+/// their arc weights, and coordinates are given as longitude and latitude in millionths of
+/// degrees.
+pub struct DimacsReader;
+
+impl DimacsReader {
+    /// Get the path of the coordinate file that belongs to the graph file at `graph_path`
+    fn coordinates_path(graph_path: &Path) -> PathBuf {
+        graph_path.with_extension("co")
+    }
+
+    /// Read the lines of the DIMACS file at `path` that are no comments, starting with the
+    /// problem line, and pass the tokens of every line after its type to `f`
+    fn read_lines<F>(path: &Path, mut f: F) -> Result<(), ParseError>
+        where F: FnMut(&str, &mut LineTokens) -> Result<(), ParseError> {
+        let mut num_lines = 0;
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            num_lines += 1;
+            let mut tokens = LineTokens::new(&line, num_lines);
+            match tokens.next_optional() {
+                None | Some("c") => (),
+                Some(line_type) => f(line_type, &mut tokens)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the next token as the `expected` DIMACS id of one of `num_nodes` nodes, which start
+    /// at `1`, and get the id of the node
+    fn parse_node_id(tokens: &mut LineTokens, expected: &'static str, num_nodes: usize) -> Result<usize, ParseError> {
+        match tokens.parse_node_id(expected, num_nodes + 1)?.index() {
+            0 => Err(ParseError::UnknownNode {
+                line: tokens.line_no,
+                column: tokens.last.0,
+                token: tokens.last.1.to_string(),
+            }),
+            node_id => Ok(node_id - 1)
+        }
+    }
+}
+
+impl GraphReader for DimacsReader {
+    fn name(&self) -> &'static str {
+        "DIMACS"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".gr"]
+    }
+
+    fn companion_paths(&self, graph_path: &Path) -> Vec<PathBuf> {
+        vec![Self::coordinates_path(graph_path)]
+    }
+
+    fn read(&self, graph_path: &str) -> Result<Graph, ParseError> {
+        log::debug!("Start parsing DIMACS graph: {}", graph_path);
+
+        let mut num_nodes = 0;
+        let mut edges = Vec::new();
+        Self::read_lines(Path::new(graph_path), |line_type, tokens| {
+            match line_type {
+                "p" => {
+                    tokens.next("problem type")?;
+                    num_nodes = tokens.parse("number of nodes")?;
+                    let num_edges: usize = tokens.parse("number of arcs")?;
+                    if num_nodes > MAX_GRAPH_ELEMENTS || num_edges > MAX_GRAPH_ELEMENTS {
+                        return Err(ParseError::TooLarge);
+                    }
+                    edges.reserve(num_edges);
+                }
+                "a" => {
+                    let src = Self::parse_node_id(tokens, "arc source", num_nodes)?;
+                    let tgt = Self::parse_node_id(tokens, "arc target", num_nodes)?;
+                    // Edge distances are stored with 32 bits
+                    let dist: u32 = tokens.parse("arc weight")?;
+                    edges.push(Edge::new(src, tgt, dist as usize, EdgeAttrs::default()));
+                }
+                _ => return Err(ParseError::InvalidToken {
+                    line: tokens.line_no,
+                    column: tokens.last.0,
+                    token: tokens.last.1.to_string(),
+                    expected: "line type",
+                })
+            }
+            Ok(())
+        })?;
+        if num_nodes == 0 {
+            return Err(ParseError::EmptyNodes);
+        }
+        if edges.len() > MAX_GRAPH_ELEMENTS {
+            return Err(ParseError::TooLarge);
+        }
+
+        let mut coords = vec![None; num_nodes];
+        Self::read_lines(&Self::coordinates_path(Path::new(graph_path)), |line_type, tokens| {
+            match line_type {
+                "p" => (),
+                "v" => {
+                    let node_id = Self::parse_node_id(tokens, "node id", num_nodes)?;
+                    let lon: f64 = tokens.parse("node longitude")?;
+                    let lat: f64 = tokens.parse("node latitude")?;
+                    coords[node_id] = Some((lat / DIMACS_COORDINATE_FACTOR, lon / DIMACS_COORDINATE_FACTOR));
+                }
+                _ => return Err(ParseError::InvalidToken {
+                    line: tokens.line_no,
+                    column: tokens.last.0,
+                    token: tokens.last.1.to_string(),
+                    expected: "line type",
+                })
+            }
+            Ok(())
+        })?;
+
+        let nodes = coords.into_iter().enumerate()
+            .map(|(id, coords)| match coords {
+                Some((lat, lon)) => Ok(Node::new(id, id as u64 + 1, lat, lon)),
+                None => Err(ParseError::MissingCoordinates { node: (id + 1).to_string() })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        log::debug!("Parsed {} nodes and {} edges", nodes.len(), edges.len());
+
+        Ok(Graph::from_nodes_and_edges(nodes, edges))
+    }
+}
+
+/// Names of the GraphML attributes of nodes that hold their latitude, longitude and OSM id
+const GRAPHML_LAT_KEYS: [&str; 3] = ["lat", "latitude", "y"];
+const GRAPHML_LON_KEYS: [&str; 4] = ["lon", "lng", "longitude", "x"];
+const GRAPHML_OSM_ID_KEYS: [&str; 2] = ["osmid", "osm_id"];
+/// Names of the GraphML attributes of edges that hold their distance, road class and speed limit
+const GRAPHML_DIST_KEYS: [&str; 4] = ["length", "dist", "distance", "weight"];
+const GRAPHML_EDGE_TYPE_KEYS: [&str; 2] = ["edge_type", "road_class"];
+const GRAPHML_MAXSPEED_KEYS: [&str; 1] = ["maxspeed"];
+
+/// Reader of graphs in the GraphML format, e.g. as exported by OSMnx. Nodes need a latitude and a
+/// longitude attribute, and keep their OSM id attribute or their numeric GraphML id as OSM id.
+/// Edges are weighted by their length attribute rounded to an integer, or by the great-circle
+/// distance in meters between their nodes if they have none. Undirected edges become edges in
+/// both directions.
+pub struct GraphmlReader;
+
+/// Node or edge of a GraphML file with its attributes by name, located by the byte position of
+/// the end of its start tag
+struct GraphmlElement {
+    position: usize,
+    attrs: HashMap<String, String>,
+    data: HashMap<String, String>,
+}
+
+impl GraphmlElement {
+    fn new(position: usize, start: &BytesStart) -> Result<Self, quick_xml::Error> {
+        let mut attrs = HashMap::new();
+        for attr in start.attributes() {
+            let attr = attr?;
+            let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+            attrs.insert(key, attr.unescape_value()?.into_owned());
+        }
+        Ok(Self { position, attrs, data: HashMap::new() })
+    }
+
+    /// Get the value of the first of the attributes `keys` that this element has
+    fn data(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter().find_map(|key| self.data.get(*key)).map(String::as_str)
+    }
+}
+
+/// Create the parse error that the value `token` of the element at byte `position` of the
+/// GraphML file at `graph_path` is no valid `expected` value
+fn invalid_graphml_value(graph_path: &str, position: usize, token: &str, expected: &'static str) -> ParseError {
+    let (line, column) = locate(graph_path, position);
+    ParseError::InvalidToken { line, column, token: token.to_string(), expected }
+}
+
+/// Get the line and the column of the byte `position` of the file at `path`
+fn locate(path: &str, position: usize) -> (usize, usize) {
+    let contents = fs::read(path).unwrap_or_default();
+    let before = String::from_utf8_lossy(&contents[..position.min(contents.len())]).into_owned();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+impl GraphReader for GraphmlReader {
+    fn name(&self) -> &'static str {
+        "GraphML"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".graphml"]
+    }
+
+    fn read(&self, graph_path: &str) -> Result<Graph, ParseError> {
+        log::debug!("Start parsing GraphML graph: {}", graph_path);
+
+        let mut reader = Reader::from_file(graph_path).map_err(|source| {
+            ParseError::Xml { line: 1, column: 1, source }
+        })?;
+        reader.trim_text(true);
+
+        // Names of the attributes by key id, and the elements of the graph in file order
+        let mut key_names = HashMap::new();
+        let mut is_directed = false;
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut element: Option<(bool, GraphmlElement)> = None;
+        let mut data_key: Option<String> = None;
+        let mut buf = Vec::new();
+        loop {
+            // Elements and errors are located at the end of the event
+            let event = reader.read_event_into(&mut buf);
+            let position = reader.buffer_position();
+            let xml_error = |source| {
+                let (line, column) = locate(graph_path, position);
+                ParseError::Xml { line, column, source }
+            };
+            let event = event.map_err(xml_error)?;
+            let (start, is_empty) = match &event {
+                Event::Start(start) => (Some(start), false),
+                Event::Empty(start) => (Some(start), true),
+                _ => (None, false)
+            };
+            if let Some(start) = start {
+                match start.name().as_ref() {
+                    b"key" => {
+                        let key = GraphmlElement::new(position, start).map_err(xml_error)?;
+                        if let (Some(id), Some(name)) = (key.attrs.get("id"), key.attrs.get("attr.name")) {
+                            key_names.insert(id.clone(), name.to_lowercase());
+                        }
+                    }
+                    b"graph" => {
+                        let graph = GraphmlElement::new(position, start).map_err(xml_error)?;
+                        is_directed = graph.attrs.get("edgedefault").map(String::as_str) == Some("directed");
+                    }
+                    name @ (b"node" | b"edge") => {
+                        let is_node = name == b"node";
+                        let new = GraphmlElement::new(position, start).map_err(xml_error)?;
+                        match (is_empty, is_node) {
+                            (true, true) => nodes.push(new),
+                            (true, false) => edges.push(new),
+                            (false, _) => element = Some((is_node, new))
+                        }
+                    }
+                    b"data" if !is_empty => {
+                        let data = GraphmlElement::new(position, start).map_err(xml_error)?;
+                        data_key = data.attrs.get("key").and_then(|key| key_names.get(key)).cloned();
+                    }
+                    _ => ()
+                }
+            }
+            match event {
+                Event::Text(text) => {
+                    if let (Some((_, element)), Some(key)) = (element.as_mut(), data_key.as_ref()) {
+                        let value = text.unescape().map_err(xml_error)?;
+                        element.data.insert(key.clone(), value.trim().to_string());
+                    }
+                }
+                Event::End(end) => match end.name().as_ref() {
+                    b"data" => data_key = None,
+                    b"node" | b"edge" => match element.take() {
+                        Some((true, node)) => nodes.push(node),
+                        Some((false, edge)) => edges.push(edge),
+                        None => ()
+                    },
+                    _ => ()
+                },
+                Event::Eof => break,
+                _ => ()
+            }
+            buf.clear();
+        }
+        if nodes.is_empty() {
+            return Err(ParseError::EmptyNodes);
+        }
+        if nodes.len() > MAX_GRAPH_ELEMENTS || edges.len() > MAX_GRAPH_ELEMENTS {
+            return Err(ParseError::TooLarge);
+        }
+
+        let mut node_ids = HashMap::with_capacity(nodes.len());
+        let mut graph_nodes = Vec::with_capacity(nodes.len());
+        for (id, node) in nodes.iter().enumerate() {
+            let name = node.attrs.get("id").map(String::as_str).unwrap_or_default();
+            let coordinate = |keys: &[&str], expected| match node.data(keys) {
+                Some(value) => value.parse::<f64>()
+                    .map_err(|_| invalid_graphml_value(graph_path, node.position, value, expected)),
+                None => Err(ParseError::MissingCoordinates { node: name.to_string() })
+            };
+            let lat = coordinate(&GRAPHML_LAT_KEYS, "node latitude")?;
+            let lon = coordinate(&GRAPHML_LON_KEYS, "node longitude")?;
+            let osm_id = match node.data(&GRAPHML_OSM_ID_KEYS) {
+                Some(osm_id) => osm_id.parse()
+                    .map_err(|_| invalid_graphml_value(graph_path, node.position, osm_id, "node OSM id"))?,
+                None => name.parse().unwrap_or(id as u64)
+            };
+            node_ids.insert(name.to_string(), id);
+            graph_nodes.push(Node::new(id, osm_id, lat, lon));
+        }
+
+        let mut graph_edges = Vec::with_capacity(edges.len());
+        for edge in &edges {
+            let node_id = |attr: &str| {
+                let name = edge.attrs.get(attr).map(String::as_str).unwrap_or_default();
+                node_ids.get(name).copied().ok_or_else(|| {
+                    let (line, column) = locate(graph_path, edge.position);
+                    ParseError::UnknownNode { line, column, token: name.to_string() }
+                })
+            };
+            let (src, tgt) = (node_id("source")?, node_id("target")?);
+            let dist = match edge.data(&GRAPHML_DIST_KEYS) {
+                Some(dist) => dist.parse::<f64>().ok()
+                    .filter(|dist| *dist >= 0.0 && dist.round() <= MAX_EDGE_DIST as f64)
+                    .ok_or_else(|| invalid_graphml_value(graph_path, edge.position, dist, "edge length"))?
+                    .round() as usize,
+                None => {
+                    let (a, b) = (&graph_nodes[src], &graph_nodes[tgt]);
+                    let dist = Point::new(a.lon, a.lat).haversine_distance(&Point::new(b.lon, b.lat));
+                    (dist.round() as usize).min(MAX_EDGE_DIST)
+                }
+            };
+            let number = |keys: &[&str], expected| match edge.data(keys) {
+                Some(value) => value.parse::<u32>()
+                    .map_err(|_| invalid_graphml_value(graph_path, edge.position, value, expected)),
+                None => Ok(0)
+            };
+            let attrs = EdgeAttrs {
+                edge_type: number(&GRAPHML_EDGE_TYPE_KEYS, "edge road class")?,
+                maxspeed: number(&GRAPHML_MAXSPEED_KEYS, "edge speed limit")?,
+            };
+            let is_edge_directed = match edge.attrs.get("directed").map(String::as_str) {
+                Some("true") => true,
+                Some("false") => false,
+                _ => is_directed
+            };
+            graph_edges.push(Edge::new(src, tgt, dist, attrs));
+            if !is_edge_directed && src != tgt {
+                graph_edges.push(Edge::new(tgt, src, dist, attrs));
+            }
+        }
+        if graph_edges.len() > MAX_GRAPH_ELEMENTS {
+            return Err(ParseError::TooLarge);
+        }
+        log::debug!("Parsed {} nodes and {} edges", graph_nodes.len(), graph_edges.len());
+
+        Ok(Graph::from_nodes_and_edges(graph_nodes, graph_edges))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use crate::graph::formats::find_reader;
+    use crate::graph::{Graph, GraphView, ParseError};
+
+    /// Write the graph files `files` given as pairs of file extension and contents to the
+    /// temporary directory, and get the path of the first one
+    fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let base = std::env::temp_dir().join(format!("osmff_formats_{}_{}", std::process::id(), name));
+        for (extension, contents) in files {
+            fs::write(base.with_extension(extension), contents).unwrap();
+        }
+        base.with_extension(files[0].0)
+    }
+
+    /// Read the graph file at `path` with the reader of its extension
+    fn read(path: &Path) -> Result<Graph, ParseError> {
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        find_reader(file_name).unwrap().1.read(path.to_str().unwrap())
+    }
+
+    #[test]
+    fn test_find_reader() {
+        let (graph_name, reader) = find_reader("stuttgart.fmi").unwrap();
+        assert_eq!((graph_name, reader.name()), ("stuttgart", "FMI"));
+        assert_eq!(find_reader("USA-road-d.NY.gr").unwrap().1.name(), "DIMACS");
+        assert_eq!(find_reader("city.graphml").unwrap().1.name(), "GraphML");
+        assert!(find_reader("USA-road-d.NY.co").is_none());
+        assert!(find_reader(".fmi").is_none());
+        assert!(find_reader("graph.osmfg").is_none());
+    }
+
+    #[test]
+    fn test_read_dimacs() {
+        let path = write_files("dimacs", &[
+            ("gr", "c Three nodes on a line\np sp 3 4\na 1 2 10\na 2 1 10\nc One-way\na 2 3 7\n\na 3 3 1\n"),
+            ("co", "c Coordinates\np aux sp co 3\nv 1 9100000 48700000\nv 2 9101000 48700000\nv 3 9102000 48701000\n"),
+        ]);
+        let graph = read(&path).unwrap();
+        assert_eq!(graph.num_nodes(), 3);
+        assert_eq!(graph.num_edges(), 4);
+        assert_eq!(graph.get_node(2).osm_id, 3);
+        assert!((graph.get_node(2).lat - 48.701).abs() < 1e-9 && (graph.get_node(2).lon - 9.102).abs() < 1e-9);
+        assert_eq!(graph.get_outgoing_edges(1).iter().map(|edge| (edge.tgt(), edge.dist())).collect::<Vec<_>>(),
+                   vec![(0, 10), (2, 7)]);
+
+        let path = write_files("dimacs_unknown", &[("gr", "p sp 2 1\na 1 3 5\n"), ("co", "v 1 0 0\nv 2 0 0\n")]);
+        assert!(matches!(read(&path), Err(ParseError::UnknownNode { line: 2, column: 5, .. })));
+        let path = write_files("dimacs_missing", &[("gr", "p sp 2 1\na 1 2 5\n"), ("co", "v 1 0 0\n")]);
+        assert!(matches!(read(&path), Err(ParseError::MissingCoordinates { node }) if node == "2"));
+        let path = write_files("dimacs_line_type", &[("gr", "p sp 2 1\ne 1 2 5\n"), ("co", "")]);
+        assert!(matches!(read(&path), Err(ParseError::InvalidToken { line: 2, column: 1, .. })));
+    }
+
+    #[test]
+    fn test_read_graphml() {
+        let path = write_files("graphml", &[("graphml", r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="d0" for="node" attr.name="y" attr.type="double"/>
+  <key id="d1" for="node" attr.name="x" attr.type="double"/>
+  <key id="d2" for="node" attr.name="osmid" attr.type="long"/>
+  <key id="d3" for="edge" attr.name="length" attr.type="double"/>
+  <graph id="G" edgedefault="undirected">
+    <node id="a"><data key="d0">48.7</data><data key="d1">9.1</data><data key="d2">1001</data></node>
+    <node id="b"><data key="d0">48.7</data><data key="d1">9.101</data><data key="d2">1002</data></node>
+    <node id="c"><data key="d0">48.701</data><data key="d1">9.101</data><data key="d2">1003</data></node>
+    <edge source="a" target="b"><data key="d3">73.4</data></edge>
+    <edge source="b" target="c" directed="true"/>
+  </graph>
+</graphml>
+"#)]);
+        let graph = read(&path).unwrap();
+        assert_eq!(graph.num_nodes(), 3);
+        assert_eq!(graph.get_node_by_osm_id(1003).unwrap().id(), 2);
+        // Both directions of the undirected edge, and one direction of the directed edge weighted
+        // by the distance between its nodes
+        assert_eq!(graph.num_edges(), 3);
+        assert_eq!(graph.get_outgoing_edges(1).iter().map(|edge| (edge.tgt(), edge.dist())).collect::<Vec<_>>(),
+                   vec![(0, 73), (2, 111)]);
+        assert!(graph.get_outgoing_edges(2).is_empty());
+
+        let path = write_files("graphml_unknown", &[("graphml",
+            "<graphml>\n<graph edgedefault=\"directed\">\n<node id=\"a\"/>\n  <edge source=\"a\" target=\"z\"/>\n</graph>\n</graphml>\n")]);
+        assert!(matches!(read(&path), Err(ParseError::MissingCoordinates { node }) if node == "a"));
+        let path = write_files("graphml_unknown_coords", &[("graphml", concat!(
+            "<graphml>\n<key id=\"lat\" attr.name=\"lat\"/><key id=\"lon\" attr.name=\"lon\"/>\n<graph>\n",
+            "<node id=\"a\"><data key=\"lat\">48.7</data><data key=\"lon\">9.1</data></node>\n",
+            "  <edge source=\"a\" target=\"z\"/>\n</graph>\n</graphml>\n"))]);
+        assert!(matches!(read(&path), Err(ParseError::UnknownNode { line: 5, column: 32, token }) if token == "z"));
+        let path = write_files("graphml_malformed", &[("graphml", "<graphml>\n<graph>\n</graphml>\n")]);
+        assert!(matches!(read(&path), Err(ParseError::Xml { line: 3, .. })));
+    }
+}
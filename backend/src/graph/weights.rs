@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{prelude::*, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use geo::{HaversineDistance, Point};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::graph::{Graph, GraphView, ParseError};
+
+/// Suffixes of the sidecar files of node weights, which are looked up next to the graph files
+/// in this order, e.g. `stuttgart.weights.csv` for the graph file `stuttgart.fmi`
+pub const SIDECAR_SUFFIXES: [&str; 2] = [".weights.csv", ".weights.geojson"];
+/// Maximum distance in meters between the coordinates of a record and the nearest node, beyond
+/// which the record is not matched to any node
+const MAX_MATCH_DIST_M: f64 = 500.0;
+
+/// Column names of the external ids, latitudes, longitudes, weights and classes of the records of
+/// sidecar files, compared in lowercase
+const ID_NAMES: [&str; 3] = ["osm_id", "osmid", "id"];
+const LAT_NAMES: [&str; 3] = ["lat", "latitude", "y"];
+const LON_NAMES: [&str; 4] = ["lon", "lng", "longitude", "x"];
+const WEIGHT_NAMES: [&str; 3] = ["weight", "population", "importance"];
+const CLASS_NAMES: [&str; 3] = ["class", "landuse", "land_use"];
+
+/// Importance weights and land-use classes of the nodes of a graph, read from a sidecar file of
+/// the graph, e.g. the population around each node. Every record of the sidecar file is matched
+/// to a node by its external id, or to the nearest node by its coordinates. The weights of all
+/// records matched to the same node are summed up, and the node gets the class of the last of
+/// them. Nodes without records have the weight `0` and no class.
+#[derive(Debug, Clone, Default)]
+pub struct NodeWeights {
+    weights: Vec<f64>,
+    /// Index of the class of every node into the class names
+    classes: Vec<Option<u16>>,
+    class_names: Vec<String>,
+    num_records: usize,
+    /// Number of records that were matched to no node
+    num_unmatched: usize,
+}
+
+/// Summary of the node weights of a graph
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeWeightsSummary {
+    pub num_records: usize,
+    pub num_unmatched: usize,
+    pub num_weighted_nodes: usize,
+    pub total_weight: f64,
+    pub max_weight: f64,
+    pub classes: Vec<String>,
+}
+
+/// Record of a sidecar file that is located by an external id or coordinates
+struct Record<'a> {
+    osm_id: Option<u64>,
+    coords: Option<(f64, f64)>,
+    weight: f64,
+    class: Option<&'a str>,
+}
+
+impl NodeWeights {
+    /// Create empty node weights of the `num_nodes` nodes of a graph
+    fn new(num_nodes: usize) -> Self {
+        Self {
+            weights: vec![0.0; num_nodes],
+            classes: vec![None; num_nodes],
+            ..Self::default()
+        }
+    }
+
+    /// Get the weight of the node with id `node_id`, which is `0` for nodes without records and
+    /// nodes that were added to the graph afterwards
+    pub fn weight(&self, node_id: usize) -> f64 {
+        self.weights.get(node_id).copied().unwrap_or(0.0)
+    }
+
+    /// Get the class of the node with id `node_id`, if it has any
+    pub fn class(&self, node_id: usize) -> Option<&str> {
+        self.classes.get(node_id).copied().flatten()
+            .map(|class| self.class_names[class as usize].as_str())
+    }
+
+    /// Get the total weight of the nodes with ids `node_ids`
+    pub fn total_weight<'a>(&self, node_ids: impl IntoIterator<Item = &'a usize>) -> f64 {
+        node_ids.into_iter().map(|&node_id| self.weight(node_id)).sum()
+    }
+
+    /// Get the greatest weight of any node
+    pub fn max_weight(&self) -> f64 {
+        self.weights.iter().copied().fold(0.0, f64::max)
+    }
+
+    /// Summarize these node weights
+    pub fn summary(&self) -> NodeWeightsSummary {
+        NodeWeightsSummary {
+            num_records: self.num_records,
+            num_unmatched: self.num_unmatched,
+            num_weighted_nodes: self.weights.iter().filter(|&&weight| weight != 0.0).count(),
+            total_weight: self.weights.iter().sum(),
+            max_weight: self.max_weight(),
+            classes: self.class_names.clone(),
+        }
+    }
+
+    /// Match the record `record` to a node of `graph` and add its weight and class
+    fn add_record(&mut self, graph: &Graph, record: Record) {
+        self.num_records += 1;
+        let node_id = match (record.osm_id, record.coords) {
+            (Some(osm_id), _) => graph.get_node_by_osm_id(osm_id).map(|node| node.id()),
+            (None, Some((lat, lon))) if graph.num_nodes > 0 => {
+                let node = graph.nearest_node(lat, lon);
+                let dist = Point::new(lon, lat).haversine_distance(&Point::new(node.lon, node.lat));
+                Some(node.id()).filter(|_| dist <= MAX_MATCH_DIST_M)
+            }
+            _ => None
+        };
+        let node_id = match node_id {
+            Some(node_id) => node_id,
+            None => {
+                self.num_unmatched += 1;
+                return;
+            }
+        };
+
+        self.weights[node_id] += record.weight;
+        if let Some(class) = record.class {
+            let index = match self.class_names.iter().position(|name| name == class) {
+                Some(index) => index,
+                None => {
+                    self.class_names.push(class.to_string());
+                    self.class_names.len() - 1
+                }
+            };
+            self.classes[node_id] = Some(index as u16);
+        }
+    }
+}
+
+/// Find the sidecar file of the node weights of the graph `graph_name` in the directory
+/// `graphs_path`, if there is any
+pub fn find_sidecar(graphs_path: &Path, graph_name: &str) -> Option<PathBuf> {
+    SIDECAR_SUFFIXES.iter()
+        .map(|suffix| graphs_path.join(format!("{}{}", graph_name, suffix)))
+        .find(|path| path.is_file())
+}
+
+/// Read the node weights of `graph` from the sidecar file at `path`, which is either a CSV file
+/// with a header line or a GeoJSON file with a feature collection, see `SIDECAR_SUFFIXES`.
+/// The records of CSV files are located by an `osm_id` column or by `lat` and `lon` columns, and
+/// the features of GeoJSON files by an `osm_id` property or by their point geometry. Records have
+/// the weight in their `weight` or `population` column or property, or the weight `1` if there
+/// is none, and optionally a land-use class in their `class` or `landuse` column or property.
+pub fn read_sidecar(graph: &Graph, path: &Path) -> Result<NodeWeights, ParseError> {
+    let is_geojson = path.extension().and_then(|extension| extension.to_str()) == Some("geojson");
+    let weights = if is_geojson { read_geojson(graph, path)? } else { read_csv(graph, path)? };
+    if weights.num_unmatched > 0 {
+        log::warn!("{} of {} node weight records in {} match no node", weights.num_unmatched,
+                   weights.num_records, path.display());
+    }
+    Ok(weights)
+}
+
+/// Find the index of the column with any of the names `names` in the header `header`
+fn find_column(header: &[String], names: &[&str]) -> Option<usize> {
+    header.iter().position(|column| names.contains(&column.as_str()))
+}
+
+/// Parse the field `field` of a CSV file in line `line` with its column as the `expected` value,
+/// if it is not empty
+fn parse_field<T: FromStr>(field: Option<(usize, &str)>, line: usize, expected: &'static str)
+    -> Result<Option<T>, ParseError> {
+    field.map(|(column, token)| token.parse().map_err(|_| ParseError::InvalidToken {
+        line,
+        column,
+        token: token.to_string(),
+        expected,
+    })).transpose()
+}
+
+/// Read the node weights of `graph` from the CSV file at `path`, whose fields are separated by
+/// commas and are not quoted
+fn read_csv(graph: &Graph, path: &Path) -> Result<NodeWeights, ParseError> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let header: Vec<_> = match lines.next() {
+        Some(line) => line?.split(',').map(|column| column.trim().to_lowercase()).collect(),
+        None => Vec::new()
+    };
+    let id_column = find_column(&header, &ID_NAMES);
+    let lat_column = find_column(&header, &LAT_NAMES);
+    let lon_column = find_column(&header, &LON_NAMES);
+    if id_column.is_none() && (lat_column.is_none() || lon_column.is_none()) {
+        return Err(ParseError::MissingColumn { expected: "external id or coordinates" });
+    }
+    let weight_column = find_column(&header, &WEIGHT_NAMES);
+    let class_column = find_column(&header, &CLASS_NAMES);
+
+    let mut weights = NodeWeights::new(graph.num_nodes);
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Fields with their columns in characters
+        let mut fields = Vec::with_capacity(header.len());
+        let mut column = 1;
+        for field in line.split(',') {
+            let leading = field.chars().take_while(|c| c.is_whitespace()).count();
+            fields.push((column + leading, field.trim()));
+            column += field.chars().count() + 1;
+        }
+        let line_no = i + 2;
+        let field = |column: Option<usize>| column
+            .and_then(|column| fields.get(column))
+            .copied()
+            .filter(|(_, token)| !token.is_empty());
+        let parse = |column: Option<usize>, expected: &'static str| parse_field::<f64>(field(column), line_no, expected);
+
+        let osm_id = parse_field(field(id_column), line_no, "external id")?;
+        let coords = match (parse(lat_column, "latitude")?, parse(lon_column, "longitude")?) {
+            (Some(lat), Some(lon)) => Some((lat, lon)),
+            _ => None
+        };
+        if osm_id.is_none() && coords.is_none() {
+            return Err(ParseError::InvalidToken {
+                line: line_no,
+                column: fields[0].0,
+                token: line.trim().to_string(),
+                expected: "external id or coordinates",
+            });
+        }
+        weights.add_record(graph, Record {
+            osm_id,
+            coords,
+            weight: parse(weight_column, "weight")?.unwrap_or(1.0),
+            class: field(class_column).map(|(_, token)| token),
+        });
+    }
+    Ok(weights)
+}
+
+/// Read the node weights of `graph` from the GeoJSON file at `path`
+fn read_geojson(graph: &Graph, path: &Path) -> Result<NodeWeights, ParseError> {
+    let collection: Value = serde_json::from_str(&fs::read_to_string(path)?).map_err(ParseError::Json)?;
+    let features = collection["features"].as_array()
+        .ok_or(ParseError::InvalidFeature { feature: 0, expected: "feature collection" })?;
+
+    let mut weights = NodeWeights::new(graph.num_nodes);
+    for (i, feature) in features.iter().enumerate() {
+        let invalid = |expected: &'static str| ParseError::InvalidFeature { feature: i + 1, expected };
+        // Property names are compared in lowercase like the columns of CSV files
+        let properties: HashMap<_, _> = feature["properties"].as_object().into_iter()
+            .flatten()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(name, value)| (name.to_lowercase(), value))
+            .collect();
+        let property = |names: &[&str]| names.iter().find_map(|&name| properties.get(name).copied());
+
+        let osm_id = match property(&ID_NAMES) {
+            Some(id) => Some(id.as_u64()
+                .or_else(|| id.as_str().and_then(|id| id.parse().ok()))
+                .ok_or_else(|| invalid("external id"))?),
+            None => None
+        };
+        let geometry = &feature["geometry"];
+        let coords = match geometry["coordinates"].as_array() {
+            Some(position) if geometry["type"] == "Point" => match (position.get(1), position.first()) {
+                (Some(lat), Some(lon)) => Some((lat.as_f64().ok_or_else(|| invalid("latitude"))?,
+                                                lon.as_f64().ok_or_else(|| invalid("longitude"))?)),
+                _ => return Err(invalid("point"))
+            },
+            _ => None
+        };
+        if osm_id.is_none() && coords.is_none() {
+            return Err(invalid("external id or coordinates"));
+        }
+        let weight = match property(&WEIGHT_NAMES) {
+            Some(weight) => weight.as_f64().ok_or_else(|| invalid("weight"))?,
+            None => 1.0
+        };
+        let class = match property(&CLASS_NAMES) {
+            Some(class) => Some(class.as_str().ok_or_else(|| invalid("class"))?),
+            None => None
+        };
+        weights.add_record(graph, Record { osm_id, coords, weight, class });
+    }
+    Ok(weights)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::graph::weights::{find_sidecar, read_sidecar};
+    use crate::graph::{Edge, EdgeAttrs, Graph, Node, ParseError};
+
+    /// Graph of three nodes on a line about 74 meters apart
+    fn line_graph() -> Graph {
+        let nodes = (0..3).map(|id| Node::new(id, 1001 + id as u64, 48.7, 9.1 + id as f64 * 1e-3)).collect();
+        let edges = vec![Edge::new(0, 1, 74, EdgeAttrs::default()), Edge::new(1, 2, 74, EdgeAttrs::default())];
+        Graph::from_nodes_and_edges(nodes, edges)
+    }
+
+    /// Write the sidecar file `contents` of the graph `name` to the temporary directory
+    fn write_sidecar(name: &str, suffix: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("osmff_weights_{}_{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("{}{}", name, suffix)), contents).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_read_csv() {
+        let graph = line_graph();
+        let dir = write_sidecar("csv", ".weights.csv",
+            "OSM_ID, lat, lon, Population, landuse\n1001,,,120,residential\n,48.7001,9.1021,30,\n\
+             ,48.7,9.1019,5,industrial\n9999,,,7,\n,48.8,9.1,1,\n");
+        let path = find_sidecar(&dir, "csv").unwrap();
+        let weights = read_sidecar(&graph, &path).unwrap();
+        assert_eq!((0..3).map(|node_id| weights.weight(node_id)).collect::<Vec<_>>(), vec![120.0, 0.0, 35.0]);
+        assert_eq!(weights.weight(3), 0.0);
+        assert_eq!((weights.class(0), weights.class(1), weights.class(2)), (Some("residential"), None, Some("industrial")));
+        assert_eq!(weights.total_weight(&[0, 1, 2]), 155.0);
+        let summary = weights.summary();
+        assert_eq!((summary.num_records, summary.num_unmatched, summary.num_weighted_nodes), (5, 2, 2));
+        assert!(find_sidecar(&dir, "other").is_none());
+
+        let dir = write_sidecar("csv_invalid", ".weights.csv", "lat,lon,weight\n48.7,9.1,many\n");
+        let path = find_sidecar(&dir, "csv_invalid").unwrap();
+        assert!(matches!(read_sidecar(&graph, &path),
+            Err(ParseError::InvalidToken { line: 2, column: 10, token, .. }) if token == "many"));
+        let dir = write_sidecar("csv_columns", ".weights.csv", "lat,weight\n48.7,1\n");
+        let path = find_sidecar(&dir, "csv_columns").unwrap();
+        assert!(matches!(read_sidecar(&graph, &path), Err(ParseError::MissingColumn { .. })));
+    }
+
+    #[test]
+    fn test_read_geojson() {
+        let graph = line_graph();
+        let dir = write_sidecar("geojson", ".weights.geojson", r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": null, "properties": {"osm_id": "1002", "weight": 2.5, "class": "forest"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [9.1, 48.7]}, "properties": {}}
+        ]}"#);
+        let path = find_sidecar(&dir, "geojson").unwrap();
+        let weights = read_sidecar(&graph, &path).unwrap();
+        assert_eq!((weights.weight(0), weights.weight(1), weights.weight(2)), (1.0, 2.5, 0.0));
+        assert_eq!(weights.class(1), Some("forest"));
+        assert_eq!(weights.max_weight(), 2.5);
+
+        let dir = write_sidecar("geojson_invalid", ".weights.geojson",
+            r#"{"features": [{"properties": {"osm_id": 1001, "weight": "heavy"}}]}"#);
+        let path = find_sidecar(&dir, "geojson_invalid").unwrap();
+        assert!(matches!(read_sidecar(&graph, &path),
+            Err(ParseError::InvalidFeature { feature: 1, expected: "weight" })));
+    }
+}
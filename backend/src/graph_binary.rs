@@ -0,0 +1,265 @@
+use std::fmt::{Debug, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::Arc;
+
+use memmap2::Mmap;
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+use crate::graph::{Edge, ExtId, Graph, GraphMetadata, Node};
+
+/// File extension of binary graph files
+pub(crate) const FILE_EXTENSION: &str = "osmfg";
+
+/// Magic bytes at the start of every binary graph file
+const MAGIC: [u8; 8] = *b"OSMFGRPH";
+
+/// Version of the binary graph format. Files of other versions are ignored.
+const FORMAT_VERSION: u64 = 4;
+
+/// Marker to detect binary graph files written on platforms with another byte order
+const BYTE_ORDER_MARKER: u64 = 0x0102_0304_0506_0708;
+
+/// Number of `u64` fields in the header of binary graph files
+const NUM_HEADER_FIELDS: usize = 12;
+
+/// Contiguous storage of graph elements that is either owned or backed by a read-only memory
+/// mapping of a binary graph file, which is shared by all processes mapping the same file
+pub(crate) enum GraphStorage<T> {
+    Owned(Vec<T>),
+    Mapped {
+        map: Arc<Mmap>,
+        offset: usize,
+        len: usize,
+        _marker: PhantomData<T>,
+    },
+}
+
+impl<T> Deref for GraphStorage<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Owned(items) => items,
+            // The section was checked to be in bounds, aligned and to consist of elements of `T`
+            // when mapping the file, and mapped files are never modified in place
+            Self::Mapped { map, offset, len, .. } => unsafe {
+                slice::from_raw_parts(map.as_ptr().add(*offset) as *const T, *len)
+            }
+        }
+    }
+}
+
+impl<T> Default for GraphStorage<T> {
+    fn default() -> Self {
+        Self::Owned(Vec::new())
+    }
+}
+
+impl<T: Debug> Debug for GraphStorage<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Serialize> Serialize for GraphStorage<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+/// Create an error for an invalid binary graph file at `path`
+fn invalid_data(path: &Path, msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", msg, path.display()))
+}
+
+/// Get the raw bytes of `items`
+fn as_bytes<T>(items: &[T]) -> &[u8] {
+    // The elements of graphs consist of fixed-size fields without implicit padding
+    unsafe { slice::from_raw_parts(items.as_ptr() as *const u8, mem::size_of_val(items)) }
+}
+
+/// Source file of a binary graph file, identified by its length and the SHA-256 checksum of its
+/// contents, such that binary graph files are outdated whenever the source file is modified
+pub(crate) struct SourceFile<'a> {
+    path: &'a Path,
+    len: u64,
+    checksum: [u64; 4],
+}
+
+impl<'a> SourceFile<'a> {
+    /// Read the source file at `path` and the files `companions` that the graph is read from
+    /// besides it to compute their combined checksum
+    pub(crate) fn open(path: &'a Path, companions: &[PathBuf]) -> io::Result<Self> {
+        let mut hasher = Sha256::new();
+        let mut len = io::copy(&mut File::open(path)?, &mut hasher)?;
+        for companion in companions {
+            len += io::copy(&mut File::open(companion)?, &mut hasher)?;
+        }
+        let digest = hasher.finalize();
+
+        let mut checksum = [0u64; 4];
+        for (field, bytes) in checksum.iter_mut().zip(digest.chunks_exact(8)) {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(bytes);
+            *field = u64::from_le_bytes(buf);
+        }
+        Ok(Self { path, len, checksum })
+    }
+
+    /// Get the length and the checksum of the source file, which identify its contents
+    pub(crate) fn fingerprint(&self) -> [u64; 5] {
+        [self.len, self.checksum[0], self.checksum[1], self.checksum[2], self.checksum[3]]
+    }
+}
+
+/// Write `graph` parsed from the source file `source` as binary graph file to `path`.
+/// The file is written to a temporary file first and then renamed, such that processes that
+/// concurrently map the file never observe partially written or modified files.
+pub(crate) fn write_file(graph: &Graph, path: &Path, source: &SourceFile) -> io::Result<()> {
+    let header: [u64; NUM_HEADER_FIELDS] = [
+        u64::from_ne_bytes(MAGIC),
+        FORMAT_VERSION,
+        BYTE_ORDER_MARKER,
+        source.len,
+        source.checksum[0],
+        source.checksum[1],
+        source.checksum[2],
+        source.checksum[3],
+        graph.num_nodes as u64,
+        graph.num_edges as u64,
+        0,
+        0,
+    ];
+
+    let tmp_path = path.with_extension(format!("{}.{}.tmp", FILE_EXTENSION, std::process::id()));
+    let mut file = File::create(&tmp_path)?;
+    // Sections are ordered by descending alignment of their elements, such that all of them are
+    // aligned without padding between them
+    file.write_all(as_bytes(&header))?;
+    file.write_all(as_bytes(graph.nodes()))?;
+    file.write_all(as_bytes(graph.ext_ids()))?;
+    file.write_all(as_bytes(graph.edges()))?;
+    file.write_all(as_bytes(graph.offsets()))?;
+    file.sync_all()?;
+    drop(file);
+
+    if let Err(err) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Map the binary graph file at `path` that was written for the source file `source`.
+/// Returns `None` if the binary graph file is outdated, i.e. was written for another version of
+/// the source file or in another format version.
+pub(crate) fn map_file(path: &Path, source: &SourceFile) -> io::Result<Option<Graph>> {
+    let mut header = [0u64; NUM_HEADER_FIELDS];
+    let mut file = File::open(path)?;
+    let mut header_bytes = [0u8; NUM_HEADER_FIELDS * 8];
+    file.read_exact(&mut header_bytes)?;
+    for (field, bytes) in header.iter_mut().zip(header_bytes.chunks_exact(8)) {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        *field = u64::from_ne_bytes(buf);
+    }
+
+    if header[0] != u64::from_ne_bytes(MAGIC) {
+        return Err(invalid_data(path, "Not a binary graph file"));
+    }
+    if header[2] != BYTE_ORDER_MARKER {
+        return Err(invalid_data(path, "Binary graph file was written with another byte order"));
+    }
+    if header[1] != FORMAT_VERSION || header[3] != source.len || header[4..8] != source.checksum {
+        return Ok(None);
+    }
+
+    // Mapped files must not be modified, which is ensured by only ever replacing them as a whole
+    let map = Arc::new(unsafe { Mmap::map(&file)? });
+    let num_nodes = header[8] as usize;
+    let num_edges = header[9] as usize;
+    if num_nodes > map.len() || num_edges > map.len() {
+        return Err(invalid_data(path, "Unexpected length of binary graph file"));
+    }
+
+    let nodes_offset = header_bytes.len();
+    let ext_ids_offset = nodes_offset + num_nodes * mem::size_of::<Node>();
+    let edges_offset = ext_ids_offset + num_nodes * mem::size_of::<ExtId>();
+    let offsets_offset = edges_offset + num_edges * mem::size_of::<Edge>();
+    let len = offsets_offset + (num_nodes + 1) * mem::size_of::<u32>();
+    if map.len() != len {
+        return Err(invalid_data(path, "Unexpected length of binary graph file"));
+    }
+
+    fn section<T>(map: &Arc<Mmap>, offset: usize, len: usize) -> GraphStorage<T> {
+        GraphStorage::Mapped { map: map.clone(), offset, len, _marker: PhantomData }
+    }
+    let mut graph = Graph::from_storage(
+        section(&map, nodes_offset, num_nodes),
+        section(&map, edges_offset, num_edges),
+        section(&map, offsets_offset, num_nodes + 1),
+        section(&map, ext_ids_offset, num_nodes),
+    );
+
+    // Corrupt adjacency arrays would only be detected when traversing the graph
+    let offsets_valid = graph.offsets().windows(2).all(|w| w[0] <= w[1])
+        && graph.offsets()[num_nodes] as usize == num_edges;
+    if num_nodes == 0 || !offsets_valid || graph.edges().iter().any(|e| e.tgt() >= num_nodes) {
+        return Err(invalid_data(path, "Corrupt binary graph file"));
+    }
+    // The metadata is small, so it is read from the header of the source file
+    graph.set_metadata(GraphMetadata::read_from_file(&source.path.to_string_lossy())?);
+
+    Ok(Some(graph))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::graph::{Graph, GraphView};
+    use crate::graph_binary::{map_file, write_file, SourceFile};
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let source_path = Path::new("data/bbgrund_undirected.fmi");
+        let source = SourceFile::open(source_path, &[]).unwrap();
+        let graph = Graph::parse_from_file(source_path.to_str().unwrap()).unwrap();
+        let path = std::env::temp_dir().join(format!("osmff_graph_{}.osmfg", std::process::id()));
+        write_file(&graph, &path, &source).unwrap();
+
+        let mapped = map_file(&path, &source).unwrap().unwrap();
+        assert_eq!(mapped.num_nodes, graph.num_nodes);
+        assert_eq!(mapped.num_edges, graph.num_edges);
+        for (n1, n2) in graph.nodes().iter().zip(mapped.nodes()) {
+            assert_eq!((n1.id(), n1.osm_id, n1.lat, n1.lon), (n2.id(), n2.osm_id, n2.lat, n2.lon));
+        }
+        assert_eq!(mapped.get_node_by_osm_id(8684768723).unwrap().id(), 347);
+        assert_eq!(mapped.run_dijkstra(&[0, 100]), graph.run_dijkstra(&[0, 100]));
+        assert_eq!(mapped.metadata().timestamp, graph.metadata().timestamp);
+
+        // Binary graph files of other source files are outdated
+        let other_source = std::env::temp_dir().join(format!("osmff_graph_{}.fmi", std::process::id()));
+        fs::write(&other_source, "# Other\n").unwrap();
+        assert!(map_file(&path, &SourceFile::open(&other_source, &[]).unwrap()).unwrap().is_none());
+
+        // Modifications of the source file that keep its length are detected by the checksum
+        let mut contents = fs::read(source_path).unwrap();
+        let last = contents.len() - 2;
+        contents[last] = if contents[last] == b'0' { b'1' } else { b'0' };
+        fs::write(&other_source, contents).unwrap();
+        assert!(map_file(&path, &SourceFile::open(&other_source, &[]).unwrap()).unwrap().is_none());
+        fs::remove_file(other_source).unwrap();
+
+        assert!(map_file(source_path, &source).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,332 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::binary_minheap::BinaryMinHeap;
+use crate::graph::GraphView;
+
+/// Maximum number of nodes settled by a single witness search. Searches that hit the limit add
+/// shortcuts that may be unnecessary, which keeps the hierarchy exact but slightly larger.
+const WITNESS_SETTLE_LIMIT: usize = 500;
+
+/// An edge of a contraction hierarchy, i.e. an edge of the graph or a shortcut for a path through
+/// contracted nodes
+#[derive(Debug, Clone, Copy)]
+struct ChEdge {
+    node: usize,
+    dist: usize,
+}
+
+/// Edges of a contraction hierarchy in compressed sparse row format, grouped by node
+#[derive(Debug, Default)]
+struct ChEdges {
+    offsets: Vec<usize>,
+    edges: Vec<ChEdge>,
+}
+
+impl ChEdges {
+    fn from_lists(lists: Vec<Vec<ChEdge>>) -> Self {
+        let mut offsets = Vec::with_capacity(lists.len() + 1);
+        offsets.push(0);
+        for list in &lists {
+            offsets.push(offsets[offsets.len() - 1] + list.len());
+        }
+        Self { offsets, edges: lists.into_iter().flatten().collect() }
+    }
+
+    fn of(&self, node_id: usize) -> &[ChEdge] {
+        &self.edges[self.offsets[node_id]..self.offsets[node_id + 1]]
+    }
+}
+
+/// Contraction hierarchy of a graph, which answers distance queries much faster than a Dijkstra
+/// on the graph. Nodes are contracted one by one in the order of their rank, and shortcuts are
+/// added between the neighbours of contracted nodes wherever a shortest path led through them.
+/// Distances are exact, i.e. always equal to the distances found by a Dijkstra on the graph.
+#[derive(Debug, Default)]
+pub struct ContractionHierarchy {
+    /// Rank of every node by node id, i.e. its position in the contraction order
+    ranks: Vec<usize>,
+    /// Node ids ordered by rank
+    order: Vec<usize>,
+    /// Edges from every node to nodes of higher rank
+    up: ChEdges,
+    /// Edges to every node from nodes of higher rank, with the ids of their source nodes
+    down: ChEdges,
+}
+
+/// Remaining graph during the contraction, i.e. the edges and shortcuts between nodes that have
+/// not been contracted yet
+struct Contraction {
+    outgoing: Vec<Vec<ChEdge>>,
+    incoming: Vec<Vec<ChEdge>>,
+    contracted: Vec<bool>,
+    /// Number of contracted neighbours of every node, which spreads the contraction evenly
+    contracted_neighbours: Vec<usize>,
+    /// Distances of the witness searches, which are reset after every search
+    witness_dists: Vec<usize>,
+}
+
+impl Contraction {
+    fn new(graph: &dyn GraphView) -> Self {
+        let num_nodes = graph.num_nodes();
+        let mut outgoing = vec![Vec::new(); num_nodes];
+        let mut incoming = vec![Vec::new(); num_nodes];
+        for edge in graph.iter_edges().filter(|edge| edge.src() != edge.tgt()) {
+            insert_edge(&mut outgoing[edge.src()], edge.tgt(), edge.dist());
+            insert_edge(&mut incoming[edge.tgt()], edge.src(), edge.dist());
+        }
+        Self {
+            outgoing,
+            incoming,
+            contracted: vec![false; num_nodes],
+            contracted_neighbours: vec![0; num_nodes],
+            witness_dists: vec![usize::MAX; num_nodes],
+        }
+    }
+
+    /// Find the shortcuts that contracting the node with id `node_id` requires, i.e. for every
+    /// path through the node from an incoming to an outgoing neighbour without a witness path of
+    /// at most the same length that avoids the node
+    fn shortcuts(&mut self, node_id: usize) -> Vec<(usize, ChEdge)> {
+        let mut shortcuts = Vec::new();
+        for i in 0..self.incoming[node_id].len() {
+            let src = self.incoming[node_id][i];
+            let max_dist = self.outgoing[node_id].iter()
+                .filter(|tgt| tgt.node != src.node)
+                .map(|tgt| src.dist + tgt.dist)
+                .max();
+            let max_dist = match max_dist {
+                Some(max_dist) => max_dist,
+                None => continue
+            };
+
+            let settled = self.witness_search(src.node, node_id, max_dist);
+            for tgt in self.outgoing[node_id].iter().filter(|tgt| tgt.node != src.node) {
+                if self.witness_dists[tgt.node] > src.dist + tgt.dist {
+                    shortcuts.push((src.node, ChEdge { node: tgt.node, dist: src.dist + tgt.dist }));
+                }
+            }
+            for settled in settled {
+                self.witness_dists[settled] = usize::MAX;
+            }
+        }
+        shortcuts
+    }
+
+    /// Run a Dijkstra from the node with id `src_id` that avoids the node with id `avoid_id` and
+    /// does not explore farther than `max_dist` or settle more than `WITNESS_SETTLE_LIMIT` nodes.
+    /// Returns the ids of all nodes with distances that need to be reset.
+    fn witness_search(&mut self, src_id: usize, avoid_id: usize, max_dist: usize) -> Vec<usize> {
+        let mut touched = vec![src_id];
+        let mut pq = BinaryHeap::new();
+        self.witness_dists[src_id] = 0;
+        pq.push(Reverse((0, src_id)));
+
+        let mut num_settled = 0;
+        while let Some(Reverse((dist, node_id))) = pq.pop() {
+            if dist > self.witness_dists[node_id] {
+                continue;
+            }
+            num_settled += 1;
+            if dist > max_dist || num_settled > WITNESS_SETTLE_LIMIT {
+                break;
+            }
+            for edge in &self.outgoing[node_id] {
+                let tgt_dist = dist + edge.dist;
+                if edge.node != avoid_id && tgt_dist < self.witness_dists[edge.node] {
+                    if self.witness_dists[edge.node] == usize::MAX {
+                        touched.push(edge.node);
+                    }
+                    self.witness_dists[edge.node] = tgt_dist;
+                    pq.push(Reverse((tgt_dist, edge.node)));
+                }
+            }
+        }
+        touched
+    }
+
+    /// Priority of contracting the node with id `node_id` next, lower is earlier. Nodes whose
+    /// contraction adds few shortcuts compared to the edges it removes are contracted first.
+    fn priority(&mut self, node_id: usize) -> i64 {
+        let num_shortcuts = self.shortcuts(node_id).len() as i64;
+        let num_removed = (self.incoming[node_id].len() + self.outgoing[node_id].len()) as i64;
+        2 * num_shortcuts - num_removed + self.contracted_neighbours[node_id] as i64
+    }
+
+    /// Contract the node with id `node_id`, i.e. add its shortcuts and remove it from the
+    /// remaining graph. Returns its edges to and from the remaining nodes.
+    fn contract(&mut self, node_id: usize) -> (Vec<ChEdge>, Vec<ChEdge>) {
+        for (src_id, shortcut) in self.shortcuts(node_id) {
+            insert_edge(&mut self.outgoing[src_id], shortcut.node, shortcut.dist);
+            insert_edge(&mut self.incoming[shortcut.node], src_id, shortcut.dist);
+        }
+
+        let outgoing = std::mem::take(&mut self.outgoing[node_id]);
+        let incoming = std::mem::take(&mut self.incoming[node_id]);
+        for edge in &outgoing {
+            self.incoming[edge.node].retain(|src| src.node != node_id);
+        }
+        for edge in &incoming {
+            self.outgoing[edge.node].retain(|tgt| tgt.node != node_id);
+        }
+        for edge in outgoing.iter().chain(&incoming) {
+            self.contracted_neighbours[edge.node] += 1;
+        }
+        self.contracted[node_id] = true;
+        (outgoing, incoming)
+    }
+}
+
+/// Insert the edge to `node` with distance `dist` into `edges`, or shorten the existing edge
+fn insert_edge(edges: &mut Vec<ChEdge>, node: usize, dist: usize) {
+    match edges.iter_mut().find(|edge| edge.node == node) {
+        Some(edge) => edge.dist = edge.dist.min(dist),
+        None => edges.push(ChEdge { node, dist })
+    }
+}
+
+impl ContractionHierarchy {
+    /// Build the contraction hierarchy of the graph `graph`. Nodes are contracted in the order of
+    /// their priorities, which are updated lazily when a node is about to be contracted.
+    pub fn new(graph: &dyn GraphView) -> Self {
+        let num_nodes = graph.num_nodes();
+        let mut contraction = Contraction::new(graph);
+        let mut pq: BinaryHeap<_> = (0..num_nodes)
+            .map(|node_id| Reverse((contraction.priority(node_id), node_id)))
+            .collect();
+
+        let mut ranks = vec![0; num_nodes];
+        let mut order = Vec::with_capacity(num_nodes);
+        let mut up = vec![Vec::new(); num_nodes];
+        let mut down = vec![Vec::new(); num_nodes];
+        while let Some(Reverse((priority, node_id))) = pq.pop() {
+            if contraction.contracted[node_id] {
+                continue;
+            }
+            let priority_now = contraction.priority(node_id);
+            if priority_now > priority && matches!(pq.peek(), Some(Reverse((next, _))) if priority_now > *next) {
+                pq.push(Reverse((priority_now, node_id)));
+                continue;
+            }
+
+            let (outgoing, incoming) = contraction.contract(node_id);
+            ranks[node_id] = order.len();
+            order.push(node_id);
+            up[node_id] = outgoing;
+            down[node_id] = incoming;
+        }
+
+        Self {
+            ranks,
+            order,
+            up: ChEdges::from_lists(up),
+            down: ChEdges::from_lists(down),
+        }
+    }
+
+    /// Get the number of shortcuts and edges of this contraction hierarchy
+    pub fn num_edges(&self) -> usize {
+        self.up.edges.len() + self.down.edges.len()
+    }
+
+    /// Get the distances from the nearest source node of `src_ids` to all nodes, which are
+    /// `usize::MAX` for unreachable nodes, like a Dijkstra on the graph.
+    /// The distances are computed by an upward search from the source nodes followed by a sweep
+    /// over all nodes in descending rank order.
+    pub fn distances_from(&self, src_ids: &[usize]) -> Vec<usize> {
+        let mut distances = vec![usize::MAX; self.ranks.len()];
+        for &src_id in src_ids {
+            distances[src_id] = 0;
+        }
+
+        let mut pq = BinaryMinHeap::with_capacity(self.ranks.len());
+        for &src_id in src_ids {
+            if !pq.contains(src_id) {
+                pq.push(src_id, &distances);
+            }
+        }
+        while !pq.is_empty() {
+            let node_id = pq.pop(&distances);
+            for edge in self.up.of(node_id) {
+                let dist = distances[node_id] + edge.dist;
+                if dist < distances[edge.node] {
+                    distances[edge.node] = dist;
+                    if pq.contains(edge.node) {
+                        pq.decrease_key(edge.node, &distances);
+                    } else {
+                        pq.push(edge.node, &distances);
+                    }
+                }
+            }
+        }
+
+        for &node_id in self.order.iter().rev() {
+            for edge in self.down.of(node_id) {
+                if distances[edge.node] != usize::MAX {
+                    distances[node_id] = distances[node_id].min(distances[edge.node] + edge.dist);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Get the distance from the node with id `src_id` to the node with id `tgt_id`, or `None` if
+    /// the target node is unreachable. Both nodes are searched upwards, and the shortest path
+    /// leads through the highest ranked node on it, which both searches find.
+    pub fn distance(&self, src_id: usize, tgt_id: usize) -> Option<usize> {
+        let forward = self.upward_search(src_id, &self.up);
+        let backward = self.upward_search(tgt_id, &self.down);
+        forward.iter()
+            .filter_map(|(node_id, dist)| backward.get(node_id).map(|other| dist + other))
+            .min()
+    }
+
+    /// Run a Dijkstra from the node with id `src_id` along the edges `edges` to nodes of higher
+    /// rank. Returns the distances of all reached nodes.
+    fn upward_search(&self, src_id: usize, edges: &ChEdges) -> HashMap<usize, usize> {
+        let mut distances = HashMap::new();
+        let mut pq = BinaryHeap::new();
+        distances.insert(src_id, 0);
+        pq.push(Reverse((0, src_id)));
+        while let Some(Reverse((dist, node_id))) = pq.pop() {
+            if dist > distances[&node_id] {
+                continue;
+            }
+            for edge in edges.of(node_id) {
+                let tgt_dist = dist + edge.dist;
+                if tgt_dist < *distances.get(&edge.node).unwrap_or(&usize::MAX) {
+                    distances.insert(edge.node, tgt_dist);
+                    pq.push(Reverse((tgt_dist, edge.node)));
+                }
+            }
+        }
+        distances
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::{Graph, GraphView};
+    use crate::graph_ch::ContractionHierarchy;
+
+    #[test]
+    fn test_ch_distances() {
+        for path in &["data/bbgrund_undirected.fmi", "data/stgcenter_undirected.fmi"] {
+            let graph = Graph::parse_from_file(path).unwrap();
+            let ch = ContractionHierarchy::new(&graph);
+            assert!(ch.num_edges() >= graph.num_edges);
+
+            let step = graph.num_nodes / 7;
+            for src in (0..graph.num_nodes).step_by(step) {
+                let dists = graph.run_dijkstra(&[src]);
+                assert_eq!(ch.distances_from(&[src]), dists);
+                for tgt in (0..graph.num_nodes).step_by(step / 3) {
+                    let expected = Some(dists[tgt]).filter(|&dist| dist < usize::MAX);
+                    assert_eq!(ch.distance(src, tgt), expected);
+                }
+            }
+            let sources = [0, graph.num_nodes / 2, graph.num_nodes - 1];
+            assert_eq!(ch.distances_from(&sources), graph.run_dijkstra(&sources));
+        }
+    }
+}
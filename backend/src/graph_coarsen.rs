@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use crate::graph::{Edge, Graph, GraphView, Node, MAX_EDGE_DIST};
+
+/// Coarsened version of a graph, in which every chain of nodes with exactly two neighbours, e.g.
+/// the shape points of a road between two junctions, is contracted into edges between the nodes
+/// at its ends. The edges of a chain are weighted with the total distance along the chain, so the
+/// fire needs about as many rounds to spread between the ends of the chain as in the original
+/// graph. Every node of the original graph is represented by a node of the coarsened graph.
+#[derive(Debug)]
+pub struct CoarsenedGraph {
+    graph: Arc<Graph>,
+    /// Node of the coarsened graph that represents every node of the original graph, by node id
+    representatives: Vec<usize>,
+    /// Number of nodes of the original graph that every node of the coarsened graph represents
+    weights: Vec<usize>,
+}
+
+impl CoarsenedGraph {
+    /// Coarsen `graph`. The nodes at the ends of chains keep their order, but get consecutive
+    /// ids starting at `0`. The inner nodes of a chain are represented by the end of the chain
+    /// they are closer to, and cycles without any other nodes are contracted into one node.
+    pub fn new(graph: &Graph) -> Self {
+        let num_nodes = graph.num_nodes();
+        let mut adjacent = vec![Vec::new(); num_nodes];
+        for edge in graph.iter_edges().filter(|edge| edge.src() != edge.tgt()) {
+            adjacent[edge.src()].push(edge.tgt());
+            adjacent[edge.tgt()].push(edge.src());
+        }
+        for neighbours in &mut adjacent {
+            neighbours.sort_unstable();
+            neighbours.dedup();
+        }
+
+        // Walk the chains from all nodes that are kept, then keep one node of every remaining cycle
+        let mut is_kept: Vec<_> = adjacent.iter().map(|neighbours| neighbours.len() != 2).collect();
+        let mut is_visited = is_kept.clone();
+        let mut chains = Vec::new();
+        for node_id in 0..num_nodes {
+            if is_kept[node_id] {
+                walk_chains(&adjacent, &is_kept, &mut is_visited, node_id, &mut chains);
+            }
+        }
+        for node_id in 0..num_nodes {
+            if !is_visited[node_id] {
+                is_kept[node_id] = true;
+                walk_chains(&adjacent, &is_kept, &mut is_visited, node_id, &mut chains);
+            }
+        }
+
+        let mut representatives = vec![usize::MAX; num_nodes];
+        let mut nodes = Vec::new();
+        for node_id in (0..num_nodes).filter(|&node_id| is_kept[node_id]) {
+            let node = graph.get_node(node_id);
+            representatives[node_id] = nodes.len();
+            nodes.push(Node::new(nodes.len(), node.osm_id, node.lat, node.lon));
+        }
+
+        let mut edges: Vec<_> = graph.iter_edges()
+            .filter(|edge| edge.src() != edge.tgt() && is_kept[edge.src()] && is_kept[edge.tgt()])
+            .map(|edge| Edge::new(representatives[edge.src()], representatives[edge.tgt()], edge.dist(), edge.attrs))
+            .collect();
+        for chain in &chains {
+            let (first, last) = (chain[0], chain[chain.len() - 1]);
+            if first != last {
+                let reversed: Vec<_> = chain.iter().rev().copied().collect();
+                for (src, tgt, path) in [(first, last, chain), (last, first, &reversed)] {
+                    if let Some(edge) = contract_path(graph, path) {
+                        edges.push(Edge::new(representatives[src], representatives[tgt], edge.dist(), edge.attrs));
+                    }
+                }
+            }
+
+            // Every inner node is represented by the end of the chain it is closer to
+            let steps: Vec<_> = chain.windows(2)
+                .map(|pair| step_dist(graph, pair[0], pair[1]))
+                .collect();
+            let total: usize = steps.iter().sum();
+            let mut dist = 0;
+            for (i, &node_id) in chain[1..chain.len() - 1].iter().enumerate() {
+                dist += steps[i];
+                representatives[node_id] = if 2 * dist <= total {
+                    representatives[first]
+                } else {
+                    representatives[last]
+                };
+            }
+        }
+        // Keep only the shortest of parallel edges
+        edges.sort_unstable_by_key(|edge| (edge.src(), edge.tgt(), edge.dist()));
+        edges.dedup_by_key(|edge| (edge.src(), edge.tgt()));
+
+        let mut weights = vec![0; nodes.len()];
+        for &representative in &representatives {
+            weights[representative] += 1;
+        }
+        let mut coarsened = Graph::from_nodes_and_edges(nodes, edges);
+        coarsened.set_metadata(graph.metadata().clone());
+
+        Self {
+            graph: Arc::new(coarsened),
+            representatives,
+            weights,
+        }
+    }
+
+    /// Get the coarsened graph
+    pub fn graph(&self) -> &Arc<Graph> {
+        &self.graph
+    }
+
+    /// Get the id of the node of the coarsened graph that represents the node with id `node_id`
+    /// of the original graph
+    pub fn representative(&self, node_id: usize) -> usize {
+        self.representatives[node_id]
+    }
+
+    /// Get the number of nodes of the original graph that the node with id `node_id` of the
+    /// coarsened graph represents
+    pub fn weight(&self, node_id: usize) -> usize {
+        self.weights[node_id]
+    }
+}
+
+/// Walk all chains from the kept node with id `start` through unvisited nodes that are not kept
+/// to the next kept node, and append them to `chains`
+fn walk_chains(adjacent: &[Vec<usize>], is_kept: &[bool], is_visited: &mut [bool], start: usize,
+               chains: &mut Vec<Vec<usize>>) {
+    for &next in &adjacent[start] {
+        if is_visited[next] {
+            continue;
+        }
+        let mut chain = vec![start];
+        let (mut prev, mut cur) = (start, next);
+        loop {
+            chain.push(cur);
+            if is_kept[cur] {
+                break;
+            }
+            is_visited[cur] = true;
+            let following = if adjacent[cur][0] == prev { adjacent[cur][1] } else { adjacent[cur][0] };
+            prev = cur;
+            cur = following;
+        }
+        chains.push(chain);
+    }
+}
+
+/// Get the shortest edge from the node with id `src` to the node with id `tgt`, if there is any
+fn shortest_edge(graph: &Graph, src: usize, tgt: usize) -> Option<&Edge> {
+    graph.get_outgoing_edges(src).iter()
+        .filter(|edge| edge.tgt() == tgt)
+        .min_by_key(|edge| edge.dist())
+}
+
+/// Get the distance between the adjacent nodes with ids `a` and `b` in either direction
+fn step_dist(graph: &Graph, a: usize, b: usize) -> usize {
+    shortest_edge(graph, a, b).or_else(|| shortest_edge(graph, b, a))
+        .map(|edge| edge.dist())
+        .unwrap_or(0)
+}
+
+/// Contract the path `path` into a single edge with the total distance along the path and the
+/// attributes of its first edge. Returns `None` if the path cannot be traversed in its direction.
+fn contract_path(graph: &Graph, path: &[usize]) -> Option<Edge> {
+    let mut dist = 0;
+    let mut attrs = None;
+    for pair in path.windows(2) {
+        let edge = shortest_edge(graph, pair[0], pair[1])?;
+        dist += edge.dist();
+        attrs.get_or_insert(edge.attrs);
+    }
+    Some(Edge::new(path[0], path[path.len() - 1], dist.min(MAX_EDGE_DIST), attrs?))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::{Edge, EdgeAttrs, Graph, GraphView, Node};
+
+    #[test]
+    fn test_coarsen_chains() {
+        // Three roads from junction 0 to the dead ends 2, 4 and 6, where the road to 6 is one-way,
+        // and a separate cycle 7 -> 8 -> 9 -> 7
+        let pairs = [(0, 1), (1, 2), (0, 3), (3, 4)];
+        let mut edges: Vec<_> = pairs.iter()
+            .flat_map(|&(a, b)| [(a, b), (b, a)])
+            .chain([(0, 5), (5, 6), (7, 8), (8, 9), (9, 7)])
+            .map(|(src, tgt)| Edge::new(src, tgt, 10, EdgeAttrs::default()))
+            .collect();
+        edges.push(Edge::new(1, 2, 30, EdgeAttrs::default()));
+        let nodes = (0..10).map(|id| Node::new(id, 100 + id as u64, 48.0, 9.0 + id as f64 * 1e-3)).collect();
+        let graph = Graph::from_nodes_and_edges(nodes, edges);
+
+        let coarsened = graph.coarsened();
+        let coarse = coarsened.graph();
+        assert_eq!(coarse.num_nodes(), 5);
+        assert_eq!(coarse.nodes().iter().map(|node| node.osm_id).collect::<Vec<_>>(), vec![100, 102, 104, 106, 107]);
+        // Both directions of the two-way roads, one direction of the one-way road, and no loop
+        assert_eq!(coarse.num_edges(), 5);
+        assert!(coarse.iter_edges().all(|edge| edge.dist() == 20));
+        assert!(coarse.get_edge_attrs(0, 3).is_some() && coarse.get_edge_attrs(3, 0).is_none());
+
+        let representatives: Vec<_> = (0..10).map(|node_id| coarsened.representative(node_id)).collect();
+        assert_eq!(representatives, vec![0, 0, 1, 0, 2, 0, 3, 4, 4, 4]);
+        assert_eq!((0..5).map(|node_id| coarsened.weight(node_id)).collect::<Vec<_>>(), vec![4, 1, 1, 1, 3]);
+    }
+
+    #[test]
+    fn test_coarsen_graph() {
+        let graph = Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+        let coarsened = graph.coarsened();
+        let coarse = coarsened.graph();
+        assert!(coarse.num_nodes() > 0 && coarse.num_nodes() < graph.num_nodes());
+        assert_eq!((0..coarse.num_nodes()).map(|node_id| coarsened.weight(node_id)).sum::<usize>(), graph.num_nodes());
+
+        // Contracting chains preserves the connected components
+        let (fine_components, coarse_components) = (graph.weak_components(), coarse.weak_components());
+        assert_eq!(fine_components.num_components(), coarse_components.num_components());
+        for edge in graph.iter_edges() {
+            assert_eq!(coarse_components.component_of(coarsened.representative(edge.src())),
+                       coarse_components.component_of(coarsened.representative(edge.tgt())));
+        }
+    }
+}
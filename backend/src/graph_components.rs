@@ -0,0 +1,241 @@
+use crate::graph::GraphView;
+
+/// Partition of the nodes of a graph into connected components. Components are numbered in the
+/// order of their first node for weakly connected components, and in the order in which they are
+/// completed by the depth-first search for strongly connected components.
+#[derive(Debug, Clone)]
+pub struct Components {
+    /// Component of every node by node id
+    component_ids: Vec<usize>,
+    /// Number of nodes of every component
+    sizes: Vec<usize>,
+}
+
+impl Components {
+    /// Compute the weakly connected components of `graph`, i.e. the components of the graph with
+    /// the direction of all edges ignored. A fire never leaves the weakly connected component of
+    /// its roots.
+    pub fn weakly_connected(graph: &dyn GraphView) -> Self {
+        let num_nodes = graph.num_nodes();
+        // Union-find with union by size and path halving
+        let mut parents: Vec<_> = (0..num_nodes).collect();
+        let mut set_sizes = vec![1; num_nodes];
+        let find = |parents: &mut Vec<usize>, mut node_id: usize| {
+            while parents[node_id] != node_id {
+                parents[node_id] = parents[parents[node_id]];
+                node_id = parents[node_id];
+            }
+            node_id
+        };
+        for edge in graph.iter_edges() {
+            let (src, tgt) = (find(&mut parents, edge.src()), find(&mut parents, edge.tgt()));
+            if src != tgt {
+                let (small, large) = if set_sizes[src] < set_sizes[tgt] { (src, tgt) } else { (tgt, src) };
+                parents[small] = large;
+                set_sizes[large] += set_sizes[small];
+            }
+        }
+
+        let mut component_of_set = vec![usize::MAX; num_nodes];
+        let mut component_ids = Vec::with_capacity(num_nodes);
+        let mut sizes = Vec::new();
+        for node_id in 0..num_nodes {
+            let set = find(&mut parents, node_id);
+            if component_of_set[set] == usize::MAX {
+                component_of_set[set] = sizes.len();
+                sizes.push(0);
+            }
+            component_ids.push(component_of_set[set]);
+            sizes[component_of_set[set]] += 1;
+        }
+
+        Self { component_ids, sizes }
+    }
+
+    /// Compute the strongly connected components of `graph`, i.e. the maximal sets of nodes that
+    /// can all reach each other, with an iterative version of Tarjan's algorithm
+    pub fn strongly_connected(graph: &dyn GraphView) -> Self {
+        let mut tarjan = Tarjan::new(graph.num_nodes());
+        for root in 0..graph.num_nodes() {
+            if tarjan.indices[root] == usize::MAX {
+                tarjan.search(graph, root);
+            }
+        }
+
+        Self {
+            component_ids: tarjan.component_ids,
+            sizes: tarjan.sizes,
+        }
+    }
+
+    /// Get the number of components
+    pub fn num_components(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// Get the component of the node with id `node_id`
+    pub fn component_of(&self, node_id: usize) -> usize {
+        self.component_ids[node_id]
+    }
+
+    /// Get the number of nodes of the component `component`
+    pub fn size(&self, component: usize) -> usize {
+        self.sizes[component]
+    }
+
+    /// Get the component with the most nodes, preferring lower components among equally large
+    /// ones. Returns `None` if the graph has no nodes.
+    pub fn largest(&self) -> Option<usize> {
+        (0..self.sizes.len()).max_by_key(|&component| (self.sizes[component], std::cmp::Reverse(component)))
+    }
+
+    /// Get whether every node, by node id, belongs to the largest component
+    pub fn largest_mask(&self) -> Vec<bool> {
+        let largest = self.largest();
+        self.component_ids.iter().map(|&component| Some(component) == largest).collect()
+    }
+}
+
+/// State of Tarjan's algorithm for strongly connected components
+struct Tarjan {
+    /// Order in which the nodes were visited, or `usize::MAX` for unvisited nodes
+    indices: Vec<usize>,
+    num_visited: usize,
+    /// Lowest index of any node on the stack reachable from every node
+    lowlinks: Vec<usize>,
+    is_on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    /// Path of the depth-first search with the index of the next outgoing edge of every node
+    path: Vec<(usize, usize)>,
+    component_ids: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl Tarjan {
+    fn new(num_nodes: usize) -> Self {
+        Self {
+            indices: vec![usize::MAX; num_nodes],
+            num_visited: 0,
+            lowlinks: vec![0; num_nodes],
+            is_on_stack: vec![false; num_nodes],
+            stack: Vec::new(),
+            path: Vec::new(),
+            component_ids: vec![usize::MAX; num_nodes],
+            sizes: Vec::new(),
+        }
+    }
+
+    /// Visit the node with id `node_id` and descend to it
+    fn visit(&mut self, node_id: usize) {
+        self.indices[node_id] = self.num_visited;
+        self.lowlinks[node_id] = self.num_visited;
+        self.num_visited += 1;
+        self.stack.push(node_id);
+        self.is_on_stack[node_id] = true;
+        self.path.push((node_id, 0));
+    }
+
+    /// Search all unvisited nodes reachable from the node with id `root` depth-first and complete
+    /// their components
+    fn search(&mut self, graph: &dyn GraphView, root: usize) {
+        self.visit(root);
+        while let Some(top) = self.path.last_mut() {
+            let node_id = top.0;
+            let next_edge = graph.get_outgoing_edges(node_id).get(top.1);
+            top.1 += 1;
+            match next_edge {
+                Some(edge) if self.indices[edge.tgt()] == usize::MAX => self.visit(edge.tgt()),
+                Some(edge) => if self.is_on_stack[edge.tgt()] {
+                    self.lowlinks[node_id] = self.lowlinks[node_id].min(self.indices[edge.tgt()]);
+                },
+                None => {
+                    // All edges of the node are searched, so its component is complete if no
+                    // node on the stack below it is reachable from it
+                    self.path.pop();
+                    if let Some(&(parent, _)) = self.path.last() {
+                        self.lowlinks[parent] = self.lowlinks[parent].min(self.lowlinks[node_id]);
+                    }
+                    if self.lowlinks[node_id] == self.indices[node_id] {
+                        self.complete_component(node_id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pop the component whose first visited node has id `node_id` from the stack
+    fn complete_component(&mut self, node_id: usize) {
+        let component = self.sizes.len();
+        let mut size = 0;
+        while let Some(member) = self.stack.pop() {
+            self.is_on_stack[member] = false;
+            self.component_ids[member] = component;
+            size += 1;
+            if member == node_id {
+                break;
+            }
+        }
+        self.sizes.push(size);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::{Edge, EdgeAttrs, Graph, GraphView, Node};
+    use crate::graph_binary::GraphStorage;
+    use crate::graph_components::Components;
+
+    /// Create a graph with `num_nodes` nodes and the edges `edges` given as `(src, tgt)` pairs
+    fn graph_with_edges(num_nodes: usize, edges: &[(usize, usize)]) -> Graph {
+        let nodes: Vec<_> = (0..num_nodes).map(|id| Node::new(id, id as u64, 48.0, 9.0 + id as f64 * 1e-3)).collect();
+        let mut edges: Vec<_> = edges.iter()
+            .map(|&(src, tgt)| Edge::new(src, tgt, 1, EdgeAttrs::default()))
+            .collect();
+        edges.sort_unstable_by_key(|edge| edge.src());
+        let mut offsets = vec![0u32; num_nodes + 1];
+        for edge in &edges {
+            offsets[edge.src() + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+        Graph::from_storage(GraphStorage::Owned(nodes), GraphStorage::Owned(edges),
+                            GraphStorage::Owned(offsets), GraphStorage::Owned(Vec::new()))
+    }
+
+    #[test]
+    fn test_components() {
+        // A cycle 0 -> 1 -> 2 -> 0 with a tail 2 -> 3, an edge 4 -> 5 and an isolated node 6
+        let graph = graph_with_edges(7, &[(0, 1), (1, 2), (2, 0), (2, 3), (4, 5)]);
+
+        let weak = Components::weakly_connected(&graph);
+        assert_eq!(weak.num_components(), 3);
+        assert_eq!((0..7).map(|node_id| weak.component_of(node_id)).collect::<Vec<_>>(), vec![0, 0, 0, 0, 1, 1, 2]);
+        assert_eq!(weak.largest(), Some(0));
+        assert_eq!(weak.size(0), 4);
+        assert_eq!(weak.largest_mask(), vec![true, true, true, true, false, false, false]);
+
+        let strong = Components::strongly_connected(&graph);
+        assert_eq!(strong.num_components(), 5);
+        let cycle = strong.component_of(0);
+        assert!((1..3).all(|node_id| strong.component_of(node_id) == cycle));
+        assert!((3..7).all(|node_id| strong.component_of(node_id) != cycle));
+        assert_eq!(strong.largest(), Some(cycle));
+        assert_eq!(strong.size(cycle), 3);
+    }
+
+    #[test]
+    fn test_undirected_components() {
+        // Weakly and strongly connected components coincide on undirected graphs
+        let graph = Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+        let weak = Components::weakly_connected(&graph);
+        let strong = Components::strongly_connected(&graph);
+        assert_eq!(weak.num_components(), strong.num_components());
+        assert_eq!((0..weak.num_components()).map(|c| weak.size(c)).sum::<usize>(), graph.num_nodes());
+        for edge in graph.iter_edges() {
+            assert_eq!(weak.component_of(edge.src()), weak.component_of(edge.tgt()));
+            assert_eq!(strong.component_of(edge.src()), strong.component_of(edge.tgt()));
+        }
+        assert_eq!(weak.size(weak.largest().unwrap()), strong.size(strong.largest().unwrap()));
+    }
+}
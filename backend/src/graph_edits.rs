@@ -0,0 +1,237 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use derive_more::{Display, Error};
+use geo::{HaversineDistance, Point};
+use serde::{Deserialize, Serialize};
+
+use crate::graph::{Edge, EdgeAttrs, Graph, GraphView, MAX_EDGE_DIST};
+use crate::graph_overlay::OverlayGraph;
+
+/// A node that is added to a graph, e.g. the junction of a planned road
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddedNode {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// An edge that is added to a graph, e.g. a planned road
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddedEdge {
+    pub src: usize,
+    pub tgt: usize,
+    /// Distance between source and target. Defaults to the great-circle distance in meters.
+    #[serde(default)]
+    pub dist: Option<usize>,
+    /// Whether only the edge from source to target is added, and not also its reverse edge
+    #[serde(default)]
+    pub one_way: bool,
+}
+
+/// An edge that is removed from a graph, e.g. a demolished bridge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemovedEdge {
+    pub src: usize,
+    pub tgt: usize,
+    /// Whether only the edges from source to target are removed, and not also their reverse edges
+    #[serde(default)]
+    pub one_way: bool,
+}
+
+/// A batch of edits of a graph.
+/// The edits of a batch are applied in the order: added nodes, removed edges, removed nodes and
+/// added edges. Added nodes get the ids following the ids of all existing nodes in the order in
+/// which they are listed. Removed nodes keep their ids, but lose all their edges.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphEdits {
+    #[serde(default)]
+    pub add_nodes: Vec<AddedNode>,
+    #[serde(default)]
+    pub remove_edges: Vec<RemovedEdge>,
+    #[serde(default)]
+    pub remove_nodes: Vec<usize>,
+    #[serde(default)]
+    pub add_edges: Vec<AddedEdge>,
+}
+
+/// Error that occurs when edits cannot be applied to a graph
+#[derive(Debug, Display, Error, PartialEq)]
+pub enum GraphEditError {
+    #[display(fmt = "Invalid coordinates of added node: {}/{}", lat, lon)]
+    InvalidCoordinates { lat: f64, lon: f64 },
+    #[display(fmt = "Unknown node: {}", node_id)]
+    UnknownNode { node_id: usize },
+    #[display(fmt = "Node has been removed: {}", node_id)]
+    RemovedNode { node_id: usize },
+    #[display(fmt = "Unknown edge: {} -> {}", src, tgt)]
+    UnknownEdge { src: usize, tgt: usize },
+    #[display(fmt = "Distance of added edge must be at most {}: {}", max, dist)]
+    InvalidDistance { max: usize, dist: usize },
+}
+
+/// A graph derived from a shared base graph by applying batches of edits.
+/// The edits are applied to an overlay of the base graph, such that the base graph is neither
+/// modified nor copied.
+pub struct EditedGraph {
+    graph: Arc<OverlayGraph>,
+    edits: Vec<GraphEdits>,
+    removed_nodes: BTreeSet<usize>,
+}
+
+impl EditedGraph {
+    /// Create a new edited graph from the base graph `base` without any edits
+    pub fn new(base: Arc<Graph>) -> Self {
+        Self {
+            graph: Arc::new(OverlayGraph::new(base)),
+            edits: Vec::new(),
+            removed_nodes: BTreeSet::new(),
+        }
+    }
+
+    /// Get the base graph that has been edited
+    pub fn base(&self) -> &Arc<Graph> {
+        self.graph.base()
+    }
+
+    /// Get the graph with all edits applied
+    pub fn graph(&self) -> &Arc<OverlayGraph> {
+        &self.graph
+    }
+
+    /// Get all batches of edits in the order in which they have been applied
+    pub fn edits(&self) -> &[GraphEdits] {
+        &self.edits
+    }
+
+    /// Get the ids of all removed nodes
+    pub fn removed_nodes(&self) -> &BTreeSet<usize> {
+        &self.removed_nodes
+    }
+
+    /// Apply the batch of edits `edits` to the edited graph.
+    /// Returns the ids of the added nodes if the operation succeeds, or an `Err` without applying
+    /// any edit of the batch otherwise.
+    pub fn apply(&mut self, edits: GraphEdits) -> Result<Vec<usize>, GraphEditError> {
+        let mut graph = OverlayGraph::clone(&self.graph);
+        let mut removed_nodes = self.removed_nodes.clone();
+
+        let mut added_node_ids = Vec::with_capacity(edits.add_nodes.len());
+        for added in &edits.add_nodes {
+            if !(-90.0..=90.0).contains(&added.lat) || !(-180.0..=180.0).contains(&added.lon) {
+                return Err(GraphEditError::InvalidCoordinates { lat: added.lat, lon: added.lon });
+            }
+            added_node_ids.push(graph.add_node(added.lat, added.lon));
+        }
+
+        let num_nodes = graph.num_nodes();
+        let known = |node_id: usize| if node_id < num_nodes {
+            Ok(node_id)
+        } else {
+            Err(GraphEditError::UnknownNode { node_id })
+        };
+
+        for removed in &edits.remove_edges {
+            let mut num_removed = graph.remove_edges(known(removed.src)?, known(removed.tgt)?);
+            if !removed.one_way {
+                num_removed += graph.remove_edges(removed.tgt, removed.src);
+            }
+            if num_removed == 0 {
+                return Err(GraphEditError::UnknownEdge { src: removed.src, tgt: removed.tgt });
+            }
+        }
+
+        let mut isolated_nodes = BTreeSet::new();
+        for &node_id in &edits.remove_nodes {
+            if removed_nodes.insert(known(node_id)?) {
+                isolated_nodes.insert(node_id);
+            }
+        }
+        if !isolated_nodes.is_empty() {
+            graph.isolate_nodes(&isolated_nodes);
+        }
+
+        for added in &edits.add_edges {
+            for node_id in [added.src, added.tgt] {
+                if removed_nodes.contains(&known(node_id)?) {
+                    return Err(GraphEditError::RemovedNode { node_id });
+                }
+            }
+            let dist = added.dist.unwrap_or_else(|| {
+                let (src, tgt) = (graph.get_node(added.src), graph.get_node(added.tgt));
+                Point::new(src.lon, src.lat).haversine_distance(&Point::new(tgt.lon, tgt.lat)).round() as usize
+            });
+            if dist > MAX_EDGE_DIST {
+                return Err(GraphEditError::InvalidDistance { max: MAX_EDGE_DIST, dist });
+            }
+            graph.add_edge(Edge::new(added.src, added.tgt, dist, EdgeAttrs::default()));
+            if !added.one_way {
+                graph.add_edge(Edge::new(added.tgt, added.src, dist, EdgeAttrs::default()));
+            }
+        }
+
+        log::debug!("Applied edits to graph: {} nodes, {} edges", graph.num_nodes(), graph.num_edges());
+        self.graph = Arc::new(graph);
+        self.edits.push(edits);
+        self.removed_nodes = removed_nodes;
+        Ok(added_node_ids)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::graph::{Graph, GraphView};
+    use crate::graph_edits::{AddedEdge, AddedNode, EditedGraph, GraphEditError, GraphEdits, RemovedEdge};
+
+    #[test]
+    fn test_edited_graph() {
+        let base = Arc::new(Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap());
+        let mut edited = EditedGraph::new(base.clone());
+
+        let edge = &base.get_outgoing_edges(0)[0];
+        let (src, tgt) = (edge.src(), edge.tgt());
+        let added = edited.apply(GraphEdits {
+            add_nodes: vec![AddedNode { lat: 48.675, lon: 9.01 }],
+            remove_edges: vec![RemovedEdge { src, tgt, one_way: false }],
+            add_edges: vec![AddedEdge { src: base.num_nodes, tgt: 0, dist: None, one_way: false }],
+            ..GraphEdits::default()
+        }).unwrap();
+        assert_eq!(added, vec![base.num_nodes]);
+
+        // The base graph is untouched
+        assert_eq!(edited.base().num_nodes, base.num_nodes);
+        assert!(base.get_outgoing_edges(src).iter().any(|edge| edge.tgt() == tgt));
+
+        let graph = edited.graph().clone();
+        assert_eq!(graph.num_nodes(), base.num_nodes + 1);
+        assert_eq!(graph.num_edges(), base.num_edges);
+        assert!(!graph.get_outgoing_edges(src).iter().any(|edge| edge.tgt() == tgt));
+        assert!(!graph.get_outgoing_edges(tgt).iter().any(|edge| edge.tgt() == src));
+        assert!(graph.get_outgoing_edges(base.num_nodes).iter().any(|edge| edge.tgt() == 0 && edge.dist() > 0));
+        assert!(graph.run_dijkstra(&[base.num_nodes])[0] < usize::MAX);
+
+        // Removed nodes lose all their edges and cannot get new ones
+        edited.apply(GraphEdits { remove_nodes: vec![0], ..GraphEdits::default() }).unwrap();
+        let num_edges = edited.graph().num_edges();
+        assert_eq!(edited.graph().get_node_degree(0), 0);
+        assert!(edited.graph().iter_edges().all(|edge| edge.tgt() != 0));
+        assert_eq!(edited.apply(GraphEdits {
+            add_edges: vec![AddedEdge { src: 1, tgt: 0, dist: Some(1), one_way: true }],
+            ..GraphEdits::default()
+        }), Err(GraphEditError::RemovedNode { node_id: 0 }));
+
+        // Invalid batches are not applied at all
+        assert_eq!(edited.apply(GraphEdits {
+            remove_nodes: vec![1, base.num_nodes + 1],
+            ..GraphEdits::default()
+        }), Err(GraphEditError::UnknownNode { node_id: base.num_nodes + 1 }));
+        assert_eq!(edited.apply(GraphEdits {
+            remove_edges: vec![RemovedEdge { src, tgt, one_way: true }],
+            ..GraphEdits::default()
+        }), Err(GraphEditError::UnknownEdge { src, tgt }));
+        assert_eq!(edited.edits().len(), 2);
+        assert_eq!(edited.graph().num_edges(), num_edges);
+        assert_eq!(edited.removed_nodes().iter().copied().collect::<Vec<_>>(), vec![0]);
+    }
+}
@@ -0,0 +1,139 @@
+use std::f64::consts::PI;
+use std::io::{self, Write};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::graph::GraphView;
+
+/// Media type of graph files in the FMI text format
+pub const MEDIA_TYPE: &str = "text/plain; charset=utf-8";
+
+/// Approximate length of one degree of latitude in meters
+const METERS_PER_DEG_LAT: f64 = 111_320.0;
+
+/// Header fields that describe the edge weights, which are kept in anonymized graph files
+const WEIGHT_KEYS: [&str; 2] = ["Type", "Weights"];
+
+/// Transform that hides where a graph is located while preserving its topology and its edge
+/// weights, e.g. to share benchmark graphs derived from proprietary or licensed data.
+/// OSM ids are replaced by node ids and all header fields except those describing the edge
+/// weights are dropped in either case.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anonymization {
+    /// Place all nodes at latitude and longitude 0
+    Strip,
+    /// Move every node to a random position within `radius_m` meters of its position. The
+    /// positions are derived from `seed`, such that the same graph file is written every time.
+    Jitter { radius_m: f64, seed: u64 },
+}
+
+impl Anonymization {
+    /// Get the name of this anonymization, which is written to the header of graph files
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Strip => "strip",
+            Self::Jitter { .. } => "jitter",
+        }
+    }
+}
+
+/// Write the graph `graph` in the FMI text format to `out`, optionally anonymized by
+/// `anonymization`. Nodes are written without elevation, which graphs do not keep.
+pub fn write_fmi<W: Write>(graph: &dyn GraphView, out: &mut W, anonymization: Option<&Anonymization>)
+    -> io::Result<()> {
+    let properties = &graph.metadata().properties;
+    match anonymization {
+        Some(anonymization) => {
+            for (key, value) in properties.iter().filter(|(key, _)| WEIGHT_KEYS.contains(&key.as_str())) {
+                writeln!(out, "# {} : {}", key, value)?;
+            }
+            writeln!(out, "# Anonymized : {}", anonymization.name())?;
+        }
+        None => {
+            for (key, value) in properties {
+                writeln!(out, "# {} : {}", key, value)?;
+            }
+        }
+    }
+    writeln!(out)?;
+    writeln!(out, "{}", graph.num_nodes())?;
+    writeln!(out, "{}", graph.num_edges())?;
+
+    let mut rng = match anonymization {
+        Some(Anonymization::Jitter { seed, .. }) => Some(StdRng::seed_from_u64(*seed)),
+        _ => None
+    };
+    for node in graph.iter_nodes() {
+        let (osm_id, lat, lon) = match (anonymization, rng.as_mut()) {
+            (None, _) => (node.osm_id, node.lat, node.lon),
+            (Some(Anonymization::Jitter { radius_m, .. }), Some(rng)) => {
+                // Uniformly distributed within the circle around the node
+                let angle = rng.gen_range(0.0..2.0 * PI);
+                let dist = radius_m * rng.gen::<f64>().sqrt();
+                let d_lat = dist * angle.cos() / METERS_PER_DEG_LAT;
+                let d_lon = dist * angle.sin() / (METERS_PER_DEG_LAT * node.lat.to_radians().cos());
+                (node.id() as u64, node.lat + d_lat, node.lon + d_lon)
+            }
+            (Some(_), _) => (node.id() as u64, 0.0, 0.0)
+        };
+        writeln!(out, "{} {} {} {} 0", node.id(), osm_id, lat, lon)?;
+    }
+    for edge in graph.iter_edges() {
+        writeln!(out, "{} {} {} {} {}", edge.src(), edge.tgt(), edge.dist(), edge.attrs.edge_type,
+                 edge.attrs.maxspeed)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::graph::{EdgeAttrs, Graph, GraphView};
+    use crate::graph_fmi::{write_fmi, Anonymization};
+
+    /// Get the source, target, weight and attributes of all edges of `graph`
+    fn edges(graph: &Graph) -> Vec<(usize, usize, usize, EdgeAttrs)> {
+        graph.edges().iter().map(|edge| (edge.src(), edge.tgt(), edge.dist(), edge.attrs)).collect()
+    }
+
+    /// Write `graph` anonymized by `anonymization` and parse the written graph file
+    fn roundtrip(graph: &Graph, anonymization: Option<&Anonymization>) -> Graph {
+        let mut buf = Vec::new();
+        write_fmi(graph, &mut buf, anonymization).unwrap();
+        let path = std::env::temp_dir().join(format!("osmff_fmi_{}_{:?}.fmi", std::process::id(),
+                                                     anonymization.map(|a| a.name())));
+        fs::write(&path, buf).unwrap();
+        let written = Graph::parse_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(path).unwrap();
+        written
+    }
+
+    #[test]
+    fn test_write_fmi() {
+        let graph = Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+
+        let written = roundtrip(&graph, None);
+        assert_eq!(written.metadata().properties, graph.metadata().properties);
+        for (n1, n2) in graph.nodes().iter().zip(written.nodes()) {
+            assert_eq!((n1.id(), n1.osm_id, n1.lat, n1.lon), (n2.id(), n2.osm_id, n2.lat, n2.lon));
+        }
+        assert_eq!(edges(&written), edges(&graph));
+
+        // Anonymized graphs keep their topology and edge weights only
+        let jitter = Anonymization::Jitter { radius_m: 100.0, seed: 42 };
+        for anonymization in &[Anonymization::Strip, jitter] {
+            let written = roundtrip(&graph, Some(anonymization));
+            assert_eq!(edges(&written), edges(&graph));
+            assert_eq!(written.metadata().properties.keys().collect::<Vec<_>>(), vec!["Anonymized", "Type"]);
+            assert!(written.nodes().iter().all(|node| node.osm_id == node.id() as u64));
+            for (n1, n2) in graph.nodes().iter().zip(written.nodes()) {
+                assert_ne!((n1.lat, n1.lon), (n2.lat, n2.lon));
+                if let Anonymization::Jitter { .. } = anonymization {
+                    assert!((n1.lat - n2.lat).abs() < 0.001 && (n1.lon - n2.lon).abs() < 0.002);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+use crate::graph::{Edge, GraphView, Node};
+
+/// Media type of GeoJSON documents
+pub const MEDIA_TYPE: &str = "application/geo+json";
+
+/// GeoJSON representation of a graph as `FeatureCollection`, with a `Point` feature for every node
+/// and a `LineString` feature for every edge, e.g. to inspect the graph in QGIS or Leaflet.
+/// Features are serialized on the fly, such that large graphs are not copied.
+pub struct GeoJsonGraph<'a> {
+    graph: &'a dyn GraphView,
+}
+
+impl<'a> GeoJsonGraph<'a> {
+    /// Create the GeoJSON representation of the graph `graph`
+    pub fn new(graph: &'a dyn GraphView) -> Self {
+        Self { graph }
+    }
+}
+
+impl Serialize for GeoJsonGraph<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("type", "FeatureCollection")?;
+        // Foreign member, since derived data must be attributed
+        map.serialize_entry("attribution", &self.graph.metadata().attribution)?;
+        map.serialize_entry("features", &Features(self.graph))?;
+        map.end()
+    }
+}
+
+/// All features of a graph, i.e. its nodes followed by its edges
+struct Features<'a>(&'a dyn GraphView);
+
+impl Serialize for Features<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let graph = self.0;
+        serializer.collect_seq(graph.iter_nodes().map(|node| Feature::from_node(graph, node))
+            .chain(graph.iter_edges().map(|edge| Feature::from_edge(graph, edge))))
+    }
+}
+
+/// GeoJSON feature of a node or an edge
+#[derive(Serialize)]
+#[serde(tag = "type", rename = "Feature")]
+struct Feature {
+    geometry: Geometry,
+    properties: Properties,
+}
+
+impl Feature {
+    fn from_node(graph: &dyn GraphView, node: &Node) -> Self {
+        let weights = graph.node_weights();
+        Self {
+            geometry: Geometry::Point { coordinates: position(node) },
+            properties: Properties::Node {
+                id: node.id(),
+                osm_id: node.osm_id,
+                weight: weights.map(|weights| weights.weight(node.id())),
+                class: weights.and_then(|weights| weights.class(node.id())).map(str::to_string),
+            },
+        }
+    }
+
+    fn from_edge(graph: &dyn GraphView, edge: &Edge) -> Self {
+        let (src, tgt) = (graph.get_node(edge.src()), graph.get_node(edge.tgt()));
+        Self {
+            geometry: Geometry::LineString { coordinates: [position(src), position(tgt)] },
+            properties: Properties::Edge {
+                src: edge.src(),
+                tgt: edge.tgt(),
+                dist: edge.dist(),
+                edge_type: edge.attrs.edge_type,
+                maxspeed: edge.attrs.maxspeed,
+            },
+        }
+    }
+}
+
+/// GeoJSON geometry of a node or an edge
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum Geometry {
+    Point { coordinates: [f64; 2] },
+    LineString { coordinates: [[f64; 2]; 2] },
+}
+
+/// Properties of a node or an edge feature
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Properties {
+    Node {
+        id: usize,
+        osm_id: u64,
+        /// Weight and class of the node, if the graph has node weights
+        #[serde(skip_serializing_if = "Option::is_none")]
+        weight: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        class: Option<String>,
+    },
+    Edge { src: usize, tgt: usize, dist: usize, edge_type: u32, maxspeed: u32 },
+}
+
+/// Get the GeoJSON position of the node `node`, which has longitude before latitude
+fn position(node: &Node) -> [f64; 2] {
+    [node.lon, node.lat]
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use crate::graph::{Graph, GraphView};
+    use crate::graph_geojson::GeoJsonGraph;
+
+    #[test]
+    fn test_geojson() {
+        let graph = Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+        let geojson = serde_json::to_value(GeoJsonGraph::new(&graph)).unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), graph.num_nodes + graph.num_edges);
+        let node = graph.get_node(0);
+        assert_eq!(features[0]["geometry"]["type"], "Point");
+        assert_eq!(features[0]["geometry"]["coordinates"], Value::from(vec![node.lon, node.lat]));
+        assert_eq!(features[0]["properties"]["osm_id"], node.osm_id);
+
+        let edge = &graph.edges()[0];
+        let feature = &features[graph.num_nodes];
+        assert_eq!(feature["type"], "Feature");
+        assert_eq!(feature["geometry"]["type"], "LineString");
+        assert_eq!(feature["geometry"]["coordinates"][1][1], graph.get_node(edge.tgt()).lat);
+        assert_eq!(feature["properties"]["dist"], edge.dist());
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::graph::weights::NodeWeights;
+use crate::graph::{Edge, Graph, GraphMetadata, GraphView, GridBounds, Node};
+
+/// Overlay of a shared graph that adds nodes and adds and removes edges without copying the
+/// shared graph, e.g. for session-specific edits, blocked roads or perturbation tests.
+/// The overlay stores the outgoing edges of all added nodes and of all nodes whose outgoing edges
+/// are modified, all other edges are read from the shared graph.
+#[derive(Debug, Clone)]
+pub struct OverlayGraph {
+    base: Arc<Graph>,
+    added_nodes: Vec<Node>,
+    modified_edges: HashMap<usize, Vec<Edge>>,
+    num_edges: usize,
+}
+
+impl OverlayGraph {
+    /// Create a new overlay of the shared graph `base` without any modifications
+    pub fn new(base: Arc<Graph>) -> Self {
+        Self {
+            num_edges: base.num_edges,
+            base,
+            added_nodes: Vec::new(),
+            modified_edges: HashMap::new(),
+        }
+    }
+
+    /// Get the shared graph of this overlay
+    pub fn base(&self) -> &Arc<Graph> {
+        &self.base
+    }
+
+    /// Add a node without OSM id at the coordinates `lat`/`lon`.
+    /// Returns the id of the added node, which follows the ids of all existing nodes.
+    pub fn add_node(&mut self, lat: f64, lon: f64) -> usize {
+        let id = self.num_nodes();
+        self.added_nodes.push(Node::new(id, 0, lat, lon));
+        id
+    }
+
+    /// Add the edge `edge`, whose source and target node must exist
+    pub fn add_edge(&mut self, edge: Edge) {
+        assert!(edge.src() < self.num_nodes() && edge.tgt() < self.num_nodes());
+        self.edges_mut(edge.src()).push(edge);
+        self.num_edges += 1;
+    }
+
+    /// Remove all edges from the node with id `src` to the node with id `tgt`.
+    /// Returns the number of removed edges.
+    pub fn remove_edges(&mut self, src: usize, tgt: usize) -> usize {
+        if !self.get_outgoing_edges(src).iter().any(|edge| edge.tgt() == tgt) {
+            return 0;
+        }
+        let edges = self.edges_mut(src);
+        let num_edges = edges.len();
+        edges.retain(|edge| edge.tgt() != tgt);
+        let num_removed = num_edges - edges.len();
+        self.num_edges -= num_removed;
+        num_removed
+    }
+
+    /// Remove all edges from and to the nodes with ids `node_ids`, such that the nodes are
+    /// isolated. Returns the number of removed edges.
+    pub fn isolate_nodes(&mut self, node_ids: &BTreeSet<usize>) -> usize {
+        // Incoming edges can only be found by scanning all edges, since graphs may be directed
+        let srcs: Vec<_> = (0..self.num_nodes())
+            .filter(|&src| node_ids.contains(&src)
+                || self.get_outgoing_edges(src).iter().any(|edge| node_ids.contains(&edge.tgt())))
+            .collect();
+
+        let mut num_removed = 0;
+        for src in srcs {
+            let edges = self.edges_mut(src);
+            let num_edges = edges.len();
+            if node_ids.contains(&src) {
+                edges.clear();
+            } else {
+                edges.retain(|edge| !node_ids.contains(&edge.tgt()));
+            }
+            num_removed += num_edges - edges.len();
+        }
+        self.num_edges -= num_removed;
+        num_removed
+    }
+
+    /// Get the modifiable outgoing edges of the node with id `node_id`, which are copied from the
+    /// shared graph on their first modification
+    fn edges_mut(&mut self, node_id: usize) -> &mut Vec<Edge> {
+        let base = &self.base;
+        self.modified_edges.entry(node_id)
+            .or_insert_with(|| if node_id < base.num_nodes {
+                base.get_outgoing_edges(node_id).to_vec()
+            } else {
+                Vec::new()
+            })
+    }
+}
+
+impl GraphView for OverlayGraph {
+    fn num_nodes(&self) -> usize {
+        self.base.num_nodes + self.added_nodes.len()
+    }
+
+    fn num_edges(&self) -> usize {
+        self.num_edges
+    }
+
+    fn get_node(&self, node_id: usize) -> &Node {
+        if node_id < self.base.num_nodes {
+            self.base.get_node(node_id)
+        } else {
+            &self.added_nodes[node_id - self.base.num_nodes]
+        }
+    }
+
+    fn get_outgoing_edges(&self, node_id: usize) -> &[Edge] {
+        match self.modified_edges.get(&node_id) {
+            Some(edges) => edges,
+            None if node_id < self.base.num_nodes => self.base.get_outgoing_edges(node_id),
+            None => {
+                assert!(node_id < self.num_nodes(), "Unknown node {}", node_id);
+                &[]
+            }
+        }
+    }
+
+    fn metadata(&self) -> &GraphMetadata {
+        self.base.metadata()
+    }
+
+    fn node_weights(&self) -> Option<&NodeWeights> {
+        // Added nodes are beyond the weights of the shared graph and thus have the weight `0`
+        self.base.node_weights()
+    }
+
+    fn max_edge_dist(&self) -> usize {
+        // Removed edges of the base graph are still counted, which is fine for an upper bound
+        self.modified_edges.values().flatten()
+            .map(|edge| edge.dist())
+            .fold(self.base.max_edge_dist(), usize::max)
+    }
+
+    fn nearest_node(&self, lat: f64, lon: f64) -> &Node {
+        // Only the nodes of the shared graph are indexed, so added nodes are searched linearly
+        let dist = |node: &Node| (node.lat - lat).powi(2) + (node.lon - lon).powi(2);
+        let nearest = self.base.nearest_node(lat, lon);
+        self.added_nodes.iter()
+            .fold(nearest, |nearest, node| if dist(node) < dist(nearest) { node } else { nearest })
+    }
+
+    fn nodes_in_bounds<'a>(&'a self, gb: &'a GridBounds) -> Box<dyn Iterator<Item = &'a Node> + 'a> {
+        Box::new(self.base.nodes_in_bounds(gb)
+            .chain(self.added_nodes.iter().filter(move |node| node.is_located_in(gb))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+    use std::sync::Arc;
+
+    use rand::prelude::*;
+
+    use crate::graph::{Edge, EdgeAttrs, Graph, GraphView};
+    use crate::graph_overlay::OverlayGraph;
+
+    #[test]
+    fn test_overlay_graph() {
+        let base = Arc::new(Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap());
+        let mut overlay = OverlayGraph::new(base.clone());
+        assert_eq!(overlay.run_dijkstra(&[0]), base.run_dijkstra(&[0]));
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let node_id = overlay.add_node(48.675, 9.01);
+        for _ in 0..20 {
+            let src = rng.gen_range(0..base.num_nodes);
+            if let Some(edge) = base.get_outgoing_edges(src).choose(&mut rng) {
+                overlay.remove_edges(edge.src(), edge.tgt());
+            }
+            let tgt = rng.gen_range(0..base.num_nodes);
+            overlay.add_edge(Edge::new(src, tgt, rng.gen_range(1..100), EdgeAttrs::default()));
+        }
+        overlay.add_edge(Edge::new(node_id, 0, 1, EdgeAttrs::default()));
+        overlay.add_edge(Edge::new(0, node_id, 1, EdgeAttrs::default()));
+        overlay.isolate_nodes(&BTreeSet::from([1, 2]));
+
+        // Distances on the overlay are consistent with the modified edges, while the shared graph
+        // is untouched
+        let edges: Vec<_> = overlay.iter_edges().collect();
+        assert_eq!(edges.len(), overlay.num_edges());
+        assert!(edges.iter().all(|edge| ![1, 2].contains(&edge.src()) && ![1, 2].contains(&edge.tgt())));
+        for src in [0, node_id, 100] {
+            let dists = overlay.run_dijkstra(&[src]);
+            assert_eq!(dists[src], 0);
+            assert_eq!(dists[1], usize::MAX);
+            for edge in &edges {
+                assert!(dists[edge.src()] == usize::MAX || dists[edge.tgt()] <= dists[edge.src()] + edge.dist());
+            }
+            for (tgt, &dist) in dists.iter().enumerate() {
+                if tgt != src && dist < usize::MAX {
+                    assert!(edges.iter().any(|edge| edge.tgt() == tgt && dists[edge.src()] < usize::MAX
+                        && dists[edge.src()] + edge.dist() == dist));
+                }
+            }
+        }
+        assert_eq!(base.num_edges, 706);
+        assert!(base.get_node_degree(1) > 0);
+    }
+
+    #[test]
+    fn test_overlay_spatial_queries() {
+        let base = Arc::new(Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap());
+        let mut overlay = OverlayGraph::new(base.clone());
+        let gb = base.get_grid_bounds();
+        let node_id = overlay.add_node(gb.max_lat + 1.0, gb.max_lon + 1.0);
+
+        // Added nodes are found besides the nodes of the shared graph
+        assert_eq!(overlay.nearest_node(gb.max_lat + 1.0, gb.max_lon).id(), node_id);
+        assert_eq!(overlay.nearest_node(gb.min_lat, gb.min_lon).id(), base.nearest_node(gb.min_lat, gb.min_lon).id());
+        assert_eq!(overlay.nodes_in_bounds(&gb).count(), base.num_nodes);
+        assert_eq!(overlay.nodes_in_bounds(&overlay.get_grid_bounds()).count(), base.num_nodes + 1);
+    }
+}
@@ -0,0 +1,198 @@
+use serde::Serialize;
+
+use crate::graph::{Edge, Node};
+
+/// Maximum number of examples of every kind of issue in a validation report
+const MAX_EXAMPLES: usize = 20;
+/// Factor of the interquartile range of the coordinates beyond the quartiles from which nodes are
+/// coordinate outliers, i.e. Tukey's fences for far outliers
+const OUTLIER_FENCE_FACTOR: f64 = 3.0;
+/// Minimum interquartile range of the coordinates in degrees, such that nodes of small graphs are
+/// not outliers because they are a few hundred meters apart
+const MIN_COORDINATE_IQR: f64 = 0.01;
+
+/// Report of the issues of a graph that indicate a malformed graph file
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphValidation {
+    pub num_nodes: usize,
+    pub num_edges: usize,
+    /// Whether the graph has none of the issues below, except for asymmetric edges, which are
+    /// expected for the one-way roads of directed graphs
+    pub is_valid: bool,
+    /// Edges whose source or target is no node of the graph
+    pub dangling_edges: ValidationIssue<InvalidEdge>,
+    /// Edges with the same source and target as another edge that precedes them
+    pub duplicate_edges: ValidationIssue<InvalidEdge>,
+    /// Edges with a distance of `0`, along which the fire spreads within the same round
+    pub zero_dist_edges: ValidationIssue<InvalidEdge>,
+    /// Edges without a reverse edge of the same distance, which break undirected graphs
+    pub asymmetric_edges: ValidationIssue<InvalidEdge>,
+    /// Nodes with invalid coordinates or coordinates far away from those of most other nodes
+    pub coordinate_outliers: ValidationIssue<Node>,
+}
+
+/// Number of occurrences of an issue with the first occurrences as examples
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue<T> {
+    pub count: usize,
+    pub examples: Vec<T>,
+}
+
+impl<T> ValidationIssue<T> {
+    /// Create an issue from all its `occurrences`, of which at most `MAX_EXAMPLES` are kept
+    fn new(occurrences: impl Iterator<Item = T>) -> Self {
+        let mut count = 0;
+        let mut examples = Vec::new();
+        for occurrence in occurrences {
+            if examples.len() < MAX_EXAMPLES {
+                examples.push(occurrence);
+            }
+            count += 1;
+        }
+        Self { count, examples }
+    }
+}
+
+/// Edge that is subject of a validation issue with its index in the edges of the graph
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidEdge {
+    pub index: usize,
+    pub src: usize,
+    pub tgt: usize,
+    pub dist: usize,
+}
+
+impl InvalidEdge {
+    fn new(index: usize, edge: &Edge) -> Self {
+        Self {
+            index,
+            src: edge.src(),
+            tgt: edge.tgt(),
+            dist: edge.dist(),
+        }
+    }
+}
+
+/// Validate the nodes `nodes` and the edges `edges` of a graph, which may be in any order
+pub fn validate_graph(nodes: &[Node], edges: &[Edge]) -> GraphValidation {
+    let num_nodes = nodes.len();
+    let dangling_edges = ValidationIssue::new(edges.iter().enumerate()
+        .filter(|(_, edge)| edge.src() >= num_nodes || edge.tgt() >= num_nodes)
+        .map(|(i, edge)| InvalidEdge::new(i, edge)));
+
+    // Indices of all edges ordered by source, target and distance, and then by index
+    let mut sorted: Vec<_> = (0..edges.len()).collect();
+    sorted.sort_unstable_by_key(|&i| (edges[i].src(), edges[i].tgt(), edges[i].dist(), i));
+    let ends = |i: usize| (edges[i].src(), edges[i].tgt());
+    let mut duplicates: Vec<_> = sorted.windows(2)
+        .filter(|pair| ends(pair[0]) == ends(pair[1]))
+        .map(|pair| pair[1])
+        .collect();
+    duplicates.sort_unstable();
+    let duplicate_edges = ValidationIssue::new(duplicates.into_iter()
+        .map(|i| InvalidEdge::new(i, &edges[i])));
+
+    let zero_dist_edges = ValidationIssue::new(edges.iter().enumerate()
+        .filter(|(_, edge)| edge.dist() == 0)
+        .map(|(i, edge)| InvalidEdge::new(i, edge)));
+
+    let key = |i: usize| (edges[i].src(), edges[i].tgt(), edges[i].dist());
+    let has_reverse = |edge: &Edge| {
+        let reverse = (edge.tgt(), edge.src(), edge.dist());
+        let pos = sorted.partition_point(|&i| key(i) < reverse);
+        pos < sorted.len() && key(sorted[pos]) == reverse
+    };
+    let asymmetric_edges = ValidationIssue::new(edges.iter().enumerate()
+        .filter(|(_, edge)| edge.src() != edge.tgt() && !has_reverse(edge))
+        .map(|(i, edge)| InvalidEdge::new(i, edge)));
+
+    let coordinate_outliers = ValidationIssue::new(find_coordinate_outliers(nodes).into_iter()
+        .map(|node_id| nodes[node_id].clone()));
+
+    GraphValidation {
+        num_nodes,
+        num_edges: edges.len(),
+        is_valid: dangling_edges.count == 0 && duplicate_edges.count == 0
+            && zero_dist_edges.count == 0 && coordinate_outliers.count == 0,
+        dangling_edges,
+        duplicate_edges,
+        zero_dist_edges,
+        asymmetric_edges,
+        coordinate_outliers,
+    }
+}
+
+/// Find the ids of all nodes with coordinates that are no valid latitude and longitude, or that
+/// lie beyond the fences of the latitudes or longitudes of all nodes with valid coordinates
+fn find_coordinate_outliers(nodes: &[Node]) -> Vec<usize> {
+    let is_valid = |node: &Node| node.lat.is_finite() && node.lon.is_finite()
+        && node.lat.abs() <= 90.0 && node.lon.abs() <= 180.0;
+    let lat_fences = coordinate_fences(nodes.iter().filter(|node| is_valid(node)).map(|node| node.lat).collect());
+    let lon_fences = coordinate_fences(nodes.iter().filter(|node| is_valid(node)).map(|node| node.lon).collect());
+    let is_within = |value: f64, fences: Option<(f64, f64)>| match fences {
+        Some((low, high)) => value >= low && value <= high,
+        None => true
+    };
+
+    (0..nodes.len())
+        .filter(|&node_id| {
+            let node = &nodes[node_id];
+            !is_valid(node) || !is_within(node.lat, lat_fences) || !is_within(node.lon, lon_fences)
+        })
+        .collect()
+}
+
+/// Get the lower and upper fence of the coordinates `coords`, or `None` if there are none
+fn coordinate_fences(mut coords: Vec<f64>) -> Option<(f64, f64)> {
+    if coords.is_empty() {
+        return None;
+    }
+    coords.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    let quartile = |q: usize| coords[(coords.len() - 1) * q / 4];
+    let (q1, q3) = (quartile(1), quartile(3));
+    let iqr = (q3 - q1).max(MIN_COORDINATE_IQR);
+    Some((q1 - OUTLIER_FENCE_FACTOR * iqr, q3 + OUTLIER_FENCE_FACTOR * iqr))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::{Edge, EdgeAttrs, Graph, Node};
+    use crate::graph_validate::{validate_graph, InvalidEdge};
+
+    #[test]
+    fn test_validate_edges() {
+        let nodes: Vec<_> = (0..4).map(|id| Node::new(id, id as u64, 48.0, 9.0 + id as f64 * 1e-3)).collect();
+        // A two-way road 0 <-> 1, a duplicate of 0 -> 1, a one-way road 1 -> 2 of distance 0,
+        // a loop at 3 and an edge to the missing node 4
+        let edges: Vec<_> = [(0, 1, 5), (1, 0, 5), (0, 1, 7), (1, 2, 0), (3, 3, 1), (3, 4, 1)].iter()
+            .map(|&(src, tgt, dist)| Edge::new(src, tgt, dist, EdgeAttrs::default()))
+            .collect();
+
+        let validation = validate_graph(&nodes, &edges);
+        assert!(!validation.is_valid);
+        let indices = |examples: &[InvalidEdge]| examples.iter()
+            .map(|edge| edge.index)
+            .collect::<Vec<_>>();
+        assert_eq!(indices(&validation.dangling_edges.examples), vec![5]);
+        assert_eq!(indices(&validation.duplicate_edges.examples), vec![2]);
+        assert_eq!(indices(&validation.zero_dist_edges.examples), vec![3]);
+        assert_eq!(indices(&validation.asymmetric_edges.examples), vec![2, 3, 5]);
+        assert_eq!(validation.coordinate_outliers.count, 0);
+    }
+
+    #[test]
+    fn test_validate_coordinates() {
+        let mut nodes: Vec<_> = (0..10).map(|id| Node::new(id, id as u64, 48.7 + id as f64 * 1e-3, 9.1)).collect();
+        nodes.push(Node::new(10, 10, 0.0, 0.0));
+        nodes.push(Node::new(11, 11, 48.7, f64::NAN));
+        let validation = validate_graph(&nodes, &[]);
+        let outliers: Vec<_> = validation.coordinate_outliers.examples.iter().map(Node::id).collect();
+        assert_eq!(outliers, vec![10, 11]);
+
+        let graph = Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+        let validation = graph.validate();
+        assert_eq!(validation.num_edges, graph.num_edges);
+        assert_eq!(validation.dangling_edges.count, 0);
+        assert_eq!(validation.coordinate_outliers.count, 0);
+    }
+}
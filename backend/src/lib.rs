@@ -1,52 +1,204 @@
+pub mod alloc_stats;
+pub mod compute;
 pub mod graph;
+pub mod graph_ch;
+pub mod graph_coarsen;
+pub mod graph_components;
+pub mod graph_edits;
+pub mod graph_fmi;
+pub mod graph_geojson;
+pub mod graph_overlay;
+pub mod graph_validate;
 pub mod firefighter;
+pub(crate) mod base_layer;
 pub(crate) mod binary_minheap;
+pub(crate) mod bucket_queue;
+pub(crate) mod graph_binary;
+pub(crate) mod simplify;
+pub(crate) mod spatial_index;
 
 use std::collections::HashMap;
 use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 use std::fs;
+use std::io;
+use std::path::Path;
 use std::sync::Arc;
 
-use crate::graph::Graph;
+use crate::base_layer::BaseLayers;
+use crate::graph::formats::{self, GraphReader};
+use crate::graph::weights;
+use crate::graph::{Graph, ParseError};
 
-/// Load all available graphs from `graphs_path`.
+/// Load all available graphs from `graphs_path`, preferring their binary graph files on
+/// subsequent starts, see `load_graphs_with_cache`. Graph files are read in the format of their
+/// file extension, see `graph::formats`, along with the node weights from their sidecar files,
+/// see `graph::weights`. The contraction hierarchies and the coarsened
+/// versions of the graphs are built while loading them, and the base layers of their views are
+/// loaded or rendered.
 /// Returns an `OSMFResult` containing  a `HashMap` with entries that allow to access shared
 /// references to the graphs by their respective names if the operation succeeds, or an `Err`
 /// otherwise.
-pub fn load_graphs(graphs_path: &str) -> Result<HashMap<String, Arc<Graph>>, Box<dyn Error>> {
-    match fs::read_dir(graphs_path) {
-        Ok(paths) => {
-            // Collect names and paths of files containing graphs
-            let graph_data: Vec<_> = paths
-                .filter_map(|path| path.ok())
-                .filter(|path| path.path().to_str()
-                    .expect("Invalid unicode path")
-                    .ends_with(".fmi"))
-                .map(|graph_path| {
-                    let graph_name = graph_path.file_name().to_str().unwrap()
-                        .split(".fmi").next().unwrap().to_string();
-                    let graph_path = graph_path.path().to_str().unwrap().to_string();
-                    (graph_name, graph_path)
-                })
-                .collect();
-
-            // Parse and load graphs into a map
-            let mut graphs = HashMap::with_capacity(graph_data.len());
-            for (graph_name, graph_path) in graph_data {
-                match Graph::parse_from_file(&graph_path) {
-                    Ok(graph) => {
-                        log::info!("Parsed graph: {}", &graph_name);
-                        graphs.insert(graph_name, Arc::new(graph))
-                    }
-                    Err(err) => {
-                        log::warn!("Failed to parse graph: {}", &graph_name);
-                        return Err(err.into());
-                    }
-                };
-            }
-
-            Ok(graphs)
-        }
-        Err(err) => Err(err.into())
+pub fn load_graphs(graphs_path: &str) -> Result<HashMap<String, Arc<Graph>>, LoadGraphsError> {
+    load_graphs_with_cache(graphs_path, None)
+}
+
+/// Error while loading the graphs from a directory
+#[derive(Debug)]
+pub enum LoadGraphsError {
+    /// The directory of graph files cannot be read
+    IO(io::Error),
+    /// The graph file of the graph `graph_name` is malformed
+    Parse { graph_name: String, source: ParseError },
+    /// There is no graph file of the graph `graph_name`
+    NotFound { graph_name: String },
+}
+
+impl Display for LoadGraphsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IO(err) => write!(f, "{}", err),
+            Self::Parse { graph_name, source } => write!(f, "Failed to parse graph '{}': {}", graph_name, source),
+            Self::NotFound { graph_name } => write!(f, "No graph file of graph '{}'", graph_name),
+        }
+    }
+}
+
+impl Error for LoadGraphsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::IO(err) => Some(err),
+            Self::Parse { source, .. } => Some(source),
+            Self::NotFound { .. } => None,
+        }
+    }
+}
+
+/// Load the graph `graph_name` parsed from `graph_path` by memory-mapping its binary graph file
+/// in `cache_path`, along with the base layers of its views from its base layer file in
+/// `cache_path`. Missing or outdated files are written after parsing the graph or rendering its
+/// base layers. If the cache directory is read-only, the parsed graph and the rendered base layers
+/// are used.
+fn load_cached_graph(graph_name: &str, graph_path: &str, reader: &dyn GraphReader, cache_path: &Path)
+    -> Result<Graph, ParseError> {
+    let companions = reader.companion_paths(Path::new(graph_path));
+    let source = match graph_binary::SourceFile::open(Path::new(graph_path), &companions) {
+        Ok(source) => source,
+        Err(err) => {
+            log::warn!("Failed to read graph file {}: {}", graph_path, err);
+            return reader.read(graph_path);
+        }
+    };
+    let binary_path = cache_path.join(format!("{}.{}", graph_name, graph_binary::FILE_EXTENSION));
+    let graph = load_binary_graph(&binary_path, graph_path, reader, &source)?;
+
+    let base_layers_path = cache_path.join(format!("{}.{}", graph_name, base_layer::FILE_EXTENSION));
+    graph.set_base_layers(BaseLayers::load_or_render(&graph, &base_layers_path, &source));
+    Ok(graph)
+}
+
+/// Map the binary graph file at `binary_path` of the graph read from `graph_path` by `reader`,
+/// whose source file is `source`. If the binary graph file is missing or outdated, the graph is
+/// read and its binary graph file is written.
+fn load_binary_graph(binary_path: &Path, graph_path: &str, reader: &dyn GraphReader,
+                     source: &graph_binary::SourceFile) -> Result<Graph, ParseError> {
+    match graph_binary::map_file(binary_path, source) {
+        Ok(Some(graph)) => {
+            log::info!("Mapped binary graph: {}", binary_path.display());
+            return Ok(graph);
+        }
+        Ok(None) => log::info!("Binary graph is outdated: {}", binary_path.display()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => (),
+        Err(err) => log::warn!("Failed to map binary graph: {}", err)
+    }
+
+    let graph = reader.read(graph_path)?;
+    // Map the written file as well, such that this process shares the graph with all others
+    match graph_binary::write_file(&graph, binary_path, source)
+        .and_then(|_| graph_binary::map_file(binary_path, source)) {
+        Ok(Some(mapped)) => {
+            log::info!("Wrote binary graph: {}", binary_path.display());
+            Ok(mapped)
+        }
+        Ok(None) => Ok(graph),
+        Err(err) => {
+            log::warn!("Failed to write binary graph {}: {}", binary_path.display(), err);
+            Ok(graph)
+        }
+    }
+}
+
+/// Set the node weights of the graph `graph_name` to those read from its sidecar file in
+/// `graphs_path`, if it has any
+fn load_node_weights(graph: Graph, graphs_path: &Path, graph_name: &str) -> Result<Graph, ParseError> {
+    if let Some(sidecar_path) = weights::find_sidecar(graphs_path, graph_name) {
+        let node_weights = weights::read_sidecar(&graph, &sidecar_path)?;
+        log::info!("Loaded node weights: {} ({} records)", sidecar_path.display(), node_weights.summary().num_records);
+        graph.set_node_weights(node_weights);
+    }
+    Ok(graph)
+}
+
+/// Load all available graphs from `graphs_path` like `load_graphs`. Graphs are memory-mapped from
+/// binary graph files instead of being parsed, which are written next to the graph files on the
+/// first start unless `cache_path` is set. If multiple backend instances share the directory of
+/// binary graph files, they also share the memory of the graphs.
+pub fn load_graphs_with_cache(graphs_path: &str, cache_path: Option<&str>)
+    -> Result<HashMap<String, Arc<Graph>>, LoadGraphsError> {
+    // Parse and load graphs into a map
+    let graph_data = find_graph_files(graphs_path)?;
+    let mut graphs = HashMap::with_capacity(graph_data.len());
+    for (graph_name, graph_path, reader) in graph_data {
+        let graph = load_graph(graphs_path, &graph_name, &graph_path, reader, cache_path)?;
+        graphs.insert(graph_name, Arc::new(graph));
+    }
+
+    Ok(graphs)
+}
+
+/// Load the graph `graph_name` from `graphs_path` like `load_graphs_with_cache` loads all graphs,
+/// e.g. to reload a graph whose graph file was replaced while the backend is running
+pub fn load_graph_with_cache(graphs_path: &str, graph_name: &str, cache_path: Option<&str>)
+    -> Result<Graph, LoadGraphsError> {
+    let (_, graph_path, reader) = find_graph_files(graphs_path)?.into_iter()
+        .find(|(name, _, _)| name == graph_name)
+        .ok_or_else(|| LoadGraphsError::NotFound { graph_name: graph_name.to_string() })?;
+    load_graph(graphs_path, graph_name, &graph_path, reader, cache_path)
+}
+
+/// Collect the names and paths of the files containing graphs in `graphs_path` along with the
+/// readers of their formats
+fn find_graph_files(graphs_path: &str) -> Result<Vec<(String, String, &'static dyn GraphReader)>, LoadGraphsError> {
+    let graph_data = fs::read_dir(graphs_path).map_err(LoadGraphsError::IO)?
+        .filter_map(|path| path.ok())
+        .filter_map(|graph_path| {
+            let file_name = graph_path.file_name();
+            let (graph_name, reader) = formats::find_reader(file_name.to_str()?)?;
+            let graph_name = graph_name.to_string();
+            let graph_path = graph_path.path().to_str()?.to_string();
+            Some((graph_name, graph_path, reader))
+        })
+        .collect();
+    Ok(graph_data)
+}
+
+/// Parse and load the graph `graph_name` from `graph_path` along with its node weights
+fn load_graph(graphs_path: &str, graph_name: &str, graph_path: &str, reader: &dyn GraphReader,
+              cache_path: Option<&str>) -> Result<Graph, LoadGraphsError> {
+    let cache_path = Path::new(cache_path.unwrap_or(graphs_path));
+    match load_cached_graph(graph_name, graph_path, reader, cache_path)
+        .and_then(|graph| load_node_weights(graph, Path::new(graphs_path), graph_name)) {
+        Ok(graph) => {
+            let ch = graph.contraction_hierarchy();
+            let coarsened = graph.coarsened();
+            let lod_levels = graph.lod_levels();
+            log::info!("Loaded graph: {} ({} contraction hierarchy edges, {} coarsened nodes, {} levels of detail)",
+                       graph_name, ch.num_edges(), coarsened.graph().num_nodes, lod_levels.len());
+            Ok(graph)
+        }
+        Err(source) => {
+            log::warn!("Failed to parse graph {}: {}", graph_name, source);
+            Err(LoadGraphsError::Parse { graph_name: graph_name.to_string(), source })
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,25 +1,103 @@
 mod web_utils;
 
-use std::{collections::HashMap, env, fs, sync::{Arc, Mutex}};
+use std::{collections::HashMap, env, fs, sync::{Arc, RwLock}, time::{Duration, Instant}};
 
 use actix_cors::Cors;
-use actix_web::{App, get, http, HttpRequest, HttpResponse, HttpResponseBuilder, HttpServer, middleware::Logger, post, Responder, web};
+use actix_files::NamedFile;
+use actix_web::{App, delete, dev::ServerHandle, get, HttpRequest, HttpResponse, HttpResponseBuilder, HttpServer, middleware::Logger, patch, post, Responder, rt, web};
+use actix_web::http::{header, StatusCode};
+use geo::{HaversineDistance, Point};
 use log;
+use rayon::prelude::*;
 use serde::{Serialize, Deserialize};
-use serde_json::json;
+use serde_json::{json, Value};
 
-use osmff_lib::firefighter::problem::{OSMFProblem, OSMFSettings};
-use osmff_lib::firefighter::strategy::OSMFStrategy;
+use osmff_lib::compute::{self, JobProgress, RenderPriority, RenderQueue};
+use osmff_lib::firefighter::analysis::{self, NodeImportanceAnalysis};
+use osmff_lib::firefighter::audit::OSMFAuditLog;
+use osmff_lib::firefighter::basemap::TileCache;
+use osmff_lib::firefighter::charts;
+use osmff_lib::firefighter::plugin;
+use osmff_lib::firefighter::problem::{DEFAULT_EXPOSURE_RADIUS_M, DEFAULT_FIRE_SPREAD_M_PER_MIN, OSMFProblem, OSMFSettings, OSMFSettingsDefaults};
+use osmff_lib::firefighter::report::{PARETO_OBJECTIVES, StrategyReport};
+use osmff_lib::firefighter::strategy::{self, OSMFStrategy};
 use osmff_lib::firefighter::TimeUnit;
-use osmff_lib::graph::Graph;
+use osmff_lib::firefighter::view::{DEFAULT_MAX_ZOOM, MIN_ZOOM, NodeRadius, ViewLayers, ViewOptions};
+use osmff_lib::graph::{Graph, GraphView, GridBounds, Node};
+use osmff_lib::graph_edits::{EditedGraph, GraphEdits};
+use osmff_lib::graph_fmi::{self, Anonymization};
+use osmff_lib::graph_geojson::{self, GeoJsonGraph};
 
+use crate::web_utils::artifacts::{OSMFArtifactConfig, OSMFArtifactStore};
+use crate::web_utils::drain::OSMFDrain;
 use crate::web_utils::error::OSMFError;
+use crate::web_utils::history::{OSMFHistory, OSMFParameterBucket};
+use crate::web_utils::i18n;
+use crate::web_utils::jobs::OSMFJobStorage;
 use crate::web_utils::query::Query;
-use crate::web_utils::session::OSMFSessionStorage;
+use crate::web_utils::session::{OSMFJanitorConfig, OSMFSessionStorage};
 
 /// Path to configuration file
 const CONFIG_PATH: &str = "./config.json";
 
+/// Number of spread corridors that are reported if not specified otherwise
+const DEFAULT_NUM_CORRIDORS: usize = 5;
+
+/// Maximum number of re-simulations of a single counterfactual node importance analysis
+const MAX_IMPORTANCE_BUDGET: usize = 200;
+
+/// Number of history entries that are returned if not specified otherwise
+const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// Maximum number of simulations that can be compared at once
+const MAX_COMPARED_SIMULATIONS: usize = 20;
+
+/// Maximum number of simulations of a batch that are included in a strategy comparison report
+const MAX_REPORT_RUNS: usize = 10_000;
+
+/// Seed of the fire roots of the previews by which strategies without recorded simulations are
+/// ranked, if not specified otherwise. All previews of a recommendation share the same seed.
+const DEFAULT_RECOMMEND_SEED: u64 = 42;
+
+/// Minimum number of recorded simulations and maximum standard error of the mean saved ratio of
+/// strategies that are recommended with high and with medium confidence
+const HIGH_CONFIDENCE: (usize, f64) = (10, 0.02);
+const MEDIUM_CONFIDENCE: (usize, f64) = (3, 0.05);
+
+/// Default number of bins of edge weight histograms
+const DEFAULT_HISTOGRAM_BINS: usize = 20;
+
+/// Maximum number of bins of edge weight histograms
+const MAX_HISTOGRAM_BINS: usize = 1000;
+
+/// Default distance in meters by which nodes of downloaded graphs are moved at most when jittered
+const DEFAULT_JITTER_M: f64 = 100.0;
+
+/// Firefighter response cadences in minutes that strategy intervals are suggested for by default
+const DEFAULT_RESPONSE_MINUTES: [f64; 4] = [1.0, 5.0, 15.0, 30.0];
+
+/// Request headers that cross-origin requests may always use, in addition to the configured ones
+const DEFAULT_CORS_HEADERS: [&str; 4] = ["accept", "content-type", "authorization", "x-problem-id"];
+
+/// Maximum time in seconds to wait for running simulations and jobs when shutting down, if not
+/// specified otherwise
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 60;
+
+/// Time in milliseconds to wait for the next step of a running simulation, if not specified
+/// otherwise
+const DEFAULT_STEP_WAIT_MILLIS: u64 = 10_000;
+
+/// Maximum time in milliseconds to wait for the next step of a running simulation
+const MAX_STEP_WAIT_MILLIS: u64 = 60_000;
+
+/// Interval in which the published steps of a running simulation are polled while waiting
+const STEP_WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Origins of the Angular development server and of the frontend container of the
+/// docker-compose setup
+const DEVELOPMENT_CORS_ORIGINS: [&str; 4] = ["http://localhost:4200", "http://127.0.0.1:4200",
+                                             "http://localhost", "https://localhost"];
+
 /// Server and backend service configuration
 #[derive(Deserialize)]
 struct Config {
@@ -27,6 +105,186 @@ struct Config {
     port: u16,
     log_level: String,
     graphs_path: String,
+    /// Directory of binary graph files that are memory-mapped instead of parsing the graphs,
+    /// which may be shared read-only by multiple backend instances. By default, binary graph files
+    /// are written next to the graph files.
+    #[serde(default)]
+    graph_cache_path: Option<String>,
+    /// Directory of plugin libraries whose fire containment strategies are registered at
+    /// startup, which requires the `plugins` feature
+    #[serde(default)]
+    plugins_path: Option<String>,
+    /// Number of worker threads of the compute pool, `0` to use one thread per logical core
+    #[serde(default)]
+    compute_threads: usize,
+    /// Maximum number of worker threads of the parallel Dijkstras of strategies, `0` to use one
+    /// thread per logical core
+    #[serde(default)]
+    dijkstra_threads: usize,
+    /// Maximum number of views, exports and pre-generated frames that are rendered concurrently,
+    /// `0` to render one per logical core
+    #[serde(default)]
+    render_threads: usize,
+    /// Maximum number of threads of the parallel components of every simulation whose settings
+    /// do not specify one, unlimited if not set
+    #[serde(default)]
+    simulation_threads: Option<usize>,
+    /// Whether strategies log their large intermediate results, like distance maps, in full at
+    /// debug level instead of only their number of entries and first entries
+    #[serde(default)]
+    verbose_strategy_logs: bool,
+    /// Directory in which every simulation writes the full intermediate results of its strategy
+    /// to its own debug file, or `None` to write no debug files
+    #[serde(default)]
+    strategy_debug_path: Option<String>,
+    /// Path to the SQLite database of the simulation history catalog
+    #[serde(default = "default_history_path")]
+    history_path: String,
+    /// Plain HTTP URL of a raster tile server with placeholders `{z}`, `{x}` and `{y}` to draw
+    /// views over basemap tiles, or `None` to disable basemaps
+    #[serde(default)]
+    basemap_url: Option<String>,
+    /// Directory in which fetched basemap tiles are cached
+    #[serde(default = "default_basemap_cache_path")]
+    basemap_cache_path: String,
+    /// Cross-origin resource sharing settings
+    #[serde(default)]
+    cors: CorsConfig,
+    /// Maximum time in seconds to wait for running simulations and jobs when shutting down
+    #[serde(default = "default_shutdown_timeout_secs")]
+    shutdown_timeout_secs: u64,
+    /// Limits and defaults of rendered views
+    #[serde(default)]
+    view: ViewConfig,
+    /// Location and limits of the store of large artifacts like exports and reports
+    #[serde(default)]
+    artifacts: OSMFArtifactConfig,
+    /// Idle time and interval of the janitor that evicts the caches of idle simulations
+    #[serde(default)]
+    janitor: OSMFJanitorConfig,
+}
+
+/// Configuration of rendered views
+#[derive(Deserialize, Clone)]
+struct ViewConfig {
+    /// Maximum zoom of views
+    #[serde(default = "default_max_zoom")]
+    max_zoom: f64,
+    /// Radius of the circles that nodes are drawn as, unless overridden per request
+    #[serde(default)]
+    node_radius: NodeRadius,
+}
+
+impl Default for ViewConfig {
+    fn default() -> Self {
+        Self {
+            max_zoom: DEFAULT_MAX_ZOOM,
+            node_radius: NodeRadius::default(),
+        }
+    }
+}
+
+impl ViewConfig {
+    /// Check whether the zoom `zoom` is within the range allowed by this configuration
+    fn validate_zoom(&self, param: &str, zoom: f64) -> Result<(), OSMFError> {
+        if !(zoom.is_finite() && (MIN_ZOOM..=self.max_zoom).contains(&zoom)) {
+            return Err(OSMFError::BadRequest {
+                message: format!("Invalid value for parameter '{}': '{}', zoom must be between {} and {}",
+                                 param, zoom, MIN_ZOOM, self.max_zoom)
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Confidence in the expected outcome of a recommended strategy
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    /// Get the confidence in a mean saved ratio of `runs` recorded simulations with standard
+    /// error `standard_error`
+    fn for_history(runs: usize, standard_error: Option<f64>) -> Self {
+        let is_within = |(min_runs, max_error): (usize, f64)| runs >= min_runs
+            && standard_error.filter(|&error| error <= max_error).is_some();
+        if is_within(HIGH_CONFIDENCE) {
+            Self::High
+        } else if is_within(MEDIUM_CONFIDENCE) {
+            Self::Medium
+        } else {
+            Self::Low
+        }
+    }
+}
+
+/// Presets of origins that are allowed to make cross-origin requests
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum CorsPreset {
+    /// Allow the local development server and the local docker-compose frontend
+    #[default]
+    Development,
+    /// Allow only the explicitly configured origins
+    Production,
+}
+
+/// Cross-origin resource sharing configuration.
+/// Since requests are made with credentials, any origin must be allowed explicitly.
+#[derive(Deserialize, Default, Clone)]
+struct CorsConfig {
+    #[serde(default)]
+    preset: CorsPreset,
+    /// Origins that are allowed in addition to the origins of the preset
+    #[serde(default)]
+    allowed_origins: Vec<String>,
+    /// Request headers that are allowed in addition to the default headers
+    #[serde(default)]
+    allowed_headers: Vec<String>,
+}
+
+impl CorsConfig {
+    /// Build the CORS middleware for this configuration
+    fn build(&self) -> Cors {
+        let preset_origins: &[&str] = match self.preset {
+            CorsPreset::Development => &DEVELOPMENT_CORS_ORIGINS,
+            CorsPreset::Production => &[],
+        };
+        let origins = preset_origins.iter().copied()
+            .chain(self.allowed_origins.iter().map(String::as_str));
+        let headers = DEFAULT_CORS_HEADERS.iter().copied()
+            .chain(self.allowed_headers.iter().map(String::as_str));
+
+        origins.fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+            .allowed_methods(vec!["GET", "POST", "PATCH", "DELETE"])
+            .allowed_headers(headers)
+            .supports_credentials()
+            .max_age(3600)
+    }
+}
+
+/// Default path to the SQLite database of the simulation history catalog
+fn default_history_path() -> String {
+    "./history.sqlite".to_string()
+}
+
+/// Default directory in which fetched basemap tiles are cached
+fn default_basemap_cache_path() -> String {
+    "./tiles".to_string()
+}
+
+/// Default maximum time in seconds to wait for running simulations and jobs when shutting down
+fn default_shutdown_timeout_secs() -> u64 {
+    DEFAULT_SHUTDOWN_TIMEOUT_SECS
+}
+
+/// Default maximum zoom of views
+fn default_max_zoom() -> f64 {
+    DEFAULT_MAX_ZOOM
 }
 
 impl Config {
@@ -40,23 +298,154 @@ impl Config {
 
 /// Storage for data associated to the web app
 struct AppData {
-    sessions: Mutex<OSMFSessionStorage>,
-    graphs: HashMap<String, Arc<Graph>>,
+    sessions: OSMFSessionStorage,
+    /// Graphs by name, which grow by subgraphs extracted at runtime
+    graphs: RwLock<HashMap<String, Arc<Graph>>>,
+    defaults: RwLock<HashMap<String, OSMFSettingsDefaults>>,
+    pool: rayon::ThreadPool,
+    jobs: OSMFJobStorage,
+    history: OSMFHistory,
+    basemap: Option<Arc<TileCache>>,
+    render_queue: Arc<RenderQueue>,
+    artifacts: OSMFArtifactStore,
+    drain: Arc<OSMFDrain>,
+    view: ViewConfig,
+    /// Default thread limit of simulations, see `Config::simulation_threads`
+    simulation_threads: Option<usize>,
+    /// Directory of the graph files, from which graphs are reloaded
+    graphs_path: String,
+    /// Directory of the binary graph files, see `Config::graph_cache_path`
+    graph_cache_path: Option<String>,
+}
+
+impl AppData {
+    /// Get the graph with name `graph_name`, if there is such a graph
+    fn get_graph(&self, graph_name: &str) -> Option<Arc<Graph>> {
+        self.graphs.read().unwrap().get(graph_name).cloned()
+    }
+}
+
+/// Strategy ranked by its expected outcome in a scenario
+#[derive(Serialize)]
+struct StrategyRecommendation {
+    rank: usize,
+    strategy_name: String,
+    /// Whether the outcome is expected from recorded simulations in the same parameter bucket
+    /// (`history`) or estimated by a single preview on the coarsened graph (`preview`)
+    source: &'static str,
+    expected_saved_ratio: f64,
+    /// Number of recorded simulations the outcome is expected from, `0` for previews
+    runs: usize,
+    /// Standard error of the expected saved ratio. Missing for fewer than two recorded simulations.
+    standard_error: Option<f64>,
+    confidence: Confidence,
+    /// Mean simulation time of the recorded simulations. Missing for previews.
+    expected_simulation_time_millis: Option<f64>,
+}
+
+/// Cost estimation for a simulation with given settings
+#[derive(Serialize)]
+struct DryRunResponse {
+    graph_name: String,
+    num_nodes: usize,
+    num_edges: usize,
+    estimated_memory_bytes: usize,
+    /// Number of past simulations the runtime estimation is based on
+    num_past_runs: usize,
+    estimated_millis_per_node: Option<f64>,
+    estimated_runtime_millis: Option<f64>,
 }
 
 #[derive(Serialize)]
 struct GraphData {
     name: String,
-    num_of_nodes: usize
+    num_of_nodes: usize,
+    /// Number of weakly connected components, i.e. of islands that fires cannot cross
+    num_components: usize,
+    num_strong_components: usize,
+    /// Number of nodes of the largest weakly connected component
+    largest_component_nodes: usize,
+}
+
+impl GraphData {
+    /// Describe the graph `graph` with name `name`
+    fn new(name: &str, graph: &Graph) -> Self {
+        let components = graph.weak_components();
+        Self {
+            name: name.to_string(),
+            num_of_nodes: graph.num_nodes,
+            num_components: components.num_components(),
+            num_strong_components: graph.strong_components().num_components(),
+            largest_component_nodes: components.largest().map_or(0, |largest| components.size(largest)),
+        }
+    }
+}
+
+/// Notes and tags to attach to a simulation in the history catalog
+#[derive(Deserialize)]
+struct SimulationAnnotation {
+    notes: Option<String>,
+    tags: Option<Vec<String>>,
+}
+
+/// A graph node matching a search query
+#[derive(Serialize)]
+struct NodeSearchResult {
+    id: usize,
+    osm_id: u64,
+    lat: f64,
+    lon: f64,
+    degree: usize,
+    matched_by: &'static str,
+}
+
+impl NodeSearchResult {
+    /// Create a new search result for `node` of `graph`
+    fn new(graph: &Graph, node: &Node, matched_by: &'static str) -> Self {
+        Self {
+            id: node.id(),
+            osm_id: node.osm_id,
+            lat: node.lat,
+            lon: node.lon,
+            degree: graph.get_node_degree(node.id()),
+            matched_by,
+        }
+    }
+}
+
+/// Graph node that is closest to the requested coordinates
+#[derive(Serialize)]
+struct NearestNode {
+    id: usize,
+    osm_id: u64,
+    lat: f64,
+    lon: f64,
+    /// Distance in meters between the node and the requested coordinates
+    distance_m: f64,
+}
+
+/// Request to extract the subgraph within a bounding box as new graph
+#[derive(Deserialize)]
+struct SubgraphRequest {
+    /// Name of the new graph
+    name: String,
+    #[serde(flatten)]
+    bounds: GridBounds,
+}
+
+/// Compute the suggested settings for each graph in `graphs`
+fn compute_defaults(graphs: &HashMap<String, Arc<Graph>>) -> HashMap<String, OSMFSettingsDefaults> {
+    graphs.iter()
+        .map(|(graph_name, graph)| (graph_name.clone(), OSMFSettingsDefaults::for_graph(&**graph)))
+        .collect()
 }
 
 /// Common function to initialize a `HttpResponseBuilder` for an incoming `HttpRequest`.
 /// This function must be called before retrieving session data.
 fn init_response(data: &web::Data<AppData>, req: &HttpRequest, mut res: HttpResponseBuilder) -> (HttpResponseBuilder, String) {
-    let mut sessions = data.sessions.lock().unwrap();
     let sid = match req.cookie("sid") {
         Some(cur_cookie) => {
-            if let Some(new_cookie) = sessions.refresh_session(cur_cookie.value()) {
+            if let Some(new_cookie) = data.sessions.refresh_session(cur_cookie.value()) {
                 let sid = new_cookie.value().to_string();
                 res.cookie(new_cookie);
                 sid
@@ -65,7 +454,7 @@ fn init_response(data: &web::Data<AppData>, req: &HttpRequest, mut res: HttpResp
             }
         }
         None => {
-            let new_cookie = sessions.open_session();
+            let new_cookie = data.sessions.open_session();
             let sid = new_cookie.value().to_string();
             res.cookie(new_cookie);
             sid
@@ -87,12 +476,350 @@ async fn ping(data: web::Data<AppData>, req: HttpRequest) -> impl Responder {
 async fn list_graphs(data: web::Data<AppData>, req: HttpRequest) -> impl Responder {
     let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
     res.json(json!(
-        data.graphs.iter()
-        .map(|(graph_name, graph)| GraphData{name: graph_name.clone(), num_of_nodes: graph.num_nodes})
+        data.graphs.read().unwrap().iter()
+        .map(|(graph_name, graph)| GraphData::new(graph_name, graph))
         .collect::<Vec<_>>()
     ))
 }
 
+/// Get suggested simulation settings for the graph with name `name`
+#[get("/graphs/{name}/defaults")]
+async fn get_graph_defaults(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    match data.defaults.read().unwrap().get(graph_name.as_str()) {
+        Some(defaults) => Ok(res.json(defaults)),
+        None => Err(OSMFError::BadRequest {
+            message: format!("Unknown graph: '{}'", graph_name)
+        })
+    }
+}
+
+/// Get the size and the metadata of the graph with name `name`, e.g. its source, license and the
+/// attribution required for derived images and data, and a summary of its node weights
+#[get("/graphs/{name}/info")]
+async fn get_graph_info(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let graph = data.get_graph(&graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
+
+    Ok(res.json(json!({
+        "name": graph_name.as_str(),
+        "num_nodes": graph.num_nodes,
+        "num_edges": graph.num_edges,
+        "metadata": graph.metadata(),
+        "node_weights": graph.node_weights().map(|weights| weights.summary()),
+    })))
+}
+
+/// Validate the graph with name `name`, i.e. report dangling, duplicate, zero-distance and
+/// asymmetric edges and nodes with outlying coordinates, which indicate a malformed graph file
+#[get("/graphs/{name}/validate")]
+async fn validate_graph(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let graph = data.get_graph(&graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
+
+    // Validate the graph on a blocking thread, such that it does not stall the actix worker
+    // while waiting for the compute pool
+    let pool_data = data.clone();
+    let validation = web::block(move || pool_data.pool.install(|| graph.validate())).await?;
+    Ok(res.json(validation))
+}
+
+/// Get the node of the graph with name `name` that is closest to the coordinates `lat`/`lon`,
+/// e.g. to pick fire roots or defenses by clicking the map
+#[get("/graphs/{name}/nearest")]
+async fn get_nearest_node(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let graph = data.get_graph(&graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
+
+    let query = Query::from(req.query_string());
+    let lat = query.get_and_parse::<f64>("lat")?;
+    let lon = query.get_and_parse::<f64>("lon")?;
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return Err(OSMFError::BadRequest {
+            message: format!("Invalid coordinates: {}, {}", lat, lon)
+        });
+    }
+
+    let node = graph.nearest_node(lat, lon);
+    Ok(res.json(NearestNode {
+        id: node.id(),
+        osm_id: node.osm_id,
+        lat: node.lat,
+        lon: node.lon,
+        distance_m: Point::new(lon, lat).haversine_distance(&Point::new(node.lon, node.lat)),
+    }))
+}
+
+/// Extract the nodes and edges of the graph with name `name` within a bounding box, and register
+/// them as a new graph for all sessions, e.g. to simulate a single district of a large city
+#[post("/graphs/{name}/subgraph")]
+async fn extract_subgraph(data: web::Data<AppData>, graph_name: web::Path<String>, body: web::Json<SubgraphRequest>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Created());
+    let SubgraphRequest { name, bounds } = body.into_inner();
+
+    let graph = data.get_graph(&graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
+    let is_valid_name = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !is_valid_name {
+        return Err(OSMFError::BadRequest {
+            message: format!("Invalid graph name: '{}'", name)
+        });
+    }
+    let is_valid_bounds = [bounds.min_lat, bounds.max_lat, bounds.min_lon, bounds.max_lon].iter()
+        .all(|coord| coord.is_finite())
+        && bounds.min_lat <= bounds.max_lat && bounds.min_lon <= bounds.max_lon;
+    if !is_valid_bounds {
+        return Err(OSMFError::BadRequest {
+            message: "Invalid bounding box".to_string()
+        });
+    }
+
+    // Extract the subgraph on a blocking thread, such that it does not stall the actix worker
+    // while waiting for the compute pool
+    let pool_data = data.clone();
+    let subgraph = web::block(move || pool_data.pool.install(|| graph.subgraph(&bounds))).await?;
+    if subgraph.num_nodes == 0 {
+        return Err(OSMFError::BadRequest {
+            message: "No nodes within the bounding box".to_string()
+        });
+    }
+    let (num_nodes, num_edges) = (subgraph.num_nodes, subgraph.num_edges);
+    let defaults = OSMFSettingsDefaults::for_graph(&subgraph);
+
+    // Check and insert under the same lock, such that concurrent requests cannot both register
+    // a graph with the same name
+    let mut graphs = data.graphs.write().unwrap();
+    if graphs.contains_key(&name) {
+        return Err(OSMFError::BadRequest {
+            message: format!("Graph already exists: '{}'", name)
+        });
+    }
+    data.defaults.write().unwrap().insert(name.clone(), defaults);
+    graphs.insert(name.clone(), Arc::new(subgraph));
+    drop(graphs);
+    log::info!("Extracted subgraph {} of graph {} with {} nodes", name, graph_name, num_nodes);
+
+    Ok(res.json(json!({
+        "name": name,
+        "num_nodes": num_nodes,
+        "num_edges": num_edges,
+    })))
+}
+
+/// Fail if simulations are running on the graph with name `graph_name` in any session, unless
+/// `force=true` is given in `query`. Running simulations keep the graph they were started on.
+/// Callers must check again while holding the write lock of the graphs before replacing or
+/// removing the graph, since simulations may have been started in between. Simulations that
+/// looked up the graph, but are not registered as running yet, are not counted and keep the
+/// previous graph as well.
+fn check_graph_unused(data: &AppData, graph_name: &str, query: &Query) -> Result<(), OSMFError> {
+    let force = query.try_get_and_parse::<bool>("force").transpose()?.unwrap_or(false);
+    let num_running = data.sessions.count_running_simulations(graph_name);
+    if num_running > 0 && !force {
+        return Err(OSMFError::Conflict {
+            message: format!("Graph '{}' is used by {} running simulations", graph_name, num_running)
+        });
+    }
+    Ok(())
+}
+
+/// Remove the graph with name `name` from the server, such that no new simulations can be started
+/// on it. The graph file is kept, so the graph is loaded again on the next start. Fails while
+/// simulations are running on the graph unless `force=true`. Edits of the graph are discarded in
+/// all sessions.
+#[delete("/graphs/{name}")]
+async fn delete_graph(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::NoContent());
+
+    let query = Query::from(req.query_string());
+    let mut graphs = data.graphs.write().unwrap();
+    if !graphs.contains_key(graph_name.as_str()) {
+        return Err(OSMFError::NotFound {
+            message: format!("Unknown graph: '{}'", graph_name)
+        });
+    }
+    // No simulations can look up the graph while the write lock is held
+    check_graph_unused(&data, &graph_name, &query)?;
+
+    graphs.remove(graph_name.as_str());
+    data.defaults.write().unwrap().remove(graph_name.as_str());
+    drop(graphs);
+    let num_edited = data.sessions.discard_all_graph_edits(&graph_name);
+    log::info!("Deleted graph {}, discarded its edits in {} sessions", graph_name, num_edited);
+
+    Ok(res.finish())
+}
+
+/// Load the graph with name `name` again from its graph file, e.g. after the file has been
+/// replaced with a newer dataset, or load a graph whose file has been added since the start.
+/// Fails while simulations are running on the graph unless `force=true`. Edits of the graph are
+/// discarded in all sessions, since they refer to the nodes of the previous graph.
+#[post("/graphs/{name}/reload")]
+async fn reload_graph(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    check_graph_unused(&data, &graph_name, &query)?;
+
+    // Load the graph on a blocking thread, such that it does not stall the actix worker while
+    // waiting for the compute pool
+    let (load_data, name) = (data.clone(), graph_name.to_string());
+    let graph = web::block(move || load_data.pool.install(|| osmff_lib::load_graph_with_cache(
+        &load_data.graphs_path, &name, load_data.graph_cache_path.as_deref())))
+        .await??;
+    let (num_nodes, num_edges) = (graph.num_nodes, graph.num_edges);
+    let defaults = OSMFSettingsDefaults::for_graph(&graph);
+
+    // Simulations may have been started on the graph while it was loaded
+    let mut graphs = data.graphs.write().unwrap();
+    check_graph_unused(&data, &graph_name, &query)?;
+    data.defaults.write().unwrap().insert(graph_name.to_string(), defaults);
+    graphs.insert(graph_name.to_string(), Arc::new(graph));
+    drop(graphs);
+    let num_edited = data.sessions.discard_all_graph_edits(&graph_name);
+    log::info!("Reloaded graph {} with {} nodes, discarded its edits in {} sessions",
+               graph_name, num_nodes, num_edited);
+
+    Ok(res.json(json!({
+        "name": graph_name.as_str(),
+        "num_nodes": num_nodes,
+        "num_edges": num_edges,
+    })))
+}
+
+/// Get the nodes and edges of the graph with name `name` as GeoJSON `FeatureCollection`, e.g. to
+/// inspect the graph in QGIS or Leaflet
+#[get("/graphs/{name}/geojson")]
+async fn get_graph_geojson(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let graph = data.get_graph(&graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
+
+    let body = serde_json::to_vec(&GeoJsonGraph::new(&*graph)).map_err(|err| OSMFError::Internal {
+        message: format!("Failed to serialize graph: {}", err)
+    })?;
+    Ok(res.content_type(graph_geojson::MEDIA_TYPE).body(body))
+}
+
+/// Download the graph with name `name` as graph file in the FMI text format. With
+/// `anonymize=strip` or `anonymize=jitter`, coordinates are removed or moved randomly by up to
+/// `jitter` meters (100 by default) using the seed `seed`, and OSM ids and the header are removed,
+/// while the topology and the edge weights are preserved.
+#[get("/graphs/{name}/download")]
+async fn download_graph(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let graph = data.get_graph(&graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
+
+    let query = Query::from(req.query_string());
+    let anonymization = match query.try_get("anonymize") {
+        None => None,
+        Some("strip") => Some(Anonymization::Strip),
+        Some("jitter") => {
+            let radius_m = query.try_get_and_parse::<f64>("jitter").transpose()?
+                .unwrap_or(DEFAULT_JITTER_M);
+            if !(radius_m.is_finite() && radius_m > 0.0) {
+                return Err(OSMFError::BadRequest {
+                    message: format!("Invalid value for parameter 'jitter': '{}'", radius_m)
+                });
+            }
+            let seed = query.try_get_and_parse::<u64>("seed").transpose()?.unwrap_or(0);
+            Some(Anonymization::Jitter { radius_m, seed })
+        }
+        Some(mode) => {
+            return Err(OSMFError::BadRequest {
+                message: format!("Invalid value for parameter 'anonymize': '{}'", mode)
+            });
+        }
+    };
+
+    let mut body = Vec::new();
+    graph_fmi::write_fmi(&*graph, &mut body, anonymization.as_ref()).map_err(|err| OSMFError::Internal {
+        message: format!("Failed to write graph: {}", err)
+    })?;
+    let file_name = match anonymization {
+        Some(_) => format!("{}_anonymized.fmi", graph_name),
+        None => format!("{}.fmi", graph_name)
+    };
+    res.insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)));
+    Ok(res.content_type(graph_fmi::MEDIA_TYPE).body(body))
+}
+
+/// Get the edge weight histogram of the graph with name `name` and suggest strategy intervals for
+/// the given comma separated firefighter response cadences in minutes at the given fire spread rate
+#[get("/graphs/{name}/calibration")]
+async fn get_spread_calibration(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let graph = data.get_graph(&graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
+
+    let query = Query::from(req.query_string());
+    let num_bins = query.try_get_and_parse::<usize>("bins").transpose()?.unwrap_or(DEFAULT_HISTOGRAM_BINS);
+    if num_bins == 0 || num_bins > MAX_HISTOGRAM_BINS {
+        return Err(OSMFError::BadRequest {
+            message: format!("Invalid value for parameter 'bins': '{}'", num_bins)
+        });
+    }
+    let fire_spread = query.try_get_and_parse::<f64>("fire_spread").transpose()?
+        .unwrap_or(DEFAULT_FIRE_SPREAD_M_PER_MIN);
+    if !(fire_spread.is_finite() && fire_spread > 0.0) {
+        return Err(OSMFError::BadRequest {
+            message: format!("Invalid value for parameter 'fire_spread': '{}'", fire_spread)
+        });
+    }
+    let response_minutes = match query.try_get("response_minutes") {
+        Some(param) => param.split(',')
+            .map(|minutes| minutes.trim().parse::<f64>().ok().filter(|m| m.is_finite() && *m > 0.0))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| OSMFError::BadRequest {
+                message: format!("Invalid value for parameter 'response_minutes': '{}'", param)
+            })?,
+        None => DEFAULT_RESPONSE_MINUTES.to_vec()
+    };
+
+    Ok(res.json(analysis::calibrate_spread(&*graph, num_bins, fire_spread, &response_minutes)))
+}
+
+/// Get the long-run statistics of all strategies on the graph with name `name` from the history
+/// catalog, i.e. their mean saved ratio, its variance and their mean runtime per bucket of the
+/// numbers of fire roots and firefighters, optionally restricted to the buckets of `num_roots`
+/// and `num_ffs`
+#[get("/graphs/{name}/strategy-stats")]
+async fn get_strategy_stats(data: web::Data<AppData>, graph_name: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    if data.get_graph(&graph_name).is_none() {
+        return Err(OSMFError::BadRequest {
+            message: format!("Unknown graph: '{}'", graph_name)
+        });
+    }
+
+    let query = Query::from(req.query_string());
+    let num_roots = query.try_get_and_parse::<usize>("num_roots").transpose()?;
+    let num_ffs = query.try_get_and_parse::<usize>("num_ffs").transpose()?;
+    let stats = data.history.strategy_stats(&graph_name, num_roots, num_ffs)?;
+
+    Ok(res.json(stats))
+}
+
 /// List all available firefighter containment strategies
 #[get("/strategies")]
 async fn list_strategies(data: web::Data<AppData>, req: HttpRequest) -> impl Responder {
@@ -100,155 +827,2894 @@ async fn list_strategies(data: web::Data<AppData>, req: HttpRequest) -> impl Res
     res.json(json!(OSMFStrategy::available_strategies()))
 }
 
-/// Simulate a new firefighter problem instance
-#[post("/simulate")]
-async fn simulate_problem(data: web::Data<AppData>, settings: web::Json<OSMFSettings>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
-    let (mut res, sid) = init_response(&data, &req, HttpResponse::Created());
+/// Search the nodes of a graph by coordinates (`lat,lon`), node id or OSM id
+#[get("/search")]
+async fn search_nodes(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
 
-    let graph = match data.graphs.get(&settings.graph_name) {
+    let query = Query::from(req.query_string());
+    let graph_name = query.get("graph")?;
+    let graph = match data.get_graph(graph_name) {
         Some(graph) => graph,
         None => {
-            log::warn!("Unknown graph {}", settings.graph_name);
+            log::warn!("Unknown graph {}", graph_name);
             return Err(OSMFError::BadRequest {
-                message: format!("Unknown value for parameter 'graph': '{}'", settings.graph_name)
+                message: format!("Unknown value for parameter 'graph': '{}'", graph_name)
             });
         }
     };
+    let q = query.get("q")?.trim();
+    let invalid_q = || OSMFError::BadRequest {
+        message: format!("Invalid value for parameter 'q': '{}'", q)
+    };
+
+    let mut results = Vec::new();
+    if let Some((lat, lon)) = q.split_once(',') {
+        let lat = lat.trim().parse::<f64>().map_err(|_| invalid_q())?;
+        let lon = lon.trim().parse::<f64>().map_err(|_| invalid_q())?;
+        results.push(NodeSearchResult::new(&graph, graph.nearest_node(lat, lon), "coordinates"));
+    } else {
+        let id = q.parse::<u64>().map_err(|_| invalid_q())?;
+        if id < graph.num_nodes as u64 {
+            results.push(NodeSearchResult::new(&graph, graph.get_node(id as usize), "id"));
+        }
+        if let Some(node) = graph.get_node_by_osm_id(id) {
+            results.push(NodeSearchResult::new(&graph, node, "osm_id"));
+        }
+    }
+
+    Ok(res.json(results))
+}
+
+/// Predict the unimpeded burn time of every node of a graph for a fire spreading from the given
+/// comma separated list of root node ids, without running a simulation
+#[get("/predict")]
+async fn predict_burn_times(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
 
-    let strategy = match OSMFStrategy::from_name_and_graph(&settings.strategy_name, graph.clone()) {
-        Some(s) => s,
+    let query = Query::from(req.query_string());
+    let graph_name = query.get("graph")?;
+    let graph = match data.get_graph(graph_name) {
+        Some(graph) => graph,
         None => {
-            log::warn!("Unknown strategy {}", settings.strategy_name);
+            log::warn!("Unknown graph {}", graph_name);
             return Err(OSMFError::BadRequest {
-                message: format!("Unknown value for parameter 'strategy': '{}'", settings.strategy_name)
+                message: format!("Unknown value for parameter 'graph': '{}'", graph_name)
             });
         }
     };
 
-    let mut problem = match OSMFProblem::new(graph.clone(), settings.into_inner(), strategy) {
-        Ok(problem) => problem,
-        Err(err) => {
-            return Err(err.into());
-        }
-    };
-    problem.simulate();
-
-    let res = res.json(problem.simulation_response());
-
-    {
-        let mut sessions = data.sessions.lock().unwrap();
-        let session = sessions.get_mut_session(&sid).unwrap();
-        session.attach_problem(problem);
+    let roots_param = query.get("roots")?;
+    let roots = roots_param.split(',')
+        .map(|root| root.trim().parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| OSMFError::BadRequest {
+            message: format!("Invalid value for parameter 'roots': '{}'", roots_param)
+        })?;
+    if let Some(root) = roots.iter().find(|&&root| root >= graph.num_nodes) {
+        return Err(OSMFError::BadRequest {
+            message: format!("Unknown node: '{}'", root)
+        });
     }
 
-    Ok(res)
+    Ok(res.json(analysis::predict_burn_times(&*graph, &roots)))
 }
 
-/// Display the view of a firefighter simulation
-#[get("/view")]
-async fn display_view(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
-    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
-
-    let mut sessions = data.sessions.lock().unwrap();
-    let session = sessions.get_mut_session(&sid).unwrap();
-    let problem = match session.get_mut_problem() {
-        Some(problem) => problem,
+/// Get the graph selected in `settings`, and fail if the graph or the selected strategy is unknown
+fn get_settings_graph(data: &AppData, settings: &OSMFSettings) -> Result<Arc<Graph>, OSMFError> {
+    let graph = match data.get_graph(&settings.graph_name) {
+        Some(graph) => graph,
         None => {
-            return Err(OSMFError::NoSimulation {
-                message: "No simulation has been started yet".to_string()
+            log::warn!("Unknown graph {}", settings.graph_name);
+            return Err(OSMFError::BadRequest {
+                message: format!("Unknown value for parameter 'graph': '{}'", settings.graph_name)
             });
         }
     };
 
-    let query = Query::from(req.query_string());
-    let center_lat = query.try_get_and_parse::<f64>("clat");
-    let center_lon = query.try_get_and_parse::<f64>("clon");
-    let zoom = query.get_and_parse::<f64>("zoom")?;
-    let time = query.get_and_parse::<TimeUnit>("time")?;
+    if !OSMFStrategy::available_strategies().contains(&settings.strategy_name) {
+        log::warn!("Unknown strategy {}", settings.strategy_name);
+        return Err(OSMFError::BadRequest {
+            message: format!("Unknown value for parameter 'strategy': '{}'", settings.strategy_name)
+        });
+    }
 
-    if center_lat.is_some() && center_lon.is_some() {
-        let center = (center_lat.unwrap()?, center_lon.unwrap()?);
+    Ok(graph)
+}
 
-        log::debug!("Computing view for center: {:?}, zoom: {} and time: {}", center, zoom, time);
+/// Get the graph that simulations with settings `settings` run on in the session with id `sid`,
+/// i.e. the edited graph if the session has edited the selected graph `graph`, or `graph` itself
+/// otherwise. Removed nodes of an edited graph are excluded from the fire roots.
+fn get_session_graph(data: &AppData, sid: &str, graph: &Arc<Graph>, settings: &mut OSMFSettings) -> Arc<dyn GraphView> {
+    let session = data.sessions.get_session(sid).unwrap();
+    let session = session.read().unwrap();
+    match session.get_edited_graph(&settings.graph_name) {
+        Some(edited) => {
+            settings.excluded_nodes.extend(edited.removed_nodes());
+            edited.graph().clone()
+        }
+        None => graph.clone()
+    }
+}
 
-        Ok(res.content_type("image/png")
-            .body(problem.view_response(center, zoom, &time)))
-    } else {
-        log::debug!("Computing view for zoom: {} and time: {}", zoom, &time);
+/// Request to edit a graph of a session
+#[derive(Deserialize)]
+struct GraphEditRequest {
+    graph_name: String,
+    #[serde(flatten)]
+    edits: GraphEdits,
+}
 
-        Ok(res.content_type("image/png")
-            .body(problem.view_response_alt(zoom, &time)))
-    }
+/// Build the response describing the edited graph `edited` with base graph `graph_name`
+fn graph_edits_response(graph_name: &str, edited: &EditedGraph) -> Value {
+    json!({
+        "graph_name": graph_name,
+        "num_nodes": edited.graph().num_nodes(),
+        "num_edges": edited.graph().num_edges(),
+        "removed_nodes": edited.removed_nodes(),
+        "edits": edited.edits(),
+    })
 }
 
-/// Get the metadata for a specific step of a firefighter simulation
-#[get("/stepmeta")]
-async fn get_sim_step_metadata(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+/// Edit a graph for all following simulations of the session, e.g. by adding planned roads or
+/// removing demolished bridges. The shared graph is not modified. Responds with the ids of the
+/// added nodes and all edits of the graph so far.
+#[post("/graph/edits")]
+async fn edit_graph(data: web::Data<AppData>, body: web::Json<GraphEditRequest>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
     let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+    let GraphEditRequest { graph_name, edits } = body.into_inner();
 
-    let mut sessions = data.sessions.lock().unwrap();
-    let session = sessions.get_session(&sid).unwrap();
-    let problem = match session.get_problem() {
-        Some(problem) => problem,
-        None => {
-            return Err(OSMFError::NoSimulation {
-                message: "No simulation has been started yet".to_string()
-            });
-        }
-    };
+    let base = data.get_graph(&graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
 
-    let query = Query::from(req.query_string());
-    let time = query.get_and_parse::<TimeUnit>("time")?;
+    let session = data.sessions.get_session(&sid).unwrap();
+    let mut session = session.write().unwrap();
+    let edited = session.edit_graph(&graph_name, &base);
+    let added_node_ids = edited.apply(edits)?;
 
-    Ok(res.json(problem.sim_step_metadata_response(&time)))
+    let mut edits = graph_edits_response(&graph_name, edited);
+    edits["added_node_ids"] = json!(added_node_ids);
+    Ok(res.json(edits))
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    // Parse config file
-    let config = Config::from_file(CONFIG_PATH);
+/// Get all edits of a graph in the session
+#[get("/graph/edits")]
+async fn get_graph_edits(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
 
-    // Initialize logger
-    env::set_var("RUST_LOG", &config.log_level);
-    env::set_var("RUST_BACKTRACE", "1");
-    env_logger::init();
+    let query = Query::from(req.query_string());
+    let graph_name = query.get("graph")?;
+    let base = data.get_graph(graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
 
-    // Initialize graphs
-    let graphs = match osmff_lib::load_graphs(&config.graphs_path) {
-        Ok(graphs) => graphs,
-        Err(err) => {
-            panic!("Failed to load graphs: {}", err.to_string());
-        }
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let edits = match session.get_edited_graph(graph_name) {
+        Some(edited) => graph_edits_response(graph_name, edited),
+        None => graph_edits_response(graph_name, &EditedGraph::new(base.clone()))
     };
+    Ok(res.json(edits))
+}
+
+/// Discard all edits of a graph in the session, such that following simulations run on the
+/// shared graph again
+#[delete("/graph/edits")]
+async fn discard_graph_edits(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::NoContent());
+
+    let query = Query::from(req.query_string());
+    let graph_name = query.get("graph")?;
+    if data.get_graph(graph_name).is_none() {
+        return Err(OSMFError::BadRequest {
+            message: format!("Unknown graph: '{}'", graph_name)
+        });
+    }
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    session.write().unwrap().discard_graph_edits(graph_name);
+    Ok(res.finish())
+}
+
+/// Simulate a new firefighter problem instance. With `audit=true`, the rule evaluation behind
+/// every ignition is recorded in an audit log. With `preview=true`, the outcome is only estimated
+/// on the coarsened graph, see `simulate_preview`.
+#[post("/simulate")]
+async fn simulate_problem(data: web::Data<AppData>, settings: web::Json<OSMFSettings>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let query = Query::from(req.query_string());
+    if query.try_get_and_parse::<bool>("preview").transpose()?.unwrap_or(false) {
+        return simulate_preview(&data, settings.into_inner(), &req).await;
+    }
+    let asynchronous = query.try_get_and_parse::<bool>("async").transpose()?.unwrap_or(false);
+    let audit = query.try_get_and_parse::<bool>("audit").transpose()?.unwrap_or(false);
+    let (mut res, sid) = if asynchronous {
+        init_response(&data, &req, HttpResponse::Accepted())
+    } else {
+        init_response(&data, &req, HttpResponse::Created())
+    };
+    let task = data.drain.start_task()?;
+
+    let mut settings = settings.into_inner();
+    let graph = get_settings_graph(&data, &settings)?;
+    let graph = get_session_graph(&data, &sid, &graph, &mut settings);
+    let strategy = OSMFStrategy::from_name_and_graph(&settings.strategy_name, graph.clone())
+        .expect("Strategy should be known after validation");
+    if settings.threads.is_none() {
+        settings.threads = data.simulation_threads;
+    }
+
+    let pregen_zoom = settings.pregen_zoom;
+    if let Some(zoom) = pregen_zoom {
+        data.view.validate_zoom("pregen_zoom", zoom)?;
+    }
+    let mut problem = match OSMFProblem::new(graph, settings, strategy) {
+        Ok(problem) => problem,
+        Err(err) => {
+            return Err(err.into());
+        }
+    };
+    if let Some(basemap) = &data.basemap {
+        problem.set_basemap(basemap.clone());
+    }
+    problem.set_render_queue(data.render_queue.clone(), &sid);
+
+    // Asynchronous simulations publish every completed round, such that they can be played back
+    // while still running
+    if asynchronous {
+        problem.enable_step_publishing();
+    }
+    if audit {
+        problem.enable_audit();
+    }
+
+    // Register the simulation so that its progress can be polled while simulating
+    {
+        let session = data.sessions.get_session(&sid).unwrap();
+        let mut session = session.write().unwrap();
+        session.start_simulation(problem.running_simulation());
+    }
+
+    // Asynchronous simulations run on the compute pool
+    if asynchronous {
+        let progress = problem.progress().snapshot();
+        let pool_data = data.clone();
+        data.pool.spawn(move || {
+            problem.simulate();
+            finish_simulation(&pool_data, &sid, problem, pregen_zoom);
+            drop(task);
+        });
+        return Ok(res.json(progress));
+    }
+
+    problem.simulate();
+    let sim = finish_simulation(&data, &sid, problem, pregen_zoom);
+    drop(task);
+
+    Ok(res.json(sim))
+}
+
+/// Estimate the outcome of a firefighter problem instance with settings `settings` within
+/// milliseconds by simulating it on the coarsened graph, e.g. to explore parameters before a full
+/// simulation. Previews are neither attached to the session nor recorded in the history, and
+/// ignore the edits of the graph in the session.
+async fn simulate_preview(data: &web::Data<AppData>, mut settings: OSMFSettings, req: &HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(data, req, HttpResponse::Ok());
+    let graph = get_settings_graph(data, &settings)?;
+    if settings.threads.is_none() {
+        settings.threads = data.simulation_threads;
+    }
+
+    // Simulate on a blocking thread, such that the preview does not stall the actix worker while
+    // waiting for the compute pool
+    let pool_data = data.clone();
+    let preview = web::block(move || pool_data.pool.install(|| OSMFProblem::simulate_preview(&graph, &settings)))
+        .await??;
+    Ok(res.json(preview))
+}
+
+/// Finish the simulated firefighter problem instance `problem` of the session with id `sid`.
+/// That is, start pre-generating frames at zoom level `pregen_zoom` if requested, record the
+/// simulation in the history catalog and attach the problem to the session.
+/// Returns the simulation response.
+fn finish_simulation(data: &AppData, sid: &str, mut problem: OSMFProblem, pregen_zoom: Option<f64>) -> Value {
+    if let Some(zoom) = pregen_zoom {
+        problem.start_frame_pregen(zoom, data.view.node_radius.clone());
+    }
+
+    // Record the simulation in the history catalog. Failing to do so does not fail the simulation.
+    let mut sim = serde_json::to_value(problem.simulation_response()).unwrap();
+    match data.history.record(&problem) {
+        Ok(simulation_id) => sim["simulation_id"] = json!(simulation_id),
+        Err(err) => log::warn!("Failed to record simulation in history: {}", err)
+    }
+
+    // The session may have been pruned while simulating asynchronously
+    if let Some(session) = data.sessions.get_session(sid) {
+        session.write().unwrap().attach_problem(problem);
+    }
+
+    sim
+}
+
+/// Display the view of a firefighter simulation. While an asynchronous simulation is running,
+/// its completed rounds are displayed.
+#[get("/view")]
+async fn display_view(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    let center_lat = query.try_get_and_parse::<f64>("clat");
+    let center_lon = query.try_get_and_parse::<f64>("clon");
+    let zoom = query.get_and_parse::<f64>("zoom")?;
+    data.view.validate_zoom("zoom", zoom)?;
+    let time = query.get_and_parse::<TimeUnit>("time")?;
+    let radius = &data.view.node_radius;
+    let node_radius = NodeRadius {
+        scale: query.try_get_and_parse::<f64>("radius_scale").transpose()?.unwrap_or(radius.scale),
+        log_base: query.try_get_and_parse::<f64>("radius_log_base").transpose()?.unwrap_or(radius.log_base),
+        min_px: query.try_get_and_parse::<u32>("min_radius").transpose()?.unwrap_or(radius.min_px),
+        max_px: query.try_get_and_parse::<u32>("max_radius").transpose()?.or(radius.max_px),
+    };
+    node_radius.validate().map_err(|message| OSMFError::BadRequest { message })?;
+    let options = ViewOptions {
+        fire_flow: query.try_get_and_parse::<bool>("flow").transpose()?.unwrap_or(false),
+        corridors: query.try_get_and_parse::<usize>("corridors").transpose()?.unwrap_or(0),
+        basemap: query.try_get_and_parse::<bool>("basemap").transpose()?.unwrap_or(false),
+        node_radius,
+        color_by_root: query.try_get_and_parse::<bool>("color_by_root").transpose()?.unwrap_or(false),
+        exposure_radius_m: parse_exposure_radius(&query)?,
+        voronoi: query.try_get_and_parse::<bool>("voronoi").transpose()?.unwrap_or(false),
+        forecast_rounds: parse_forecast_rounds(&query)?,
+        layers: query.try_get_and_parse::<ViewLayers>("layers").transpose()?.unwrap_or_default(),
+    };
+    let center = match (center_lat, center_lon) {
+        (Some(lat), Some(lon)) => Some((lat?, lon?)),
+        _ => None
+    };
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+
+    if let Some(running) = session.get_running_simulation() {
+        log::debug!("Computing partial view for center: {:?}, zoom: {} and time: {}", center, zoom, time);
+
+        return match running.view_response(center, zoom, &time, &options) {
+            Some(png) => Ok(res.content_type("image/png").body(png)),
+            None => Err(round_not_completed(time))
+        };
+    }
+
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    if let Some(center) = center {
+        log::debug!("Computing view for center: {:?}, zoom: {} and time: {}", center, zoom, time);
+
+        Ok(res.content_type("image/png")
+            .body(problem.view_response(center, zoom, &time, &options)))
+    } else {
+        log::debug!("Computing view for zoom: {} and time: {}", zoom, &time);
+
+        Ok(res.content_type("image/png")
+            .body(problem.view_response_alt(zoom, &time, &options)))
+    }
+}
+
+/// Parse the optional number of rounds for which the danger zone of the fire is forecast from the
+/// query parameter `forecast`
+fn parse_forecast_rounds(query: &Query) -> Result<Option<TimeUnit>, OSMFError> {
+    match query.try_get_and_parse::<TimeUnit>("forecast").transpose()? {
+        Some(0) => Err(OSMFError::BadRequest {
+            message: "Invalid value for parameter 'forecast': '0'".to_string()
+        }),
+        rounds => Ok(rounds)
+    }
+}
+
+/// Parse the optional distance in meters within which nodes are exposed to the smoke of burning
+/// nodes from the query parameter `exposure_radius`
+fn parse_exposure_radius(query: &Query) -> Result<Option<f64>, OSMFError> {
+    match query.try_get_and_parse::<f64>("exposure_radius").transpose()? {
+        Some(radius_m) if !(radius_m.is_finite() && radius_m > 0.0) => Err(OSMFError::BadRequest {
+            message: format!("Invalid value for parameter 'exposure_radius': '{}'", radius_m)
+        }),
+        radius_m => Ok(radius_m)
+    }
+}
+
+/// Create the error for a request of round `time` of a running simulation that has not been
+/// completed yet
+fn round_not_completed(time: TimeUnit) -> OSMFError {
+    OSMFError::NoSimulation {
+        message: format!("Round {} of the running simulation has not been completed yet", time)
+    }
+}
+
+/// Rank all strategies by the expected ratio of nodes of the graph `graph` that do not burn when
+/// `num_roots` fires are fought by `num_ffs` firefighters. Outcomes are expected from the recorded
+/// statistics of the strategies in the parameter bucket of the scenario, see
+/// `/graphs/{name}/strategy-stats`, or estimated by previews on the coarsened graph for strategies
+/// without recorded simulations, whose fire roots are generated from `seed`. Previews act every
+/// `strategy_every` rounds, which defaults to the suggestion for the graph. Strategies that
+/// require further settings, e.g. a defense plan, are not ranked.
+#[get("/recommend")]
+async fn recommend_strategies(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    let graph_name = query.get("graph")?;
+    let graph = data.get_graph(graph_name).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Unknown graph: '{}'", graph_name)
+    })?;
+    let num_roots = query.get_and_parse::<usize>("num_roots")?;
+    let num_ffs = query.get_and_parse::<usize>("num_ffs")?;
+    let strategy_every = match query.try_get_and_parse::<TimeUnit>("strategy_every").transpose()? {
+        Some(strategy_every) => strategy_every,
+        None => OSMFSettingsDefaults::for_graph(&*graph).strategy_every
+    };
+    let seed = query.try_get_and_parse::<u64>("seed").transpose()?.unwrap_or(DEFAULT_RECOMMEND_SEED);
+
+    let mut base = OSMFSettings::new(graph_name, "Greedy", num_roots, num_ffs, strategy_every);
+    base.seed = Some(seed);
+    base.threads = data.simulation_threads;
+    base.validate(&*graph)?;
+
+    let stats = data.history.strategy_stats(graph_name, Some(num_roots), Some(num_ffs))?;
+    let mut recommendations = Vec::new();
+    let mut preview_settings = Vec::new();
+    for strategy_name in OSMFStrategy::available_strategies() {
+        let settings = OSMFSettings { strategy_name: strategy_name.clone(), ..base.clone() };
+        if settings.validate(&*graph).is_err() {
+            continue;
+        }
+        match stats.iter().find(|stats| stats.strategy_name == strategy_name) {
+            Some(stats) => {
+                let standard_error = stats.saved_ratio_variance
+                    .map(|variance| (variance / stats.runs as f64).sqrt());
+                recommendations.push(StrategyRecommendation {
+                    rank: 0,
+                    strategy_name,
+                    source: "history",
+                    expected_saved_ratio: stats.mean_saved_ratio,
+                    runs: stats.runs,
+                    standard_error,
+                    confidence: Confidence::for_history(stats.runs, standard_error),
+                    expected_simulation_time_millis: Some(stats.mean_simulation_time_millis),
+                });
+            }
+            None => preview_settings.push(settings)
+        }
+    }
+
+    // Simulate the previews of all strategies without recorded simulations in parallel on a
+    // blocking thread, such that they do not stall the actix worker while waiting for the
+    // compute pool
+    let pool_data = data.clone();
+    let previews = web::block(move || pool_data.pool.install(|| preview_settings.into_par_iter()
+        .map(|settings| OSMFProblem::simulate_preview(&graph, &settings)
+            .map(|preview| (settings.strategy_name, preview)))
+        .collect::<Result<Vec<_>, _>>()))
+        .await??;
+    for (strategy_name, preview) in previews {
+        recommendations.push(StrategyRecommendation {
+            rank: 0,
+            strategy_name,
+            source: "preview",
+            expected_saved_ratio: 1.0 - preview.nodes_burned as f64 / preview.nodes_total.max(1) as f64,
+            runs: 0,
+            standard_error: None,
+            confidence: Confidence::Low,
+            expected_simulation_time_millis: None,
+        });
+    }
+
+    // Rank by expected outcome and prefer more confident expectations among equal outcomes
+    recommendations.sort_by(|r1, r2| r2.expected_saved_ratio.partial_cmp(&r1.expected_saved_ratio).unwrap()
+        .then(r2.confidence.cmp(&r1.confidence))
+        .then(r1.strategy_name.cmp(&r2.strategy_name)));
+    for (i, recommendation) in recommendations.iter_mut().enumerate() {
+        recommendation.rank = i + 1;
+    }
+
+    Ok(res.json(json!({
+        "graph_name": graph_name,
+        "num_roots": num_roots,
+        "num_ffs": num_ffs,
+        "bucket": OSMFParameterBucket::new(num_roots, num_ffs),
+        "strategies": recommendations,
+    })))
+}
+
+/// Validate the settings of a firefighter problem instance and estimate the costs of simulating it
+/// without running the simulation. The runtime is estimated from past simulations on the same
+/// graph, preferably with the same strategy.
+#[post("/simulate/dryrun")]
+async fn dry_run_problem(data: web::Data<AppData>, settings: web::Json<OSMFSettings>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let mut settings = settings.into_inner();
+    let graph = get_settings_graph(&data, &settings)?;
+    let graph = get_session_graph(&data, &sid, &graph, &mut settings);
+    settings.validate(&*graph)?;
+
+    let (mut num_past_runs, mut mean_millis) = data.history
+        .runtime_stats(&settings.graph_name, Some(&settings.strategy_name))?;
+    if num_past_runs == 0 {
+        let stats = data.history.runtime_stats(&settings.graph_name, None)?;
+        num_past_runs = stats.0;
+        mean_millis = stats.1;
+    }
+    let estimated_millis_per_node = mean_millis.map(|millis| millis / graph.num_nodes().max(1) as f64);
+
+    Ok(res.json(DryRunResponse {
+        graph_name: settings.graph_name.clone(),
+        num_nodes: graph.num_nodes(),
+        num_edges: graph.num_edges(),
+        estimated_memory_bytes: OSMFProblem::estimate_memory_bytes(&*graph, &settings),
+        num_past_runs,
+        estimated_millis_per_node,
+        estimated_runtime_millis: estimated_millis_per_node
+            .map(|per_node| per_node * graph.num_nodes() as f64),
+    }))
+}
+
+/// Get the metrics of the janitor that evicts the caches of idle simulations, e.g. the number of
+/// reclaimed bytes
+#[get("/sessions/janitor")]
+async fn get_janitor_stats(data: web::Data<AppData>, req: HttpRequest) -> impl Responder {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+    res.json(data.sessions.janitor_stats())
+}
+
+/// Get the progress of the running or last firefighter simulation
+#[get("/simulate/progress")]
+async fn get_simulation_progress(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    match session.get_progress() {
+        Some(progress) => Ok(res.json(progress)),
+        None => Err(OSMFError::NoSimulation {
+            message: "No simulation has been started yet".to_string()
+        })
+    }
+}
+
+/// Get the progress of the frame pre-generation of a firefighter simulation
+#[get("/view/status")]
+async fn get_view_status(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    match problem.pregen_status_response() {
+        Some(status) => Ok(res.json(status)),
+        None => Err(OSMFError::BadRequest {
+            message: "Frame pre-generation has not been requested for this simulation".to_string()
+        })
+    }
+}
+
+/// Get the colors of the burn trees of all fire roots by the given time in color-by-root views
+#[get("/view/palette")]
+async fn get_root_palette(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    let time = query.try_get_and_parse::<TimeUnit>("time").transpose()?.unwrap_or(TimeUnit::MAX);
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    if let Some(running) = session.get_running_simulation() {
+        return match running.root_palette_response(&time.min(running.watermark().unwrap_or(0))) {
+            Some(palette) => Ok(res.json(palette)),
+            None => Err(round_not_completed(time))
+        };
+    }
+
+    match session.get_problem() {
+        Some(problem) => Ok(res.json(problem.root_palette_response(&time))),
+        None => Err(OSMFError::NoSimulation {
+            message: "No simulation has been started yet".to_string()
+        })
+    }
+}
+
+/// Get the nearest-root partition of a firefighter simulation, i.e. the fire root that would
+/// reach each node first without firefighters, together with the Voronoi regions of all fire
+/// roots and their number of burned and defended nodes
+#[get("/voronoi")]
+async fn get_voronoi(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    match session.get_problem() {
+        Some(problem) => Ok(res.json(problem.voronoi_response())),
+        None => Err(OSMFError::NoSimulation {
+            message: "No simulation has been started yet".to_string()
+        })
+    }
+}
+
+/// Export the state of every node at the end of a firefighter simulation, i.e. its burn and
+/// defend time, the fire root it burned from and its smoke exposure within the distance
+/// `exposure_radius` in meters of burning nodes. Supported formats are `json` (default) and, if
+/// the backend is built with the `parquet` feature, `parquet`.
+#[get("/export")]
+async fn export_node_states(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    // Exports yield to interactive renders
+    let _permit = data.render_queue.acquire(RenderPriority::Export, &sid);
+    let query = Query::from(req.query_string());
+    let exposure_radius_m = parse_exposure_radius(&query)?.unwrap_or(DEFAULT_EXPOSURE_RADIUS_M);
+    match query.try_get("format").unwrap_or("json") {
+        "json" => {
+            // Derived data must carry the attribution of the graph
+            let metadata = problem.graph_metadata();
+            for (name, value) in [("X-Data-Source", &metadata.source), ("X-Data-License", &metadata.license),
+                                  ("X-Data-Attribution", &metadata.attribution)] {
+                if let Ok(value) = header::HeaderValue::from_bytes(value.as_bytes()) {
+                    res.insert_header((name, value));
+                }
+            }
+            Ok(res.json(problem.node_states_response(exposure_radius_m)))
+        }
+        #[cfg(feature = "parquet")]
+        "parquet" => {
+            let states = problem.node_states_response(exposure_radius_m);
+            let bytes = osmff_lib::firefighter::export::write_parquet(&states, problem.graph_metadata())
+                .map_err(|err| OSMFError::Internal {
+                    message: format!("Failed to write Parquet file: {}", err)
+                })?;
+            artifact_response(&data, res, &bytes, "parquet")
+        }
+        #[cfg(not(feature = "parquet"))]
+        "parquet" => Err(OSMFError::BadRequest {
+            message: "Parquet export requires the backend to be built with the 'parquet' feature".to_string()
+        }),
+        format => Err(OSMFError::BadRequest {
+            message: format!("Invalid value for parameter 'format': '{}'", format)
+        })
+    }
+}
+
+/// Get the burn forest of a firefighter simulation, i.e. the neighbour that ignited each
+/// burning node
+#[get("/burnforest")]
+async fn get_burn_forest(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    Ok(res.json(problem.burn_forest_response()))
+}
+
+/// Get the defense plan of a firefighter simulation, i.e. the nodes defended in each round. The
+/// plan can be replayed against other fires with the `ReplayPlan` strategy.
+#[get("/plan")]
+async fn get_defense_plan(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    Ok(res.json(problem.defense_plan()))
+}
+
+/// Download the audit log of a firefighter simulation that was started in audit mode. The log
+/// can be verified against the current engine with `/audit/verify`.
+#[get("/audit")]
+async fn get_audit_log(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    match problem.audit_log() {
+        Some(log) => {
+            res.insert_header((header::CONTENT_DISPOSITION, "attachment; filename=\"osmff_audit.json\""));
+            Ok(res.json(log))
+        }
+        None => Err(OSMFError::BadRequest {
+            message: "The simulation was not started in audit mode".to_string()
+        })
+    }
+}
+
+/// Replay the simulation of an audit log with the current engine and report every ignition that
+/// diverges from the log
+#[post("/audit/verify")]
+async fn verify_audit_log(data: web::Data<AppData>, log: web::Json<OSMFAuditLog>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+    let task = data.drain.start_task()?;
+
+    let mut log = log.into_inner();
+    let graph = get_settings_graph(&data, &log.settings)?;
+    let graph = get_session_graph(&data, &sid, &graph, &mut log.settings);
+    let strategy = OSMFStrategy::from_name_and_graph(&log.settings.strategy_name, graph.clone())
+        .expect("Strategy should be known after validation");
+
+    let report = log.verify(graph, strategy)?;
+    drop(task);
+
+    Ok(res.json(report))
+}
+
+/// Get the dominant spread corridors of a firefighter simulation, i.e. the paths in the burn
+/// forest through which the fire reached the most nodes
+#[get("/corridors")]
+async fn get_corridors(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    let query = Query::from(req.query_string());
+    let k = query.try_get_and_parse::<usize>("k").transpose()?.unwrap_or(DEFAULT_NUM_CORRIDORS);
+
+    Ok(res.json(problem.corridors_response(k)))
+}
+
+/// Get summary statistics about the outcome of a firefighter simulation, including the
+/// savings per defended node
+#[get("/simstats")]
+async fn get_sim_stats(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    Ok(res.json(problem.sim_stats_response()))
+}
+
+/// Classify each defended node of a firefighter simulation as effective, i.e. reached by the
+/// fire, or wasted
+#[get("/defended")]
+async fn get_defense_efficiency(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    Ok(res.json(problem.defense_efficiency_response()))
+}
+
+/// Prepare the counterfactual node importance analysis of the firefighter simulation of the
+/// session with id `sid` with the parameters given in `query`
+fn prepare_node_importance(data: &AppData, sid: &str, query: &Query) -> Result<NodeImportanceAnalysis, OSMFError> {
+    let k = query.try_get_and_parse::<usize>("k").transpose()?.unwrap_or(3);
+    let rounds = query.try_get_and_parse::<usize>("rounds").transpose()?.unwrap_or(5);
+    let budget = query.try_get_and_parse::<usize>("budget").transpose()?.unwrap_or(20);
+    if budget > MAX_IMPORTANCE_BUDGET {
+        return Err(OSMFError::BadRequest {
+            message: format!("Value for parameter 'budget' must not be greater than {}: {}",
+                             MAX_IMPORTANCE_BUDGET, budget)
+        });
+    }
+
+    let session = data.sessions.get_session(sid).unwrap();
+    let session = session.read().unwrap();
+    match session.get_problem() {
+        Some(problem) => Ok(problem.node_importance_analysis(k, rounds, budget)),
+        None => Err(OSMFError::NoSimulation {
+            message: "No simulation has been started yet".to_string()
+        })
+    }
+}
+
+/// Rank the nodes that caught fire in the early rounds of a firefighter simulation by how much
+/// fewer nodes would have burned if they had been defended from the start
+#[get("/analysis/node-importance")]
+async fn get_node_importance(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    let analysis = prepare_node_importance(&data, &sid, &query)?;
+
+    // Run the analysis on a blocking thread, such that it does not stall the actix worker while
+    // waiting for the compute pool
+    let pool_data = data.clone();
+    let result = web::block(move || pool_data.pool.install(|| analysis.run(&JobProgress::new())))
+        .await?;
+
+    Ok(res.json(result))
+}
+
+/// Start a counterfactual node importance analysis as a job on the compute pool.
+/// The progress and the result of the analysis can be polled with the returned job id.
+#[post("/analysis/node-importance")]
+async fn start_node_importance(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Accepted());
+
+    let query = Query::from(req.query_string());
+    let analysis = prepare_node_importance(&data, &sid, &query)?;
+    let task = data.drain.start_task()?;
+
+    let (job_id, job) = data.jobs.create_job("node-importance");
+    log::info!("Starting node importance job {} with {} simulations", job_id,
+        analysis.num_simulations());
+    data.pool.spawn(move || {
+        let result = analysis.run(job.progress());
+        job.finish(serde_json::to_value(result).unwrap());
+        drop(task);
+    });
+
+    Ok(res.json(json!({ "job_id": job_id })))
+}
+
+/// Get the progress and, if finished, the result of a job
+#[get("/jobs/{id}")]
+async fn get_job(data: web::Data<AppData>, job_id: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    match data.jobs.get_status(&job_id) {
+        Some(status) => Ok(res.json(status)),
+        None => Err(OSMFError::BadRequest {
+            message: format!("Unknown job: '{}'", job_id)
+        })
+    }
+}
+
+/// Get the most recent completed simulations from the history catalog, optionally filtered by
+/// graph, strategy and tag
+#[get("/history")]
+async fn get_history(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    let limit = query.try_get_and_parse::<usize>("limit").transpose()?.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let entries = data.history.query(query.try_get("graph"), query.try_get("strategy"),
+                                     query.try_get("tag"), limit)?;
+
+    Ok(res.json(entries))
+}
+
+/// Compare the simulations with the comma-separated history ids `ids` by their key metrics and by
+/// the pairwise overlap of their burned nodes
+#[get("/compare")]
+async fn compare_simulations(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    let ids_param = query.get("ids")?;
+    let ids = ids_param.split(',')
+        .map(|id| id.trim().parse::<i64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| OSMFError::BadRequest {
+            message: format!("Invalid value for parameter 'ids': '{}'", ids_param)
+        })?;
+    if ids.len() > MAX_COMPARED_SIMULATIONS {
+        return Err(OSMFError::BadRequest {
+            message: format!("Number of compared simulations must not be greater than {}: {}",
+                             MAX_COMPARED_SIMULATIONS, ids.len())
+        });
+    }
+
+    match data.history.compare(&ids)? {
+        Ok(comparison) => Ok(res.json(comparison)),
+        Err(id) => Err(OSMFError::BadRequest {
+            message: format!("Unknown simulation: '{}'", id)
+        })
+    }
+}
+
+/// Replace the notes and/or the tags of a simulation in the history catalog
+#[patch("/simulations/{id}")]
+async fn annotate_simulation(data: web::Data<AppData>, id: web::Path<i64>,
+                             annotation: web::Json<SimulationAnnotation>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let id = id.into_inner();
+    if let Some(tags) = &annotation.tags {
+        if tags.iter().any(|tag| tag.trim().is_empty()) {
+            return Err(OSMFError::BadRequest {
+                message: "Tags must not be empty".to_string()
+            });
+        }
+    }
+
+    let tags: Option<Vec<_>> = annotation.tags.as_ref()
+        .map(|tags| tags.iter().map(|tag| tag.trim().to_string()).collect());
+    if !data.history.annotate(id, annotation.notes.as_deref(), tags.as_deref())? {
+        return Err(OSMFError::NotFound {
+            message: format!("Unknown simulation: '{}'", id)
+        });
+    }
+
+    match data.history.get(id)? {
+        Some(entry) => Ok(res.json(entry)),
+        None => Err(OSMFError::Internal {
+            message: format!("Simulation '{}' vanished from the history", id)
+        })
+    }
+}
+
+/// Render a line chart of the number of burned and defended nodes over the rounds of a
+/// firefighter simulation
+#[get("/charts/burned_over_time.png")]
+async fn chart_burned_over_time(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let counts = {
+        let session = data.sessions.get_session(&sid).unwrap();
+        let session = session.read().unwrap();
+        match session.get_problem() {
+            Some(problem) => problem.cumulative_counts(),
+            None => {
+                return Err(OSMFError::NoSimulation {
+                    message: "No simulation has been started yet".to_string()
+                });
+            }
+        }
+    };
+
+    let series = vec![
+        ("Burned".to_string(), counts.iter().enumerate()
+            .map(|(time, (burned, _))| (time as TimeUnit, *burned as f64))
+            .collect()),
+        ("Defended".to_string(), counts.iter().enumerate()
+            .map(|(time, (_, defended))| (time as TimeUnit, *defended as f64))
+            .collect()),
+    ];
+    let png = charts::line_chart_png("Nodes burned over time", "Round", "Nodes", &series)?;
+
+    Ok(res.content_type("image/png").body(png))
+}
+
+/// Render a bar chart comparing the mean outcome of all recorded simulations of every strategy,
+/// optionally restricted to a graph and a tag. The compared metric is the number of burned
+/// (default) or defended nodes, the end time or the simulation time.
+#[get("/charts/strategy_comparison.png")]
+async fn chart_strategy_comparison(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    let (column, caption, y_desc) = match query.try_get("metric").unwrap_or("burned") {
+        "burned" => ("nodes_burned", "Mean burned nodes", "Nodes"),
+        "defended" => ("nodes_defended", "Mean defended nodes", "Nodes"),
+        "end_time" => ("end_time", "Mean end time", "Rounds"),
+        "runtime" => ("simulation_time_millis", "Mean simulation time", "Milliseconds"),
+        metric => {
+            return Err(OSMFError::BadRequest {
+                message: format!("Invalid value for parameter 'metric': '{}'", metric)
+            });
+        }
+    };
+
+    let bars = data.history.strategy_means(column, query.try_get("graph"), query.try_get("tag"))?;
+    let png = charts::bar_chart_png(caption, y_desc, &bars)?;
+
+    Ok(res.content_type("image/png").body(png))
+}
+
+/// Render a self-contained HTML report comparing the strategies of a batch of simulations.
+/// A batch consists of all simulations in the history catalog that are tagged with the batch id.
+#[get("/report/{batch_id}")]
+async fn get_batch_report(data: web::Data<AppData>, batch_id: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let report = batch_report(&data, &batch_id)?;
+    artifact_response(&data, res, report.to_html().as_bytes(), "html")
+}
+
+/// Get the Pareto-optimal configurations of a batch of simulations, i.e. all strategies and
+/// parameterizations that are not outperformed on all objectives by another configuration.
+/// The objectives are the burned nodes, the burned area, the travel distance of the firefighters
+/// and the containment time.
+#[get("/report/{batch_id}/pareto")]
+async fn get_batch_pareto_front(data: web::Data<AppData>, batch_id: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, _) = init_response(&data, &req, HttpResponse::Ok());
+
+    let report = batch_report(&data, &batch_id)?;
+    Ok(res.json(json!({
+        "objectives": PARETO_OBJECTIVES,
+        "pareto_front": report.pareto_front(),
+    })))
+}
+
+/// Create a strategy comparison report of all simulations of the batch with id `batch_id`
+fn batch_report(data: &AppData, batch_id: &str) -> Result<StrategyReport, OSMFError> {
+    let entries = data.history.query(None, None, Some(batch_id), MAX_REPORT_RUNS)?;
+    if entries.is_empty() {
+        return Err(OSMFError::BadRequest {
+            message: format!("Unknown batch: '{}'", batch_id)
+        });
+    }
+
+    let mut report = StrategyReport::new(&format!("Strategy comparison of batch {}", batch_id));
+    for entry in entries.iter().rev() {
+        report.add_run(entry.report_run());
+    }
+    Ok(report)
+}
+
+/// Store `bytes` as artifact with file extension `extension` and redirect to the artifact
+fn artifact_response(data: &AppData, mut res: HttpResponseBuilder, bytes: &[u8], extension: &str)
+    -> Result<HttpResponse, OSMFError> {
+    let artifact = data.artifacts.store(bytes, extension)
+        .map_err(|err| OSMFError::Internal {
+            message: format!("Failed to store artifact: {}", err)
+        })?;
+
+    Ok(res.status(StatusCode::SEE_OTHER)
+        .insert_header((header::LOCATION, artifact.url.clone()))
+        .json(artifact))
+}
+
+/// Download a stored artifact. Artifacts are identified by their content and are therefore not
+/// bound to a session. Range requests are supported to resume downloads.
+#[get("/artifacts/{id}")]
+async fn get_artifact(data: web::Data<AppData>, id: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let path = data.artifacts.path(&id).ok_or_else(|| OSMFError::NotFound {
+        message: format!("Unknown artifact: '{}'", id)
+    })?;
+    let file = NamedFile::open(path).map_err(|_| OSMFError::NotFound {
+        message: format!("Artifact '{}' has been collected", id)
+    })?;
+
+    Ok(file.into_response(&req))
+}
+
+/// Get the timeline of a specific node of a firefighter simulation
+#[get("/node/{id}/timeline")]
+async fn get_node_timeline(data: web::Data<AppData>, node_id: web::Path<usize>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    let node_id = node_id.into_inner();
+    match problem.node_timeline_response(node_id) {
+        Some(timeline) => Ok(res.json(timeline)),
+        None => Err(OSMFError::BadRequest {
+            message: format!("Unknown node: '{}'", node_id)
+        })
+    }
+}
+
+/// Get the metadata for a specific step of a firefighter simulation. While an asynchronous
+/// simulation is running, the metadata of its completed rounds is served.
+#[get("/stepmeta")]
+async fn get_sim_step_metadata(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    let time = query.get_and_parse::<TimeUnit>("time")?;
+
+    let session = data.sessions.get_session(&sid).unwrap();
+    let session = session.read().unwrap();
+
+    if let Some(running) = session.get_running_simulation() {
+        return match running.sim_step_metadata_response(&time) {
+            Some(step) => Ok(res.json(step)),
+            None => Err(round_not_completed(time))
+        };
+    }
+
+    let problem = match session.get_problem() {
+        Some(problem) => problem,
+        None => {
+            return Err(OSMFError::NoSimulation {
+                message: "No simulation has been started yet".to_string()
+            });
+        }
+    };
+
+    Ok(res.json(problem.sim_step_metadata_response(&time)))
+}
+
+/// Wait at most `timeout` milliseconds until the step after time `after` of a firefighter
+/// simulation is available and get its metadata. This allows to play back asynchronous
+/// simulations while they are still running.
+/// The metadata is `null` if the step is not available in time or the simulation finished before.
+#[get("/stepmeta/wait")]
+async fn wait_sim_step_metadata(data: web::Data<AppData>, req: HttpRequest) -> Result<HttpResponse, OSMFError> {
+    let (mut res, sid) = init_response(&data, &req, HttpResponse::Ok());
+
+    let query = Query::from(req.query_string());
+    let after = query.get_and_parse::<TimeUnit>("after")?;
+    let timeout = query.try_get_and_parse::<u64>("timeout").transpose()?
+        .unwrap_or(DEFAULT_STEP_WAIT_MILLIS)
+        .min(MAX_STEP_WAIT_MILLIS);
+    let time = after.checked_add(1).ok_or_else(|| OSMFError::BadRequest {
+        message: format!("Invalid value for parameter 'after': '{}'", after)
+    })?;
+
+    let start = Instant::now();
+    loop {
+        let (step, is_finished) = {
+            let session = data.sessions.get_session(&sid).unwrap();
+            let session = session.read().unwrap();
+            match session.get_step(time) {
+                Some(step) => step,
+                None => {
+                    return Err(OSMFError::NoSimulation {
+                        message: "No simulation has been started yet".to_string()
+                    });
+                }
+            }
+        };
+
+        if step.is_some() || is_finished || start.elapsed() >= Duration::from_millis(timeout) {
+            return Ok(res.json(json!({
+                "time": time,
+                "is_finished": is_finished,
+                "step": step,
+            })));
+        }
+        rt::time::sleep(STEP_WAIT_POLL_INTERVAL).await;
+    }
+}
+
+/// Register all services of the web app
+fn configure_services(cfg: &mut web::ServiceConfig) {
+    cfg.service(ping)
+        .service(list_graphs)
+        .service(get_graph_defaults)
+        .service(get_graph_info)
+        .service(validate_graph)
+        .service(get_nearest_node)
+        .service(extract_subgraph)
+        .service(delete_graph)
+        .service(reload_graph)
+        .service(get_graph_geojson)
+        .service(download_graph)
+        .service(get_spread_calibration)
+        .service(get_strategy_stats)
+        .service(recommend_strategies)
+        .service(list_strategies)
+        .service(edit_graph)
+        .service(get_graph_edits)
+        .service(discard_graph_edits)
+        .service(search_nodes)
+        .service(predict_burn_times)
+        .service(simulate_problem)
+        .service(dry_run_problem)
+        .service(get_simulation_progress)
+        .service(get_janitor_stats)
+        .service(display_view)
+        .service(get_view_status)
+        .service(get_root_palette)
+        .service(get_voronoi)
+        .service(get_sim_step_metadata)
+        .service(wait_sim_step_metadata)
+        .service(get_node_timeline)
+        .service(export_node_states)
+        .service(get_burn_forest)
+        .service(get_defense_plan)
+        .service(get_audit_log)
+        .service(verify_audit_log)
+        .service(get_corridors)
+        .service(get_defense_efficiency)
+        .service(get_sim_stats)
+        .service(get_node_importance)
+        .service(start_node_importance)
+        .service(get_job)
+        .service(get_history)
+        .service(compare_simulations)
+        .service(get_batch_report)
+        .service(get_batch_pareto_front)
+        .service(get_artifact)
+        .service(chart_burned_over_time)
+        .service(chart_strategy_comparison)
+        .service(annotate_simulation);
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    // Parse config file
+    let config = Config::from_file(CONFIG_PATH);
+
+    // Initialize logger
+    env::set_var("RUST_LOG", &config.log_level);
+    env::set_var("RUST_BACKTRACE", "1");
+    env_logger::init();
+
+    if let Err(err) = config.view.node_radius.validate() {
+        panic!("Invalid node radius in config file: {}", err);
+    }
+
+    compute::init_dijkstra_pool(config.dijkstra_threads);
+    strategy::init_strategy_logs(config.verbose_strategy_logs, config.strategy_debug_path.as_deref());
+
+    // Register plugin strategies
+    if let Some(ref plugins_path) = config.plugins_path {
+        match plugin::load_plugins(plugins_path) {
+            Ok(names) => log::info!("Loaded {} plugin strategies from {}", names.len(), plugins_path),
+            Err(err) => panic!("Failed to load plugins: {}", err)
+        }
+    }
+
+    // Initialize graphs
+    let graphs = match osmff_lib::load_graphs_with_cache(&config.graphs_path,
+                                                         config.graph_cache_path.as_deref()) {
+        Ok(graphs) => graphs,
+        Err(err) => {
+            panic!("Failed to load graphs: {}", err);
+        }
+    };
+
+    // Initialize app data
+    let data = web::Data::new(AppData {
+        sessions: OSMFSessionStorage::new(),
+        defaults: RwLock::new(compute_defaults(&graphs)),
+        graphs: RwLock::new(graphs),
+        pool: compute::build_compute_pool(config.compute_threads),
+        jobs: OSMFJobStorage::new(),
+        history: OSMFHistory::open(&config.history_path)
+            .expect("Failed to open simulation history"),
+        basemap: config.basemap_url.as_ref().map(|url|
+            Arc::new(TileCache::new(url, &config.basemap_cache_path)
+                .expect("Failed to initialize basemap tiles"))),
+        render_queue: Arc::new(RenderQueue::new(config.render_threads)),
+        artifacts: OSMFArtifactStore::open(&config.artifacts)
+            .expect("Failed to open artifact store"),
+        drain: OSMFDrain::new(),
+        view: config.view.clone(),
+        simulation_threads: config.simulation_threads,
+        graphs_path: config.graphs_path.clone(),
+        graph_cache_path: config.graph_cache_path.clone(),
+    });
+
+    // Initialize and start server. Signals are handled below to drain running simulations first.
+    let cors = config.cors;
+    let server_data = data.clone();
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(server_data.clone())
+            .wrap(cors.build())
+            .wrap(i18n::localize_errors())
+            .wrap(Logger::default())
+            .configure(configure_services)
+    })
+        .disable_signals()
+        .shutdown_timeout(config.shutdown_timeout_secs)
+        .bind((config.host.as_str(), config.port))?
+        .run();
+
+    // Evict the caches of idle simulations in the background
+    let (janitor, janitor_data) = (config.janitor, data.clone());
+    rt::spawn(async move {
+        let idle = Duration::from_secs(janitor.idle_secs);
+        let mut interval = rt::time::interval(Duration::from_secs(janitor.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            janitor_data.sessions.compact_idle_simulations(idle);
+        }
+    });
+
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let (handle, ctrl_c_data) = (server.handle(), data.clone());
+    rt::spawn(async move {
+        if rt::signal::ctrl_c().await.is_ok() {
+            shut_down(ctrl_c_data, handle, timeout).await;
+        }
+    });
+    #[cfg(unix)]
+    {
+        use rt::signal::unix::{signal, SignalKind};
+
+        let mut sigterm = signal(SignalKind::terminate())?;
+        let handle = server.handle();
+        rt::spawn(async move {
+            if sigterm.recv().await.is_some() {
+                shut_down(data, handle, timeout).await;
+            }
+        });
+    }
+
+    server.await
+}
+
+/// Shut the service down gracefully: Stop accepting new simulations, wait at most `timeout` for
+/// running simulations and jobs to finish, flush the history catalog and stop the server
+async fn shut_down(data: web::Data<AppData>, handle: ServerHandle, timeout: Duration) {
+    if data.drain.is_draining() {
+        return;
+    }
+
+    log::info!("Shutting down. Waiting for {} running simulations and jobs", data.drain.num_running());
+    if !data.drain.drain(timeout).await {
+        log::warn!("Aborting {} simulations and jobs that did not finish in time",
+                   data.drain.num_running());
+    }
+    if let Err(err) = data.history.flush() {
+        log::warn!("Failed to flush simulation history: {}", err);
+    }
+
+    handle.stop(true).await;
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, RwLock};
+    use std::time::Duration;
+
+    use actix_web::{App, test, web};
+    use actix_web::cookie::Cookie;
+    use actix_web::dev::ServiceResponse;
+    use actix_web::http::{header, Method, StatusCode};
+    use serde_json::{json, Value};
+
+    use osmff_lib::compute::{self, RenderQueue};
+    use osmff_lib::firefighter::problem::OSMFProblem;
+    use osmff_lib::firefighter::strategy::OSMFStrategy;
+    use osmff_lib::graph::GraphView;
+
+    use crate::{AppData, CorsConfig, CorsPreset, DEFAULT_HISTOGRAM_BINS, DEFAULT_RESPONSE_MINUTES, ViewConfig,
+                compute_defaults, configure_services};
+    use crate::web_utils::artifacts::{OSMFArtifactConfig, OSMFArtifactStore};
+    use crate::web_utils::drain::OSMFDrain;
+    use crate::web_utils::history::OSMFHistory;
+    use crate::web_utils::i18n;
+    use crate::web_utils::jobs::OSMFJobStorage;
+    use crate::web_utils::session::OSMFSessionStorage;
+
+    const TEST_GRAPH: &str = "bbgrund_undirected";
+
+    fn init_data() -> web::Data<AppData> {
+        let graphs = osmff_lib::load_graphs("data/").unwrap();
+        web::Data::new(AppData {
+            sessions: OSMFSessionStorage::new(),
+            defaults: RwLock::new(compute_defaults(&graphs)),
+            graphs: RwLock::new(graphs),
+            pool: compute::build_compute_pool(2),
+            jobs: OSMFJobStorage::new(),
+            history: OSMFHistory::open_in_memory().unwrap(),
+            basemap: None,
+            render_queue: Arc::new(RenderQueue::new(2)),
+            artifacts: OSMFArtifactStore::open(&OSMFArtifactConfig {
+                path: std::env::temp_dir().join(format!("osmff-artifacts-{}", nanoid::nanoid!()))
+                    .to_string_lossy().to_string(),
+                ..OSMFArtifactConfig::default()
+            }).unwrap(),
+            drain: OSMFDrain::new(),
+            view: ViewConfig::default(),
+            simulation_threads: None,
+            graphs_path: "data/".to_string(),
+            graph_cache_path: None,
+        })
+    }
+
+    fn settings(strategy: &str) -> Value {
+        json!({
+            "graph_name": TEST_GRAPH,
+            "strategy_name": strategy,
+            "num_roots": 3,
+            "num_ffs": 2,
+            "strategy_every": 5,
+        })
+    }
+
+    /// Get the session cookie set by `res`
+    fn session_cookie(res: &ServiceResponse) -> Cookie<'static> {
+        res.response().cookies()
+            .find(|cookie| cookie.name() == "sid")
+            .expect("Response does not set a session cookie")
+            .into_owned()
+    }
+
+    /// Assert that `res` is an error response with status `status` and error name `error`
+    async fn assert_error(res: ServiceResponse, status: StatusCode, error: &str) {
+        assert_eq!(res.status(), status);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["status_code"], status.as_u16());
+        assert_eq!(body["error"], error);
+        assert!(body["message"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn test_shutdown_rejects_simulations() {
+        let data = init_data();
+        let app = test::init_service(App::new()
+            .app_data(data.clone())
+            .configure(configure_services)).await;
 
-    // Initialize app data
-    let data = web::Data::new(AppData {
-        sessions: Mutex::new(OSMFSessionStorage::new()),
-        graphs,
-    });
+        assert!(data.drain.drain(Duration::from_secs(1)).await);
+        let req = test::TestRequest::post().uri("/simulate").set_json(settings("Greedy")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::SERVICE_UNAVAILABLE, "Service Unavailable").await;
+        assert_eq!(data.drain.num_running(), 0);
+    }
 
-    // Initialize and start server
-    let server = HttpServer::new(move || {
-        // Initialize cors settings
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allowed_methods(vec!["GET", "POST"])
-            .allowed_headers(vec![http::header::ACCEPT, http::header::CONTENT_TYPE,
-                                  http::header::AUTHORIZATION,])
-            .supports_credentials()
-            .max_age(3600);
-        App::new()
+    #[actix_web::test]
+    async fn test_async_simulation() {
+        let data = init_data();
+        let app = test::init_service(App::new()
             .app_data(data.clone())
-            .wrap(cors)
-            .wrap(Logger::default())
-            .service(ping)
-            .service(list_graphs)
-            .service(list_strategies)
-            .service(simulate_problem)
-            .service(display_view)
-            .service(get_sim_step_metadata)
-    });
-    server.bind((config.host.as_str(), config.port))?
-        .run()
-        .await
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let req = test::TestRequest::post().uri("/simulate?async=true").cookie(sid.clone())
+            .set_json(settings("Greedy")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+
+        // Follow the simulation step by step until it has finished
+        let mut after = 0;
+        loop {
+            let uri = format!("/stepmeta/wait?after={}&timeout=5000", after);
+            let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+            let wait: Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(wait["time"].as_u64().unwrap(), after + 1);
+            if wait["step"].is_null() {
+                assert_eq!(wait["is_finished"], true);
+                break;
+            }
+            assert!(wait["step"]["nodes_burned_by"].as_u64().unwrap() >= 3);
+            after += 1;
+
+            // Completed rounds can be viewed while the simulation is running
+            let uri = format!("/view?zoom=1&time={}", after);
+            let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            let uri = format!("/stepmeta?time={}", after);
+            let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+            let step: Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(step, wait["step"]);
+        }
+
+        // The finished simulation is attached to the session and ends at the last step
+        let mut progress = Value::Null;
+        for _ in 0..100 {
+            let req = test::TestRequest::get().uri("/simulate/progress").cookie(sid.clone()).to_request();
+            progress = test::call_and_read_body_json(&app, req).await;
+            if progress["is_finished"] == true {
+                break;
+            }
+            actix_web::rt::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(progress["is_finished"], true);
+        let uri = format!("/stepmeta?time={}", after);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let step: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(step["nodes_burned_by"].as_u64().unwrap() >= 3);
+
+        // The published steps of the idle simulation are evicted once, while its steps can
+        // still be reconstructed
+        assert_eq!(data.sessions.compact_idle_simulations(Duration::from_secs(60)), 0);
+        assert!(data.sessions.compact_idle_simulations(Duration::ZERO) > 0);
+        assert_eq!(data.sessions.compact_idle_simulations(Duration::ZERO), 0);
+        let req = test::TestRequest::get().uri("/sessions/janitor").to_request();
+        let stats: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(stats["runs"], 3);
+        assert_eq!(stats["simulations_compacted"], 1);
+        assert!(stats["reclaimed_bytes"].as_u64().unwrap() > 0);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let evicted_step: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(evicted_step, step);
+    }
+
+    #[actix_web::test]
+    async fn test_cors() {
+        let config = CorsConfig {
+            preset: CorsPreset::Production,
+            allowed_origins: vec!["https://firefighter.example.org".to_string()],
+            allowed_headers: vec!["x-custom".to_string()],
+        };
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .wrap(config.build())
+            .configure(configure_services)).await;
+
+        let preflight = |origin: &str, headers: &str| test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/ping")
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, headers))
+            .to_request();
+
+        let req = preflight("https://firefighter.example.org", "authorization,x-problem-id,x-custom");
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success());
+        assert_eq!(res.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+                   "https://firefighter.example.org");
+        assert_eq!(res.headers().get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).unwrap(), "true");
+
+        let req = preflight("https://firefighter.example.org", "x-unknown");
+        assert!(!test::call_service(&app, req).await.status().is_success());
+
+        // Origins of the development preset are not allowed in production
+        let req = preflight("http://localhost:4200", "authorization");
+        assert!(!test::call_service(&app, req).await.status().is_success());
+
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .wrap(CorsConfig::default().build())
+            .configure(configure_services)).await;
+        let req = preflight("http://localhost:4200", "authorization");
+        assert!(test::call_service(&app, req).await.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_cors_delete() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .wrap(CorsConfig::default().build())
+            .configure(configure_services)).await;
+
+        for uri in ["/graph/edits", "/graphs/bbgrund_undirected"] {
+            let req = test::TestRequest::default()
+                .method(Method::OPTIONS)
+                .uri(uri)
+                .insert_header((header::ORIGIN, "http://localhost:4200"))
+                .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "DELETE"))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert!(res.status().is_success(), "Preflight of DELETE {} failed", uri);
+            let methods = res.headers().get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap();
+            assert!(methods.to_str().unwrap().contains("DELETE"));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_full_session() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let req = test::TestRequest::get().uri("/graphs").cookie(sid.clone()).to_request();
+        let graphs: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(graphs.as_array().unwrap().iter().any(|g| g["name"] == TEST_GRAPH
+            && g["num_of_nodes"] == 350 && g["num_components"] == 5 && g["largest_component_nodes"] == 281));
+
+        let req = test::TestRequest::get().uri("/strategies").cookie(sid.clone()).to_request();
+        let strategies: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(strategies.as_array().unwrap().contains(&json!("Greedy")));
+
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(settings("Greedy")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let sim: Value = test::read_body_json(res).await;
+        let nodes_burned = sim["nodes_burned"].as_u64().unwrap();
+        let nodes_defended = sim["nodes_defended"].as_u64().unwrap();
+        let end_time = sim["end_time"].as_u64().unwrap();
+        assert_eq!(sim["nodes_total"], 350);
+        assert_eq!(sim["units"]["time"], "rounds");
+        assert_eq!(sim["units"]["distance"], "m");
+        assert_eq!(sim["end_time_minutes"].as_f64().unwrap(),
+                   end_time as f64 * sim["units"]["minutes_per_round"].as_f64().unwrap());
+        assert!(nodes_burned >= 3 && nodes_burned + nodes_defended <= 350);
+        assert_eq!(sim["view_center"].as_array().unwrap().len(), 2);
+        assert!(sim["strategy_report"]["num_executions"].as_u64().unwrap() > 0);
+        for bound in ["min_lat", "max_lat", "min_lon", "max_lon"] {
+            assert!(sim["view_bounds"][bound].is_f64());
+        }
+
+        let req = test::TestRequest::get().uri("/simulate/progress").cookie(sid.clone()).to_request();
+        let progress: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(progress["is_finished"], true);
+        assert_eq!(progress["progress"], 1.0);
+
+        let req = test::TestRequest::get().uri("/stepmeta?time=0").cookie(sid.clone()).to_request();
+        let step: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(step["nodes_burned_by"], 3);
+        assert_eq!(step["nodes_burned_at"].as_array().unwrap().len(), 3);
+
+        let uri = format!("/stepmeta?time={}", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let step: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(step["nodes_burned_by"].as_u64().unwrap(), nodes_burned);
+        assert_eq!(step["nodes_defended_by"].as_u64().unwrap(), nodes_defended);
+        assert_eq!(step["time"]["round"], end_time);
+        assert_eq!(step["time"]["minutes"], sim["end"]["minutes"]);
+
+        let uri = format!("/view?zoom=1&time={}&clat={}&clon={}", end_time,
+                          sim["view_center"][0], sim["view_center"][1]);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("content-type").unwrap(), "image/png");
+        let png = test::read_body(res).await;
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let req = test::TestRequest::get().uri("/view?zoom=2&time=0").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let uri = format!("/view?zoom=1&time={}&flow=true", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let uri = format!("/view?zoom=1&time={}", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let default_png = test::call_and_read_body(&app, req).await;
+        let uri = format!("/view?zoom=1&time={}&radius_scale=2&min_radius=4&max_radius=6", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let larger_png = test::call_and_read_body(&app, req).await;
+        assert_ne!(default_png, larger_png);
+
+        let uri = format!("/view?zoom=1&time={}&color_by_root=true", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let by_root_png = test::read_body(res).await;
+        assert_eq!(&by_root_png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let uri = format!("/view?zoom=1&time={}&exposure_radius=300", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let exposure_png = test::call_and_read_body(&app, req).await;
+        assert_eq!(&exposure_png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_ne!(exposure_png, default_png);
+
+        let uri = format!("/view?zoom=1&time={}&voronoi=true", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let voronoi_png = test::call_and_read_body(&app, req).await;
+        assert_eq!(&voronoi_png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_ne!(voronoi_png, default_png);
+
+        let uri = format!("/view?zoom=1&time={}&layers=edges,burned", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let burned_png = test::call_and_read_body(&app, req).await;
+        assert_eq!(&burned_png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_ne!(burned_png, default_png);
+        let uri = format!("/view?zoom=1&time={}&layers=edges,untouched,burned,defended,roots", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, default_png);
+        let uri = format!("/view?zoom=1&time={}&layers=burned,smoke", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+
+        let req = test::TestRequest::get().uri("/voronoi").cookie(sid.clone()).to_request();
+        let voronoi: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(voronoi["labels"].as_array().unwrap().len(), 350);
+        let regions = voronoi["regions"].as_array().unwrap();
+        assert_eq!(regions.len(), 3);
+        for region in regions {
+            let root = region["root"].as_u64().unwrap() as usize;
+            assert_eq!(voronoi["labels"][root], json!(root));
+            assert!(region["num_burned"].as_u64().unwrap() <= region["num_nodes"].as_u64().unwrap());
+        }
+
+        let req = test::TestRequest::get().uri("/view/palette").cookie(sid.clone()).to_request();
+        let palette: Value = test::call_and_read_body_json(&app, req).await;
+        let palette = palette.as_array().unwrap();
+        assert_eq!(palette.len(), 3);
+        assert!(palette.iter().all(|entry| entry["color"].as_str().unwrap().starts_with('#')));
+        assert_ne!(palette[0]["color"], palette[1]["color"]);
+
+        let req = test::TestRequest::get().uri("/corridors?k=2").cookie(sid.clone()).to_request();
+        let corridors: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(corridors.as_array().unwrap().len() <= 2);
+
+        let req = test::TestRequest::get().uri("/defended").cookie(sid.clone()).to_request();
+        let defended: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(defended["nodes"].as_array().unwrap().len() as u64, nodes_defended);
+        assert_eq!(defended["num_effective"].as_u64().unwrap()
+                       + defended["num_wasted"].as_u64().unwrap(), nodes_defended);
+
+        let req = test::TestRequest::get().uri("/simstats").cookie(sid.clone()).to_request();
+        let stats: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(stats["nodes_burned"].as_u64().unwrap(), nodes_burned);
+        assert!(stats["total_savings"].as_f64().unwrap() <= (350 - nodes_burned) as f64);
+
+        let req = test::TestRequest::get().uri("/analysis/node-importance?k=1&rounds=2&budget=2")
+            .cookie(sid.clone()).to_request();
+        let importance: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(importance["baseline_burned"].as_u64().unwrap(), nodes_burned);
+        assert!(importance["nodes"].as_array().unwrap().len() <= 2);
+
+        let req = test::TestRequest::get().uri("/burnforest").cookie(sid.clone()).to_request();
+        let forest: Value = test::call_and_read_body_json(&app, req).await;
+        let forest = forest.as_array().unwrap();
+        assert_eq!(forest.len() as u64, nodes_burned);
+        assert_eq!(forest.iter().filter(|entry| entry["parent"].is_null()).count(), 3);
+
+        let req = test::TestRequest::get().uri("/export").cookie(sid.clone()).to_request();
+        let states: Value = test::call_and_read_body_json(&app, req).await;
+        let states = states.as_array().unwrap();
+        assert_eq!(states.len(), 350);
+        assert_eq!(states.iter().filter(|state| !state["burn_time"].is_null()).count() as u64, nodes_burned);
+        assert!(states.iter().all(|state| state["burn_time"].is_null() == state["root_id"].is_null()));
+        // Burning nodes are exposed to their own smoke
+        assert!(states.iter().filter(|state| !state["burn_time"].is_null())
+            .all(|state| state["exposure"].as_f64().unwrap() > 0.0));
+
+        let req = test::TestRequest::get().uri("/export?exposure_radius=-1").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+
+        let req = test::TestRequest::get().uri("/export?format=csv").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+
+        let burned_node = step["nodes_burned_at"][0].as_u64().unwrap_or(0);
+        let uri = format!("/node/{}/timeline", burned_node);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let timeline: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(timeline["node_id"], burned_node);
+        assert!(timeline["fire_path"].is_array());
+
+        let req = test::TestRequest::get().uri("/node/350/timeline").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+
+        let req = test::TestRequest::get().uri("/view/status").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+
+        // Replaying the defense plan with the resolved settings reproduces the simulation
+        let req = test::TestRequest::get().uri("/plan").cookie(sid.clone()).to_request();
+        let plan: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(plan["graph_name"], TEST_GRAPH);
+        let mut replay_settings = sim["settings"].clone();
+        replay_settings["strategy_name"] = json!("ReplayPlan");
+        replay_settings["defense_plan"] = plan;
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(replay_settings).to_request();
+        let replay: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(replay["nodes_burned"].as_u64().unwrap(), nodes_burned);
+        assert_eq!(replay["nodes_defended"].as_u64().unwrap(), nodes_defended);
+    }
+
+    #[actix_web::test]
+    async fn test_forecast_view() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        // Without firefighters, the roots in the connected largest component have neighbors that
+        // burn later, so the danger zone of the roots is never empty
+        let mut settings = settings("Greedy");
+        settings["num_ffs"] = json!(0);
+        settings["largest_component_only"] = json!(true);
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(settings).to_request();
+        let sim: Value = test::call_and_read_body_json(&app, req).await;
+        let end_time = sim["end_time"].as_u64().unwrap();
+        assert!(sim["nodes_burned"].as_u64().unwrap() > 3);
+
+        let uri = format!("/view?zoom=1&time=0&forecast={}", end_time);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let forecast_png = test::call_and_read_body(&app, req).await;
+        let req = test::TestRequest::get().uri("/view?zoom=1&time=0").cookie(sid.clone()).to_request();
+        let start_png = test::call_and_read_body(&app, req).await;
+        assert_eq!(&forecast_png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_ne!(forecast_png, start_png);
+
+        let req = test::TestRequest::get().uri("/view?zoom=1&time=0&forecast=0").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_unknown_session() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+
+        // Requests with an unknown session id open a new session
+        let req = test::TestRequest::get().uri("/ping")
+            .cookie(Cookie::new("sid", "unknown")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_ne!(session_cookie(&res).value(), "unknown");
+    }
+
+    #[actix_web::test]
+    async fn test_no_simulation() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        for uri in ["/view?zoom=1&time=0", "/stepmeta?time=0", "/view/status", "/simulate/progress",
+                    "/defended", "/simstats", "/export", "/stepmeta/wait?after=0",
+                    "/charts/burned_over_time.png", "/view/palette", "/plan"] {
+            let req = test::TestRequest::get().uri(uri).cookie(sid.clone()).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::CONFLICT, "No Simulation").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_bad_requests() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let mut unknown_graph = settings("Greedy");
+        unknown_graph["graph_name"] = json!("unknown");
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(unknown_graph).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(settings("Unknown")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+
+        let mut too_many_roots = settings("Greedy");
+        too_many_roots["num_roots"] = json!(351);
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(too_many_roots).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::CONFLICT, "Invalid Simulation Settings").await;
+
+        let mut no_strategy_rounds = settings("Greedy");
+        no_strategy_rounds["strategy_every"] = json!(0);
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(no_strategy_rounds).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::CONFLICT, "Invalid Simulation Settings").await;
+
+        let mut no_fire_spread = settings("Greedy");
+        no_fire_spread["fire_spread_m_per_min"] = json!(0.0);
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(no_fire_spread).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::CONFLICT, "Invalid Simulation Settings").await;
+
+        let mut invalid_failures = settings("Greedy");
+        invalid_failures["defense_failure_prob"] = json!(1.5);
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(invalid_failures).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::CONFLICT, "Invalid Simulation Settings").await;
+
+        let mut invalid_predefended = settings("Greedy");
+        invalid_predefended["predefended"] = json!([usize::MAX]);
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(invalid_predefended).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::CONFLICT, "Invalid Simulation Settings").await;
+
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(settings("Random")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        for uri in ["/stepmeta", "/stepmeta?time=abc", "/stepmeta?time=-1", "/stepmeta/wait",
+                    "/stepmeta/wait?after=0&timeout=x", "/stepmeta/wait?after=18446744073709551615",
+                    "/view?time=0",
+                    "/view?zoom=1", "/view?zoom=abc&time=0", "/view?zoom=1&time=0&clat=x&clon=1",
+                    "/view?zoom=1&time=0&flow=yes", "/view?zoom=1&time=0&basemap=maybe",
+                    "/view?zoom=0&time=0", "/view?zoom=-1&time=0", "/view?zoom=1e9&time=0",
+                    "/view?zoom=NaN&time=0", "/view?zoom=1&time=0&radius_log_base=1",
+                    "/view?zoom=1&time=0&radius_scale=0", "/view?zoom=1&time=0&min_radius=5&max_radius=2",
+                    "/analysis/node-importance?budget=1000"] {
+            let req = test::TestRequest::get().uri(uri).cookie(sid.clone()).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_search() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+
+        let uri = format!("/search?graph={}&q=347", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let results: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(results.as_array().unwrap().len(), 1);
+        assert_eq!(results[0]["id"], 347);
+        assert_eq!(results[0]["matched_by"], "id");
+
+        let uri = format!("/search?graph={}&q=8684768723", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let results: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(results[0]["id"], 347);
+        assert_eq!(results[0]["matched_by"], "osm_id");
+
+        let uri = format!("/search?graph={}&q=48.676977,%209.0114947", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let results: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(results[0]["id"], 347);
+        assert_eq!(results[0]["matched_by"], "coordinates");
+        assert!(results[0]["degree"].as_u64().unwrap() > 0);
+
+        for uri in ["/search?graph=unknown&q=1", "/search?q=1", "/search?graph=bbgrund_undirected",
+                    "/search?graph=bbgrund_undirected&q=abc", "/search?graph=bbgrund_undirected&q=1,x"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_predict() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+
+        let uri = format!("/predict?graph={}&roots=0,5", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let prediction: Value = test::call_and_read_body_json(&app, req).await;
+        let burn_times = prediction["burn_times"].as_array().unwrap();
+        assert_eq!(burn_times.len(), 350);
+        assert_eq!(burn_times[0], 0);
+        assert_eq!(burn_times[5], 0);
+        assert_eq!(prediction["roots"], json!([0, 5]));
+        assert!(prediction["num_reachable"].as_u64().unwrap() >= 2);
+
+        for uri in ["/predict?graph=unknown&roots=0", "/predict?graph=bbgrund_undirected",
+                    "/predict?graph=bbgrund_undirected&roots=0,x",
+                    "/predict?graph=bbgrund_undirected&roots=350"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_frame_pregen() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let mut pregen = settings("Greedy");
+        pregen["pregen_zoom"] = json!(1.0);
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(pregen).to_request();
+        let sim: Value = test::call_and_read_body_json(&app, req).await;
+
+        // Wait until the first frame has been pre-generated
+        let mut status = Value::Null;
+        for _ in 0..100 {
+            let req = test::TestRequest::get().uri("/view/status").cookie(sid.clone()).to_request();
+            status = test::call_and_read_body_json(&app, req).await;
+            if status["frames_rendered"].as_u64().unwrap() > 0 {
+                break;
+            }
+            actix_web::rt::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(status["zoom"], 1.0);
+        assert_eq!(status["frames_total"].as_u64().unwrap(), sim["end_time"].as_u64().unwrap() + 1);
+        assert!(status["frames_rendered"].as_u64().unwrap() > 0);
+        let progress = status["progress"].as_f64().unwrap();
+        assert!(progress > 0.0 && progress <= 1.0);
+
+        let uri = format!("/view?zoom=1&time=0&clat={}&clon={}",
+                          sim["view_center"][0], sim["view_center"][1]);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_node_importance_job() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(settings("Greedy")).to_request();
+        let sim: Value = test::call_and_read_body_json(&app, req).await;
+
+        let req = test::TestRequest::post().uri("/analysis/node-importance?k=2&rounds=2&budget=4")
+            .cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        let job: Value = test::read_body_json(res).await;
+        let uri = format!("/jobs/{}", job["job_id"].as_str().unwrap());
+
+        // Wait until the job has finished
+        let mut status = Value::Null;
+        for _ in 0..100 {
+            let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+            status = test::call_and_read_body_json(&app, req).await;
+            if status["is_finished"].as_bool().unwrap() {
+                break;
+            }
+            actix_web::rt::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(status["kind"], "node-importance");
+        assert!(status["is_finished"].as_bool().unwrap());
+        assert_eq!(status["progress"]["done"], status["progress"]["total"]);
+        assert_eq!(status["result"]["baseline_burned"], sim["nodes_burned"]);
+        assert_eq!(status["result"]["num_simulations"], status["progress"]["total"]);
+
+        let req = test::TestRequest::get().uri("/jobs/unknown").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_graph_defaults() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let uri = format!("/graphs/{}/defaults", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let defaults: Value = test::call_and_read_body_json(&app, req).await;
+        let mut sim_settings = settings("Greedy");
+        for param in ["num_roots", "num_ffs", "strategy_every"] {
+            assert!(defaults[param].as_u64().unwrap() >= 1);
+            sim_settings[param] = defaults[param].clone();
+        }
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(sim_settings).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        let req = test::TestRequest::get().uri("/graphs/unknown/defaults").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_graph_info() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+
+        let uri = format!("/graphs/{}/info", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let info: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(info["name"], TEST_GRAPH);
+        assert!(info["num_edges"].as_u64().unwrap() > 0);
+        assert_eq!(info["metadata"]["license"], "ODbL-1.0");
+        // The test graphs have no sidecar files of node weights
+        assert!(info["node_weights"].is_null());
+        assert!(info["metadata"]["attribution"].as_str().unwrap().contains("OpenStreetMap"));
+
+        let req = test::TestRequest::get().uri("/graphs/unknown/info").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_validate_graph() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+
+        let uri = format!("/graphs/{}/validate", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let validation: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(validation["num_edges"].as_u64().unwrap() > 0);
+        assert_eq!(validation["dangling_edges"]["count"], 0);
+        assert!(validation["duplicate_edges"]["examples"].is_array());
+
+        let req = test::TestRequest::get().uri("/graphs/unknown/validate").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_nearest_node() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+
+        let uri = format!("/graphs/{}/nearest?lat=48.676977&lon=9.0114947", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let node: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(node["id"], 347);
+        assert_eq!(node["osm_id"], 8684768723u64);
+        assert!(node["distance_m"].as_f64().unwrap() < 10.0);
+
+        for uri in ["/graphs/unknown/nearest?lat=48.6&lon=9.0", "/graphs/bbgrund_undirected/nearest?lat=48.6",
+                    "/graphs/bbgrund_undirected/nearest?lat=x&lon=9.0",
+                    "/graphs/bbgrund_undirected/nearest?lat=91&lon=9.0"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_simulate_preview() {
+        let data = init_data();
+        let app = test::init_service(App::new()
+            .app_data(data.clone())
+            .configure(configure_services)).await;
+        let num_nodes = data.get_graph(TEST_GRAPH).unwrap().num_nodes;
+
+        let mut sim_settings = settings("Greedy");
+        sim_settings["seed"] = json!(7);
+        sim_settings["predefended"] = json!([1, 2]);
+        let req = test::TestRequest::post().uri("/simulate?preview=true").set_json(&sim_settings).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let sid = session_cookie(&res);
+        let preview: Value = test::read_body_json(res).await;
+        assert_eq!(preview["is_estimate"], true);
+        assert_eq!(preview["seed"], 7);
+        assert_eq!(preview["nodes_total"].as_u64().unwrap() as usize, num_nodes);
+        assert!(preview["nodes_burned"].as_u64().unwrap() as usize <= num_nodes);
+        let coarsened_nodes = preview["coarsened_nodes"].as_u64().unwrap() as usize;
+        assert!(coarsened_nodes > 0 && coarsened_nodes < num_nodes);
+
+        // Previews are not attached to the session
+        let req = test::TestRequest::get().uri("/simulate/progress").cookie(sid).to_request();
+        assert_error(test::call_service(&app, req).await, StatusCode::CONFLICT, "No Simulation").await;
+
+        let mut invalid = settings("Greedy");
+        invalid["num_roots"] = json!(num_nodes + 1);
+        let req = test::TestRequest::post().uri("/simulate?preview=true").set_json(&invalid).to_request();
+        assert_error(test::call_service(&app, req).await, StatusCode::CONFLICT, "Invalid Simulation Settings").await;
+        let req = test::TestRequest::post().uri("/simulate?preview=maybe").set_json(settings("Greedy")).to_request();
+        assert_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_extract_subgraph() {
+        let data = init_data();
+        let app = test::init_service(App::new()
+            .app_data(data.clone())
+            .configure(configure_services)).await;
+        let gb = data.get_graph(TEST_GRAPH).unwrap().get_grid_bounds();
+        let bounds = json!({
+            "name": "bbgrund_west",
+            "min_lat": gb.min_lat,
+            "max_lat": gb.max_lat,
+            "min_lon": gb.min_lon,
+            "max_lon": (gb.min_lon + gb.max_lon) / 2.0,
+        });
+
+        let uri = format!("/graphs/{}/subgraph", TEST_GRAPH);
+        let req = test::TestRequest::post().uri(&uri).set_json(&bounds).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let subgraph: Value = test::read_body_json(res).await;
+        assert_eq!(subgraph["name"], "bbgrund_west");
+        let num_nodes = subgraph["num_nodes"].as_u64().unwrap() as usize;
+        assert!(num_nodes > 0 && num_nodes < data.get_graph(TEST_GRAPH).unwrap().num_nodes);
+        assert_eq!(data.get_graph("bbgrund_west").unwrap().num_nodes, num_nodes);
+
+        // The subgraph can be simulated on like any other graph
+        let req = test::TestRequest::get().uri("/graphs/bbgrund_west/defaults").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+        let mut sim_settings = settings("Greedy");
+        sim_settings["graph_name"] = json!("bbgrund_west");
+        let req = test::TestRequest::post().uri("/simulate").set_json(&sim_settings).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+
+        let mut invalid_name = bounds.clone();
+        invalid_name["name"] = json!("../west");
+        let mut invalid_bounds = bounds.clone();
+        invalid_bounds["name"] = json!("bbgrund_empty");
+        invalid_bounds["min_lat"] = json!(gb.max_lat + 1.0);
+        let mut empty = invalid_bounds.clone();
+        empty["min_lat"] = json!(0.0);
+        empty["max_lat"] = json!(1.0);
+        for (uri, body) in [(uri.as_str(), &bounds), (uri.as_str(), &invalid_name), (uri.as_str(), &invalid_bounds),
+                            (uri.as_str(), &empty), ("/graphs/unknown/subgraph", &bounds)] {
+            let req = test::TestRequest::post().uri(uri).set_json(body).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_delete_and_reload_graph() {
+        let data = init_data();
+        let app = test::init_service(App::new()
+            .app_data(data.clone())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+        let req = test::TestRequest::post().uri("/graph/edits").cookie(sid.clone())
+            .set_json(json!({"graph_name": TEST_GRAPH, "remove_nodes": [1]})).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        // Graphs are only replaced while no simulations are running on them, unless forced
+        let graph = data.get_graph(TEST_GRAPH).unwrap();
+        let strategy = OSMFStrategy::from_name_and_graph("Greedy", graph.clone()).unwrap();
+        let problem = OSMFProblem::new(graph.clone(), serde_json::from_value(settings("Greedy")).unwrap(), strategy).unwrap();
+        data.sessions.get_session(sid.value()).unwrap().write().unwrap()
+            .start_simulation(problem.running_simulation());
+        let uri = format!("/graphs/{}/reload", TEST_GRAPH);
+        let res = test::call_service(&app, test::TestRequest::post().uri(&uri).to_request()).await;
+        assert_error(res, StatusCode::CONFLICT, "Conflict").await;
+        let req = test::TestRequest::delete().uri(&format!("/graphs/{}", TEST_GRAPH)).to_request();
+        assert_error(test::call_service(&app, req).await, StatusCode::CONFLICT, "Conflict").await;
+
+        let req = test::TestRequest::post().uri(&format!("{}?force=true", uri)).to_request();
+        let reloaded: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(reloaded["num_nodes"], graph.num_nodes);
+        assert!(!Arc::ptr_eq(&data.get_graph(TEST_GRAPH).unwrap(), &graph));
+        let req = test::TestRequest::get().uri(&format!("/graph/edits?graph={}", TEST_GRAPH))
+            .cookie(sid.clone()).to_request();
+        let edits: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(edits["edits"], json!([]));
+
+        // Once the simulation has finished, the graph can be deleted
+        data.sessions.get_session(sid.value()).unwrap().write().unwrap().attach_problem(problem);
+        let req = test::TestRequest::delete().uri(&format!("/graphs/{}", TEST_GRAPH)).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+        assert!(data.get_graph(TEST_GRAPH).is_none());
+        let req = test::TestRequest::get().uri(&format!("/graphs/{}/defaults", TEST_GRAPH)).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+        // Deleted graphs can be loaded again from their graph files
+        let res = test::call_service(&app, test::TestRequest::post().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(data.get_graph(TEST_GRAPH).is_some());
+
+        for req in [test::TestRequest::delete().uri("/graphs/unknown"), test::TestRequest::post().uri("/graphs/unknown/reload")] {
+            assert_error(test::call_service(&app, req.to_request()).await, StatusCode::NOT_FOUND, "Not Found").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_graph_geojson() {
+        let data = init_data();
+        let app = test::init_service(App::new()
+            .app_data(data.clone())
+            .configure(configure_services)).await;
+
+        let uri = format!("/graphs/{}/geojson", TEST_GRAPH);
+        let res = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), "application/geo+json");
+        let geojson: Value = test::read_body_json(res).await;
+        let graph = data.get_graph(TEST_GRAPH).unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+        assert_eq!(geojson["features"].as_array().unwrap().len(), graph.num_nodes + graph.num_edges);
+
+        let req = test::TestRequest::get().uri("/graphs/unknown/geojson").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_download_graph() {
+        let data = init_data();
+        let app = test::init_service(App::new()
+            .app_data(data.clone())
+            .configure(configure_services)).await;
+        let graph = data.get_graph(TEST_GRAPH).unwrap();
+
+        let uri = format!("/graphs/{}/download", TEST_GRAPH);
+        let res = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let disposition = res.headers().get(header::CONTENT_DISPOSITION).unwrap().to_str().unwrap();
+        assert!(disposition.contains(&format!("{}.fmi", TEST_GRAPH)));
+        let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        let node = &graph.nodes()[0];
+        assert!(body.lines().any(|line| line == format!("0 {} {} {} 0", node.osm_id, node.lat, node.lon)));
+
+        let uri = format!("/graphs/{}/download?anonymize=jitter&jitter=50&seed=3", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let jittered = test::call_and_read_body(&app, req).await;
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, jittered);
+        let jittered = String::from_utf8(jittered.to_vec()).unwrap();
+        assert!(jittered.starts_with("# Type : maxspeed\n# Anonymized : jitter\n"));
+        assert!(!jittered.contains(&node.osm_id.to_string()));
+        assert_eq!(jittered.lines().count(), body.lines().count() - 2);
+
+        for uri in &["/graphs/unknown/download", "/graphs/bbgrund_undirected/download?anonymize=shuffle",
+                     "/graphs/bbgrund_undirected/download?anonymize=jitter&jitter=0"] {
+            let res = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_graph_edits() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+        let simulate = |sid: Cookie<'static>| test::TestRequest::post().uri("/simulate").cookie(sid)
+            .set_json(settings("Greedy")).to_request();
+        let sim: Value = test::call_and_read_body_json(&app, simulate(sid.clone())).await;
+        let num_nodes = sim["nodes_total"].as_u64().unwrap();
+
+        let req = test::TestRequest::post().uri("/graph/edits").cookie(sid.clone())
+            .set_json(json!({
+                "graph_name": TEST_GRAPH,
+                "add_nodes": [{"lat": 48.675, "lon": 9.01}],
+                "remove_nodes": [1],
+                "add_edges": [{"src": num_nodes, "tgt": 0}],
+            })).to_request();
+        let edits: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(edits["num_nodes"], num_nodes + 1);
+        assert_eq!(edits["added_node_ids"], json!([num_nodes]));
+        assert_eq!(edits["removed_nodes"], json!([1]));
+
+        // Edits apply to following simulations of the session only
+        let sim: Value = test::call_and_read_body_json(&app, simulate(sid.clone())).await;
+        assert_eq!(sim["nodes_total"], num_nodes + 1);
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let other_sid = session_cookie(&res);
+        let sim: Value = test::call_and_read_body_json(&app, simulate(other_sid)).await;
+        assert_eq!(sim["nodes_total"], num_nodes);
+
+        let uri = format!("/graph/edits?graph={}", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let edits: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(edits["edits"].as_array().unwrap().len(), 1);
+
+        // Invalid edits are rejected
+        for body in [
+            json!({"graph_name": "unknown"}),
+            json!({"graph_name": TEST_GRAPH, "add_edges": [{"src": 0, "tgt": num_nodes + 5}]}),
+            json!({"graph_name": TEST_GRAPH, "add_edges": [{"src": 0, "tgt": 1}]}),
+        ] {
+            let req = test::TestRequest::post().uri("/graph/edits").cookie(sid.clone())
+                .set_json(body).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+
+        let req = test::TestRequest::delete().uri(&uri).cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        let sim: Value = test::call_and_read_body_json(&app, simulate(sid.clone())).await;
+        assert_eq!(sim["nodes_total"], num_nodes);
+    }
+
+    #[actix_web::test]
+    async fn test_spread_calibration() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+
+        let uri = format!("/graphs/{}/calibration?bins=10&fire_spread=2&response_minutes=5,30", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let calibration: Value = test::call_and_read_body_json(&app, req).await;
+        let num_edges = calibration["num_edges"].as_u64().unwrap();
+        let bins = calibration["bins"].as_array().unwrap();
+        assert_eq!(bins.len(), 10);
+        assert_eq!(bins.iter().map(|bin| bin["count"].as_u64().unwrap()).sum::<u64>(), num_edges);
+        assert!(bins[9]["max"].as_u64().unwrap() > calibration["max_weight"].as_u64().unwrap());
+        let suggestions = calibration["suggestions"].as_array().unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0]["strategy_every"], 10);
+        assert_eq!(suggestions[1]["strategy_every"], 60);
+
+        let uri = format!("/graphs/{}/calibration", TEST_GRAPH);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let calibration: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(calibration["bins"].as_array().unwrap().len(), DEFAULT_HISTOGRAM_BINS);
+        assert_eq!(calibration["suggestions"].as_array().unwrap().len(), DEFAULT_RESPONSE_MINUTES.len());
+
+        for query in ["bins=0", "bins=x", "fire_spread=-1", "response_minutes=5,x", "response_minutes=0"] {
+            let uri = format!("/graphs/{}/calibration?{}", TEST_GRAPH, query);
+            let req = test::TestRequest::get().uri(&uri).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+        let req = test::TestRequest::get().uri("/graphs/unknown/calibration").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_dry_run() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let req = test::TestRequest::post().uri("/simulate/dryrun").cookie(sid.clone())
+            .set_json(settings("Greedy")).to_request();
+        let dry_run: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(dry_run["num_nodes"], 350);
+        assert!(dry_run["estimated_memory_bytes"].as_u64().unwrap() > 0);
+        assert_eq!(dry_run["num_past_runs"], 0);
+        assert!(dry_run["estimated_runtime_millis"].is_null());
+
+        // Past runs with other strategies on the same graph are used as a fallback
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(settings("Random")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let req = test::TestRequest::post().uri("/simulate/dryrun").cookie(sid.clone())
+            .set_json(settings("Greedy")).to_request();
+        let dry_run: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(dry_run["num_past_runs"], 1);
+        assert!(dry_run["estimated_runtime_millis"].as_f64().unwrap() >= 0.0);
+
+        let mut too_many_roots = settings("Greedy");
+        too_many_roots["num_roots"] = json!(351);
+        let req = test::TestRequest::post().uri("/simulate/dryrun").cookie(sid.clone())
+            .set_json(too_many_roots).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::CONFLICT, "Invalid Simulation Settings").await;
+
+        let req = test::TestRequest::post().uri("/simulate/dryrun").cookie(sid.clone())
+            .set_json(settings("Unknown")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_history() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let mut seeded = settings("Greedy");
+        seeded["seed"] = json!(7);
+        let mut ids = Vec::new();
+        for settings in [seeded.clone(), seeded, settings("Random")] {
+            let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+                .set_json(settings).to_request();
+            let sim: Value = test::call_and_read_body_json(&app, req).await;
+            ids.push(sim["simulation_id"].as_i64().unwrap());
+        }
+        assert!(ids[0] < ids[1] && ids[1] < ids[2]);
+
+        let req = test::TestRequest::get().uri("/history").cookie(sid.clone()).to_request();
+        let history: Value = test::call_and_read_body_json(&app, req).await;
+        let history = history.as_array().unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0]["id"].as_i64().unwrap(), ids[2]);
+        assert_eq!(history[1]["seed"], 7);
+        assert_eq!(history[1]["result_hash"], history[2]["result_hash"]);
+        assert_eq!(history[1]["settings"]["seed"], 7);
+        assert_eq!(history[1]["settings"]["strategy_name"], "Greedy");
+
+        let req = test::TestRequest::get().uri("/history?strategy=Greedy&limit=1")
+            .cookie(sid.clone()).to_request();
+        let history: Value = test::call_and_read_body_json(&app, req).await;
+        let history = history.as_array().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0]["id"].as_i64().unwrap(), ids[1]);
+        assert_eq!(history[0]["graph_name"], TEST_GRAPH);
+
+        let req = test::TestRequest::get().uri("/history?graph=unknown").cookie(sid.clone()).to_request();
+        let history: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(history.as_array().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_audit() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(settings("Greedy")).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+        let req = test::TestRequest::get().uri("/audit").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+
+        let req = test::TestRequest::post().uri("/simulate?audit=true").cookie(sid.clone())
+            .set_json(settings("Greedy")).to_request();
+        let sim: Value = test::call_and_read_body_json(&app, req).await;
+        let req = test::TestRequest::get().uri("/audit").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().contains_key(header::CONTENT_DISPOSITION));
+        let mut log: Value = test::read_body_json(res).await;
+        assert_eq!(log["ignitions"].as_array().unwrap().len() as u64, sim["nodes_burned"].as_u64().unwrap());
+        assert_eq!(log["ignitions"][0]["cause"], "root");
+
+        let req = test::TestRequest::post().uri("/audit/verify").cookie(sid.clone())
+            .set_json(&log).to_request();
+        let report: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(report["is_consistent"], true);
+        assert_eq!(report["num_divergences"], 0);
+
+        log["ignitions"][0]["round"] = json!(1);
+        let req = test::TestRequest::post().uri("/audit/verify").cookie(sid.clone())
+            .set_json(&log).to_request();
+        let report: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(report["is_consistent"], false);
+        assert_eq!(report["first_divergent_round"], 0);
+    }
+
+    #[actix_web::test]
+    async fn test_strategy_stats() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let mut more_ffs = settings("Greedy");
+        more_ffs["num_ffs"] = json!(5);
+        let mut saved_ratios = Vec::new();
+        for settings in [settings("Greedy"), settings("Greedy"), settings("Random"), more_ffs] {
+            let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+                .set_json(settings).to_request();
+            let sim: Value = test::call_and_read_body_json(&app, req).await;
+            let burned = sim["nodes_burned"].as_f64().unwrap();
+            saved_ratios.push(1.0 - burned / sim["nodes_total"].as_f64().unwrap());
+        }
+
+        let uri = format!("/graphs/{}/strategy-stats", TEST_GRAPH);
+        let stats: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        let stats = stats.as_array().unwrap();
+        assert_eq!(stats.len(), 3);
+        // Buckets of 2-3 firefighters precede those of 4-7 firefighters
+        assert_eq!((&stats[2]["strategy_name"], &stats[2]["min_ffs"], &stats[2]["max_ffs"]), (&json!("Greedy"), &json!(4), &json!(7)));
+        let greedy = stats.iter().find(|stats| stats["strategy_name"] == "Greedy" && stats["min_ffs"] == 2).unwrap();
+        assert_eq!((&greedy["runs"], &greedy["min_roots"], &greedy["max_roots"]), (&json!(2), &json!(2), &json!(3)));
+        let mean = (saved_ratios[0] + saved_ratios[1]) / 2.0;
+        assert!((greedy["mean_saved_ratio"].as_f64().unwrap() - mean).abs() < 1e-9);
+        let variance = (saved_ratios[0] - mean).powi(2) + (saved_ratios[1] - mean).powi(2);
+        assert!((greedy["saved_ratio_variance"].as_f64().unwrap() - variance).abs() < 1e-9);
+        let random = stats.iter().find(|stats| stats["strategy_name"] == "Random").unwrap();
+        assert!(random["saved_ratio_variance"].is_null());
+        assert!(stats[0]["mean_saved_ratio"].as_f64() >= stats[1]["mean_saved_ratio"].as_f64());
+
+        let req = test::TestRequest::get().uri(&format!("{}?num_roots=2&num_ffs=6", uri)).to_request();
+        let stats: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(stats.as_array().unwrap().len(), 1);
+        assert_eq!(stats[0]["runs"], 1);
+
+        let req = test::TestRequest::get().uri("/graphs/unknown/strategy-stats").to_request();
+        assert_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_recommend_strategies() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+        let uri = format!("/recommend?graph={}&num_roots=3&num_ffs=2", TEST_GRAPH);
+
+        // Without recorded simulations, all strategies are ranked by previews
+        let recommend: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(recommend["bucket"]["min_ffs"], 2);
+        let strategies = recommend["strategies"].as_array().unwrap();
+        let names: Vec<_> = strategies.iter().map(|strategy| strategy["strategy_name"].as_str().unwrap()).collect();
+        assert!(names.contains(&"Greedy") && !names.contains(&"ReplayPlan") && !names.contains(&"Ensemble"));
+        for (i, strategy) in strategies.iter().enumerate() {
+            assert_eq!(strategy["rank"], i + 1);
+            assert_eq!((&strategy["source"], &strategy["confidence"]), (&json!("preview"), &json!("low")));
+        }
+        assert!(strategies.windows(2)
+            .all(|pair| pair[0]["expected_saved_ratio"].as_f64() >= pair[1]["expected_saved_ratio"].as_f64()));
+
+        // Recorded simulations in the same parameter bucket replace the previews
+        for _ in 0..3 {
+            let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+                .set_json(settings("Greedy")).to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+        }
+        let req = test::TestRequest::get().uri(&format!("/recommend?graph={}&num_roots=2&num_ffs=3", TEST_GRAPH)).to_request();
+        let recommend: Value = test::call_and_read_body_json(&app, req).await;
+        let greedy = recommend["strategies"].as_array().unwrap().iter()
+            .find(|strategy| strategy["strategy_name"] == "Greedy").unwrap();
+        assert_eq!((&greedy["source"], &greedy["runs"]), (&json!("history"), &json!(3)));
+        assert!(greedy["expected_simulation_time_millis"].is_number());
+        let req = test::TestRequest::get().uri(&format!("/recommend?graph={}&num_roots=3&num_ffs=4", TEST_GRAPH)).to_request();
+        let recommend: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(recommend["strategies"].as_array().unwrap().iter().all(|strategy| strategy["source"] == "preview"));
+
+        for uri in ["/recommend?graph=unknown&num_roots=3&num_ffs=2", "/recommend?graph=bbgrund_undirected&num_roots=3"] {
+            let res = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+        let req = test::TestRequest::get().uri("/recommend?graph=bbgrund_undirected&num_roots=351&num_ffs=2").to_request();
+        assert_error(test::call_service(&app, req).await, StatusCode::CONFLICT, "Invalid Simulation Settings").await;
+    }
+
+    #[actix_web::test]
+    async fn test_compare_simulations() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let mut seeded = settings("Greedy");
+        seeded["seed"] = json!(7);
+        let mut ids = Vec::new();
+        for settings in [seeded.clone(), seeded, settings("Random")] {
+            let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+                .set_json(settings).to_request();
+            let sim: Value = test::call_and_read_body_json(&app, req).await;
+            ids.push(sim["simulation_id"].as_i64().unwrap());
+        }
+
+        let uri = format!("/compare?ids={},{},{}", ids[2], ids[0], ids[1]);
+        let req = test::TestRequest::get().uri(&uri).cookie(sid.clone()).to_request();
+        let comparison: Value = test::call_and_read_body_json(&app, req).await;
+        let runs = comparison["runs"].as_array().unwrap();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0]["id"].as_i64().unwrap(), ids[2]);
+        assert_eq!(runs[0]["strategy_name"], "Random");
+        assert!(runs[1]["containment_round"].as_u64().unwrap() > 0);
+
+        // Identical simulations burn the same nodes
+        let overlap = &comparison["burned_overlap"];
+        let nodes_burned = runs[1]["nodes_burned"].as_u64().unwrap();
+        assert_eq!(overlap[1][2]["shared"].as_u64().unwrap(), nodes_burned);
+        assert_eq!(overlap[1][2]["differing"], 0);
+        assert_eq!(overlap[1][2]["jaccard"], 1.0);
+        let jaccard = overlap[0][1]["jaccard"].as_f64().unwrap();
+        assert!((0.0..=1.0).contains(&jaccard));
+        assert_eq!(overlap[0][1]["shared"], overlap[1][0]["shared"]);
+
+        for uri in ["/compare", "/compare?ids=1,x", "/compare?ids=999999"] {
+            let req = test::TestRequest::get().uri(uri).cookie(sid.clone()).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_annotate_simulation() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+                .set_json(settings("Greedy")).to_request();
+            let sim: Value = test::call_and_read_body_json(&app, req).await;
+            ids.push(sim["simulation_id"].as_i64().unwrap());
+        }
+
+        let uri = format!("/simulations/{}", ids[0]);
+        let req = test::TestRequest::patch().uri(&uri).cookie(sid.clone())
+            .set_json(json!({"notes": "baseline run", "tags": ["baseline", " greedy "]})).to_request();
+        let entry: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(entry["notes"], "baseline run");
+        assert_eq!(entry["tags"], json!(["baseline", "greedy"]));
+
+        // Only the given fields are replaced
+        let req = test::TestRequest::patch().uri(&uri).cookie(sid.clone())
+            .set_json(json!({"tags": ["final"]})).to_request();
+        let entry: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(entry["notes"], "baseline run");
+        assert_eq!(entry["tags"], json!(["final"]));
+
+        let req = test::TestRequest::get().uri("/history?tag=final").cookie(sid.clone()).to_request();
+        let history: Value = test::call_and_read_body_json(&app, req).await;
+        let history = history.as_array().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0]["id"].as_i64().unwrap(), ids[0]);
+
+        let req = test::TestRequest::get().uri("/history").cookie(sid.clone()).to_request();
+        let history: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(history[0]["notes"].is_null());
+        assert_eq!(history[0]["tags"], json!([]));
+
+        let req = test::TestRequest::patch().uri(&uri).cookie(sid.clone())
+            .set_json(json!({"tags": ["ok", " "]})).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+
+        let req = test::TestRequest::patch().uri("/simulations/9999").cookie(sid.clone())
+            .set_json(json!({"notes": "x"})).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::NOT_FOUND, "Not Found").await;
+    }
+
+    #[actix_web::test]
+    async fn test_batch_report() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        // Every simulation of the batch is tagged with the batch id
+        for strategy in ["Greedy", "Priority"] {
+            let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+                .set_json(settings(strategy)).to_request();
+            let sim: Value = test::call_and_read_body_json(&app, req).await;
+            let uri = format!("/simulations/{}", sim["simulation_id"]);
+            let req = test::TestRequest::patch().uri(&uri).cookie(sid.clone())
+                .set_json(json!({"tags": ["batch-1"]})).to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+        }
+
+        // Reports are stored as artifacts, which are identified by their content
+        let req = test::TestRequest::get().uri("/report/batch-1").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        let location = res.headers().get(header::LOCATION).unwrap().to_str().unwrap().to_string();
+        let artifact: Value = test::read_body_json(res).await;
+        assert_eq!(artifact["url"], location.as_str());
+        let req = test::TestRequest::get().uri("/report/batch-1").cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get(header::LOCATION).unwrap().to_str().unwrap(), location);
+
+        let req = test::TestRequest::get().uri(&location).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(header::CONTENT_TYPE).unwrap().to_str().unwrap().starts_with("text/html"));
+        let html = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert_eq!(html.len() as u64, artifact["size"].as_u64().unwrap());
+        assert!(html.contains("<h1>Strategy comparison of batch batch-1</h1>"));
+        assert!(html.contains("<td>Greedy</td><td>1</td>"));
+        assert!(html.contains("<td>Priority</td><td>1</td>"));
+
+        // Downloads can be resumed
+        let req = test::TestRequest::get().uri(&location)
+            .insert_header((header::RANGE, "bytes=10-")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(test::read_body(res).await, html.as_bytes()[10..]);
+
+        for uri in ["/artifacts/unknown", "/artifacts/0123456789abcdef0123456789abcdef.html",
+                    "/artifacts/..%2Fconfig.json"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::NOT_FOUND, "Not Found").await;
+        }
+
+        // Configurations on the Pareto front do not dominate each other
+        let req = test::TestRequest::get().uri("/report/batch-1/pareto").cookie(sid.clone()).to_request();
+        let pareto: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(pareto["objectives"].as_array().unwrap().len(), 4);
+        let front = pareto["pareto_front"].as_array().unwrap();
+        assert!(!front.is_empty() && front.len() <= 2);
+        assert!(front.iter().all(|config| config["num_runs"] == 1 && config["num_ffs"] == 2));
+
+        for uri in ["/report/batch-2", "/report/batch-2/pareto"] {
+            let req = test::TestRequest::get().uri(uri).cookie(sid.clone()).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_charts() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .configure(configure_services)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/ping").to_request()).await;
+        let sid = session_cookie(&res);
+
+        let req = test::TestRequest::post().uri("/simulate").cookie(sid.clone())
+            .set_json(settings("Greedy")).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+
+        for uri in ["/charts/burned_over_time.png", "/charts/strategy_comparison.png",
+                    "/charts/strategy_comparison.png?metric=runtime&graph=bbgrund_undirected"] {
+            let req = test::TestRequest::get().uri(uri).cookie(sid.clone()).to_request();
+            let res = test::call_service(&app, req).await;
+            if cfg!(feature = "charts") {
+                assert_eq!(res.status(), StatusCode::OK);
+                assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), "image/png");
+            } else {
+                assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+            }
+        }
+
+        let req = test::TestRequest::get().uri("/charts/strategy_comparison.png?metric=area")
+            .cookie(sid.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_error(res, StatusCode::BAD_REQUEST, "Bad Request").await;
+    }
+
+    #[actix_web::test]
+    async fn test_localized_errors() {
+        let app = test::init_service(App::new()
+            .app_data(init_data())
+            .wrap(i18n::localize_errors())
+            .configure(configure_services)).await;
+
+        let req = test::TestRequest::get().uri("/graphs/unknown/info").to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.headers().get(header::CONTENT_LANGUAGE).unwrap() == "en");
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "BAD_REQUEST");
+        assert_eq!(body["error"], "Bad Request");
+        assert_eq!(body["message"], "Unknown graph: 'unknown'");
+
+        // Error codes are the same in every language
+        let req = test::TestRequest::get().uri("/graphs/unknown/info")
+            .insert_header((header::ACCEPT_LANGUAGE, "de-DE,de;q=0.9,en;q=0.8")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(res.headers().get(header::CONTENT_LANGUAGE).unwrap() == "de");
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "BAD_REQUEST");
+        assert_eq!(body["error"], "Ungültige Anfrage");
+        assert_eq!(body["message"], "Unbekannter Graph: 'unknown'");
+    }
 }
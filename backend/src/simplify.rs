@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+
+use crate::graph::{Graph, GraphView};
+
+/// Type alias for a latitude/longitude point
+pub(crate) type Point = (f64, f64);
+
+/// Type alias for a straight line segment between two points
+pub(crate) type Segment = (Point, Point);
+
+/// Zoom levels for which levels of detail are precomputed, ordered from finest to coarsest
+pub(crate) const LOD_ZOOMS: [f64; 2] = [4.0, 1.0];
+
+/// Nominal view width in pixels that is used to derive the simplification tolerances
+const LOD_NOMINAL_WIDTH: f64 = 1920.0;
+
+/// Nominal view height in pixels that is used to derive the simplification tolerances
+const LOD_NOMINAL_HEIGHT: f64 = 1080.0;
+
+/// Dead end chains shorter than this number of tolerances are dropped entirely
+const STUB_TOLERANCE_FACTOR: f64 = 4.0;
+
+/// Road classes of the OsmGraphCreator car profile, i.e. living streets, service roads and
+/// turning circles, whose chains are dropped at the coarsest level of detail
+const MINOR_ROAD_CLASSES: [u32; 3] = [13, 15, 16];
+
+/// A simplified representation of the edges of a graph
+#[derive(Debug)]
+pub(crate) struct LodLevel {
+    /// Maximum deviation in degrees between the simplified and the original edges
+    pub tolerance: f64,
+    pub segments: Vec<Segment>,
+}
+
+/// Normalize an undirected edge between `a` and `b`
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Get the distinct neighbours of the node with id `node_id`
+fn neighbours(graph: &Graph, node_id: usize) -> Vec<usize> {
+    let mut neighbours: Vec<_> = graph.get_outgoing_edges(node_id).iter()
+        .map(|edge| edge.tgt())
+        .filter(|&tgt| tgt != node_id)
+        .collect();
+    neighbours.sort_unstable();
+    neighbours.dedup();
+    neighbours
+}
+
+/// Check whether all edges along `chain` belong to one of the `MINOR_ROAD_CLASSES`.
+/// Edges without a road class are never considered minor.
+fn is_minor_chain(graph: &Graph, chain: &[usize]) -> bool {
+    chain.windows(2).all(|w| graph.get_outgoing_edges(w[0]).iter()
+        .filter(|edge| edge.tgt() == w[1])
+        .all(|edge| MINOR_ROAD_CLASSES.contains(&edge.attrs.edge_type)))
+}
+
+/// Perpendicular distance between point `p` and the line through `a` and `b`
+fn perpendicular_dist(p: Point, a: Point, b: Point) -> f64 {
+    let (d_lat, d_lon) = (b.0 - a.0, b.1 - a.1);
+    let len = (d_lat * d_lat + d_lon * d_lon).sqrt();
+    if len == 0.0 {
+        ((p.0 - a.0).powi(2) + (p.1 - a.1).powi(2)).sqrt()
+    } else {
+        (d_lon * (p.0 - a.0) - d_lat * (p.1 - a.1)).abs() / len
+    }
+}
+
+/// Simplify a polyline using the Douglas-Peucker algorithm with tolerance `tolerance`
+fn douglas_peucker(points: &[Point], tolerance: f64) -> Vec<Point> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let (max_index, max_dist) = points[1..points.len() - 1].iter()
+        .enumerate()
+        .map(|(i, &p)| (i + 1, perpendicular_dist(p, first, last)))
+        .fold((0, 0.0), |max, cur| if cur.1 > max.1 { cur } else { max });
+
+    if max_dist > tolerance {
+        let mut left = douglas_peucker(&points[..=max_index], tolerance);
+        let right = douglas_peucker(&points[max_index..], tolerance);
+        left.pop();
+        left.extend(right);
+        left
+    } else {
+        vec![first, last]
+    }
+}
+
+/// Length of a polyline in degrees
+fn polyline_len(points: &[Point]) -> f64 {
+    points.windows(2)
+        .map(|w| ((w[1].0 - w[0].0).powi(2) + (w[1].1 - w[0].1).powi(2)).sqrt())
+        .sum()
+}
+
+/// Decompose `graph` into chains, i.e. paths whose inner nodes have exactly two distinct
+/// neighbours. Every undirected edge of the graph is part of exactly one chain.
+pub(crate) fn compute_chains(graph: &Graph) -> Vec<Vec<usize>> {
+    let all_neighbours: Vec<_> = (0..graph.num_nodes)
+        .map(|node_id| neighbours(graph, node_id))
+        .collect();
+    let is_chain_node = |node_id: usize| all_neighbours[node_id].len() == 2;
+
+    let mut visited = HashSet::with_capacity(graph.num_edges / 2);
+    let mut chains = Vec::new();
+
+    let mut walk = |start: usize, first: usize, visited: &mut HashSet<(usize, usize)>| {
+        let mut chain = vec![start];
+        let (mut prev, mut cur) = (start, first);
+        while visited.insert(edge_key(prev, cur)) {
+            chain.push(cur);
+            if !is_chain_node(cur) || cur == start {
+                break;
+            }
+            let next = all_neighbours[cur].iter()
+                .find(|&&n| n != prev)
+                .copied()
+                .unwrap_or(prev);
+            prev = cur;
+            cur = next;
+        }
+        if chain.len() > 1 {
+            chains.push(chain);
+        }
+    };
+
+    // Chains between junctions and dead ends
+    for node_id in (0..graph.num_nodes).filter(|&n| !is_chain_node(n)) {
+        for &next in &all_neighbours[node_id] {
+            walk(node_id, next, &mut visited);
+        }
+    }
+    // Remaining isolated cycles only consist of chain nodes
+    for node_id in (0..graph.num_nodes).filter(|&n| is_chain_node(n)) {
+        for &next in &all_neighbours[node_id] {
+            walk(node_id, next, &mut visited);
+        }
+    }
+
+    chains
+}
+
+/// Compute the levels of detail for `graph`, ordered from finest to coarsest.
+/// Each level contracts chains of degree-2 nodes and simplifies them with a tolerance of half a
+/// pixel at the respective zoom level of `LOD_ZOOMS`. The coarsest level additionally drops
+/// short dead end chains and chains of minor roads.
+/// Levels of detail only serve rendering, so they are computed when the backend loads a graph
+/// rather than by graph_tool, which writes graph files that do not store them.
+pub(crate) fn compute_lod_levels(graph: &Graph) -> Vec<LodLevel> {
+    let gb = graph.get_grid_bounds();
+    let deg_per_px = ((gb.max_lon - gb.min_lon) / LOD_NOMINAL_WIDTH)
+        .min((gb.max_lat - gb.min_lat) / LOD_NOMINAL_HEIGHT);
+
+    let chains: Vec<_> = compute_chains(graph).into_iter()
+        .map(|chain| {
+            let is_stub = graph.get_node_degree(chain[0]) <= 1
+                || graph.get_node_degree(chain[chain.len() - 1]) <= 1;
+            let is_minor = is_minor_chain(graph, &chain);
+            let points: Vec<_> = chain.iter()
+                .map(|&node_id| {
+                    let node = graph.get_node(node_id);
+                    (node.lat, node.lon)
+                })
+                .collect();
+            (is_stub, is_minor, points)
+        })
+        .collect();
+
+    LOD_ZOOMS.iter()
+        .enumerate()
+        .map(|(i, &zoom)| {
+            let tolerance = deg_per_px / zoom / 2.0;
+            let is_coarsest = i == LOD_ZOOMS.len() - 1;
+            let segments: Vec<_> = chains.iter()
+                .filter(|(is_stub, is_minor, points)| !(is_coarsest && (*is_minor || *is_stub
+                    && polyline_len(points) < STUB_TOLERANCE_FACTOR * tolerance)))
+                .flat_map(|(_, _, points)| {
+                    douglas_peucker(points, tolerance).windows(2)
+                        .map(|w| (w[0], w[1]))
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            log::debug!("Computed level of detail with tolerance {} and {} segments",
+                tolerance, segments.len());
+            LodLevel {
+                tolerance,
+                segments,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::graph::{Graph, GraphView};
+    use crate::simplify::{compute_chains, compute_lod_levels, douglas_peucker, is_minor_chain};
+
+    #[test]
+    fn test_chains_cover_edges() {
+        let graph =
+            Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+
+        let mut chain_edges = HashSet::new();
+        for chain in compute_chains(&graph) {
+            for w in chain.windows(2) {
+                assert!(chain_edges.insert((w[0].min(w[1]), w[0].max(w[1]))));
+            }
+        }
+        let graph_edges: HashSet<_> = graph.edges().iter()
+            .filter(|e| e.src() != e.tgt())
+            .map(|e| (e.src().min(e.tgt()), e.src().max(e.tgt())))
+            .collect();
+        assert_eq!(chain_edges, graph_edges);
+    }
+
+    #[test]
+    fn test_lod_levels() {
+        let graph =
+            Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+
+        let levels = compute_lod_levels(&graph);
+        assert_eq!(levels.len(), 2);
+        assert!(levels[0].tolerance < levels[1].tolerance);
+        assert!(levels[0].segments.len() >= levels[1].segments.len());
+        assert!(levels[0].segments.len() < graph.num_edges);
+    }
+
+    #[test]
+    fn test_lod_drops_minor_roads() {
+        let graph =
+            Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+
+        let levels = compute_lod_levels(&graph);
+        let minor_segments: Vec<_> = compute_chains(&graph).into_iter()
+            .filter(|chain| is_minor_chain(&graph, chain))
+            .flat_map(|chain| {
+                let points: Vec<_> = chain.iter()
+                    .map(|&node_id| (graph.get_node(node_id).lat, graph.get_node(node_id).lon))
+                    .collect();
+                douglas_peucker(&points, levels[1].tolerance).windows(2)
+                    .map(|w| (w[0], w[1]))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert!(!minor_segments.is_empty());
+        assert!(minor_segments.iter().all(|segment| !levels[1].segments.contains(segment)));
+    }
+
+    #[test]
+    fn test_douglas_peucker() {
+        let points = vec![(0.0, 0.0), (1.0, 0.1), (2.0, -0.1), (3.0, 5.0), (4.0, 6.0)];
+        let simplified = douglas_peucker(&points, 0.5);
+        assert_eq!(simplified.first(), points.first());
+        assert_eq!(simplified.last(), points.last());
+        assert!(simplified.contains(&(3.0, 5.0)));
+        assert!(!simplified.contains(&(1.0, 0.1)));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::graph::{GridBounds, Node};
+
+/// Static grid over the coordinates of the nodes of a graph for nearest-node and bounding box
+/// queries. The grid has about as many cells as nodes, and the node ids of every cell are stored
+/// consecutively ordered by cell.
+#[derive(Debug, Default)]
+pub(crate) struct SpatialIndex {
+    min_lat: f64,
+    min_lon: f64,
+    cell_lat: f64,
+    cell_lon: f64,
+    num_rows: usize,
+    num_cols: usize,
+    /// Offsets of the node ids of every cell into `node_ids`, ordered by row and column
+    cell_offsets: Vec<u32>,
+    node_ids: Vec<u32>,
+}
+
+impl SpatialIndex {
+    /// Build the spatial index over the nodes `nodes`
+    pub(crate) fn new(nodes: &[Node]) -> Self {
+        if nodes.is_empty() {
+            return Self::default();
+        }
+
+        let (mut min_lat, mut max_lat) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut min_lon, mut max_lon) = (f64::INFINITY, f64::NEG_INFINITY);
+        for node in nodes {
+            min_lat = min_lat.min(node.lat);
+            max_lat = max_lat.max(node.lat);
+            min_lon = min_lon.min(node.lon);
+            max_lon = max_lon.max(node.lon);
+        }
+        let num_cells_per_axis = ((nodes.len() as f64).sqrt().ceil() as usize).max(1);
+        // Cells must not be empty, even if all nodes share a latitude or longitude
+        let cell_lat = ((max_lat - min_lat) / num_cells_per_axis as f64).max(f64::EPSILON);
+        let cell_lon = ((max_lon - min_lon) / num_cells_per_axis as f64).max(f64::EPSILON);
+        let mut index = Self {
+            min_lat,
+            min_lon,
+            cell_lat,
+            cell_lon,
+            num_rows: ((max_lat - min_lat) / cell_lat) as usize + 1,
+            num_cols: ((max_lon - min_lon) / cell_lon) as usize + 1,
+            cell_offsets: Vec::new(),
+            node_ids: Vec::new(),
+        };
+
+        // Sort the node ids by cell with a counting sort
+        let cells: Vec<_> = nodes.iter().map(|node| index.cell_of(node.lat, node.lon)).collect();
+        let mut cell_offsets = vec![0u32; index.num_rows * index.num_cols + 1];
+        for &cell in &cells {
+            cell_offsets[cell + 1] += 1;
+        }
+        for i in 1..cell_offsets.len() {
+            cell_offsets[i] += cell_offsets[i - 1];
+        }
+        let mut next = cell_offsets.clone();
+        let mut node_ids = vec![0u32; nodes.len()];
+        for (node_id, &cell) in cells.iter().enumerate() {
+            node_ids[next[cell] as usize] = node_id as u32;
+            next[cell] += 1;
+        }
+
+        index.cell_offsets = cell_offsets;
+        index.node_ids = node_ids;
+        index
+    }
+
+    /// Get the row of the latitude `lat`, clamped to the grid
+    fn row_of(&self, lat: f64) -> usize {
+        (((lat - self.min_lat) / self.cell_lat).max(0.0) as usize).min(self.num_rows - 1)
+    }
+
+    /// Get the column of the longitude `lon`, clamped to the grid
+    fn col_of(&self, lon: f64) -> usize {
+        (((lon - self.min_lon) / self.cell_lon).max(0.0) as usize).min(self.num_cols - 1)
+    }
+
+    /// Get the index of the cell that contains the coordinates `lat`/`lon`, clamped to the grid
+    fn cell_of(&self, lat: f64, lon: f64) -> usize {
+        self.row_of(lat) * self.num_cols + self.col_of(lon)
+    }
+
+    /// Get the ids of the nodes in the cell at row `row` and column `col`
+    fn cell_nodes(&self, row: usize, col: usize) -> impl Iterator<Item = usize> + '_ {
+        let cell = row * self.num_cols + col;
+        let (start, end) = (self.cell_offsets[cell] as usize, self.cell_offsets[cell + 1] as usize);
+        self.node_ids[start..end].iter().map(|&node_id| node_id as usize)
+    }
+
+    /// Get the id of the node of `nodes` that is closest to the coordinates `lat`/`lon`, or
+    /// `None` if there are no nodes. Distances are measured in degrees like in
+    /// `GraphView::nearest_node`.
+    pub(crate) fn nearest(&self, nodes: &[Node], lat: f64, lon: f64) -> Option<usize> {
+        if self.node_ids.is_empty() {
+            return None;
+        }
+
+        let (row, col) = (self.row_of(lat), self.col_of(lon));
+        let mut nearest: Option<(f64, usize)> = None;
+        // Search rings of cells around the cell of the coordinates until no unsearched cell can
+        // contain a closer node
+        for ring in 0.. {
+            let (min_row, max_row) = (row.saturating_sub(ring), (row + ring).min(self.num_rows - 1));
+            let (min_col, max_col) = (col.saturating_sub(ring), (col + ring).min(self.num_cols - 1));
+            for r in min_row..=max_row {
+                // Rows in between the first and last row of the ring only have cells at its sides
+                let is_edge_row = r + ring == row || r == row + ring;
+                let step = if is_edge_row { 1 } else { (max_col - min_col).max(1) };
+                for c in (min_col..=max_col).step_by(step) {
+                    let is_on_ring = is_edge_row || c + ring == col || c == col + ring;
+                    if !is_on_ring {
+                        continue;
+                    }
+                    for node_id in self.cell_nodes(r, c) {
+                        let node = &nodes[node_id];
+                        let dist = (node.lat - lat).powi(2) + (node.lon - lon).powi(2);
+                        let is_closer = match nearest {
+                            Some((nearest_dist, _)) => dist < nearest_dist,
+                            None => true
+                        };
+                        if is_closer {
+                            nearest = Some((dist, node_id));
+                        }
+                    }
+                }
+            }
+
+            // Distance from the coordinates to the unsearched cells beyond every side of the
+            // searched cells, where there are any
+            let mut unsearched_dist = f64::INFINITY;
+            if min_row > 0 {
+                unsearched_dist = unsearched_dist.min(lat - (self.min_lat + min_row as f64 * self.cell_lat));
+            }
+            if max_row + 1 < self.num_rows {
+                unsearched_dist = unsearched_dist.min(self.min_lat + (max_row + 1) as f64 * self.cell_lat - lat);
+            }
+            if min_col > 0 {
+                unsearched_dist = unsearched_dist.min(lon - (self.min_lon + min_col as f64 * self.cell_lon));
+            }
+            if max_col + 1 < self.num_cols {
+                unsearched_dist = unsearched_dist.min(self.min_lon + (max_col + 1) as f64 * self.cell_lon - lon);
+            }
+            match nearest {
+                Some((dist, node_id)) if unsearched_dist.max(0.0).powi(2) >= dist => return Some(node_id),
+                _ if unsearched_dist == f64::INFINITY => return nearest.map(|(_, node_id)| node_id),
+                _ => ()
+            }
+        }
+        unreachable!("The search ends once all cells are searched")
+    }
+
+    /// Get the ids of all nodes of `nodes` that are located within the grid bounds `gb`
+    pub(crate) fn in_bounds<'a>(&'a self, nodes: &'a [Node], gb: &'a GridBounds)
+        -> impl Iterator<Item = usize> + 'a {
+        let is_empty = self.node_ids.is_empty() || gb.min_lat > gb.max_lat || gb.min_lon > gb.max_lon;
+        let (rows, cols) = if is_empty {
+            (0..0, 0..0)
+        } else {
+            (self.row_of(gb.min_lat)..self.row_of(gb.max_lat) + 1, self.col_of(gb.min_lon)..self.col_of(gb.max_lon) + 1)
+        };
+        rows.flat_map(move |row| cols.clone().flat_map(move |col| self.cell_nodes(row, col)))
+            .filter(move |&node_id| nodes[node_id].is_located_in(gb))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::{Graph, GraphView, GridBounds};
+    use crate::spatial_index::SpatialIndex;
+
+    #[test]
+    fn test_nearest() {
+        let graph = Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+        let index = SpatialIndex::new(graph.nodes());
+        let gb = graph.get_grid_bounds();
+
+        // Query points within and around the graph, compared with a linear search
+        for i in 0..20 {
+            for j in 0..20 {
+                let lat = gb.min_lat + (gb.max_lat - gb.min_lat) * (i as f64 / 10.0 - 0.5);
+                let lon = gb.min_lon + (gb.max_lon - gb.min_lon) * (j as f64 / 10.0 - 0.5);
+                let nearest = &graph.nodes()[index.nearest(graph.nodes(), lat, lon).unwrap()];
+                let expected = graph.iter_nodes()
+                    .map(|node| (node.lat - lat).powi(2) + (node.lon - lon).powi(2))
+                    .fold(f64::INFINITY, f64::min);
+                assert_eq!((nearest.lat - lat).powi(2) + (nearest.lon - lon).powi(2), expected);
+            }
+        }
+        assert_eq!(SpatialIndex::new(&[]).nearest(&[], 48.0, 9.0), None);
+    }
+
+    #[test]
+    fn test_in_bounds() {
+        let graph = Graph::parse_from_file("data/bbgrund_undirected.fmi").unwrap();
+        let index = SpatialIndex::new(graph.nodes());
+        let gb = graph.get_grid_bounds();
+
+        let quarter = GridBounds {
+            min_lat: gb.min_lat + (gb.max_lat - gb.min_lat) / 4.0,
+            max_lat: gb.max_lat - (gb.max_lat - gb.min_lat) / 4.0,
+            min_lon: gb.min_lon - 1.0,
+            max_lon: gb.min_lon + (gb.max_lon - gb.min_lon) / 2.0,
+        };
+        for bounds in [&gb, &quarter] {
+            let mut node_ids: Vec<_> = index.in_bounds(graph.nodes(), bounds).collect();
+            node_ids.sort_unstable();
+            let expected: Vec<_> = graph.iter_nodes()
+                .filter(|node| node.is_located_in(bounds))
+                .map(|node| node.id())
+                .collect();
+            assert_eq!(node_ids, expected);
+        }
+        assert_eq!(index.in_bounds(graph.nodes(), &gb).count(), graph.num_nodes());
+    }
+}
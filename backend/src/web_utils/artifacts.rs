@@ -0,0 +1,241 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Number of hex digits of the content hash that identify an artifact
+const ARTIFACT_HASH_LEN: usize = 32;
+/// Suffix of artifacts that are still being written
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Configuration of the artifact store
+#[derive(Deserialize, Clone)]
+pub struct OSMFArtifactConfig {
+    /// Directory in which artifacts are stored
+    #[serde(default = "default_artifacts_path")]
+    pub path: String,
+    /// Maximum age in seconds of an artifact before it is collected
+    #[serde(default = "default_max_age_secs")]
+    pub max_age_secs: u64,
+    /// Maximum total size in bytes of all artifacts. The oldest artifacts are collected first
+    /// once the size is exceeded.
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: u64,
+}
+
+impl Default for OSMFArtifactConfig {
+    fn default() -> Self {
+        Self {
+            path: default_artifacts_path(),
+            max_age_secs: default_max_age_secs(),
+            max_bytes: default_max_bytes(),
+        }
+    }
+}
+
+/// Default directory in which artifacts are stored
+fn default_artifacts_path() -> String {
+    "./artifacts".to_string()
+}
+
+/// Default maximum age of artifacts, i.e. one day
+fn default_max_age_secs() -> u64 {
+    24 * 60 * 60
+}
+
+/// Default maximum total size of all artifacts, i.e. 1 GiB
+fn default_max_bytes() -> u64 {
+    1 << 30
+}
+
+/// Metadata of a stored artifact
+#[derive(Debug, Serialize)]
+pub struct OSMFArtifact {
+    pub id: String,
+    pub size: u64,
+    pub url: String,
+}
+
+/// Disk-backed store of large artifacts like exports and reports.
+/// Artifacts are named by their content, such that identical artifacts are stored only once,
+/// and are collected once they exceed the maximum age or the maximum total size.
+pub struct OSMFArtifactStore {
+    dir: PathBuf,
+    max_age: Duration,
+    max_bytes: u64,
+    /// Serializes garbage collections with each other
+    collecting: Mutex<()>,
+    /// Number of the next partial file, which distinguishes concurrent stores of the same artifact
+    next_partial: AtomicUsize,
+}
+
+impl OSMFArtifactStore {
+    /// Open the artifact store configured by `config`, creating its directory if it does not
+    /// exist yet
+    pub fn open(config: &OSMFArtifactConfig) -> io::Result<Self> {
+        let dir = PathBuf::from(&config.path);
+        fs::create_dir_all(&dir)?;
+        log::info!("Opened artifact store in {}", dir.display());
+
+        Ok(Self {
+            dir,
+            max_age: Duration::from_secs(config.max_age_secs),
+            max_bytes: config.max_bytes,
+            collecting: Mutex::new(()),
+            next_partial: AtomicUsize::new(0),
+        })
+    }
+
+    /// Store `bytes` as artifact with file extension `extension` and collect old artifacts.
+    /// Returns the metadata of the stored artifact.
+    pub fn store(&self, bytes: &[u8], extension: &str) -> io::Result<OSMFArtifact> {
+        let hash = format!("{:x}", Sha256::digest(bytes));
+        let id = format!("{}.{}", &hash[..ARTIFACT_HASH_LEN], extension);
+        let path = self.dir.join(&id);
+
+        // Artifacts are written to a partial file first, such that they are never served
+        // incompletely. Existing artifacts are replaced to renew their age. Each store writes its
+        // own partial file, such that concurrent stores of the same artifact do not interfere.
+        let partial = self.next_partial.fetch_add(1, Ordering::Relaxed);
+        let partial_path = self.dir.join(format!("{}.{}.{}{}", id, process::id(), partial, PARTIAL_SUFFIX));
+        fs::write(&partial_path, bytes)?;
+        fs::rename(&partial_path, &path)?;
+        log::debug!("Stored artifact {} of {} bytes", id, bytes.len());
+
+        if let Err(err) = self.collect_garbage() {
+            log::warn!("Failed to collect artifacts: {}", err);
+        }
+
+        Ok(OSMFArtifact {
+            url: format!("/artifacts/{}", id),
+            size: bytes.len() as u64,
+            id,
+        })
+    }
+
+    /// Get the path of the artifact with id `id`, if it exists
+    pub fn path(&self, id: &str) -> Option<PathBuf> {
+        if !is_valid_id(id) {
+            return None;
+        }
+        let path = self.dir.join(id);
+        if path.is_file() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Delete all artifacts that are older than the maximum age, and then the oldest artifacts
+    /// until the total size of all artifacts does not exceed the maximum size anymore. The newest
+    /// artifact is always kept, and partial artifacts are only deleted once they are too old.
+    /// Returns the number of deleted artifacts.
+    pub fn collect_garbage(&self) -> io::Result<usize> {
+        let _collecting = self.collecting.lock().unwrap();
+        let now = SystemTime::now();
+
+        let mut artifacts = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let modified = metadata.modified()?;
+            artifacts.push((modified, metadata.len(), entry.path()));
+        }
+        // Newest artifacts first
+        artifacts.sort_unstable_by_key(|&(modified, _, _)| std::cmp::Reverse(modified));
+
+        let mut num_deleted = 0;
+        let mut total_bytes = 0;
+        for (modified, size, path) in artifacts {
+            let age = now.duration_since(modified).unwrap_or_default();
+            let is_partial = path.to_string_lossy().ends_with(PARTIAL_SUFFIX);
+            let exceeds_size = !is_partial && total_bytes > 0 && total_bytes + size > self.max_bytes;
+            if age > self.max_age || exceeds_size {
+                fs::remove_file(&path)?;
+                num_deleted += 1;
+            } else if !is_partial {
+                total_bytes += size;
+            }
+        }
+        if num_deleted > 0 {
+            log::info!("Collected {} artifacts, {} bytes remaining", num_deleted, total_bytes);
+        }
+
+        Ok(num_deleted)
+    }
+}
+
+/// Is `id` a valid artifact id, i.e. a content hash followed by a file extension?
+/// This ensures that only files in the artifact directory are served.
+fn is_valid_id(id: &str) -> bool {
+    match id.split_once('.') {
+        Some((hash, extension)) => hash.len() == ARTIFACT_HASH_LEN
+            && hash.chars().all(|c| c.is_ascii_hexdigit())
+            && !extension.is_empty()
+            && extension.chars().all(|c| c.is_ascii_alphanumeric()),
+        None => false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+
+    use crate::web_utils::artifacts::{OSMFArtifactConfig, OSMFArtifactStore};
+
+    #[test]
+    fn test_artifact_store() {
+        let dir = env::temp_dir().join(format!("osmff-artifacts-{}", nanoid::nanoid!()));
+        let store = OSMFArtifactStore::open(&OSMFArtifactConfig {
+            path: dir.to_string_lossy().to_string(),
+            max_bytes: 10,
+            ..OSMFArtifactConfig::default()
+        }).unwrap();
+
+        let first = store.store(b"first", "txt").unwrap();
+        assert_eq!(store.store(b"first", "txt").unwrap().id, first.id);
+        assert!(store.path(&first.id).is_some());
+        assert!(store.path("../first.txt").is_none());
+
+        // The oldest artifacts are collected once the maximum size is exceeded
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let second = store.store(b"second", "txt").unwrap();
+        assert!(store.path(&first.id).is_none());
+        assert!(store.path(&second.id).is_some());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_store() {
+        let dir = env::temp_dir().join(format!("osmff-artifacts-{}", nanoid::nanoid!()));
+        let store = OSMFArtifactStore::open(&OSMFArtifactConfig {
+            path: dir.to_string_lossy().to_string(),
+            ..OSMFArtifactConfig::default()
+        }).unwrap();
+
+        let bytes = vec![42; 1 << 20];
+        let ids: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| store.store(&bytes, "bin").unwrap().id))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        assert!(ids.iter().all(|id| *id == ids[0]));
+        assert_eq!(std::fs::read(store.path(&ids[0]).unwrap()).unwrap(), bytes);
+
+        // No partial files are left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::web_utils::error::OSMFError;
+
+/// Interval in which the number of running tasks is polled while draining
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Tracker of running simulations and jobs that allows to stop accepting new ones and to wait
+/// for the running ones to finish before the service shuts down
+pub struct OSMFDrain {
+    is_draining: AtomicBool,
+    num_running: AtomicUsize,
+}
+
+/// Guard of a running simulation or job. The task is considered finished once the guard is
+/// dropped.
+pub struct OSMFDrainGuard {
+    drain: Arc<OSMFDrain>,
+}
+
+impl Drop for OSMFDrainGuard {
+    fn drop(&mut self) {
+        self.drain.num_running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl OSMFDrain {
+    /// Create a new drain tracker without running tasks
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            is_draining: AtomicBool::new(false),
+            num_running: AtomicUsize::new(0),
+        })
+    }
+
+    /// Register a new running task.
+    /// Returns an error if the service is shutting down and does not accept new tasks.
+    pub fn start_task(self: &Arc<Self>) -> Result<OSMFDrainGuard, OSMFError> {
+        self.num_running.fetch_add(1, Ordering::SeqCst);
+        let guard = OSMFDrainGuard { drain: self.clone() };
+        if self.is_draining() {
+            return Err(OSMFError::ShuttingDown {
+                message: "The service is shutting down and does not accept new simulations".to_string()
+            });
+        }
+        Ok(guard)
+    }
+
+    /// Is the service shutting down, i.e. does it reject new tasks?
+    pub fn is_draining(&self) -> bool {
+        self.is_draining.load(Ordering::SeqCst)
+    }
+
+    /// Get the number of currently running tasks
+    pub fn num_running(&self) -> usize {
+        self.num_running.load(Ordering::SeqCst)
+    }
+
+    /// Stop accepting new tasks and wait at most `timeout` for all running tasks to finish.
+    /// Returns `true` if all running tasks finished in time.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.is_draining.store(true, Ordering::SeqCst);
+
+        let start = Instant::now();
+        while self.num_running() > 0 {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            actix_web::rt::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::web_utils::drain::OSMFDrain;
+
+    #[actix_web::test]
+    async fn test_drain() {
+        let drain = OSMFDrain::new();
+        let guard = drain.start_task().unwrap();
+        assert_eq!(drain.num_running(), 1);
+
+        // The running task does not finish in time and new tasks are rejected while draining
+        assert!(!drain.drain(Duration::from_millis(200)).await);
+        assert!(drain.start_task().is_err());
+        assert_eq!(drain.num_running(), 1);
+
+        drop(guard);
+        assert!(drain.drain(Duration::from_millis(200)).await);
+    }
+}
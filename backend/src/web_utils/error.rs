@@ -1,21 +1,28 @@
-use actix_web::{HttpResponse, http::StatusCode, ResponseError};
+use actix_web::{HttpResponse, error::BlockingError, http::{header, StatusCode}, ResponseError};
 use derive_more::{Display, Error};
 use serde::Serialize;
+use crate::web_utils::i18n::{self, Language};
+use osmff_lib::firefighter::charts::ChartError;
 use osmff_lib::firefighter::problem::OSMFSettingsError;
+use osmff_lib::graph_edits::GraphEditError;
+use osmff_lib::LoadGraphsError;
 
 /// Blueprint for error responses
 #[derive(Serialize)]
 struct ErrorResponse {
     status_code: u16,
+    /// Code of the error, which is the same in all languages
+    code: &'static str,
     error: String,
     message: String,
 }
 
 impl ErrorResponse {
     /// Create a new error response
-    fn new(status_code: StatusCode, error: String, message: String) -> Self {
+    fn new(status_code: StatusCode, code: &'static str, error: String, message: String) -> Self {
         Self {
             status_code: status_code.as_u16(),
+            code,
             error,
             message,
         }
@@ -30,9 +37,15 @@ pub enum OSMFError {
     #[display(fmt = "{}", message)]
     BadRequest { message: String },
     #[display(fmt = "{}", message)]
+    NotFound { message: String },
+    #[display(fmt = "{}", message)]
     NoSimulation { message: String },
     #[display(fmt = "{}", message)]
     InvalidSimulationSettings { message: String },
+    #[display(fmt = "{}", message)]
+    Conflict { message: String },
+    #[display(fmt = "{}", message)]
+    ShuttingDown { message: String },
 }
 
 impl OSMFError {
@@ -41,10 +54,36 @@ impl OSMFError {
         match self {
             Self::Internal { .. } => "Internal Server Error",
             Self::BadRequest { .. } => "Bad Request",
+            Self::NotFound { .. } => "Not Found",
             Self::NoSimulation { .. } => "No Simulation",
-            Self::InvalidSimulationSettings { .. } => "Invalid Simulation Settings"
+            Self::InvalidSimulationSettings { .. } => "Invalid Simulation Settings",
+            Self::Conflict { .. } => "Conflict",
+            Self::ShuttingDown { .. } => "Service Unavailable"
         }.to_string()
     }
+
+    /// Return the stable code of this error, which clients can rely on regardless of the language
+    /// of error messages
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Internal { .. } => "INTERNAL",
+            Self::BadRequest { .. } => "BAD_REQUEST",
+            Self::NotFound { .. } => "NOT_FOUND",
+            Self::NoSimulation { .. } => "NO_SIMULATION",
+            Self::InvalidSimulationSettings { .. } => "INVALID_SIMULATION_SETTINGS",
+            Self::Conflict { .. } => "CONFLICT",
+            Self::ShuttingDown { .. } => "SHUTTING_DOWN"
+        }
+    }
+
+    /// Create the response to this error in the language `language`
+    pub fn localized_response(&self, language: Language) -> HttpResponse {
+        let (name, message) = i18n::translate(language, self.code(), &self.name(), &self.to_string());
+        let res = ErrorResponse::new(self.status_code(), self.code(), name, message);
+        HttpResponse::build(self.status_code())
+            .insert_header((header::CONTENT_LANGUAGE, language.tag()))
+            .json(res)
+    }
 }
 
 impl From<OSMFSettingsError> for OSMFError {
@@ -55,21 +94,61 @@ impl From<OSMFSettingsError> for OSMFError {
     }
 }
 
+impl From<GraphEditError> for OSMFError {
+    fn from(err: GraphEditError) -> Self {
+        Self::BadRequest {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<LoadGraphsError> for OSMFError {
+    fn from(err: LoadGraphsError) -> Self {
+        match err {
+            LoadGraphsError::NotFound { .. } => Self::NotFound {
+                message: err.to_string(),
+            },
+            _ => Self::Internal {
+                message: err.to_string(),
+            }
+        }
+    }
+}
+
+impl From<ChartError> for OSMFError {
+    fn from(err: ChartError) -> Self {
+        match err {
+            ChartError::Unavailable => Self::BadRequest {
+                message: err.to_string(),
+            },
+            ChartError::Render { .. } => Self::Internal {
+                message: err.to_string(),
+            }
+        }
+    }
+}
+
+impl From<BlockingError> for OSMFError {
+    fn from(_: BlockingError) -> Self {
+        Self::Internal {
+            message: "Blocking computation has been cancelled".to_string(),
+        }
+    }
+}
+
 impl ResponseError for OSMFError {
     fn status_code(&self) -> StatusCode {
         match *self {
             Self::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            Self::NotFound { .. } => StatusCode::NOT_FOUND,
             Self::NoSimulation { .. } => StatusCode::CONFLICT,
             Self::InvalidSimulationSettings { .. } => StatusCode::CONFLICT,
+            Self::Conflict { .. } => StatusCode::CONFLICT,
+            Self::ShuttingDown { .. } => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
     fn error_response(&self) -> HttpResponse {
-        let res = ErrorResponse::new(
-            self.status_code(),
-            self.name(),
-            self.to_string(),
-        );
-        HttpResponse::build(self.status_code()).json(res)
+        self.localized_response(Language::English)
     }
 }
\ No newline at end of file
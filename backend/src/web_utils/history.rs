@@ -0,0 +1,561 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, OptionalExtension, params, Row, Transaction};
+use serde::Serialize;
+use serde_json::Value;
+
+use osmff_lib::firefighter::problem::OSMFProblem;
+use osmff_lib::firefighter::report::ReportRun;
+
+use crate::web_utils::error::OSMFError;
+
+/// Summary record of a completed simulation in the history catalog
+#[derive(Serialize)]
+pub struct OSMFHistoryEntry {
+    id: i64,
+    created_at: u64,
+    graph_name: String,
+    strategy_name: String,
+    num_roots: usize,
+    num_ffs: usize,
+    strategy_every: u64,
+    seed: u64,
+    result_hash: String,
+    nodes_burned: usize,
+    nodes_defended: usize,
+    /// Estimated burned area and travel distance of the firefighters. Missing for simulations
+    /// recorded before they were stored.
+    burned_area_km2: Option<f64>,
+    travel_distance_m: Option<f64>,
+    end_time: u64,
+    simulation_time_millis: u64,
+    notes: Option<String>,
+    tags: Vec<String>,
+    /// Fully resolved settings the simulation ran with. Missing for simulations recorded before
+    /// the settings were stored.
+    settings: Option<Value>,
+}
+
+impl OSMFHistoryEntry {
+    /// Read a history entry from a row of the `simulations` table
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            created_at: row.get::<_, i64>("created_at")? as u64,
+            graph_name: row.get("graph_name")?,
+            strategy_name: row.get("strategy_name")?,
+            num_roots: row.get::<_, i64>("num_roots")? as usize,
+            num_ffs: row.get::<_, i64>("num_ffs")? as usize,
+            strategy_every: row.get::<_, i64>("strategy_every")? as u64,
+            // Seeds are stored as their two's complement to fit into SQLite integers
+            seed: row.get::<_, i64>("seed")? as u64,
+            result_hash: row.get("result_hash")?,
+            nodes_burned: row.get::<_, i64>("nodes_burned")? as usize,
+            nodes_defended: row.get::<_, i64>("nodes_defended")? as usize,
+            burned_area_km2: row.get("burned_area_km2")?,
+            travel_distance_m: row.get("travel_distance_m")?,
+            end_time: row.get::<_, i64>("end_time")? as u64,
+            simulation_time_millis: row.get::<_, i64>("simulation_time_millis")? as u64,
+            notes: row.get("notes")?,
+            settings: row.get::<_, Option<String>>("settings")?
+                .and_then(|settings| serde_json::from_str(&settings).ok()),
+            // Tags are stored in a separate table and loaded afterwards
+            tags: Vec::new(),
+        })
+    }
+
+    /// Get the outcome of the recorded simulation for strategy comparison reports
+    pub fn report_run(&self) -> ReportRun {
+        ReportRun {
+            graph_name: self.graph_name.clone(),
+            strategy_name: self.strategy_name.clone(),
+            seed: self.seed,
+            defense_failure_prob: self.settings.as_ref()
+                .and_then(|settings| settings["defense_failure_prob"].as_f64())
+                .unwrap_or(0.0),
+            num_ffs: self.num_ffs,
+            strategy_every: self.strategy_every,
+            nodes_burned: self.nodes_burned,
+            nodes_defended: self.nodes_defended,
+            burned_area_km2: self.burned_area_km2,
+            travel_distance_m: self.travel_distance_m,
+            end_time: self.end_time,
+            simulation_time_millis: self.simulation_time_millis as u128,
+        }
+    }
+}
+
+/// Key metrics of a simulation in a comparison of recorded simulations
+#[derive(Serialize)]
+pub struct OSMFComparedRun {
+    id: i64,
+    graph_name: String,
+    strategy_name: String,
+    nodes_burned: usize,
+    nodes_defended: usize,
+    /// Round in which the fire was contained, i.e. in which the simulation ended
+    containment_round: u64,
+    burned_area_km2: Option<f64>,
+    simulation_time_millis: u64,
+}
+
+impl OSMFComparedRun {
+    /// Get the key metrics of the simulation recorded in `entry`
+    fn from_entry(entry: &OSMFHistoryEntry) -> Self {
+        Self {
+            id: entry.id,
+            graph_name: entry.graph_name.clone(),
+            strategy_name: entry.strategy_name.clone(),
+            nodes_burned: entry.nodes_burned,
+            nodes_defended: entry.nodes_defended,
+            containment_round: entry.end_time,
+            burned_area_km2: entry.burned_area_km2,
+            simulation_time_millis: entry.simulation_time_millis,
+        }
+    }
+}
+
+/// Overlap of the burned nodes of two simulations on the same graph
+#[derive(Serialize)]
+pub struct OSMFBurnedOverlap {
+    /// Number of nodes burned in both simulations
+    shared: usize,
+    /// Number of nodes burned in exactly one of the simulations
+    differing: usize,
+    /// Jaccard index of the burned nodes, i.e. the shared nodes relative to all burned nodes
+    jaccard: f64,
+}
+
+impl OSMFBurnedOverlap {
+    /// Compute the overlap of the burned nodes `burned1` and `burned2`, which are sorted by id
+    fn new(burned1: &[usize], burned2: &[usize]) -> Self {
+        let (mut i, mut j, mut shared) = (0, 0, 0);
+        while i < burned1.len() && j < burned2.len() {
+            match burned1[i].cmp(&burned2[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    shared += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        let union = burned1.len() + burned2.len() - shared;
+        Self {
+            shared,
+            differing: union - shared,
+            jaccard: if union > 0 { shared as f64 / union as f64 } else { 1.0 },
+        }
+    }
+}
+
+/// Comparison matrix of recorded simulations
+#[derive(Serialize)]
+pub struct OSMFComparison {
+    /// Key metrics of the compared simulations in the requested order
+    runs: Vec<OSMFComparedRun>,
+    /// Pairwise overlap of the burned nodes, indexed like `runs`. Missing for simulations on
+    /// different graphs and for simulations recorded before burned nodes were stored.
+    burned_overlap: Vec<Vec<Option<OSMFBurnedOverlap>>>,
+}
+
+impl OSMFComparison {
+    /// Compare the simulations `runs` with their burned nodes
+    fn new(runs: Vec<(OSMFComparedRun, Option<Vec<usize>>)>) -> Self {
+        let burned_overlap = runs.iter()
+            .map(|(run1, burned1)| runs.iter()
+                .map(|(run2, burned2)| match (burned1, burned2) {
+                    (Some(burned1), Some(burned2)) if run1.graph_name == run2.graph_name =>
+                        Some(OSMFBurnedOverlap::new(burned1, burned2)),
+                    _ => None
+                })
+                .collect())
+            .collect();
+
+        Self {
+            runs: runs.into_iter().map(|(run, _)| run).collect(),
+            burned_overlap,
+        }
+    }
+}
+
+/// Bucket of the numbers of fire roots and firefighters of simulations, in which the statistics
+/// of similar scenarios are aggregated. Buckets span powers of two, i.e. 1, 2-3, 4-7 and so on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OSMFParameterBucket {
+    pub min_roots: usize,
+    pub max_roots: usize,
+    pub min_ffs: usize,
+    pub max_ffs: usize,
+}
+
+impl OSMFParameterBucket {
+    /// Get the bucket of simulations with `num_roots` fire roots and `num_ffs` firefighters
+    pub fn new(num_roots: usize, num_ffs: usize) -> Self {
+        let (min_roots, max_roots) = bucket_bounds(num_roots);
+        let (min_ffs, max_ffs) = bucket_bounds(num_ffs);
+        Self { min_roots, max_roots, min_ffs, max_ffs }
+    }
+}
+
+/// Get the smallest and the largest number in the bucket of `n`
+fn bucket_bounds(n: usize) -> (usize, usize) {
+    if n == 0 {
+        return (0, 0);
+    }
+    let min = 1 << (usize::BITS - 1 - n.leading_zeros());
+    (min, min + (min - 1))
+}
+
+/// Long-run statistics of all recorded simulations of a strategy on a graph within a parameter
+/// bucket
+#[derive(Debug, Clone, Serialize)]
+pub struct OSMFStrategyStats {
+    pub strategy_name: String,
+    #[serde(flatten)]
+    pub bucket: OSMFParameterBucket,
+    pub runs: usize,
+    /// Mean ratio of the nodes of the graph that did not burn
+    pub mean_saved_ratio: f64,
+    /// Sample variance of the saved ratio. Missing for a single run.
+    pub saved_ratio_variance: Option<f64>,
+    pub mean_simulation_time_millis: f64,
+}
+
+impl OSMFStrategyStats {
+    /// Read the statistics from a row of the `strategy_stats` table
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let runs = row.get::<_, i64>("runs")? as usize;
+        let m2_saved_ratio: f64 = row.get("m2_saved_ratio")?;
+        Ok(Self {
+            strategy_name: row.get("strategy_name")?,
+            bucket: OSMFParameterBucket::new(row.get::<_, i64>("roots_bucket")? as usize,
+                                             row.get::<_, i64>("ffs_bucket")? as usize),
+            runs,
+            mean_saved_ratio: row.get("mean_saved_ratio")?,
+            saved_ratio_variance: if runs > 1 { Some(m2_saved_ratio / (runs - 1) as f64) } else { None },
+            mean_simulation_time_millis: row.get("mean_simulation_time_millis")?,
+        })
+    }
+}
+
+impl From<rusqlite::Error> for OSMFError {
+    fn from(err: rusqlite::Error) -> Self {
+        log::error!("History catalog error: {}", err);
+        Self::Internal {
+            message: "Failed to access the simulation history".to_string()
+        }
+    }
+}
+
+/// Catalog of all completed simulations that is persisted in a SQLite database
+pub struct OSMFHistory {
+    conn: Mutex<Connection>,
+}
+
+impl OSMFHistory {
+    /// Open the history catalog stored in the SQLite database at `path`, creating it if it
+    /// does not exist yet
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Open a history catalog that is only kept in memory
+    #[cfg(test)]
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    /// Create the tables of the history catalog if they do not exist yet
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS simulations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at INTEGER NOT NULL,
+                graph_name TEXT NOT NULL,
+                strategy_name TEXT NOT NULL,
+                num_roots INTEGER NOT NULL,
+                num_ffs INTEGER NOT NULL,
+                strategy_every INTEGER NOT NULL,
+                seed INTEGER NOT NULL,
+                result_hash TEXT NOT NULL,
+                nodes_burned INTEGER NOT NULL,
+                nodes_defended INTEGER NOT NULL,
+                end_time INTEGER NOT NULL,
+                simulation_time_millis INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS simulations_graph_strategy
+                ON simulations (graph_name, strategy_name);
+            CREATE TABLE IF NOT EXISTS simulation_tags (
+                simulation_id INTEGER NOT NULL REFERENCES simulations (id),
+                tag TEXT NOT NULL,
+                PRIMARY KEY (simulation_id, tag)
+            );
+            CREATE INDEX IF NOT EXISTS simulation_tags_tag ON simulation_tags (tag);
+            CREATE TABLE IF NOT EXISTS strategy_stats (
+                graph_name TEXT NOT NULL,
+                strategy_name TEXT NOT NULL,
+                roots_bucket INTEGER NOT NULL,
+                ffs_bucket INTEGER NOT NULL,
+                runs INTEGER NOT NULL,
+                mean_saved_ratio REAL NOT NULL,
+                m2_saved_ratio REAL NOT NULL,
+                mean_simulation_time_millis REAL NOT NULL,
+                PRIMARY KEY (graph_name, strategy_name, roots_bucket, ffs_bucket)
+            );")?;
+
+        // Catalogs created before notes were introduced lack the notes column
+        let has_notes: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('simulations') WHERE name = 'notes'",
+            [], |row| row.get(0))?;
+        if !has_notes {
+            conn.execute_batch("ALTER TABLE simulations ADD COLUMN notes TEXT;")?;
+        }
+        // Catalogs created before settings were recorded lack the settings column
+        let has_settings: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('simulations') WHERE name = 'settings'",
+            [], |row| row.get(0))?;
+        if !has_settings {
+            conn.execute_batch("ALTER TABLE simulations ADD COLUMN settings TEXT;")?;
+        }
+        // Catalogs created before burned nodes were recorded lack the burned_nodes column
+        let has_burned_nodes: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('simulations') WHERE name = 'burned_nodes'",
+            [], |row| row.get(0))?;
+        if !has_burned_nodes {
+            conn.execute_batch("ALTER TABLE simulations ADD COLUMN burned_nodes TEXT;")?;
+        }
+        // Catalogs created before the objectives of the Pareto analysis were recorded lack their
+        // columns
+        for column in ["burned_area_km2", "travel_distance_m"] {
+            let has_column: bool = conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('simulations') WHERE name = ?1",
+                params![column], |row| row.get(0))?;
+            if !has_column {
+                conn.execute_batch(&format!("ALTER TABLE simulations ADD COLUMN {} REAL;", column))?;
+            }
+        }
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Flush all cached changes of the history catalog to disk
+    pub fn flush(&self) -> rusqlite::Result<()> {
+        self.conn.lock().unwrap().cache_flush()
+    }
+
+    /// Record the completed simulation `problem` in the history catalog.
+    /// Returns the id of the new history entry.
+    pub fn record(&self, problem: &OSMFProblem) -> rusqlite::Result<i64> {
+        let sim = problem.simulation_response();
+        let settings = &sim.settings;
+        let settings_json = serde_json::to_string(settings)
+            .expect("Settings should be serializable");
+        let burned_nodes_json = serde_json::to_string(&problem.burned_nodes())
+            .expect("Node ids should be serializable");
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO simulations (created_at, graph_name, strategy_name, num_roots, num_ffs,
+                strategy_every, seed, result_hash, nodes_burned, nodes_defended, end_time,
+                simulation_time_millis, settings, burned_area_km2, travel_distance_m, burned_nodes)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            params![created_at as i64, settings.graph_name, settings.strategy_name,
+                settings.num_roots as i64, settings.num_ffs as i64, settings.strategy_every as i64,
+                sim.seed as i64, problem.result_hash(), sim.nodes_burned as i64,
+                sim.nodes_defended as i64, sim.end_time as i64, sim.simulation_time_millis as i64,
+                settings_json, sim.burned_area_km2, sim.travel_distance_m, burned_nodes_json])?;
+
+        let id = tx.last_insert_rowid();
+        if sim.nodes_total > 0 {
+            let saved_ratio = 1.0 - sim.nodes_burned as f64 / sim.nodes_total as f64;
+            let bucket = OSMFParameterBucket::new(settings.num_roots, settings.num_ffs);
+            Self::update_strategy_stats(&tx, &settings.graph_name, &settings.strategy_name, bucket,
+                                        saved_ratio, sim.simulation_time_millis as f64)?;
+        }
+        tx.commit()?;
+
+        Ok(id)
+    }
+
+    /// Add a simulation of the strategy `strategy_name` on the graph `graph_name` in the bucket
+    /// `bucket` with the saved ratio `saved_ratio` and the simulation time `millis` to the
+    /// statistics of the strategy. Means and variances are updated with Welford's algorithm.
+    fn update_strategy_stats(tx: &Transaction, graph_name: &str, strategy_name: &str,
+                             bucket: OSMFParameterBucket, saved_ratio: f64, millis: f64)
+        -> rusqlite::Result<()> {
+        let key = params![graph_name, strategy_name, bucket.min_roots as i64, bucket.min_ffs as i64];
+        let (runs, mean, m2, mean_millis) = tx.query_row(
+            "SELECT runs, mean_saved_ratio, m2_saved_ratio, mean_simulation_time_millis
+            FROM strategy_stats
+            WHERE graph_name = ?1 AND strategy_name = ?2 AND roots_bucket = ?3 AND ffs_bucket = ?4",
+            key, |row| Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?, row.get::<_, f64>(2)?, row.get::<_, f64>(3)?)))
+            .optional()?
+            .unwrap_or((0, 0.0, 0.0, 0.0));
+
+        let runs = runs + 1;
+        let delta = saved_ratio - mean;
+        let mean = mean + delta / runs as f64;
+        let m2 = m2 + delta * (saved_ratio - mean);
+        let mean_millis = mean_millis + (millis - mean_millis) / runs as f64;
+        tx.execute(
+            "INSERT OR REPLACE INTO strategy_stats (graph_name, strategy_name, roots_bucket, ffs_bucket,
+                runs, mean_saved_ratio, m2_saved_ratio, mean_simulation_time_millis)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![graph_name, strategy_name, bucket.min_roots as i64, bucket.min_ffs as i64,
+                runs, mean, m2, mean_millis])?;
+
+        Ok(())
+    }
+
+    /// Load the tags of all entries in `entries`
+    fn load_tags(conn: &Connection, entries: &mut [OSMFHistoryEntry]) -> rusqlite::Result<()> {
+        let mut stmt = conn.prepare(
+            "SELECT tag FROM simulation_tags WHERE simulation_id = ?1 ORDER BY tag")?;
+        for entry in entries {
+            entry.tags = stmt.query_map(params![entry.id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the history entry with id `id`, if it exists
+    pub fn get(&self, id: i64) -> rusqlite::Result<Option<OSMFHistoryEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let entry = conn.query_row("SELECT * FROM simulations WHERE id = ?1", params![id],
+                                   OSMFHistoryEntry::from_row)
+            .optional()?;
+
+        let mut entries: Vec<_> = entry.into_iter().collect();
+        Self::load_tags(&conn, &mut entries)?;
+
+        Ok(entries.pop())
+    }
+
+    /// Compare the history entries with ids `ids` by their key metrics and by the overlap of their
+    /// burned nodes.
+    /// Returns `Err(id)` with the id of the first entry that does not exist.
+    pub fn compare(&self, ids: &[i64]) -> rusqlite::Result<Result<OSMFComparison, i64>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT * FROM simulations WHERE id = ?1")?;
+        let mut runs = Vec::with_capacity(ids.len());
+        for &id in ids {
+            let run = stmt.query_row(params![id], |row| {
+                // Missing for simulations recorded before burned nodes were stored
+                let burned_nodes: Option<Vec<usize>> = row.get::<_, Option<String>>("burned_nodes")?
+                    .and_then(|burned_nodes| serde_json::from_str(&burned_nodes).ok());
+                Ok((OSMFComparedRun::from_entry(&OSMFHistoryEntry::from_row(row)?), burned_nodes))
+            }).optional()?;
+            match run {
+                Some(run) => runs.push(run),
+                None => return Ok(Err(id))
+            }
+        }
+
+        Ok(Ok(OSMFComparison::new(runs)))
+    }
+
+    /// Replace the notes and/or the tags of the history entry with id `id`.
+    /// Returns `false` if there is no such entry.
+    pub fn annotate(&self, id: i64, notes: Option<&str>, tags: Option<&[String]>)
+        -> rusqlite::Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let exists: bool = tx.query_row("SELECT COUNT(*) > 0 FROM simulations WHERE id = ?1",
+                                        params![id], |row| row.get(0))?;
+        if !exists {
+            return Ok(false);
+        }
+        if let Some(notes) = notes {
+            tx.execute("UPDATE simulations SET notes = ?1 WHERE id = ?2", params![notes, id])?;
+        }
+        if let Some(tags) = tags {
+            tx.execute("DELETE FROM simulation_tags WHERE simulation_id = ?1", params![id])?;
+            for tag in tags {
+                tx.execute("INSERT OR IGNORE INTO simulation_tags (simulation_id, tag) VALUES (?1, ?2)",
+                           params![id, tag])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(true)
+    }
+
+    /// Get the number of recorded simulations on the graph `graph_name`, optionally restricted to
+    /// the strategy `strategy_name`, and their mean simulation time in milliseconds
+    pub fn runtime_stats(&self, graph_name: &str, strategy_name: Option<&str>)
+        -> rusqlite::Result<(usize, Option<f64>)> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*), AVG(simulation_time_millis) FROM simulations
+            WHERE graph_name = ?1 AND (?2 IS NULL OR strategy_name = ?2)",
+            params![graph_name, strategy_name],
+            |row| Ok((row.get::<_, i64>(0)? as usize, row.get(1)?)))
+    }
+
+    /// Get the mean of the column `column` of all recorded simulations of every strategy, optionally
+    /// restricted to the graph `graph_name` and the tag `tag`, ordered by strategy name.
+    /// `column` must be a numeric column of the `simulations` table.
+    pub fn strategy_means(&self, column: &str, graph_name: Option<&str>, tag: Option<&str>)
+        -> rusqlite::Result<Vec<(String, f64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT strategy_name, AVG({}) FROM simulations
+            WHERE (?1 IS NULL OR graph_name = ?1)
+                AND (?2 IS NULL OR id IN (SELECT simulation_id FROM simulation_tags WHERE tag = ?2))
+            GROUP BY strategy_name
+            ORDER BY strategy_name", column))?;
+        let means = stmt.query_map(params![graph_name, tag], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(means)
+    }
+
+    /// Get the statistics of all strategies on the graph `graph_name`, optionally restricted to the
+    /// buckets of `num_roots` fire roots and `num_ffs` firefighters, ordered by bucket and by
+    /// decreasing mean saved ratio.
+    /// Simulations recorded before the statistics were introduced are not included.
+    pub fn strategy_stats(&self, graph_name: &str, num_roots: Option<usize>, num_ffs: Option<usize>)
+        -> rusqlite::Result<Vec<OSMFStrategyStats>> {
+        let roots_bucket = num_roots.map(|num_roots| bucket_bounds(num_roots).0 as i64);
+        let ffs_bucket = num_ffs.map(|num_ffs| bucket_bounds(num_ffs).0 as i64);
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT * FROM strategy_stats
+            WHERE graph_name = ?1 AND (?2 IS NULL OR roots_bucket = ?2) AND (?3 IS NULL OR ffs_bucket = ?3)
+            ORDER BY roots_bucket, ffs_bucket, mean_saved_ratio DESC, strategy_name")?;
+        let stats = stmt.query_map(params![graph_name, roots_bucket, ffs_bucket], OSMFStrategyStats::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(stats)
+    }
+
+    /// Get the `limit` most recent history entries, optionally filtered by graph name, strategy
+    /// name and tag
+    pub fn query(&self, graph_name: Option<&str>, strategy_name: Option<&str>, tag: Option<&str>,
+                 limit: usize) -> rusqlite::Result<Vec<OSMFHistoryEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT * FROM simulations
+            WHERE (?1 IS NULL OR graph_name = ?1) AND (?2 IS NULL OR strategy_name = ?2)
+                AND (?3 IS NULL OR id IN (SELECT simulation_id FROM simulation_tags WHERE tag = ?3))
+            ORDER BY id DESC
+            LIMIT ?4")?;
+        let mut entries = stmt.query_map(params![graph_name, strategy_name, tag, limit as i64],
+                                         OSMFHistoryEntry::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Self::load_tags(&conn, &mut entries)?;
+
+        Ok(entries)
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use actix_web::dev::ServiceResponse;
+use actix_web::http::header;
+use actix_web::middleware::{ErrorHandlerResponse, ErrorHandlers};
+use actix_web::{HttpRequest, Result};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::web_utils::error::OSMFError;
+
+/// Placeholder for the arguments of message templates
+const PLACEHOLDER: &str = "{}";
+
+/// Languages that error messages are available in. Messages are written in English, which is
+/// used whenever the client accepts none of the other languages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    English,
+    German,
+}
+
+impl Language {
+    /// Get the language tag of this language
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::German => "de",
+        }
+    }
+
+    /// Get the language with the primary language tag `tag`, if it is available
+    fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split('-').next().unwrap_or(tag).trim();
+        [Self::English, Self::German].iter().copied()
+            .find(|language| primary.eq_ignore_ascii_case(language.tag()))
+    }
+
+    /// Negotiate the language of responses to `req` from its `Accept-Language` header, i.e. the
+    /// available language with the highest quality value
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let accepted = match req.headers().get(header::ACCEPT_LANGUAGE).and_then(|value| value.to_str().ok()) {
+            Some(accepted) => accepted,
+            None => return Self::English
+        };
+
+        let mut best: Option<(f64, Self)> = None;
+        for range in accepted.split(',') {
+            let mut parts = range.split(';');
+            let tag = parts.next().unwrap_or("");
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|quality| quality.trim().parse::<f64>().ok())
+                .unwrap_or(1.0);
+            if let Some(language) = Self::from_tag(tag) {
+                let better = match best {
+                    Some((best_quality, _)) => quality > best_quality,
+                    None => quality > 0.0
+                };
+                if better {
+                    best = Some((quality, language));
+                }
+            }
+        }
+        best.map(|(_, language)| language).unwrap_or(Self::English)
+    }
+}
+
+/// Translations of error names by error code and of error messages into another language.
+/// Messages are translated from templates, in which `{}` stands for an argument of the message,
+/// e.g. a parameter name. Arguments are translated as well, if they are messages themselves.
+#[derive(Deserialize)]
+struct Bundle {
+    names: HashMap<String, String>,
+    /// Pairs of English and translated message templates. The first matching template is used,
+    /// so more specific templates must precede more general ones.
+    messages: Vec<(String, String)>,
+}
+
+/// German translations of error names and messages
+static GERMAN: Lazy<Bundle> = Lazy::new(|| serde_json::from_str(include_str!("../../i18n/de.json"))
+    .expect("Invalid German translation bundle"));
+
+impl Bundle {
+    /// Translate the message `message`, or return it unchanged if no template matches it
+    fn translate(&self, message: &str) -> String {
+        for (template, translation) in &self.messages {
+            if let Some(args) = match_template(template, message) {
+                let mut translated = String::with_capacity(translation.len());
+                let mut parts = translation.split(PLACEHOLDER);
+                translated.push_str(parts.next().unwrap_or(""));
+                for (part, arg) in parts.zip(args.into_iter().chain(std::iter::repeat(""))) {
+                    translated.push_str(&self.translate(arg));
+                    translated.push_str(part);
+                }
+                return translated;
+            }
+        }
+        message.to_string()
+    }
+}
+
+/// Match the message `message` against the template `template`.
+/// Returns the arguments of the message, or `None` if it does not match.
+fn match_template<'a>(template: &str, message: &'a str) -> Option<Vec<&'a str>> {
+    let mut parts = template.split(PLACEHOLDER);
+    let mut rest = message.strip_prefix(parts.next()?)?;
+    let parts: Vec<_> = parts.collect();
+    let mut args = Vec::with_capacity(parts.len());
+    for (i, part) in parts.iter().enumerate() {
+        // Arguments extend to the next occurrence of the following part of the template, or to
+        // the end of the message if the part is the last one
+        let end = if i + 1 == parts.len() {
+            rest.strip_suffix(part)?.len()
+        } else {
+            rest.find(part)?
+        };
+        args.push(&rest[..end]);
+        rest = &rest[end + part.len()..];
+    }
+    if rest.is_empty() { Some(args) } else { None }
+}
+
+/// Get the name of the error with code `code` and the message `message` in the language
+/// `language`
+pub fn translate(language: Language, code: &str, name: &str, message: &str) -> (String, String) {
+    match language {
+        Language::English => (name.to_string(), message.to_string()),
+        Language::German => (
+            GERMAN.names.get(code).cloned().unwrap_or_else(|| name.to_string()),
+            GERMAN.translate(message),
+        ),
+    }
+}
+
+/// Create the middleware that responds with error messages in the language negotiated from the
+/// `Accept-Language` header of requests. Error codes are the same in all languages.
+pub fn localize_errors<B: 'static>() -> ErrorHandlers<B> {
+    ErrorHandlers::new().default_handler(localize_error_response)
+}
+
+/// Replace the error response `res` by a response in the negotiated language
+fn localize_error_response<B>(res: ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
+    let language = Language::from_request(res.request());
+    let localized = res.response().error()
+        .and_then(|err| err.as_error::<OSMFError>())
+        .filter(|_| language != Language::English)
+        .map(|err| err.localized_response(language));
+    match localized {
+        Some(localized) => {
+            let (req, _) = res.into_parts();
+            Ok(ErrorHandlerResponse::Response(ServiceResponse::new(req, localized).map_into_right_body()))
+        }
+        None => Ok(ErrorHandlerResponse::Response(res.map_into_left_body()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::http::header;
+    use actix_web::test::TestRequest;
+
+    use crate::web_utils::i18n::{match_template, translate, Language, GERMAN};
+
+    #[test]
+    fn test_negotiate_language() {
+        let language = |accepted: &str| Language::from_request(&TestRequest::default()
+            .insert_header((header::ACCEPT_LANGUAGE, accepted))
+            .to_http_request());
+        assert_eq!(Language::from_request(&TestRequest::default().to_http_request()), Language::English);
+        assert_eq!(language("de-DE,de;q=0.9,en;q=0.8"), Language::German);
+        assert_eq!(language("fr-FR, en;q=0.5, de;q=0.7"), Language::German);
+        assert_eq!(language("fr, de;q=0"), Language::English);
+        assert_eq!(language("*"), Language::English);
+    }
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(match_template("Unknown edge: {} -> {}", "Unknown edge: 1 -> 2"), Some(vec!["1", "2"]));
+        assert_eq!(match_template("Unknown graph: '{}'", "Unknown node: '1'"), None);
+
+        let (name, message) = translate(Language::German, "BAD_REQUEST", "Bad Request",
+                                        "Invalid value for parameter 'zoom': '0', zoom must be between 0.1 and 1000");
+        assert_eq!(name, "Ungültige Anfrage");
+        assert_eq!(message, "Ungültiger Wert für Parameter 'zoom': '0', der Zoom muss zwischen 0.1 und 1000 liegen");
+
+        // Nested messages are translated, unknown messages are kept
+        assert_eq!(GERMAN.translate("Invalid ignition wave in round 2: Unknown node 7"),
+                   "Ungültige Zündwelle in Runde 2: Unbekannter Knoten 7");
+        assert_eq!(GERMAN.translate("Something else"), "Something else");
+        assert_eq!(translate(Language::English, "BAD_REQUEST", "Bad Request", "Something else"),
+                   ("Bad Request".to_string(), "Something else".to_string()));
+
+        // All templates have the same number of arguments in every language
+        for (template, translation) in &GERMAN.messages {
+            assert_eq!(template.matches("{}").count(), translation.matches("{}").count(), "{}", template);
+        }
+    }
+}
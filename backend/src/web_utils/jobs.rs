@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use osmff_lib::compute::{JobProgress, OSMFJobProgress};
+
+/// Time, after which to prune finished `OSMFJob` instances
+const PRUNE_JOBS_AFTER_SECS: Duration = Duration::from_secs(60 * 60);
+
+/// A long-running job that is executed on the compute pool
+pub struct OSMFJob {
+    kind: &'static str,
+    progress: JobProgress,
+    result: Mutex<Option<Value>>,
+    finished_at: Mutex<Option<Instant>>,
+}
+
+/// Container for data about the state of an `OSMFJob`
+#[derive(Serialize)]
+pub struct OSMFJobStatus {
+    job_id: String,
+    kind: &'static str,
+    is_finished: bool,
+    progress: OSMFJobProgress,
+    result: Option<Value>,
+}
+
+impl OSMFJob {
+    /// Get a reference to the progress of this `OSMFJob`
+    pub fn progress(&self) -> &JobProgress {
+        &self.progress
+    }
+
+    /// Store the result of this `OSMFJob` and mark it as finished
+    pub fn finish(&self, result: Value) {
+        *self.result.lock().unwrap() = Some(result);
+        *self.finished_at.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Get the current status of this `OSMFJob`
+    fn status(&self, job_id: &str) -> OSMFJobStatus {
+        let result = self.result.lock().unwrap().clone();
+        OSMFJobStatus {
+            job_id: job_id.to_string(),
+            kind: self.kind,
+            is_finished: result.is_some(),
+            progress: self.progress.snapshot(),
+            result,
+        }
+    }
+}
+
+/// Storage for `OSMFJob` instances
+pub struct OSMFJobStorage {
+    jobs: Mutex<HashMap<String, Arc<OSMFJob>>>,
+}
+
+impl OSMFJobStorage {
+    /// Create a new storage for `OSMFJob` instances
+    pub fn new() -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register a new `OSMFJob` of kind `kind`.
+    /// Returns the id of the new job and a shared reference to it.
+    pub fn create_job(&self, kind: &'static str) -> (String, Arc<OSMFJob>) {
+        let mut jobs = self.jobs.lock().unwrap();
+
+        // Prune jobs that have been finished for a long time
+        jobs.retain(|_, job| match *job.finished_at.lock().unwrap() {
+            Some(finished_at) => finished_at.elapsed() < PRUNE_JOBS_AFTER_SECS,
+            None => true
+        });
+
+        let job_id = nanoid::nanoid!();
+        let job = Arc::new(OSMFJob {
+            kind,
+            progress: JobProgress::new(),
+            result: Mutex::new(None),
+            finished_at: Mutex::new(None),
+        });
+        jobs.insert(job_id.clone(), job.clone());
+
+        (job_id, job)
+    }
+
+    /// Get the current status of the `OSMFJob` with id `job_id`
+    pub fn get_status(&self, job_id: &str) -> Option<OSMFJobStatus> {
+        self.jobs.lock().unwrap()
+            .get(job_id)
+            .map(|job| job.status(job_id))
+    }
+}
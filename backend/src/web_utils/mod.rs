@@ -1,3 +1,8 @@
+pub(crate) mod artifacts;
+pub(crate) mod drain;
 pub(crate) mod error;
+pub(crate) mod history;
+pub(crate) mod i18n;
+pub(crate) mod jobs;
 pub(crate) mod query;
 pub(crate) mod session;
@@ -1,15 +1,63 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Instant, Duration};
 
 use actix_web::cookie::{Cookie, SameSite};
+use dashmap::DashMap;
 use nanoid;
-use transient_hashmap::TransientHashMap;
+use serde::{Deserialize, Serialize};
 
-use osmff_lib::firefighter::problem::OSMFProblem;
+use osmff_lib::graph::Graph;
+use osmff_lib::graph_edits::EditedGraph;
+use osmff_lib::firefighter::problem::{OSMFProblem, OSMFRunningSimulation, OSMFSimulationProgress,
+                                      OSMFSimulationStepMetadata};
+use osmff_lib::firefighter::TimeUnit;
+
+/// Configuration of the janitor that evicts the caches of simulations that have not been
+/// accessed recently, long before their sessions are pruned
+#[derive(Deserialize, Clone)]
+pub struct OSMFJanitorConfig {
+    /// Time in seconds after which the caches of a simulation that has not been accessed are
+    /// evicted
+    #[serde(default = "default_idle_secs")]
+    pub idle_secs: u64,
+    /// Interval in seconds in which the janitor runs
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl Default for OSMFJanitorConfig {
+    fn default() -> Self {
+        Self {
+            idle_secs: default_idle_secs(),
+            interval_secs: default_interval_secs(),
+        }
+    }
+}
+
+/// Default time after which the caches of a simulation that has not been accessed are evicted,
+/// i.e. ten minutes
+fn default_idle_secs() -> u64 {
+    10 * 60
+}
+
+/// Default interval in which the janitor runs, i.e. one minute
+fn default_interval_secs() -> u64 {
+    60
+}
 
 /// Container for OSM-Firefighter session data
 pub struct OSMFSession {
     id: String,
     problem: Option<OSMFProblem>,
+    /// Time the attached firefighter problem instance was last accessed
+    problem_accessed: Mutex<Instant>,
+    /// Have the caches of the attached firefighter problem instance been evicted?
+    is_compacted: bool,
+    running: Option<OSMFRunningSimulation>,
+    /// Edited graphs of this session by the names of their base graphs
+    edited_graphs: HashMap<String, EditedGraph>,
 }
 
 impl OSMFSession {
@@ -18,6 +66,10 @@ impl OSMFSession {
         Self {
             id,
             problem: None,
+            problem_accessed: Mutex::new(Instant::now()),
+            is_compacted: false,
+            running: None,
+            edited_graphs: HashMap::new(),
         }
     }
 
@@ -32,84 +84,255 @@ impl OSMFSession {
     /// Attach a firefighter problem instance to this `OSMFSession`
     pub fn attach_problem(&mut self, problem: OSMFProblem) {
         self.problem = Some(problem);
+        self.running = None;
+        *self.problem_accessed.get_mut().unwrap() = Instant::now();
+        self.is_compacted = false;
+    }
+
+    /// Mark the simulation `running` as running in this `OSMFSession` until the next firefighter
+    /// problem instance is attached
+    pub fn start_simulation(&mut self, running: OSMFRunningSimulation) {
+        self.running = Some(running);
+    }
+
+    /// Get a reference to the running simulation of this `OSMFSession`, if its completed rounds
+    /// can be viewed while it is running
+    pub fn get_running_simulation(&self) -> Option<&OSMFRunningSimulation> {
+        self.running.as_ref().filter(|running| running.publishes_steps())
+    }
+
+    /// Get the progress of the running simulation of this `OSMFSession`, or of the simulation
+    /// of the attached firefighter problem instance if no simulation is running
+    pub fn get_progress(&self) -> Option<OSMFSimulationProgress> {
+        match (&self.running, &self.problem) {
+            (Some(running), _) => Some(running.progress().snapshot()),
+            (None, Some(problem)) => Some(problem.progress().snapshot()),
+            (None, None) => None
+        }
+    }
+
+    /// Get the metadata of the step at time `time` of the running simulation of this `OSMFSession`,
+    /// or of the simulation of the attached firefighter problem instance if no simulation is
+    /// running, together with whether that simulation has finished.
+    /// Returns `None` if no simulation has been started yet. The metadata is `None` if the step
+    /// has not been published yet or does not exist.
+    pub fn get_step(&self, time: TimeUnit) -> Option<(Option<OSMFSimulationStepMetadata>, bool)> {
+        match (&self.running, &self.problem) {
+            (Some(running), _) => Some((running.progress().get_step(time), running.progress().is_finished())),
+            (None, Some(problem)) => {
+                let step = if time <= problem.end_time() {
+                    Some(problem.sim_step_metadata_response(&time))
+                } else {
+                    None
+                };
+                Some((step, true))
+            }
+            (None, None) => None
+        }
+    }
+
+    /// Get the edited graph of this `OSMFSession` with base graph `graph_name`, if the graph has
+    /// been edited
+    pub fn get_edited_graph(&self, graph_name: &str) -> Option<&EditedGraph> {
+        self.edited_graphs.get(graph_name)
+    }
+
+    /// Get the edited graph of this `OSMFSession` with base graph `base` named `graph_name`,
+    /// which is created without edits if the graph has not been edited yet
+    pub fn edit_graph(&mut self, graph_name: &str, base: &Arc<Graph>) -> &mut EditedGraph {
+        self.edited_graphs.entry(graph_name.to_string())
+            .or_insert_with(|| EditedGraph::new(base.clone()))
+    }
+
+    /// Discard all edits of the graph `graph_name` of this `OSMFSession`.
+    /// Returns whether the graph had been edited.
+    pub fn discard_graph_edits(&mut self, graph_name: &str) -> bool {
+        self.edited_graphs.remove(graph_name).is_some()
     }
 
     /// Get a reference to the attached firefighter problem instance of this `OSMFSession`
     pub fn get_problem(&self) -> Option<&OSMFProblem> {
         if let Some(ref problem) = self.problem {
+            *self.problem_accessed.lock().unwrap() = Instant::now();
             Some(problem)
         } else {
             None
         }
     }
 
-    /// Get a mutable reference to the attached firefighter problem instance of this `OSMFSession`
-    pub fn get_mut_problem(&mut self) -> Option<&mut OSMFProblem> {
-        if let Some(ref mut problem) = self.problem {
-            Some(problem)
-        } else {
-            None
+    /// Evict the caches of the attached firefighter problem instance of this `OSMFSession` if it
+    /// has not been accessed for `idle` and has not been compacted yet.
+    /// Returns the approximate number of reclaimed bytes if the caches have been evicted.
+    fn compact_if_idle(&mut self, idle: Duration) -> Option<usize> {
+        if self.is_compacted || self.problem_accessed.get_mut().unwrap().elapsed() < idle {
+            return None;
         }
+        let problem = self.problem.as_mut()?;
+        self.is_compacted = true;
+        Some(problem.evict_caches())
     }
 }
 
 /// Time, after which to prune unused `OSMFSession` instances
 const PRUNE_SESSIONS_AFTER_SECS: Duration = Duration::from_secs(60 * 60);
 
-/// Storage for `OSMFSession` instances
+/// An `OSMFSession` in the storage together with the time it was last accessed
+struct OSMFSessionEntry {
+    session: Arc<RwLock<OSMFSession>>,
+    last_accessed: Instant,
+}
+
+/// Metrics of the janitor of an `OSMFSessionStorage`
+#[derive(Default)]
+struct OSMFJanitorMetrics {
+    runs: AtomicU64,
+    simulations_compacted: AtomicU64,
+    reclaimed_bytes: AtomicU64,
+}
+
+/// Container for the metrics of the janitor of an `OSMFSessionStorage`
+#[derive(Serialize)]
+pub struct OSMFJanitorStats {
+    runs: u64,
+    simulations_compacted: u64,
+    reclaimed_bytes: u64,
+}
+
+/// Storage for `OSMFSession` instances.
+/// Sessions are stored in a sharded map and each session, together with its firefighter problem
+/// instance, is guarded by its own lock, such that requests of different sessions never contend.
 pub struct OSMFSessionStorage {
-    sessions: TransientHashMap<String, OSMFSession>,
-    last_pruned: Instant,
+    sessions: DashMap<String, OSMFSessionEntry>,
+    last_pruned: Mutex<Instant>,
+    janitor: OSMFJanitorMetrics,
 }
 
 impl OSMFSessionStorage {
     /// Create a new storage for `OSMFSession` instances
     pub fn new() -> Self {
         Self {
-            sessions: TransientHashMap::new(PRUNE_SESSIONS_AFTER_SECS.as_secs() as u32),
-            last_pruned: Instant::now(),
+            sessions: DashMap::new(),
+            last_pruned: Mutex::new(Instant::now()),
+            janitor: OSMFJanitorMetrics::default(),
         }
     }
 
     /// Prune unused `OSMFSession` instances
-    fn prune_sessions(&mut self) {
-        if self.last_pruned.elapsed() >= PRUNE_SESSIONS_AFTER_SECS {
-            self.sessions.prune();
-            self.last_pruned = Instant::now();
+    fn prune_sessions(&self) {
+        // Only a single request prunes the sessions, all others skip pruning in the meantime
+        if let Ok(mut last_pruned) = self.last_pruned.try_lock() {
+            if last_pruned.elapsed() >= PRUNE_SESSIONS_AFTER_SECS {
+                self.sessions.retain(|_, entry| entry.last_accessed.elapsed() < PRUNE_SESSIONS_AFTER_SECS);
+                *last_pruned = Instant::now();
+            }
+        }
+    }
+
+    /// Evict the caches of all simulations that have not been accessed for `idle`.
+    /// Sessions that are locked by a request are skipped until the next run.
+    /// Returns the approximate number of reclaimed bytes.
+    pub fn compact_idle_simulations(&self, idle: Duration) -> usize {
+        let mut num_compacted = 0;
+        let mut reclaimed = 0;
+        for entry in self.sessions.iter() {
+            if let Ok(mut session) = entry.session.try_write() {
+                if let Some(bytes) = session.compact_if_idle(idle) {
+                    num_compacted += 1;
+                    reclaimed += bytes;
+                }
+            }
+        }
+
+        self.janitor.runs.fetch_add(1, Ordering::SeqCst);
+        self.janitor.simulations_compacted.fetch_add(num_compacted, Ordering::SeqCst);
+        self.janitor.reclaimed_bytes.fetch_add(reclaimed as u64, Ordering::SeqCst);
+        if num_compacted > 0 {
+            log::info!("Compacted {} idle simulations, reclaimed {} bytes", num_compacted, reclaimed);
+        }
+        reclaimed
+    }
+
+    /// Count the simulations running on the graph `graph_name` in all sessions, including
+    /// simulations on edited versions of the graph
+    pub fn count_running_simulations(&self, graph_name: &str) -> usize {
+        self.sessions.iter()
+            .filter(|entry| {
+                let session = entry.session.read().unwrap();
+                session.running.as_ref().filter(|running| !running.progress().is_finished())
+                    .map(|running| running.graph_name()) == Some(graph_name)
+            })
+            .count()
+    }
+
+    /// Discard all edits of the graph `graph_name` in all sessions, e.g. because the graph has
+    /// been replaced. Returns the number of sessions that had edited the graph.
+    pub fn discard_all_graph_edits(&self, graph_name: &str) -> usize {
+        self.sessions.iter()
+            .filter(|entry| entry.session.write().unwrap().discard_graph_edits(graph_name))
+            .count()
+    }
+
+    /// Get the metrics of all janitor runs so far
+    pub fn janitor_stats(&self) -> OSMFJanitorStats {
+        OSMFJanitorStats {
+            runs: self.janitor.runs.load(Ordering::SeqCst),
+            simulations_compacted: self.janitor.simulations_compacted.load(Ordering::SeqCst),
+            reclaimed_bytes: self.janitor.reclaimed_bytes.load(Ordering::SeqCst),
         }
     }
 
     /// Open a new `OSMFSession`
-    pub fn open_session(&mut self) -> Cookie {
+    pub fn open_session(&self) -> Cookie<'static> {
         self.prune_sessions();
         let session = OSMFSession::new(nanoid::nanoid!());
         let cookie = session.build_cookie();
-        self.sessions.insert(session.id.clone(), session);
+        self.sessions.insert(session.id.clone(), OSMFSessionEntry {
+            session: Arc::new(RwLock::new(session)),
+            last_accessed: Instant::now(),
+        });
         cookie
     }
 
-    /// Refresh the `OSMFSession` with session id `id`
-    pub fn refresh_session(&mut self, id: &str) -> Option<Cookie> {
-        self.prune_sessions();
-        let string_id = &id.to_string();
-        if self.sessions.contains_key(string_id) {
+    /// Refresh the `OSMFSession` with session id `id`.
+    /// Returns the cookie of a newly opened session if no session with id `id` exists.
+    pub fn refresh_session(&self, id: &str) -> Option<Cookie<'static>> {
+        if self.get_session(id).is_some() {
             None
         } else {
             Some(self.open_session())
         }
     }
 
-    /// Get a reference to the `OSMFSession` with session id `id`
-    pub fn get_session(&mut self, id: &str) -> Option<&OSMFSession> {
+    /// Get the `OSMFSession` with session id `id`, which must be locked before accessing it
+    pub fn get_session(&self, id: &str) -> Option<Arc<RwLock<OSMFSession>>> {
         self.prune_sessions();
-        let string_id = &id.to_string();
-        self.sessions.get(string_id)
+        self.sessions.get_mut(id).map(|mut entry| {
+            entry.last_accessed = Instant::now();
+            entry.session.clone()
+        })
     }
+}
 
-    /// Get a mutable reference to the `OSMFSession` with session id `id`
-    pub fn get_mut_session(&mut self, id: &str) -> Option<&mut OSMFSession> {
-        self.prune_sessions();
-        let string_id = &id.to_string();
-        self.sessions.get_mut(string_id)
+#[cfg(test)]
+mod test {
+    use crate::web_utils::session::OSMFSessionStorage;
+
+    #[test]
+    fn test_independent_session_locks() {
+        let sessions = OSMFSessionStorage::new();
+        let sid1 = sessions.open_session().value().to_string();
+        let sid2 = sessions.open_session().value().to_string();
+        assert_ne!(sid1, sid2);
+
+        // Locking one session neither blocks access to other sessions nor to the storage
+        let session1 = sessions.get_session(&sid1).unwrap();
+        let _guard = session1.write().unwrap();
+        assert!(sessions.get_session(&sid2).unwrap().try_write().is_ok());
+        assert!(sessions.get_session(&sid1).unwrap().try_read().is_err());
+        assert!(sessions.refresh_session(&sid1).is_none());
+
+        let new_cookie = sessions.refresh_session("unknown").unwrap();
+        assert!(sessions.get_session(new_cookie.value()).is_some());
+        assert!(sessions.get_session("unknown").is_none());
     }
 }
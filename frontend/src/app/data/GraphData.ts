@@ -1,4 +1,7 @@
 export class GraphData {
   name: string;
   num_of_nodes: number;
+  num_components: number;
+  num_strong_components: number;
+  largest_component_nodes: number;
 }
@@ -0,0 +1,7 @@
+export class NearestNode {
+  id: number;
+  osm_id: number;
+  lat: number;
+  lon: number;
+  distance_m: number;
+}
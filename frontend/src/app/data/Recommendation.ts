@@ -0,0 +1,8 @@
+import { StrategyRecommendation } from './StrategyRecommendation';
+
+export class Recommendation {
+  graph_name: string;
+  num_roots: number;
+  num_ffs: number;
+  strategies: StrategyRecommendation[];
+}
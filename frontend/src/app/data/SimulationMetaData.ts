@@ -1,5 +1,16 @@
+import { SimulationTime } from './SimulationTime';
+
 export class SimulationMetaData {
   end_time: number;
+  end_time_minutes: number;
+  end: SimulationTime;
+  units: {
+    time: string;
+    distance: string;
+    area: string;
+    minutes_per_round: number;
+    start_timestamp: number | null;
+  };
   view_center: number[];
   view_bounds: {
     min_lon: number;
@@ -0,0 +1,12 @@
+export class SimulationPreview {
+  is_estimate: boolean;
+  nodes_burned: number;
+  nodes_defended: number;
+  nodes_total: number;
+  end_time: number;
+  end_time_minutes: number;
+  simulation_time_millis: number;
+  seed: number;
+  coarsened_nodes: number;
+  coarsened_edges: number;
+}
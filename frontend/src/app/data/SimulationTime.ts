@@ -0,0 +1,5 @@
+export class SimulationTime {
+  round: number;
+  minutes: number;
+  timestamp?: number;
+}
@@ -1,4 +1,8 @@
+import { SimulationTime } from './SimulationTime';
+
 export class StepMetaData {
+  time: SimulationTime;
+  time_minutes: number;
   nodes_burned_at: number[];
   nodes_burned_by: number;
   nodes_defended_at: number[];
@@ -0,0 +1,10 @@
+export class StrategyRecommendation {
+  rank: number;
+  strategy_name: string;
+  source: 'history' | 'preview';
+  expected_saved_ratio: number;
+  runs: number;
+  standard_error: number | null;
+  confidence: 'low' | 'medium' | 'high';
+  expected_simulation_time_millis: number | null;
+}
@@ -0,0 +1,11 @@
+export class StrategyStats {
+  strategy_name: string;
+  min_roots: number;
+  max_roots: number;
+  min_ffs: number;
+  max_ffs: number;
+  runs: number;
+  mean_saved_ratio: number;
+  saved_ratio_variance: number | null;
+  mean_simulation_time_millis: number;
+}
@@ -7,6 +7,10 @@ import { environment } from '../../environments/environment';
 import { SimulationMetaData } from '../data/SimulationMetaData';
 import { StepMetaData } from '../data/StepMetaData';
 import { GraphData } from '../data/GraphData';
+import { NearestNode } from '../data/NearestNode';
+import { SimulationPreview } from '../data/SimulationPreview';
+import { StrategyStats } from '../data/StrategyStats';
+import { Recommendation } from '../data/Recommendation';
 
 @Injectable({
   providedIn: 'root'
@@ -25,6 +29,28 @@ export class GraphServiceService {
     return this.http.get<GraphData[]>(this.path + "/graphs");
   }
 
+  getNearestNode(graphName: string, coord: Coordinates): Observable<NearestNode> {
+    let params = new HttpParams()
+      .append('lat', coord.lat)
+      .append('lon', coord.lon);
+    return this.http.get<NearestNode>(this.path + "/graphs/" + encodeURIComponent(graphName) + "/nearest", {params: params});
+  }
+
+  getStrategyStats(graphName: string, numRoots: number, numFfs: number): Observable<StrategyStats[]> {
+    let params = new HttpParams()
+      .append('num_roots', numRoots)
+      .append('num_ffs', numFfs);
+    return this.http.get<StrategyStats[]>(this.path + "/graphs/" + encodeURIComponent(graphName) + "/strategy-stats", {params: params});
+  }
+
+  recommendStrategies(graphName: string, numRoots: number, numFfs: number): Observable<Recommendation> {
+    let params = new HttpParams()
+      .append('graph', graphName)
+      .append('num_roots', numRoots)
+      .append('num_ffs', numFfs);
+    return this.http.get<Recommendation>(this.path + "/recommend", {params: params});
+  }
+
   getStrategies(): Observable<string[]> {
     return this.http.get<string[]>(this.path + "/strategies")
   }
@@ -33,6 +59,11 @@ export class GraphServiceService {
     return this.http.post<SimulationMetaData>(this.path + "/simulate",config ,{withCredentials: true});
   }
 
+  previewSimulation(config: SimulationConfig): Observable<SimulationPreview> {
+    let params = new HttpParams().append('preview', true);
+    return this.http.post<SimulationPreview>(this.path + "/simulate", config, {params: params});
+  }
+
   getStepMetaData(turn: number): Observable<StepMetaData> {
     let params = new HttpParams().append('time', turn);
     return this.http.get<StepMetaData>(this.path + "/stepmeta", {params: params, withCredentials: true})
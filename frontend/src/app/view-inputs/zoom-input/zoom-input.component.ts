@@ -34,7 +34,8 @@ export class ZoomInputComponent implements OnInit {
     } else {
       this.currentZoom = zoomIn ? this.currentZoom + 10 : this.currentZoom - 10;
     }
-    this.currentZoom = Math.round((this.currentZoom) * 100) / 100;
+    // The backend rejects zoom levels below 0.1
+    this.currentZoom = Math.max(0.1, Math.round((this.currentZoom) * 100) / 100);
   }
 
 }
@@ -0,0 +1,76 @@
+use std::f64::consts::PI;
+
+use crate::{Graph, ParseError, CANONICAL_COORD_PRECISION};
+
+/// Approximate length of one degree of latitude in meters
+const METERS_PER_DEG_LAT: f64 = 111_320.0;
+
+/// Header fields that describe the edge weights, which are kept in anonymized graphs
+const WEIGHT_KEYS: [&str; 2] = ["Type", "Weights"];
+
+/// Transform that hides where a graph is located while preserving its topology and its edge
+/// weights. External ids are replaced by node ids, elevations are removed and all header fields
+/// except those describing the edge weights are dropped in either case.
+#[derive(Clone, Copy)]
+pub(crate) enum Anonymization {
+    /// Place all nodes at latitude and longitude 0
+    Strip,
+    /// Move every node to a random position within `radius_m` meters of its position, derived
+    /// from `seed`
+    Jitter { radius_m: f64, seed: u64 },
+}
+
+/// Pseudo random number generator with the SplitMix64 algorithm, which is good enough to jitter
+/// coordinates and does not require a dependency
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Get the next random number in `[0, 1)`
+    fn next_f64(&mut self) -> f64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Anonymize `graph` by `anonymization`
+pub(crate) fn anonymize(graph: &mut Graph, anonymization: Anonymization) -> Result<(), ParseError> {
+    let mut meta: String = graph.meta.lines()
+        .filter(|line| matches!(line.trim_start_matches('#').split_once(':'),
+                                Some((key, _)) if WEIGHT_KEYS.contains(&key.trim())))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    let name = match anonymization {
+        Anonymization::Strip => "strip",
+        Anonymization::Jitter { .. } => "jitter",
+    };
+    meta.push_str(&format!("# Anonymized : {}\n\n", name));
+    graph.meta = meta;
+
+    let mut rng = match anonymization {
+        Anonymization::Jitter { seed, .. } => Some(SplitMix64(seed)),
+        Anonymization::Strip => None
+    };
+    for node in &mut graph.nodes {
+        node.id2 = node.id;
+        node.elevation = "0".to_string();
+        let (lat, lon) = match (anonymization, rng.as_mut()) {
+            (Anonymization::Jitter { radius_m, .. }, Some(rng)) => {
+                // Uniformly distributed within the circle around the node
+                let (lat, lon): (f64, f64) = (node.lat.parse()?, node.lon.parse()?);
+                let angle = 2.0 * PI * rng.next_f64();
+                let dist = radius_m * rng.next_f64().sqrt();
+                (lat + dist * angle.cos() / METERS_PER_DEG_LAT,
+                 lon + dist * angle.sin() / (METERS_PER_DEG_LAT * lat.to_radians().cos()))
+            }
+            _ => (0.0, 0.0)
+        };
+        node.lat = format!("{:.*}", CANONICAL_COORD_PRECISION, lat);
+        node.lon = format!("{:.*}", CANONICAL_COORD_PRECISION, lon);
+    }
+
+    Ok(())
+}
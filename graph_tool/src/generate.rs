@@ -0,0 +1,155 @@
+use crate::{Edge, Graph, Node, ParseError};
+
+/// Latitude of the north west corner of generated graphs
+const BASE_LAT: f64 = 48.7;
+
+/// Longitude of the north west corner of generated graphs
+const BASE_LON: f64 = 9.1;
+
+/// Approximate length of one degree of latitude in meters
+const METERS_PER_DEG_LAT: f64 = 111_320.0;
+
+/// Edge type of generated edges
+const GENERATED_EDGE_TYPE: &str = "0";
+
+/// Maximum speed in km/h of generated edges
+const GENERATED_MAXSPEED: &str = "50";
+
+/// Create a new, empty graph with a header describing the generated graph as `description`
+fn new_generated_graph(description: &str) -> Graph {
+    let mut graph = Graph::new();
+    graph.meta = format!("# Id : 0\n# Timestamp : 0\n# Type : {}\n# Revision : 1\n\n", description);
+    graph
+}
+
+/// Add a node at `dx` meters east and `dy` meters south of the north west corner to `graph`
+fn add_node(graph: &mut Graph, dx: f64, dy: f64) {
+    let lat = BASE_LAT - dy / METERS_PER_DEG_LAT;
+    let lon = BASE_LON + dx / (METERS_PER_DEG_LAT * BASE_LAT.to_radians().cos());
+    let id = graph.nodes.len();
+    graph.nodes.push(Node {
+        id,
+        id2: id,
+        lat: format!("{:.7}", lat),
+        lon: format!("{:.7}", lon),
+        elevation: "0".to_string(),
+    });
+}
+
+/// Add edges in both directions between the nodes `a` and `b` with weight `dist` to `graph`
+fn add_undirected_edge(graph: &mut Graph, a: usize, b: usize, dist: usize) {
+    for (src, tgt) in [(a, b), (b, a)] {
+        graph.edges.push(Edge {
+            a: src,
+            b: tgt,
+            dist,
+            edge_type: GENERATED_EDGE_TYPE.to_string(),
+            maxspeed: GENERATED_MAXSPEED.to_string(),
+        });
+    }
+}
+
+/// Generate an undirected grid graph with `rows` rows and `cols` columns of nodes that are
+/// `spacing` meters apart. Edges between adjacent nodes have weight `spacing`.
+pub(crate) fn generate_grid(rows: usize, cols: usize, spacing: usize) -> Graph {
+    let mut graph = new_generated_graph(
+        &format!("grid rows={} cols={} spacing={}", rows, cols, spacing));
+
+    for row in 0..rows {
+        for col in 0..cols {
+            add_node(&mut graph, (col * spacing) as f64, (row * spacing) as f64);
+        }
+    }
+    for row in 0..rows {
+        for col in 0..cols {
+            let id = row * cols + col;
+            if col + 1 < cols {
+                add_undirected_edge(&mut graph, id, id + 1, spacing);
+            }
+            if row + 1 < rows {
+                add_undirected_edge(&mut graph, id, id + cols, spacing);
+            }
+        }
+    }
+
+    graph.edges.sort_by_key(|edge| (edge.a, edge.b));
+    graph.num_nodes = graph.nodes.len();
+    graph.num_edges = graph.edges.len();
+    graph
+}
+
+/// Generate a complete undirected tree of depth `depth` in which every inner node has
+/// `branching` children. Levels are `spacing` meters apart, leaves are spread evenly below
+/// their ancestors and edges have weight `spacing`. Nodes are numbered in breadth-first order.
+/// Returns an error if `branching` is `0`.
+pub(crate) fn generate_tree(depth: usize, branching: usize, spacing: usize) -> Result<Graph, ParseError> {
+    if branching == 0 {
+        return Err(ParseError::InvalidArgument("--branching"));
+    }
+    let mut graph = new_generated_graph(
+        &format!("tree depth={} branching={} spacing={}", depth, branching, spacing));
+
+    let num_leaves = branching.pow(depth as u32);
+    let mut level_start = 0;
+    for level in 0..=depth {
+        let level_size = branching.pow(level as u32);
+        // Width in meters of the subtree below each node of this level
+        let subtree_width = (num_leaves / level_size * spacing) as f64;
+        for i in 0..level_size {
+            add_node(&mut graph, (i as f64 + 0.5) * subtree_width, (level * spacing) as f64);
+            if level > 0 {
+                let parent = level_start - level_size / branching + i / branching;
+                add_undirected_edge(&mut graph, parent, level_start + i, spacing);
+            }
+        }
+        level_start += level_size;
+    }
+
+    graph.edges.sort_by_key(|edge| (edge.a, edge.b));
+    graph.num_nodes = graph.nodes.len();
+    graph.num_edges = graph.edges.len();
+    Ok(graph)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate_grid() {
+        let graph = generate_grid(3, 4, 50);
+        assert_eq!((graph.num_nodes, graph.nodes.len()), (12, 12));
+        // 3 * 3 horizontal and 2 * 4 vertical edges in both directions
+        assert_eq!((graph.num_edges, graph.edges.len()), (34, 34));
+        assert!(graph.edges.iter().all(|edge| edge.dist == 50));
+        assert!(graph.edges.iter().any(|edge| (edge.a, edge.b) == (5, 9)));
+        assert!(!graph.edges.iter().any(|edge| (edge.a, edge.b) == (3, 4)));
+    }
+
+    #[test]
+    fn test_generate_tree() {
+        let graph = generate_tree(3, 2, 100).unwrap();
+        assert_eq!(graph.num_nodes, 15);
+        assert_eq!(graph.num_edges, 28);
+
+        // Every node but the root is connected to its parent in breadth-first order
+        for child in 1..graph.num_nodes {
+            let parent = (child - 1) / 2;
+            assert!(graph.edges.iter().any(|edge| (edge.a, edge.b) == (parent, child)));
+            assert!(graph.edges.iter().any(|edge| (edge.a, edge.b) == (child, parent)));
+        }
+
+        // Leaves are spread evenly below the root
+        let lon = |id: usize| graph.nodes[id].lon.parse::<f64>().unwrap();
+        assert!(lon(7) < lon(0) && lon(0) < lon(14));
+        assert!((lon(0) - lon(7) - (lon(14) - lon(0))).abs() < 1e-6);
+
+        let graph = generate_tree(0, 3, 100).unwrap();
+        assert_eq!((graph.num_nodes, graph.num_edges), (1, 0));
+    }
+
+    #[test]
+    fn test_generate_tree_without_branching() {
+        assert!(matches!(generate_tree(2, 0, 100), Err(ParseError::InvalidArgument("--branching"))));
+    }
+}
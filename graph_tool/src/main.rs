@@ -1,25 +1,36 @@
 use std::collections::BTreeMap;
 use std::env;
 use std::fmt::Formatter;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{BufRead, BufReader, LineWriter, Write};
 use std::num::{ParseFloatError, ParseIntError};
+use std::process;
+
+use crate::anonymize::Anonymization;
+use crate::weights::Weights;
+
+mod anonymize;
+mod generate;
+mod merge;
+mod weights;
 
 #[derive(Debug)]
 enum ParseError {
     IO(std::io::Error),
     ParseInt(ParseIntError),
     ParseFloat(ParseFloatError),
-    //InvalidNode(usize),
+    InvalidNode(usize),
+    InvalidArgument(&'static str),
 }
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::IO(err) => write!(f, "{}", err.to_string()),
-            Self::ParseInt(err) => write!(f, "{}", err.to_string()),
-            Self::ParseFloat(err) => write!(f, "{}", err.to_string()),
-            //Self::InvalidNode(node_id) => write!(f, "Invalid node {}", node_id)
+            Self::IO(err) => write!(f, "{}", err),
+            Self::ParseInt(err) => write!(f, "{}", err),
+            Self::ParseFloat(err) => write!(f, "{}", err),
+            Self::InvalidNode(node_id) => write!(f, "Invalid node {}", node_id),
+            Self::InvalidArgument(name) => write!(f, "Invalid value for argument: {}", name),
         }
     }
 }
@@ -30,7 +41,7 @@ impl std::error::Error for ParseError {
             Self::IO(ref err) => Some(err),
             Self::ParseInt(ref err) => Some(err),
             Self::ParseFloat(ref err) => Some(err),
-            //_ => None
+            _ => None
         }
     }
 }
@@ -53,7 +64,14 @@ impl From<ParseFloatError> for ParseError {
     }
 }
 
-/// An undirected graph edge between two nodes a and b
+/// Number of decimal places of latitudes and longitudes in canonical output
+const CANONICAL_COORD_PRECISION: usize = 7;
+
+/// Default distance in meters between adjacent nodes of generated graphs
+const DEFAULT_SPACING: usize = 100;
+
+/// A graph edge between two nodes a and b
+#[derive(Clone)]
 struct Edge {
     a: usize,
     b: usize,
@@ -76,15 +94,33 @@ struct Graph {
     meta: String,
     nodes: Vec<Node>,
     edges: Vec<Edge>,
-    new_edges: Vec<(usize, usize, usize, String, String)>,
+    new_edges: Vec<Edge>,
     num_nodes: usize,
     num_edges: usize,
-    new_num_edges: usize,
+}
+
+/// Get the next whitespace separated field from `split` or panic with a message that refers to
+/// `what` and line `line_no` if there is none
+fn next_field<'a>(split: &mut impl Iterator<Item = &'a str>, what: &str, line_no: usize) -> &'a str {
+    split.next()
+        .unwrap_or_else(|| panic!("Unexpected EOL while parsing {} in line {}", what, line_no))
 }
 
 impl Graph {
-    /// Parse node and edge data from a file into an undirected graph
-    fn parse_graph (&mut self, graph_file_path: &str) -> Result<(), ParseError> {
+    /// Create a new, empty graph
+    fn new() -> Self {
+        Self {
+            meta: "".to_string(),
+            nodes: vec![],
+            edges: vec![],
+            new_edges: vec![],
+            num_nodes: 0,
+            num_edges: 0,
+        }
+    }
+
+    /// Parse node and edge data from a file
+    fn parse_graph(&mut self, graph_file_path: &str) -> Result<(), ParseError> {
         let graph_file = File::open(graph_file_path)?;
         let graph_reader = BufReader::new(graph_file);
 
@@ -93,161 +129,361 @@ impl Graph {
 
         loop {
             let line = lines.next()
-                .expect(&format!("Unexpected EOF while parsing header in line {}", line_no))?;
+                .unwrap_or_else(|| panic!("Unexpected EOF while parsing header in line {}", line_no))?;
             line_no += 1;
 
             self.meta.push_str(&line);
-            self.meta.push_str("\n");
+            self.meta.push('\n');
 
-            if !line.starts_with("#") {
+            if !line.starts_with('#') {
                 break;
             }
         }
 
         self.num_nodes = lines.next()
             .expect("Unexpected EOF while parsing number of nodes")?
+            .trim()
             .parse()?;
         self.num_edges = lines.next()
             .expect("Unexpected EOF while parsing number of edges")?
+            .trim()
             .parse()?;
-        line_no += 3;
+        line_no += 2;
 
         self.nodes.reserve_exact(self.num_nodes);
-        for i in 0..self.num_nodes {
+        for _ in 0..self.num_nodes {
             let line = lines.next()
-                .expect(&format!("Unexpected EOF while parsing nodes in line {}", line_no))?;
-            let mut split = line.split(" ");
+                .unwrap_or_else(|| panic!("Unexpected EOF while parsing nodes in line {}", line_no))?;
+            let mut split = line.split_whitespace();
             line_no += 1;
-            split.next(); // id
 
             let node = Node {
-                id: i,
-                id2: split.next()
-                    .expect(&format!("Unexpected EOL while parsing node latitude in line {}",
-                                     line_no))
-                    .parse()?,
-                lat: split.next()
-                    .expect(&format!("Unexpected EOL while parsing node latitude in line {}",
-                                     line_no))
-                    .to_string(),
-                lon: split.next()
-                    .expect(&format!("Unexpected EOL while parsing node longitude in line {}",
-                                     line_no))
-                    .to_string(),
-                elevation: split.next()
-                    .expect(&format!("Unexpected EOL while parsing node latitude in line {}",
-                                     line_no))
-                    .to_string(),
+                id: next_field(&mut split, "node id", line_no).parse()?,
+                id2: next_field(&mut split, "node external id", line_no).parse()?,
+                lat: next_field(&mut split, "node latitude", line_no).to_string(),
+                lon: next_field(&mut split, "node longitude", line_no).to_string(),
+                elevation: next_field(&mut split, "node elevation", line_no).to_string(),
             };
             self.nodes.push(node);
         }
 
         self.edges.reserve_exact(self.num_edges);
-        let mut new_temp_edges: BTreeMap<(usize, usize), Vec<(usize, usize, usize, String, String)>> = BTreeMap::new();
         for _ in 0..self.num_edges {
             let line = lines.next()
-                .expect(&format!("Unexpected EOF while parsing edges in line {}", line_no))?;
-            let mut split = line.split(" ");
+                .unwrap_or_else(|| panic!("Unexpected EOF while parsing edges in line {}", line_no))?;
+            let mut split = line.split_whitespace();
             line_no += 1;
 
             let edge = Edge {
-                a: split.next()
-                    .expect(&format!("Unexpected EOL while parsing edge source in line {}",
-                                     line_no))
-                    .parse()?,
-                b: split.next()
-                    .expect(&format!("Unexpected EOL while parsing edge target in line {}",
-                                     line_no))
-                    .parse()?,
-                dist: split.next()
-                    .expect(&format!("Unexpected EOL while parsing edge weight in line {}",
-                                     line_no))
-                    .parse()?,
-                edge_type: split.next()
-                    .expect(&format!("Unexpected EOL while parsing edge weight in line {}",
-                                     line_no))
-                    .to_string(),
-                maxspeed: split.next()
-                    .expect(&format!("Unexpected EOL while parsing edge weight in line {}",
-                                     line_no))
-                    .to_string(),
+                a: next_field(&mut split, "edge source", line_no).parse()?,
+                b: next_field(&mut split, "edge target", line_no).parse()?,
+                dist: next_field(&mut split, "edge weight", line_no).parse()?,
+                edge_type: next_field(&mut split, "edge type", line_no).to_string(),
+                maxspeed: next_field(&mut split, "edge maxspeed", line_no).to_string(),
             };
+            self.edges.push(edge);
+        }
 
+        Ok(())
+    }
+
+    /// Compute the edges of the undirected graph. For every pair of adjacent nodes, edges in both
+    /// directions with the minimum weight of all edges between the nodes are kept.
+    fn make_undirected(&mut self) {
+        let mut new_temp_edges: BTreeMap<(usize, usize), Vec<Edge>> = BTreeMap::new();
+        for edge in &self.edges {
             let min_vertex = edge.a.min(edge.b);
             let max_vertex = edge.a.max(edge.b);
             new_temp_edges.entry((min_vertex, max_vertex))
-                .and_modify(|edges|{
-                    if edge.dist < edges[0].2 {
-                        edges[0].2 = edge.dist;
-                        edges[1].2 = edge.dist;
+                .and_modify(|edges| {
+                    if edge.dist < edges[0].dist {
+                        edges[0].dist = edge.dist;
+                        edges[1].dist = edge.dist;
                     }
                 })
-                .or_insert(vec![(edge.a, edge.b, edge.dist, edge.edge_type.clone(), edge.maxspeed.clone()), (edge.b, edge.a, edge.dist, edge.edge_type, edge.maxspeed)]);
+                .or_insert_with(|| vec![edge.clone(), Edge { a: edge.b, b: edge.a, ..edge.clone() }]);
         }
 
-        self.new_edges.reserve_exact(new_temp_edges.len() * 2);
-        for edge_values in new_temp_edges.values() {
-            for (a, b, dist, edge_type, maxspeed) in edge_values {
-                self.new_edges.push((*a, *b, *dist, edge_type.clone(), maxspeed.clone()));
+        self.new_edges = new_temp_edges.into_values().flatten().collect();
+        self.new_edges.sort_by_key(|edge| (edge.a, edge.b));
+    }
+
+    /// Write the header, the nodes and the edges `edges` of this graph to `out`.
+    /// In canonical mode, whitespace in the header is normalized, nodes are sorted by id, edges
+    /// are sorted by source and target, coordinates are written with a fixed precision and
+    /// elevations in their shortest exact form, such that equal graphs are always written
+    /// identically.
+    fn write_to<W: Write>(&self, out: &mut W, edges: &[Edge], canonical: bool) -> Result<(), ParseError> {
+        if canonical {
+            for line in self.meta.lines().filter(|line| line.starts_with('#')) {
+                writeln!(out, "{}", line.split_whitespace().collect::<Vec<_>>().join(" "))?;
+            }
+            writeln!(out)?;
+        } else {
+            write!(out, "{}", self.meta)?;
+        }
+        writeln!(out, "{}", self.nodes.len())?;
+        writeln!(out, "{}", edges.len())?;
+
+        if canonical {
+            let mut nodes: Vec<_> = self.nodes.iter().collect();
+            nodes.sort_by_key(|node| node.id);
+            for node in nodes {
+                writeln!(out, "{} {} {:.*} {:.*} {}", node.id, node.id2,
+                         CANONICAL_COORD_PRECISION, node.lat.parse::<f64>()?,
+                         CANONICAL_COORD_PRECISION, node.lon.parse::<f64>()?,
+                         node.elevation.parse::<f64>()?)?;
+            }
+
+            let mut edges: Vec<_> = edges.iter().collect();
+            edges.sort_by(|e1, e2| (e1.a, e1.b, e1.dist, &e1.edge_type, &e1.maxspeed)
+                .cmp(&(e2.a, e2.b, e2.dist, &e2.edge_type, &e2.maxspeed)));
+            for edge in edges {
+                writeln!(out, "{} {} {} {} {}", edge.a, edge.b, edge.dist, edge.edge_type, edge.maxspeed)?;
+            }
+        } else {
+            for node in &self.nodes {
+                writeln!(out, "{} {} {} {} {}", node.id, node.id2, node.lat, node.lon, node.elevation)?;
+            }
+            for edge in edges {
+                writeln!(out, "{} {} {} {} {}", edge.a, edge.b, edge.dist, edge.edge_type, edge.maxspeed)?;
             }
         }
-        self.new_edges.sort_unstable_by(|e1, e2| {
-            let id1 = e1.0;
-            let id2 = e2.0;
-            id1.cmp(&id2).then_with(||{
-                let id1 = e1.1;
-                let id2 = e2.1;
-                id1.cmp(&id2)
-            })
-        });
-        self.new_num_edges = self.new_edges.len();
 
         Ok(())
     }
 
-    fn write_graph(&mut self, graph_file_path_out: &str) -> std::io::Result<()> {
+    /// Write the header, the nodes and the edges `edges` of this graph to the file at
+    /// `graph_file_path_out`
+    fn write_graph(&self, graph_file_path_out: &str, edges: &[Edge], canonical: bool) -> Result<(), ParseError> {
         let file = File::create(graph_file_path_out)?;
         let mut file = LineWriter::new(file);
 
-        file.write((format!("{}", self.meta)).as_bytes())?;
-        file.write((format!("{}\n", self.num_nodes)).as_bytes())?;
-        file.write((format!("{}\n", self.new_num_edges)).as_bytes())?;
+        self.write_to(&mut file, edges, canonical)
+    }
 
-        for node in &self.nodes {
-            file.write((format!("{} {} {} {} {}\n", node.id, node.id2, node.lat, node.lon, node.elevation)).as_bytes())?;
+    /// Check whether the file at `graph_file_path` that this graph has been parsed from is
+    /// written in canonical form.
+    /// Returns the number of the first line that differs from the canonical form, if any.
+    fn check_canonical(&self, graph_file_path: &str) -> Result<Option<usize>, ParseError> {
+        let mut canonical = Vec::new();
+        self.write_to(&mut canonical, &self.edges, true)?;
+        let canonical = String::from_utf8(canonical).expect("Canonical output is valid UTF-8");
+        let actual = fs::read_to_string(graph_file_path)?;
+
+        if canonical == actual {
+            return Ok(None);
         }
+        let first_diff = canonical.lines().zip(actual.lines())
+            .position(|(c, a)| c != a)
+            .unwrap_or_else(|| canonical.lines().count().min(actual.lines().count()));
 
-        for (a, b, dist, edge_type, maxspeed) in &self.new_edges {
-            file.write((format!("{} {} {} {} {}\n", a, b, dist, edge_type, maxspeed)).as_bytes())?;
-        }
+        Ok(Some(first_diff + 1))
+    }
+}
 
-        Ok(())
+/// Get the value of the named argument `name` from `args` parsed as `usize`, or `default` if the
+/// argument is not given
+fn named_arg(args: &[String], name: &str, default: Option<usize>) -> usize {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) => args.get(i + 1)
+            .unwrap_or_else(|| panic!("Missing value for argument: {}", name))
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid value for argument: {}", name)),
+        None => default.unwrap_or_else(|| panic!("Missing argument: {}", name))
     }
 }
 
-/// Given arguments look like: "path/graphname.fmi path/new_graphname.fmi". Parses "graphname.fmi", creates an undirected graph and writes it in "new_graphname.fmi".
+/// Given arguments look like the following. All commands that write a graph additionally accept
+/// "--weights traveltime|distance" to recompute the edge weights as travel times in seconds or
+/// distances in meters.
+/// - "path/graphname.fmi path/new_graphname.fmi [--canonical]". Parses "graphname.fmi", creates
+///   an undirected graph and writes it in "new_graphname.fmi", optionally in canonical form.
+/// - "--check path/graphname.fmi". Checks whether "graphname.fmi" is written in canonical form
+///   and exits with a non-zero exit code if it is not.
+/// - "merge path/merged.fmi path/graph1.fmi path/graph2.fmi ... [--canonical]". Merges all given
+///   graphs into "merged.fmi", optionally in canonical form.
+/// - "generate grid path/grid.fmi --rows R --cols C [--spacing S]" or
+///   "generate tree path/tree.fmi --depth D --branching B [--spacing S]". Generates a synthetic
+///   grid or tree graph with nodes `S` meters apart (100 by default) and writes it in the given
+///   file.
+/// - "anonymize path/graphname.fmi path/anonymized.fmi [--jitter M [--seed S]]". Writes the graph
+///   in "graphname.fmi" with the same topology and edge weights, but without external ids and
+///   header fields and with all nodes at latitude and longitude 0, or moved randomly by up to `M`
+///   meters, into "anonymized.fmi".
 fn main() -> Result<(), ParseError> {
     let args: Vec<_> = env::args().collect();
 
+    let canonical = args.iter().any(|arg| arg == "--canonical");
+    let mut args: Vec<_> = args.into_iter().filter(|arg| arg != "--canonical").collect();
+    let weights = match args.iter().position(|arg| arg == "--weights") {
+        Some(i) => {
+            let weights: Weights = args.get(i + 1)
+                .expect("Missing value for argument: --weights")
+                .parse()
+                .unwrap_or_else(|err| panic!("{}", err));
+            args.drain(i..i + 2);
+            Some(weights)
+        }
+        None => None
+    };
+
     if args.len() < 3 {
         let err = "Missing argument: path to new undirected graph file";
         panic!("{}", err);
     }
 
-    let in_graph = args[1].to_string();
-    let out_graph = args[2].to_string();
+    match args[1].as_str() {
+        "--check" => {
+            let mut graph = Graph::new();
+            graph.parse_graph(&args[2])?;
+            match graph.check_canonical(&args[2])? {
+                None => println!("{} is canonical", args[2]),
+                Some(line_no) => {
+                    eprintln!("{} is not canonical, first difference in line {}", args[2], line_no);
+                    process::exit(1);
+                }
+            }
+        }
+        "merge" => {
+            if args.len() < 4 {
+                panic!("Missing argument: paths to the graph files to merge");
+            }
+            let mut merged = merge::merge_graphs(&args[3..])?;
+            if let Some(weights) = weights {
+                weights::recompute_weights(&mut merged, weights)?;
+            }
+            merged.write_graph(&args[2], &merged.edges, canonical)?;
+        }
+        "generate" => {
+            if args.len() < 4 {
+                panic!("Missing argument: path to generated graph file");
+            }
+            let spacing = named_arg(&args, "--spacing", Some(DEFAULT_SPACING));
+            let mut graph = match args[2].as_str() {
+                "grid" => generate::generate_grid(named_arg(&args, "--rows", None),
+                                                  named_arg(&args, "--cols", None), spacing),
+                "tree" => generate::generate_tree(named_arg(&args, "--depth", None),
+                                                  named_arg(&args, "--branching", None), spacing)?,
+                kind => panic!("Unknown graph kind: {}", kind)
+            };
+            if let Some(weights) = weights {
+                weights::recompute_weights(&mut graph, weights)?;
+            }
+            graph.write_graph(&args[3], &graph.edges, canonical)?;
+        }
+        "anonymize" => {
+            if args.len() < 4 {
+                panic!("Missing argument: path to anonymized graph file");
+            }
+            let anonymization = match args.iter().position(|arg| arg == "--jitter") {
+                Some(_) => Anonymization::Jitter {
+                    radius_m: named_arg(&args, "--jitter", None) as f64,
+                    seed: named_arg(&args, "--seed", Some(0)) as u64,
+                },
+                None => Anonymization::Strip
+            };
+            let mut graph = Graph::new();
+            graph.parse_graph(&args[2])?;
+            if let Some(weights) = weights {
+                weights::recompute_weights(&mut graph, weights)?;
+            }
+            anonymize::anonymize(&mut graph, anonymization)?;
+            graph.write_graph(&args[3], &graph.edges, canonical)?;
+        }
+        in_graph => {
+            if let Some(arg) = args.get(3) {
+                panic!("Unknown argument: {}", arg);
+            }
 
-    let mut graph = Graph {
-        meta: "".to_string(),
-        nodes: vec![],
-        edges: vec![],
-        new_edges: Default::default(),
-        num_nodes: 0,
-        num_edges: 0,
-        new_num_edges: 0
-    };
-    graph.parse_graph(&in_graph)?;
-    graph.write_graph(&out_graph)?;
+            let mut graph = Graph::new();
+            graph.parse_graph(in_graph)?;
+            if let Some(weights) = weights {
+                weights::recompute_weights(&mut graph, weights)?;
+            }
+            graph.make_undirected();
+            graph.write_graph(&args[2], &graph.new_edges, canonical)?;
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Graph in the format of the graph files, but not in canonical form
+    const GRAPH: &str = "# Id : 1\n#  Type :   test\n\n3\n3\n\
+        1 11 48.70000001 9.1 312.25\n\
+        0 10 48.7 9.1000001 0\n\
+        2 12 48.7001 9.1001 -2.5\n\
+        1 2 15 3 50\n\
+        0 1 10 3 30\n\
+        0 2 20 4 70\n";
+
+    /// Write `content` into a temporary file named after `name` and get the path of the file
+    pub(crate) fn write_temp_graph(name: &str, content: &str) -> String {
+        let path = env::temp_dir().join(format!("graph_tool_{}_{}.fmi", process::id(), name));
+        fs::write(&path, content).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    /// Parse the graph in the file at `path`
+    fn parse(path: &str) -> Graph {
+        let mut graph = Graph::new();
+        graph.parse_graph(path).unwrap();
+        graph
+    }
+
+    /// Write `graph` with its edges `edges` in canonical form into a string
+    fn canonical_string(graph: &Graph, edges: &[Edge]) -> String {
+        let mut out = Vec::new();
+        graph.write_to(&mut out, edges, true).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_canonical_round_trip() {
+        let path = write_temp_graph("canonical_in", GRAPH);
+        let graph = parse(&path);
+        let canonical = canonical_string(&graph, &graph.edges);
+
+        assert_eq!(canonical, "# Id : 1\n# Type : test\n\n3\n3\n\
+            0 10 48.7000000 9.1000001 0\n\
+            1 11 48.7000000 9.1000000 312.25\n\
+            2 12 48.7001000 9.1001000 -2.5\n\
+            0 1 10 3 30\n\
+            0 2 20 4 70\n\
+            1 2 15 3 50\n");
+
+        // Canonical output is a fixed point of parsing and writing it in canonical form again
+        let out_path = write_temp_graph("canonical_out", &canonical);
+        let reparsed = parse(&out_path);
+        assert_eq!(canonical_string(&reparsed, &reparsed.edges), canonical);
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(out_path).unwrap();
+    }
+
+    #[test]
+    fn test_check_canonical() {
+        let path = write_temp_graph("check_in", GRAPH);
+        let graph = parse(&path);
+        // The header whitespace in line 2 is the first difference to the canonical form
+        assert_eq!(graph.check_canonical(&path).unwrap(), Some(2));
+
+        let canonical_path = write_temp_graph("check_out", "");
+        graph.write_graph(&canonical_path, &graph.edges, true).unwrap();
+        assert_eq!(parse(&canonical_path).check_canonical(&canonical_path).unwrap(), None);
+
+        // Trailing lines that only the file has are reported as well
+        let mut extended = fs::read_to_string(&canonical_path).unwrap();
+        extended.push_str("0 1 10 3 30\n");
+        fs::write(&canonical_path, extended).unwrap();
+        assert_eq!(graph.check_canonical(&canonical_path).unwrap(), Some(12));
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(canonical_path).unwrap();
+    }
+}
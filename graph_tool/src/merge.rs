@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{CANONICAL_COORD_PRECISION, Edge, Graph, Node, ParseError};
+
+/// Statistics about the overlap of a graph with all previously merged graphs
+struct OverlapStats {
+    num_nodes: usize,
+    num_edges: usize,
+    by_external_id: usize,
+    by_coords: usize,
+    duplicate_edges: usize,
+}
+
+/// Get the key by which nodes with equal coordinates are identified
+fn coords_key(node: &Node) -> Result<(String, String), ParseError> {
+    Ok((format!("{:.*}", CANONICAL_COORD_PRECISION, node.lat.parse::<f64>()?),
+        format!("{:.*}", CANONICAL_COORD_PRECISION, node.lon.parse::<f64>()?)))
+}
+
+/// Merge the graphs in the files at `graph_file_paths` into a single graph.
+/// Nodes with equal external ids or equal coordinates are merged into one node and all nodes
+/// are reindexed in order of their first occurrence. Of multiple edges between the same nodes,
+/// only the one with the minimum weight is kept. Overlap statistics are printed for each graph.
+pub(crate) fn merge_graphs(graph_file_paths: &[String]) -> Result<Graph, ParseError> {
+    let mut merged = Graph::new();
+    let mut by_external_id: HashMap<usize, usize> = HashMap::new();
+    let mut by_coords: HashMap<(String, String), usize> = HashMap::new();
+    let mut edges: BTreeMap<(usize, usize), Edge> = BTreeMap::new();
+
+    for (i, graph_file_path) in graph_file_paths.iter().enumerate() {
+        let mut graph = Graph::new();
+        graph.parse_graph(graph_file_path)?;
+        if i == 0 {
+            merged.meta = graph.meta.clone();
+        }
+
+        let mut stats = OverlapStats {
+            num_nodes: graph.nodes.len(),
+            num_edges: graph.edges.len(),
+            by_external_id: 0,
+            by_coords: 0,
+            duplicate_edges: 0,
+        };
+
+        // Map the node ids of the graph to the ids of the merged graph
+        let mut new_ids: HashMap<usize, usize> = HashMap::with_capacity(graph.nodes.len());
+        for node in graph.nodes {
+            let key = coords_key(&node)?;
+            let new_id = if let Some(&new_id) = by_external_id.get(&node.id2) {
+                stats.by_external_id += 1;
+                new_id
+            } else if let Some(&new_id) = by_coords.get(&key) {
+                stats.by_coords += 1;
+                new_id
+            } else {
+                let new_id = merged.nodes.len();
+                merged.nodes.push(Node {
+                    id: new_id,
+                    ..node
+                });
+                new_id
+            };
+            by_external_id.entry(merged.nodes[new_id].id2).or_insert(new_id);
+            by_coords.entry(key).or_insert(new_id);
+            new_ids.insert(node.id, new_id);
+        }
+
+        for edge in graph.edges {
+            let map_id = |id: usize| new_ids.get(&id).copied().ok_or(ParseError::InvalidNode(id));
+            let edge = Edge {
+                a: map_id(edge.a)?,
+                b: map_id(edge.b)?,
+                ..edge
+            };
+            match edges.get_mut(&(edge.a, edge.b)) {
+                Some(existing) => {
+                    stats.duplicate_edges += 1;
+                    if edge.dist < existing.dist {
+                        *existing = edge;
+                    }
+                }
+                None => {
+                    edges.insert((edge.a, edge.b), edge);
+                }
+            }
+        }
+
+        println!("{}: {} nodes, {} edges", graph_file_path, stats.num_nodes, stats.num_edges);
+        println!("  nodes merged by external id: {}", stats.by_external_id);
+        println!("  nodes merged by coordinates: {}", stats.by_coords);
+        println!("  duplicate edges dropped: {}", stats.duplicate_edges);
+    }
+
+    merged.edges = edges.into_values().collect();
+    merged.num_nodes = merged.nodes.len();
+    merged.num_edges = merged.edges.len();
+    println!("Merged {} graphs into {} nodes and {} edges", graph_file_paths.len(),
+             merged.num_nodes, merged.num_edges);
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::test::write_temp_graph;
+
+    use super::*;
+
+    #[test]
+    fn test_merge_graphs() {
+        let paths = vec![
+            write_temp_graph("merge_1", "# Id : 1\n\n2\n2\n\
+                0 100 48.7 9.1 0\n\
+                1 101 48.8 9.2 0\n\
+                0 1 30 3 50\n\
+                1 0 30 3 50\n"),
+            // Node 0 equals node 0 of the first graph by external id and node 1 equals node 1 of
+            // the first graph by coordinates
+            write_temp_graph("merge_2", "# Id : 2\n\n3\n3\n\
+                0 100 48.75 9.15 0\n\
+                1 201 48.80000001 9.2 0\n\
+                2 202 48.9 9.3 0\n\
+                0 1 20 3 50\n\
+                1 0 40 3 50\n\
+                1 2 10 3 50\n"),
+        ];
+
+        let merged = merge_graphs(&paths).unwrap();
+        assert_eq!(merged.meta, "# Id : 1\n\n");
+        assert_eq!(merged.nodes.iter().map(|node| (node.id, node.id2)).collect::<Vec<_>>(),
+                   vec![(0, 100), (1, 101), (2, 202)]);
+        assert_eq!(merged.nodes[0].lat, "48.7");
+        assert_eq!(merged.edges.iter().map(|edge| (edge.a, edge.b, edge.dist)).collect::<Vec<_>>(),
+                   vec![(0, 1, 20), (1, 0, 30), (1, 2, 10)]);
+        assert_eq!((merged.num_nodes, merged.num_edges), (3, 3));
+
+        for path in paths {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_merge_unknown_node() {
+        let paths = vec![write_temp_graph("merge_unknown", "# Id : 1\n\n1\n1\n\
+            0 100 48.7 9.1 0\n\
+            0 5 30 3 50\n")];
+
+        assert!(matches!(merge_graphs(&paths), Err(ParseError::InvalidNode(5))));
+
+        fs::remove_file(&paths[0]).unwrap();
+    }
+}
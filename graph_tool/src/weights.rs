@@ -0,0 +1,166 @@
+use std::str::FromStr;
+
+use crate::{Graph, ParseError};
+
+/// Mean earth radius in meters
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Maximum speed in km/h that is assumed for edges without a valid maxspeed
+const DEFAULT_MAXSPEED_KMH: f64 = 50.0;
+
+/// Kind of edge weights to recompute
+#[derive(Clone, Copy)]
+pub(crate) enum Weights {
+    /// Time in seconds to travel along an edge at its maximum speed
+    TravelTime,
+    /// Geometric length of an edge in meters
+    Distance,
+}
+
+impl FromStr for Weights {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "traveltime" => Ok(Self::TravelTime),
+            "distance" => Ok(Self::Distance),
+            _ => Err(format!("Unknown weights: {}", s))
+        }
+    }
+}
+
+impl Weights {
+    /// Get the description of these weights and their unit for the graph header
+    fn header_line(&self) -> &'static str {
+        match self {
+            Self::TravelTime => "# Weights : traveltime (s)",
+            Self::Distance => "# Weights : distance (m)",
+        }
+    }
+}
+
+/// Great circle distance in meters between two points given by latitude and longitude
+fn haversine_dist(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = phi2 - phi1;
+    let d_lambda = (lon2 - lon1).to_radians();
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+}
+
+/// Recompute the weights of all edges of `graph` from their geometric lengths and, for travel
+/// times, their maximum speeds. Weights are rounded to whole units, but are at least `1`.
+/// The kind and the unit of the weights are documented in the header of `graph`.
+pub(crate) fn recompute_weights(graph: &mut Graph, weights: Weights) -> Result<(), ParseError> {
+    let coords = graph.nodes.iter()
+        .map(|node| Ok((node.id, (node.lat.parse::<f64>()?, node.lon.parse::<f64>()?))))
+        .collect::<Result<std::collections::HashMap<_, _>, ParseError>>()?;
+    let node_coords = |id: usize| coords.get(&id).copied().ok_or(ParseError::InvalidNode(id));
+
+    let mut num_default_speed = 0;
+    for edge in &mut graph.edges {
+        let (lat1, lon1) = node_coords(edge.a)?;
+        let (lat2, lon2) = node_coords(edge.b)?;
+        let dist = haversine_dist(lat1, lon1, lat2, lon2);
+
+        let weight = match weights {
+            Weights::Distance => dist,
+            Weights::TravelTime => {
+                let maxspeed = match edge.maxspeed.parse::<f64>() {
+                    Ok(maxspeed) if maxspeed > 0.0 => maxspeed,
+                    _ => {
+                        num_default_speed += 1;
+                        DEFAULT_MAXSPEED_KMH
+                    }
+                };
+                dist / (maxspeed / 3.6)
+            }
+        };
+        edge.dist = (weight.round() as usize).max(1);
+    }
+    if num_default_speed > 0 {
+        println!("Assumed {} km/h for {} edges without a valid maxspeed", DEFAULT_MAXSPEED_KMH,
+                 num_default_speed);
+    }
+
+    // Document the weights in the header, replacing any previous documentation
+    let mut header: Vec<_> = graph.meta.lines()
+        .filter(|line| line.starts_with('#') && !line.starts_with("# Weights"))
+        .collect();
+    header.push(weights.header_line());
+    graph.meta = format!("{}\n\n", header.join("\n"));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Edge, Node};
+
+    use super::*;
+
+    /// Create a graph with a header and two nodes that are one hundredth of a degree of
+    /// latitude apart, connected by edges with the maximum speeds `maxspeeds`
+    fn two_node_graph(maxspeeds: &[&str]) -> Graph {
+        let mut graph = Graph::new();
+        graph.meta = "# Id : 1\n# Weights : distance (m)\n# Type : test\n\n".to_string();
+        for (id, lat) in ["48.70", "48.71"].iter().enumerate() {
+            graph.nodes.push(Node {
+                id,
+                id2: id,
+                lat: lat.to_string(),
+                lon: "9.1".to_string(),
+                elevation: "0".to_string(),
+            });
+        }
+        for maxspeed in maxspeeds {
+            graph.edges.push(Edge {
+                a: 0,
+                b: 1,
+                dist: 0,
+                edge_type: "3".to_string(),
+                maxspeed: maxspeed.to_string(),
+            });
+        }
+        graph
+    }
+
+    #[test]
+    fn test_haversine_dist() {
+        assert_eq!(haversine_dist(48.7, 9.1, 48.7, 9.1), 0.0);
+        // One degree of latitude is about 111.2 km along every meridian
+        assert!((haversine_dist(0.0, 0.0, 1.0, 0.0) - 111_195.0).abs() < 1.0);
+        assert!((haversine_dist(48.0, 9.0, 49.0, 9.0) - 111_195.0).abs() < 1.0);
+        // Stuttgart to Berlin is about 511 km
+        assert!((haversine_dist(48.7758, 9.1829, 52.5200, 13.4050) - 511_000.0).abs() < 2_000.0);
+    }
+
+    #[test]
+    fn test_recompute_distances() {
+        let mut graph = two_node_graph(&["50"]);
+        recompute_weights(&mut graph, Weights::Distance).unwrap();
+        assert_eq!(graph.edges[0].dist, 1112);
+    }
+
+    #[test]
+    fn test_recompute_travel_times() {
+        let mut graph = two_node_graph(&["100", "0", "none"]);
+        recompute_weights(&mut graph, Weights::TravelTime).unwrap();
+        // 1112 m at 100 km/h, and at the default maximum speed of 50 km/h for invalid maxspeeds
+        assert_eq!(graph.edges.iter().map(|edge| edge.dist).collect::<Vec<_>>(), vec![40, 80, 80]);
+    }
+
+    #[test]
+    fn test_recompute_weights_header() {
+        let mut graph = two_node_graph(&["50"]);
+        recompute_weights(&mut graph, Weights::TravelTime).unwrap();
+        assert_eq!(graph.meta, "# Id : 1\n# Type : test\n# Weights : traveltime (s)\n\n");
+    }
+
+    #[test]
+    fn test_recompute_weights_unknown_node() {
+        let mut graph = two_node_graph(&["50"]);
+        graph.edges[0].b = 7;
+        assert!(matches!(recompute_weights(&mut graph, Weights::Distance), Err(ParseError::InvalidNode(7))));
+    }
+}